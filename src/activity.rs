@@ -0,0 +1,266 @@
+use anyhow::Result;
+use std::collections::BTreeMap;
+use std::sync::LazyLock;
+
+use crate::models::{confidence, ActivityType};
+
+/// Built-in keyword weights: 3 is a clear signal on its own, 1 only tips a close call
+const BUILTIN_WEIGHTS: &[(ActivityType, &str, f64)] = &[
+    (ActivityType::Coding, "implement", 3.0),
+    (ActivityType::Coding, "refactor", 3.0),
+    (ActivityType::Coding, "create", 2.0),
+    (ActivityType::Coding, "build", 2.0),
+    (ActivityType::Coding, "feature", 2.0),
+    (ActivityType::Coding, "add", 1.0),
+    (ActivityType::Coding, "write", 1.0),
+    (ActivityType::Coding, "function", 1.0),
+    (ActivityType::Coding, "実装", 3.0),
+    (ActivityType::Coding, "追加", 2.0),
+    (ActivityType::Coding, "作成", 2.0),
+    (ActivityType::Debugging, "debug", 3.0),
+    (ActivityType::Debugging, "bug", 3.0),
+    (ActivityType::Debugging, "crash", 3.0),
+    (ActivityType::Debugging, "not working", 3.0),
+    (ActivityType::Debugging, "fix", 2.0),
+    (ActivityType::Debugging, "error", 2.0),
+    (ActivityType::Debugging, "fail", 2.0),
+    (ActivityType::Debugging, "broken", 2.0),
+    (ActivityType::Debugging, "exception", 2.0),
+    (ActivityType::Debugging, "panic", 2.0),
+    (ActivityType::Debugging, "バグ", 3.0),
+    (ActivityType::Debugging, "エラー", 2.0),
+    (ActivityType::Debugging, "修正", 2.0),
+    (ActivityType::Planning, "plan", 3.0),
+    (ActivityType::Planning, "architect", 3.0),
+    (ActivityType::Planning, "architecture", 3.0),
+    (ActivityType::Planning, "roadmap", 3.0),
+    (ActivityType::Planning, "design", 2.0),
+    (ActivityType::Planning, "strategy", 2.0),
+    (ActivityType::Planning, "approach", 1.0),
+    (ActivityType::Planning, "設計", 3.0),
+    (ActivityType::Planning, "計画", 3.0),
+    (ActivityType::Research, "research", 3.0),
+    (ActivityType::Research, "investigate", 3.0),
+    (ActivityType::Research, "analyze", 2.0),
+    (ActivityType::Research, "analysis", 2.0),
+    (ActivityType::Research, "compare", 2.0),
+    (ActivityType::Research, "evaluate", 2.0),
+    (ActivityType::Research, "look into", 2.0),
+    (ActivityType::Research, "調査", 3.0),
+    (ActivityType::Research, "比較", 2.0),
+    (ActivityType::Documentation, "document", 3.0),
+    (ActivityType::Documentation, "readme", 3.0),
+    (ActivityType::Documentation, "docs", 3.0),
+    (ActivityType::Documentation, "docstring", 3.0),
+    (ActivityType::Documentation, "changelog", 3.0),
+    (ActivityType::Documentation, "comment", 2.0),
+    (ActivityType::Documentation, "ドキュメント", 3.0),
+    (ActivityType::Documentation, "コメント", 2.0),
+    (ActivityType::Learning, "learn", 3.0),
+    (ActivityType::Learning, "explain", 3.0),
+    (ActivityType::Learning, "tutorial", 3.0),
+    (ActivityType::Learning, "understand", 2.0),
+    (ActivityType::Learning, "how does", 2.0),
+    (ActivityType::Learning, "what is", 2.0),
+    (ActivityType::Learning, "教えて", 3.0),
+    (ActivityType::Learning, "説明", 2.0),
+];
+
+/// Score of a clear signal, which alone makes a classification 75% confident
+const CLEAR_SIGNAL: f64 = 3.0;
+
+/// Endings a keyword may take within a word, e.g. `fix` in `fixes` but not `add` in `address`
+const INFLECTIONS: [&str; 15] =
+    ["", "e", "s", "es", "d", "ed", "ing", "ion", "ions", "ation", "ations", "er", "ers", "ment", "ments"];
+
+static BUILTIN: LazyLock<ActivityScoring> = LazyLock::new(ActivityScoring::new);
+
+/// An activity a message was scored for
+#[derive(Debug, Clone, PartialEq)]
+pub struct ActivityScore {
+    pub activity: ActivityType,
+    /// Sum of the weights of the activity's keywords in the message
+    pub score: f64,
+    /// Share of the message's total score, between 0 and 1
+    pub confidence: f64,
+}
+
+/// Keyword weights per activity type that messages are scored with
+///
+/// Each keyword found in a message adds its weight once to its activity, so a
+/// message mentioning several activities is labeled with all of them and counted
+/// for the highest; "fix the README" is documentation rather than debugging.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ActivityScoring {
+    weights: BTreeMap<(ActivityType, String), f64>,
+}
+
+impl Default for ActivityScoring {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ActivityScoring {
+    /// The built-in keyword weights
+    pub fn new() -> Self {
+        Self {
+            weights: BUILTIN_WEIGHTS
+                .iter()
+                .map(|(activity, keyword, weight)| ((*activity, keyword.to_string()), *weight))
+                .collect(),
+        }
+    }
+
+    /// The built-in scoring, shared rather than built per message
+    pub fn builtin() -> &'static Self {
+        &BUILTIN
+    }
+
+    /// Set the weight of a keyword for an activity; a weight of 0 removes the keyword
+    pub fn with_weight(mut self, activity: ActivityType, keyword: &str, weight: f64) -> Self {
+        let key = (activity, keyword.trim().to_lowercase());
+        if weight > 0.0 {
+            self.weights.insert(key, weight);
+        } else {
+            self.weights.remove(&key);
+        }
+        self
+    }
+
+    /// Apply `[activity_weights]` of the config file: weights by keyword per activity name
+    pub fn with_configured_weights(mut self, configured: &BTreeMap<String, BTreeMap<String, f64>>) -> Result<Self> {
+        for (name, keywords) in configured {
+            let activity: ActivityType = name.parse()?;
+            if activity == ActivityType::Other {
+                return Err(anyhow::anyhow!("Other is the activity of messages without keywords and has no weights"));
+            }
+            for (keyword, &weight) in keywords {
+                if keyword.trim().is_empty() || !weight.is_finite() || weight < 0.0 {
+                    return Err(anyhow::anyhow!(
+                        "Invalid weight {} of '{}' for {}. Expected a keyword with a weight of 0 or more",
+                        weight,
+                        keyword,
+                        activity.as_str()
+                    ));
+                }
+                self = self.with_weight(activity, keyword, weight);
+            }
+        }
+        Ok(self)
+    }
+
+    /// The scoring table: activity, keyword and weight
+    pub fn weights(&self) -> impl Iterator<Item = (ActivityType, &str, f64)> {
+        self.weights.iter().map(|((activity, keyword), weight)| (*activity, keyword.as_str(), *weight))
+    }
+
+    /// Every activity the message has keywords of, highest score first
+    pub fn classify(&self, content: &str) -> Vec<ActivityScore> {
+        let content = content.to_lowercase();
+        let mut scores: BTreeMap<ActivityType, f64> = BTreeMap::new();
+        for ((activity, keyword), weight) in &self.weights {
+            if contains_keyword(&content, keyword) {
+                *scores.entry(*activity).or_insert(0.0) += weight;
+            }
+        }
+
+        let total: f64 = scores.values().sum();
+        let mut labels: Vec<ActivityScore> = scores
+            .into_iter()
+            .map(|(activity, score)| ActivityScore { activity, score, confidence: score / total })
+            .collect();
+        // Stable, so a tie keeps the order of the activity types
+        labels.sort_by(|a, b| b.score.total_cmp(&a.score));
+        labels
+    }
+
+    /// The activity with the highest score, `Other` for a message without keywords
+    pub fn primary(&self, content: &str) -> ActivityType {
+        self.classify(content).first().map_or(ActivityType::Other, |label| label.activity)
+    }
+
+    /// The primary activity and the confidence in it: its share of the score,
+    /// lowered when only weak keywords were found, and 0 for `Other`
+    pub fn primary_with_confidence(&self, content: &str) -> (ActivityType, f64) {
+        match self.classify(content).first() {
+            Some(label) => (label.activity, label.confidence * confidence(2.0 * label.score / CLEAR_SIGNAL)),
+            None => (ActivityType::Other, 0.0),
+        }
+    }
+}
+
+/// Whether lowercase `content` has the keyword at the start of a word, with an inflected ending
+///
+/// Keywords in scripts without spaces between words, like Japanese, match anywhere.
+fn contains_keyword(content: &str, keyword: &str) -> bool {
+    if !keyword.is_ascii() {
+        return content.contains(keyword);
+    }
+    // "create" as in "creating", "plan" as in "planning"
+    let stems = [Some(keyword), keyword.strip_suffix('e')];
+    stems.into_iter().flatten().any(|stem| {
+        content.match_indices(stem).any(|(start, _)| {
+            if content[..start].chars().next_back().is_some_and(char::is_alphanumeric) {
+                return false;
+            }
+            let rest = &content[start + stem.len()..];
+            let ending = &rest[..rest.find(|c: char| !c.is_alphanumeric()).unwrap_or(rest.len())];
+            let doubled = stem.chars().next_back().and_then(|last| ending.strip_prefix(last));
+            INFLECTIONS.contains(&ending) || doubled.is_some_and(|ending| ["ed", "ing", "er", "ers"].contains(&ending))
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_weighted_classification() {
+        let scoring = ActivityScoring::new();
+        // The README outweighs the fix, where the first keyword used to win
+        assert_eq!(scoring.primary("fix the README"), ActivityType::Documentation);
+        assert_eq!(scoring.primary("implement a new feature"), ActivityType::Coding);
+        assert_eq!(scoring.primary("Planning the caching layer"), ActivityType::Planning);
+        assert_eq!(scoring.primary("認証のバグを修正して"), ActivityType::Debugging);
+        // Keywords inside other words do not count
+        assert_eq!(scoring.primary("update the address field"), ActivityType::Other);
+        assert_eq!(scoring.primary("nothing to see"), ActivityType::Other);
+
+        let labels = scoring.classify("Fix the failing test and document the fix");
+        assert_eq!(
+            labels.iter().map(|label| label.activity).collect::<Vec<_>>(),
+            vec![ActivityType::Debugging, ActivityType::Documentation]
+        );
+        assert_eq!((labels[0].score, labels[1].score), (4.0, 3.0));
+        assert!((labels[0].confidence - 4.0 / 7.0).abs() < 1e-9);
+
+        assert_eq!(scoring.primary("add a comment"), ActivityType::Documentation);
+        // A clear keyword alone is 75% confident, a weak one less, a mixed message less still
+        assert_eq!(scoring.primary_with_confidence("implement it"), (ActivityType::Coding, 0.75));
+        assert!(scoring.primary_with_confidence("add it").1 < 0.5);
+        assert!(scoring.primary_with_confidence("Fix the failing test and document the fix").1 < 0.75);
+        assert_eq!(scoring.primary_with_confidence("thanks"), (ActivityType::Other, 0.0));
+        // A tie goes to the earlier activity type
+        assert_eq!(scoring.primary("design and build it"), ActivityType::Coding);
+    }
+
+    #[test]
+    fn test_configured_weights() {
+        let configured: BTreeMap<String, BTreeMap<String, f64>> = toml::from_str(
+            "[documentation]\nreadme = 1\n\n[Debugging]\nfix = 0\nflaky = 3\n",
+        )
+        .unwrap();
+        let scoring = ActivityScoring::new().with_configured_weights(&configured).unwrap();
+        assert_eq!(scoring.primary("fix the flaky README test"), ActivityType::Debugging);
+        assert_eq!(scoring.primary("fix the README"), ActivityType::Documentation);
+        assert!(scoring.weights().any(|entry| entry == (ActivityType::Debugging, "flaky", 3.0)));
+        assert!(!scoring.weights().any(|(_, keyword, _)| keyword == "fix"));
+
+        for invalid in ["[other]\nmisc = 1\n", "[chores]\nbump = 1\n", "[coding]\nhack = -1\n"] {
+            let configured: BTreeMap<String, BTreeMap<String, f64>> = toml::from_str(invalid).unwrap();
+            assert!(ActivityScoring::new().with_configured_weights(&configured).is_err(), "{}", invalid);
+        }
+    }
+}
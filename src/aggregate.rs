@@ -0,0 +1,227 @@
+use chrono::NaiveDate;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+
+use crate::models::{TokenStats, WorkAnalysis};
+use crate::plan::week_start;
+use crate::timezone::TimezoneSchedule;
+
+/// Marks an aggregate export
+const AGGREGATE_FORMAT: &str = "claude-work-analysis-aggregate";
+
+/// Bumped whenever the layout changes
+const AGGREGATE_VERSION: u32 = 1;
+
+/// Numbers of a period's work that can be shared without its transcripts
+///
+/// Nothing here holds text from the logs: the rollups are counts, hours and
+/// tokens, and projects are only told apart by a [`ProjectLabel`]. Keep it that
+/// way; a field of a type that can carry log text defeats the purpose.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AggregateExport {
+    format: &'static str,
+    version: u32,
+    /// Local days of the first and last session
+    pub first_day: Option<NaiveDate>,
+    pub last_day: Option<NaiveDate>,
+    pub total: Rollup,
+    /// One rollup per local week with sessions, by its Monday
+    pub weeks: Vec<WeekRollup>,
+    /// Projects by work time, longest first
+    pub projects: Vec<ProjectRollup>,
+}
+
+/// Figures of some of the work
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct Rollup {
+    pub sessions: usize,
+    pub messages: usize,
+    pub hours: f64,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub cache_creation_tokens: u64,
+    pub cache_read_tokens: u64,
+    pub estimated_cost: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct WeekRollup {
+    pub week_start: NaiveDate,
+    #[serde(flatten)]
+    pub rollup: Rollup,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ProjectRollup {
+    pub project: ProjectLabel,
+    #[serde(flatten)]
+    pub rollup: Rollup,
+    pub weeks: Vec<WeekRollup>,
+}
+
+/// Stand-in for a project's name, `project-1` for the project worked on longest
+///
+/// Only this module makes labels, so a project's name cannot end up in an export.
+/// Labels are numbered per export and do not identify a project across exports.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(transparent)]
+pub struct ProjectLabel(String);
+
+impl ProjectLabel {
+    fn numbered(rank: usize) -> Self {
+        Self(format!("project-{}", rank))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+/// Sessions, work seconds and tokens summed before hours are rounded
+#[derive(Default)]
+struct Totals {
+    sessions: usize,
+    messages: usize,
+    work_seconds: i64,
+    tokens: TokenStats,
+}
+
+impl Totals {
+    fn rollup(&self) -> Rollup {
+        let usage = &self.tokens.usage;
+        Rollup {
+            sessions: self.sessions,
+            messages: self.messages,
+            hours: (self.work_seconds as f64 / 3600.0 * 100.0).round() / 100.0,
+            input_tokens: usage.input_tokens,
+            output_tokens: usage.output_tokens,
+            cache_creation_tokens: usage.cache_creation_tokens,
+            cache_read_tokens: usage.cache_read_tokens,
+            estimated_cost: (self.tokens.estimated_cost * 100.0).round() / 100.0,
+        }
+    }
+}
+
+#[derive(Default)]
+struct ProjectTotals {
+    totals: Totals,
+    weeks: BTreeMap<NaiveDate, Totals>,
+}
+
+fn week_rollups(weeks: &BTreeMap<NaiveDate, Totals>) -> Vec<WeekRollup> {
+    weeks.iter().map(|(week_start, totals)| WeekRollup { week_start: *week_start, rollup: totals.rollup() }).collect()
+}
+
+impl AggregateExport {
+    /// The rollups of the sessions of `analysis`, in weeks of the local calendar
+    pub fn from_analysis(analysis: &WorkAnalysis, schedule: &TimezoneSchedule) -> Self {
+        let session_tokens = analysis.token_analysis.as_ref().map(|tokens| tokens.stats_by_session()).unwrap_or_default();
+
+        let mut total = Totals::default();
+        let mut weeks: BTreeMap<NaiveDate, Totals> = BTreeMap::new();
+        let mut projects: HashMap<String, ProjectTotals> = HashMap::new();
+        let mut days = None;
+        for session in &analysis.sessions {
+            let day = schedule.to_local(session.start_time).date_naive();
+            days = Some(days.map_or((day, day), |(first, last): (NaiveDate, NaiveDate)| (first.min(day), last.max(day))));
+            let week = week_start(day);
+            let project = analysis.project_name(session);
+            let tokens = session_tokens.get(&(session.session_id, session.start_time));
+
+            let project = projects.entry(project).or_default();
+            for totals in [&mut total, weeks.entry(week).or_default(), &mut project.totals, project.weeks.entry(week).or_default()] {
+                totals.sessions += 1;
+                totals.messages += session.total_messages;
+                totals.work_seconds += (session.end_time - session.start_time).num_seconds();
+                if let Some(tokens) = tokens {
+                    totals.tokens.merge(tokens);
+                }
+            }
+        }
+
+        let mut projects: Vec<(String, ProjectTotals)> = projects.into_iter().collect();
+        projects.sort_by(|a, b| b.1.totals.work_seconds.cmp(&a.1.totals.work_seconds).then_with(|| a.0.cmp(&b.0)));
+        Self {
+            format: AGGREGATE_FORMAT,
+            version: AGGREGATE_VERSION,
+            first_day: days.map(|(first, _)| first),
+            last_day: days.map(|(_, last)| last),
+            total: total.rollup(),
+            weeks: week_rollups(&weeks),
+            projects: projects
+                .iter()
+                .enumerate()
+                .map(|(index, (_, project))| ProjectRollup {
+                    project: ProjectLabel::numbered(index + 1),
+                    rollup: project.totals.rollup(),
+                    weeks: week_rollups(&project.weeks),
+                })
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyzer::WorkAnalyzer;
+    use crate::parser::JsonlParser;
+    use crate::sample::write_sample;
+    use crate::scanner::ProjectScanner;
+    use chrono::FixedOffset;
+    use tempfile::TempDir;
+
+    #[tokio::test]
+    async fn test_aggregate_export_holds_no_log_text() {
+        let temp_dir = TempDir::new().unwrap();
+        write_sample(temp_dir.path()).unwrap();
+        let files = ProjectScanner::new().scan_projects(temp_dir.path()).unwrap();
+        let entries = JsonlParser::new().parse_files(&files).await.unwrap();
+        let analysis = WorkAnalyzer::new().analyze_entries(&entries).unwrap();
+        let schedule = TimezoneSchedule::fixed(FixedOffset::east_opt(9 * 3600).unwrap());
+
+        let export = AggregateExport::from_analysis(&analysis, &schedule);
+        assert_eq!(export.total.sessions, analysis.total_sessions);
+        assert_eq!(export.total.messages, analysis.total_messages);
+        assert_eq!(export.projects.len(), analysis.project_stats.len());
+        assert_eq!(export.projects[0].project.as_str(), "project-1");
+        assert!(export.projects.windows(2).all(|pair| pair[0].rollup.hours >= pair[1].rollup.hours));
+        let week_sessions: usize = export.weeks.iter().map(|week| week.rollup.sessions).sum();
+        assert_eq!(week_sessions, export.total.sessions);
+        let tokens = analysis.token_analysis.as_ref().unwrap();
+        assert_eq!(export.total.output_tokens, tokens.total.usage.output_tokens);
+
+        // Neither project names, paths nor prompts are exported
+        let json = serde_json::to_string(&export).unwrap();
+        for stats in analysis.project_stats.values() {
+            assert!(!json.contains(&stats.project_name));
+        }
+        for entry in &entries {
+            assert!(!json.contains(&entry.cwd));
+            assert!(!json.contains(&entry.session_id.to_string()));
+        }
+        assert!(json.contains("\"format\":\"claude-work-analysis-aggregate\""));
+    }
+
+    #[tokio::test]
+    async fn test_log_session_split_by_the_gap_counts_each_part() {
+        let temp_dir = TempDir::new().unwrap();
+        write_sample(temp_dir.path()).unwrap();
+        let files = ProjectScanner::new().scan_projects(temp_dir.path()).unwrap();
+        let mut entries = JsonlParser::new().parse_files(&files).await.unwrap();
+        // The second half of a log session comes after a break longer than the session gap
+        let split_id = entries[0].session_id;
+        let indices: Vec<usize> = (0..entries.len()).filter(|&i| entries[i].session_id == split_id).collect();
+        for &i in &indices[indices.len() / 2..] {
+            entries[i].timestamp += chrono::Duration::hours(5);
+        }
+        let analysis = WorkAnalyzer::new().analyze_entries(&entries).unwrap();
+        assert_eq!(analysis.sessions.iter().filter(|session| session.session_id == split_id).count(), 2);
+        let schedule = TimezoneSchedule::fixed(FixedOffset::east_opt(0).unwrap());
+
+        let export = AggregateExport::from_analysis(&analysis, &schedule);
+        let tokens = analysis.token_analysis.as_ref().unwrap();
+        assert_eq!(export.total.output_tokens, tokens.total.usage.output_tokens);
+        assert_eq!(export.total.input_tokens, tokens.total.usage.input_tokens);
+    }
+}
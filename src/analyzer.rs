@@ -1,22 +1,68 @@
 use anyhow::Result;
-use chrono::{DateTime, Utc, Duration};
-use std::collections::HashMap;
+use futures::{Stream, TryStreamExt};
+use chrono::{DateTime, Utc, Duration, FixedOffset, NaiveDate};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::PathBuf;
+use tokio_util::sync::CancellationToken;
 use uuid::Uuid;
 
 use crate::models::{
-    ClaudeLogEntry, WorkSession, WorkAnalysis, ProjectStats, ActivityType, 
-    MessageContentVariant, EntryType, ConversationSummary
+    ClaudeLogEntry, WorkSession, WorkAnalysis, ProjectStats, 
+    MessageContentVariant, EntryType, ConversationSummary, ToolStats, ToolUsageSummary, CodeBlockStats, CodeBlockSummary,
+    TimeBucket, TimeBuckets, TimeGrouping, TokenAnalysis, TokenStats, SessionTokens, SessionSummary, ActivityHeatmap,
+    TopicAnalysis, FollowUpStats, SidechainStats, SidechainSummary, SessionSidechains, AssistantStats
 };
-use crate::scanner::ProjectScanner;
-use crate::message_analyzer::MessageAnalyzer;
+use crate::activity::ActivityScoring;
+use crate::clock_skew::correct_timestamps;
+use crate::paths;
+use crate::scanner::{ProjectAliases, ProjectNames, ProjectScanner};
+use crate::clustering::cluster_sessions;
+use crate::error::Error;
+use crate::importers::CLAUDE_CODE;
+use crate::initiatives::{roll_up, Initiative};
+use crate::message_analyzer::{MessageAnalyzer, TopicCounts};
+use crate::privacy::Redactor;
+use crate::profile::Profiler;
+use crate::timezone::TimezoneSchedule;
+use crate::token_analyzer::TokenAnalyzer;
 
 pub struct WorkAnalyzer {
     /// Minimum time between messages to consider them part of the same session
     session_gap_threshold: Duration,
     /// Minimum number of messages to consider a session meaningful
     min_session_messages: usize,
+    /// Whether sidechain (subagent) entries are included in the analysis
+    include_sidechains: bool,
+    /// Merge sessions of the same project that resume within this gap after a restart
+    restart_merge_gap: Option<Duration>,
+    /// Calendar unit for the per-bucket activity aggregates, if requested
+    grouping: Option<TimeGrouping>,
+    /// Encoded names of the known project directories (see [`Self::with_project_dirs`])
+    project_dirs: HashSet<String>,
+    /// Checkouts counted as the same project
+    project_aliases: ProjectAliases,
     /// Message analyzer for content analysis
     message_analyzer: MessageAnalyzer,
+    /// Token usage and cost aggregation
+    token_analyzer: TokenAnalyzer,
+    /// Keyword weights that classify user messages by activity type
+    activity_scoring: ActivityScoring,
+    /// Tool invocation analysis
+    tool_usage_analyzer: ToolUsageAnalyzer,
+    /// Code block counting in the assistant's responses
+    code_block_analyzer: CodeBlockAnalyzer,
+    /// Whether sessions keep their raw entries after their statistics are taken
+    keep_session_entries: bool,
+    /// Whether project topics are counted over every message rather than from session summaries
+    deep_topics: bool,
+    /// Initiatives to roll up work for
+    initiatives: Vec<Initiative>,
+    /// Strips paths, secrets and names from the entries of each session before it is analyzed
+    redactor: Option<Redactor>,
+    /// Records the time spent sessionizing and analyzing messages
+    profiler: Profiler,
+    /// Stops an analysis whose result is no longer wanted
+    cancellation: CancellationToken,
 }
 
 impl WorkAnalyzer {
@@ -24,7 +70,22 @@ impl WorkAnalyzer {
         Self {
             session_gap_threshold: Duration::hours(2), // 2 hours gap = new session
             min_session_messages: 3,
+            include_sidechains: true,
+            restart_merge_gap: None,
+            grouping: None,
+            project_dirs: HashSet::new(),
+            project_aliases: ProjectAliases::default(),
             message_analyzer: MessageAnalyzer::new(),
+            token_analyzer: TokenAnalyzer::new(),
+            activity_scoring: ActivityScoring::new(),
+            tool_usage_analyzer: ToolUsageAnalyzer::new(),
+            code_block_analyzer: CodeBlockAnalyzer::new(),
+            keep_session_entries: true,
+            deep_topics: false,
+            initiatives: Vec::new(),
+            redactor: None,
+            profiler: Profiler::default(),
+            cancellation: CancellationToken::new(),
         }
     }
 
@@ -38,12 +99,190 @@ impl WorkAnalyzer {
         self
     }
 
+    pub fn with_sidechains(mut self, include: bool) -> Self {
+        self.include_sidechains = include;
+        self
+    }
+
+    /// Merge adjacent sessions in the same project separated by at most `gap`
+    ///
+    /// Restarting Claude Code starts a new log session even when the same task continues.
+    pub fn with_restart_merge(mut self, gap: Duration) -> Self {
+        self.restart_merge_gap = Some(gap);
+        self
+    }
+
+    /// Count the sessions of aliased checkouts under the project they map to
+    pub fn with_project_aliases(mut self, aliases: ProjectAliases) -> Self {
+        self.project_aliases = aliases;
+        self
+    }
+
+    /// Redact each session's entries once its project is known, see [`Redactor`]
+    pub fn with_redactor(mut self, redactor: Redactor) -> Self {
+        self.redactor = Some(redactor);
+        self
+    }
+
+    /// Aggregate sessions, work time, messages and tokens per day, week or month
+    pub fn with_grouping(mut self, grouping: TimeGrouping) -> Self {
+        self.grouping = Some(grouping);
+        self
+    }
+
+    /// Project directories from the scanner, used to find the root of each working directory
+    ///
+    /// A cwd belongs to the deepest ancestor that Claude was started in, so moving into
+    /// a subfolder stays in the project while a nested project of its own does not.
+    pub fn with_project_dirs(mut self, project_dirs: &[PathBuf]) -> Self {
+        self.project_dirs = project_dirs
+            .iter()
+            .filter_map(|dir| dir.to_str().and_then(paths::last_segment))
+            .map(str::to_string)
+            .collect();
+        self
+    }
+
+    /// Timezone used for calendar-day aggregation
+    pub fn with_timezone(mut self, timezone: FixedOffset) -> Self {
+        self.token_analyzer = std::mem::take(&mut self.token_analyzer).with_timezone(timezone);
+        self
+    }
+
+    /// Timezones that change over the analyzed period, e.g. while travelling
+    pub fn with_timezone_schedule(mut self, schedule: TimezoneSchedule) -> Self {
+        self.token_analyzer = std::mem::take(&mut self.token_analyzer).with_timezone_schedule(schedule);
+        self
+    }
+
+    pub fn with_message_analyzer(mut self, message_analyzer: MessageAnalyzer) -> Self {
+        self.message_analyzer = message_analyzer;
+        self
+    }
+
+    /// Analyze the assistant's extended thinking along with the visible messages
+    pub fn with_thinking(mut self, include: bool) -> Self {
+        self.message_analyzer = std::mem::take(&mut self.message_analyzer).with_thinking(include);
+        self
+    }
+
+    /// Token analyzer, classifying prompts with this analyzer's activity scoring
+    pub fn with_token_analyzer(mut self, token_analyzer: TokenAnalyzer) -> Self {
+        self.token_analyzer = token_analyzer.with_activity_scoring(self.activity_scoring.clone());
+        self
+    }
+
+    /// Keyword weights for the activity types of messages and of the tokens spent on them
+    pub fn with_activity_scoring(mut self, activity_scoring: ActivityScoring) -> Self {
+        self.token_analyzer = std::mem::take(&mut self.token_analyzer).with_activity_scoring(activity_scoring.clone());
+        self.activity_scoring = activity_scoring;
+        self
+    }
+
+    /// Whether sessions keep their raw entries
+    ///
+    /// Without them, the memory held per session no longer grows with its messages,
+    /// but reasoning volume is not available, and sessions merged after a restart
+    /// combine their summaries instead of analyzing their messages again.
+    pub fn with_session_entries(mut self, keep: bool) -> Self {
+        self.keep_session_entries = keep;
+        self
+    }
+
+    /// Count each project's topics over all of its messages
+    ///
+    /// By default they are derived from the session summaries, which is much
+    /// cheaper but ranks topics by sessions rather than by messages.
+    pub fn with_deep_topics(mut self, deep: bool) -> Self {
+        self.deep_topics = deep;
+        self
+    }
+
+    /// Roll up hours, sessions and outcomes per initiative
+    pub fn with_initiatives(mut self, initiatives: Vec<Initiative>) -> Self {
+        self.initiatives = initiatives;
+        self
+    }
+
+    /// Record folding entries into sessions as the `sessionize` stage, and the
+    /// message analysis done meanwhile as a stage nested in it
+    pub fn with_profiler(mut self, profiler: Profiler) -> Self {
+        self.profiler = profiler;
+        self
+    }
+
+    /// Stop analyzing once `cancellation` is cancelled, e.g. when a client no longer
+    /// waits for the result
+    ///
+    /// Entries pushed afterwards are dropped and the remaining statistics are skipped,
+    /// so [`SessionFolder::finish`] gives an incomplete analysis, and
+    /// [`Self::analyze_entries`] and [`Self::analyze_stream`] fail with [`Error::Cancelled`].
+    pub fn with_cancellation(mut self, cancellation: CancellationToken) -> Self {
+        self.cancellation = cancellation;
+        self
+    }
+
+    /// Fail with [`Error::Cancelled`] once the analysis is cancelled
+    pub fn check_cancelled(&self) -> Result<()> {
+        if self.cancellation.is_cancelled() {
+            return Err(Error::Cancelled.into());
+        }
+        Ok(())
+    }
+
+    /// Start folding entries into sessions one at a time
+    pub fn folder(&self) -> SessionFolder<'_> {
+        SessionFolder {
+            analyzer: self,
+            seen: HashSet::new(),
+            seen_messages: HashSet::new(),
+            run: Vec::new(),
+            sessions: Vec::new(),
+            time_range: None,
+        }
+    }
+
     /// Analyze a collection of Claude log entries and produce work analysis
+    ///
+    /// Entries with the same uuid, as when one log is present in several
+    /// projects directories, count once.
     pub fn analyze_entries(&self, entries: &[ClaudeLogEntry]) -> Result<WorkAnalysis> {
-        if entries.is_empty() {
+        let mut folder = self.folder();
+        for entry in entries {
+            self.check_cancelled()?;
+            folder.push(entry.clone());
+        }
+        let analysis = folder.finish();
+        self.check_cancelled()?;
+        Ok(analysis)
+    }
+
+    /// Analyze entries as they are read, e.g. from [`crate::parser::JsonlParser::stream_files`]
+    ///
+    /// Only the entries of the log session being read are buffered on top of the
+    /// sessions found so far (see [`Self::with_session_entries`]).
+    pub async fn analyze_stream(&self, entries: impl Stream<Item = Result<ClaudeLogEntry>>) -> Result<WorkAnalysis> {
+        let mut folder = self.folder();
+        let mut entries = std::pin::pin!(entries);
+        while let Some(entry) = entries.try_next().await? {
+            self.check_cancelled()?;
+            folder.push(entry);
+        }
+        let analysis = folder.finish();
+        self.check_cancelled()?;
+        Ok(analysis)
+    }
+
+    /// Aggregate the sessions folded from all entries
+    fn analyze_sessions(
+        &self,
+        sessions: Vec<(WorkSession, SessionDigest)>,
+        time_range: Option<(DateTime<Utc>, DateTime<Utc>)>,
+    ) -> WorkAnalysis {
+        let Some(time_range) = time_range else {
             // Use epoch time for empty entries instead of current time
             let epoch = DateTime::from_timestamp(0, 0).unwrap_or(Utc::now());
-            return Ok(WorkAnalysis {
+            return WorkAnalysis {
                 sessions: Vec::new(),
                 project_stats: HashMap::new(),
                 time_range: (epoch, epoch),
@@ -51,39 +290,89 @@ impl WorkAnalyzer {
                 total_messages: 0,
                 total_work_time: Duration::zero(),
                 conversation_summary: None,
-            });
-        }
+                token_analysis: None,
+                tool_usage: None,
+                code_blocks: None,
+                time_buckets: None,
+                initiatives: None,
+                commits: None,
+                log_gaps: None,
+                heatmap: None,
+                machines: None,
+                assistants: None,
+                sidechains: None,
+                budget: None,
+                duplicate_entries: 0,
+                project_names: ProjectNames::default(),
+            };
+        };
+
+        let sessions = self.stitch_continued_sessions(sessions);
+        let project_names = ProjectNames::new(sessions.iter().map(|(session, _)| session.project_path.as_str()));
 
-        // Group entries by session
-        let sessions = self.group_entries_into_sessions(entries);
-        
         // Filter sessions by minimum message count
-        let meaningful_sessions: Vec<WorkSession> = sessions
+        let (meaningful, short): (Vec<_>, Vec<_>) = sessions
             .into_iter()
-            .filter(|session| session.entries.len() >= self.min_session_messages)
-            .collect();
+            .partition(|(session, _)| session.total_messages >= self.min_session_messages);
+        let (meaningful_sessions, digests): (Vec<WorkSession>, Vec<SessionDigest>) = meaningful.into_iter().unzip();
 
         // Calculate project statistics
-        let project_stats = self.calculate_project_stats(&meaningful_sessions);
-
-        // Calculate time range
-        let time_range = self.calculate_time_range(entries);
+        let project_stats = self.calculate_project_stats(&meaningful_sessions, &digests, &project_names);
 
         // Calculate totals
         let total_sessions = meaningful_sessions.len();
         let total_messages = meaningful_sessions
             .iter()
-            .map(|s| s.entries.len())
+            .map(|s| s.total_messages)
             .sum();
         let total_work_time = meaningful_sessions
             .iter()
             .map(|s| s.end_time - s.start_time)
             .fold(Duration::zero(), |acc, d| acc + d);
 
-        // Generate conversation summary
-        let conversation_summary = self.generate_conversation_summary(&meaningful_sessions);
+        let sidechains = self.summarize_sidechains(&meaningful_sessions, &digests, &project_names);
+        let assistants = digests
+            .iter()
+            .any(|digest| digest.source.is_some())
+            .then(|| self.summarize_assistants(&meaningful_sessions, &digests));
+
+        // Aggregate token usage, estimated cost and tool invocations of the sessions
+        let mut token_analysis = TokenAnalysis::default();
+        // Short sessions are not reported, but their tokens were still spent
+        for (_, digest) in short {
+            let mut tokens = digest.tokens;
+            tokens.by_session.clear();
+            token_analysis.merge(tokens);
+        }
+        let mut tool_usage = ToolUsageSummary::default();
+        let mut code_blocks = CodeBlockSummary::default();
+        let mut heatmap = ActivityHeatmap::default();
+        let mut follow_ups: HashMap<String, FollowUpStats> = HashMap::new();
+        for digest in digests {
+            token_analysis.merge(digest.tokens);
+            tool_usage.merge(&digest.tool_usage);
+            code_blocks.merge(&digest.code_blocks);
+            heatmap.merge(&digest.heatmap);
+            for (tech, stats) in digest.follow_ups {
+                follow_ups.entry(tech).or_default().merge(&stats);
+            }
+        }
+        token_analysis.name_projects(&project_names);
+        tool_usage.by_project = project_names.rename(tool_usage.by_project);
+        code_blocks.by_project = project_names.rename(code_blocks.by_project);
+
+        // Generate conversation summary, with work time and cost per theme
+        let mut conversation_summary = self.generate_conversation_summary(&meaningful_sessions, &token_analysis);
+        conversation_summary.follow_ups_by_technology = follow_ups;
 
-        Ok(WorkAnalysis {
+        let time_buckets = self
+            .grouping
+            .map(|grouping| self.bucket_sessions(grouping, &meaningful_sessions, &token_analysis));
+
+        let initiatives = (!self.initiatives.is_empty())
+            .then(|| roll_up(&self.initiatives, &meaningful_sessions, &token_analysis, &project_names));
+
+        WorkAnalysis {
             sessions: meaningful_sessions,
             project_stats,
             time_range,
@@ -91,11 +380,137 @@ impl WorkAnalyzer {
             total_messages,
             total_work_time,
             conversation_summary: Some(conversation_summary),
-        })
+            token_analysis: Some(token_analysis),
+            tool_usage: Some(tool_usage),
+            code_blocks: Some(code_blocks),
+            time_buckets,
+            initiatives,
+            commits: None,
+            log_gaps: None,
+            heatmap: Some(heatmap),
+            machines: None,
+            assistants,
+            sidechains: self.include_sidechains.then_some(sidechains),
+            budget: None,
+            duplicate_entries: 0,
+            project_names,
+        }
+    }
+
+    /// Work per assistant, Claude Code's own sessions included, most worked-with first
+    fn summarize_assistants(&self, sessions: &[WorkSession], digests: &[SessionDigest]) -> Vec<AssistantStats> {
+        let mut assistants: HashMap<&str, AssistantStats> = HashMap::new();
+        for (session, digest) in sessions.iter().zip(digests) {
+            let name = digest.source.as_deref().unwrap_or(CLAUDE_CODE);
+            let stats = assistants.entry(name).or_insert_with(|| AssistantStats {
+                name: name.to_string(),
+                sessions: 0,
+                messages: 0,
+                work_time: Duration::zero(),
+                tokens: TokenStats::default(),
+            });
+            stats.sessions += 1;
+            stats.messages += session.total_messages;
+            stats.work_time += session.end_time - session.start_time;
+            stats.tokens.merge(&digest.tokens.total);
+        }
+        let mut assistants: Vec<AssistantStats> = assistants.into_values().collect();
+        assistants.sort_by(|a, b| b.work_time.cmp(&a.work_time).then_with(|| a.name.cmp(&b.name)));
+        assistants
+    }
+
+    /// Subagent messages and usage per session and project
+    fn summarize_sidechains(&self, sessions: &[WorkSession], digests: &[SessionDigest], names: &ProjectNames) -> SidechainSummary {
+        let mut summary = SidechainSummary::default();
+        for (session, digest) in sessions.iter().zip(digests) {
+            if digest.sidechain_messages == 0 {
+                continue;
+            }
+            let stats = SidechainStats { messages: digest.sidechain_messages, tokens: digest.tokens.sidechain.clone() };
+            let project = names.name(&session.project_path);
+            summary.total.merge(&stats);
+            summary.by_project.entry(project.clone()).or_default().merge(&stats);
+            summary.by_session.push(SessionSidechains {
+                session_id: session.session_id,
+                start_time: session.start_time,
+                project,
+                stats,
+            });
+        }
+        summary.by_session.sort_by(|a, b| {
+            b.stats.tokens.usage.total().cmp(&a.stats.tokens.usage.total()).then_with(|| a.start_time.cmp(&b.start_time))
+        });
+        summary
+    }
+
+    /// Statistics of a session that need its entries
+    fn digest(&self, session: &WorkSession, seen_messages: &mut HashSet<String>) -> SessionDigest {
+        let _analysis = self.profiler.enter("message analysis");
+        let entry_uuids: HashSet<Uuid> = session.entries.iter().map(|entry| entry.uuid).collect();
+        let parent_uuid = session.entries.first().and_then(|entry| entry.parent_uuid).filter(|parent| !entry_uuids.contains(parent));
+        let mut activity_types = HashMap::new();
+        let mut activity_confidence = HashMap::new();
+        let mut heatmap = ActivityHeatmap::default();
+        for entry in &session.entries {
+            heatmap.add(&self.token_analyzer.timezone().to_local(entry.timestamp));
+            if let EntryType::User = entry.entry_type {
+                let (activity_type, confidence) = self.activity_scoring.primary_with_confidence(&entry.message.content.text());
+                *activity_types.entry(activity_type.as_str().to_string()).or_insert(0) += 1;
+                *activity_confidence.entry(activity_type.as_str().to_string()).or_insert(0.0) += confidence;
+            }
+        }
+
+        SessionDigest {
+            tokens: self.token_analyzer.analyze_session(session, seen_messages),
+            tool_usage: self.tool_usage_analyzer.analyze_by_path(std::slice::from_ref(session)),
+            code_blocks: self.code_block_analyzer.analyze_by_path(std::slice::from_ref(session), self.token_analyzer.timezone()),
+            activity_types,
+            activity_confidence,
+            heatmap,
+            topics: if self.deep_topics {
+                self.message_analyzer.count_topics(&session.entries)
+            } else {
+                TopicCounts::default()
+            },
+            follow_ups: self.message_analyzer.count_follow_ups(&session.entries),
+            entry_uuids,
+            parent_uuid,
+            sidechain_messages: session.entries.iter().filter(|entry| entry.is_sidechain).count(),
+            source: session.entries.first().and_then(|entry| entry.source.clone()),
+        }
+    }
+
+    /// Aggregate sessions into calendar buckets by their local start date
+    fn bucket_sessions(&self, grouping: TimeGrouping, sessions: &[WorkSession], tokens: &TokenAnalysis) -> TimeBuckets {
+        let session_tokens = tokens.stats_by_session();
+
+        let mut buckets: BTreeMap<NaiveDate, TimeBucket> = BTreeMap::new();
+        for session in sessions {
+            let date = self.token_analyzer.timezone().to_local(session.start_time).date_naive();
+            let start = grouping.bucket_start(date);
+            let bucket = buckets.entry(start).or_insert_with(|| TimeBucket {
+                start,
+                sessions: 0,
+                messages: 0,
+                work_time: Duration::zero(),
+                tokens: TokenStats::default(),
+            });
+            bucket.sessions += 1;
+            bucket.messages += session.total_messages;
+            bucket.work_time += session.end_time - session.start_time;
+            if let Some(stats) = session_tokens.get(&(session.session_id, session.start_time)) {
+                bucket.tokens.merge(stats);
+            }
+        }
+
+        TimeBuckets {
+            grouping,
+            buckets: buckets.into_values().collect(),
+        }
     }
 
     /// Group entries into work sessions based on timing and project
-    fn group_entries_into_sessions(&self, entries: &[ClaudeLogEntry]) -> Vec<WorkSession> {
+    fn group_entries_into_sessions(&self, entries: Vec<ClaudeLogEntry>) -> Vec<WorkSession> {
         let mut sessions = Vec::new();
         let mut current_session_entries = Vec::new();
         let mut last_timestamp: Option<DateTime<Utc>> = None;
@@ -124,10 +539,10 @@ impl WorkAnalyzer {
                 current_session_entries = Vec::new();
             }
 
-            current_session_entries.push(entry.clone());
             last_timestamp = Some(entry.timestamp);
             last_session_id = Some(entry.session_id);
             last_project_path = Some(entry.cwd.clone());
+            current_session_entries.push(entry);
         }
 
         // Don't forget the last session
@@ -140,6 +555,93 @@ impl WorkAnalyzer {
         sessions
     }
 
+    /// Fold sessions that continue an earlier one into it, as one block of work
+    ///
+    /// A session continues another when its first entry replies to an entry of
+    /// the other (Claude Code gives resumed conversations a new session ID) and
+    /// it starts within the session gap, or, with a restart merge gap, when it
+    /// resumes its project within that gap of the project's latest session, even
+    /// if sessions of other projects ran in between. Subagents log to
+    /// files of their own under the session ID of the session that ran them,
+    /// which they are folded into.
+    fn stitch_continued_sessions(
+        &self,
+        mut sessions: Vec<(WorkSession, SessionDigest)>,
+    ) -> Vec<(WorkSession, SessionDigest)> {
+        sessions.sort_by_key(|(s, _)| s.start_time);
+        let mut merged: Vec<(WorkSession, SessionDigest)> = Vec::with_capacity(sessions.len());
+        // Stitched session each entry and each session ID belongs to
+        let mut owners: HashMap<Uuid, usize> = HashMap::new();
+        let mut session_owners: HashMap<Uuid, usize> = HashMap::new();
+        // Stitched session each project path last worked in
+        let mut latest_by_project: HashMap<String, usize> = HashMap::new();
+        let within = |previous: &WorkSession, session: &WorkSession, gap: Duration| {
+            let pause = session.start_time - previous.end_time;
+            pause >= Duration::zero() && pause <= gap
+        };
+
+        for (session, digest) in sessions {
+            let continued = digest
+                .parent_uuid
+                .and_then(|parent| owners.get(&parent).copied())
+                .filter(|&index| within(&merged[index].0, &session, self.session_gap_threshold));
+            let restarted = self.restart_merge_gap.and_then(|gap| {
+                let index = latest_by_project
+                    .iter()
+                    .filter(|(project_path, _)| self.is_same_project(project_path, &session.project_path))
+                    .map(|(_, &index)| index)
+                    .max_by_key(|&index| merged[index].0.end_time)?;
+                let (previous, previous_digest) = &merged[index];
+                // A session with another assistant is not a restart, even in the same project
+                (within(previous, &session, gap) && previous_digest.source == digest.source).then_some(index)
+            });
+            let delegated = (digest.sidechain_messages == session.total_messages)
+                .then(|| session_owners.get(&session.session_id).copied())
+                .flatten()
+                .filter(|&index| session.start_time - merged[index].0.end_time <= self.session_gap_threshold);
+            let Some(index) = delegated.or(continued).or(restarted) else {
+                owners.extend(digest.entry_uuids.iter().map(|&uuid| (uuid, merged.len())));
+                session_owners.insert(session.session_id, merged.len());
+                latest_by_project.insert(session.project_path.clone(), merged.len());
+                merged.push((session, digest));
+                continue;
+            };
+            owners.extend(digest.entry_uuids.iter().map(|&uuid| (uuid, index)));
+            session_owners.insert(session.session_id, index);
+            latest_by_project.insert(merged[index].0.project_path.clone(), index);
+            let (previous, previous_digest) = &mut merged[index];
+
+            if session.session_id != previous.session_id
+                && !previous.merged_session_ids.contains(&session.session_id)
+            {
+                previous.merged_session_ids.push(session.session_id);
+            }
+            for id in session.merged_session_ids {
+                if id != previous.session_id && !previous.merged_session_ids.contains(&id) {
+                    previous.merged_session_ids.push(id);
+                }
+            }
+            previous.end_time = previous.end_time.max(session.end_time);
+            previous.total_messages += session.total_messages;
+            previous.user_messages += session.user_messages;
+            previous.assistant_messages += session.assistant_messages;
+            previous.summary = if self.keep_session_entries {
+                previous.entries.extend(session.entries);
+                // Subagent entries interleave with those of the session
+                previous.entries.sort_by_key(|entry| entry.timestamp);
+                Some(self.profiler.time("message analysis", || self.message_analyzer.analyze_session(&previous.entries)))
+            } else {
+                match (&previous.summary, &session.summary) {
+                    (Some(first), Some(second)) => Some(self.message_analyzer.merge_summaries(first, second)),
+                    (first, second) => first.clone().or_else(|| second.clone()),
+                }
+            };
+            previous_digest.merge(digest, previous);
+        }
+
+        merged
+    }
+
     /// Create a WorkSession from a collection of entries
     fn create_session_from_entries(&self, entries: Vec<ClaudeLogEntry>) -> Option<WorkSession> {
         if entries.is_empty() {
@@ -148,10 +650,26 @@ impl WorkAnalyzer {
         
         // Sort entries by timestamp to ensure chronological order
         let mut sorted_entries = entries;
-        sorted_entries.sort_by(|a, b| a.timestamp.cmp(&b.timestamp));
+        sorted_entries.sort_by_key(|e| e.timestamp);
 
         let session_id = sorted_entries[0].session_id;
-        let project_path = sorted_entries[0].cwd.clone();
+        // Subfolders visited during the session share its project root
+        let mut project_path = self
+            .project_root(&sorted_entries[0].cwd)
+            .or_else(|| {
+                sorted_entries
+                    .iter()
+                    .map(|e| e.cwd.as_str())
+                    .min_by_key(|cwd| paths::segments(cwd).count())
+                    .map(str::to_string)
+            })?;
+        project_path = self.project_aliases.resolve(&project_path).to_string();
+        if let Some(redactor) = &self.redactor {
+            for entry in &mut sorted_entries {
+                redactor.redact_entry(entry, &project_path);
+            }
+            project_path = redactor.project(&project_path);
+        }
         let start_time = sorted_entries[0].timestamp;
         let end_time = sorted_entries.last()?.timestamp;
         
@@ -160,13 +678,15 @@ impl WorkAnalyzer {
         
         // Log warnings for data integrity issues
         if start_time > end_time {
-            eprintln!("WARNING: Session {} has invalid time order (start > end)", session_id);
-            eprintln!("  This may indicate data corruption or timezone handling issues");
+            tracing::warn!(
+                "Session {} has invalid time order (start > end). This may indicate data corruption or timezone handling issues",
+                session_id
+            );
         }
         
         // Detect unusually long sessions (>4 hours) 
         if duration.num_hours() > 4 {
-            eprintln!("INFO: Long session detected: {} hours (Session: {})", 
+            tracing::info!("Long session detected: {} hours (Session: {})",
                 duration.num_hours(), &session_id.to_string()[..8]);
         }
         
@@ -182,7 +702,7 @@ impl WorkAnalyzer {
             .count();
 
         // Generate session summary
-        let session_summary = self.message_analyzer.analyze_session(&sorted_entries);
+        let session_summary = self.profiler.time("message analysis", || self.message_analyzer.analyze_session(&sorted_entries));
         
         Some(WorkSession {
             session_id,
@@ -194,31 +714,53 @@ impl WorkAnalyzer {
             assistant_messages,
             entries: sorted_entries,
             summary: Some(session_summary),
+            merged_session_ids: Vec::new(),
         })
     }
 
     /// Check if two project paths represent the same project
+    ///
+    /// Paths with known project roots match when the roots do. Otherwise one path has
+    /// to contain the other, so `~/a/api/src` matches `~/a/api` but `~/b/api` does not.
     fn is_same_project(&self, path1: &str, path2: &str) -> bool {
-        // Simple heuristic: if they share the same final directory name, they're the same project
-        let extract_project_name = |path: &str| -> String {
-            std::path::Path::new(path)
-                .file_name()
-                .and_then(|name| name.to_str())
-                .unwrap_or(path)
-                .to_string()
-        };
+        if let (Some(root1), Some(root2)) = (self.project_root(path1), self.project_root(path2)) {
+            return paths::is_within(&root1, &root2) && paths::is_within(&root2, &root1);
+        }
+
+        paths::is_within(path1, path2) || paths::is_within(path2, path1)
+    }
+
+    /// The deepest ancestor of `cwd` (itself included) that has a known project directory
+    pub(crate) fn project_root(&self, cwd: &str) -> Option<String> {
+        if self.project_dirs.is_empty() {
+            return None;
+        }
 
-        extract_project_name(path1) == extract_project_name(path2)
+        // Windows paths may differ in case from the directory Claude created
+        let windows = paths::is_windows_path(cwd);
+        paths::ancestors(cwd).into_iter().find(|path| {
+            let encoded = ProjectScanner::encode_project_path(path);
+            if windows {
+                self.project_dirs.iter().any(|dir| dir.eq_ignore_ascii_case(&encoded))
+            } else {
+                self.project_dirs.contains(&encoded)
+            }
+        })
     }
 
-    /// Calculate statistics for each project
-    fn calculate_project_stats(&self, sessions: &[WorkSession]) -> HashMap<String, ProjectStats> {
+    /// Calculate statistics for each project, keyed by the display names of their paths
+    fn calculate_project_stats(
+        &self,
+        sessions: &[WorkSession],
+        digests: &[SessionDigest],
+        names: &ProjectNames,
+    ) -> HashMap<String, ProjectStats> {
         let mut project_stats = HashMap::new();
+        let mut project_sessions: HashMap<String, Vec<(&WorkSession, &SessionDigest)>> = HashMap::new();
 
-        for session in sessions {
-            let project_name = ProjectScanner::extract_project_name(
-                std::path::Path::new(&session.project_path)
-            ).unwrap_or_else(|| session.project_path.clone());
+        for (session, digest) in sessions.iter().zip(digests) {
+            let project_name = names.name(&session.project_path);
+            project_sessions.entry(project_name.clone()).or_default().push((session, digest));
 
             let stats = project_stats
                 .entry(project_name.clone())
@@ -228,21 +770,21 @@ impl WorkAnalyzer {
                     total_messages: 0,
                     work_time: Duration::zero(),
                     activity_types: HashMap::new(),
+                    activity_confidence: HashMap::new(),
                     most_active_day: None,
                     topic_analysis: None,
                 });
 
             stats.total_sessions += 1;
             stats.total_messages += session.total_messages;
-            stats.work_time = stats.work_time + (session.end_time - session.start_time);
+            stats.work_time += session.end_time - session.start_time;
 
-            // Analyze activity types in this session
-            for entry in &session.entries {
-                if let EntryType::User = entry.entry_type {
-                    let content = self.extract_message_content(&entry.message.content);
-                    let activity_type = ActivityType::from_message_content(&content);
-                    *stats.activity_types.entry(activity_type.as_str().to_string()).or_insert(0) += 1;
-                }
+            for (activity_type, count) in &digest.activity_types {
+                *stats.activity_types.entry(activity_type.clone()).or_insert(0) += count;
+            }
+            // Summed here, divided into means once every session is counted
+            for (activity_type, confidence) in &digest.activity_confidence {
+                *stats.activity_confidence.entry(activity_type.clone()).or_insert(0.0) += confidence;
             }
 
             // Update most active day
@@ -261,20 +803,49 @@ impl WorkAnalyzer {
             }
         }
 
-        // Generate topic analysis for each project
-        for (project_name, stats) in project_stats.iter_mut() {
-            let project_entries: Vec<ClaudeLogEntry> = sessions
-                .iter()
-                .filter(|session| {
-                    ProjectScanner::extract_project_name(
-                        std::path::Path::new(&session.project_path)
-                    ).unwrap_or_else(|| session.project_path.clone()) == *project_name
+        for stats in project_stats.values_mut() {
+            for (activity_type, confidence) in stats.activity_confidence.iter_mut() {
+                *confidence /= stats.activity_types[activity_type].max(1) as f64;
+            }
+        }
+
+        // Generate topic analysis for each project from its sessions' summaries,
+        // or the topics counted in their messages, spreading the projects over threads
+        let projects: Vec<(String, Vec<(&WorkSession, &SessionDigest)>)> = project_sessions.into_iter().collect();
+        let deep_topics = self.deep_topics;
+        let threads = std::thread::available_parallelism().map_or(1, |n| n.get()).min(projects.len()).max(1);
+        let message_analyzer = &self.message_analyzer;
+        let cancellation = &self.cancellation;
+        let topic_analyses: Vec<(String, TopicAnalysis)> = std::thread::scope(|scope| {
+            let handles: Vec<_> = projects
+                .chunks(projects.len().div_ceil(threads).max(1))
+                .map(|chunk| {
+                    scope.spawn(move || {
+                        chunk
+                            .iter()
+                            .take_while(|_| !cancellation.is_cancelled())
+                            .map(|(project_name, sessions)| {
+                                let mut merged = TopicCounts::default();
+                                for (session, digest) in sessions {
+                                    if deep_topics {
+                                        merged.merge(&digest.topics);
+                                    } else if let Some(summary) = &session.summary {
+                                        merged.merge(&message_analyzer.count_summary_topics(summary));
+                                    }
+                                }
+                                (project_name.clone(), message_analyzer.topic_analysis(&merged))
+                            })
+                            .collect::<Vec<_>>()
+                    })
                 })
-                .flat_map(|session| session.entries.clone())
                 .collect();
-            
-            if !project_entries.is_empty() {
-                let topic_analysis = self.message_analyzer.analyze_project_topics(&project_entries);
+            handles
+                .into_iter()
+                .flat_map(|handle| handle.join().unwrap_or_else(|panic| std::panic::resume_unwind(panic)))
+                .collect()
+        });
+        for (project_name, topic_analysis) in topic_analyses {
+            if let Some(stats) = project_stats.get_mut(&project_name) {
                 stats.topic_analysis = Some(topic_analysis);
             }
         }
@@ -282,41 +853,27 @@ impl WorkAnalyzer {
         project_stats
     }
 
-    /// Extract readable content from message content variant
-    fn extract_message_content(&self, content: &MessageContentVariant) -> String {
-        match content {
-            MessageContentVariant::String(s) => s.clone(),
-            MessageContentVariant::Array(blocks) => {
-                blocks
-                    .iter()
-                    .filter_map(|block| block.text.as_ref())
-                    .cloned()
-                    .collect::<Vec<String>>()
-                    .join(" ")
-            }
+    /// Analysis of one project's sessions of `analysis`, as if only its logs had been read
+    ///
+    /// `project_name` is a key of [`WorkAnalysis::project_stats`]. The sessions'
+    /// entries are analyzed again, so they must have been kept (see
+    /// [`Self::with_session_entries`]).
+    pub fn analyze_project(&self, analysis: &WorkAnalysis, project_name: &str) -> Result<WorkAnalysis> {
+        let mut sessions: Vec<&WorkSession> = analysis
+            .sessions
+            .iter()
+            .filter(|session| analysis.project_name(session) == project_name)
+            .collect();
+        if sessions.iter().any(|session| session.entries.is_empty()) {
+            return Err(anyhow::anyhow!("Per-project analysis needs the entries of the sessions"));
         }
-    }
+        sessions.sort_by_key(|session| session.start_time);
 
-    /// Calculate the overall time range of the entries
-    fn calculate_time_range(&self, entries: &[ClaudeLogEntry]) -> (DateTime<Utc>, DateTime<Utc>) {
-        if entries.is_empty() {
-            let now = Utc::now();
-            return (now, now);
+        let mut folder = self.folder();
+        for entry in sessions.into_iter().flat_map(|session| session.entries.iter()) {
+            folder.push(entry.clone());
         }
-
-        let min_time = entries
-            .iter()
-            .map(|e| e.timestamp)
-            .min()
-            .unwrap_or_else(Utc::now);
-
-        let max_time = entries
-            .iter()
-            .map(|e| e.timestamp)
-            .max()
-            .unwrap_or_else(Utc::now);
-
-        (min_time, max_time)
+        Ok(folder.finish())
     }
 
     /// Get sessions for a specific project
@@ -324,12 +881,7 @@ impl WorkAnalyzer {
         analysis
             .sessions
             .iter()
-            .filter(|session| {
-                ProjectScanner::extract_project_name(
-                    std::path::Path::new(&session.project_path)
-                ).map(|name| name.contains(project_name))
-                .unwrap_or(false)
-            })
+            .filter(|session| analysis.project_name(session).contains(project_name))
             .collect()
     }
 
@@ -348,31 +900,28 @@ impl WorkAnalyzer {
     }
 
     /// Generate conversation summary from all sessions
-    fn generate_conversation_summary(&self, sessions: &[WorkSession]) -> ConversationSummary {
-        let sessions_with_summaries: Vec<(Vec<ClaudeLogEntry>, crate::models::SessionSummary)> = sessions
-            .iter()
-            .filter_map(|session| {
-                if let Some(ref summary) = session.summary {
-                    Some((session.entries.clone(), summary.clone()))
-                } else {
-                    None
-                }
-            })
-            .collect();
+    fn generate_conversation_summary(&self, sessions: &[WorkSession], tokens: &TokenAnalysis) -> ConversationSummary {
+        let _analysis = self.profiler.enter("message analysis");
+        let summaries: Vec<&SessionSummary> = sessions.iter().filter_map(|session| session.summary.as_ref()).collect();
 
-        if sessions_with_summaries.is_empty() {
+        if summaries.is_empty() {
             return ConversationSummary {
                 total_topics: 0,
                 most_discussed_topics: Vec::new(),
                 technology_usage: HashMap::new(),
                 common_problems: Vec::new(),
+                problem_confidence: HashMap::new(),
                 learning_progression: Vec::new(),
                 productivity_insights: Vec::new(),
                 overall_themes: Vec::new(),
+                topic_themes: Vec::new(),
+                follow_ups_by_technology: HashMap::new(),
             };
         }
 
-        self.message_analyzer.analyze_conversations(&sessions_with_summaries)
+        let mut summary = self.message_analyzer.analyze_conversations(&summaries);
+        summary.topic_themes = cluster_sessions(sessions, tokens);
+        summary
     }
 }
 
@@ -382,10 +931,309 @@ impl Default for WorkAnalyzer {
     }
 }
 
+/// Folds log entries into work sessions as they are read
+///
+/// Entries are expected in log order, as they are in the files: a log session's
+/// entries are buffered until another session starts, then split into work
+/// sessions whose statistics are taken right away.
+pub struct SessionFolder<'a> {
+    analyzer: &'a WorkAnalyzer,
+    /// Uuids of the entries added so far, to count copies once
+    seen: HashSet<Uuid>,
+    /// API responses whose usage was counted
+    seen_messages: HashSet<String>,
+    /// Entries of the log session being read
+    run: Vec<ClaudeLogEntry>,
+    sessions: Vec<(WorkSession, SessionDigest)>,
+    time_range: Option<(DateTime<Utc>, DateTime<Utc>)>,
+}
+
+impl SessionFolder<'_> {
+    /// Add the next entry
+    pub fn push(&mut self, entry: ClaudeLogEntry) {
+        if self.analyzer.cancellation.is_cancelled() {
+            return;
+        }
+        let _sessionize = self.analyzer.profiler.enter("sessionize");
+        if (!self.analyzer.include_sidechains && entry.is_sidechain) || !self.seen.insert(entry.uuid) {
+            return;
+        }
+        if self.run.last().is_some_and(|last| last.session_id != entry.session_id) {
+            self.close_run();
+        }
+        self.run.push(entry);
+    }
+
+    /// Analyze the sessions found in all entries added
+    pub fn finish(mut self) -> WorkAnalysis {
+        let _sessionize = self.analyzer.profiler.enter("sessionize");
+        self.close_run();
+        if self.analyzer.cancellation.is_cancelled() {
+            self.sessions.clear();
+        }
+        self.analyzer.analyze_sessions(self.sessions, self.time_range)
+    }
+
+    fn close_run(&mut self) {
+        let mut entries = std::mem::take(&mut self.run);
+        if self.analyzer.cancellation.is_cancelled() {
+            return;
+        }
+        // Skewed clocks would otherwise create false gaps between sessions
+        correct_timestamps(&mut entries);
+        for entry in &entries {
+            self.time_range = Some(match self.time_range {
+                Some((first, last)) => (first.min(entry.timestamp), last.max(entry.timestamp)),
+                None => (entry.timestamp, entry.timestamp),
+            });
+        }
+
+        for mut session in self.analyzer.group_entries_into_sessions(entries) {
+            let digest = self.analyzer.digest(&session, &mut self.seen_messages);
+            if !self.analyzer.keep_session_entries {
+                session.entries = Vec::new();
+            }
+            self.sessions.push((session, digest));
+        }
+    }
+}
+
+/// What a session adds to the statistics of the analysis, taken from its entries
+#[derive(Default)]
+struct SessionDigest {
+    tokens: TokenAnalysis,
+    tool_usage: ToolUsageSummary,
+    code_blocks: CodeBlockSummary,
+    /// Activity type of each user message
+    activity_types: HashMap<String, usize>,
+    /// Summed confidence of the classifications counted in `activity_types`
+    activity_confidence: HashMap<String, f64>,
+    /// Messages per local weekday and hour
+    heatmap: ActivityHeatmap,
+    /// Topics of the messages, for the project's topic analysis
+    topics: TopicCounts,
+    /// Follow-up prompts of the discussions about each technology
+    follow_ups: HashMap<String, FollowUpStats>,
+    /// Uuids of the session's entries, to find the sessions that continue it
+    entry_uuids: HashSet<Uuid>,
+    /// Entry of another session the first entry replies to, when the session continues it
+    parent_uuid: Option<Uuid>,
+    /// Entries of subagent conversations
+    sidechain_messages: usize,
+    /// Assistant the session was imported from; None for Claude Code
+    source: Option<String>,
+}
+
+impl SessionDigest {
+    /// Add the digest of a session merged into `session`
+    fn merge(&mut self, other: SessionDigest, session: &WorkSession) {
+        self.tokens.merge(other.tokens);
+        let mut stats = TokenStats::default();
+        for tokens in self.tokens.by_session.drain(..) {
+            stats.merge(&tokens.stats);
+        }
+        if stats.requests > 0 {
+            self.tokens.by_session.push(SessionTokens {
+                session_id: session.session_id,
+                project_path: session.project_path.clone(),
+                start_time: session.start_time,
+                stats,
+            });
+        }
+        self.tool_usage.merge(&other.tool_usage);
+        self.code_blocks.merge(&other.code_blocks);
+        for (activity_type, count) in other.activity_types {
+            *self.activity_types.entry(activity_type).or_insert(0) += count;
+        }
+        for (activity_type, confidence) in other.activity_confidence {
+            *self.activity_confidence.entry(activity_type).or_insert(0.0) += confidence;
+        }
+        self.heatmap.merge(&other.heatmap);
+        self.topics.merge(&other.topics);
+        for (tech, stats) in other.follow_ups {
+            self.follow_ups.entry(tech).or_default().merge(&stats);
+        }
+        self.sidechain_messages += other.sidechain_messages;
+    }
+}
+
+/// Counts tool_use blocks and matches them with their tool_result outcomes
+pub struct ToolUsageAnalyzer;
+
+impl ToolUsageAnalyzer {
+    pub fn new() -> Self {
+        Self
+    }
+
+    pub fn analyze(&self, sessions: &[WorkSession]) -> ToolUsageSummary {
+        let mut summary = self.analyze_by_path(sessions);
+        summary.by_project = ProjectNames::new(sessions.iter().map(|session| session.project_path.as_str())).rename(summary.by_project);
+        summary
+    }
+
+    /// Summary with its projects keyed by their paths, to be named once all sessions are known
+    fn analyze_by_path(&self, sessions: &[WorkSession]) -> ToolUsageSummary {
+        let mut summary = ToolUsageSummary::default();
+
+        for session in sessions {
+            let project_path = &session.project_path;
+            // tool_use id -> tool name, to attribute results to their tool
+            let mut pending_calls: HashMap<String, String> = HashMap::new();
+
+            for entry in &session.entries {
+                let MessageContentVariant::Array(blocks) = &entry.message.content else {
+                    continue;
+                };
+
+                for block in blocks {
+                    match block.content_type.as_str() {
+                        "tool_use" => {
+                            let tool = block.name.clone().unwrap_or_else(|| "unknown".to_string());
+                            if let Some(id) = &block.id {
+                                pending_calls.insert(id.clone(), tool.clone());
+                            }
+                            summary.total_invocations += 1;
+                            summary.by_tool.entry(tool.clone()).or_default().invocations += 1;
+                            summary.by_project.entry(project_path.clone()).or_default()
+                                .entry(tool).or_default().invocations += 1;
+                        }
+                        "tool_result" => {
+                            let Some(tool) = block.tool_use_id.as_ref().and_then(|id| pending_calls.remove(id)) else {
+                                continue;
+                            };
+                            let failed = block.is_error.unwrap_or_else(|| Self::is_error_result(entry));
+                            let record = |stats: &mut ToolStats| {
+                                if failed {
+                                    stats.failures += 1;
+                                } else {
+                                    stats.successes += 1;
+                                }
+                            };
+                            record(summary.by_tool.entry(tool.clone()).or_default());
+                            record(summary.by_project.entry(project_path.clone()).or_default()
+                                .entry(tool).or_default());
+                        }
+                        _ => {}
+                    }
+                }
+            }
+        }
+
+        summary
+    }
+
+    /// Older logs only mark failures through an "Error: ..." toolUseResult string
+    fn is_error_result(entry: &ClaudeLogEntry) -> bool {
+        entry.tool_use_result
+            .as_ref()
+            .is_some_and(|result| result.get().starts_with("\"Error"))
+    }
+}
+
+impl Default for ToolUsageAnalyzer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Counts the fenced code blocks of the assistant's text by language
+pub struct CodeBlockAnalyzer;
+
+impl CodeBlockAnalyzer {
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Days are local to `timezone`
+    pub fn analyze(&self, sessions: &[WorkSession], timezone: &TimezoneSchedule) -> CodeBlockSummary {
+        let mut summary = self.analyze_by_path(sessions, timezone);
+        summary.by_project = ProjectNames::new(sessions.iter().map(|session| session.project_path.as_str())).rename(summary.by_project);
+        summary
+    }
+
+    /// Summary with its projects keyed by their paths, to be named once all sessions are known
+    fn analyze_by_path(&self, sessions: &[WorkSession], timezone: &TimezoneSchedule) -> CodeBlockSummary {
+        let mut summary = CodeBlockSummary::default();
+
+        for session in sessions {
+            let project_path = &session.project_path;
+
+            for entry in session.entries.iter().filter(|entry| matches!(entry.entry_type, EntryType::Assistant)) {
+                let day = timezone.to_local(entry.timestamp).date_naive();
+                for (language, lines) in Self::code_blocks(&entry.message.content.text()) {
+                    let stats = CodeBlockStats { blocks: 1, lines };
+                    summary.total.merge(&stats);
+                    summary.by_language.entry(language.clone()).or_default().merge(&stats);
+                    summary.by_project.entry(project_path.clone()).or_default()
+                        .entry(language.clone()).or_default().merge(&stats);
+                    summary.by_day.entry(day).or_default().entry(language).or_default().merge(&stats);
+                }
+            }
+        }
+
+        summary
+    }
+
+    /// Language and line count of each fenced block, one left open running to the end
+    fn code_blocks(text: &str) -> Vec<(String, usize)> {
+        let mut blocks = Vec::new();
+        // Fence character and length of the open block, its language and its lines so far
+        let mut open: Option<(char, usize, String, usize)> = None;
+        for line in text.lines() {
+            let trimmed = line.trim_start();
+            let fence_char = trimmed.chars().next().filter(|c| matches!(c, '`' | '~')).unwrap_or('\0');
+            let fence_len = trimmed.chars().take_while(|c| *c == fence_char).count();
+            match open.take() {
+                // A closing fence is at least as long as the opening one and has no info string
+                Some((c, len, language, lines)) => {
+                    if fence_char == c && fence_len >= len && trimmed[fence_len..].trim().is_empty() {
+                        blocks.push((language, lines));
+                    } else {
+                        open = Some((c, len, language, lines + 1));
+                    }
+                }
+                None if fence_len >= 3 => {
+                    let info = trimmed[fence_len..].split_whitespace().next().unwrap_or("");
+                    open = Some((fence_char, fence_len, Self::language(info), 0));
+                }
+                None => {}
+            }
+        }
+        if let Some((_, _, language, lines)) = open {
+            blocks.push((language, lines));
+        }
+        blocks
+    }
+
+    /// Common aliases of the fence info string folded into one name
+    fn language(info: &str) -> String {
+        let info = info.trim_matches(|c: char| c == '{' || c == '}' || c == '.').to_lowercase();
+        let language = match info.as_str() {
+            "" => "unspecified",
+            "rs" => "rust",
+            "py" | "python3" => "python",
+            "js" | "jsx" | "mjs" => "javascript",
+            "ts" | "tsx" => "typescript",
+            "sh" | "shell" | "zsh" | "console" => "bash",
+            "yml" => "yaml",
+            "golang" => "go",
+            "c++" | "cc" => "cpp",
+            other => other,
+        };
+        language.to_string()
+    }
+}
+
+impl Default for CodeBlockAnalyzer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::models::{MessageContent, MessageContentVariant};
+    use crate::models::{ActivityType, MessageContent, MessageContentVariant};
     use uuid::Uuid;
 
     fn create_test_entry(
@@ -420,6 +1268,7 @@ mod tests {
             timestamp,
             request_id: None,
             tool_use_result: None,
+            source: None,
         }
     }
 
@@ -436,7 +1285,7 @@ mod tests {
             create_test_entry(base_time + Duration::minutes(15), session_id, "/project1", EntryType::Assistant, "response 2"),
         ];
 
-        let sessions = analyzer.group_entries_into_sessions(&entries);
+        let sessions = analyzer.group_entries_into_sessions(entries);
         
         assert_eq!(sessions.len(), 1);
         assert_eq!(sessions[0].entries.len(), 4);
@@ -458,13 +1307,573 @@ mod tests {
             create_test_entry(base_time + Duration::hours(2) + Duration::minutes(5), session_id, "/project1", EntryType::Assistant, "response 2"),
         ];
 
-        let sessions = analyzer.group_entries_into_sessions(&entries);
+        let sessions = analyzer.group_entries_into_sessions(entries);
         
         assert_eq!(sessions.len(), 2);
         assert_eq!(sessions[0].entries.len(), 2);
         assert_eq!(sessions[1].entries.len(), 2);
     }
 
+    #[test]
+    fn test_duplicate_entries_count_once() {
+        let session_id = Uuid::new_v4();
+        let base_time = Utc::now();
+        let entries = [
+            create_test_entry(base_time, session_id, "/project1", EntryType::User, "test 1"),
+            create_test_entry(base_time + Duration::minutes(5), session_id, "/project1", EntryType::Assistant, "response 1"),
+            create_test_entry(base_time + Duration::minutes(10), session_id, "/project1", EntryType::User, "test 2"),
+        ];
+        // The same log synced into a second projects directory
+        let merged: Vec<ClaudeLogEntry> = entries.iter().chain(entries.iter()).cloned().collect();
+
+        let analysis = WorkAnalyzer::new().analyze_entries(&merged).unwrap();
+
+        assert_eq!(analysis.total_sessions, 1);
+        assert_eq!(analysis.total_messages, 3);
+    }
+
+    #[test]
+    fn test_clock_skew_does_not_split_sessions() {
+        let analyzer = WorkAnalyzer::new().with_session_gap(Duration::hours(1));
+        let session_id = Uuid::new_v4();
+        let base_time = Utc::now();
+
+        let entries = vec![
+            create_test_entry(base_time, session_id, "/project1", EntryType::User, "test 1"),
+            create_test_entry(base_time + Duration::minutes(5), session_id, "/project1", EntryType::Assistant, "response 1"),
+            // Logged from a machine whose clock is three hours ahead
+            create_test_entry(base_time + Duration::hours(3), session_id, "/project1", EntryType::User, "test 2"),
+            create_test_entry(base_time + Duration::minutes(15), session_id, "/project1", EntryType::Assistant, "response 2"),
+            create_test_entry(base_time + Duration::minutes(20), session_id, "/project1", EntryType::User, "test 3"),
+        ];
+
+        let analysis = analyzer.analyze_entries(&entries).unwrap();
+
+        assert_eq!(analysis.total_sessions, 1);
+        assert_eq!(analysis.total_work_time, Duration::minutes(20));
+    }
+
+    #[test]
+    fn test_restart_merging() {
+        let base_time = Utc::now() - Duration::hours(5);
+        let (first, restarted, other) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+        let messages = |session_id: Uuid, project: &str, start: DateTime<Utc>| -> Vec<ClaudeLogEntry> {
+            (0..3)
+                .map(|i| create_test_entry(start + Duration::minutes(i), session_id, project, EntryType::User, "continue"))
+                .collect()
+        };
+
+        let mut entries = messages(first, "/project1", base_time);
+        // Restarted two minutes later in the same project
+        entries.extend(messages(restarted, "/project1", base_time + Duration::minutes(4)));
+        // A different project is never merged
+        entries.extend(messages(other, "/project2", base_time + Duration::minutes(8)));
+
+        let unmerged = WorkAnalyzer::new().analyze_entries(&entries).unwrap();
+        assert_eq!(unmerged.total_sessions, 3);
+
+        let analysis = WorkAnalyzer::new()
+            .with_restart_merge(Duration::minutes(5))
+            .analyze_entries(&entries)
+            .unwrap();
+        assert_eq!(analysis.total_sessions, 2);
+        let merged = analysis.sessions.iter().find(|s| s.session_id == first).unwrap();
+        assert_eq!(merged.merged_session_ids, vec![restarted]);
+        assert_eq!(merged.total_messages, 6);
+        assert_eq!(merged.end_time - merged.start_time, Duration::minutes(6));
+
+        let strict = WorkAnalyzer::new()
+            .with_restart_merge(Duration::minutes(1))
+            .analyze_entries(&entries)
+            .unwrap();
+        assert_eq!(strict.total_sessions, 3);
+
+        // Restarts in two projects worked on side by side
+        let (web, web_restarted) = (Uuid::new_v4(), Uuid::new_v4());
+        let mut entries = messages(first, "/project1", base_time);
+        entries.extend(messages(web, "/project2", base_time + Duration::minutes(3)));
+        entries.extend(messages(restarted, "/project1", base_time + Duration::minutes(6)));
+        entries.extend(messages(web_restarted, "/project2", base_time + Duration::minutes(9)));
+
+        let interleaved = WorkAnalyzer::new()
+            .with_restart_merge(Duration::minutes(5))
+            .analyze_entries(&entries)
+            .unwrap();
+        assert_eq!(interleaved.total_sessions, 2);
+        let merged_ids = |id: Uuid| &interleaved.sessions.iter().find(|s| s.session_id == id).unwrap().merged_session_ids;
+        assert_eq!(merged_ids(first), &vec![restarted]);
+        assert_eq!(merged_ids(web), &vec![web_restarted]);
+    }
+
+    #[test]
+    fn test_continued_sessions() {
+        let base_time = Utc::now() - Duration::hours(10);
+        let (first, resumed, other, late) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+        // Three messages, the first replying to `parent`
+        let messages = |session_id: Uuid, project: &str, start: DateTime<Utc>, parent: Option<Uuid>| -> Vec<ClaudeLogEntry> {
+            let mut entries: Vec<ClaudeLogEntry> = (0..3)
+                .map(|i| create_test_entry(start + Duration::minutes(i), session_id, project, EntryType::User, "continue"))
+                .collect();
+            entries[0].parent_uuid = parent;
+            for i in 1..entries.len() {
+                entries[i].parent_uuid = Some(entries[i - 1].uuid);
+            }
+            entries
+        };
+
+        let mut entries = messages(first, "/project1", base_time, None);
+        let first_last = entries[2].uuid;
+        // Another project in between does not stop the resumed session from joining the first
+        entries.extend(messages(other, "/project2", base_time + Duration::minutes(10), None));
+        let resumed_entries = messages(resumed, "/project1", base_time + Duration::minutes(40), Some(first_last));
+        let resumed_last = resumed_entries[2].uuid;
+        entries.extend(resumed_entries);
+        // Resumed the next morning: a new block of work
+        entries.extend(messages(late, "/project1", base_time + Duration::hours(8), Some(resumed_last)));
+
+        let analysis = WorkAnalyzer::new().analyze_entries(&entries).unwrap();
+        assert_eq!(analysis.total_sessions, 3);
+        let stitched = analysis.sessions.iter().find(|s| s.session_id == first).unwrap();
+        assert_eq!(stitched.merged_session_ids, vec![resumed]);
+        assert_eq!((stitched.total_messages, stitched.end_time - stitched.start_time), (6, Duration::minutes(42)));
+        assert_eq!((analysis.log_sessions(), analysis.stitched_sessions()), (4, 1));
+
+        let report = crate::reporter::ReportGenerator::new().generate_markdown_report(&analysis).unwrap();
+        assert!(report.contains("- **Log Sessions:** 4 (1 continued under a new session ID"));
+    }
+
+    #[test]
+    fn test_short_sessions_count_their_tokens() {
+        let base_time = Utc::now() - Duration::hours(5);
+        let mut reply = create_test_entry(base_time + Duration::minutes(1), Uuid::new_v4(), "/project1", EntryType::Assistant, "done");
+        reply.message.id = Some("msg_1".to_string());
+        reply.message.usage = Some(crate::models::UsageInfo {
+            input_tokens: Some(100),
+            output_tokens: Some(10),
+            cache_creation_input_tokens: None,
+            cache_read_input_tokens: None,
+            service_tier: None,
+        });
+        let entries = vec![
+            create_test_entry(base_time, reply.session_id, "/project1", EntryType::User, "fix the error"),
+            reply,
+        ];
+
+        let analysis = WorkAnalyzer::new().analyze_entries(&entries).unwrap();
+        assert_eq!(analysis.total_sessions, 0);
+        let tokens = analysis.token_analysis.unwrap();
+        assert_eq!((tokens.total.usage.input_tokens, tokens.total.usage.output_tokens), (100, 10));
+        assert_eq!(tokens.by_project["project1"].usage.output_tokens, 10);
+        assert_eq!(tokens.by_day.values().map(|day| day.usage.output_tokens).sum::<u64>(), 10);
+        assert!(tokens.by_session.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_streaming_without_session_entries() {
+        let base_time = Utc::now() - Duration::hours(5);
+        let (first, restarted) = (Uuid::new_v4(), Uuid::new_v4());
+        let messages = |session_id: Uuid, start: DateTime<Utc>, message_id: &str| -> Vec<ClaudeLogEntry> {
+            let mut entries: Vec<ClaudeLogEntry> = (0..3)
+                .map(|i| create_test_entry(start + Duration::minutes(i), session_id, "/project1", EntryType::User, "fix the error"))
+                .collect();
+            let mut reply = create_test_entry(start + Duration::minutes(3), session_id, "/project1", EntryType::Assistant, "done");
+            reply.message.id = Some(message_id.to_string());
+            reply.message.usage = Some(crate::models::UsageInfo {
+                input_tokens: Some(100),
+                output_tokens: Some(10),
+                cache_creation_input_tokens: None,
+                cache_read_input_tokens: None,
+                service_tier: None,
+            });
+            entries.push(reply);
+            entries
+        };
+        let mut entries = messages(first, base_time, "msg_1");
+        entries.extend(messages(restarted, base_time + Duration::minutes(5), "msg_2"));
+
+        let analyzer = WorkAnalyzer::new().with_restart_merge(Duration::minutes(5));
+        let full = analyzer.analyze_entries(&entries).unwrap();
+        let streamed = analyzer
+            .with_session_entries(false)
+            .analyze_stream(futures::stream::iter(entries.into_iter().map(Ok)))
+            .await
+            .unwrap();
+
+        assert_eq!(streamed.total_sessions, 1);
+        assert_eq!((streamed.total_messages, streamed.time_range), (full.total_messages, full.time_range));
+        assert!(streamed.sessions[0].entries.is_empty());
+        assert_eq!(streamed.sessions[0].merged_session_ids, vec![restarted]);
+        assert!(streamed.sessions[0].summary.is_some());
+
+        // Token usage of the merged sessions is attributed to the merged session
+        let tokens = streamed.token_analysis.unwrap();
+        assert_eq!(tokens.total.usage, full.token_analysis.as_ref().unwrap().total.usage);
+        assert_eq!(tokens.by_session.len(), 1);
+        assert_eq!((tokens.by_session[0].session_id, tokens.by_session[0].stats.requests), (first, 2));
+
+        let (stats, full_stats) = (&streamed.project_stats["project1"], &full.project_stats["project1"]);
+        assert_eq!(stats.activity_types, full_stats.activity_types);
+        // Topics are counted as sessions are read, so dropping the entries keeps them
+        assert!(full_stats.topic_analysis.is_some());
+        assert_eq!(stats.topic_analysis, full_stats.topic_analysis);
+    }
+
+    #[test]
+    fn test_analyze_project() {
+        let base_time = Utc::now() - Duration::hours(5);
+        let messages = |start: DateTime<Utc>, cwd: &str| -> Vec<ClaudeLogEntry> {
+            let session_id = Uuid::new_v4();
+            (0..3)
+                .map(|i| create_test_entry(start + Duration::minutes(i), session_id, cwd, EntryType::User, "fix the error"))
+                .collect()
+        };
+        let mut entries = messages(base_time, "/home/me/api");
+        entries.extend(messages(base_time + Duration::minutes(30), "/home/me/web"));
+        entries.extend(messages(base_time + Duration::minutes(60), "/home/me/api"));
+
+        let analyzer = WorkAnalyzer::new();
+        let analysis = analyzer.analyze_entries(&entries).unwrap();
+        let api = analyzer.analyze_project(&analysis, "api").unwrap();
+        assert_eq!((api.total_sessions, api.total_messages), (2, 6));
+        assert_eq!(api.project_stats.keys().collect::<Vec<_>>(), ["api"]);
+        assert_eq!(api.project_stats["api"].total_sessions, analysis.project_stats["api"].total_sessions);
+        assert_eq!(analyzer.analyze_project(&analysis, "ap").unwrap().total_sessions, 0);
+
+        let streamed = WorkAnalyzer::new().with_session_entries(false).analyze_entries(&entries).unwrap();
+        assert!(analyzer.analyze_project(&streamed, "api").is_err());
+    }
+
+    #[test]
+    fn test_project_topic_analysis() {
+        let base_time = Utc::now() - Duration::hours(20);
+        let contents = [
+            "implement the parser in rust",
+            "configure docker for the rust service",
+            "fix the compile error in typescript",
+        ];
+        let mut entries = Vec::new();
+        for project in 0..12 {
+            for (i, content) in contents.iter().enumerate() {
+                let start = base_time + Duration::minutes(90 * (project * contents.len() + i) as i64);
+                let session_id = Uuid::new_v4();
+                let cwd = format!("/home/me/project{}", project % 6);
+                entries.extend((0..3).map(|j| {
+                    create_test_entry(start + Duration::minutes(j), session_id, &cwd, EntryType::User, content)
+                }));
+            }
+        }
+
+        let analyzer = WorkAnalyzer::new().with_deep_topics(true);
+        let analysis = analyzer.analyze_entries(&entries).unwrap();
+        assert_eq!(analysis.project_stats.len(), 6);
+        for (project_name, stats) in &analysis.project_stats {
+            let project_entries: Vec<&ClaudeLogEntry> = analysis
+                .sessions
+                .iter()
+                .filter(|session| session.project_path.ends_with(project_name.as_str()))
+                .flat_map(|session| &session.entries)
+                .collect();
+            let expected = analyzer.message_analyzer.analyze_project_topics(&project_entries);
+            assert_eq!(stats.topic_analysis.as_ref(), Some(&expected));
+            assert_eq!(expected.technical_stack, ["docker", "rust", "typescript"]);
+        }
+
+        // Derived from the session summaries, topics are ranked by sessions
+        let summarized = WorkAnalyzer::new().analyze_entries(&entries).unwrap();
+        for (project_name, stats) in &summarized.project_stats {
+            let topics = stats.topic_analysis.as_ref().unwrap();
+            let deep = analysis.project_stats[project_name].topic_analysis.as_ref().unwrap();
+            assert_eq!(topics.technical_stack, deep.technical_stack);
+            assert_eq!(topics.primary_topics, deep.primary_topics);
+            // Only messages the summary kept as problems are categorized
+            assert_eq!(topics.problem_categories, HashMap::from([("Runtime Error".to_string(), 6)]));
+            assert_eq!(deep.problem_categories["Configuration Issue"], 6);
+        }
+    }
+
+    #[test]
+    fn test_time_buckets() {
+        use chrono::TimeZone;
+
+        let messages = |start: DateTime<Utc>| -> Vec<ClaudeLogEntry> {
+            let session_id = Uuid::new_v4();
+            (0..3)
+                .map(|i| create_test_entry(start + Duration::minutes(10 * i), session_id, "/project1", EntryType::User, "work"))
+                .collect()
+        };
+        // Sunday 2025-06-29 23:30 JST, then Monday 2025-06-30 and Tuesday 2025-07-01 in JST
+        let mut entries = messages(Utc.with_ymd_and_hms(2025, 6, 29, 14, 30, 0).unwrap());
+        entries.extend(messages(Utc.with_ymd_and_hms(2025, 6, 29, 16, 0, 0).unwrap()));
+        entries.extend(messages(Utc.with_ymd_and_hms(2025, 7, 1, 1, 0, 0).unwrap()));
+        let date = |m, d| NaiveDate::from_ymd_opt(2025, m, d).unwrap();
+
+        let analyze = |grouping| {
+            WorkAnalyzer::new()
+                .with_timezone(crate::timezone::jst())
+                .with_grouping(grouping)
+                .analyze_entries(&entries)
+                .unwrap()
+                .time_buckets
+                .unwrap()
+        };
+
+        let weeks = analyze(TimeGrouping::Week);
+        let starts: Vec<NaiveDate> = weeks.buckets.iter().map(|b| b.start).collect();
+        assert_eq!(starts, [date(6, 23), date(6, 30)]);
+        assert_eq!(weeks.buckets[1].sessions, 2);
+        assert_eq!(weeks.buckets[1].messages, 6);
+        assert_eq!(weeks.buckets[1].work_time, Duration::minutes(40));
+
+        let months = analyze(TimeGrouping::Month);
+        let starts: Vec<NaiveDate> = months.buckets.iter().map(|b| b.start).collect();
+        assert_eq!(starts, [date(6, 1), date(7, 1)]);
+        assert_eq!(months.buckets[0].sessions, 2);
+
+        assert!(WorkAnalyzer::new().analyze_entries(&entries).unwrap().time_buckets.is_none());
+    }
+
+    #[test]
+    fn test_activity_heatmap() {
+        use chrono::{TimeZone, Weekday};
+
+        let session_id = Uuid::new_v4();
+        // Sunday 23:30 JST, then an hour later on Monday and four messages on Tuesday morning
+        let times = [(6, 29, 14, 30), (6, 29, 14, 50), (6, 29, 15, 30), (7, 1, 1, 0), (7, 1, 1, 5), (7, 1, 1, 10), (7, 1, 1, 15)];
+        let entries: Vec<ClaudeLogEntry> = times
+            .iter()
+            .map(|&(m, d, h, min)| {
+                let timestamp = Utc.with_ymd_and_hms(2025, m, d, h, min, 0).unwrap();
+                create_test_entry(timestamp, session_id, "/project1", EntryType::User, "work")
+            })
+            .collect();
+
+        let heatmap = WorkAnalyzer::new()
+            .with_timezone(crate::timezone::jst())
+            .with_session_gap(Duration::days(3))
+            .analyze_entries(&entries)
+            .unwrap()
+            .heatmap
+            .unwrap();
+        assert_eq!(heatmap.count(Weekday::Sun, 23), 2);
+        assert_eq!(heatmap.count(Weekday::Mon, 0), 1);
+        assert_eq!(heatmap.count(Weekday::Tue, 10), 4);
+        assert_eq!(heatmap.total(), 7);
+        assert_eq!(heatmap.peak(), Some((Weekday::Tue, 10, 4)));
+    }
+
+    #[test]
+    fn test_subfolders_stay_in_project() {
+        let analyzer = WorkAnalyzer::new();
+        let session_id = Uuid::new_v4();
+        let base_time = Utc::now();
+
+        let entries = vec![
+            create_test_entry(base_time, session_id, "/home/user/a/api", EntryType::User, "test 1"),
+            create_test_entry(base_time + Duration::minutes(5), session_id, "/home/user/a/api/src", EntryType::Assistant, "response 1"),
+            create_test_entry(base_time + Duration::minutes(10), session_id, "/home/user/a/api/src/db", EntryType::User, "test 2"),
+            create_test_entry(base_time + Duration::minutes(15), session_id, "/home/user/a/api", EntryType::Assistant, "response 2"),
+            // Shares a prefix but is a different directory
+            create_test_entry(base_time + Duration::minutes(20), session_id, "/home/user/a/api-v2", EntryType::User, "test 3"),
+        ];
+
+        let sessions = analyzer.group_entries_into_sessions(entries);
+
+        assert_eq!(sessions.len(), 2);
+        assert_eq!(sessions[0].entries.len(), 4);
+        assert_eq!(sessions[0].project_path, "/home/user/a/api");
+        assert_eq!(sessions[1].project_path, "/home/user/a/api-v2");
+    }
+
+    #[test]
+    fn test_project_aliases() {
+        let aliases = ProjectAliases::new([("/home/user/clones/api".to_string(), "/home/user/work/api".to_string())]);
+        let analyzer = WorkAnalyzer::new().with_min_messages(1).with_project_aliases(aliases);
+        let base_time = Utc::now();
+        let (first, second) = (Uuid::new_v4(), Uuid::new_v4());
+
+        let entries = vec![
+            create_test_entry(base_time, first, "/home/user/work/api", EntryType::User, "test 1"),
+            create_test_entry(base_time + Duration::minutes(5), first, "/home/user/work/api", EntryType::Assistant, "response 1"),
+            create_test_entry(base_time + Duration::hours(3), second, "/home/user/clones/api/src", EntryType::User, "test 2"),
+        ];
+
+        let analysis = analyzer.analyze_entries(&entries).unwrap();
+        assert_eq!(analysis.total_sessions, 2);
+        assert!(analysis.sessions.iter().all(|session| session.project_path == "/home/user/work/api"));
+        assert_eq!(analysis.project_stats.len(), 1);
+    }
+
+    #[test]
+    fn test_windows_paths() {
+        let analyzer = WorkAnalyzer::new().with_project_dirs(&[PathBuf::from(r"C:\Users\me\.claude\projects\C--Users-me-api")]);
+        let session_id = Uuid::new_v4();
+        let base_time = Utc::now();
+
+        let entries = vec![
+            create_test_entry(base_time, session_id, r"C:\Users\me\api\src", EntryType::User, "test 1"),
+            // Drive letters and folder names may change case between entries
+            create_test_entry(base_time + Duration::minutes(5), session_id, r"c:\users\me\api", EntryType::Assistant, "response 1"),
+            create_test_entry(base_time + Duration::minutes(10), session_id, r"C:\Users\me\web", EntryType::User, "test 2"),
+        ];
+
+        let sessions = analyzer.group_entries_into_sessions(entries);
+
+        assert_eq!(sessions.len(), 2);
+        assert_eq!(sessions[0].entries.len(), 2);
+        assert_eq!(sessions[0].project_path, r"C:\Users\me\api");
+        assert_eq!(sessions[1].project_path, r"C:\Users\me\web");
+    }
+
+    #[test]
+    fn test_same_named_projects_are_distinct() {
+        let base_time = Utc::now() - Duration::hours(5);
+        let (first, second) = (Uuid::new_v4(), Uuid::new_v4());
+        let mut entries: Vec<_> = (0..3)
+            .map(|i| create_test_entry(base_time + Duration::minutes(i), first, "/home/user/a/api", EntryType::User, "work"))
+            .collect();
+        entries.extend((0..3).map(|i| {
+            create_test_entry(base_time + Duration::minutes(4 + i), second, "/home/user/b/api", EntryType::User, "work")
+        }));
+
+        let analysis = WorkAnalyzer::new()
+            .with_restart_merge(Duration::minutes(5))
+            .analyze_entries(&entries)
+            .unwrap();
+
+        assert_eq!(analysis.total_sessions, 2);
+        let mut names: Vec<&String> = analysis.project_stats.keys().collect();
+        names.sort();
+        assert_eq!(names, ["a/api", "b/api"]);
+        assert!(analysis.project_stats.values().all(|stats| stats.total_sessions == 1 && stats.total_messages == 3));
+        let b_sessions = WorkAnalyzer::new().get_project_sessions(&analysis, "b/api");
+        assert_eq!(b_sessions.len(), 1);
+        assert_eq!(b_sessions[0].project_path, "/home/user/b/api");
+    }
+
+    #[test]
+    fn test_nested_project_roots() {
+        let project_dirs = [
+            PathBuf::from("/projects/-home-user-api"),
+            PathBuf::from("/projects/-home-user-api-tools"),
+        ];
+        let analyzer = WorkAnalyzer::new().with_project_dirs(&project_dirs);
+        let session_id = Uuid::new_v4();
+        let base_time = Utc::now();
+
+        let entries = vec![
+            create_test_entry(base_time, session_id, "/home/user/api/src", EntryType::User, "test 1"),
+            create_test_entry(base_time + Duration::minutes(5), session_id, "/home/user/api", EntryType::Assistant, "response 1"),
+            // A project of its own, even though it lives inside the first one
+            create_test_entry(base_time + Duration::minutes(10), session_id, "/home/user/api/tools", EntryType::User, "test 2"),
+            create_test_entry(base_time + Duration::minutes(15), session_id, "/home/user/api/tools/bin", EntryType::Assistant, "response 2"),
+        ];
+
+        let sessions = analyzer.group_entries_into_sessions(entries);
+
+        assert_eq!(sessions.len(), 2);
+        assert_eq!(sessions[0].project_path, "/home/user/api");
+        assert_eq!(sessions[1].project_path, "/home/user/api/tools");
+        assert_eq!(sessions[1].entries.len(), 2);
+    }
+
+    #[test]
+    fn test_tool_usage_analysis() {
+        let session_id = Uuid::new_v4();
+        let base_time = Utc::now();
+        let block = |content_type: &str, id: Option<&str>, name: Option<&str>, tool_use_id: Option<&str>, is_error: Option<bool>| {
+            crate::models::ContentBlock {
+                content_type: content_type.to_string(),
+                text: None,
+                thinking: None,
+                signature: None,
+                id: id.map(str::to_string),
+                name: name.map(str::to_string),
+                input: None,
+                tool_use_id: tool_use_id.map(str::to_string),
+                is_error,
+            }
+        };
+
+        let mut call = create_test_entry(base_time, session_id, "/work/api", EntryType::Assistant, "");
+        call.message.content = MessageContentVariant::Array(vec![
+            block("tool_use", Some("t1"), Some("Bash"), None, None),
+            block("tool_use", Some("t2"), Some("Bash"), None, None),
+            block("tool_use", Some("t3"), Some("Read"), None, None),
+        ]);
+        let mut results = create_test_entry(base_time + Duration::minutes(1), session_id, "/work/api", EntryType::User, "");
+        results.message.content = MessageContentVariant::Array(vec![
+            block("tool_result", None, None, Some("t1"), Some(false)),
+            block("tool_result", None, None, Some("t2"), Some(true)),
+        ]);
+        let mut legacy_failure = create_test_entry(base_time + Duration::minutes(2), session_id, "/work/api", EntryType::User, "");
+        legacy_failure.message.content = MessageContentVariant::Array(vec![
+            block("tool_result", None, None, Some("t3"), None),
+        ]);
+        legacy_failure.tool_use_result = Some(serde_json::value::RawValue::from_string("\"Error: file not found\"".to_string()).unwrap());
+
+        let analysis = WorkAnalyzer::new()
+            .analyze_entries(&[call, results, legacy_failure])
+            .unwrap();
+        let tool_usage = analysis.tool_usage.unwrap();
+
+        assert_eq!(tool_usage.total_invocations, 3);
+        assert_eq!(tool_usage.by_tool["Bash"], ToolStats { invocations: 2, successes: 1, failures: 1 });
+        assert_eq!(tool_usage.by_tool["Read"].failures, 1);
+        assert_eq!(tool_usage.by_project["api"]["Bash"].invocations, 2);
+    }
+
+    #[test]
+    fn test_code_block_analysis() {
+        let session_id = Uuid::new_v4();
+        let base_time = Utc::now();
+        let response = "Here is the fix:\n```rs\nfn main() {\n    run();\n}\n```\nThen run:\n  ~~~\n  cargo test\n  ~~~\n````markdown\n```python\nprint(1)\n```\n````\n";
+        let entries = [
+            create_test_entry(base_time, session_id, "/work/api", EntryType::User, "```python\nnot counted\n```"),
+            create_test_entry(base_time + Duration::minutes(1), session_id, "/work/api", EntryType::Assistant, response),
+            // Cut off before the closing fence
+            create_test_entry(base_time + Duration::minutes(2), session_id, "/work/web", EntryType::Assistant, "```Rust\nlet x = 1;\nlet y = 2;"),
+        ];
+
+        let code_blocks = WorkAnalyzer::new().with_min_messages(1).analyze_entries(&entries).unwrap().code_blocks.unwrap();
+
+        assert_eq!(code_blocks.total, CodeBlockStats { blocks: 4, lines: 9 });
+        assert_eq!(code_blocks.by_language["rust"], CodeBlockStats { blocks: 2, lines: 5 });
+        assert_eq!(code_blocks.by_language["unspecified"], CodeBlockStats { blocks: 1, lines: 1 });
+        // The python block is the content of the markdown one
+        assert_eq!(code_blocks.by_language["markdown"].lines, 3);
+        assert!(!code_blocks.by_language.contains_key("python"));
+        assert_eq!(code_blocks.by_project["web"]["rust"].lines, 2);
+        assert_eq!(code_blocks.by_project["api"].len(), 3);
+        let lines_by_day: usize = code_blocks.by_day.values().flat_map(|languages| languages.values()).map(|stats| stats.lines).sum();
+        assert_eq!(lines_by_day, 9);
+    }
+
+    #[test]
+    fn test_redacted_analysis() {
+        let session_id = Uuid::new_v4();
+        let base_time = Utc::now();
+        let entries = [
+            create_test_entry(base_time, session_id, "/home/me/acme/api", EntryType::User, "Mail ops@acme.io about /home/me/acme/api/deploy.sh"),
+            create_test_entry(base_time + Duration::minutes(1), session_id, "/home/me/acme/api/src", EntryType::Assistant, "Acme's token: abc123 is set"),
+        ];
+        let redactor = Redactor::new(&["acme".to_string()]).unwrap();
+        let project = redactor.project("/home/me/acme/api");
+
+        let analysis = WorkAnalyzer::new()
+            .with_min_messages(1)
+            .with_project_dirs(&[PathBuf::from("-home-me-acme-api")])
+            .with_redactor(redactor)
+            .analyze_entries(&entries)
+            .unwrap();
+
+        assert_eq!(analysis.project_stats.keys().collect::<Vec<_>>(), [&project]);
+        let session = &analysis.sessions[0];
+        assert_eq!(session.project_path, project);
+        assert_eq!(session.entries[1].cwd, format!("{}/src", project));
+        assert_eq!(session.entries[0].message.content.text(), format!("Mail [email] about {}/deploy.sh", project));
+        assert_eq!(session.entries[1].message.content.text(), "[redacted]'s token: [secret] is set");
+    }
+
     #[test]
     fn test_activity_type_classification() {
         assert!(matches!(
@@ -488,6 +1897,76 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn test_sidechain_exclusion() {
+        let session_id = Uuid::new_v4();
+        let base_time = Utc::now();
+
+        let mut entries: Vec<ClaudeLogEntry> = (0..4)
+            .map(|i| create_test_entry(base_time + Duration::minutes(i), session_id, "/project1", EntryType::User, "test"))
+            .collect();
+        entries[2].is_sidechain = true;
+        entries[3].is_sidechain = true;
+
+        let with_sidechains = WorkAnalyzer::new().analyze_entries(&entries).unwrap();
+        assert_eq!(with_sidechains.total_messages, 4);
+
+        // Only two main-chain entries remain, which is below the 3 message minimum
+        let without_sidechains = WorkAnalyzer::new().with_sidechains(false).analyze_entries(&entries).unwrap();
+        assert_eq!(without_sidechains.total_sessions, 0);
+    }
+
+    #[test]
+    fn test_subagent_sessions() {
+        let base_time = Utc::now() - Duration::hours(5);
+        let (parent, other) = (Uuid::new_v4(), Uuid::new_v4());
+        let reply = |timestamp: DateTime<Utc>, session_id: Uuid, message_id: &str, input: u32| {
+            let mut entry = create_test_entry(timestamp, session_id, "/project1", EntryType::Assistant, "done");
+            entry.message.id = Some(message_id.to_string());
+            entry.message.usage = Some(crate::models::UsageInfo {
+                input_tokens: Some(input),
+                output_tokens: Some(10),
+                cache_creation_input_tokens: None,
+                cache_read_input_tokens: None,
+                service_tier: None,
+            });
+            entry
+        };
+
+        let mut entries: Vec<ClaudeLogEntry> = (0..3)
+            .map(|i| create_test_entry(base_time + Duration::minutes(i * 10), parent, "/project1", EntryType::User, "review the api"))
+            .collect();
+        entries.push(reply(base_time + Duration::minutes(30), parent, "msg_1", 200));
+        entries.extend((0..3).map(|i| create_test_entry(base_time + Duration::minutes(i), other, "/project2", EntryType::User, "fix the test")));
+        // The subagent's log is read as its own run under the parent's session ID
+        let mut subagent = vec![
+            create_test_entry(base_time + Duration::minutes(11), parent, "/project1", EntryType::User, "find the handlers"),
+            reply(base_time + Duration::minutes(12), parent, "msg_2", 100),
+        ];
+        subagent.iter_mut().for_each(|entry| entry.is_sidechain = true);
+        entries.extend(subagent);
+
+        let analysis = WorkAnalyzer::new().analyze_entries(&entries).unwrap();
+        assert_eq!(analysis.total_sessions, 2);
+        let session = analysis.sessions.iter().find(|s| s.session_id == parent).unwrap();
+        assert_eq!(session.total_messages, 6);
+
+        let sidechains = analysis.sidechains.as_ref().unwrap();
+        assert_eq!(sidechains.total.messages, 2);
+        assert_eq!(sidechains.total.tokens.usage.total(), 110);
+        assert_eq!(sidechains.by_project["project1"].messages, 2);
+        assert_eq!((sidechains.by_session.len(), sidechains.by_session[0].session_id), (1, parent));
+        assert_eq!(analysis.token_analysis.as_ref().unwrap().total.usage.total(), 320);
+
+        let report = crate::reporter::ReportGenerator::new().generate_markdown_report(&analysis).unwrap();
+        assert!(report.contains("## 🤖 Subagents"));
+        assert!(report.contains("**Delegated Messages:** 2 of 9 (22%)"));
+        assert!(report.contains("**Delegated Tokens:** 110 of 320 (34%)"));
+
+        let without = WorkAnalyzer::new().with_sidechains(false).analyze_entries(&entries).unwrap();
+        assert!(without.sidechains.is_none());
+    }
+
     #[test]
     fn test_empty_entries_analysis() {
         let analyzer = WorkAnalyzer::new();
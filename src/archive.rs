@@ -0,0 +1,99 @@
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+
+use crate::scanner::ProjectScanner;
+
+/// Log files [`archive_logs`] copied and found already archived
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ArchiveCounts {
+    pub copied: usize,
+    pub unchanged: usize,
+}
+
+/// `<data dir>/claude-work-analysis/archive`
+pub fn default_archive_dir() -> Option<PathBuf> {
+    dirs::data_dir().map(|dir| dir.join("claude-work-analysis").join("archive"))
+}
+
+/// Copy the JSONL logs under `projects_dirs` into `archive_dir`
+///
+/// Files keep their path within their projects directory, so the archive reads
+/// like one more projects directory. A file is copied again when its size
+/// differs from the archived copy or it was modified since; archived files
+/// whose log has been deleted are kept.
+pub fn archive_logs(projects_dirs: &[PathBuf], archive_dir: &Path) -> Result<ArchiveCounts> {
+    let archive = archive_dir.canonicalize().unwrap_or_else(|_| archive_dir.to_path_buf());
+    let scanner = ProjectScanner::new();
+    let mut counts = ArchiveCounts::default();
+
+    for projects_dir in projects_dirs {
+        // Archiving the archive into itself would only copy files onto themselves
+        if projects_dir.canonicalize().is_ok_and(|dir| dir == archive) {
+            continue;
+        }
+        for source in scanner.scan_projects(projects_dir)? {
+            let Ok(relative) = source.strip_prefix(projects_dir) else {
+                continue;
+            };
+            let target = archive_dir.join(relative);
+            if is_archived(&source, &target)? {
+                counts.unchanged += 1;
+                continue;
+            }
+            if let Some(parent) = target.parent() {
+                std::fs::create_dir_all(parent)
+                    .with_context(|| format!("Failed to create archive directory: {}", parent.display()))?;
+            }
+            std::fs::copy(&source, &target)
+                .with_context(|| format!("Failed to archive {} to {}", source.display(), target.display()))?;
+            counts.copied += 1;
+        }
+    }
+
+    Ok(counts)
+}
+
+/// Whether `target` holds the current contents of `source`
+fn is_archived(source: &Path, target: &Path) -> Result<bool> {
+    let Ok(archived) = target.metadata() else {
+        return Ok(false);
+    };
+    let current = source
+        .metadata()
+        .with_context(|| format!("Failed to read log file: {}", source.display()))?;
+    // The copy is written after the log was last modified
+    Ok(current.len() == archived.len() && current.modified()? <= archived.modified()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_archive_logs() {
+        let temp_dir = TempDir::new().unwrap();
+        let projects_dir = temp_dir.path().join("projects");
+        let archive_dir = temp_dir.path().join("archive");
+        let log = projects_dir.join("-home-me-api").join("session.jsonl");
+        std::fs::create_dir_all(log.parent().unwrap()).unwrap();
+        std::fs::write(&log, "{}\n").unwrap();
+
+        let dirs = vec![projects_dir.clone()];
+        assert_eq!(archive_logs(&dirs, &archive_dir).unwrap(), ArchiveCounts { copied: 1, unchanged: 0 });
+        let archived = archive_dir.join("-home-me-api").join("session.jsonl");
+        assert_eq!(std::fs::read_to_string(&archived).unwrap(), "{}\n");
+        assert_eq!(archive_logs(&dirs, &archive_dir).unwrap(), ArchiveCounts { copied: 0, unchanged: 1 });
+
+        // Grown logs are copied again, pruned ones stay in the archive
+        std::fs::write(&log, "{}\n{}\n").unwrap();
+        assert_eq!(archive_logs(&dirs, &archive_dir).unwrap().copied, 1);
+        std::fs::remove_file(&log).unwrap();
+        assert_eq!(archive_logs(&dirs, &archive_dir).unwrap(), ArchiveCounts::default());
+        assert_eq!(std::fs::read_to_string(&archived).unwrap(), "{}\n{}\n");
+
+        // The archive listed as a projects directory is skipped
+        let with_archive = vec![projects_dir, archive_dir.clone()];
+        assert_eq!(archive_logs(&with_archive, &archive_dir).unwrap(), ArchiveCounts::default());
+    }
+}
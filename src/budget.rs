@@ -0,0 +1,74 @@
+use chrono::{Datelike, Months, NaiveDate};
+use serde::{Deserialize, Serialize};
+
+/// Month-to-date estimated spend against a monthly budget
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BudgetStatus {
+    /// Budget for the calendar month in USD
+    pub monthly_budget: f64,
+    /// Local day the spend was measured on
+    pub today: NaiveDate,
+    /// Estimated cost of the month's tokens up to and including today
+    pub spent: f64,
+}
+
+impl BudgetStatus {
+    /// The spend of the month of `today` from the estimated cost per local day
+    pub fn new(monthly_budget: f64, daily_costs: impl IntoIterator<Item = (NaiveDate, f64)>, today: NaiveDate) -> Self {
+        let (first_day, _) = Self::month_of(today);
+        let spent = daily_costs
+            .into_iter()
+            .filter(|(day, _)| (first_day..=today).contains(day))
+            .map(|(_, cost)| cost)
+            .sum();
+        Self { monthly_budget, today, spent }
+    }
+
+    /// First and last day of the calendar month containing `day`
+    pub fn month_of(day: NaiveDate) -> (NaiveDate, NaiveDate) {
+        let first_day = day.with_day(1).unwrap();
+        (first_day, first_day + Months::new(1) - chrono::Duration::days(1))
+    }
+
+    pub fn month_start(&self) -> NaiveDate {
+        Self::month_of(self.today).0
+    }
+
+    pub fn days_in_month(&self) -> u32 {
+        Self::month_of(self.today).1.day()
+    }
+
+    /// Spend of the whole month if every remaining day costs as much as the days so far
+    pub fn projected(&self) -> f64 {
+        self.spent / self.today.day() as f64 * self.days_in_month() as f64
+    }
+
+    /// Whether the month is on track to cost more than the budget
+    pub fn exceeded(&self) -> bool {
+        self.projected() > self.monthly_budget
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn day(month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2025, month, day).unwrap()
+    }
+
+    #[test]
+    fn test_projection() {
+        let costs = [(day(5, 31), 50.0), (day(6, 1), 10.0), (day(6, 9), 20.0), (day(6, 10), 5.0), (day(6, 11), 100.0)];
+        let status = BudgetStatus::new(100.0, costs, day(6, 10));
+        assert_eq!(status.month_start(), day(6, 1));
+        assert_eq!(status.days_in_month(), 30);
+        assert!((status.spent - 35.0).abs() < 1e-9);
+        assert!((status.projected() - 105.0).abs() < 1e-9);
+        assert!(status.exceeded());
+
+        let status = BudgetStatus::new(120.0, costs, day(6, 10));
+        assert!(!status.exceeded());
+        assert_eq!(BudgetStatus::new(1.0, [], day(2, 28)).projected(), 0.0);
+    }
+}
@@ -0,0 +1,176 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, NaiveDate, Utc};
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::{Deserialize, Serialize};
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use crate::daily_summary::WorkSummary;
+use crate::importers::write_sessions;
+use crate::models::{ClaudeLogEntry, WorkAnalysis};
+
+/// Marks the first line of a bundle
+const BUNDLE_FORMAT: &str = "claude-work-analysis-bundle";
+
+/// Bumped whenever the layout changes
+const BUNDLE_VERSION: u32 = 1;
+
+/// What a bundle holds besides its entries
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BundleManifest {
+    format: String,
+    version: u32,
+    pub created_at: DateTime<Utc>,
+    /// Project the entries were limited to
+    pub project: Option<String>,
+    /// Whether paths, project names and secrets were replaced as with `--redact`
+    pub redacted: bool,
+    pub entries: usize,
+    /// Figures of the analysis the bundle was exported from
+    pub summary: WorkSummary,
+}
+
+/// Log entries of a period with the figures they were analyzed to, portable to
+/// another machine
+///
+/// The file is gzip-compressed JSON Lines: the manifest, then one entry per line.
+#[derive(Debug, Clone)]
+pub struct Bundle {
+    pub manifest: BundleManifest,
+    pub entries: Vec<ClaudeLogEntry>,
+}
+
+impl Bundle {
+    /// The entries of the sessions of `analysis`, from the local days `first_day` to `last_day`
+    ///
+    /// The analysis must keep the entries of its sessions; they are redacted
+    /// already when the analyzer was given a redactor.
+    pub fn from_analysis(
+        analysis: &WorkAnalysis,
+        (first_day, last_day): (NaiveDate, NaiveDate),
+        project: Option<String>,
+        redacted: bool,
+        created_at: DateTime<Utc>,
+    ) -> Self {
+        let mut entries: Vec<ClaudeLogEntry> =
+            analysis.sessions.iter().flat_map(|session| session.entries.iter().cloned()).collect();
+        entries.sort_by_key(|entry| entry.timestamp);
+        let manifest = BundleManifest {
+            format: BUNDLE_FORMAT.to_string(),
+            version: BUNDLE_VERSION,
+            created_at,
+            project,
+            redacted,
+            entries: entries.len(),
+            summary: WorkSummary::of_period(first_day, last_day, analysis),
+        };
+        Self { manifest, entries }
+    }
+
+    pub fn write(&self, path: &Path) -> Result<()> {
+        let file = std::fs::File::create(path).with_context(|| format!("Failed to create {}", path.display()))?;
+        let mut writer = BufWriter::new(GzEncoder::new(file, Compression::default()));
+        serde_json::to_writer(&mut writer, &self.manifest)?;
+        writer.write_all(b"\n")?;
+        for entry in &self.entries {
+            serde_json::to_writer(&mut writer, entry)?;
+            writer.write_all(b"\n")?;
+        }
+        writer
+            .into_inner()
+            .map_err(|e| e.into_error())?
+            .finish()
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+        Ok(())
+    }
+
+    pub fn read(path: &Path) -> Result<Self> {
+        let file = std::fs::File::open(path).with_context(|| format!("Failed to open {}", path.display()))?;
+        let mut lines = BufReader::new(GzDecoder::new(file)).lines();
+        let first_line = lines
+            .next()
+            .transpose()
+            .with_context(|| format!("Not a bundle: {}", path.display()))?
+            .unwrap_or_default();
+        let manifest: BundleManifest = serde_json::from_str(&first_line)
+            .ok()
+            .filter(|manifest: &BundleManifest| manifest.format == BUNDLE_FORMAT)
+            .ok_or_else(|| anyhow::anyhow!("Not a bundle: {}", path.display()))?;
+        if manifest.version != BUNDLE_VERSION {
+            return Err(anyhow::anyhow!(
+                "Bundle version {} is not supported (expected {}): {}",
+                manifest.version,
+                BUNDLE_VERSION,
+                path.display()
+            ));
+        }
+        let mut entries = Vec::with_capacity(manifest.entries);
+        for (index, line) in lines.enumerate() {
+            let line = line.with_context(|| format!("Failed to read {}", path.display()))?;
+            let entry = serde_json::from_str(&line)
+                .with_context(|| format!("Corrupt entry on line {} of {}", index + 2, path.display()))?;
+            entries.push(entry);
+        }
+        Ok(Self { manifest, entries })
+    }
+
+    /// Write the entries under `dir` laid out like a projects directory, one log
+    /// file per session; returns the number of sessions
+    pub fn unpack(&self, dir: &Path) -> Result<usize> {
+        write_sessions(&self.entries, "", dir)
+    }
+}
+
+/// `<data dir>/claude-work-analysis/bundles/<name of the bundle file>`
+pub fn default_unpack_dir(bundle: &Path) -> Option<PathBuf> {
+    let name = bundle.file_name()?.to_str()?.split('.').next()?.to_string();
+    dirs::data_dir().map(|dir| dir.join("claude-work-analysis").join("bundles").join(name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyzer::WorkAnalyzer;
+    use crate::parser::JsonlParser;
+    use crate::privacy::Redactor;
+    use crate::sample::write_sample;
+    use crate::scanner::ProjectScanner;
+    use tempfile::TempDir;
+
+    #[tokio::test]
+    async fn test_export_and_reanalyze_bundle() {
+        let temp_dir = TempDir::new().unwrap();
+        let logs = temp_dir.path().join("logs");
+        write_sample(&logs).unwrap();
+        let files = ProjectScanner::new().scan_projects(&logs).unwrap();
+        let entries = JsonlParser::new().parse_files(&files).await.unwrap();
+        let analyzer = WorkAnalyzer::new().with_redactor(Redactor::new(&["acme".to_string()]).unwrap());
+        let analysis = analyzer.analyze_entries(&entries).unwrap();
+        let day = NaiveDate::from_ymd_opt(2025, 6, 1).unwrap();
+
+        let bundle = Bundle::from_analysis(&analysis, (day, day), None, true, Utc::now());
+        let path = temp_dir.path().join("client.cwa.gz");
+        bundle.write(&path).unwrap();
+        let read = Bundle::read(&path).unwrap();
+        assert_eq!(read.manifest, bundle.manifest);
+        assert_eq!(read.entries.len(), bundle.entries.len());
+        assert!(read.manifest.redacted);
+        assert!(read.manifest.summary.projects.keys().all(|project| project.starts_with("project-")));
+
+        // The unpacked entries analyze to the figures of the bundle
+        let unpacked = temp_dir.path().join("unpacked");
+        assert_eq!(read.unpack(&unpacked).unwrap(), analysis.sessions.len());
+        let files = ProjectScanner::new().scan_projects(&unpacked).unwrap();
+        let entries = JsonlParser::new().parse_files(&files).await.unwrap();
+        assert!(entries.iter().all(|entry| !entry.cwd.starts_with("/home")));
+        let reanalyzed = WorkAnalyzer::new().analyze_entries(&entries).unwrap();
+        let summary = WorkSummary::of_period(day, day, &reanalyzed);
+        assert_eq!((summary.sessions, summary.messages, summary.work_seconds), (read.manifest.summary.sessions, read.manifest.summary.messages, read.manifest.summary.work_seconds));
+        assert_eq!(summary.projects, read.manifest.summary.projects);
+
+        std::fs::write(temp_dir.path().join("plain.jsonl"), "{}\n").unwrap();
+        assert!(Bundle::read(&temp_dir.path().join("plain.jsonl")).is_err());
+    }
+}
@@ -0,0 +1,231 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use crate::models::ClaudeLogEntry;
+use crate::parser::ParseStats;
+
+/// Bumped whenever the cached layout or parsing rules change
+const CACHE_VERSION: u32 = 3;
+
+/// Size and modification time used to detect changed log files
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct FileFingerprint {
+    pub(crate) size: u64,
+    pub(crate) modified_nanos: u128,
+}
+
+impl FileFingerprint {
+    pub(crate) async fn of(path: &Path) -> Option<Self> {
+        let metadata = tokio::fs::metadata(path).await.ok()?;
+        let modified = metadata.modified().ok()?.duration_since(UNIX_EPOCH).ok()?;
+        Some(Self {
+            size: metadata.len(),
+            modified_nanos: modified.as_nanos(),
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct CachedFile {
+    version: u32,
+    source: PathBuf,
+    fingerprint: FileFingerprint,
+    max_line_length: usize,
+    /// Whether malformed lines were skipped rather than failing the parse
+    skip_malformed: bool,
+    /// Bytes read up to the last complete line, where parsing can resume once the
+    /// file grows; `None` if the file ended in a partial line
+    parsed_bytes: Option<u64>,
+    stats: ParseStats,
+    entries: Vec<ClaudeLogEntry>,
+}
+
+/// On-disk cache of parsed JSONL files, keyed by path and invalidated by size/mtime
+#[derive(Debug, Clone)]
+pub struct ParseCache {
+    dir: PathBuf,
+}
+
+impl ParseCache {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// `~/.cache/claude-work-analysis` (or the platform equivalent)
+    pub fn default_location() -> Option<Self> {
+        dirs::cache_dir().map(|dir| Self::new(dir.join("claude-work-analysis")))
+    }
+
+    /// Directory holding the cached files
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Cached parse result for a file, if the file has not changed since it was stored
+    pub async fn load(&self, source: &Path, max_line_length: usize, skip_malformed: bool) -> Option<(Vec<ClaudeLogEntry>, ParseStats)> {
+        let fingerprint = FileFingerprint::of(source).await?;
+        let cached = self.read(source, max_line_length, skip_malformed).await?;
+        // Lines written while the file was being parsed make it longer than what was read
+        let complete = cached.parsed_bytes.is_none_or(|bytes| bytes == fingerprint.size);
+        (cached.fingerprint == fingerprint && complete).then_some((cached.entries, cached.stats))
+    }
+
+    /// Cached parse result for a file that has grown since it was stored, with the
+    /// offset where the appended lines start
+    ///
+    /// Logs are append-only, so only the new lines need parsing.
+    pub async fn load_appended(
+        &self,
+        source: &Path,
+        max_line_length: usize,
+        skip_malformed: bool,
+    ) -> Option<(Vec<ClaudeLogEntry>, ParseStats, u64)> {
+        let fingerprint = FileFingerprint::of(source).await?;
+        let cached = self.read(source, max_line_length, skip_malformed).await?;
+        let parsed_bytes = cached.parsed_bytes?;
+        (fingerprint.size >= cached.fingerprint.size && fingerprint.size > parsed_bytes)
+            .then_some((cached.entries, cached.stats, parsed_bytes))
+    }
+
+    /// Cached parse result for a file, even if the file has changed since
+    pub async fn load_stale(&self, source: &Path, max_line_length: usize, skip_malformed: bool) -> Option<(Vec<ClaudeLogEntry>, ParseStats)> {
+        let cached = self.read(source, max_line_length, skip_malformed).await?;
+        Some((cached.entries, cached.stats))
+    }
+
+    async fn read(&self, source: &Path, max_line_length: usize, skip_malformed: bool) -> Option<CachedFile> {
+        let content = tokio::fs::read(self.cache_path(source)).await.ok()?;
+        let cached: CachedFile = serde_json::from_slice(&content).ok()?;
+
+        let valid = cached.version == CACHE_VERSION
+            && cached.source == source
+            && cached.max_line_length == max_line_length
+            && cached.skip_malformed == skip_malformed;
+        valid.then_some(cached)
+    }
+
+    /// Store a parse result for a file
+    pub async fn store(
+        &self,
+        source: &Path,
+        max_line_length: usize,
+        skip_malformed: bool,
+        parsed_bytes: Option<u64>,
+        entries: &[ClaudeLogEntry],
+        stats: &ParseStats,
+    ) -> Result<()> {
+        let fingerprint = FileFingerprint::of(source)
+            .await
+            .with_context(|| format!("Failed to read metadata: {}", source.display()))?;
+        let cached = CachedFile {
+            version: CACHE_VERSION,
+            source: source.to_path_buf(),
+            fingerprint,
+            max_line_length,
+            skip_malformed,
+            parsed_bytes,
+            stats: stats.clone(),
+            entries: entries.to_vec(),
+        };
+
+        let cache_path = self.cache_path(source);
+        tokio::fs::create_dir_all(&self.dir)
+            .await
+            .with_context(|| format!("Failed to create cache directory: {}", self.dir.display()))?;
+
+        // Write then rename so a concurrent reader never sees a partial file
+        let temp_path = cache_path.with_extension(format!("tmp{}", std::process::id()));
+        tokio::fs::write(&temp_path, serde_json::to_vec(&cached)?).await?;
+        tokio::fs::rename(&temp_path, &cache_path).await?;
+        Ok(())
+    }
+
+    fn cache_path(&self, source: &Path) -> PathBuf {
+        self.dir.join(format!("{:016x}.json", fnv1a(source.to_string_lossy().as_bytes())))
+    }
+}
+
+/// Stable 64-bit FNV-1a hash used to name cache files
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ *byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::JsonlParser;
+    use tempfile::TempDir;
+
+    const LINE: &str = r#"{"parentUuid":null,"isSidechain":false,"userType":"external","cwd":"/test","sessionId":"5abda76b-79c3-406d-bea9-e607520f0047","version":"1.0.0","type":"user","message":{"role":"user","content":"hello"},"uuid":"de267e16-94b3-41bd-8516-5fd1de7b3252","timestamp":"2025-07-01T01:00:00Z"}"#;
+
+    #[tokio::test]
+    async fn test_cache_round_trip_and_invalidation() {
+        let temp_dir = TempDir::new().unwrap();
+        let log_path = temp_dir.path().join("session.jsonl");
+        std::fs::write(&log_path, format!("{}\n", LINE)).unwrap();
+
+        let cache = ParseCache::new(temp_dir.path().join("cache"));
+        assert!(cache.load(&log_path, 1024, true).await.is_none());
+
+        let (entries, stats) = JsonlParser::new().parse_file_with_stats(&log_path).await.unwrap();
+        cache.store(&log_path, 1024, true, None, &entries, &stats).await.unwrap();
+
+        let (cached_entries, cached_stats) = cache.load(&log_path, 1024, true).await.unwrap();
+        assert_eq!(cached_entries.len(), 1);
+        assert_eq!(cached_entries[0].uuid, entries[0].uuid);
+        assert_eq!(cached_stats.total_lines, 1);
+
+        // Different parser limits or a grown file invalidate the entry
+        assert!(cache.load(&log_path, 2048, true).await.is_none());
+        // A strict parser does not take results that may have skipped malformed lines
+        assert!(cache.load(&log_path, 1024, false).await.is_none());
+        assert!(cache.load_stale(&log_path, 1024, false).await.is_none());
+        std::fs::write(&log_path, format!("{}\n{}\n", LINE, LINE)).unwrap();
+        assert!(cache.load(&log_path, 1024, true).await.is_none());
+        assert_eq!(cache.load_stale(&log_path, 1024, true).await.unwrap().0.len(), 1);
+        // Without a known line boundary the grown file has to be parsed in full
+        assert!(cache.load_appended(&log_path, 1024, true).await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_appended_lines_are_parsed_incrementally() {
+        let temp_dir = TempDir::new().unwrap();
+        let log_path = temp_dir.path().join("session.jsonl");
+        std::fs::write(&log_path, format!("{}\n", LINE)).unwrap();
+
+        let cache = ParseCache::new(temp_dir.path().join("cache"));
+        let parser = JsonlParser::new().with_max_line_length(1024).with_cache(cache.clone());
+        let (entries, _, cached) = parser.parse_file_with_cache_status(&log_path).await.unwrap();
+        assert_eq!((entries.len(), cached), (1, false));
+
+        let appended = LINE.replace("de267e16-94b3-41bd-8516-5fd1de7b3252", "9f1c1d2e-3b4a-4c5d-8e6f-708192a3b4c5");
+        std::fs::write(&log_path, format!("{}\n{}\n", LINE, appended)).unwrap();
+        let (_, _, offset) = cache.load_appended(&log_path, 1024, true).await.unwrap();
+        assert_eq!(offset, LINE.len() as u64 + 1);
+
+        // Without refresh the cached result is used as it is, and unknown files are skipped
+        let frozen = JsonlParser::new().with_max_line_length(1024).with_cache(cache.clone()).with_refresh(false);
+        assert_eq!(frozen.parse_file(&log_path).await.unwrap().len(), 1);
+        let other_path = temp_dir.path().join("other.jsonl");
+        std::fs::write(&other_path, format!("{}\n", LINE)).unwrap();
+        let (entries, stats, cached) = frozen.parse_file_with_cache_status(&other_path).await.unwrap();
+        assert!(entries.is_empty());
+        assert_eq!((stats.uncached_files, cached), (1, false));
+
+        let (entries, stats) = parser.parse_file_with_stats(&log_path).await.unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[1].uuid.to_string(), "9f1c1d2e-3b4a-4c5d-8e6f-708192a3b4c5");
+        assert_eq!(stats.total_lines, 2);
+        assert!(parser.parse_file_with_cache_status(&log_path).await.unwrap().2);
+    }
+
+    #[test]
+    fn test_fnv1a_is_stable() {
+        assert_eq!(fnv1a(b""), 0xcbf29ce484222325);
+        assert_eq!(fnv1a(b"a"), 0xaf63dc4c8601ec8c);
+    }
+}
@@ -0,0 +1,243 @@
+use chrono::{DateTime, Duration, Utc};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+use crate::models::ClaudeLogEntry;
+
+/// A timestamp moved so that a log session stays in chronological order
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TimestampCorrection {
+    /// Position of the entry in the slice that was checked
+    pub index: usize,
+    pub session_id: Uuid,
+    pub original: DateTime<Utc>,
+    pub corrected: DateTime<Utc>,
+}
+
+impl TimestampCorrection {
+    /// How far the original timestamp was off
+    pub fn skew(&self) -> Duration {
+        (self.original - self.corrected).abs()
+    }
+}
+
+/// A lone final entry this far after its predecessor, and [`FINAL_SPIKE_STEPS`]
+/// times the session's median step, is taken to be logged with a skewed clock
+const MIN_FINAL_SPIKE_MINUTES: i64 = 60;
+const FINAL_SPIKE_STEPS: i32 = 10;
+
+/// Find timestamps that break the order in which a session's entries were logged
+///
+/// Log files are append-only, so the file order of a session is trusted over its
+/// clock. Each step back in time ends a run of entries logged ahead of the
+/// others or starts one logged behind them: an ahead run begins with a jump
+/// forward at least as large as the step back, a behind run ends with one. Of
+/// the two, the run bounded by the larger jump is taken, and the whole run is
+/// moved by the step back, so that it joins its neighbours without reordering
+/// its own entries. A behind run without a jump after it runs to the end of the
+/// session. A final entry is also moved back when it is far ahead of the
+/// session's median step. Entries of different sessions are never compared.
+pub fn find_corrections(entries: &[ClaudeLogEntry]) -> Vec<TimestampCorrection> {
+    let mut by_session: HashMap<Uuid, Vec<usize>> = HashMap::new();
+    for (index, entry) in entries.iter().enumerate() {
+        by_session.entry(entry.session_id).or_default().push(index);
+    }
+
+    let mut corrections = Vec::new();
+    for (session_id, indices) in by_session {
+        let originals: Vec<DateTime<Utc>> = indices.iter().map(|&index| entries[index].timestamp).collect();
+        let corrected = correct_session(&originals);
+        for ((&index, original), corrected) in indices.iter().zip(originals).zip(corrected) {
+            if corrected != original {
+                corrections.push(TimestampCorrection {
+                    index,
+                    session_id,
+                    original,
+                    corrected,
+                });
+            }
+        }
+    }
+
+    corrections.sort_by_key(|c| c.index);
+    corrections
+}
+
+/// The timestamps of one session's entries in file order, put back in order
+fn correct_session(originals: &[DateTime<Utc>]) -> Vec<DateTime<Utc>> {
+    let mut times = originals.to_vec();
+    let len = times.len();
+
+    let mut k = 0;
+    while k + 1 < len {
+        if times[k + 1] >= times[k] {
+            k += 1;
+            continue;
+        }
+        let back = times[k] - times[k + 1];
+
+        // Entries `start..=k` ahead, and the jump into them; the first entries
+        // have no jump and only count while fewer than the rest
+        let ahead = (1..=k)
+            .rev()
+            .map(|j| (j, times[j] - times[j - 1]))
+            .find(|(_, jump)| *jump >= back)
+            .or_else(|| (k + 1 < len - (k + 1)).then_some((0, back)));
+        // Entries `k + 1..=end` behind, and the jump out of them
+        let behind = (k + 1..len - 1)
+            .map(|l| (l, times[l + 1] - times[l]))
+            .find(|(_, jump)| *jump >= back)
+            .unwrap_or((len - 1, back));
+
+        match ahead {
+            Some((start, jump)) if jump >= behind.1 => {
+                for time in &mut times[start..=k] {
+                    *time -= back;
+                }
+            }
+            _ => {
+                for time in &mut times[k + 1..=behind.0] {
+                    *time += back;
+                }
+            }
+        }
+        k += 1;
+    }
+
+    // A lone final entry far ahead has no step back after it to give it away
+    if len >= 4 {
+        let mut steps: Vec<Duration> = times[..len - 1].windows(2).map(|pair| pair[1] - pair[0]).collect();
+        steps.sort();
+        let median = steps[steps.len() / 2];
+        let last = times[len - 1] - times[len - 2];
+        if last >= Duration::minutes(MIN_FINAL_SPIKE_MINUTES) && last > median * FINAL_SPIKE_STEPS {
+            times[len - 1] = times[len - 2] + median;
+        }
+    }
+
+    times
+}
+
+/// Apply [`find_corrections`] in place and return what was changed
+pub fn correct_timestamps(entries: &mut [ClaudeLogEntry]) -> Vec<TimestampCorrection> {
+    let corrections = find_corrections(entries);
+    for correction in &corrections {
+        entries[correction.index].timestamp = correction.corrected;
+    }
+    corrections
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{EntryType, MessageContent, MessageContentVariant};
+    use chrono::TimeZone;
+
+    fn create_test_entry(session_id: Uuid, minute: i64) -> ClaudeLogEntry {
+        ClaudeLogEntry {
+            parent_uuid: None,
+            is_sidechain: false,
+            user_type: "external".to_string(),
+            cwd: "/test".to_string(),
+            session_id,
+            version: "1.0.0".to_string(),
+            entry_type: EntryType::User,
+            message: MessageContent {
+                role: "user".to_string(),
+                content: MessageContentVariant::String("test".to_string()),
+                id: None,
+                message_type: None,
+                model: None,
+                stop_reason: None,
+                stop_sequence: None,
+                usage: None,
+            },
+            uuid: Uuid::new_v4(),
+            timestamp: Utc.with_ymd_and_hms(2025, 7, 1, 9, 0, 0).unwrap() + Duration::minutes(minute),
+            request_id: None,
+            tool_use_result: None,
+            source: None,
+        }
+    }
+
+    fn minutes(entries: &[ClaudeLogEntry]) -> Vec<i64> {
+        let base = Utc.with_ymd_and_hms(2025, 7, 1, 9, 0, 0).unwrap();
+        entries.iter().map(|e| (e.timestamp - base).num_minutes()).collect()
+    }
+
+    #[test]
+    fn test_backward_step_is_moved_forward() {
+        let session = Uuid::new_v4();
+        let mut entries: Vec<_> = [0, 5, 2, 10].iter().map(|m| create_test_entry(session, *m)).collect();
+
+        let corrections = correct_timestamps(&mut entries);
+
+        assert_eq!(minutes(&entries), [0, 5, 5, 10]);
+        assert_eq!(corrections.len(), 1);
+        assert_eq!(corrections[0].index, 2);
+        assert_eq!(corrections[0].skew(), Duration::minutes(3));
+    }
+
+    #[test]
+    fn test_forward_spike_is_moved_back() {
+        let session = Uuid::new_v4();
+        // A clock three hours ahead for one entry would otherwise split the session
+        let mut entries: Vec<_> = [0, 5, 185, 10, 15].iter().map(|m| create_test_entry(session, *m)).collect();
+
+        let corrections = correct_timestamps(&mut entries);
+
+        assert_eq!(minutes(&entries), [0, 5, 10, 10, 15]);
+        assert_eq!(corrections.len(), 1);
+        assert_eq!(corrections[0].skew(), Duration::minutes(175));
+    }
+
+    #[test]
+    fn test_skewed_run_is_moved_back() {
+        let session = Uuid::new_v4();
+        let mut entries: Vec<_> = [0, 5, 185, 186, 190, 10, 12].iter().map(|m| create_test_entry(session, *m)).collect();
+
+        let corrections = correct_timestamps(&mut entries);
+
+        // The run keeps its own steps
+        assert_eq!(minutes(&entries), [0, 5, 5, 6, 10, 10, 12]);
+        assert_eq!(corrections.iter().map(|c| c.index).collect::<Vec<_>>(), [2, 3, 4]);
+        assert!(corrections.iter().all(|c| c.skew() == Duration::minutes(180)));
+
+        // Ahead up to the last step back
+        let mut entries: Vec<_> = [0, 5, 185, 186, 10].iter().map(|m| create_test_entry(session, *m)).collect();
+        correct_timestamps(&mut entries);
+        assert_eq!(minutes(&entries), [0, 5, 9, 10, 10]);
+
+        // A run of earlier timestamps is moved forward as a whole
+        let mut entries: Vec<_> = [0, 5, 10, 2, 3, 4, 15].iter().map(|m| create_test_entry(session, *m)).collect();
+        correct_timestamps(&mut entries);
+        assert_eq!(minutes(&entries), [0, 5, 10, 10, 11, 12, 15]);
+    }
+
+    #[test]
+    fn test_skewed_final_entry_is_moved_back() {
+        let session = Uuid::new_v4();
+        // Ahead without a step back after it
+        let mut entries: Vec<_> = [0, 5, 10, 15, 195].iter().map(|m| create_test_entry(session, *m)).collect();
+        let corrections = correct_timestamps(&mut entries);
+        assert_eq!(minutes(&entries), [0, 5, 10, 15, 20]);
+        assert_eq!(corrections.len(), 1);
+
+        // A short break is kept
+        let mut entries: Vec<_> = [0, 5, 10, 15, 50].iter().map(|m| create_test_entry(session, *m)).collect();
+        assert!(correct_timestamps(&mut entries).is_empty());
+    }
+
+    #[test]
+    fn test_sessions_are_checked_independently() {
+        let (first, second) = (Uuid::new_v4(), Uuid::new_v4());
+        let entries = vec![
+            create_test_entry(first, 30),
+            create_test_entry(first, 40),
+            create_test_entry(second, 0),
+            create_test_entry(second, 10),
+        ];
+
+        assert!(find_corrections(&entries).is_empty());
+    }
+}
@@ -0,0 +1,252 @@
+use std::collections::HashMap;
+
+use chrono::Duration;
+
+use crate::models::{TokenAnalysis, TokenStats, TopicTheme, WorkSession};
+
+/// Upper bound on the number of themes, however many sessions there are
+const MAX_THEMES: usize = 8;
+/// Terms used to name a theme
+const NAME_TERMS: usize = 3;
+/// Topics listed under a theme
+const THEME_TOPICS: usize = 8;
+const MAX_ITERATIONS: usize = 20;
+
+/// Group sessions into themes by the topics and technologies found in them
+///
+/// Each session is a TF-IDF vector over its terms, clustered with k-means
+/// (about √(n/2) clusters, farthest-first seeding so the result is deterministic).
+/// Themes are named after the heaviest terms of their centroid and ordered by
+/// number of sessions, with the work time and token cost of their sessions.
+/// Sessions without any terms are left out.
+pub fn cluster_sessions(sessions: &[WorkSession], tokens: &TokenAnalysis) -> Vec<TopicTheme> {
+    let mut vocabulary: HashMap<String, usize> = HashMap::new();
+    let mut terms: Vec<String> = Vec::new();
+    let mut documents: Vec<(&WorkSession, Vec<usize>)> = Vec::new();
+    for session in sessions {
+        let Some(summary) = &session.summary else { continue };
+        let mut ids: Vec<usize> = summary
+            .main_topics
+            .iter()
+            .chain(&summary.technologies_mentioned)
+            .map(|term| {
+                *vocabulary.entry(term.clone()).or_insert_with(|| {
+                    terms.push(term.clone());
+                    terms.len() - 1
+                })
+            })
+            .collect();
+        ids.sort_unstable();
+        ids.dedup();
+        if !ids.is_empty() {
+            documents.push((session, ids));
+        }
+    }
+    if documents.is_empty() {
+        return Vec::new();
+    }
+
+    let mut document_frequency = vec![0usize; terms.len()];
+    for (_, ids) in &documents {
+        for &term in ids {
+            document_frequency[term] += 1;
+        }
+    }
+    let count = documents.len() as f32;
+    let vectors: Vec<Vec<(usize, f32)>> = documents
+        .iter()
+        .map(|(_, ids)| {
+            let weights: Vec<(usize, f32)> = ids
+                .iter()
+                .map(|&term| (term, ((1.0 + count) / (1.0 + document_frequency[term] as f32)).ln() + 1.0))
+                .collect();
+            let norm = weights.iter().map(|(_, weight)| weight * weight).sum::<f32>().sqrt();
+            weights.into_iter().map(|(term, weight)| (term, weight / norm)).collect()
+        })
+        .collect();
+
+    let k = ((documents.len() as f64 / 2.0).sqrt().round() as usize).clamp(1, MAX_THEMES);
+    let mut centroids = seed_centroids(&vectors, k, terms.len());
+    let mut assignments = vec![usize::MAX; vectors.len()];
+    for _ in 0..MAX_ITERATIONS {
+        let next: Vec<usize> = vectors.iter().map(|vector| nearest(vector, &centroids)).collect();
+        if next == assignments {
+            break;
+        }
+        assignments = next;
+        centroids = (0..centroids.len())
+            .map(|cluster| {
+                let members = vectors.iter().zip(&assignments).filter(|(_, &a)| a == cluster).map(|(v, _)| v);
+                mean(members, terms.len())
+            })
+            .collect();
+    }
+
+    let session_tokens = tokens.stats_by_session();
+    let mut themes: Vec<TopicTheme> = centroids
+        .iter()
+        .enumerate()
+        .filter_map(|(cluster, centroid)| {
+            let mut members: Vec<usize> = (0..documents.len()).filter(|&i| assignments[i] == cluster).collect();
+            if members.is_empty() {
+                return None;
+            }
+            members.sort_by_key(|&i| documents[i].0.start_time);
+
+            let mut heaviest: Vec<(usize, f32)> =
+                centroid.iter().copied().enumerate().filter(|(_, weight)| *weight > 0.0).collect();
+            heaviest.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| terms[a.0].cmp(&terms[b.0])));
+            let name = heaviest.iter().take(NAME_TERMS).map(|(term, _)| terms[*term].as_str()).collect::<Vec<_>>();
+
+            let mut topic_counts: HashMap<usize, usize> = HashMap::new();
+            for &i in &members {
+                for &term in &documents[i].1 {
+                    *topic_counts.entry(term).or_insert(0) += 1;
+                }
+            }
+            let mut topics: Vec<(String, usize)> =
+                topic_counts.into_iter().map(|(term, sessions)| (terms[term].clone(), sessions)).collect();
+            topics.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+            topics.truncate(THEME_TOPICS);
+
+            let mut work_time = Duration::zero();
+            let mut theme_tokens = TokenStats::default();
+            for &i in &members {
+                let session = documents[i].0;
+                work_time += session.end_time - session.start_time;
+                if let Some(stats) = session_tokens.get(&(session.session_id, session.start_time)) {
+                    theme_tokens.merge(stats);
+                }
+            }
+
+            Some(TopicTheme {
+                name: name.join(" / "),
+                topics,
+                session_ids: members.iter().map(|&i| documents[i].0.session_id).collect(),
+                work_time,
+                tokens: theme_tokens,
+            })
+        })
+        .collect();
+    themes.sort_by(|a, b| b.session_ids.len().cmp(&a.session_ids.len()).then_with(|| a.name.cmp(&b.name)));
+    themes
+}
+
+/// Start from the document with most terms, then repeatedly add the one least
+/// similar to the centroids chosen so far
+fn seed_centroids(vectors: &[Vec<(usize, f32)>], k: usize, dimensions: usize) -> Vec<Vec<f32>> {
+    let first = (0..vectors.len()).max_by_key(|&i| (vectors[i].len(), std::cmp::Reverse(i))).unwrap_or(0);
+    let mut centroids = vec![mean(std::iter::once(&vectors[first]), dimensions)];
+    while centroids.len() < k {
+        let farthest = (0..vectors.len())
+            .map(|i| (i, centroids.iter().map(|c| dot(&vectors[i], c)).fold(f32::MIN, f32::max)))
+            .min_by(|a, b| a.1.total_cmp(&b.1).then_with(|| a.0.cmp(&b.0)));
+        match farthest {
+            // Every document is already a centroid or identical to one
+            Some((_, similarity)) if similarity >= 1.0 - f32::EPSILON => break,
+            Some((i, _)) => centroids.push(mean(std::iter::once(&vectors[i]), dimensions)),
+            None => break,
+        }
+    }
+    centroids
+}
+
+fn nearest(vector: &[(usize, f32)], centroids: &[Vec<f32>]) -> usize {
+    (0..centroids.len())
+        .max_by(|&a, &b| dot(vector, &centroids[a]).total_cmp(&dot(vector, &centroids[b])).then_with(|| b.cmp(&a)))
+        .unwrap_or(0)
+}
+
+fn dot(vector: &[(usize, f32)], centroid: &[f32]) -> f32 {
+    vector.iter().map(|(term, weight)| weight * centroid[*term]).sum()
+}
+
+/// Normalized mean of sparse vectors
+fn mean<'a>(vectors: impl Iterator<Item = &'a Vec<(usize, f32)>>, dimensions: usize) -> Vec<f32> {
+    let mut centroid = vec![0.0; dimensions];
+    for vector in vectors {
+        for (term, weight) in vector {
+            centroid[*term] += weight;
+        }
+    }
+    let norm = centroid.iter().map(|weight| weight * weight).sum::<f32>().sqrt();
+    if norm > 0.0 {
+        centroid.iter_mut().for_each(|weight| *weight /= norm);
+    }
+    centroid
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{SessionSummary, SessionTokens};
+    use chrono::{TimeZone, Utc};
+    use uuid::Uuid;
+
+    fn session(hour: i64, topics: &[&str]) -> WorkSession {
+        let start_time = Utc.with_ymd_and_hms(2025, 7, 1, 0, 0, 0).unwrap() + Duration::hours(hour);
+        WorkSession {
+            session_id: Uuid::new_v4(),
+            project_path: "/home/me/app".to_string(),
+            start_time,
+            end_time: start_time + Duration::minutes(30),
+            entries: Vec::new(),
+            total_messages: 4,
+            user_messages: 2,
+            assistant_messages: 2,
+            summary: Some(SessionSummary {
+                main_topics: topics.iter().map(|topic| topic.to_string()).collect(),
+                topic_mentions: HashMap::new(),
+                key_discussions: Vec::new(),
+                technologies_mentioned: Vec::new(),
+                problems_addressed: Vec::new(),
+                problem_confidence: HashMap::new(),
+                solutions_proposed: Vec::new(),
+                learning_moments: Vec::new(),
+                overall_summary: String::new(),
+            }),
+            merged_session_ids: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_cluster_sessions() {
+        let sessions = vec![
+            session(0, &["rust", "cargo", "borrow"]),
+            session(1, &["css", "layout"]),
+            session(2, &["rust", "borrow"]),
+            session(3, &["css", "tailwind", "layout"]),
+            session(4, &["rust", "cargo"]),
+            session(5, &["css", "layout"]),
+            session(6, &["rust", "cargo", "borrow"]),
+            session(7, &["tailwind", "css"]),
+            session(8, &[]),
+        ];
+
+        // Token usage of one session of each theme
+        let priced = |i: usize, cost| SessionTokens {
+            session_id: sessions[i].session_id,
+            project_path: sessions[i].project_path.clone(),
+            start_time: sessions[i].start_time,
+            stats: TokenStats { estimated_cost: cost, ..Default::default() },
+        };
+        let tokens = TokenAnalysis { by_session: vec![priced(0, 1.5), priced(2, 0.5), priced(1, 3.0)], ..Default::default() };
+
+        let themes = cluster_sessions(&sessions, &tokens);
+        assert_eq!(themes.len(), 2);
+        let rust = themes.iter().find(|theme| theme.name.contains("rust")).unwrap();
+        let css = themes.iter().find(|theme| theme.name.contains("css")).unwrap();
+        assert_eq!(rust.session_ids, [0, 2, 4, 6].map(|i| sessions[i].session_id));
+        assert_eq!(css.session_ids.len(), 4);
+        assert_eq!(rust.topics[0], ("rust".to_string(), 4));
+        assert!(!css.name.contains("rust"));
+        assert_eq!(rust.work_time, Duration::minutes(120));
+        assert_eq!(rust.tokens.estimated_cost, 2.0);
+        assert_eq!(css.tokens.estimated_cost, 3.0);
+
+        // The same input gives the same themes
+        let again = cluster_sessions(&sessions, &tokens);
+        assert_eq!(again.iter().map(|t| &t.name).collect::<Vec<_>>(), themes.iter().map(|t| &t.name).collect::<Vec<_>>());
+        assert!(cluster_sessions(&sessions[8..], &tokens).is_empty());
+    }
+}
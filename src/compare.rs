@@ -0,0 +1,222 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use crate::models::WorkAnalysis;
+
+/// A measure in the baseline period and in the current period
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Change<T> {
+    pub baseline: T,
+    pub current: T,
+}
+
+impl Change<f64> {
+    /// Relative change, e.g. 0.25 for 25% more; `None` when the baseline is zero
+    pub fn ratio(&self) -> Option<f64> {
+        (self.baseline != 0.0).then(|| (self.current - self.baseline) / self.baseline)
+    }
+}
+
+impl Change<usize> {
+    /// Relative change, e.g. 0.25 for 25% more; `None` when the baseline is zero
+    pub fn ratio(&self) -> Option<f64> {
+        Change { baseline: self.baseline as f64, current: self.current as f64 }.ratio()
+    }
+}
+
+/// Hours and sessions of one project in both periods
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProjectChange {
+    pub project: String,
+    pub hours: Change<f64>,
+    pub sessions: Change<usize>,
+}
+
+/// Differences between two analyses, such as this sprint and the last one
+#[derive(Debug, Clone, PartialEq)]
+pub struct PeriodComparison {
+    pub hours: Change<f64>,
+    pub sessions: Change<usize>,
+    pub messages: Change<usize>,
+    /// Share of classified messages per activity type, in percent
+    pub activity_mix: BTreeMap<String, Change<f64>>,
+    /// Technologies seen only in the current period
+    pub new_technologies: Vec<String>,
+    /// Technologies seen only in the baseline period
+    pub dropped_technologies: Vec<String>,
+    /// Technologies seen in both periods
+    pub kept_technologies: Vec<String>,
+    /// Every project active in either period, largest change in hours first
+    pub projects: Vec<ProjectChange>,
+}
+
+impl PeriodComparison {
+    pub fn new(baseline: &WorkAnalysis, current: &WorkAnalysis) -> Self {
+        let hours = |analysis: &WorkAnalysis| analysis.total_work_time.num_minutes() as f64 / 60.0;
+
+        let baseline_mix = activity_shares(baseline);
+        let current_mix = activity_shares(current);
+        let activity_mix = baseline_mix
+            .keys()
+            .chain(current_mix.keys())
+            .map(|activity| {
+                let change = Change {
+                    baseline: baseline_mix.get(activity).copied().unwrap_or(0.0),
+                    current: current_mix.get(activity).copied().unwrap_or(0.0),
+                };
+                (activity.clone(), change)
+            })
+            .collect();
+
+        let baseline_tech = technologies(baseline);
+        let current_tech = technologies(current);
+
+        let mut projects: Vec<ProjectChange> = baseline
+            .project_stats
+            .keys()
+            .chain(current.project_stats.keys())
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .map(|project| {
+                let base = baseline.project_stats.get(project);
+                let cur = current.project_stats.get(project);
+                let project_hours = |stats: Option<&crate::models::ProjectStats>| {
+                    stats.map(|s| s.work_time.num_minutes() as f64 / 60.0).unwrap_or(0.0)
+                };
+                ProjectChange {
+                    project: project.clone(),
+                    hours: Change { baseline: project_hours(base), current: project_hours(cur) },
+                    sessions: Change {
+                        baseline: base.map(|s| s.total_sessions).unwrap_or(0),
+                        current: cur.map(|s| s.total_sessions).unwrap_or(0),
+                    },
+                }
+            })
+            .collect();
+        projects.sort_by(|a, b| {
+            let delta = |p: &ProjectChange| (p.hours.current - p.hours.baseline).abs();
+            delta(b).total_cmp(&delta(a)).then_with(|| a.project.cmp(&b.project))
+        });
+
+        Self {
+            hours: Change { baseline: hours(baseline), current: hours(current) },
+            sessions: Change { baseline: baseline.total_sessions, current: current.total_sessions },
+            messages: Change { baseline: baseline.total_messages, current: current.total_messages },
+            activity_mix,
+            new_technologies: current_tech.difference(&baseline_tech).cloned().collect(),
+            dropped_technologies: baseline_tech.difference(&current_tech).cloned().collect(),
+            kept_technologies: current_tech.intersection(&baseline_tech).cloned().collect(),
+            projects,
+        }
+    }
+}
+
+/// Percentage of classified messages per activity type across all projects
+fn activity_shares(analysis: &WorkAnalysis) -> HashMap<String, f64> {
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    for stats in analysis.project_stats.values() {
+        for (activity, count) in &stats.activity_types {
+            *counts.entry(activity.clone()).or_insert(0) += count;
+        }
+    }
+
+    let total: usize = counts.values().sum();
+    counts
+        .into_iter()
+        .map(|(activity, count)| (activity, count as f64 / total as f64 * 100.0))
+        .collect()
+}
+
+/// Technical stack of every project, merged
+fn technologies(analysis: &WorkAnalysis) -> BTreeSet<String> {
+    analysis
+        .project_stats
+        .values()
+        .filter_map(|stats| stats.topic_analysis.as_ref())
+        .flat_map(|topics| topics.technical_stack.iter().cloned())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{ProjectStats, TopicAnalysis};
+    use chrono::{Duration, Utc};
+
+    fn project(name: &str, minutes: i64, sessions: usize, activities: &[(&str, usize)], tech: &[&str]) -> ProjectStats {
+        ProjectStats {
+            project_name: name.to_string(),
+            total_sessions: sessions,
+            total_messages: sessions * 10,
+            work_time: Duration::minutes(minutes),
+            activity_types: activities.iter().map(|(a, c)| (a.to_string(), *c)).collect(),
+            activity_confidence: HashMap::new(),
+            most_active_day: None,
+            topic_analysis: Some(TopicAnalysis {
+                primary_topics: Vec::new(),
+                secondary_topics: Vec::new(),
+                technical_stack: tech.iter().map(|t| t.to_string()).collect(),
+                problem_categories: HashMap::new(),
+                solution_patterns: Vec::new(),
+                complexity_indicators: Vec::new(),
+            }),
+        }
+    }
+
+    fn analysis(projects: Vec<ProjectStats>) -> WorkAnalysis {
+        let now = Utc::now();
+        WorkAnalysis {
+            sessions: Vec::new(),
+            total_sessions: projects.iter().map(|p| p.total_sessions).sum(),
+            total_messages: projects.iter().map(|p| p.total_messages).sum(),
+            total_work_time: projects.iter().map(|p| p.work_time).fold(Duration::zero(), |a, b| a + b),
+            project_stats: projects.into_iter().map(|p| (p.project_name.clone(), p)).collect(),
+            time_range: (now, now),
+            conversation_summary: None,
+            token_analysis: None,
+            tool_usage: None,
+            code_blocks: None,
+            time_buckets: None,
+            initiatives: None,
+            commits: None,
+            log_gaps: None,
+            heatmap: None,
+            machines: None,
+            assistants: None,
+            sidechains: None,
+            budget: None,
+            duplicate_entries: 0,
+            project_names: Default::default(),
+        }
+    }
+
+    #[test]
+    fn test_period_comparison() {
+        let baseline = analysis(vec![
+            project("api", 120, 2, &[("Coding", 3), ("Debugging", 1)], &["Rust", "SQL"]),
+            project("docs", 60, 1, &[("Documentation", 4)], &[]),
+        ]);
+        let current = analysis(vec![project("api", 300, 4, &[("Coding", 6), ("Debugging", 2)], &["Rust", "React"])]);
+
+        let comparison = PeriodComparison::new(&baseline, &current);
+
+        assert_eq!(comparison.hours, Change { baseline: 3.0, current: 5.0 });
+        assert_eq!(comparison.hours.ratio(), Some(2.0 / 3.0));
+        assert_eq!(comparison.sessions.ratio(), Some(1.0 / 3.0));
+        assert_eq!(comparison.activity_mix["Coding"], Change { baseline: 37.5, current: 75.0 });
+        assert_eq!(comparison.activity_mix["Documentation"], Change { baseline: 50.0, current: 0.0 });
+        assert_eq!(comparison.new_technologies, ["React"]);
+        assert_eq!(comparison.dropped_technologies, ["SQL"]);
+        assert_eq!(comparison.kept_technologies, ["Rust"]);
+
+        assert_eq!(comparison.projects[0].project, "api");
+        assert_eq!(comparison.projects[0].hours, Change { baseline: 2.0, current: 5.0 });
+        assert_eq!(comparison.projects[1].sessions, Change { baseline: 1, current: 0 });
+    }
+
+    #[test]
+    fn test_empty_baseline_has_no_ratio() {
+        let comparison = PeriodComparison::new(&analysis(Vec::new()), &analysis(vec![project("api", 60, 1, &[], &[])]));
+        assert_eq!(comparison.hours.ratio(), None);
+        assert!(comparison.activity_mix.is_empty());
+    }
+}
@@ -0,0 +1,350 @@
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use crate::activity::ActivityScoring;
+use crate::analyzer::WorkAnalyzer;
+use crate::error::Error;
+use crate::initiatives::{Initiative, InitiativeConfig};
+use crate::insights::InsightRules;
+use crate::machines::MachineLabels;
+use crate::message_analyzer::{KeywordLists, Language, MessageAnalyzer};
+use crate::privacy::Redactor;
+use crate::reporter::ReportSection;
+use crate::scanner::{ProjectAliases, ProjectPatterns, ProjectScanner};
+use crate::storage::StorageKind;
+use crate::timezone::parse_timezone;
+
+/// Every `--format` value; each command accepts some of them
+pub const OUTPUT_FORMATS: [&str; 9] = ["markdown", "json", "json-timeseries", "aggregate", "csv", "csv-daily", "html", "compact", "sqlite"];
+
+/// Settings from `config.toml`; every field is optional
+///
+/// Command-line flags override these defaults.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// Projects directories to read logs from instead of the default one,
+    /// e.g. logs synced from other machines
+    pub projects_dirs: Vec<PathBuf>,
+    /// Timezone for dates and reports, e.g. `UTC`, `JST` or `+05:30`
+    pub timezone: Option<String>,
+    /// Minutes of inactivity that start a new session
+    pub session_gap_minutes: Option<i64>,
+    /// Messages a session needs to be counted
+    pub min_messages: Option<usize>,
+    /// Default `--format` of the commands that support it
+    pub format: Option<String>,
+    /// Sections of the markdown report; all of them when unset
+    pub sections: Option<Vec<ReportSection>>,
+    /// Handlebars template laying out the markdown report instead of the built-in one
+    pub template: Option<PathBuf>,
+    /// Keywords detected in addition to the built-in ones
+    pub keywords: KeywordLists,
+    /// Language of the prompts: `auto` (default) detects problems, solutions and
+    /// learning with both the English and the Japanese keywords, `en` or `ja` with one
+    pub language: Language,
+    /// Weights of the keywords that classify messages by activity type, by activity
+    /// and keyword; they replace the built-in weight of the keyword, 0 removes it
+    pub activity_weights: BTreeMap<String, BTreeMap<String, f64>>,
+    /// Analyze the assistant's extended thinking for topics, problems and solutions
+    pub include_thinking: bool,
+    /// Where analyses read entries from: `jsonl` (default), `sqlite` or `duckdb`
+    pub storage: StorageKind,
+    /// Database file of the `sqlite` or `duckdb` storage
+    pub database: Option<PathBuf>,
+    /// Initiatives spanning projects, reported with their hours and outcomes
+    pub initiatives: Vec<InitiativeConfig>,
+    /// Thresholds of the report's recommendations, and which of them are given
+    pub insights: InsightRules,
+    /// File every report's headline metrics are recorded in, read by `history --recorded`
+    pub report_history: Option<PathBuf>,
+    /// Where `archive` copies the logs to before Claude Code prunes them
+    pub archive_dir: Option<PathBuf>,
+    /// Where `import` writes the history of other assistants as Claude Code logs
+    pub import_dir: Option<PathBuf>,
+    /// Case-insensitive regular expressions that `--redact` removes, e.g. client names
+    pub redact_patterns: Vec<String>,
+    /// Projects directories synced from other machines, by machine name; their
+    /// logs are merged with the others and broken down per machine
+    pub machines: BTreeMap<String, PathBuf>,
+    /// Checkouts counted as another project, by directory: the sessions in or
+    /// below each are counted under the project path (or name) it maps to
+    pub project_aliases: BTreeMap<String, String>,
+    /// Glob patterns of the project directories to read, e.g. `*work*`; all of them when empty
+    pub include_projects: Vec<String>,
+    /// Glob patterns of project directories to skip, e.g. `*-scratch*`
+    pub exclude_projects: Vec<String>,
+    /// Bearer token `serve --port` requires unless `--token` is given
+    pub api_token: Option<String>,
+    /// Origins whose web pages may read the `serve --port` API, e.g. `http://localhost:3000`
+    pub api_allowed_origins: Vec<String>,
+}
+
+impl Config {
+    /// `~/.config/claude-work-analysis/config.toml` on Linux
+    pub fn default_path() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join("claude-work-analysis").join("config.toml"))
+    }
+
+    /// Read the config file at the default path, or the defaults if there is none
+    pub fn load_default() -> Result<Self> {
+        match Self::default_path() {
+            Some(path) => Self::load(&path),
+            None => Ok(Self::default()),
+        }
+    }
+
+    /// Read a config file; a missing file gives the defaults
+    pub fn load(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read config file: {}", path.display()))?;
+        let config: Self = toml::from_str(&content).map_err(|e| Error::InvalidConfig {
+            path: path.to_path_buf(),
+            reason: e.to_string(),
+        })?;
+        config.validate().map_err(|e| Error::InvalidConfig {
+            path: path.to_path_buf(),
+            reason: e.to_string(),
+        })?;
+        Ok(config)
+    }
+
+    /// Reject values the command line would also reject
+    fn validate(&self) -> Result<()> {
+        if let Some(timezone) = &self.timezone {
+            parse_timezone(timezone)?;
+        }
+        if self.api_token.as_ref().is_some_and(|token| token.trim().is_empty()) {
+            return Err(anyhow::anyhow!("api_token must not be empty; leave it out and pass --no-auth to serve without authentication"));
+        }
+        if self.session_gap_minutes.is_some_and(|minutes| minutes <= 0) {
+            return Err(anyhow::anyhow!("session_gap_minutes must be a positive number of minutes"));
+        }
+        if self.min_messages == Some(0) {
+            return Err(anyhow::anyhow!("min_messages must be at least 1"));
+        }
+        self.compiled_initiatives()?;
+        self.insights.validate()?;
+        self.activity_scoring()?;
+        self.redactor()?;
+        if self.project_aliases.iter().any(|(root, project)| root.trim().is_empty() || project.trim().is_empty()) {
+            return Err(anyhow::anyhow!("project_aliases must map directories to non-empty project paths or names"));
+        }
+        if let Some(format) = &self.format {
+            if self.output_format().is_none() {
+                return Err(anyhow::anyhow!(
+                    "Invalid format '{}'. Expected markdown, json, json-timeseries, aggregate, csv, csv-daily, html, compact or sqlite",
+                    format
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// The configured output format, as one of [`OUTPUT_FORMATS`]
+    pub fn output_format(&self) -> Option<&'static str> {
+        let format = self.format.as_deref()?.to_lowercase();
+        OUTPUT_FORMATS.into_iter().find(|known| *known == format)
+    }
+
+    /// Analyzer with the configured session gap, minimum messages and keywords
+    pub fn analyzer(&self) -> WorkAnalyzer {
+        let mut analyzer = WorkAnalyzer::new().with_message_analyzer(self.message_analyzer());
+        if let Some(minutes) = self.session_gap_minutes {
+            analyzer = analyzer.with_session_gap(chrono::Duration::minutes(minutes));
+        }
+        if let Some(min_messages) = self.min_messages {
+            analyzer = analyzer.with_min_messages(min_messages);
+        }
+        // Loading the file already rejected invalid initiatives and weights
+        if let Ok(initiatives) = self.compiled_initiatives() {
+            analyzer = analyzer.with_initiatives(initiatives);
+        }
+        if let Ok(scoring) = self.activity_scoring() {
+            analyzer = analyzer.with_activity_scoring(scoring);
+        }
+        if let Ok(aliases) = self.project_aliases() {
+            analyzer = analyzer.with_project_aliases(aliases);
+        }
+        analyzer
+    }
+
+    /// Message analyzer for the configured language, with the configured keywords
+    pub fn message_analyzer(&self) -> MessageAnalyzer {
+        MessageAnalyzer::new()
+            .with_language(self.language)
+            .with_keywords(&self.keywords)
+            .with_thinking(self.include_thinking)
+    }
+
+    /// The built-in activity scoring with the configured weights applied
+    pub fn activity_scoring(&self) -> Result<ActivityScoring> {
+        ActivityScoring::new().with_configured_weights(&self.activity_weights)
+    }
+
+    /// The configured project aliases with `~` expanded
+    pub fn project_aliases(&self) -> Result<ProjectAliases> {
+        let expand = |path: &str| Ok::<_, anyhow::Error>(expand_home(Path::new(path))?.to_string_lossy().into_owned());
+        let roots = self
+            .project_aliases
+            .iter()
+            .map(|(root, project)| Ok((expand(root)?, expand(project)?)))
+            .collect::<Result<Vec<_>>>()?;
+        Ok(ProjectAliases::new(roots))
+    }
+
+    /// The project directories to read
+    pub fn project_patterns(&self) -> ProjectPatterns {
+        ProjectPatterns::new(self.include_projects.clone(), self.exclude_projects.clone())
+    }
+
+    /// Redactor for `--redact` that also removes the configured patterns
+    pub fn redactor(&self) -> Result<Redactor> {
+        Redactor::new(&self.redact_patterns)
+    }
+
+    /// The configured initiatives with their keyword patterns compiled
+    pub fn compiled_initiatives(&self) -> Result<Vec<Initiative>> {
+        self.initiatives.iter().map(Initiative::new).collect()
+    }
+
+    /// The configured projects directories and machine directories with `~`
+    /// expanded, or the default one if neither is set
+    pub fn resolved_projects_dirs(&self) -> Result<Vec<PathBuf>> {
+        if self.projects_dirs.is_empty() && self.machines.is_empty() {
+            return Ok(vec![ProjectScanner::default_projects_dir().ok_or(Error::HomeDirNotFound)?]);
+        }
+        let mut dirs: Vec<PathBuf> = Vec::new();
+        for dir in self.projects_dirs.iter().chain(self.machines.values()) {
+            let dir = expand_home(dir)?;
+            if !dirs.contains(&dir) {
+                dirs.push(dir);
+            }
+        }
+        Ok(dirs)
+    }
+
+    /// The configured machine directories with `~` expanded, labeled with their machine
+    pub fn machine_labels(&self) -> Result<MachineLabels> {
+        let roots = self
+            .machines
+            .iter()
+            .map(|(name, dir)| Ok((expand_home(dir)?, name.clone())))
+            .collect::<Result<Vec<_>>>()?;
+        Ok(MachineLabels::new(roots))
+    }
+}
+
+/// Replace a leading `~` with the home directory
+pub fn expand_home(path: &Path) -> Result<PathBuf> {
+    match path.strip_prefix("~") {
+        Ok(rest) => Ok(dirs::home_dir().ok_or(Error::HomeDirNotFound)?.join(rest)),
+        Err(_) => Ok(path.to_path_buf()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::ActivityType;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_load() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("config.toml");
+        assert_eq!(Config::load(&path).unwrap(), Config::default());
+
+        std::fs::write(&path, "projects_dirs = [\"/mnt/laptop/projects\", \"~/synced/projects\"]\n").unwrap();
+        let config = Config::load(&path).unwrap();
+        assert_eq!(config.projects_dirs.len(), 2);
+
+        let dirs = config.resolved_projects_dirs().unwrap();
+        assert_eq!(dirs[0], PathBuf::from("/mnt/laptop/projects"));
+        assert!(!dirs[1].starts_with("~"));
+        assert!(dirs[1].ends_with("synced/projects"));
+
+        std::fs::write(&path, "projects_dirs = [\"/mnt/nas/projects\"]\n[machines]\nlaptop = \"/mnt/nas/projects/laptop\"\ndesktop = \"/mnt/nas/projects\"\n").unwrap();
+        let config = Config::load(&path).unwrap();
+        let dirs = config.resolved_projects_dirs().unwrap();
+        assert_eq!(dirs, [PathBuf::from("/mnt/nas/projects"), PathBuf::from("/mnt/nas/projects/laptop")]);
+        let labels = config.machine_labels().unwrap();
+        assert_eq!(labels.machine_of(Path::new("/mnt/nas/projects/laptop/-home-me-api/a.jsonl")), "laptop");
+        assert_eq!(labels.machine_of(Path::new("/mnt/nas/projects/-home-me-api/a.jsonl")), "desktop");
+
+        std::fs::write(&path, "[project_aliases]\n\"~/clones/api\" = \"~/work/api\"\n\"/tmp/api\" = \"api\"\n").unwrap();
+        let aliases = Config::load(&path).unwrap().project_aliases().unwrap();
+        assert_eq!(aliases.resolve("/tmp/api/src"), "api");
+        let home = dirs::home_dir().unwrap();
+        let clone = home.join("clones/api").to_string_lossy().into_owned();
+        assert_eq!(aliases.resolve(&clone), home.join("work/api").to_string_lossy());
+        std::fs::write(&path, "[project_aliases]\n\"/tmp/api\" = \"\"\n").unwrap();
+        assert!(Config::load(&path).is_err());
+
+        std::fs::write(&path, "storage = \"sqlite\"\ndatabase = \"/tmp/entries.db\"\n").unwrap();
+        let config = Config::load(&path).unwrap();
+        assert_eq!(config.storage, StorageKind::Sqlite);
+        assert_eq!(config.database, Some(PathBuf::from("/tmp/entries.db")));
+        std::fs::write(&path, "storage = \"duckdb\"\n").unwrap();
+        assert_eq!(Config::load(&path).unwrap().storage, StorageKind::Duckdb);
+
+        std::fs::write(&path, "projects_dirs = \"/mnt/laptop/projects\"\n").unwrap();
+        let error = Config::load(&path).unwrap_err();
+        assert!(error.to_string().starts_with("Invalid config file"));
+    }
+
+    #[test]
+    fn test_defaults() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("config.toml");
+        std::fs::write(
+            &path,
+            r#"
+timezone = "+05:30"
+session_gap_minutes = 45
+min_messages = 2
+format = "JSON"
+sections = ["summary", "group-by"]
+redact_patterns = ["acme"]
+api_token = "s3cret"
+api_allowed_origins = ["http://localhost:3000"]
+language = "ja"
+
+[keywords]
+technologies = ["svelte"]
+
+[activity_weights.debugging]
+flaky = 3
+
+[[initiatives]]
+name = "Checkout"
+projects = ["web", "api"]
+keywords = ["checkout|payment"]
+"#,
+        )
+        .unwrap();
+        let config = Config::load(&path).unwrap();
+        assert_eq!(config.output_format(), Some("json"));
+        assert_eq!(config.api_token.as_deref(), Some("s3cret"));
+        assert_eq!(config.api_allowed_origins, ["http://localhost:3000"]);
+        assert_eq!(config.sections, Some(vec![ReportSection::Summary, ReportSection::GroupBy]));
+        assert_eq!(config.keywords.technologies, ["svelte"]);
+        assert_eq!(config.language, Language::Ja);
+        assert!(!config.message_analyzer().raises_problem("the build fails"));
+        assert_eq!(config.activity_scoring().unwrap().primary("the flaky test"), ActivityType::Debugging);
+        assert_eq!(config.initiatives[0].projects, ["web", "api"]);
+        assert_eq!(config.compiled_initiatives().unwrap()[0].name(), "Checkout");
+        assert_eq!(config.redactor().unwrap().redact_text("ACME's api", "/work"), "[redacted]'s api");
+
+        for invalid in ["session_gap_minutes = 0", "min_messages = 0", "format = \"pdf\"", "timezone = \"Mars\"", "sections = [\"charts\"]", "[[initiatives]]\nname = \"Bad\"\nkeywords = [\"(\"]", "redact_patterns = [\"(\"]", "api_token = \"\"", "[activity_weights.chores]\nbump = 1", "language = \"fr\""] {
+            std::fs::write(&path, invalid).unwrap();
+            let error = Config::load(&path).unwrap_err();
+            assert!(error.to_string().starts_with("Invalid config file"), "{}", invalid);
+        }
+    }
+}
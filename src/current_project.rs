@@ -0,0 +1,200 @@
+use chrono::{DateTime, Duration, Utc};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use uuid::Uuid;
+
+use crate::message_analyzer::MessageAnalyzer;
+use crate::models::{WorkAnalysis, WorkSession};
+use crate::paths;
+use crate::scanner::ProjectScanner;
+
+/// Problems and topics listed
+const MAX_ITEMS: usize = 10;
+
+/// Where Claude Code keeps the logs of a working directory's project
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProjectLogs {
+    /// The project root: the deepest ancestor of the working directory, itself included, with logs
+    pub path: String,
+    pub name: String,
+    /// Its log directory in each projects directory that has one
+    pub log_dirs: Vec<PathBuf>,
+}
+
+impl ProjectLogs {
+    /// The project of `cwd` among the log directories of the projects directories
+    ///
+    /// Opening a subfolder of a repository finds the repository's logs, unless
+    /// Claude Code was started in the subfolder itself.
+    pub fn find(cwd: &str, project_dirs: &[PathBuf]) -> Option<Self> {
+        // Windows paths may differ in case from the directory Claude created
+        let windows = paths::is_windows_path(cwd);
+        paths::ancestors(cwd).into_iter().find_map(|path| {
+            let encoded = ProjectScanner::encode_project_path(&path);
+            let log_dirs: Vec<PathBuf> = project_dirs
+                .iter()
+                .filter(|dir| {
+                    dir.file_name().and_then(|name| name.to_str()).is_some_and(|name| {
+                        if windows { name.eq_ignore_ascii_case(&encoded) } else { name == encoded }
+                    })
+                })
+                .cloned()
+                .collect();
+            if log_dirs.is_empty() {
+                return None;
+            }
+            let name = ProjectScanner::extract_project_name(Path::new(&path)).unwrap_or_else(|| path.clone());
+            Some(Self { path, name, log_dirs })
+        })
+    }
+}
+
+/// A session in the recap of a project
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecentSession {
+    pub session_id: Uuid,
+    pub start_time: DateTime<Utc>,
+    pub end_time: DateTime<Utc>,
+    pub messages: usize,
+    pub summary: Option<String>,
+    pub topics: Vec<String>,
+}
+
+/// What was done recently in the project of a working directory, for an editor
+/// extension to show when a repository is opened
+#[derive(Debug, Clone, PartialEq)]
+pub struct CurrentProject {
+    pub cwd: String,
+    /// None when Claude Code has no logs for the directory or its parents
+    pub project: Option<ProjectLogs>,
+    /// Start of the period the recap covers
+    pub since: DateTime<Utc>,
+    pub sessions: usize,
+    pub messages: usize,
+    pub work_time: Duration,
+    /// Latest sessions, newest first
+    pub recent_sessions: Vec<RecentSession>,
+    /// Problems the latest sessions ended on without a proposed solution, newest first
+    pub open_problems: Vec<String>,
+    /// Main topics of the latest sessions, newest first
+    pub recent_topics: Vec<String>,
+}
+
+impl CurrentProject {
+    /// The recap of a directory without logs
+    pub fn unknown(cwd: &str, since: DateTime<Utc>) -> Self {
+        Self {
+            cwd: cwd.to_string(),
+            project: None,
+            since,
+            sessions: 0,
+            messages: 0,
+            work_time: Duration::zero(),
+            recent_sessions: Vec::new(),
+            open_problems: Vec::new(),
+            recent_topics: Vec::new(),
+        }
+    }
+
+    /// Recap of an analysis of the project's logs since `since`, listing up to `limit` sessions
+    pub fn new(
+        cwd: &str,
+        project: ProjectLogs,
+        since: DateTime<Utc>,
+        analysis: &WorkAnalysis,
+        message_analyzer: &MessageAnalyzer,
+        limit: usize,
+    ) -> Self {
+        let mut sessions: Vec<&WorkSession> = analysis.sessions.iter().collect();
+        sessions.sort_by_key(|session| std::cmp::Reverse(session.start_time));
+
+        let recent_sessions = sessions
+            .iter()
+            .take(limit)
+            .map(|session| RecentSession {
+                session_id: session.session_id,
+                start_time: session.start_time,
+                end_time: session.end_time,
+                messages: session.total_messages,
+                summary: session.summary.as_ref().map(|summary| summary.overall_summary.clone()),
+                topics: session.summary.as_ref().map(|summary| summary.main_topics.clone()).unwrap_or_default(),
+            })
+            .collect();
+
+        let mut seen = HashSet::new();
+        let open_problems = sessions
+            .iter()
+            .flat_map(|session| message_analyzer.open_problems(&session.entries).into_iter().rev())
+            .filter(|problem| seen.insert(problem.clone()))
+            .take(MAX_ITEMS)
+            .collect();
+        let mut seen = HashSet::new();
+        let recent_topics = sessions
+            .iter()
+            .filter_map(|session| session.summary.as_ref())
+            .flat_map(|summary| summary.main_topics.iter())
+            .filter(|topic| seen.insert(topic.as_str()))
+            .take(MAX_ITEMS)
+            .cloned()
+            .collect();
+
+        Self {
+            cwd: cwd.to_string(),
+            project: Some(project),
+            since,
+            sessions: analysis.total_sessions,
+            messages: analysis.total_messages,
+            work_time: analysis.total_work_time,
+            recent_sessions,
+            open_problems,
+            recent_topics,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyzer::WorkAnalyzer;
+    use crate::pipeline::AnalysisPipeline;
+    use crate::sample::write_sample;
+    use tempfile::TempDir;
+
+    #[tokio::test]
+    async fn test_current_project() {
+        let temp_dir = TempDir::new().unwrap();
+        let projects_dir = temp_dir.path().join("projects");
+        write_sample(&projects_dir).unwrap();
+        let project_dirs = ProjectScanner::new().get_project_directories(&projects_dir).unwrap();
+        let api_dir = project_dirs.iter().find(|dir| dir.to_string_lossy().ends_with("api")).unwrap();
+        let api_path = "/home/demo/work/api";
+
+        // A subfolder of the repository finds the repository's logs
+        let project = ProjectLogs::find(&format!("{}/src/handlers", api_path), &project_dirs).unwrap();
+        assert_eq!((project.path.as_str(), project.log_dirs.as_slice()), (api_path, std::slice::from_ref(api_dir)));
+        assert!(ProjectLogs::find("/somewhere/else", &project_dirs).is_none());
+
+        let analysis = AnalysisPipeline::builder()
+            .data_dirs(&project.log_dirs)
+            .analyzer(WorkAnalyzer::new())
+            .build()
+            .unwrap()
+            .run()
+            .await
+            .unwrap()
+            .analysis;
+        let since = Utc::now() - Duration::days(30);
+        let recap = CurrentProject::new(api_path, project, since, &analysis, &MessageAnalyzer::new(), 1);
+        assert_eq!((recap.sessions, recap.recent_sessions.len()), (2, 1));
+        let latest = analysis.sessions.iter().map(|session| session.start_time).max().unwrap();
+        assert_eq!(recap.recent_sessions[0].start_time, latest);
+        // Both sessions end on the negative offset, the reply proposes no fix
+        assert_eq!(recap.open_problems, ["Fix the error when offset is negative"]);
+        let reporter = crate::reporter::ReportGenerator::new();
+        let json: serde_json::Value = serde_json::from_str(&reporter.generate_current_project_json(&recap).unwrap()).unwrap();
+        assert_eq!((json["project"]["name"].as_str(), json["recent_sessions"][0]["messages"].as_u64()), (Some("api"), Some(6)));
+
+        let unknown = CurrentProject::unknown("/somewhere/else", since);
+        assert_eq!((unknown.project, unknown.sessions), (None, 0));
+    }
+}
@@ -0,0 +1,272 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration, FixedOffset, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::path::{Path, PathBuf};
+
+use crate::analyzer::WorkAnalyzer;
+use crate::config::Config;
+use crate::filter::TimeRangeFilter;
+use crate::models::{ClaudeLogEntry, WorkAnalysis};
+use crate::timezone::TimezoneSchedule;
+
+/// Bumped whenever the cached layout changes
+const CACHE_VERSION: u32 = 1;
+
+/// Local days kept summarized: yesterday and today
+pub const CACHED_DAYS: u32 = 2;
+
+/// How long summaries are trusted after they were written
+///
+/// The daemon rewrites them at least every five minutes, so an older file means
+/// nothing is keeping it current and the logs have to be read instead.
+pub const MAX_AGE_MINUTES: i64 = 10;
+
+/// Headline figures of the work of one or more local days
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WorkSummary {
+    pub first_day: NaiveDate,
+    pub last_day: NaiveDate,
+    pub sessions: usize,
+    pub messages: usize,
+    pub work_seconds: i64,
+    pub estimated_cost: f64,
+    /// Sessions and work time per project name
+    pub projects: BTreeMap<String, ProjectWork>,
+    /// Mentions of the most discussed topics
+    pub topics: BTreeMap<String, usize>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ProjectWork {
+    pub sessions: usize,
+    pub work_seconds: i64,
+}
+
+impl WorkSummary {
+    /// The figures of an analysis of the entries of `day`
+    pub fn of_day(day: NaiveDate, analysis: &WorkAnalysis) -> Self {
+        Self::of_period(day, day, analysis)
+    }
+
+    /// The figures of an analysis of the entries of the local days `first_day` to `last_day`
+    pub fn of_period(first_day: NaiveDate, last_day: NaiveDate, analysis: &WorkAnalysis) -> Self {
+        Self {
+            first_day,
+            last_day,
+            sessions: analysis.total_sessions,
+            messages: analysis.total_messages,
+            work_seconds: analysis.total_work_time.num_seconds(),
+            estimated_cost: analysis.token_analysis.as_ref().map_or(0.0, |tokens| tokens.total.estimated_cost),
+            projects: analysis
+                .project_stats
+                .values()
+                .map(|stats| {
+                    let work = ProjectWork { sessions: stats.total_sessions, work_seconds: stats.work_time.num_seconds() };
+                    (stats.project_name.clone(), work)
+                })
+                .collect(),
+            topics: analysis
+                .conversation_summary
+                .iter()
+                .flat_map(|summary| summary.most_discussed_topics.iter().cloned())
+                .collect(),
+        }
+    }
+
+    /// Add the figures of the days of `other`
+    pub fn merge(&mut self, other: &WorkSummary) {
+        self.first_day = self.first_day.min(other.first_day);
+        self.last_day = self.last_day.max(other.last_day);
+        self.sessions += other.sessions;
+        self.messages += other.messages;
+        self.work_seconds += other.work_seconds;
+        self.estimated_cost += other.estimated_cost;
+        for (project, work) in &other.projects {
+            let total = self.projects.entry(project.clone()).or_default();
+            total.sessions += work.sessions;
+            total.work_seconds += work.work_seconds;
+        }
+        for (topic, mentions) in &other.topics {
+            *self.topics.entry(topic.clone()).or_default() += mentions;
+        }
+    }
+
+    /// The project worked on longest; on a tie the first by name
+    pub fn busiest_project(&self) -> Option<&str> {
+        self.projects
+            .iter()
+            .max_by(|a, b| a.1.work_seconds.cmp(&b.1.work_seconds).then_with(|| b.0.cmp(a.0)))
+            .map(|(project, _)| project.as_str())
+    }
+
+    /// Projects by work time, longest first
+    pub fn projects_by_work_time(&self) -> Vec<(&str, &ProjectWork)> {
+        let mut projects: Vec<_> = self.projects.iter().map(|(project, work)| (project.as_str(), work)).collect();
+        projects.sort_by(|a, b| b.1.work_seconds.cmp(&a.1.work_seconds).then_with(|| a.0.cmp(b.0)));
+        projects
+    }
+
+    /// Topics by mentions, most mentioned first
+    pub fn top_topics(&self) -> Vec<(&str, usize)> {
+        let mut topics: Vec<_> = self.topics.iter().map(|(topic, mentions)| (topic.as_str(), *mentions)).collect();
+        topics.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+        topics
+    }
+}
+
+/// Summaries of the [`CACHED_DAYS`] local days up to `today`, each day analyzed on its own
+///
+/// `entries` must include every entry of those days.
+pub fn summarize_days(
+    analyzer: &WorkAnalyzer,
+    entries: &[ClaudeLogEntry],
+    schedule: &TimezoneSchedule,
+    today: NaiveDate,
+) -> Result<Vec<WorkSummary>> {
+    (0..CACHED_DAYS)
+        .rev()
+        .map(|days_ago| {
+            let day = today - Duration::days(i64::from(days_ago));
+            let filter = TimeRangeFilter::new(Some(schedule.start_of_day(day)), Some(schedule.end_of_day(day)), None);
+            let day_entries: Vec<ClaudeLogEntry> = entries.iter().filter(|entry| filter.matches_entry(entry)).cloned().collect();
+            Ok(WorkSummary::of_day(day, &analyzer.analyze_entries(&day_entries)?))
+        })
+        .collect()
+}
+
+/// What the summaries were computed from; summaries of other logs, another
+/// timezone or other settings are not reused
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SummaryKey {
+    projects_dirs: Vec<PathBuf>,
+    utc_offset_seconds: i32,
+    /// Hash of the settings rather than the settings, which may hold the API token
+    settings: u64,
+}
+
+impl SummaryKey {
+    pub fn new(projects_dirs: &[PathBuf], timezone: FixedOffset, config: &Config) -> Self {
+        let mut hasher = DefaultHasher::new();
+        format!("{:?}", config).hash(&mut hasher);
+        Self {
+            projects_dirs: projects_dirs.to_vec(),
+            utc_offset_seconds: timezone.local_minus_utc(),
+            settings: hasher.finish(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct CachedSummaries {
+    version: u32,
+    key: SummaryKey,
+    written_at: DateTime<Utc>,
+    /// One summary per day, oldest first
+    days: Vec<WorkSummary>,
+}
+
+/// The rolling per-day summaries that `--watch` and `--daemon` keep current
+///
+/// Quick questions about today and yesterday, like the status line, read this
+/// file instead of the logs while it is fresh.
+#[derive(Debug, Clone)]
+pub struct DailySummaryCache {
+    path: PathBuf,
+}
+
+impl DailySummaryCache {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    /// `~/.cache/claude-work-analysis/daily_summaries.json` (or the platform equivalent)
+    pub fn default_location() -> Option<Self> {
+        dirs::cache_dir().map(|dir| Self::new(dir.join("claude-work-analysis").join("daily_summaries.json")))
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Replace the cached summaries; the file is swapped in whole so readers never see half of it
+    pub fn store(&self, key: &SummaryKey, days: Vec<WorkSummary>, now: DateTime<Utc>) -> Result<()> {
+        let cached = CachedSummaries { version: CACHE_VERSION, key: key.clone(), written_at: now, days };
+        if let Some(dir) = self.path.parent() {
+            std::fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+        }
+        let temp_path = self.path.with_extension("json.tmp");
+        std::fs::write(&temp_path, serde_json::to_vec(&cached)?)
+            .with_context(|| format!("Failed to write {}", temp_path.display()))?;
+        std::fs::rename(&temp_path, &self.path).with_context(|| format!("Failed to write {}", self.path.display()))?;
+        Ok(())
+    }
+
+    /// The work of the `days` local days up to `today` and when it was summarized,
+    /// if fresh summaries of all of them were computed with `key`
+    pub fn recent(&self, key: &SummaryKey, days: u32, today: NaiveDate, now: DateTime<Utc>) -> Option<(WorkSummary, DateTime<Utc>)> {
+        let content = std::fs::read(&self.path).ok()?;
+        let cached: CachedSummaries = serde_json::from_slice(&content).ok()?;
+        if cached.version != CACHE_VERSION || cached.key != *key || now - cached.written_at > Duration::minutes(MAX_AGE_MINUTES) {
+            return None;
+        }
+        let first_day = today - Duration::days(i64::from(days) - 1);
+        let mut found = cached.days.iter().filter(|summary| (first_day..=today).contains(&summary.first_day));
+        let mut total = found.next()?.clone();
+        let mut count = 1;
+        for summary in found {
+            total.merge(summary);
+            count += 1;
+        }
+        (count == days && total.first_day == first_day).then_some((total, cached.written_at))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn summary(day: NaiveDate, project: &str, minutes: i64) -> WorkSummary {
+        WorkSummary {
+            first_day: day,
+            last_day: day,
+            sessions: 1,
+            messages: 4,
+            work_seconds: minutes * 60,
+            estimated_cost: 0.5,
+            projects: BTreeMap::from([(project.to_string(), ProjectWork { sessions: 1, work_seconds: minutes * 60 })]),
+            topics: BTreeMap::from([("login form".to_string(), 2)]),
+        }
+    }
+
+    #[test]
+    fn test_store_and_read_recent_days() {
+        let temp_dir = TempDir::new().unwrap();
+        let cache = DailySummaryCache::new(temp_dir.path().join("daily_summaries.json"));
+        let jst = FixedOffset::east_opt(9 * 3600).unwrap();
+        let key = SummaryKey::new(&[PathBuf::from("/logs")], jst, &Config::default());
+        let today = NaiveDate::from_ymd_opt(2025, 6, 10).unwrap();
+        let yesterday = today - Duration::days(1);
+        let now = Utc::now();
+        assert!(cache.recent(&key, 1, today, now).is_none());
+
+        cache.store(&key, vec![summary(yesterday, "api", 90), summary(today, "web", 30)], now).unwrap();
+        let (work, written_at) = cache.recent(&key, 1, today, now).unwrap();
+        assert_eq!(written_at, now);
+        assert_eq!((work.sessions, work.work_seconds, work.busiest_project()), (1, 1800, Some("web")));
+
+        let (work, _) = cache.recent(&key, 2, today, now).unwrap();
+        assert_eq!((work.first_day, work.last_day), (yesterday, today));
+        assert_eq!((work.sessions, work.messages, work.busiest_project()), (2, 8, Some("api")));
+        assert_eq!(work.top_topics(), [("login form", 4)]);
+
+        // More days than cached, a later day, stale summaries or other settings are not answered
+        assert!(cache.recent(&key, 3, today, now).is_none());
+        assert!(cache.recent(&key, 1, today + Duration::days(1), now).is_none());
+        assert!(cache.recent(&key, 1, today, now + Duration::minutes(MAX_AGE_MINUTES + 1)).is_none());
+        let utc_key = SummaryKey::new(&[PathBuf::from("/logs")], FixedOffset::east_opt(0).unwrap(), &Config::default());
+        assert!(cache.recent(&utc_key, 1, today, now).is_none());
+    }
+}
@@ -0,0 +1,53 @@
+use std::path::PathBuf;
+use thiserror::Error;
+
+use crate::scanner::ProjectScanner;
+
+/// Errors caused by the arguments or the environment rather than by a bug
+///
+/// Their messages are shown to the user as they are, so they say what to change.
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("Invalid date '{value}': {reason}. Expected YYYY-MM-DD")]
+    InvalidDate { value: String, reason: chrono::ParseError },
+
+    #[error("Cannot find the home directory. Set HOME, or set CLAUDE_CONFIG_DIR to the directory that contains `projects`")]
+    HomeDirNotFound,
+
+    /// `found` lists Claude log directories in other known locations
+    #[error("{}", ProjectScanner::missing_projects_dir_message(.path, .found))]
+    ProjectsDirNotFound { path: PathBuf, found: Vec<PathBuf> },
+
+    #[error("Invalid config file {}: {reason}", .path.display())]
+    InvalidConfig { path: PathBuf, reason: String },
+
+    /// `--fail-on-budget` with a month on track to cost more than `--budget-monthly`
+    #[error("The projected spend of this month, ${projected:.2}, exceeds the monthly budget of ${budget:.2}")]
+    BudgetExceeded { projected: f64, budget: f64 },
+
+    /// The caller no longer wanted the result, see [`crate::analyzer::WorkAnalyzer::with_cancellation`]
+    #[error("The analysis was cancelled")]
+    Cancelled,
+}
+
+impl Error {
+    /// Whether `error` is an analysis that was cancelled
+    pub fn is_cancellation(error: &anyhow::Error) -> bool {
+        matches!(error.downcast_ref::<Error>(), Some(Error::Cancelled))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_messages() {
+        let reason = chrono::NaiveDate::parse_from_str("2025-13-01", "%Y-%m-%d").unwrap_err();
+        let error = Error::InvalidDate { value: "2025-13-01".to_string(), reason };
+        assert_eq!(error.to_string(), "Invalid date '2025-13-01': input is out of range. Expected YYYY-MM-DD");
+
+        let error = Error::ProjectsDirNotFound { path: PathBuf::from("/home/me/.claude/projects"), found: Vec::new() };
+        assert!(error.to_string().starts_with("Projects directory does not exist: /home/me/.claude/projects"));
+    }
+}
@@ -0,0 +1,280 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Deserializer};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+
+use crate::activity::ActivityScoring;
+use crate::message_analyzer::MessageAnalyzer;
+use crate::models::ActivityType;
+
+/// The labeled corpus `eval` scores against unless given another
+pub const DEFAULT_CORPUS: &str = include_str!("../eval/corpus.toml");
+
+/// Prompts labeled with what the analyzer heuristics should find in them
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct EvalCorpus {
+    pub activity: Vec<ActivityCase>,
+    pub problems: Vec<ProblemCase>,
+    pub topics: Vec<TopicCase>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ActivityCase {
+    pub text: String,
+    #[serde(deserialize_with = "activity_name")]
+    pub expected: ActivityType,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ProblemCase {
+    pub text: String,
+    /// Whether the prompt raises a problem
+    pub problem: bool,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct TopicCase {
+    pub text: String,
+    /// Lowercase topics of the prompt; an extracted topic covers the labels it contains
+    pub expected: Vec<String>,
+}
+
+fn activity_name<'de, D: Deserializer<'de>>(deserializer: D) -> std::result::Result<ActivityType, D::Error> {
+    String::deserialize(deserializer)?.parse().map_err(serde::de::Error::custom)
+}
+
+impl EvalCorpus {
+    /// The corpus in `eval/corpus.toml`, built into the binary
+    pub fn builtin() -> Result<Self> {
+        Self::parse(DEFAULT_CORPUS).context("Invalid built-in evaluation corpus")
+    }
+
+    /// Load a corpus from a TOML file laid out like `eval/corpus.toml`
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read evaluation corpus: {}", path.display()))?;
+        Self::parse(&content).with_context(|| format!("Invalid evaluation corpus: {}", path.display()))
+    }
+
+    pub fn parse(content: &str) -> Result<Self> {
+        let corpus: Self = toml::from_str(content)?;
+        if corpus.activity.is_empty() && corpus.problems.is_empty() && corpus.topics.is_empty() {
+            return Err(anyhow::anyhow!("The corpus has no [[activity]], [[problems]] or [[topics]] cases"));
+        }
+        Ok(corpus)
+    }
+}
+
+/// Hits and mistakes of a detector
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Counts {
+    pub true_positives: usize,
+    pub false_positives: usize,
+    pub false_negatives: usize,
+}
+
+impl Counts {
+    /// Share of the detections that were right; 1 without detections
+    pub fn precision(&self) -> f64 {
+        ratio(self.true_positives, self.true_positives + self.false_positives)
+    }
+
+    /// Share of the labels that were detected; 1 without labels
+    pub fn recall(&self) -> f64 {
+        ratio(self.true_positives, self.true_positives + self.false_negatives)
+    }
+
+    pub fn f1(&self) -> f64 {
+        let (precision, recall) = (self.precision(), self.recall());
+        if precision + recall == 0.0 {
+            0.0
+        } else {
+            2.0 * precision * recall / (precision + recall)
+        }
+    }
+}
+
+fn ratio(part: usize, whole: usize) -> f64 {
+    if whole == 0 {
+        1.0
+    } else {
+        part as f64 / whole as f64
+    }
+}
+
+/// A case the heuristics got wrong
+#[derive(Debug, Clone, PartialEq)]
+pub struct Miss {
+    pub text: String,
+    pub expected: String,
+    pub actual: String,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ActivityEvaluation {
+    pub cases: usize,
+    pub correct: usize,
+    /// Per activity, counting each case once for its expected and once for its classified activity
+    pub classes: BTreeMap<ActivityType, Counts>,
+    pub misses: Vec<Miss>,
+}
+
+impl ActivityEvaluation {
+    pub fn accuracy(&self) -> f64 {
+        ratio(self.correct, self.cases)
+    }
+
+    /// Mean F1 of the activities, so rare ones weigh as much as common ones
+    pub fn macro_f1(&self) -> f64 {
+        if self.classes.is_empty() {
+            return 1.0;
+        }
+        self.classes.values().map(Counts::f1).sum::<f64>() / self.classes.len() as f64
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ProblemEvaluation {
+    pub cases: usize,
+    pub counts: Counts,
+    pub misses: Vec<Miss>,
+}
+
+impl ProblemEvaluation {
+    pub fn accuracy(&self) -> f64 {
+        ratio(self.cases - self.misses.len(), self.cases)
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct TopicEvaluation {
+    pub cases: usize,
+    /// Covered and missed labels, and extracted topics covering no label
+    pub counts: Counts,
+    pub misses: Vec<Miss>,
+}
+
+/// Scores of the heuristics on a corpus
+#[derive(Debug, Clone, Default)]
+pub struct Evaluation {
+    pub activity: ActivityEvaluation,
+    pub problems: ProblemEvaluation,
+    pub topics: TopicEvaluation,
+}
+
+impl Evaluation {
+    /// Classify the corpus with the given scoring and detect problems and topics with the analyzer
+    pub fn run(corpus: &EvalCorpus, scoring: &ActivityScoring, analyzer: &MessageAnalyzer) -> Self {
+        let mut activity = ActivityEvaluation { cases: corpus.activity.len(), ..Default::default() };
+        for case in &corpus.activity {
+            let actual = scoring.primary(&case.text);
+            if actual == case.expected {
+                activity.correct += 1;
+                activity.classes.entry(actual).or_default().true_positives += 1;
+            } else {
+                activity.classes.entry(case.expected).or_default().false_negatives += 1;
+                activity.classes.entry(actual).or_default().false_positives += 1;
+                activity.misses.push(Miss {
+                    text: case.text.clone(),
+                    expected: case.expected.as_str().to_string(),
+                    actual: actual.as_str().to_string(),
+                });
+            }
+        }
+
+        let mut problems = ProblemEvaluation { cases: corpus.problems.len(), ..Default::default() };
+        for case in &corpus.problems {
+            let detected = analyzer.raises_problem(&case.text);
+            match (case.problem, detected) {
+                (true, true) => problems.counts.true_positives += 1,
+                (false, true) => problems.counts.false_positives += 1,
+                (true, false) => problems.counts.false_negatives += 1,
+                (false, false) => {}
+            }
+            if detected != case.problem {
+                let label = |problem: bool| if problem { "problem" } else { "no problem" }.to_string();
+                problems.misses.push(Miss { text: case.text.clone(), expected: label(case.problem), actual: label(detected) });
+            }
+        }
+
+        let mut topics = TopicEvaluation { cases: corpus.topics.len(), ..Default::default() };
+        for case in &corpus.topics {
+            let extracted: BTreeSet<String> = analyzer.extract_topics(&case.text).into_iter().collect();
+            let expected: Vec<String> = case.expected.iter().map(|label| label.to_lowercase()).collect();
+            let (covered, missed): (Vec<&String>, Vec<&String>) =
+                expected.iter().partition(|label| extracted.iter().any(|topic| topic.contains(label.as_str())));
+            let extra: Vec<&String> = extracted
+                .iter()
+                .filter(|topic| !expected.iter().any(|label| topic.contains(label.as_str())))
+                .collect();
+            topics.counts.true_positives += covered.len();
+            topics.counts.false_negatives += missed.len();
+            topics.counts.false_positives += extra.len();
+            if !missed.is_empty() || !extra.is_empty() {
+                let join = |items: &[&String]| items.iter().map(|item| item.as_str()).collect::<Vec<_>>().join(", ");
+                topics.misses.push(Miss {
+                    text: case.text.clone(),
+                    expected: join(&missed),
+                    actual: join(&extra),
+                });
+            }
+        }
+
+        Self { activity, problems, topics }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_evaluation() {
+        let corpus = EvalCorpus::parse(
+            r#"
+[[activity]]
+text = "fix the README"
+expected = "documentation"
+
+[[activity]]
+text = "Thanks!"
+expected = "coding"
+
+[[problems]]
+text = "the build fails"
+problem = true
+
+[[problems]]
+text = "add a null check"
+problem = false
+
+[[topics]]
+text = "Configure docker for the postgresql database"
+expected = ["docker", "Database", "migrations"]
+"#,
+        )
+        .unwrap();
+        let evaluation = Evaluation::run(&corpus, &ActivityScoring::new(), &MessageAnalyzer::new());
+
+        assert_eq!((evaluation.activity.cases, evaluation.activity.correct), (2, 1));
+        assert_eq!(evaluation.activity.misses[0].actual, "Other");
+        assert_eq!(evaluation.activity.classes[&ActivityType::Documentation].f1(), 1.0);
+        assert_eq!(evaluation.activity.classes[&ActivityType::Coding].recall(), 0.0);
+        assert!((evaluation.activity.macro_f1() - 1.0 / 3.0).abs() < 1e-9);
+
+        // "null" is a problem indicator
+        let problems = &evaluation.problems;
+        assert_eq!(problems.counts, Counts { true_positives: 1, false_positives: 1, false_negatives: 0 });
+        assert_eq!((problems.accuracy(), problems.counts.precision()), (0.5, 0.5));
+
+        // "postgresql database" covers database, nothing covers migrations
+        assert_eq!(evaluation.topics.counts, Counts { true_positives: 2, false_positives: 0, false_negatives: 1 });
+        assert_eq!(evaluation.topics.misses[0].expected, "migrations");
+
+        assert!(EvalCorpus::parse("[[activity]]\ntext = \"x\"\nexpected = \"chores\"\n").is_err());
+        assert!(EvalCorpus::parse("").is_err());
+        let builtin = EvalCorpus::builtin().unwrap();
+        assert!(!builtin.activity.is_empty() && !builtin.problems.is_empty() && !builtin.topics.is_empty());
+    }
+}
@@ -1,8 +1,32 @@
-use chrono::{DateTime, Utc, Datelike, TimeZone, FixedOffset};
+use chrono::{DateTime, Utc, Datelike, DurationRound};
 
 use crate::models::ClaudeLogEntry;
 use crate::scanner::ProjectScanner;
+use crate::timezone::TimezoneSchedule;
+
+/// What a filter read and kept, to explain an analysis that found nothing
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FilterCoverage {
+    /// Entries read before filtering
+    pub total_entries: usize,
+    /// Entries the filter kept
+    pub matched_entries: usize,
+    /// Earliest and latest timestamp of all entries read
+    pub first_entry: Option<DateTime<Utc>>,
+    pub last_entry: Option<DateTime<Utc>>,
+}
+
+impl FilterCoverage {
+    /// Add the counts of another batch of files
+    pub fn merge(&mut self, other: &FilterCoverage) {
+        self.total_entries += other.total_entries;
+        self.matched_entries += other.matched_entries;
+        self.first_entry = self.first_entry.into_iter().chain(other.first_entry).min();
+        self.last_entry = self.last_entry.into_iter().chain(other.last_entry).max();
+    }
+}
 
+#[derive(Clone, Default)]
 pub struct TimeRangeFilter {
     /// Start of the time range (inclusive)
     from_date: Option<DateTime<Utc>>,
@@ -25,29 +49,41 @@ impl TimeRangeFilter {
         }
     }
 
-    /// Create a filter for the last N days (in JST)
+    /// Create a filter for the last N days (N * 24 hours up to now)
+    ///
+    /// The start is at a whole minute, so the same query repeated within a minute
+    /// has the same filter and can reuse a cached result.
     pub fn last_days(days: i64) -> Self {
-        let jst = FixedOffset::east_opt(9 * 3600).unwrap();
-        let now_jst = Utc::now().with_timezone(&jst);
-        let from_date_jst = now_jst - chrono::Duration::days(days);
-        
+        let now = Utc::now();
+        let minute = now.duration_trunc(chrono::Duration::minutes(1)).unwrap_or(now);
+
         Self {
-            from_date: Some(from_date_jst.with_timezone(&Utc)),
-            to_date: Some(now_jst.with_timezone(&Utc)),
+            from_date: Some(minute - chrono::Duration::days(days)),
+            to_date: Some(now),
             project_filter: None,
         }
     }
 
-    /// Create a filter for the current week (in JST)
-    pub fn current_week() -> Self {
-        let jst = FixedOffset::east_opt(9 * 3600).unwrap();
-        let now_jst = Utc::now().with_timezone(&jst);
-        let days_since_monday = now_jst.weekday().num_days_from_monday() as i64;
-        let monday_jst = now_jst - chrono::Duration::days(days_since_monday);
-        
+    /// Create a filter from local midnight of this week's Monday until now
+    pub fn current_week(schedule: &TimezoneSchedule) -> Self {
+        let now = Utc::now();
+        let today = schedule.to_local(now).date_naive();
+        let monday = today - chrono::Duration::days(today.weekday().num_days_from_monday() as i64);
+
+        Self {
+            from_date: Some(schedule.start_of_day(monday)),
+            to_date: Some(now),
+            project_filter: None,
+        }
+    }
+
+    /// Create a filter from local midnight today until now
+    pub fn today(schedule: &TimezoneSchedule) -> Self {
+        let now = Utc::now();
+
         Self {
-            from_date: Some(monday_jst.with_timezone(&Utc)),
-            to_date: Some(now_jst.with_timezone(&Utc)),
+            from_date: Some(schedule.start_of_day(schedule.to_local(now).date_naive())),
+            to_date: Some(now),
             project_filter: None,
         }
     }
@@ -69,6 +105,38 @@ impl TimeRangeFilter {
             .collect()
     }
 
+    /// Filter entries like [`Self::filter_entries`], adding what was read to `coverage`
+    pub fn filter_entries_with_coverage(
+        &self,
+        entries: Vec<ClaudeLogEntry>,
+        coverage: &mut FilterCoverage,
+    ) -> Vec<ClaudeLogEntry> {
+        let total_entries = entries.len();
+        let first_entry = entries.iter().map(|entry| entry.timestamp).min();
+        let last_entry = entries.iter().map(|entry| entry.timestamp).max();
+        let matched = self.filter_entries(entries);
+
+        coverage.merge(&FilterCoverage {
+            total_entries,
+            matched_entries: matched.len(),
+            first_entry,
+            last_entry,
+        });
+        matched
+    }
+
+    /// Check an entry like [`Self::matches_entry`], adding it to `coverage`
+    pub fn matches_entry_with_coverage(&self, entry: &ClaudeLogEntry, coverage: &mut FilterCoverage) -> bool {
+        let matched = self.matches_entry(entry);
+        coverage.merge(&FilterCoverage {
+            total_entries: 1,
+            matched_entries: usize::from(matched),
+            first_entry: Some(entry.timestamp),
+            last_entry: Some(entry.timestamp),
+        });
+        matched
+    }
+
     /// Check if an entry matches the filter criteria
     pub fn matches_entry(&self, entry: &ClaudeLogEntry) -> bool {
         // Check time range
@@ -106,7 +174,7 @@ impl TimeRangeFilter {
             project_dirs
                 .into_iter()
                 .filter(|dir| {
-                    if let Some(project_name) = ProjectScanner::extract_project_name(dir) {
+                    if let Some(project_name) = ProjectScanner::project_name_of_dir(dir) {
                         self.matches_project(&project_name, project_filter)
                     } else {
                         false
@@ -167,15 +235,6 @@ impl TimeRangeFilter {
     }
 }
 
-impl Default for TimeRangeFilter {
-    fn default() -> Self {
-        Self {
-            from_date: None,
-            to_date: None,
-            project_filter: None,
-        }
-    }
-}
 
 #[cfg(test)]
 mod tests {
@@ -207,6 +266,7 @@ mod tests {
             timestamp,
             request_id: None,
             tool_use_result: None,
+            source: None,
         }
     }
 
@@ -286,6 +346,26 @@ mod tests {
         assert!(!filter.matches_entry(&entry2));
     }
 
+    #[test]
+    fn test_filter_coverage() {
+        let day = |d| Utc.with_ymd_and_hms(2025, 7, d, 12, 0, 0).unwrap();
+        let filter = TimeRangeFilter::new(Some(day(10)), None, None);
+        let mut coverage = FilterCoverage::default();
+
+        let kept = filter.filter_entries_with_coverage(
+            vec![create_test_entry(day(5), "/a"), create_test_entry(day(12), "/a")],
+            &mut coverage,
+        );
+        assert_eq!(kept.len(), 1);
+        filter.filter_entries_with_coverage(vec![create_test_entry(day(1), "/b")], &mut coverage);
+        filter.filter_entries_with_coverage(Vec::new(), &mut coverage);
+
+        assert_eq!(coverage.total_entries, 3);
+        assert_eq!(coverage.matched_entries, 1);
+        assert_eq!(coverage.first_entry, Some(day(1)));
+        assert_eq!(coverage.last_entry, Some(day(12)));
+    }
+
     #[test]
     fn test_empty_filter() {
         let filter = TimeRangeFilter::default();
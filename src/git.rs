@@ -0,0 +1,207 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration, Utc};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::models::{Commit, SessionCommits, WorkSession};
+
+/// Minutes after a session during which commits still count as its outcome
+pub const COMMIT_GRACE_MINUTES: i64 = 30;
+
+/// Top level of the git repository containing `dir`, none outside a repository or without git
+pub fn repo_root(dir: &Path) -> Option<PathBuf> {
+    let output = Command::new("git").arg("-C").arg(dir).args(["rev-parse", "--show-toplevel"]).output().ok()?;
+    output
+        .status
+        .success()
+        .then(|| PathBuf::from(String::from_utf8_lossy(&output.stdout).trim()))
+}
+
+/// Commits on any branch of the repository authored since `since`, oldest first
+///
+/// Only commits by the repository's `user.email` are listed when it is set.
+pub fn commits_since(repo: &Path, since: DateTime<Utc>) -> Result<Vec<Commit>> {
+    let mut command = Command::new("git");
+    command
+        .arg("-C")
+        .arg(repo)
+        .args(["log", "--all", "--no-merges", "--format=%H%x1f%aI%x1f%s"])
+        // The committer date is never before the author date, so this only skips older commits
+        .arg(format!("--since={}", since.to_rfc3339()));
+    if let Some(email) = user_email(repo) {
+        command.arg(format!("--author=<{}>", email));
+    }
+
+    let output = command
+        .output()
+        .with_context(|| format!("Failed to run git log in {}", repo.display()))?;
+    if !output.status.success() {
+        return Err(anyhow::anyhow!(
+            "git log failed in {}: {}",
+            repo.display(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+
+    let mut commits: Vec<Commit> = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.splitn(3, '\x1f');
+            let hash = fields.next()?.to_string();
+            let time = DateTime::parse_from_rfc3339(fields.next()?).ok()?.with_timezone(&Utc);
+            let subject = fields.next().unwrap_or_default().to_string();
+            Some(Commit { hash, time, subject })
+        })
+        .filter(|commit| commit.time >= since)
+        .collect();
+    commits.sort_by_key(|commit| commit.time);
+    Ok(commits)
+}
+
+fn user_email(repo: &Path) -> Option<String> {
+    let output = Command::new("git").arg("-C").arg(repo).args(["config", "user.email"]).output().ok()?;
+    let email = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    (output.status.success() && !email.is_empty()).then_some(email)
+}
+
+/// Commits made during each session or within [`COMMIT_GRACE_MINUTES`] after it
+///
+/// Sessions are grouped by the repository of their working directory.
+/// Sessions outside a repository, or without commits, are left out; a
+/// repository git cannot read is skipped with a warning.
+pub fn correlate(sessions: &[WorkSession]) -> Vec<SessionCommits> {
+    let mut roots: HashMap<&str, Option<PathBuf>> = HashMap::new();
+    let mut repos: BTreeMap<PathBuf, Vec<&WorkSession>> = BTreeMap::new();
+    for session in sessions {
+        let root = roots
+            .entry(session.project_path.as_str())
+            .or_insert_with(|| repo_root(Path::new(&session.project_path)));
+        if let Some(root) = root {
+            repos.entry(root.clone()).or_default().push(session);
+        }
+    }
+
+    let mut correlated = Vec::new();
+    for (repo, sessions) in repos {
+        let Some(since) = sessions.iter().map(|session| session.start_time).min() else {
+            continue;
+        };
+        match commits_since(&repo, since) {
+            Ok(commits) => correlated.extend(assign_commits(sessions, commits)),
+            Err(e) => tracing::warn!("{:#}", e),
+        }
+    }
+    correlated.sort_by_key(|session| session.start_time);
+    correlated
+}
+
+/// Give each commit to the latest of the repository's sessions that started
+/// before it, if it was made before that session ended plus the grace period
+pub fn assign_commits(mut sessions: Vec<&WorkSession>, commits: Vec<Commit>) -> Vec<SessionCommits> {
+    sessions.sort_by_key(|session| session.start_time);
+    let grace = Duration::minutes(COMMIT_GRACE_MINUTES);
+
+    let mut assigned: BTreeMap<usize, Vec<Commit>> = BTreeMap::new();
+    for commit in commits {
+        let started = sessions.partition_point(|session| session.start_time <= commit.time);
+        if started == 0 || commit.time > sessions[started - 1].end_time + grace {
+            continue;
+        }
+        assigned.entry(started - 1).or_default().push(commit);
+    }
+
+    assigned
+        .into_iter()
+        .map(|(index, commits)| SessionCommits {
+            session_id: sessions[index].session_id,
+            start_time: sessions[index].start_time,
+            commits,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use tempfile::TempDir;
+    use uuid::Uuid;
+
+    fn session(project_path: &str, start_time: DateTime<Utc>, minutes: i64) -> WorkSession {
+        WorkSession {
+            session_id: Uuid::new_v4(),
+            project_path: project_path.to_string(),
+            start_time,
+            end_time: start_time + Duration::minutes(minutes),
+            entries: Vec::new(),
+            total_messages: 4,
+            user_messages: 2,
+            assistant_messages: 2,
+            summary: None,
+            merged_session_ids: Vec::new(),
+        }
+    }
+
+    fn commit(time: DateTime<Utc>, subject: &str) -> Commit {
+        Commit { hash: format!("{:040}", time.timestamp()), time, subject: subject.to_string() }
+    }
+
+    #[test]
+    fn test_assign_commits() {
+        let start = Utc.with_ymd_and_hms(2025, 7, 1, 9, 0, 0).unwrap();
+        let morning = session("/work/api", start, 60);
+        let afternoon = session("/work/api", start + Duration::hours(4), 30);
+        let commits = vec![
+            commit(start - Duration::minutes(5), "before any session"),
+            commit(start + Duration::minutes(50), "during the morning"),
+            commit(start + Duration::minutes(80), "right after the morning"),
+            commit(start + Duration::hours(2), "long after the morning"),
+            commit(start + Duration::hours(4) + Duration::minutes(10), "during the afternoon"),
+        ];
+
+        let assigned = assign_commits(vec![&afternoon, &morning], commits);
+        assert_eq!(assigned.len(), 2);
+        assert_eq!(assigned[0].session_id, morning.session_id);
+        let subjects: Vec<&str> = assigned[0].commits.iter().map(|commit| commit.subject.as_str()).collect();
+        assert_eq!(subjects, ["during the morning", "right after the morning"]);
+        assert_eq!(assigned[1].commits[0].subject, "during the afternoon");
+    }
+
+    #[test]
+    fn test_correlate_with_repository() {
+        let temp_dir = TempDir::new().unwrap();
+        let repo = temp_dir.path();
+        let git = |args: &[&str], date: &str| {
+            let status = Command::new("git")
+                .arg("-C")
+                .arg(repo)
+                .args(args)
+                .env("GIT_AUTHOR_DATE", date)
+                .env("GIT_COMMITTER_DATE", date)
+                .status()
+                .unwrap();
+            assert!(status.success());
+        };
+        git(&["init", "-q"], "");
+        git(&["config", "user.email", "me@example.com"], "");
+        git(&["config", "user.name", "Me"], "");
+        git(&["commit", "-q", "--allow-empty", "-m", "Fix login redirect"], "2025-07-01T09:20:00+00:00");
+        git(&["-c", "user.email=other@example.com", "commit", "-q", "--allow-empty", "-m", "Someone else's change"], "2025-07-01T09:25:00+00:00");
+
+        let subdir = repo.join("src");
+        std::fs::create_dir(&subdir).unwrap();
+        let start = Utc.with_ymd_and_hms(2025, 7, 1, 9, 0, 0).unwrap();
+        let sessions = vec![
+            session(subdir.to_str().unwrap(), start, 30),
+            session(temp_dir.path().join("missing").to_str().unwrap(), start, 30),
+        ];
+
+        let correlated = correlate(&sessions);
+        assert_eq!(correlated.len(), 1);
+        assert_eq!(correlated[0].session_id, sessions[0].session_id);
+        assert_eq!(correlated[0].commits.len(), 1);
+        assert_eq!(correlated[0].commits[0].subject, "Fix login redirect");
+        assert_eq!(correlated[0].commits[0].time, start + Duration::minutes(20));
+    }
+}
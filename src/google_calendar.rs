@@ -0,0 +1,391 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, FixedOffset, Utc};
+use reqwest::{StatusCode, Url};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::path::Path;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+
+use crate::models::WorkSession;
+use crate::scanner::ProjectScanner;
+
+const AUTH_URL: &str = "https://accounts.google.com/o/oauth2/v2/auth";
+const TOKEN_URL: &str = "https://oauth2.googleapis.com/token";
+const API_URL: &str = "https://www.googleapis.com/calendar/v3";
+const SCOPE: &str = "https://www.googleapis.com/auth/calendar";
+
+/// Default name of the calendar that receives session events
+pub const DEFAULT_CALENDAR_NAME: &str = "Claude Work";
+
+/// OAuth client of type "Desktop app" created in the Google Cloud console
+#[derive(Debug, Clone, Deserialize)]
+pub struct OAuthClient {
+    pub client_id: String,
+    pub client_secret: String,
+}
+
+impl OAuthClient {
+    /// Load the credentials JSON downloaded from the Google Cloud console
+    pub fn from_file(path: &Path) -> Result<Self> {
+        #[derive(Deserialize)]
+        struct Credentials {
+            installed: OAuthClient,
+        }
+
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read Google credentials: {}", path.display()))?;
+        let credentials: Credentials = serde_json::from_str(&content)
+            .with_context(|| format!("Expected desktop app credentials in {}", path.display()))?;
+        Ok(credentials.installed)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct StoredToken {
+    refresh_token: String,
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    refresh_token: Option<String>,
+}
+
+/// A work session rendered as a calendar event
+#[derive(Debug, Clone, PartialEq)]
+pub struct SessionEvent {
+    /// Stable id so repeated syncs update the event instead of duplicating it
+    pub id: String,
+    pub summary: String,
+    pub description: String,
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+}
+
+impl SessionEvent {
+    pub fn from_session(session: &WorkSession) -> Self {
+        let project = ProjectScanner::extract_project_name(Path::new(&session.project_path))
+            .unwrap_or_else(|| session.project_path.clone());
+
+        let mut description = format!(
+            "{} messages ({} user, {} assistant)",
+            session.total_messages, session.user_messages, session.assistant_messages
+        );
+        if let Some(summary) = &session.summary {
+            description.push_str(&format!("\n\n{}", summary.overall_summary));
+            if !summary.main_topics.is_empty() {
+                description.push_str(&format!("\nTopics: {}", summary.main_topics.join(", ")));
+            }
+        }
+
+        Self {
+            id: Self::event_id(session),
+            summary: format!("Claude: {}", project),
+            description,
+            start: session.start_time,
+            end: session.end_time,
+        }
+    }
+
+    /// Event ids may only use base32hex characters (0-9, a-v)
+    ///
+    /// A log session can be split into several work sessions, so the start time is part of the id.
+    fn event_id(session: &WorkSession) -> String {
+        format!("{}{}", session.session_id.simple(), session.start_time.timestamp())
+    }
+
+    fn to_json(&self, timezone: &FixedOffset) -> serde_json::Value {
+        json!({
+            "id": self.id,
+            "summary": self.summary,
+            "description": self.description,
+            "start": { "dateTime": self.start.with_timezone(timezone).to_rfc3339() },
+            "end": { "dateTime": self.end.with_timezone(timezone).to_rfc3339() },
+            "transparency": "transparent",
+        })
+    }
+}
+
+/// Whether an upsert created a new event or updated an existing one
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UpsertOutcome {
+    Created,
+    Updated,
+}
+
+/// Authorize this tool once via the browser and store the refresh token
+///
+/// Uses the loopback redirect flow for desktop apps: Google redirects to a
+/// temporary local HTTP listener that receives the authorization code.
+pub async fn authorize(client: &OAuthClient, token_path: &Path) -> Result<()> {
+    let listener = TcpListener::bind("127.0.0.1:0").await?;
+    let redirect_uri = format!("http://127.0.0.1:{}", listener.local_addr()?.port());
+    let state = uuid::Uuid::new_v4().simple().to_string();
+
+    let url = Url::parse_with_params(
+        AUTH_URL,
+        &[
+            ("client_id", client.client_id.as_str()),
+            ("redirect_uri", redirect_uri.as_str()),
+            ("response_type", "code"),
+            ("scope", SCOPE),
+            ("access_type", "offline"),
+            // Ensures a refresh token is returned even if access was granted before
+            ("prompt", "consent"),
+            ("state", state.as_str()),
+        ],
+    )?;
+    eprintln!("Open this URL in your browser to grant calendar access:\n\n{}\n", url);
+
+    let code = receive_authorization_code(&listener, &state).await?;
+    let response: TokenResponse = reqwest::Client::new()
+        .post(TOKEN_URL)
+        .form(&[
+            ("code", code.as_str()),
+            ("client_id", client.client_id.as_str()),
+            ("client_secret", client.client_secret.as_str()),
+            ("redirect_uri", redirect_uri.as_str()),
+            ("grant_type", "authorization_code"),
+        ])
+        .send()
+        .await?
+        .error_for_status()
+        .context("Failed to exchange the authorization code")?
+        .json()
+        .await?;
+
+    let refresh_token = response
+        .refresh_token
+        .ok_or_else(|| anyhow::anyhow!("Google did not return a refresh token"))?;
+    if let Some(parent) = token_path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(token_path, serde_json::to_string(&StoredToken { refresh_token })?)
+        .with_context(|| format!("Failed to write token: {}", token_path.display()))
+}
+
+/// Wait for the browser redirect and extract the `code` query parameter
+async fn receive_authorization_code(listener: &TcpListener, state: &str) -> Result<String> {
+    let (mut stream, _) = listener.accept().await?;
+    let mut buffer = vec![0u8; 8192];
+    let read = stream.read(&mut buffer).await?;
+    let request = String::from_utf8_lossy(&buffer[..read]);
+
+    // Request line: GET /?code=...&state=... HTTP/1.1
+    let target = request
+        .lines()
+        .next()
+        .and_then(|line| line.split_whitespace().nth(1))
+        .ok_or_else(|| anyhow::anyhow!("Malformed OAuth redirect request"))?;
+    let result = parse_redirect(target, state);
+
+    let body = match &result {
+        Ok(_) => "Authorization complete. You can close this window.",
+        Err(_) => "Authorization failed. Check the terminal for details.",
+    };
+    let response = format!(
+        "HTTP/1.1 200 OK\r\nContent-Type: text/plain; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        body.len(),
+        body
+    );
+    stream.write_all(response.as_bytes()).await?;
+    result
+}
+
+fn parse_redirect(target: &str, state: &str) -> Result<String> {
+    let url = Url::parse(&format!("http://127.0.0.1{}", target))?;
+    let param = |name: &str| {
+        url.query_pairs()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.into_owned())
+    };
+
+    if let Some(error) = param("error") {
+        return Err(anyhow::anyhow!("Authorization was denied: {}", error));
+    }
+    if param("state").as_deref() != Some(state) {
+        return Err(anyhow::anyhow!("OAuth state mismatch, ignoring redirect"));
+    }
+    param("code").ok_or_else(|| anyhow::anyhow!("OAuth redirect did not contain a code"))
+}
+
+/// Authorized Google Calendar API client
+pub struct GoogleCalendar {
+    http: reqwest::Client,
+    access_token: String,
+}
+
+impl GoogleCalendar {
+    /// Exchange the stored refresh token for an access token
+    pub async fn connect(client: &OAuthClient, token_path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(token_path).with_context(|| {
+            format!("No Google token at {}, run `calendar auth` first", token_path.display())
+        })?;
+        let stored: StoredToken = serde_json::from_str(&content)
+            .with_context(|| format!("Invalid token file: {}", token_path.display()))?;
+
+        let http = reqwest::Client::new();
+        let response: TokenResponse = http
+            .post(TOKEN_URL)
+            .form(&[
+                ("refresh_token", stored.refresh_token.as_str()),
+                ("client_id", client.client_id.as_str()),
+                ("client_secret", client.client_secret.as_str()),
+                ("grant_type", "refresh_token"),
+            ])
+            .send()
+            .await?
+            .error_for_status()
+            .context("Failed to refresh the Google access token, try `calendar auth` again")?
+            .json()
+            .await?;
+
+        Ok(Self {
+            http,
+            access_token: response.access_token,
+        })
+    }
+
+    /// Id of the calendar with the given name, creating it if necessary
+    pub async fn find_or_create_calendar(&self, name: &str) -> Result<String> {
+        #[derive(Deserialize)]
+        struct CalendarList {
+            #[serde(default)]
+            items: Vec<CalendarListEntry>,
+            #[serde(rename = "nextPageToken")]
+            next_page_token: Option<String>,
+        }
+        #[derive(Deserialize)]
+        struct CalendarListEntry {
+            id: String,
+            summary: Option<String>,
+        }
+
+        let mut page_token: Option<String> = None;
+        loop {
+            let mut request = self
+                .http
+                .get(format!("{}/users/me/calendarList", API_URL))
+                .bearer_auth(&self.access_token);
+            if let Some(token) = &page_token {
+                request = request.query(&[("pageToken", token)]);
+            }
+            let list: CalendarList = request.send().await?.error_for_status()?.json().await?;
+
+            if let Some(entry) = list.items.into_iter().find(|c| c.summary.as_deref() == Some(name)) {
+                return Ok(entry.id);
+            }
+            match list.next_page_token {
+                Some(token) => page_token = Some(token),
+                None => break,
+            }
+        }
+
+        let created: CalendarListEntry = self
+            .http
+            .post(format!("{}/calendars", API_URL))
+            .bearer_auth(&self.access_token)
+            .json(&json!({ "summary": name }))
+            .send()
+            .await?
+            .error_for_status()
+            .with_context(|| format!("Failed to create calendar '{}'", name))?
+            .json()
+            .await?;
+        Ok(created.id)
+    }
+
+    /// Update the event with the same id, or insert it if it does not exist yet
+    pub async fn upsert_event(
+        &self,
+        calendar_id: &str,
+        event: &SessionEvent,
+        timezone: &FixedOffset,
+    ) -> Result<UpsertOutcome> {
+        let body = event.to_json(timezone);
+        let events_url = format!("{}/calendars/{}/events", API_URL, encode_path_segment(calendar_id));
+
+        let response = self
+            .http
+            .put(format!("{}/{}", events_url, event.id))
+            .bearer_auth(&self.access_token)
+            .json(&body)
+            .send()
+            .await?;
+        if response.status() != StatusCode::NOT_FOUND {
+            response
+                .error_for_status()
+                .with_context(|| format!("Failed to update event {}", event.id))?;
+            return Ok(UpsertOutcome::Updated);
+        }
+
+        self.http
+            .post(events_url)
+            .bearer_auth(&self.access_token)
+            .json(&body)
+            .send()
+            .await?
+            .error_for_status()
+            .with_context(|| format!("Failed to create event {}", event.id))?;
+        Ok(UpsertOutcome::Created)
+    }
+}
+
+/// Calendar ids look like e-mail addresses and must be escaped in URL paths
+fn encode_path_segment(segment: &str) -> String {
+    let mut url = Url::parse("http://localhost/").unwrap();
+    url.path_segments_mut().unwrap().push(segment);
+    url.path().trim_start_matches('/').to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, TimeZone};
+    use uuid::Uuid;
+
+    fn create_test_session() -> WorkSession {
+        let start = Utc.with_ymd_and_hms(2025, 7, 1, 1, 0, 0).unwrap();
+        WorkSession {
+            session_id: Uuid::parse_str("5abda76b-79c3-406d-bea9-e607520f0047").unwrap(),
+            project_path: "/home/user/.claude/projects/-home-user-work-api".to_string(),
+            start_time: start,
+            end_time: start + Duration::minutes(35),
+            entries: Vec::new(),
+            total_messages: 4,
+            user_messages: 2,
+            assistant_messages: 2,
+            summary: None,
+            merged_session_ids: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_session_event() {
+        let event = SessionEvent::from_session(&create_test_session());
+
+        assert_eq!(event.id, "5abda76b79c3406dbea9e607520f00471751331600");
+        assert!(event.id.chars().all(|c| matches!(c, '0'..='9' | 'a'..='v')));
+        assert_eq!(event.summary, "Claude: user/work/api");
+        assert_eq!(event.description, "4 messages (2 user, 2 assistant)");
+
+        let tz = FixedOffset::east_opt(9 * 3600).unwrap();
+        let body = event.to_json(&tz);
+        assert_eq!(body["start"]["dateTime"], "2025-07-01T10:00:00+09:00");
+        assert_eq!(body["end"]["dateTime"], "2025-07-01T10:35:00+09:00");
+    }
+
+    #[test]
+    fn test_parse_redirect() {
+        assert_eq!(parse_redirect("/?state=abc&code=4%2F0Ab", "abc").unwrap(), "4/0Ab");
+        assert!(parse_redirect("/?state=other&code=x", "abc").is_err());
+        assert!(parse_redirect("/?error=access_denied&state=abc", "abc").is_err());
+    }
+
+    #[test]
+    fn test_encode_path_segment() {
+        assert_eq!(encode_path_segment("abc@group.calendar.google.com"), "abc@group.calendar.google.com");
+        assert_eq!(encode_path_segment("a/b c"), "a%2Fb%20c");
+    }
+}
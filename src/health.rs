@@ -0,0 +1,344 @@
+use chrono::{DateTime, Duration, FixedOffset, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::clock_skew::find_corrections;
+use crate::models::ClaudeLogEntry;
+use crate::parser::ParseStats;
+
+/// Consecutive days without entries reported as a gap unless configured otherwise
+pub const DEFAULT_MIN_GAP_DAYS: i64 = 3;
+
+/// Data coverage for a single project directory
+#[derive(Debug, Clone)]
+pub struct ProjectHealth {
+    pub project_name: String,
+    pub file_count: usize,
+    pub entry_count: usize,
+    pub earliest_entry: Option<DateTime<Utc>>,
+    pub latest_entry: Option<DateTime<Utc>>,
+    pub parse_stats: ParseStats,
+    /// Files that could not be read at all, so have no lines to count
+    pub unreadable_files: usize,
+    /// Entries whose timestamps are out of order within their session
+    pub skewed_entries: usize,
+    /// Largest correction needed to restore the order
+    pub max_skew: Duration,
+}
+
+impl ProjectHealth {
+    fn new(project_name: String) -> Self {
+        Self {
+            project_name,
+            file_count: 0,
+            entry_count: 0,
+            earliest_entry: None,
+            latest_entry: None,
+            parse_stats: ParseStats::default(),
+            unreadable_files: 0,
+            skewed_entries: 0,
+            max_skew: Duration::zero(),
+        }
+    }
+}
+
+/// A run of consecutive days without any log entries between active days
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CoverageGap {
+    pub first_missing_day: NaiveDate,
+    pub last_missing_day: NaiveDate,
+}
+
+impl CoverageGap {
+    pub fn days(&self) -> i64 {
+        (self.last_missing_day - self.first_missing_day).num_days() + 1
+    }
+}
+
+/// Days of a report's period without logs, typically because Claude Code pruned them
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LogGaps {
+    /// Days of the period before the earliest log entry
+    pub before_logs: Option<CoverageGap>,
+    /// Runs of empty days between days with activity
+    pub gaps: Vec<CoverageGap>,
+}
+
+impl LogGaps {
+    /// Gaps of a period starting on `period_start`, when bounded
+    ///
+    /// `first_log_day` is the day of the earliest entry of any log read and
+    /// `active_days` the days with sessions; runs of at least `min_gap_days`
+    /// empty days between them are gaps.
+    pub fn find(
+        active_days: impl IntoIterator<Item = NaiveDate>,
+        period_start: Option<NaiveDate>,
+        first_log_day: Option<NaiveDate>,
+        min_gap_days: i64,
+    ) -> Self {
+        let before_logs = match (period_start, first_log_day) {
+            (Some(start), Some(first)) if start < first => Some(CoverageGap {
+                first_missing_day: start,
+                last_missing_day: first - Duration::days(1),
+            }),
+            _ => None,
+        };
+        Self {
+            before_logs,
+            gaps: gaps_between(&active_days.into_iter().collect(), min_gap_days),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.before_logs.is_none() && self.gaps.is_empty()
+    }
+
+    /// Whether `day` falls into one of the gaps
+    pub fn contains(&self, day: NaiveDate) -> bool {
+        self.before_logs
+            .iter()
+            .chain(&self.gaps)
+            .any(|gap| (gap.first_missing_day..=gap.last_missing_day).contains(&day))
+    }
+}
+
+/// Runs of at least `min_gap_days` days missing from `days` between two of them
+fn gaps_between(days: &BTreeSet<NaiveDate>, min_gap_days: i64) -> Vec<CoverageGap> {
+    let days: Vec<&NaiveDate> = days.iter().collect();
+    days.windows(2)
+        .filter(|pair| (*pair[1] - *pair[0]).num_days() > min_gap_days)
+        .map(|pair| CoverageGap {
+            first_missing_day: *pair[0] + Duration::days(1),
+            last_missing_day: *pair[1] - Duration::days(1),
+        })
+        .collect()
+}
+
+/// Aggregated view of how complete the available log data is
+pub struct DataHealth {
+    /// Per-project coverage keyed by project name
+    projects: BTreeMap<String, ProjectHealth>,
+    /// Local calendar days that have at least one entry
+    active_days: BTreeSet<NaiveDate>,
+    /// Timezone used to decide which calendar day an entry belongs to
+    timezone: FixedOffset,
+    /// Minimum number of consecutive empty days reported as a gap
+    min_gap_days: i64,
+}
+
+impl DataHealth {
+    pub fn new(timezone: FixedOffset) -> Self {
+        Self {
+            projects: BTreeMap::new(),
+            active_days: BTreeSet::new(),
+            timezone,
+            min_gap_days: DEFAULT_MIN_GAP_DAYS,
+        }
+    }
+
+    pub fn with_min_gap_days(mut self, days: i64) -> Self {
+        self.min_gap_days = days.max(1);
+        self
+    }
+
+    /// Record the parse result of one JSONL file belonging to a project
+    pub fn record_file(&mut self, project_name: &str, entries: &[ClaudeLogEntry], stats: &ParseStats) {
+        let project = self
+            .projects
+            .entry(project_name.to_string())
+            .or_insert_with(|| ProjectHealth::new(project_name.to_string()));
+
+        project.file_count += 1;
+        project.entry_count += entries.len();
+        project.parse_stats.add(stats);
+
+        for correction in find_corrections(entries) {
+            project.skewed_entries += 1;
+            project.max_skew = project.max_skew.max(correction.skew());
+        }
+
+        for entry in entries {
+            if project.earliest_entry.is_none_or(|earliest| entry.timestamp < earliest) {
+                project.earliest_entry = Some(entry.timestamp);
+            }
+            if project.latest_entry.is_none_or(|latest| entry.timestamp > latest) {
+                project.latest_entry = Some(entry.timestamp);
+            }
+            self.active_days
+                .insert(entry.timestamp.with_timezone(&self.timezone).date_naive());
+        }
+    }
+
+    /// Record a file that could not be read at all
+    pub fn record_unreadable_file(&mut self, project_name: &str) {
+        let project = self
+            .projects
+            .entry(project_name.to_string())
+            .or_insert_with(|| ProjectHealth::new(project_name.to_string()));
+        project.file_count += 1;
+        project.unreadable_files += 1;
+    }
+
+    pub fn projects(&self) -> impl Iterator<Item = &ProjectHealth> {
+        self.projects.values()
+    }
+
+    pub fn timezone(&self) -> FixedOffset {
+        self.timezone
+    }
+
+    pub fn total_files(&self) -> usize {
+        self.projects.values().map(|p| p.file_count).sum()
+    }
+
+    pub fn total_entries(&self) -> usize {
+        self.projects.values().map(|p| p.entry_count).sum()
+    }
+
+    pub fn unreadable_files(&self) -> usize {
+        self.projects.values().map(|p| p.unreadable_files).sum()
+    }
+
+    /// Parse statistics summed over all projects
+    pub fn total_parse_stats(&self) -> ParseStats {
+        let mut total = ParseStats::default();
+        for project in self.projects.values() {
+            total.add(&project.parse_stats);
+        }
+        total
+    }
+
+    /// Projects with out-of-order timestamps, most skewed first
+    pub fn skewed_projects(&self) -> Vec<&ProjectHealth> {
+        let mut projects: Vec<&ProjectHealth> = self
+            .projects
+            .values()
+            .filter(|p| p.skewed_entries > 0)
+            .collect();
+        projects.sort_by_key(|p| std::cmp::Reverse(p.max_skew));
+        projects
+    }
+
+    /// First and last local day with any activity
+    pub fn covered_range(&self) -> Option<(NaiveDate, NaiveDate)> {
+        Some((*self.active_days.first()?, *self.active_days.last()?))
+    }
+
+    /// Runs of empty days between active days that are at least `min_gap_days` long
+    pub fn coverage_gaps(&self) -> Vec<CoverageGap> {
+        gaps_between(&self.active_days, self.min_gap_days)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{EntryType, MessageContent, MessageContentVariant};
+    use crate::timezone::jst;
+    use chrono::TimeZone;
+    use uuid::Uuid;
+
+    fn create_test_entry(timestamp: DateTime<Utc>) -> ClaudeLogEntry {
+        ClaudeLogEntry {
+            parent_uuid: None,
+            is_sidechain: false,
+            user_type: "external".to_string(),
+            cwd: "/test".to_string(),
+            session_id: Uuid::new_v4(),
+            version: "1.0.0".to_string(),
+            entry_type: EntryType::User,
+            message: MessageContent {
+                role: "user".to_string(),
+                content: MessageContentVariant::String("test".to_string()),
+                id: None,
+                message_type: None,
+                model: None,
+                stop_reason: None,
+                stop_sequence: None,
+                usage: None,
+            },
+            uuid: Uuid::new_v4(),
+            timestamp,
+            request_id: None,
+            tool_use_result: None,
+            source: None,
+        }
+    }
+
+    #[test]
+    fn test_project_coverage() {
+        let mut health = DataHealth::new(jst());
+        let first = Utc.with_ymd_and_hms(2025, 7, 1, 0, 0, 0).unwrap();
+        let last = Utc.with_ymd_and_hms(2025, 7, 2, 0, 0, 0).unwrap();
+        let stats = ParseStats {
+            total_lines: 4,
+            parsed_entries: 2,
+            parse_errors: 2,
+            ..Default::default()
+        };
+
+        health.record_file("api", &[create_test_entry(last), create_test_entry(first)], &stats);
+        health.record_unreadable_file("api");
+
+        let project = health.projects().next().unwrap();
+        assert_eq!(project.file_count, 2);
+        assert_eq!(project.entry_count, 2);
+        assert_eq!(project.earliest_entry, Some(first));
+        assert_eq!(project.latest_entry, Some(last));
+        assert_eq!(health.total_parse_stats().parse_errors, 2);
+        // The unreadable file has no lines, so it is counted apart from the error rate
+        assert_eq!((project.unreadable_files, health.unreadable_files()), (1, 1));
+        assert!((health.total_parse_stats().error_rate() - 0.5).abs() < f64::EPSILON);
+        assert!(health.skewed_projects().is_empty());
+    }
+
+    #[test]
+    fn test_clock_skew_warnings() {
+        let mut health = DataHealth::new(jst());
+        let start = Utc.with_ymd_and_hms(2025, 7, 1, 0, 0, 0).unwrap();
+        let mut entries: Vec<ClaudeLogEntry> = [0, 10, 5, 20]
+            .iter()
+            .map(|minutes| create_test_entry(start + Duration::minutes(*minutes)))
+            .collect();
+        let session_id = entries[0].session_id;
+        for entry in &mut entries {
+            entry.session_id = session_id;
+        }
+        health.record_file("api", &entries, &ParseStats::default());
+
+        let skewed = health.skewed_projects();
+        assert_eq!(skewed.len(), 1);
+        assert_eq!(skewed[0].skewed_entries, 1);
+        assert_eq!(skewed[0].max_skew, Duration::minutes(5));
+    }
+
+    #[test]
+    fn test_coverage_gaps() {
+        let mut health = DataHealth::new(jst()).with_min_gap_days(3);
+        let entries: Vec<ClaudeLogEntry> = [1, 2, 4, 10]
+            .iter()
+            .map(|day| create_test_entry(Utc.with_ymd_and_hms(2025, 7, *day, 3, 0, 0).unwrap()))
+            .collect();
+        health.record_file("api", &entries, &ParseStats::default());
+
+        let gaps = health.coverage_gaps();
+        assert_eq!(gaps.len(), 1);
+        assert_eq!(gaps[0].first_missing_day, NaiveDate::from_ymd_opt(2025, 7, 5).unwrap());
+        assert_eq!(gaps[0].days(), 5);
+    }
+
+    #[test]
+    fn test_log_gaps() {
+        let day = |d| NaiveDate::from_ymd_opt(2025, 7, d).unwrap();
+        let gaps = LogGaps::find([day(10), day(11), day(13), day(20)], Some(day(1)), Some(day(8)), 3);
+
+        assert_eq!(gaps.before_logs.as_ref().map(|gap| (gap.first_missing_day, gap.days())), Some((day(1), 7)));
+        assert_eq!(gaps.gaps, [CoverageGap { first_missing_day: day(14), last_missing_day: day(19) }]);
+        assert!(gaps.contains(day(7)) && gaps.contains(day(16)));
+        assert!(!gaps.contains(day(8)) && !gaps.contains(day(12)));
+
+        // A period within the logs, without long pauses, is fully covered
+        assert!(LogGaps::find([day(10), day(12)], Some(day(9)), Some(day(8)), 3).is_empty());
+        assert!(LogGaps::find([day(10)], None, Some(day(8)), 3).is_empty());
+    }
+}
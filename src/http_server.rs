@@ -0,0 +1,861 @@
+use anyhow::Result;
+use chrono::{DateTime, NaiveDate, Utc};
+use regex::{Regex, RegexBuilder};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use thiserror::Error;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+use uuid::Uuid;
+
+use crate::config::Config;
+use crate::error::Error;
+use crate::filter::TimeRangeFilter;
+use crate::metrics;
+use crate::models::{WorkAnalysis, WorkSession};
+use crate::parser::JsonlParser;
+use crate::pipeline::AnalysisPipeline;
+use crate::result_cache::ResultCache;
+use crate::timezone::TimezoneSchedule;
+
+/// Longest request line and headers accepted, in bytes
+const MAX_REQUEST_HEAD: usize = 16 * 1024;
+
+/// How long a client may take to send the request line and headers
+const REQUEST_HEAD_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Sessions per page of `/sessions` and `/search` unless `limit` is given
+const DEFAULT_PAGE_SIZE: usize = 50;
+
+/// Characters of context on each side of a search match
+const SNIPPET_CONTEXT: usize = 60;
+
+/// Query parameters every endpoint accepts
+const PERIOD_PARAMS: [&str; 4] = ["from", "to", "days", "project"];
+
+/// A request the client has to change; answered with 400
+#[derive(Debug, Error)]
+#[error("{0}")]
+struct BadRequest(String);
+
+/// A session or route that does not exist; answered with 404
+#[derive(Debug, Error)]
+#[error("{0}")]
+struct NotFound(String);
+
+/// Content type of the Prometheus text exposition format
+const METRICS_CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+/// Status and JSON body of an answer
+#[derive(Debug, Clone, PartialEq)]
+pub struct HttpResponse {
+    pub status: u16,
+    pub body: Value,
+    /// Plain text sent instead of the JSON body, for `/metrics`
+    pub text: Option<String>,
+}
+
+impl HttpResponse {
+    fn json(body: Value) -> Self {
+        Self { status: 200, body, text: None }
+    }
+
+    fn error(status: u16, message: impl Into<String>) -> Self {
+        Self { status, body: json!({ "error": message.into() }), text: None }
+    }
+
+    fn reason(&self) -> &'static str {
+        match self.status {
+            200 => "OK",
+            204 => "No Content",
+            400 => "Bad Request",
+            401 => "Unauthorized",
+            404 => "Not Found",
+            405 => "Method Not Allowed",
+            408 => "Request Timeout",
+            _ => "Internal Server Error",
+        }
+    }
+}
+
+/// Read-only JSON API over the analysis, for dashboards such as Grafana
+///
+/// Every endpoint takes the period as `from` / `to` (YYYY-MM-DD, local dates)
+/// or `days`, and a `project`; without them all logs are analyzed. Results are
+/// kept in a [`ResultCache`], so polling an unchanged period does not parse the
+/// logs again.
+///
+/// `/metrics` serves all logs in the Prometheus text format. It answers with the
+/// metrics of the last refresh, see [`HttpServer::refresh_metrics_every`].
+pub struct HttpServer {
+    projects_dirs: Vec<PathBuf>,
+    parser: JsonlParser,
+    config: Config,
+    schedule: TimezoneSchedule,
+    results: ResultCache,
+    metrics: Mutex<Option<String>>,
+    /// Bearer token every request has to carry, if any
+    token: Option<String>,
+    /// Origins whose web pages may read the answers; no other page can
+    allowed_origins: Vec<String>,
+}
+
+impl HttpServer {
+    pub fn new(projects_dirs: Vec<PathBuf>, parser: JsonlParser, config: Config, schedule: TimezoneSchedule) -> Self {
+        Self {
+            projects_dirs,
+            parser,
+            config,
+            schedule,
+            results: ResultCache::default(),
+            metrics: Mutex::new(None),
+            token: None,
+            allowed_origins: Vec::new(),
+        }
+    }
+
+    /// Answer only requests with an `Authorization: Bearer <token>` header
+    pub fn with_token(mut self, token: Option<String>) -> Self {
+        self.token = token;
+        self
+    }
+
+    /// Let web pages served from `origins`, e.g. `http://localhost:3000`, read the answers
+    ///
+    /// Browsers keep the answers from every other page, so that a site open in
+    /// the browser cannot read the logs through the API.
+    pub fn with_allowed_origins(mut self, origins: Vec<String>) -> Self {
+        self.allowed_origins = origins;
+        self
+    }
+
+    /// CORS headers for a request from `origin`, none unless it is allowed
+    fn cors_headers(&self, origin: Option<&str>) -> String {
+        match origin {
+            Some(origin) if self.allowed_origins.iter().any(|allowed| allowed == origin) => {
+                format!("Access-Control-Allow-Origin: {}\r\nVary: Origin\r\n", origin)
+            }
+            _ => String::new(),
+        }
+    }
+
+    /// Whether the `Authorization` header carries the token, if one is required
+    fn authorized(&self, authorization: Option<&str>) -> bool {
+        let Some(token) = &self.token else {
+            return true;
+        };
+        let given = authorization.and_then(|value| value.strip_prefix("Bearer ")).unwrap_or("").trim();
+        // An empty token never matches, so a blank setting locks the API instead of opening it
+        if given.is_empty() {
+            return false;
+        }
+        // Compare every byte so that the time taken does not tell how much matched
+        given.len() == token.len() && given.bytes().zip(token.bytes()).fold(0, |diff, (a, b)| diff | (a ^ b)) == 0
+    }
+
+    /// Analyze all logs and keep their metrics for `/metrics`
+    pub async fn refresh_metrics(&self) -> Result<String> {
+        let started = Instant::now();
+        let analysis = self.analyze(TimeRangeFilter::new(None, None, None)).await?;
+        let text = metrics::render(&analysis, Utc::now(), started.elapsed());
+        *self.metrics.lock().unwrap() = Some(text.clone());
+        Ok(text)
+    }
+
+    /// Refresh the metrics now and then every `interval`; never returns
+    pub async fn refresh_metrics_every(self: Arc<Self>, interval: Duration) {
+        let mut ticks = tokio::time::interval(interval);
+        ticks.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            ticks.tick().await;
+            if let Err(e) = self.refresh_metrics().await {
+                tracing::warn!("Failed to refresh the metrics: {:#}", e);
+            }
+        }
+    }
+
+    /// Answer connections until the listener fails, one task per connection
+    pub async fn serve(self: Arc<Self>, listener: TcpListener) -> Result<()> {
+        loop {
+            let (stream, _) = listener.accept().await?;
+            let server = Arc::clone(&self);
+            tokio::spawn(async move {
+                if let Err(e) = server.handle_connection(stream).await {
+                    tracing::debug!("HTTP connection failed: {:#}", e);
+                }
+            });
+        }
+    }
+
+    /// Read one request and write its answer, then close the connection
+    async fn handle_connection(&self, stream: TcpStream) -> Result<()> {
+        let (reader, mut writer) = stream.into_split();
+        // One byte past the limit is enough to tell that the head is too large
+        let mut reader = BufReader::new(reader.take(MAX_REQUEST_HEAD as u64 + 1));
+        let mut request_line = String::new();
+        let mut head_size = 0;
+        // Only the authorization and origin headers are needed, the others are read past
+        let mut authorization = None;
+        let mut origin = None;
+        let head = tokio::time::timeout(REQUEST_HEAD_TIMEOUT, async {
+            head_size = reader.read_line(&mut request_line).await?;
+            loop {
+                let mut header = String::new();
+                let read = reader.read_line(&mut header).await?;
+                head_size += read;
+                if read == 0 || header.trim_end().is_empty() || head_size > MAX_REQUEST_HEAD {
+                    return Ok::<_, std::io::Error>(());
+                }
+                if let Some((name, value)) = header.split_once(':') {
+                    if name.eq_ignore_ascii_case("authorization") {
+                        authorization = Some(value.trim().to_string());
+                    } else if name.eq_ignore_ascii_case("origin") {
+                        origin = Some(value.trim().to_string());
+                    }
+                }
+            }
+        })
+        .await;
+        let timed_out = head.is_err();
+        head.unwrap_or(Ok(()))?;
+
+        let cors = self.cors_headers(origin.as_deref());
+        let mut parts = request_line.split_whitespace();
+        let response = match (parts.next(), parts.next(), parts.next()) {
+            _ if timed_out => HttpResponse::error(408, "The request was not sent in time"),
+            _ if head_size > MAX_REQUEST_HEAD => HttpResponse::error(400, "Request headers are too large"),
+            // Browsers ask without the token whether an allowed page may send it
+            (Some("OPTIONS"), Some(_), Some(_)) if !cors.is_empty() => {
+                HttpResponse { status: 204, body: Value::Null, text: Some(String::new()) }
+            }
+            _ if !self.authorized(authorization.as_deref()) => {
+                HttpResponse::error(401, "Missing or wrong token, send Authorization: Bearer <token>")
+            }
+            (Some(method), Some(target), Some(version)) if version.starts_with("HTTP/") => self.handle(method, target).await,
+            _ => HttpResponse::error(400, "Malformed request line"),
+        };
+
+        let (body, content_type) = match &response.text {
+            Some(text) => (text.clone(), METRICS_CONTENT_TYPE),
+            None => (serde_json::to_string_pretty(&response.body)?, "application/json"),
+        };
+        let challenge = if response.status == 401 { "WWW-Authenticate: Bearer\r\n" } else { "" };
+        let preflight = if response.status == 204 {
+            "Access-Control-Allow-Methods: GET\r\nAccess-Control-Allow-Headers: Authorization\r\n"
+        } else {
+            ""
+        };
+        let head = format!(
+            "HTTP/1.1 {} {}\r\nContent-Type: {}\r\nContent-Length: {}\r\n{}{}{}Connection: close\r\n\r\n",
+            response.status,
+            response.reason(),
+            content_type,
+            body.len(),
+            challenge,
+            cors,
+            preflight
+        );
+        writer.write_all(head.as_bytes()).await?;
+        writer.write_all(body.as_bytes()).await?;
+        writer.shutdown().await?;
+        Ok(())
+    }
+
+    /// Answer a request for `target`, a path with an optional query string
+    pub async fn handle(&self, method: &str, target: &str) -> HttpResponse {
+        if method != "GET" {
+            return HttpResponse::error(405, format!("Method {} is not allowed, only GET", method));
+        }
+        let (path, query) = target.split_once('?').unwrap_or((target, ""));
+        let path = path.trim_end_matches('/');
+        let response = match path {
+            "/metrics" => self.metrics(query).await.map(|text| HttpResponse { text: Some(text), ..HttpResponse::json(Value::Null) }),
+            _ => self.route(path, query).await.map(HttpResponse::json),
+        };
+        match response {
+            Ok(response) => response,
+            Err(e) if e.is::<BadRequest>() || matches!(e.downcast_ref::<Error>(), Some(Error::InvalidDate { .. })) => {
+                HttpResponse::error(400, e.to_string())
+            }
+            Err(e) if e.is::<NotFound>() => HttpResponse::error(404, e.to_string()),
+            Err(e) => {
+                // Error chains name files and paths, they only go to the server's log
+                tracing::error!("HTTP request {} failed: {:#}", target, e);
+                HttpResponse::error(500, "Internal error, see the server's log")
+            }
+        }
+    }
+
+    async fn route(&self, path: &str, query: &str) -> Result<Value> {
+        let params = parse_query(query)?;
+        match path {
+            "" => Ok(json!({
+                "endpoints": ["/summary", "/projects", "/projects/{name}", "/sessions", "/sessions/{id}", "/search", "/metrics"]
+            })),
+            "/summary" => {
+                check_params(&params, &[])?;
+                let filter = self.period_filter(&params)?;
+                Ok(self.summary(&self.analyze(filter.clone()).await?, &filter))
+            }
+            "/projects" => {
+                check_params(&params, &[])?;
+                Ok(self.projects(&self.analyze(self.period_filter(&params)?).await?))
+            }
+            "/sessions" => {
+                check_params(&params, &["limit", "offset"])?;
+                let limit = number_param(&params, "limit")?.unwrap_or(DEFAULT_PAGE_SIZE);
+                if limit == 0 {
+                    return Err(BadRequest("limit must be at least 1".to_string()).into());
+                }
+                let offset = number_param(&params, "offset")?.unwrap_or(0);
+                Ok(self.sessions(&self.analyze(self.period_filter(&params)?).await?, limit, offset))
+            }
+            "/search" => {
+                check_params(&params, &["q", "limit"])?;
+                let query = params.get("q").map(|q| q.trim()).unwrap_or("");
+                if query.is_empty() {
+                    return Err(BadRequest("q must not be empty".to_string()).into());
+                }
+                let limit = number_param(&params, "limit")?.unwrap_or(DEFAULT_PAGE_SIZE);
+                if limit == 0 {
+                    return Err(BadRequest("limit must be at least 1".to_string()).into());
+                }
+                Ok(self.search(&self.analyze(self.period_filter(&params)?).await?, query, limit))
+            }
+            _ => {
+                if let Some(id) = path.strip_prefix("/sessions/") {
+                    check_params(&params, &[])?;
+                    let id: Uuid = id.parse().map_err(|_| BadRequest(format!("Invalid session id '{}'", id)))?;
+                    let analysis = self.analyze(self.period_filter(&params)?).await?;
+                    self.session(&analysis, id)
+                } else if let Some(name) = path.strip_prefix("/projects/") {
+                    check_params(&params, &[])?;
+                    if params.contains_key("project") {
+                        return Err(BadRequest("The project is given by the path, not by project".to_string()).into());
+                    }
+                    let name = percent_decode(name)?;
+                    let (from, to) = self.period_filter(&params)?.get_date_range();
+                    let analysis = self.analyze(TimeRangeFilter::new(from, to, Some(name.clone()))).await?;
+                    self.project(&analysis, &name)
+                } else {
+                    Err(NotFound(format!("No endpoint {}", path)).into())
+                }
+            }
+        }
+    }
+
+    /// The metrics of the last refresh, computed now if there was none yet
+    async fn metrics(&self, query: &str) -> Result<String> {
+        if !parse_query(query)?.is_empty() {
+            return Err(BadRequest("/metrics takes no query parameters".to_string()).into());
+        }
+        let refreshed = self.metrics.lock().unwrap().clone();
+        match refreshed {
+            Some(text) => Ok(text),
+            None => self.refresh_metrics().await,
+        }
+    }
+
+    async fn analyze(&self, filter: TimeRangeFilter) -> Result<WorkAnalysis> {
+        let output = AnalysisPipeline::builder()
+            .data_dirs(&self.projects_dirs)
+            .parser(&self.parser)
+            .filter(filter)
+            .analyzer(self.config.analyzer().with_timezone_schedule(self.schedule.clone()))
+            .skip_failed_files(true)
+            .result_cache(&self.results, format!("{:?}|{:?}", self.config, self.schedule))
+            .machines(self.config.machine_labels()?)
+            .run()
+            .await?;
+        for (path, error) in &output.diagnostics.failed_files {
+            tracing::debug!("Failed to parse {}: {}", path.display(), error);
+        }
+        Ok(output.analysis)
+    }
+
+    fn period_filter(&self, params: &HashMap<String, String>) -> Result<TimeRangeFilter> {
+        let project = params.get("project").cloned();
+        if let Some(days) = number_param(params, "days")? {
+            if params.contains_key("from") || params.contains_key("to") {
+                return Err(BadRequest("days cannot be combined with from or to".to_string()).into());
+            }
+            if days == 0 {
+                return Err(BadRequest("days must be at least 1".to_string()).into());
+            }
+            let (from, to) = TimeRangeFilter::last_days(days as i64).get_date_range();
+            return Ok(TimeRangeFilter::new(from, to, project));
+        }
+        let date = |name: &str| -> Result<Option<NaiveDate>> {
+            params
+                .get(name)
+                .map(|value| {
+                    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+                        .map_err(|reason| Error::InvalidDate { value: value.clone(), reason }.into())
+                })
+                .transpose()
+        };
+        let from = date("from")?.map(|day| self.schedule.start_of_day(day));
+        let to = date("to")?.map(|day| self.schedule.end_of_day(day));
+        Ok(TimeRangeFilter::new(from, to, project))
+    }
+
+    fn summary(&self, analysis: &WorkAnalysis, filter: &TimeRangeFilter) -> Value {
+        let (from, to) = filter.get_date_range();
+        let tokens = analysis.token_analysis.as_ref().map(|tokens| &tokens.total);
+        json!({
+            "period": {
+                "from": from.map(|time| self.local(time)),
+                "to": to.map(|time| self.local(time)),
+                "project": filter.get_project_filter(),
+                "timezone": self.schedule.label()
+            },
+            "total_sessions": analysis.total_sessions,
+            "total_messages": analysis.total_messages,
+            "total_work_hours": hours(analysis.total_work_time.num_minutes()),
+            "projects": analysis.project_stats.len(),
+            "first_session": analysis.sessions.iter().map(|session| session.start_time).min().map(|time| self.local(time)),
+            "last_session": analysis.sessions.iter().map(|session| session.end_time).max().map(|time| self.local(time)),
+            "tokens": tokens.map(|stats| stats.usage.total()).unwrap_or(0),
+            "estimated_cost": tokens.map(|stats| stats.estimated_cost).unwrap_or(0.0)
+        })
+    }
+
+    fn projects(&self, analysis: &WorkAnalysis) -> Value {
+        let mut projects: Vec<_> = analysis.project_stats.values().collect();
+        projects.sort_by(|a, b| b.work_time.cmp(&a.work_time).then_with(|| a.project_name.cmp(&b.project_name)));
+        let tokens = analysis.token_analysis.as_ref();
+        let projects: Vec<Value> = projects
+            .into_iter()
+            .map(|stats| {
+                let project_tokens = tokens.and_then(|tokens| tokens.by_project.get(&stats.project_name));
+                json!({
+                    "name": stats.project_name,
+                    "sessions": stats.total_sessions,
+                    "messages": stats.total_messages,
+                    "work_hours": hours(stats.work_time.num_minutes()),
+                    "activity_types": stats.activity_types,
+                    "tokens": project_tokens.map(|stats| stats.usage.total()).unwrap_or(0),
+                    "estimated_cost": project_tokens.map(|stats| stats.estimated_cost).unwrap_or(0.0)
+                })
+            })
+            .collect();
+        json!({ "projects": projects })
+    }
+
+    /// A project's totals, topics and sessions, newest first
+    fn project(&self, analysis: &WorkAnalysis, name: &str) -> Result<Value> {
+        let stats = analysis.project_stats.get(name).ok_or_else(|| NotFound(format!("No project {} in this period", name)))?;
+        let tokens = analysis.token_analysis.as_ref().and_then(|tokens| tokens.by_project.get(name));
+        let topics = stats.topic_analysis.as_ref();
+        let mut sessions: Vec<&WorkSession> =
+            analysis.sessions.iter().filter(|session| analysis.project_name(session) == name).collect();
+        sessions.sort_by_key(|session| std::cmp::Reverse(session.start_time));
+        Ok(json!({
+            "name": stats.project_name,
+            "sessions": stats.total_sessions,
+            "messages": stats.total_messages,
+            "work_hours": hours(stats.work_time.num_minutes()),
+            "activity_types": stats.activity_types,
+            "topics": topics.map(|topics| topics.primary_topics.clone()).unwrap_or_default(),
+            "technologies": topics.map(|topics| topics.technical_stack.clone()).unwrap_or_default(),
+            "tokens": tokens.map(|stats| json!(stats)).unwrap_or(Value::Null),
+            "recent_sessions": sessions.iter().map(|session| self.session_json(analysis, session)).collect::<Vec<_>>()
+        }))
+    }
+
+    /// Sessions whose project, summary or messages contain every word of `query`, newest first
+    fn search(&self, analysis: &WorkAnalysis, query: &str, limit: usize) -> Value {
+        let terms: Vec<Regex> = query
+            .split_whitespace()
+            .filter_map(|term| RegexBuilder::new(&regex::escape(term)).case_insensitive(true).build().ok())
+            .collect();
+        let mut matches: Vec<(&WorkSession, String)> = analysis
+            .sessions
+            .iter()
+            .filter_map(|session| {
+                let texts = self.searchable_texts(analysis, session);
+                let found = terms.iter().all(|term| texts.iter().any(|text| term.is_match(text)));
+                found.then(|| (session, snippet(&texts, &terms[0])))
+            })
+            .collect();
+        matches.sort_by_key(|(session, _)| std::cmp::Reverse(session.start_time));
+        let results: Vec<Value> = matches
+            .iter()
+            .take(limit)
+            .map(|(session, snippet)| {
+                let mut value = self.session_json(analysis, session);
+                value["snippet"] = json!(snippet);
+                value
+            })
+            .collect();
+        json!({ "query": query, "total": matches.len(), "sessions": results })
+    }
+
+    /// The project name, summary and message texts of a session
+    fn searchable_texts(&self, analysis: &WorkAnalysis, session: &WorkSession) -> Vec<String> {
+        let mut texts = vec![analysis.project_name(session)];
+        if let Some(summary) = &session.summary {
+            texts.push(summary.overall_summary.clone());
+            for list in [
+                &summary.main_topics,
+                &summary.key_discussions,
+                &summary.technologies_mentioned,
+                &summary.problems_addressed,
+                &summary.solutions_proposed,
+            ] {
+                texts.extend(list.iter().cloned());
+            }
+        }
+        texts.extend(session.entries.iter().map(|entry| entry.message.content.text()).filter(|text| !text.is_empty()));
+        texts
+    }
+
+    /// One page of the sessions, newest first
+    fn sessions(&self, analysis: &WorkAnalysis, limit: usize, offset: usize) -> Value {
+        let mut sessions: Vec<&WorkSession> = analysis.sessions.iter().collect();
+        sessions.sort_by_key(|session| std::cmp::Reverse(session.start_time));
+        let page: Vec<Value> = sessions.iter().skip(offset).take(limit).map(|session| self.session_json(analysis, session)).collect();
+        let next_offset = offset + page.len();
+        json!({
+            "total": sessions.len(),
+            "offset": offset,
+            "limit": limit,
+            "next_offset": (next_offset < sessions.len()).then_some(next_offset),
+            "sessions": page
+        })
+    }
+
+    /// A session by its id, or the id of a session merged into it
+    fn session(&self, analysis: &WorkAnalysis, id: Uuid) -> Result<Value> {
+        let session = analysis
+            .sessions
+            .iter()
+            .find(|session| session.session_id == id || session.merged_session_ids.contains(&id))
+            .ok_or_else(|| NotFound(format!("No session {} in this period", id)))?;
+        let mut value = self.session_json(analysis, session);
+        value["summary"] = json!(session.summary);
+        let tokens = analysis
+            .token_analysis
+            .as_ref()
+            .and_then(|tokens| tokens.stats_by_session().get(&(session.session_id, session.start_time)).map(|stats| json!(stats)));
+        value["tokens"] = tokens.unwrap_or(Value::Null);
+        Ok(value)
+    }
+
+    fn session_json(&self, analysis: &WorkAnalysis, session: &WorkSession) -> Value {
+        json!({
+            "session_id": session.session_id,
+            "project": analysis.project_name(session),
+            "project_path": session.project_path,
+            "start_time": self.local(session.start_time),
+            "end_time": self.local(session.end_time),
+            "duration_minutes": (session.end_time - session.start_time).num_minutes(),
+            "messages": session.total_messages,
+            "user_messages": session.user_messages,
+            "assistant_messages": session.assistant_messages,
+            "merged_session_ids": session.merged_session_ids,
+            "summary": session.summary.as_ref().map(|summary| summary.overall_summary.clone())
+        })
+    }
+
+    fn local(&self, time: DateTime<Utc>) -> String {
+        self.schedule.to_local(time).to_rfc3339()
+    }
+}
+
+/// The first text matching `term`, cut to the match and some context on each side
+fn snippet(texts: &[String], term: &Regex) -> String {
+    texts
+        .iter()
+        .find_map(|text| {
+            let found = term.find(text)?;
+            let before: String = text[..found.start()].chars().rev().take(SNIPPET_CONTEXT).collect::<Vec<_>>().into_iter().rev().collect();
+            let after: String = text[found.end()..].chars().take(SNIPPET_CONTEXT).collect();
+            let start = if before.len() < found.start() { "…" } else { "" };
+            let end = if found.end() + after.len() < text.len() { "…" } else { "" };
+            let snippet = format!("{}{}{}{}{}", start, before, found.as_str(), after, end);
+            Some(snippet.split_whitespace().collect::<Vec<_>>().join(" "))
+        })
+        .unwrap_or_default()
+}
+
+/// Rounded to one decimal
+fn hours(minutes: i64) -> f64 {
+    (minutes as f64 / 6.0).round() / 10.0
+}
+
+/// Reject parameters an endpoint does not know, so that typos are not silently ignored
+fn check_params(params: &HashMap<String, String>, extra: &[&str]) -> Result<()> {
+    match params.keys().find(|name| !PERIOD_PARAMS.contains(&name.as_str()) && !extra.contains(&name.as_str())) {
+        Some(name) => Err(BadRequest(format!("Unknown query parameter '{}'", name)).into()),
+        None => Ok(()),
+    }
+}
+
+fn number_param(params: &HashMap<String, String>, name: &str) -> Result<Option<usize>> {
+    params
+        .get(name)
+        .map(|value| value.parse().map_err(|_| BadRequest(format!("{} must be a non-negative integer, got '{}'", name, value)).into()))
+        .transpose()
+}
+
+fn parse_query(query: &str) -> Result<HashMap<String, String>> {
+    query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (name, value) = pair.split_once('=').unwrap_or((pair, ""));
+            Ok((percent_decode(name)?, percent_decode(value)?))
+        })
+        .collect()
+}
+
+/// Decode `%XX` escapes and `+` as a space
+fn percent_decode(value: &str) -> Result<String> {
+    let invalid = || BadRequest(format!("Invalid escape in query '{}'", value));
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'%' => {
+                let hex = value.get(i + 1..i + 3).ok_or_else(invalid)?;
+                decoded.push(u8::from_str_radix(hex, 16).map_err(|_| invalid())?);
+                i += 3;
+            }
+            b'+' => {
+                decoded.push(b' ');
+                i += 1;
+            }
+            byte => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+    Ok(String::from_utf8(decoded).map_err(|_| invalid())?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sample::write_sample;
+    use chrono::FixedOffset;
+    use std::path::Path;
+    use tempfile::TempDir;
+    use tokio::io::AsyncReadExt;
+
+    fn server(dir: &Path) -> HttpServer {
+        let config = Config { min_messages: Some(1), ..Config::default() };
+        let schedule = TimezoneSchedule::fixed(FixedOffset::east_opt(0).unwrap());
+        HttpServer::new(vec![dir.to_path_buf()], JsonlParser::new(), config, schedule)
+    }
+
+    #[tokio::test]
+    async fn test_endpoints() {
+        let temp_dir = TempDir::new().unwrap();
+        write_sample(temp_dir.path()).unwrap();
+        let server = server(temp_dir.path());
+
+        let summary = server.handle("GET", "/summary").await;
+        assert_eq!(summary.status, 200);
+        assert_eq!((summary.body["total_sessions"].as_u64(), summary.body["projects"].as_u64()), (Some(3), Some(2)));
+        assert_eq!(server.results.len(), 1);
+
+        let projects = server.handle("GET", "/projects/").await.body;
+        let names: Vec<&str> = projects["projects"].as_array().unwrap().iter().map(|p| p["name"].as_str().unwrap()).collect();
+        assert_eq!(names.len(), 2);
+
+        let page = server.handle("GET", "/sessions?project=api&limit=1").await.body;
+        assert_eq!((page["total"].as_u64(), page["next_offset"].as_u64()), (Some(2), Some(1)));
+        let id = page["sessions"][0]["session_id"].as_str().unwrap().to_string();
+        let session = server.handle("GET", &format!("/sessions/{}", id)).await;
+        assert_eq!(session.status, 200);
+        assert_eq!(session.body["project"], "api");
+        assert!(session.body["tokens"]["requests"].as_u64().unwrap() > 0);
+
+        // Requests the client has to fix
+        assert_eq!(server.handle("GET", &format!("/sessions/{}", Uuid::new_v4())).await.status, 404);
+        assert_eq!(server.handle("GET", "/sessions/not-a-uuid").await.status, 400);
+        assert_eq!(server.handle("GET", "/summary?from=2025-13-01").await.status, 400);
+        assert_eq!(server.handle("GET", "/summary?days=7&from=2025-01-01").await.status, 400);
+        assert_eq!(server.handle("GET", "/projects?limit=1").await.status, 400);
+        assert_eq!(server.handle("GET", "/unknown").await.status, 404);
+        assert_eq!(server.handle("POST", "/summary").await.status, 405);
+
+        // Failures are logged, the answer does not tell which files were involved
+        let missing = temp_dir.path().join("missing");
+        let failed = self::server(&missing).handle("GET", "/summary").await;
+        assert_eq!(failed.status, 500);
+        assert!(!failed.body["error"].as_str().unwrap().contains(&*missing.to_string_lossy()));
+
+        let empty = server.handle("GET", "/summary?from=2000-01-01&to=2000-01-31").await.body;
+        assert_eq!(empty["total_sessions"], 0);
+        assert_eq!(empty["period"]["from"], "2000-01-01T00:00:00+00:00");
+    }
+
+    #[tokio::test]
+    async fn test_project_and_search() {
+        let temp_dir = TempDir::new().unwrap();
+        write_sample(temp_dir.path()).unwrap();
+        let server = server(temp_dir.path());
+
+        let project = server.handle("GET", "/projects/api").await;
+        assert_eq!(project.status, 200);
+        assert_eq!((project.body["name"].as_str(), project.body["sessions"].as_u64()), (Some("api"), Some(2)));
+        assert_eq!(project.body["recent_sessions"].as_array().unwrap().len(), 2);
+        assert!(project.body["tokens"]["requests"].as_u64().unwrap() > 0);
+        assert_eq!(server.handle("GET", "/projects/mobile").await.status, 404);
+        assert_eq!(server.handle("GET", "/projects/api?project=web").await.status, 400);
+
+        // Every word has to match, in any case and any text of the session
+        let found = server.handle("GET", "/search?q=NEGATIVE+offset").await.body;
+        assert_eq!(found["total"], 2);
+        assert_eq!(found["sessions"][0]["project"], "api");
+        assert!(found["sessions"][0]["snippet"].as_str().unwrap().contains("negative"));
+        let found = server.handle("GET", "/search?q=hook&limit=1").await.body;
+        assert_eq!((found["total"].as_u64(), found["sessions"].as_array().unwrap().len()), (Some(1), 1));
+        assert_eq!(server.handle("GET", "/search?q=offset+hook").await.body["total"], 0);
+        assert_eq!(server.handle("GET", "/search?q=+").await.status, 400);
+
+        let text = "x".repeat(100) + " the Needle here " + &"y".repeat(100);
+        let cut = snippet(&[text], &Regex::new("(?i)needle").unwrap());
+        assert!(cut.starts_with('…') && cut.ends_with('…') && cut.contains(" the Needle here "));
+    }
+
+    #[tokio::test]
+    async fn test_token() {
+        let temp_dir = TempDir::new().unwrap();
+        write_sample(temp_dir.path()).unwrap();
+        let listener = TcpListener::bind(("127.0.0.1", 0)).await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(Arc::new(server(temp_dir.path()).with_token(Some("s3cret".to_string()))).serve(listener));
+
+        let request = |authorization: &'static str| async move {
+            let mut stream = TcpStream::connect(address).await.unwrap();
+            let request = format!("GET /summary HTTP/1.1\r\n{}\r\n", authorization);
+            stream.write_all(request.as_bytes()).await.unwrap();
+            let mut response = String::new();
+            stream.read_to_string(&mut response).await.unwrap();
+            response
+        };
+        let denied = request("").await;
+        assert!(denied.starts_with("HTTP/1.1 401 Unauthorized\r\n"));
+        assert!(denied.contains("WWW-Authenticate: Bearer\r\n"));
+        assert!(request("Authorization: Bearer s3cre\r\n").await.starts_with("HTTP/1.1 401 "));
+        assert!(request("authorization: Bearer s3cret\r\n").await.starts_with("HTTP/1.1 200 OK\r\n"));
+
+        let blank = server(temp_dir.path()).with_token(Some(String::new()));
+        assert!(!blank.authorized(None));
+        assert!(!blank.authorized(Some("Bearer ")));
+    }
+
+    #[tokio::test]
+    async fn test_serve() {
+        let temp_dir = TempDir::new().unwrap();
+        write_sample(temp_dir.path()).unwrap();
+        let listener = TcpListener::bind(("127.0.0.1", 0)).await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(Arc::new(server(temp_dir.path())).serve(listener));
+
+        let mut stream = TcpStream::connect(address).await.unwrap();
+        stream.write_all(b"GET /summary?project=web HTTP/1.1\r\nHost: localhost\r\n\r\n").await.unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        let (head, body) = response.split_once("\r\n\r\n").unwrap();
+        assert!(head.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(head.contains(&format!("Content-Length: {}", body.len())));
+        let summary: Value = serde_json::from_str(body).unwrap();
+        assert_eq!(summary["total_sessions"], 1);
+    }
+
+    #[tokio::test]
+    async fn test_request_head_limit() {
+        let temp_dir = TempDir::new().unwrap();
+        write_sample(temp_dir.path()).unwrap();
+        let listener = TcpListener::bind(("127.0.0.1", 0)).await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(Arc::new(server(temp_dir.path())).serve(listener));
+
+        // A header line that never ends is cut off at the limit instead of buffered
+        let mut request = b"GET /summary HTTP/1.1\r\nX-Filler: ".to_vec();
+        request.resize(MAX_REQUEST_HEAD + 1, b'a');
+        let mut stream = TcpStream::connect(address).await.unwrap();
+        stream.write_all(&request).await.unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        assert!(response.starts_with("HTTP/1.1 400 Bad Request\r\n"));
+        assert!(response.contains("Request headers are too large"));
+    }
+
+    #[tokio::test]
+    async fn test_cors() {
+        let temp_dir = TempDir::new().unwrap();
+        write_sample(temp_dir.path()).unwrap();
+        let listener = TcpListener::bind(("127.0.0.1", 0)).await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let server = server(temp_dir.path()).with_allowed_origins(vec!["http://localhost:3000".to_string()]);
+        tokio::spawn(Arc::new(server).serve(listener));
+
+        let request = |request: &'static str| async move {
+            let mut stream = TcpStream::connect(address).await.unwrap();
+            stream.write_all(request.as_bytes()).await.unwrap();
+            let mut response = String::new();
+            stream.read_to_string(&mut response).await.unwrap();
+            response
+        };
+        // Pages from other origins, or no origin at all, get no CORS header
+        let other = request("GET /summary HTTP/1.1\r\nOrigin: https://evil.example\r\n\r\n").await;
+        assert!(other.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(!other.contains("Access-Control-Allow-Origin"));
+        assert!(!request("GET /summary HTTP/1.1\r\n\r\n").await.contains("Access-Control-Allow-Origin"));
+        assert!(request("OPTIONS /summary HTTP/1.1\r\nOrigin: https://evil.example\r\n\r\n").await.starts_with("HTTP/1.1 405 "));
+
+        let allowed = request("GET /summary HTTP/1.1\r\nOrigin: http://localhost:3000\r\n\r\n").await;
+        assert!(allowed.contains("Access-Control-Allow-Origin: http://localhost:3000\r\n"));
+        let preflight = request("OPTIONS /summary HTTP/1.1\r\nOrigin: http://localhost:3000\r\n\r\n").await;
+        assert!(preflight.starts_with("HTTP/1.1 204 No Content\r\n"));
+        assert!(preflight.contains("Access-Control-Allow-Headers: Authorization\r\n"));
+    }
+
+    #[tokio::test]
+    async fn test_metrics() {
+        let temp_dir = TempDir::new().unwrap();
+        write_sample(temp_dir.path()).unwrap();
+        let server = Arc::new(server(temp_dir.path()));
+
+        let metrics = server.handle("GET", "/metrics").await;
+        assert_eq!(metrics.status, 200);
+        assert!(metrics.text.unwrap().contains("claude_work_sessions 3\n"));
+        assert_eq!(server.handle("GET", "/metrics?days=7").await.status, 400);
+
+        // Scrapes between refreshes answer with the metrics of the last one
+        write_sample(&temp_dir.path().join("more")).unwrap();
+        assert!(server.handle("GET", "/metrics").await.text.unwrap().contains("claude_work_sessions 3\n"));
+        server.refresh_metrics().await.unwrap();
+        assert!(server.handle("GET", "/metrics").await.text.unwrap().contains("claude_work_sessions 6\n"));
+
+        let listener = TcpListener::bind(("127.0.0.1", 0)).await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(Arc::clone(&server).serve(listener));
+        let mut stream = TcpStream::connect(address).await.unwrap();
+        stream.write_all(b"GET /metrics HTTP/1.1\r\n\r\n").await.unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        assert!(response.contains(&format!("Content-Type: {}\r\n", METRICS_CONTENT_TYPE)));
+        assert!(response.contains("\r\n\r\n# HELP claude_work_sessions "));
+    }
+
+    #[test]
+    fn test_parse_query() {
+        let params = parse_query("project=my%20app&from=2025-07-01&empty&to=a+b").unwrap();
+        assert_eq!(params["project"], "my app");
+        assert_eq!(params["empty"], "");
+        assert_eq!(params["to"], "a b");
+        assert!(parse_query("project=%zz").is_err());
+        assert!(parse_query("project=%e3").is_err());
+    }
+}
@@ -0,0 +1,433 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration, Utc};
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use uuid::Uuid;
+use walkdir::WalkDir;
+
+use crate::models::{ClaudeLogEntry, EntryType, MessageContent, MessageContentVariant, UsageInfo};
+use crate::scanner::ProjectScanner;
+
+/// Assistant of the entries of Claude Code's own logs
+pub const CLAUDE_CODE: &str = "claude-code";
+
+/// Another assistant whose history can be imported
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImportSource {
+    /// Codex CLI session logs (`~/.codex/sessions/**/rollout-*.jsonl`)
+    Codex,
+    /// GitHub Copilot chats exported from VS Code ("Chat: Export Chat...")
+    Copilot,
+    /// Cursor chats exported in the same JSON format as VS Code
+    Cursor,
+}
+
+impl ImportSource {
+    pub const ALL: [ImportSource; 3] = [ImportSource::Codex, ImportSource::Copilot, ImportSource::Cursor];
+
+    /// The name entries are tagged with
+    pub fn name(&self) -> &'static str {
+        match self {
+            ImportSource::Codex => "codex",
+            ImportSource::Copilot => "copilot",
+            ImportSource::Cursor => "cursor",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|source| source.name() == name)
+    }
+
+    /// Extension of the files a directory is searched for
+    fn extension(&self) -> &'static str {
+        match self {
+            ImportSource::Codex => "jsonl",
+            ImportSource::Copilot | ImportSource::Cursor => "json",
+        }
+    }
+
+    /// Where the assistant keeps its history, when it keeps it in files
+    pub fn default_path(&self) -> Option<PathBuf> {
+        match self {
+            ImportSource::Codex => dirs::home_dir().map(|home| home.join(".codex").join("sessions")),
+            ImportSource::Copilot | ImportSource::Cursor => None,
+        }
+    }
+}
+
+/// Assistant an entry was written with
+pub fn source_of(entry: &ClaudeLogEntry) -> &str {
+    entry.source.as_deref().unwrap_or(CLAUDE_CODE)
+}
+
+/// `<data dir>/claude-work-analysis/imported`
+pub fn default_import_dir() -> Option<PathBuf> {
+    dirs::data_dir().map(|dir| dir.join("claude-work-analysis").join("imported"))
+}
+
+/// Files, sessions and entries [`import_history`] wrote
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ImportCounts {
+    pub files: usize,
+    pub sessions: usize,
+    pub entries: usize,
+}
+
+/// Convert the history of another assistant under `paths` into Claude Code logs in `import_dir`
+///
+/// Directories are searched for the source's files. Chat exports do not record
+/// the directory worked in, so their sessions belong to the project of `cwd`.
+/// Each session is written to its own file, replaced on every import, so
+/// importing the same history again changes nothing.
+pub fn import_history(source: ImportSource, paths: &[PathBuf], cwd: &str, import_dir: &Path) -> Result<ImportCounts> {
+    let mut counts = ImportCounts::default();
+    for path in paths {
+        for file in files_of(path, source.extension())? {
+            let content = std::fs::read_to_string(&file).with_context(|| format!("Failed to read {}", file.display()))?;
+            let entries = match source {
+                ImportSource::Codex => parse_codex_session(&content),
+                ImportSource::Copilot | ImportSource::Cursor => parse_chat_export(&content, source, cwd),
+            }
+            .with_context(|| format!("Failed to import {}", file.display()))?;
+            counts.files += 1;
+            counts.entries += entries.len();
+            counts.sessions += write_sessions(&entries, &format!("{}-", source.name()), import_dir)?;
+        }
+    }
+    Ok(counts)
+}
+
+/// `path` itself, or the files with `extension` under it
+fn files_of(path: &Path, extension: &str) -> Result<Vec<PathBuf>> {
+    if !path.is_dir() {
+        return Ok(vec![path.to_path_buf()]);
+    }
+    let mut files = Vec::new();
+    for entry in WalkDir::new(path) {
+        let entry = entry.with_context(|| format!("Failed to read {}", path.display()))?;
+        if entry.file_type().is_file() && entry.path().extension().is_some_and(|ext| ext == extension) {
+            files.push(entry.into_path());
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Write the entries of each session to `<dir>/<encoded cwd>/<file_prefix><session>.jsonl`,
+/// the layout of a projects directory; returns the number of sessions
+pub(crate) fn write_sessions(entries: &[ClaudeLogEntry], file_prefix: &str, dir: &Path) -> Result<usize> {
+    let mut sessions: BTreeMap<Uuid, Vec<&ClaudeLogEntry>> = BTreeMap::new();
+    for entry in entries {
+        sessions.entry(entry.session_id).or_default().push(entry);
+    }
+    for (session_id, entries) in &sessions {
+        let project_dir = dir.join(ProjectScanner::encode_project_path(&entries[0].cwd));
+        std::fs::create_dir_all(&project_dir).with_context(|| format!("Failed to create {}", project_dir.display()))?;
+        let mut content = String::new();
+        for entry in entries {
+            content.push_str(&serde_json::to_string(entry)?);
+            content.push('\n');
+        }
+        let path = project_dir.join(format!("{}{}.jsonl", file_prefix, session_id));
+        std::fs::write(&path, content).with_context(|| format!("Failed to write {}", path.display()))?;
+    }
+    Ok(sessions.len())
+}
+
+/// An imported message as a log entry replying to `parent`
+///
+/// Entry uuids are derived from the session and the message's position, so a
+/// session imported twice has the same entries.
+#[allow(clippy::too_many_arguments)]
+fn entry(
+    source: ImportSource,
+    session_id: Uuid,
+    index: usize,
+    parent: Option<Uuid>,
+    cwd: &str,
+    version: &str,
+    role: EntryType,
+    text: String,
+    timestamp: DateTime<Utc>,
+    model: Option<String>,
+) -> ClaudeLogEntry {
+    ClaudeLogEntry {
+        parent_uuid: parent,
+        is_sidechain: false,
+        user_type: "external".to_string(),
+        cwd: cwd.to_string(),
+        session_id,
+        version: version.to_string(),
+        entry_type: role,
+        message: MessageContent {
+            role: match role {
+                EntryType::User => "user",
+                EntryType::Assistant => "assistant",
+            }
+            .to_string(),
+            content: MessageContentVariant::String(text),
+            id: None,
+            message_type: None,
+            model,
+            stop_reason: None,
+            stop_sequence: None,
+            usage: None,
+        },
+        uuid: Uuid::new_v5(&session_id, index.to_string().as_bytes()),
+        timestamp,
+        request_id: None,
+        tool_use_result: None,
+        source: Some(source.name().to_string()),
+    }
+}
+
+/// A line of a Codex CLI session log
+#[derive(Deserialize)]
+struct CodexLine {
+    timestamp: DateTime<Utc>,
+    #[serde(rename = "type")]
+    line_type: String,
+    #[serde(default)]
+    payload: Value,
+}
+
+/// Prompts Codex CLI adds to a session on its own
+const CODEX_CONTEXT_PREFIXES: [&str; 2] = ["<environment_context>", "<user_instructions>"];
+
+/// The messages of a Codex CLI session log
+///
+/// The token usage Codex reports after each turn is attached to the last
+/// response of the turn. Tool calls and reasoning are not imported; lines
+/// that cannot be read are skipped.
+pub fn parse_codex_session(content: &str) -> Result<Vec<ClaudeLogEntry>> {
+    let mut session: Option<(Uuid, String, String)> = None;
+    let mut model = None;
+    let mut entries: Vec<ClaudeLogEntry> = Vec::new();
+    // Response the next token count belongs to
+    let mut last_response: Option<usize> = None;
+
+    for line in content.lines().filter(|line| !line.trim().is_empty()) {
+        let Ok(line) = serde_json::from_str::<CodexLine>(line) else {
+            continue;
+        };
+        let payload = &line.payload;
+        match line.line_type.as_str() {
+            "session_meta" => {
+                let id = payload["id"].as_str().and_then(|id| Uuid::parse_str(id).ok());
+                let cwd = payload["cwd"].as_str();
+                if let (Some(id), Some(cwd)) = (id, cwd) {
+                    let version = payload["cli_version"].as_str().unwrap_or_default();
+                    session = Some((id, cwd.to_string(), version.to_string()));
+                }
+            }
+            "turn_context" => {
+                if let Some(name) = payload["model"].as_str() {
+                    model = Some(name.to_string());
+                }
+            }
+            "response_item" if payload["type"] == "message" => {
+                let Some((session_id, cwd, version)) = &session else {
+                    continue;
+                };
+                let role = match payload["role"].as_str() {
+                    Some("user") => EntryType::User,
+                    Some("assistant") => EntryType::Assistant,
+                    _ => continue,
+                };
+                let text = payload["content"]
+                    .as_array()
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|block| block["text"].as_str())
+                    .collect::<Vec<_>>()
+                    .join("\n");
+                if text.trim().is_empty() || CODEX_CONTEXT_PREFIXES.iter().any(|prefix| text.trim_start().starts_with(prefix)) {
+                    continue;
+                }
+                let model = matches!(role, EntryType::Assistant).then(|| model.clone()).flatten();
+                let parent = entries.last().map(|entry| entry.uuid);
+                let entry = entry(ImportSource::Codex, *session_id, entries.len(), parent, cwd, version, role, text, line.timestamp, model);
+                if matches!(role, EntryType::Assistant) {
+                    last_response = Some(entries.len());
+                }
+                entries.push(entry);
+            }
+            "event_msg" if payload["type"] == "token_count" => {
+                let usage = &payload["info"]["last_token_usage"];
+                let Some(index) = last_response.take() else {
+                    continue;
+                };
+                let count = |field: &str| usage[field].as_u64().map(|count| count.min(u32::MAX as u64) as u32);
+                let (Some(input), Some(output)) = (count("input_tokens"), count("output_tokens")) else {
+                    continue;
+                };
+                // Codex counts cached input within the input tokens
+                let cached = count("cached_input_tokens").unwrap_or(0).min(input);
+                entries[index].message.usage = Some(UsageInfo {
+                    input_tokens: Some(input - cached),
+                    output_tokens: Some(output),
+                    cache_creation_input_tokens: None,
+                    cache_read_input_tokens: Some(cached),
+                    service_tier: None,
+                });
+            }
+            _ => {}
+        }
+    }
+
+    if session.is_none() {
+        return Err(anyhow::anyhow!("Not a Codex CLI session log: no session_meta line"));
+    }
+    Ok(entries)
+}
+
+/// A chat exported from VS Code or Cursor
+#[derive(Deserialize)]
+struct ChatExport {
+    #[serde(rename = "sessionId")]
+    session_id: Option<String>,
+    requests: Vec<ChatRequest>,
+}
+
+#[derive(Deserialize)]
+struct ChatRequest {
+    message: ChatMessage,
+    #[serde(default)]
+    response: Vec<Value>,
+    /// Milliseconds since the epoch; missing in older exports
+    timestamp: Option<i64>,
+    #[serde(rename = "modelId")]
+    model_id: Option<String>,
+    #[serde(default)]
+    result: Value,
+}
+
+#[derive(Deserialize)]
+struct ChatMessage {
+    text: String,
+}
+
+/// The prompts and responses of an exported chat, in the project of `cwd`
+///
+/// A response is timed when the request took its elapsed time to answer.
+/// Requests without a timestamp cannot be placed in time and are skipped.
+pub fn parse_chat_export(content: &str, source: ImportSource, cwd: &str) -> Result<Vec<ClaudeLogEntry>> {
+    let export: ChatExport = serde_json::from_str(content).context("Not a chat export: expected an object with requests")?;
+    let requests: Vec<(DateTime<Utc>, &ChatRequest)> = export
+        .requests
+        .iter()
+        .filter_map(|request| Some((DateTime::from_timestamp_millis(request.timestamp?)?, request)))
+        .collect();
+    let Some((first_time, _)) = requests.first() else {
+        return Ok(Vec::new());
+    };
+    // Exports without an id are told apart by their source and first request
+    let session_id = export
+        .session_id
+        .as_deref()
+        .and_then(|id| Uuid::parse_str(id).ok())
+        .unwrap_or_else(|| Uuid::new_v5(&Uuid::NAMESPACE_OID, format!("{}:{}", source.name(), first_time.timestamp_millis()).as_bytes()));
+
+    let mut entries: Vec<ClaudeLogEntry> = Vec::new();
+    for (time, request) in requests {
+        let parent = entries.last().map(|entry| entry.uuid);
+        entries.push(entry(source, session_id, entries.len(), parent, cwd, "", EntryType::User, request.message.text.clone(), time, None));
+
+        let text = request.response.iter().filter_map(|part| part["value"].as_str()).collect::<String>();
+        if text.trim().is_empty() {
+            continue;
+        }
+        let elapsed = request.result["timings"]["totalElapsed"].as_i64().unwrap_or(0);
+        let parent = entries.last().map(|entry| entry.uuid);
+        let answered = time + Duration::milliseconds(elapsed);
+        entries.push(entry(source, session_id, entries.len(), parent, cwd, "", EntryType::Assistant, text, answered, request.model_id.clone()));
+    }
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyzer::WorkAnalyzer;
+    use crate::parser::JsonlParser;
+    use tempfile::TempDir;
+
+    const CODEX_SESSION: &str = r#"{"timestamp":"2025-06-10T01:00:00Z","type":"session_meta","payload":{"id":"0197f1c2-0000-7000-8000-000000000001","timestamp":"2025-06-10T01:00:00Z","cwd":"/home/me/api","cli_version":"0.20.0"}}
+{"timestamp":"2025-06-10T01:00:00Z","type":"response_item","payload":{"type":"message","role":"user","content":[{"type":"input_text","text":"<environment_context>\n  <cwd>/home/me/api</cwd>\n</environment_context>"}]}}
+{"timestamp":"2025-06-10T01:00:01Z","type":"turn_context","payload":{"cwd":"/home/me/api","model":"gpt-5"}}
+{"timestamp":"2025-06-10T01:00:05Z","type":"response_item","payload":{"type":"message","role":"user","content":[{"type":"input_text","text":"Fix the failing login test"}]}}
+{"timestamp":"2025-06-10T01:00:20Z","type":"response_item","payload":{"type":"reasoning","summary":[]}}
+{"timestamp":"2025-06-10T01:01:00Z","type":"response_item","payload":{"type":"message","role":"assistant","content":[{"type":"output_text","text":"The test expected the old error message; I updated it."}]}}
+{"timestamp":"2025-06-10T01:01:01Z","type":"event_msg","payload":{"type":"token_count","info":{"last_token_usage":{"input_tokens":1200,"cached_input_tokens":1000,"output_tokens":80}}}}
+not json
+"#;
+
+    #[test]
+    fn test_parse_codex_session() {
+        let entries = parse_codex_session(CODEX_SESSION).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert!(matches!(entries[0].entry_type, EntryType::User));
+        assert_eq!(entries[0].message.content.text(), "Fix the failing login test");
+        assert_eq!(entries[0].cwd, "/home/me/api");
+        assert_eq!(entries[1].parent_uuid, Some(entries[0].uuid));
+        assert_eq!(entries[1].message.model.as_deref(), Some("gpt-5"));
+        let usage = entries[1].message.usage.as_ref().unwrap();
+        assert_eq!((usage.input_tokens, usage.cache_read_input_tokens, usage.output_tokens), (Some(200), Some(1000), Some(80)));
+        assert!(entries.iter().all(|entry| source_of(entry) == "codex"));
+        // Importing again gives the same entries
+        assert_eq!(parse_codex_session(CODEX_SESSION).unwrap()[1].uuid, entries[1].uuid);
+
+        assert!(parse_codex_session("{\"timestamp\":\"2025-06-10T01:00:00Z\",\"type\":\"event_msg\"}\n").is_err());
+    }
+
+    #[test]
+    fn test_parse_chat_export() {
+        let export = r#"{
+            "requesterUsername": "me",
+            "responderUsername": "GitHub Copilot",
+            "requests": [
+                {"message": {"text": "Why is the build slow?"}, "response": [{"value": "Incremental compilation "}, {"kind": "inlineReference"}, {"value": "is disabled."}], "timestamp": 1749517200000, "modelId": "copilot/gpt-4.1", "result": {"timings": {"totalElapsed": 9000}}},
+                {"message": {"text": "Without a time"}, "response": [{"value": "Skipped"}]},
+                {"message": {"text": "Thanks"}, "response": [], "timestamp": 1749517500000}
+            ]
+        }"#;
+        let entries = parse_chat_export(export, ImportSource::Copilot, "/home/me/web").unwrap();
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries[1].message.content.text(), "Incremental compilation is disabled.");
+        assert_eq!(entries[1].timestamp - entries[0].timestamp, Duration::seconds(9));
+        assert_eq!(entries[1].message.model.as_deref(), Some("copilot/gpt-4.1"));
+        assert_eq!(entries[2].message.content.text(), "Thanks");
+        assert!(entries.iter().all(|entry| entry.session_id == entries[0].session_id && entry.cwd == "/home/me/web"));
+        assert_eq!(parse_chat_export(export, ImportSource::Copilot, "/home/me/web").unwrap()[0].session_id, entries[0].session_id);
+        assert_ne!(parse_chat_export(export, ImportSource::Cursor, "/home/me/web").unwrap()[0].session_id, entries[0].session_id);
+    }
+
+    #[tokio::test]
+    async fn test_imported_logs_are_analyzed_by_assistant() {
+        let temp_dir = TempDir::new().unwrap();
+        let codex_dir = temp_dir.path().join("codex").join("2025").join("06").join("10");
+        std::fs::create_dir_all(&codex_dir).unwrap();
+        std::fs::write(codex_dir.join("rollout-2025-06-10T01-00-00.jsonl"), CODEX_SESSION).unwrap();
+        let import_dir = temp_dir.path().join("imported");
+
+        let counts = import_history(ImportSource::Codex, &[temp_dir.path().join("codex")], "/unused", &import_dir).unwrap();
+        assert_eq!(counts, ImportCounts { files: 1, sessions: 1, entries: 2 });
+        // Imported again, the session's file is replaced rather than duplicated
+        import_history(ImportSource::Codex, &[temp_dir.path().join("codex")], "/unused", &import_dir).unwrap();
+
+        let files = ProjectScanner::new().scan_projects(&import_dir).unwrap();
+        assert_eq!(files.len(), 1);
+        assert!(files[0].starts_with(import_dir.join("-home-me-api")));
+        let entries = JsonlParser::new().parse_files(&files).await.unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[1].message.usage.as_ref().unwrap().output_tokens, Some(80));
+
+        let analysis = WorkAnalyzer::new().with_min_messages(1).analyze_entries(&entries).unwrap();
+        let assistants = analysis.assistants.unwrap();
+        assert_eq!(assistants.len(), 1);
+        assert_eq!((assistants[0].name.as_str(), assistants[0].sessions, assistants[0].messages), ("codex", 1, 2));
+        assert_eq!(assistants[0].tokens.usage.output_tokens, 80);
+    }
+}
@@ -0,0 +1,105 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use std::collections::HashSet;
+use std::path::PathBuf;
+use uuid::Uuid;
+
+use crate::parser::JsonlParser;
+use crate::scanner::ProjectScanner;
+
+/// Counts from one pass over every log file
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct IndexStats {
+    pub files: usize,
+    /// Files parsed in this pass because they were new or had changed
+    pub parsed_files: usize,
+    /// Files that could not be read
+    pub failed_files: usize,
+    /// Files left unread because refresh was off and they were not in the cache
+    pub uncached_files: usize,
+    pub projects: usize,
+    pub sessions: usize,
+    pub entries: usize,
+    pub first_entry: Option<DateTime<Utc>>,
+    pub last_entry: Option<DateTime<Utc>>,
+}
+
+impl IndexStats {
+    /// Files whose cached parse result was still current
+    pub fn up_to_date_files(&self) -> usize {
+        self.files - self.parsed_files - self.failed_files - self.uncached_files
+    }
+}
+
+/// Parse every log file under `projects_dirs` into the parser's cache
+///
+/// Later runs, including the MCP server, then read unchanged files from the
+/// cache instead of parsing them again.
+pub async fn build_index(parser: &JsonlParser, projects_dirs: &[PathBuf]) -> Result<IndexStats> {
+    let scanner = ProjectScanner::new();
+    // A project synced into several roots counts once
+    let projects: HashSet<_> = scanner
+        .get_all_project_directories(projects_dirs)?
+        .into_iter()
+        .filter_map(|dir| dir.file_name().map(|name| name.to_os_string()))
+        .collect();
+    let mut stats = IndexStats {
+        projects: projects.len(),
+        ..IndexStats::default()
+    };
+    let mut sessions: HashSet<Uuid> = HashSet::new();
+
+    for path in scanner.scan_all_projects(projects_dirs)? {
+        stats.files += 1;
+        let entries = match parser.parse_file_with_cache_status(&path).await {
+            Ok((entries, parse_stats, cached)) => {
+                if parse_stats.uncached_files > 0 {
+                    stats.uncached_files += 1;
+                } else if !cached {
+                    stats.parsed_files += 1;
+                }
+                entries
+            }
+            Err(e) => {
+                eprintln!("Failed to parse {}: {}", path.display(), e);
+                stats.failed_files += 1;
+                continue;
+            }
+        };
+
+        stats.entries += entries.len();
+        for entry in &entries {
+            sessions.insert(entry.session_id);
+            stats.first_entry = Some(stats.first_entry.map_or(entry.timestamp, |t| t.min(entry.timestamp)));
+            stats.last_entry = Some(stats.last_entry.map_or(entry.timestamp, |t| t.max(entry.timestamp)));
+        }
+    }
+
+    stats.sessions = sessions.len();
+    Ok(stats)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cache::ParseCache;
+    use crate::sample::write_sample;
+    use tempfile::TempDir;
+
+    #[tokio::test]
+    async fn test_second_build_reads_the_cache() {
+        let temp_dir = TempDir::new().unwrap();
+        let projects_dirs = [temp_dir.path().join("projects")];
+        write_sample(&projects_dirs[0]).unwrap();
+        let parser = JsonlParser::new().with_cache(ParseCache::new(temp_dir.path().join("cache")));
+
+        let first = build_index(&parser, &projects_dirs).await.unwrap();
+        assert_eq!((first.files, first.parsed_files, first.up_to_date_files()), (3, 3, 0));
+        assert_eq!((first.projects, first.sessions, first.entries), (2, 3, 18));
+        assert!(first.first_entry < first.last_entry);
+
+        let second = build_index(&parser, &projects_dirs).await.unwrap();
+        assert_eq!((second.parsed_files, second.up_to_date_files()), (0, 3));
+        assert_eq!(second.entries, first.entries);
+    }
+}
@@ -0,0 +1,232 @@
+use anyhow::{Context, Result};
+use chrono::Duration;
+use regex::{Regex, RegexBuilder};
+use serde::Deserialize;
+use std::collections::BTreeSet;
+use std::path::Path;
+
+use crate::models::{EntryType, InitiativeRollup, MessageContentVariant, TokenAnalysis, TokenStats, WorkSession};
+use crate::scanner::{ProjectNames, ProjectScanner};
+
+/// Outcomes listed per initiative
+const MAX_OUTCOMES: usize = 5;
+
+/// An `[[initiatives]]` table of the config file
+///
+/// A session belongs to the initiative when its project is one of `projects`
+/// and its summary or prompts match one of `keywords`; an empty list matches
+/// any session.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct InitiativeConfig {
+    pub name: String,
+    /// Project names, e.g. `web` for `~/src/web`
+    pub projects: Vec<String>,
+    /// Case-insensitive regular expressions
+    pub keywords: Vec<String>,
+}
+
+/// A configured initiative with its keyword patterns compiled
+#[derive(Debug, Clone)]
+pub struct Initiative {
+    name: String,
+    projects: Vec<String>,
+    keywords: Vec<Regex>,
+}
+
+impl Initiative {
+    pub fn new(config: &InitiativeConfig) -> Result<Self> {
+        if config.name.trim().is_empty() {
+            return Err(anyhow::anyhow!("Every initiative needs a name"));
+        }
+        if config.projects.is_empty() && config.keywords.is_empty() {
+            return Err(anyhow::anyhow!("Initiative '{}' needs projects or keywords", config.name));
+        }
+        let keywords = config
+            .keywords
+            .iter()
+            .map(|pattern| {
+                RegexBuilder::new(pattern)
+                    .case_insensitive(true)
+                    .build()
+                    .with_context(|| format!("Invalid keyword pattern '{}' of initiative '{}'", pattern, config.name))
+            })
+            .collect::<Result<_>>()?;
+
+        Ok(Self {
+            name: config.name.clone(),
+            projects: config.projects.iter().map(|project| project.to_lowercase()).collect(),
+            keywords,
+        })
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Whether the session is part of this initiative
+    pub fn matches(&self, session: &WorkSession) -> bool {
+        if !self.projects.is_empty() {
+            let project = ProjectScanner::extract_project_name(Path::new(&session.project_path))
+                .unwrap_or_else(|| session.project_path.clone())
+                .to_lowercase();
+            if !self.projects.contains(&project) {
+                return false;
+            }
+        }
+        self.keywords.is_empty() || session_texts(session).any(|text| self.keywords.iter().any(|keyword| keyword.is_match(&text)))
+    }
+}
+
+/// Summary and user prompts of a session, the texts keywords are looked for in
+fn session_texts(session: &WorkSession) -> impl Iterator<Item = String> + '_ {
+    let summary = session.summary.iter().flat_map(|summary| {
+        std::iter::once(&summary.overall_summary)
+            .chain(&summary.main_topics)
+            .chain(&summary.technologies_mentioned)
+            .chain(&summary.key_discussions)
+            .chain(&summary.problems_addressed)
+            .chain(&summary.solutions_proposed)
+            .cloned()
+    });
+    let prompts = session
+        .entries
+        .iter()
+        .filter(|entry| matches!(entry.entry_type, EntryType::User))
+        .map(|entry| match &entry.message.content {
+            MessageContentVariant::String(text) => text.clone(),
+            MessageContentVariant::Array(blocks) => {
+                blocks.iter().filter_map(|block| block.text.as_deref()).collect::<Vec<_>>().join(" ")
+            }
+        });
+    summary.chain(prompts)
+}
+
+/// Hours, sessions and outcomes per initiative, in the configured order
+///
+/// Each session counts toward the first initiative it matches, so the rollups
+/// add up to at most the totals of the analysis. Initiatives without sessions
+/// are listed too. Projects are listed by their names in `project_names`.
+pub fn roll_up(
+    initiatives: &[Initiative],
+    sessions: &[WorkSession],
+    tokens: &TokenAnalysis,
+    project_names: &ProjectNames,
+) -> Vec<InitiativeRollup> {
+    let mut rollups: Vec<InitiativeRollup> = initiatives
+        .iter()
+        .map(|initiative| InitiativeRollup {
+            name: initiative.name.clone(),
+            sessions: 0,
+            messages: 0,
+            work_time: Duration::zero(),
+            projects: Vec::new(),
+            tokens: TokenStats::default(),
+            outcomes: Vec::new(),
+        })
+        .collect();
+    let mut projects: Vec<BTreeSet<String>> = vec![BTreeSet::new(); initiatives.len()];
+    let session_tokens = tokens.stats_by_session();
+
+    let mut sorted: Vec<&WorkSession> = sessions.iter().collect();
+    sorted.sort_by_key(|session| session.start_time);
+    for session in sorted {
+        let Some(index) = initiatives.iter().position(|initiative| initiative.matches(session)) else {
+            continue;
+        };
+        let rollup = &mut rollups[index];
+        rollup.sessions += 1;
+        rollup.messages += session.total_messages;
+        rollup.work_time += session.end_time - session.start_time;
+        if let Some(stats) = session_tokens.get(&(session.session_id, session.start_time)) {
+            rollup.tokens.merge(stats);
+        }
+        projects[index].insert(project_names.name(&session.project_path));
+        // The latest sessions' solutions are the most telling
+        if let Some(summary) = &session.summary {
+            for solution in &summary.solutions_proposed {
+                if !rollup.outcomes.contains(solution) {
+                    rollup.outcomes.insert(0, solution.clone());
+                }
+            }
+            rollup.outcomes.truncate(MAX_OUTCOMES);
+        }
+    }
+
+    for (rollup, projects) in rollups.iter_mut().zip(projects) {
+        rollup.projects = projects.into_iter().collect();
+    }
+    rollups
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::SessionSummary;
+    use chrono::{TimeZone, Utc};
+    use uuid::Uuid;
+
+    fn session(project: &str, hour: i64, topic: &str, solution: &str) -> WorkSession {
+        let start_time = Utc.with_ymd_and_hms(2025, 7, 1, 0, 0, 0).unwrap() + Duration::hours(hour);
+        WorkSession {
+            session_id: Uuid::new_v4(),
+            project_path: format!("/home/me/{}", project),
+            start_time,
+            end_time: start_time + Duration::minutes(30),
+            entries: Vec::new(),
+            total_messages: 4,
+            user_messages: 2,
+            assistant_messages: 2,
+            summary: Some(SessionSummary {
+                main_topics: vec![topic.to_string()],
+                topic_mentions: std::collections::HashMap::new(),
+                key_discussions: Vec::new(),
+                technologies_mentioned: Vec::new(),
+                problems_addressed: Vec::new(),
+                problem_confidence: std::collections::HashMap::new(),
+                solutions_proposed: vec![solution.to_string()],
+                learning_moments: Vec::new(),
+                overall_summary: String::new(),
+            }),
+            merged_session_ids: Vec::new(),
+        }
+    }
+
+    fn initiative(name: &str, projects: &[&str], keywords: &[&str]) -> Initiative {
+        Initiative::new(&InitiativeConfig {
+            name: name.to_string(),
+            projects: projects.iter().map(|project| project.to_string()).collect(),
+            keywords: keywords.iter().map(|keyword| keyword.to_string()).collect(),
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_roll_up_initiatives() {
+        let sessions = vec![
+            session("web", 0, "checkout form", "validate card numbers"),
+            session("api", 1, "Payment intents", "retry failed charges"),
+            session("api", 2, "logging", "structured logs"),
+            session("docs", 3, "checkout guide", "add screenshots"),
+        ];
+        let initiatives = vec![
+            initiative("Checkout", &["web", "API"], &["checkout|payment"]),
+            initiative("Platform", &["api"], &[]),
+            initiative("Unused", &["mobile"], &[]),
+        ];
+
+        let rollups = roll_up(&initiatives, &sessions, &TokenAnalysis::default(), &ProjectNames::default());
+        let checkout = &rollups[0];
+        assert_eq!((checkout.sessions, checkout.messages), (2, 8));
+        assert_eq!(checkout.work_time, Duration::minutes(60));
+        assert_eq!(checkout.projects, ["api", "web"]);
+        assert_eq!(checkout.outcomes, ["retry failed charges", "validate card numbers"]);
+        // The payment session counts once, toward the first initiative it matches
+        assert_eq!(rollups[1].sessions, 1);
+        assert_eq!(rollups[2].sessions, 0);
+
+        assert!(Initiative::new(&InitiativeConfig { name: "Empty".to_string(), ..Default::default() }).is_err());
+        let invalid = InitiativeConfig { name: "Bad".to_string(), keywords: vec!["(".to_string()], ..Default::default() };
+        assert!(Initiative::new(&invalid).unwrap_err().to_string().contains("Invalid keyword pattern '('"));
+    }
+}
@@ -0,0 +1,136 @@
+use anyhow::Result;
+use serde::Deserialize;
+
+/// `[insights]` of the config file: when each recommendation of the report is
+/// given, and whether it is given at all
+///
+/// ```toml
+/// [insights.short_sessions]
+/// max_average_minutes = 25
+/// [insights.single_project]
+/// enabled = false
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct InsightRules {
+    /// "Short Sessions Detected" when the average session is shorter
+    pub short_sessions: ShortSessionsRule,
+    /// "Long Sessions Detected" when the average session is longer
+    pub long_sessions: LongSessionsRule,
+    /// "High Project Diversity" when more projects were worked on
+    pub project_diversity: ProjectDiversityRule,
+    /// "Single Project Focus" when all sessions were in one project
+    pub single_project: InsightRule,
+    /// Advice on the most frequent activity type (debugging, learning or coding)
+    pub top_activity: InsightRule,
+    /// "Overall" when no other recommendation applies
+    pub overall: InsightRule,
+}
+
+impl InsightRules {
+    /// Reject thresholds no analysis could meet sensibly
+    pub fn validate(&self) -> Result<()> {
+        if self.short_sessions.max_average_minutes <= 0 {
+            return Err(anyhow::anyhow!("insights.short_sessions.max_average_minutes must be a positive number of minutes"));
+        }
+        if self.long_sessions.min_average_minutes <= 0 {
+            return Err(anyhow::anyhow!("insights.long_sessions.min_average_minutes must be a positive number of minutes"));
+        }
+        if self.short_sessions.enabled
+            && self.long_sessions.enabled
+            && self.short_sessions.max_average_minutes > self.long_sessions.min_average_minutes
+        {
+            return Err(anyhow::anyhow!(
+                "insights.short_sessions.max_average_minutes ({}) must not exceed insights.long_sessions.min_average_minutes ({})",
+                self.short_sessions.max_average_minutes,
+                self.long_sessions.min_average_minutes
+            ));
+        }
+        if self.project_diversity.max_projects == 0 {
+            return Err(anyhow::anyhow!("insights.project_diversity.max_projects must be at least 1"));
+        }
+        Ok(())
+    }
+}
+
+/// A recommendation without a threshold
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct InsightRule {
+    pub enabled: bool,
+}
+
+impl Default for InsightRule {
+    fn default() -> Self {
+        Self { enabled: true }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct ShortSessionsRule {
+    pub enabled: bool,
+    /// Average session length in minutes below which sessions are too short
+    pub max_average_minutes: i64,
+}
+
+impl Default for ShortSessionsRule {
+    fn default() -> Self {
+        Self { enabled: true, max_average_minutes: 15 }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct LongSessionsRule {
+    pub enabled: bool,
+    /// Average session length in minutes above which sessions are too long
+    pub min_average_minutes: i64,
+}
+
+impl Default for LongSessionsRule {
+    fn default() -> Self {
+        Self { enabled: true, min_average_minutes: 120 }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct ProjectDiversityRule {
+    pub enabled: bool,
+    /// Projects that can be worked on before the work is too fragmented
+    pub max_projects: usize,
+}
+
+impl Default for ProjectDiversityRule {
+    fn default() -> Self {
+        Self { enabled: true, max_projects: 5 }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_validate() {
+        let rules: InsightRules = toml::from_str(
+            "[short_sessions]\nmax_average_minutes = 30\n[project_diversity]\nenabled = false\n",
+        )
+        .unwrap();
+        assert_eq!(rules.short_sessions, ShortSessionsRule { enabled: true, max_average_minutes: 30 });
+        assert_eq!(rules.long_sessions, LongSessionsRule::default());
+        assert!(!rules.project_diversity.enabled);
+        assert_eq!(rules.project_diversity.max_projects, 5);
+        assert!(rules.overall.enabled);
+        rules.validate().unwrap();
+
+        let rules: InsightRules = toml::from_str("[short_sessions]\nmax_average_minutes = 180\n").unwrap();
+        assert!(rules.validate().unwrap_err().to_string().contains("must not exceed"));
+        let rules: InsightRules =
+            toml::from_str("[short_sessions]\nmax_average_minutes = 180\n[long_sessions]\nenabled = false\n").unwrap();
+        rules.validate().unwrap();
+        let rules: InsightRules = toml::from_str("[project_diversity]\nmax_projects = 0\n").unwrap();
+        assert!(rules.validate().is_err());
+    }
+}
@@ -0,0 +1,134 @@
+use chrono::{DateTime, Duration, NaiveDate, NaiveTime, TimeZone, Utc};
+
+use crate::timezone::TimezoneSchedule;
+
+/// When a working day is considered finished
+#[derive(Debug, Clone, Copy)]
+pub struct EndOfDayConfig {
+    /// Local time before which a day is never considered finished
+    pub not_before: NaiveTime,
+    /// Inactivity required after the last log entry
+    pub idle: Duration,
+}
+
+/// Decides when the summary of a working day should be written
+pub struct EndOfDayDetector {
+    config: EndOfDayConfig,
+    timezone: TimezoneSchedule,
+    /// Most recent day whose summary has been written
+    last_written: Option<NaiveDate>,
+}
+
+impl EndOfDayDetector {
+    pub fn new(config: EndOfDayConfig, timezone: TimezoneSchedule) -> Self {
+        Self {
+            config,
+            timezone,
+            last_written: None,
+        }
+    }
+
+    /// The day that has just ended, if its summary is due
+    ///
+    /// The working day is the local day of the last activity, so a session
+    /// running past midnight belongs to the day it ended on.
+    pub fn due_day(&self, last_activity: DateTime<Utc>, now: DateTime<Utc>) -> Option<NaiveDate> {
+        let day = self.timezone.to_local(last_activity).date_naive();
+        if self.last_written.is_some_and(|written| written >= day) {
+            return None;
+        }
+
+        let end_of_day = self
+            .timezone
+            .offset_on(day)
+            .from_local_datetime(&day.and_time(self.config.not_before))
+            .single()?
+            .with_timezone(&Utc);
+
+        if now >= end_of_day && now - last_activity >= self.config.idle {
+            Some(day)
+        } else {
+            None
+        }
+    }
+
+    pub fn mark_written(&mut self, day: NaiveDate) {
+        self.last_written = Some(day);
+    }
+
+    /// UTC bounds of a local calendar day
+    pub fn day_range(&self, day: NaiveDate) -> (DateTime<Utc>, DateTime<Utc>) {
+        (self.timezone.start_of_day(day), self.timezone.end_of_day(day))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::timezone::jst;
+
+    fn detector() -> EndOfDayDetector {
+        EndOfDayDetector::new(
+            EndOfDayConfig {
+                not_before: NaiveTime::from_hms_opt(18, 0, 0).unwrap(),
+                idle: Duration::hours(2),
+            },
+            TimezoneSchedule::fixed(jst()),
+        )
+    }
+
+    fn jst_time(day: u32, hour: u32, minute: u32) -> DateTime<Utc> {
+        jst().with_ymd_and_hms(2025, 7, day, hour, minute, 0).unwrap().with_timezone(&Utc)
+    }
+
+    #[test]
+    fn test_day_ends_after_configured_time_and_idle_period() {
+        let detector = detector();
+        let last_activity = jst_time(1, 17, 0);
+        let day = NaiveDate::from_ymd_opt(2025, 7, 1).unwrap();
+
+        // Idle long enough, but too early in the day
+        assert_eq!(detector.due_day(jst_time(1, 8, 0), jst_time(1, 12, 0)), None);
+        // After 18:00 but still within the idle period
+        assert_eq!(detector.due_day(last_activity, jst_time(1, 18, 30)), None);
+        assert_eq!(detector.due_day(last_activity, jst_time(1, 19, 0)), Some(day));
+    }
+
+    #[test]
+    fn test_late_sessions_and_written_days() {
+        let mut detector = detector();
+        let day = NaiveDate::from_ymd_opt(2025, 7, 1).unwrap();
+
+        // Work until 23:30 keeps the day open until 01:30
+        assert_eq!(detector.due_day(jst_time(1, 23, 30), jst_time(2, 1, 0)), None);
+        assert_eq!(detector.due_day(jst_time(1, 23, 30), jst_time(2, 1, 30)), Some(day));
+
+        detector.mark_written(day);
+        assert_eq!(detector.due_day(jst_time(1, 23, 30), jst_time(2, 3, 0)), None);
+    }
+
+    #[test]
+    fn test_day_range() {
+        let (start, end) = detector().day_range(NaiveDate::from_ymd_opt(2025, 7, 1).unwrap());
+        assert_eq!(start, jst_time(1, 0, 0));
+        assert_eq!(end, jst_time(1, 23, 59) + Duration::seconds(59));
+    }
+
+    #[test]
+    fn test_days_follow_the_timezone_schedule() {
+        let day = NaiveDate::from_ymd_opt(2025, 7, 10).unwrap();
+        let pacific = crate::timezone::parse_timezone("-07:00").unwrap();
+        let schedule = TimezoneSchedule::fixed(jst()).with_change(day, pacific);
+        let config = EndOfDayConfig {
+            not_before: NaiveTime::from_hms_opt(18, 0, 0).unwrap(),
+            idle: Duration::hours(2),
+        };
+        let detector = EndOfDayDetector::new(config, schedule);
+        let pacific_time = |hour| pacific.with_ymd_and_hms(2025, 7, 10, hour, 0, 0).unwrap().with_timezone(&Utc);
+
+        // 17:00 in California is already the next day in Japan
+        assert_eq!(detector.due_day(pacific_time(17), pacific_time(18)), None);
+        assert_eq!(detector.due_day(pacific_time(17), pacific_time(19)), Some(day));
+        assert_eq!(detector.day_range(day).1, pacific_time(23) + Duration::minutes(59) + Duration::seconds(59));
+    }
+}
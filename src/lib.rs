@@ -0,0 +1,54 @@
+pub mod activity;
+pub mod aggregate;
+pub mod analyzer;
+pub mod archive;
+pub mod budget;
+pub mod bundle;
+pub mod cache;
+pub mod clock_skew;
+pub mod clustering;
+pub mod compare;
+pub mod config;
+pub mod current_project;
+pub mod daily_summary;
+pub mod error;
+pub mod eval;
+pub mod filter;
+pub mod git;
+#[cfg(feature = "google-calendar")]
+pub mod google_calendar;
+pub mod health;
+pub mod http_server;
+pub mod importers;
+pub mod index;
+pub mod initiatives;
+pub mod insights;
+pub mod journal;
+pub mod machines;
+pub mod message_analyzer;
+pub mod metrics;
+pub mod milestones;
+pub mod models;
+pub mod output;
+pub mod parser;
+pub mod paths;
+pub mod period;
+pub mod pipeline;
+pub mod plan;
+pub mod privacy;
+pub mod profile;
+pub mod progress;
+pub mod replay;
+pub mod report_history;
+pub mod reporter;
+pub mod result_cache;
+pub mod sample;
+pub mod scanner;
+pub mod similarity;
+pub mod standup;
+pub mod storage;
+pub mod template;
+pub mod timezone;
+pub mod token_analyzer;
+pub mod watcher;
+pub mod weekly;
@@ -0,0 +1,129 @@
+use chrono::Duration;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use uuid::Uuid;
+
+use crate::models::{MachineBreakdown, MachineStats, WorkAnalysis};
+
+/// Machine of the logs in projects directories that have no label
+pub const UNLABELED_MACHINE: &str = "other";
+
+/// Projects directories labeled with the machine their logs were synced from
+///
+/// A log session belongs to the machine of the first file it was read from.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MachineLabels {
+    roots: Vec<(PathBuf, String)>,
+}
+
+impl MachineLabels {
+    pub fn new(roots: impl IntoIterator<Item = (PathBuf, String)>) -> Self {
+        Self { roots: roots.into_iter().collect() }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.roots.is_empty()
+    }
+
+    /// Machine of a log file: the label of the deepest directory containing it
+    pub fn machine_of(&self, file: &Path) -> &str {
+        self.roots
+            .iter()
+            .filter(|(root, _)| file.starts_with(root))
+            .max_by_key(|(root, _)| root.components().count())
+            .map_or(UNLABELED_MACHINE, |(_, label)| label.as_str())
+    }
+
+    /// Work per machine of the analysis, given the machine of each log session
+    pub fn breakdown(
+        &self,
+        analysis: &WorkAnalysis,
+        session_machines: &HashMap<Uuid, String>,
+        duplicate_entries: usize,
+    ) -> MachineBreakdown {
+        let session_tokens = analysis.token_analysis.as_ref().map(|tokens| tokens.stats_by_session()).unwrap_or_default();
+        let mut machines: HashMap<&str, MachineStats> = HashMap::new();
+        for session in &analysis.sessions {
+            let name = session_machines.get(&session.session_id).map_or(UNLABELED_MACHINE, String::as_str);
+            let stats = machines.entry(name).or_insert_with(|| MachineStats {
+                name: name.to_string(),
+                sessions: 0,
+                messages: 0,
+                work_time: Duration::zero(),
+                tokens: Default::default(),
+            });
+            stats.sessions += 1;
+            stats.messages += session.total_messages;
+            stats.work_time += session.end_time - session.start_time;
+            if let Some(tokens) = session_tokens.get(&(session.session_id, session.start_time)) {
+                stats.tokens.merge(tokens);
+            }
+        }
+
+        let mut machines: Vec<MachineStats> = machines.into_values().collect();
+        machines.sort_by(|a, b| b.work_time.cmp(&a.work_time).then_with(|| a.name.cmp(&b.name)));
+        MachineBreakdown { machines, duplicate_entries }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pipeline::{AnalysisPipeline, AnalysisTuning};
+    use crate::sample::write_sample;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_machine_of() {
+        let labels = MachineLabels::new([
+            (PathBuf::from("/sync"), "nas".to_string()),
+            (PathBuf::from("/sync/laptop"), "laptop".to_string()),
+        ]);
+        assert_eq!(labels.machine_of(Path::new("/sync/laptop/-home-me-api/a.jsonl")), "laptop");
+        assert_eq!(labels.machine_of(Path::new("/sync/desktop/-home-me-api/a.jsonl")), "nas");
+        assert_eq!(labels.machine_of(Path::new("/sync-old/a.jsonl")), UNLABELED_MACHINE);
+    }
+
+    #[tokio::test]
+    async fn test_merged_machines() {
+        let temp_dir = TempDir::new().unwrap();
+        let laptop = temp_dir.path().join("laptop");
+        let desktop = temp_dir.path().join("desktop");
+        write_sample(&laptop).unwrap();
+        let project = |name: &str| {
+            std::fs::read_dir(&laptop).unwrap().map(|dir| dir.unwrap().path()).find(|dir| dir.to_string_lossy().ends_with(name)).unwrap()
+        };
+        // The web project was worked on at the desktop, which also synced a copy of the laptop's api logs
+        std::fs::create_dir_all(&desktop).unwrap();
+        let web = project("web");
+        std::fs::rename(&web, desktop.join(web.file_name().unwrap())).unwrap();
+        let api = project("api");
+        let copy = desktop.join(api.file_name().unwrap());
+        std::fs::create_dir_all(&copy).unwrap();
+        for file in std::fs::read_dir(&api).unwrap() {
+            let file = file.unwrap().path();
+            let copied = copy.join(file.file_name().unwrap());
+            std::fs::copy(&file, &copied).unwrap();
+            // Synced like rsync -a, keeping the time the laptop wrote it
+            let modified = file.metadata().unwrap().modified().unwrap();
+            std::fs::File::options().write(true).open(&copied).unwrap().set_modified(modified).unwrap();
+        }
+
+        let machines = MachineLabels::new([(laptop.clone(), "laptop".to_string()), (desktop.clone(), "desktop".to_string())]);
+        let output = AnalysisPipeline::builder()
+            .data_dirs(&[laptop, desktop])
+            .tuning(AnalysisTuning { min_messages: Some(1), ..Default::default() })
+            .machines(machines)
+            .run()
+            .await
+            .unwrap();
+
+        assert_eq!(output.analysis.total_sessions, 3);
+        let breakdown = output.analysis.machines.unwrap();
+        let sessions: Vec<(&str, usize)> = breakdown.machines.iter().map(|machine| (machine.name.as_str(), machine.sessions)).collect();
+        assert_eq!(sessions, [("laptop", 2), ("desktop", 1)]);
+        assert_eq!(breakdown.machines.iter().map(|machine| machine.messages).sum::<usize>(), output.analysis.total_messages);
+        assert_eq!(breakdown.duplicate_entries, output.diagnostics.duplicate_entries);
+        assert!(breakdown.duplicate_entries > 0);
+    }
+}
@@ -1,142 +1,2617 @@
-use anyhow::Result;
-use chrono::{DateTime, Utc, NaiveDate, TimeZone, FixedOffset};
-use clap::{Arg, Command};
-use std::path::PathBuf;
-
-mod models;
-mod scanner;
-mod parser;
-mod filter;
-mod analyzer;
-mod reporter;
-mod message_analyzer;
-
-use crate::scanner::ProjectScanner;
-use crate::filter::TimeRangeFilter;
-use crate::parser::JsonlParser;
-use crate::analyzer::WorkAnalyzer;
-use crate::reporter::ReportGenerator;
-
-/// Parse a date string in YYYY-MM-DD format to DateTime<Utc> (start of day in JST)
-fn parse_date_string(date_str: &str) -> Result<DateTime<Utc>> {
-    let naive_date = NaiveDate::parse_from_str(date_str, "%Y-%m-%d")
-        .map_err(|e| anyhow::anyhow!("Invalid date format '{}': {}. Expected YYYY-MM-DD", date_str, e))?;
-    
-    // JST timezone (UTC+9)
-    let jst = FixedOffset::east_opt(9 * 3600).unwrap();
-    
-    // Convert to DateTime in JST at start of day (00:00:00), then to UTC
-    Ok(jst.from_local_datetime(&naive_date.and_hms_opt(0, 0, 0).unwrap()).unwrap().with_timezone(&Utc))
-}
-
-/// Parse a date string in YYYY-MM-DD format to DateTime<Utc> (end of day in JST)
-fn parse_end_date_string(date_str: &str) -> Result<DateTime<Utc>> {
-    let naive_date = NaiveDate::parse_from_str(date_str, "%Y-%m-%d")
-        .map_err(|e| anyhow::anyhow!("Invalid date format '{}': {}. Expected YYYY-MM-DD", date_str, e))?;
-    
-    // JST timezone (UTC+9)
-    let jst = FixedOffset::east_opt(9 * 3600).unwrap();
-    
-    // Convert to DateTime in JST at end of day (23:59:59), then to UTC
-    Ok(jst.from_local_datetime(&naive_date.and_hms_opt(23, 59, 59).unwrap()).unwrap().with_timezone(&Utc))
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration, FixedOffset, Utc, NaiveDate, NaiveTime};
+use clap::parser::ValueSource;
+use clap::{Arg, ArgAction, ArgMatches, Command};
+use std::collections::HashMap;
+use std::io::IsTerminal;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration as StdDuration;
+use tokio_util::sync::CancellationToken;
+use tracing::Level;
+
+use claude_work_analysis::filter::{FilterCoverage, TimeRangeFilter};
+use claude_work_analysis::git;
+use claude_work_analysis::health::{LogGaps, DEFAULT_MIN_GAP_DAYS};
+use claude_work_analysis::http_server::HttpServer;
+use claude_work_analysis::index::build_index;
+use claude_work_analysis::parser::{ContentLevel, JsonlParser};
+use claude_work_analysis::cache::ParseCache;
+use claude_work_analysis::compare::PeriodComparison;
+use claude_work_analysis::config::{expand_home, Config};
+use claude_work_analysis::error::Error;
+use claude_work_analysis::eval::{EvalCorpus, Evaluation};
+use claude_work_analysis::analyzer::WorkAnalyzer;
+use claude_work_analysis::archive::{archive_logs, default_archive_dir};
+use claude_work_analysis::importers::{default_import_dir, import_history, ImportSource};
+use claude_work_analysis::budget::BudgetStatus;
+use claude_work_analysis::bundle::{default_unpack_dir, Bundle};
+use claude_work_analysis::message_analyzer::KeywordLists;
+use claude_work_analysis::current_project::{CurrentProject, ProjectLogs};
+use claude_work_analysis::daily_summary::{summarize_days, DailySummaryCache, SummaryKey, CACHED_DAYS};
+use claude_work_analysis::models::{ClaudeLogEntry, SessionOrder, TimeGrouping, WorkAnalysis};
+use claude_work_analysis::replay::{find_session, format_turn, replay_delay, replay_turns, ReplaySpeed};
+use claude_work_analysis::report_history::ReportHistory;
+use claude_work_analysis::reporter::{ReportGenerator, ReportSection};
+use claude_work_analysis::sample::write_sample;
+use claude_work_analysis::standup::Standup;
+use claude_work_analysis::storage::{JsonlStorage, Storage, StorageBackend, StorageKind};
+use claude_work_analysis::template::ReportTemplate;
+#[cfg(feature = "sqlite")]
+use claude_work_analysis::storage::SqliteStore;
+use claude_work_analysis::output::{project_file_names, OutputMode, ReportWriter, INDEX_FILE_NAME};
+use claude_work_analysis::journal::{EndOfDayConfig, EndOfDayDetector};
+use claude_work_analysis::pipeline::AnalysisTuning;
+use claude_work_analysis::period::ReportingPeriod;
+use claude_work_analysis::plan::{week_start, PlanStore};
+use claude_work_analysis::profile::{CountingAllocator, Profiler};
+use claude_work_analysis::progress::ParseProgress;
+use claude_work_analysis::milestones::{notify_desktop, MilestoneConfig, MilestoneTracker};
+use claude_work_analysis::timezone::{resolve_timezone, TimezoneSchedule};
+use claude_work_analysis::token_analyzer::{PriceTable, TokenAnalyzer};
+use claude_work_analysis::watcher::LogWatcher;
+use claude_work_analysis::weekly::{parse_iso_week, WeeklyReport};
+
+/// Parse a date string in YYYY-MM-DD format
+fn parse_naive_date(date_str: &str) -> Result<NaiveDate> {
+    NaiveDate::parse_from_str(date_str, "%Y-%m-%d").map_err(|reason| {
+        Error::InvalidDate {
+            value: date_str.to_string(),
+            reason,
+        }
+        .into()
+    })
 }
 
-#[tokio::main]
-async fn main() -> Result<()> {
-    let matches = Command::new("claude-work-analysis")
+/// Parse a date string in YYYY-MM-DD format to DateTime<Utc> (start of the local day)
+fn parse_date_string(date_str: &str, schedule: &TimezoneSchedule) -> Result<DateTime<Utc>> {
+    Ok(schedule.start_of_day(parse_naive_date(date_str)?))
+}
+
+/// Parse a date string in YYYY-MM-DD format to DateTime<Utc> (end of the local day)
+fn parse_end_date_string(date_str: &str, schedule: &TimezoneSchedule) -> Result<DateTime<Utc>> {
+    Ok(schedule.end_of_day(parse_naive_date(date_str)?))
+}
+
+/// Entries of the selected storage that pass the filter
+async fn load_entries(storage: &StorageBackend<'_>, filter: &TimeRangeFilter) -> Result<Vec<ClaudeLogEntry>> {
+    Ok(storage.load_entries(filter).await?.0)
+}
+
+/// Settings for the long-running `--daemon` mode
+struct DaemonOptions {
+    project_filter: Option<String>,
+    milestones: MilestoneConfig,
+    end_of_day: Option<EndOfDayConfig>,
+    journal_dir: PathBuf,
+    schedule: TimezoneSchedule,
+}
+
+/// Summarize yesterday and today for `status` and the MCP server
+///
+/// `entries` are every entry of those days. Summaries that cannot be written
+/// only warn; the readers then read the logs as usual.
+fn store_daily_summaries(storage: &StorageBackend<'_>, config: &Config, schedule: &TimezoneSchedule, entries: &[ClaudeLogEntry]) {
+    let Some(cache) = DailySummaryCache::default_location() else {
+        return;
+    };
+    let now = Utc::now();
+    let key = SummaryKey::new(storage.source().projects_dirs(), schedule.offset_at(now), config);
+    let stored = storage.project_directories().and_then(|project_dirs| {
+        // The analyzer `status` and the MCP server use without overrides
+        let analyzer = config.analyzer().with_timezone_schedule(schedule.clone()).with_project_dirs(&project_dirs);
+        let days = summarize_days(&analyzer, entries, schedule, schedule.to_local(now).date_naive())?;
+        cache.store(&key, days, now)
+    });
+    if let Err(e) = stored {
+        eprintln!("Warning: Failed to update the daily summaries in {}: {:#}", cache.path().display(), e);
+    }
+}
+
+/// Watch the logs, raise milestone notifications and write end-of-day summaries
+async fn run_daemon(storage: &StorageBackend<'_>, config: &Config, options: DaemonOptions) -> Result<()> {
+    let projects_dirs = storage.source().projects_dirs();
+    let schedule = options.schedule;
+    let mut tracker = MilestoneTracker::new(options.milestones.clone(), schedule.clone());
+    let mut end_of_day = options.end_of_day.map(|config| EndOfDayDetector::new(config, schedule.clone()));
+    let mut watcher = LogWatcher::watch_all(projects_dirs)?;
+    let analyzer = config.analyzer().with_timezone_schedule(schedule.clone());
+    eprintln!("Watching {} (Ctrl-C to stop)", display_dirs(projects_dirs));
+
+    loop {
+        let now = Utc::now();
+        let today = schedule.to_local(now).date_naive();
+        let since = schedule.start_of_day(today - Duration::days(i64::from(CACHED_DAYS) - 1));
+        let filter = TimeRangeFilter::new(Some(since), Some(now), options.project_filter.clone());
+        let entries = load_entries(storage, &filter).await?;
+        // The summaries stand for all projects
+        if options.project_filter.is_none() {
+            store_daily_summaries(storage, config, &schedule, &entries);
+        }
+
+        // Sessions from yesterday evening may still be running past midnight
+        let from = now - Duration::days(1);
+        let recent: Vec<ClaudeLogEntry> = entries.iter().filter(|entry| entry.timestamp >= from).cloned().collect();
+        let mut analysis = analyzer.analyze_entries(&recent)?;
+        if let Some(budget) = options.milestones.monthly_budget {
+            let filter = TimeRangeFilter::new(Some(from), Some(now), options.project_filter.clone());
+            analysis.budget = Some(month_to_date_budget(budget, storage, &analyzer, &filter, &analysis, &schedule).await?);
+        }
+
+        for milestone in tracker.check(&analysis, now) {
+            eprintln!("[{}] {}: {}", schedule.to_local(now).format("%H:%M"), milestone.title(), milestone.body());
+            if let Err(e) = notify_desktop(&milestone) {
+                eprintln!("Failed to show desktop notification: {}", e);
+            }
+        }
+
+        let last_activity = entries.iter().map(|entry| entry.timestamp).max();
+        if let (Some(detector), Some(last_activity)) = (end_of_day.as_mut(), last_activity) {
+            if let Some(day) = detector.due_day(last_activity, now) {
+                let journal_path = options.journal_dir.join("journal.md");
+                // A previous daemon run may already have written this day
+                if !ReportWriter::dated_path(&journal_path, day).exists() {
+                    let (day_start, day_end) = detector.day_range(day);
+                    let filter = TimeRangeFilter::new(Some(day_start), Some(day_end), options.project_filter.clone());
+                    let day_entries = load_entries(storage, &filter).await?;
+                    let report = ReportGenerator::new()
+                        .with_timezone_schedule(schedule.clone())
+                        .generate_markdown_report(&analyzer.analyze_entries(&day_entries)?)?;
+                    std::fs::create_dir_all(&options.journal_dir)?;
+                    let written_path = ReportWriter::new(OutputMode::Dated).write(&journal_path, &report, day)?;
+                    eprintln!("Daily summary for {} written to {}", day, written_path.display());
+                }
+                detector.mark_written(day);
+            }
+        }
+
+        // Re-check periodically so the end of the day is noticed without new log lines
+        tokio::select! {
+            batch = watcher.next_batch() => {
+                if batch.is_none() {
+                    return Ok(());
+                }
+            }
+            _ = tokio::time::sleep(StdDuration::from_secs(300)) => {}
+        }
+    }
+}
+
+/// The plans file given, or the one in the data directory
+fn plans_path(file: Option<&String>) -> Result<PathBuf> {
+    match file {
+        Some(path) => Ok(PathBuf::from(path)),
+        None => Ok(dirs::data_dir()
+            .ok_or_else(|| anyhow::anyhow!("Cannot find data directory, pass the plans file"))?
+            .join("claude-work-analysis")
+            .join("plans.json")),
+    }
+}
+
+/// Record estimates or compare them with actual hours
+async fn run_plan(matches: &ArgMatches, storage: &StorageBackend<'_>, schedule: &TimezoneSchedule, config: &Config) -> Result<()> {
+    let mut store = PlanStore::load(&plans_path(matches.get_one::<String>("file"))?)?;
+    let tz = schedule.offset_at(Utc::now());
+    let today = Utc::now().with_timezone(&tz).date_naive();
+
+    match matches.subcommand() {
+        Some(("set", sub)) => {
+            let project = sub.get_one::<String>("project").unwrap();
+            let hours = *sub.get_one::<f64>("hours").unwrap();
+            let week = match sub.get_one::<String>("week") {
+                Some(date) => NaiveDate::parse_from_str(date, "%Y-%m-%d")
+                    .map_err(|e| anyhow::anyhow!("Invalid week '{}': {}. Expected YYYY-MM-DD", date, e))?,
+                None => today,
+            };
+            store.set_estimate(project, week, hours);
+            store.save()?;
+            eprintln!("Planned {:.1}h for {} in the week of {}", hours, project, week_start(week));
+        }
+        Some(("show", sub)) => {
+            let weeks = *sub.get_one::<i64>("weeks").unwrap();
+            let since = week_start(today) - Duration::weeks(weeks.max(1) - 1);
+            let from = schedule.start_of_day(since);
+            let filter = TimeRangeFilter::new(Some(from), Some(Utc::now()), None);
+            let analyzer = config.analyzer().with_timezone_schedule(schedule.clone());
+            let (analysis, _) = storage.analyze(&filter, &analyzer).await?;
+            let rows = store.compare(&analysis, &tz, since);
+            println!("{}", ReportGenerator::new().with_timezone_schedule(schedule.clone()).generate_plan_report(&rows));
+        }
+        _ => unreachable!("clap requires a plan subcommand"),
+    }
+
+    Ok(())
+}
+
+/// Compare the selected period with a baseline period
+async fn run_compare(
+    matches: &ArgMatches,
+    storage: &StorageBackend<'_>,
+    schedule: &TimezoneSchedule,
+    config: &Config,
+    reporter: &ReportGenerator,
+) -> Result<()> {
+    let current = analyze_selection(matches, storage, schedule, config).await?;
+    let (from_date, to_date) = baseline_range(matches, schedule)?;
+    let baseline_filter = TimeRangeFilter::new(from_date, to_date, matches.get_one::<String>("project").cloned());
+    let analyzer = selection_analyzer(matches, storage, schedule, config)?;
+    let (baseline, _) = storage.analyze(&baseline_filter, &analyzer).await?;
+
+    let comparison = PeriodComparison::new(&baseline, &current.analysis);
+    let report = match matches.get_one::<String>("format").unwrap().as_str() {
+        "json" => reporter.generate_comparison_json(&comparison, &baseline_filter, &current.filter)?,
+        _ => reporter.generate_comparison_report(&comparison, &baseline_filter, &current.filter),
+    };
+    emit_report(matches, &report, &schedule.offset_at(Utc::now()))
+}
+
+/// Print the report of this week, or of `--week`, against the week before
+async fn run_weekly(
+    matches: &ArgMatches,
+    storage: &StorageBackend<'_>,
+    schedule: &TimezoneSchedule,
+    config: &Config,
+    reporter: &ReportGenerator,
+) -> Result<()> {
+    let week = match matches.get_one::<String>("week") {
+        Some(week) => parse_iso_week(week)?,
+        None => week_start(schedule.to_local(Utc::now()).date_naive()),
+    };
+    let week_filter = |monday: NaiveDate| {
+        TimeRangeFilter::new(
+            Some(schedule.start_of_day(monday)),
+            Some(schedule.end_of_day(monday + Duration::days(6))),
+            matches.get_one::<String>("project").cloned(),
+        )
+    };
+    let analyzer = selection_analyzer(matches, storage, schedule, config)?;
+    let (current, _) = storage.analyze(&week_filter(week), &analyzer).await?;
+    let (previous, _) = storage.analyze(&week_filter(week - Duration::weeks(1)), &analyzer).await?;
+
+    let plans = PlanStore::load(&plans_path(matches.get_one::<String>("plans"))?)?;
+    let tz = schedule.offset_at(schedule.start_of_day(week));
+    let weekly = WeeklyReport::new(&current, &previous, &plans, week, &tz);
+    print!("{}", reporter.generate_weekly_report(&weekly));
+    Ok(())
+}
+
+/// Print the standup update of the days before today
+async fn run_standup(
+    matches: &ArgMatches,
+    storage: &StorageBackend<'_>,
+    schedule: &TimezoneSchedule,
+    config: &Config,
+    reporter: &ReportGenerator,
+) -> Result<()> {
+    let today = schedule.to_local(Utc::now()).date_naive();
+    let (first_day, last_day) = Standup::days_before(today, *matches.get_one::<u32>("days").unwrap());
+    let filter = TimeRangeFilter::new(
+        Some(schedule.start_of_day(first_day)),
+        Some(schedule.end_of_day(last_day)),
+        matches.get_one::<String>("project").cloned(),
+    );
+    let analyzer = selection_analyzer(matches, storage, schedule, config)?;
+    let (analysis, _) = storage.analyze(&filter, &analyzer).await?;
+    print!("{}", reporter.generate_standup_report(&Standup::new(&analysis, first_day, last_day)));
+    Ok(())
+}
+
+/// Print the status line of today's or the last days' work
+async fn run_status(
+    matches: &ArgMatches,
+    storage: &StorageBackend<'_>,
+    schedule: &TimezoneSchedule,
+    config: &Config,
+    reporter: &ReportGenerator,
+    no_cache: bool,
+) -> Result<()> {
+    let now = Utc::now();
+    let today = schedule.to_local(now).date_naive();
+    let days = matches.get_one::<u32>("days").copied().unwrap_or(1);
+    // What --watch or --daemon summarized is current enough for the default settings
+    let default_settings = matches.get_one::<String>("project").is_none()
+        && tuning_args().iter().all(|arg| matches.value_source(arg.get_id().as_str()) != Some(ValueSource::CommandLine));
+    if default_settings && !no_cache && days <= CACHED_DAYS {
+        let key = SummaryKey::new(storage.source().projects_dirs(), schedule.offset_at(now), config);
+        if let Some((summary, _)) = DailySummaryCache::default_location().and_then(|cache| cache.recent(&key, days, today, now)) {
+            println!("{}", reporter.generate_summary_status_line(&summary));
+            return Ok(());
+        }
+    }
+
+    let filter = TimeRangeFilter::new(
+        Some(schedule.start_of_day(today - Duration::days(i64::from(days) - 1))),
+        Some(schedule.end_of_day(today)),
+        matches.get_one::<String>("project").cloned(),
+    );
+    let analyzer = selection_analyzer(matches, storage, schedule, config)?;
+    let (analysis, _) = storage.analyze(&filter, &analyzer).await?;
+    println!("{}", reporter.generate_status_line(&analysis));
+    Ok(())
+}
+
+/// Print the JSON recap of a working directory's project
+async fn run_current_project(
+    matches: &ArgMatches,
+    storage: &StorageBackend<'_>,
+    schedule: &TimezoneSchedule,
+    config: &Config,
+    reporter: &ReportGenerator,
+) -> Result<()> {
+    let cwd = match matches.get_one::<PathBuf>("cwd") {
+        Some(path) => std::path::absolute(expand_home(path)?)?,
+        None => std::env::current_dir()?,
+    };
+    let cwd = cwd.to_string_lossy();
+    let today = schedule.to_local(Utc::now()).date_naive();
+    let since = schedule.start_of_day(today - Duration::days(i64::from(*matches.get_one::<u32>("days").unwrap()) - 1));
+
+    let current = match ProjectLogs::find(&cwd, &storage.project_directories()?) {
+        Some(project) => {
+            // Only the project's own log directories are read
+            let analyzer = selection_analyzer(matches, storage, schedule, config)?;
+            let filter = TimeRangeFilter::new(Some(since), None, None);
+            let logs = JsonlStorage::new(storage.source().parser(), &project.log_dirs);
+            let (analysis, _) = logs.analyze(&filter, &analyzer).await?;
+            let message_analyzer = config.message_analyzer();
+            let limit = *matches.get_one::<usize>("limit").unwrap();
+            CurrentProject::new(&cwd, project, since, &analysis, &message_analyzer, limit)
+        }
+        None => CurrentProject::unknown(&cwd, since),
+    };
+    println!("{}", reporter.generate_current_project_json(&current)?);
+    Ok(())
+}
+
+/// Score the analyzer heuristics, with the configured keywords and weights, on a labeled corpus
+fn run_eval(matches: &ArgMatches, config: &Config, reporter: &ReportGenerator) -> Result<()> {
+    let corpus = match matches.get_one::<PathBuf>("corpus") {
+        Some(path) => EvalCorpus::from_file(&expand_home(path)?)?,
+        None => EvalCorpus::builtin()?,
+    };
+    let analyzer = config.message_analyzer();
+    let evaluation = Evaluation::run(&corpus, &config.activity_scoring()?, &analyzer);
+    print!("{}", reporter.generate_eval_report(&evaluation));
+    Ok(())
+}
+
+/// Build the parse cache for every log file
+async fn run_index(
+    parser: &JsonlParser,
+    storage: &StorageBackend<'_>,
+    projects_dirs: &[PathBuf],
+    schedule: &TimezoneSchedule,
+    config: &Config,
+) -> Result<()> {
+    let Some(cache) = parser.cache() else {
+        return Err(anyhow::anyhow!("index build fills the parse cache; run it without --no-cache"));
+    };
+
+    let started = std::time::Instant::now();
+    let stats = build_index(parser, projects_dirs).await?;
+    println!(
+        "Indexed {} files in {:.1}s ({} parsed, {} up to date, {} failed, {} not in the cache)",
+        stats.files,
+        started.elapsed().as_secs_f64(),
+        stats.parsed_files,
+        stats.up_to_date_files(),
+        stats.failed_files,
+        stats.uncached_files
+    );
+    println!("Entries: {} in {} sessions across {} projects", stats.entries, stats.sessions, stats.projects);
+    if let (Some(first), Some(last)) = (stats.first_entry, stats.last_entry) {
+        println!(
+            "Logs cover: {} to {}",
+            schedule.to_local(first).format("%Y-%m-%d"),
+            schedule.to_local(last).format("%Y-%m-%d")
+        );
+    }
+    println!("Cache: {}", cache.dir().display());
+
+    // The database `analyze` and the other reports read with storage = "sqlite" or "duckdb"
+    if !matches!(storage, StorageBackend::Jsonl(_)) {
+        let counts = storage.ingest().await?;
+        println!(
+            "Storage: {} new entries from {} files ({} unchanged)",
+            counts.new_entries, counts.files_read, counts.files_unchanged
+        );
+    }
+
+    // `status` and the MCP `summarize_recent` answer yesterday and today from these
+    let today = schedule.to_local(Utc::now()).date_naive();
+    let since = schedule.start_of_day(today - Duration::days(i64::from(CACHED_DAYS) - 1));
+    let entries = load_entries(storage, &TimeRangeFilter::new(Some(since), None, None)).await?;
+    store_daily_summaries(storage, config, schedule, &entries);
+    if let Some(summaries) = DailySummaryCache::default_location() {
+        println!("Daily summaries: {}", summaries.path().display());
+    }
+    Ok(())
+}
+
+/// `ingest`: bring the storage up to date, or with `--verify` compare it with the log files
+async fn run_ingest(args: &ArgMatches, storage: &StorageBackend<'_>) -> Result<()> {
+    if !args.get_flag("verify") {
+        let started = std::time::Instant::now();
+        let counts = storage.ingest().await?;
+        println!(
+            "Ingested {} files in {:.1}s ({} unchanged)",
+            counts.files_read,
+            started.elapsed().as_secs_f64(),
+            counts.files_unchanged
+        );
+        println!(
+            "Entries: {} read, {} new, {} already stored",
+            counts.entries_read,
+            counts.new_entries,
+            counts.entries_read - counts.new_entries
+        );
+        return Ok(());
+    }
+
+    let report = storage.verify().await?;
+    for mismatch in &report.mismatches {
+        println!("{:<8} {} {}", mismatch.kind.as_str(), mismatch.uuid, mismatch.file.display());
+    }
+    println!(
+        "Checked {} entries in {} files: {} mismatches",
+        report.entries_checked,
+        report.files_checked,
+        report.mismatches.len()
+    );
+    if !report.mismatches.is_empty() {
+        return Err(anyhow::anyhow!(
+            "The stored data differs from the log files; run `ingest` to update it"
+        ));
+    }
+    Ok(())
+}
+
+/// `calendar` subcommand, available with the `google-calendar` feature
+#[cfg(feature = "google-calendar")]
+fn calendar_command() -> Command {
+    Command::new("calendar")
+        .about("Sync work sessions to Google Calendar")
+        .subcommand_required(true)
+        .arg(
+            Arg::new("credentials")
+                .long("credentials")
+                .value_name("FILE")
+                .help("OAuth desktop client JSON (default: <config dir>/claude-work-analysis/google-credentials.json)")
+                .global(true),
+        )
+        .subcommand(Command::new("auth").about("Grant access to Google Calendar in the browser"))
+        .subcommand(
+            Command::new("sync")
+                .about("Create or update one event per work session")
+                .arg(
+                    Arg::new("days")
+                        .long("days")
+                        .value_name("N")
+                        .help("Sync sessions from the last N days")
+                        .value_parser(clap::value_parser!(i64))
+                        .default_value("7"),
+                )
+                .arg(
+                    Arg::new("calendar")
+                        .long("calendar")
+                        .value_name("NAME")
+                        .help("Calendar to write to, created if missing")
+                        .default_value(claude_work_analysis::google_calendar::DEFAULT_CALENDAR_NAME),
+                ),
+        )
+}
+
+/// `similar` subcommand, available with the `embeddings` feature
+#[cfg(feature = "embeddings")]
+fn similar_command() -> Command {
+    Command::new("similar")
+        .about("Find past sessions similar to a session or to a description of a problem")
+        .arg(
+            Arg::new("session")
+                .value_name("SESSION_ID")
+                .help("Session id, or its first characters as shown in reports")
+                .required_unless_present("text")
+                .conflicts_with("text"),
+        )
+        .arg(
+            Arg::new("text")
+                .long("text")
+                .value_name("QUERY")
+                .help("Search by a description instead of a session"),
+        )
+        .arg(
+            Arg::new("limit")
+                .long("limit")
+                .value_name("N")
+                .help("Number of sessions to show")
+                .value_parser(clap::value_parser!(usize))
+                .default_value("10"),
+        )
+        .args(range_args())
+        .args(tuning_args())
+}
+
+/// Rank the selected sessions by similarity to a session or a text
+#[cfg(feature = "embeddings")]
+async fn run_similar(
+    matches: &ArgMatches,
+    storage: &StorageBackend<'_>,
+    schedule: &TimezoneSchedule,
+    config: &Config,
+    reporter: &ReportGenerator,
+) -> Result<()> {
+    use claude_work_analysis::similarity::{default_cache_path, FastEmbedder, SessionIndex};
+
+    let selection = analyze_selection(matches, storage, schedule, config).await?;
+    let embedder = FastEmbedder::new()?;
+    let index = SessionIndex::build(&selection.analysis.sessions, &embedder, default_cache_path().as_deref())?;
+    let limit = *matches.get_one::<usize>("limit").unwrap();
+    let similar = match matches.get_one::<String>("text") {
+        Some(text) => index.search(text, &embedder, limit)?,
+        None => index.similar_to(index.find(matches.get_one::<String>("session").unwrap())?, limit),
+    };
+    print!("{}", reporter.generate_similar_report(&similar));
+    Ok(())
+}
+
+/// The markdown report followed by earlier sessions resembling the reported ones
+#[cfg(feature = "embeddings")]
+async fn related_sessions_report(
+    matches: &ArgMatches,
+    storage: &StorageBackend<'_>,
+    schedule: &TimezoneSchedule,
+    config: &Config,
+    reporter: &ReportGenerator,
+) -> Result<String> {
+    use claude_work_analysis::similarity::{default_cache_path, FastEmbedder, SessionIndex};
+
+    let selection = analyze_selection(matches, storage, schedule, config).await?;
+    if let Some(report) = selection.no_activity_report(reporter) {
+        return Ok(report);
+    }
+    let mut report = reporter.generate_markdown_report(&selection.analysis)?;
+
+    // Earlier sessions may lie outside the range and the project filter
+    let analyzer = selection_analyzer(matches, storage, schedule, config)?;
+    let (history, _) = storage.analyze(&TimeRangeFilter::new(None, None, None), &analyzer).await?;
+    let index = SessionIndex::build(&history.sessions, &FastEmbedder::new()?, default_cache_path().as_deref())?;
+    let mut sessions: Vec<_> = selection.analysis.sessions.iter().collect();
+    sessions.sort_by_key(|session| session.start_time);
+    let related: Vec<_> = sessions
+        .into_iter()
+        .filter_map(|session| Some((session, index.related_before(session)?)))
+        .collect();
+    report.push_str(&reporter.generate_related_sessions_section(&related));
+    Ok(report)
+}
+
+/// Authorize Google Calendar access or upsert recent sessions as events
+#[cfg(feature = "google-calendar")]
+async fn run_calendar(matches: &ArgMatches, storage: &StorageBackend<'_>, config: &Config, tz: FixedOffset) -> Result<()> {
+    use claude_work_analysis::google_calendar::{authorize, GoogleCalendar, OAuthClient, SessionEvent, UpsertOutcome};
+
+    let config_dir = dirs::config_dir()
+        .ok_or_else(|| anyhow::anyhow!("Cannot find config directory"))?
+        .join("claude-work-analysis");
+    let credentials_path = match matches.get_one::<String>("credentials") {
+        Some(path) => PathBuf::from(path),
+        None => config_dir.join("google-credentials.json"),
+    };
+    let client = OAuthClient::from_file(&credentials_path)?;
+    let token_path = config_dir.join("google-token.json");
+
+    match matches.subcommand() {
+        Some(("auth", _)) => {
+            authorize(&client, &token_path).await?;
+            eprintln!("Google Calendar access granted, token stored in {}", token_path.display());
+        }
+        Some(("sync", sub)) => {
+            let days = *sub.get_one::<i64>("days").unwrap();
+            let calendar_name = sub.get_one::<String>("calendar").unwrap();
+            let filter = TimeRangeFilter::last_days(days);
+            let (analysis, _) = storage.analyze(&filter, &config.analyzer().with_timezone(tz)).await?;
+
+            let calendar = GoogleCalendar::connect(&client, &token_path).await?;
+            let calendar_id = calendar.find_or_create_calendar(calendar_name).await?;
+            let (mut created, mut updated) = (0, 0);
+            for session in &analysis.sessions {
+                match calendar.upsert_event(&calendar_id, &SessionEvent::from_session(session), &tz).await? {
+                    UpsertOutcome::Created => created += 1,
+                    UpsertOutcome::Updated => updated += 1,
+                }
+            }
+            eprintln!("Synced to '{}': {} created, {} updated", calendar_name, created, updated);
+        }
+        _ => unreachable!("clap requires a calendar subcommand"),
+    }
+
+    Ok(())
+}
+
+/// `--from`/`--to`/`--period` and `--project`, shared by the analysis subcommands
+fn range_args() -> Vec<Arg> {
+    vec![
+        Arg::new("from")
+            .long("from")
+            .value_name("DATE")
+            .help("Start date (YYYY-MM-DD)")
+            .required(false),
+        Arg::new("to")
+            .long("to")
+            .value_name("DATE")
+            .help("End date (YYYY-MM-DD)")
+            .required(false),
+        Arg::new("period")
+            .long("period")
+            .value_name("PERIOD")
+            .help("Report on a calendar period: week, month, sprint:YYYY-MM-DD:DAYS or fiscal-month:DAY")
+            .conflicts_with_all(["from", "to"])
+            .required(false),
+        Arg::new("periods-ago")
+            .long("periods-ago")
+            .value_name("N")
+            .help("With --period, go back N periods (0 = current period)")
+            .value_parser(clap::value_parser!(u32))
+            .requires("period")
+            .default_value("0"),
+        project_arg(),
+    ]
+}
+
+fn project_arg() -> Arg {
+    Arg::new("project")
+        .long("project")
+        .short('p')
+        .value_name("PROJECT")
+        .help("Filter by project name")
+        .required(false)
+}
+
+/// Options that change how sessions are built and priced
+fn tuning_args() -> Vec<Arg> {
+    vec![
+        Arg::new("session-gap")
+            .long("session-gap")
+            .visible_alias("session-gap-minutes")
+            .value_name("MINUTES")
+            .help("Start a new session after this many minutes without messages (default: 120)")
+            .value_parser(clap::value_parser!(i64).range(1..))
+            .required(false),
+        Arg::new("min-messages")
+            .long("min-messages")
+            .visible_alias("min-session-messages")
+            .value_name("N")
+            .help("Count only sessions with at least N messages (default: 3)")
+            .value_parser(clap::value_parser!(u64).range(1..))
+            .required(false),
+        Arg::new("merge-restarts")
+            .long("merge-restarts")
+            .value_name("MINUTES")
+            .help("Merge sessions in the same project that resume within this many minutes (e.g. after a restart, at most a week: 10080)")
+            .value_parser(clap::value_parser!(i64).range(0..=10_080))
+            .required(false),
+        Arg::new("include-thinking")
+            .long("include-thinking")
+            .help("Also analyze the assistant's extended thinking for topics, problems and solutions")
+            .action(ArgAction::SetTrue),
+        Arg::new("deep")
+            .long("deep")
+            .help("Count project topics and technical stacks over every message instead of deriving them from session summaries (slower, more precise)")
+            .action(ArgAction::SetTrue),
+        Arg::new("redact")
+            .long("redact")
+            .help("Hash project names and paths and strip emails, API keys, secrets and the config's redact_patterns before analyzing, for reports shared with others")
+            .action(ArgAction::SetTrue),
+        Arg::new("prices")
+            .long("prices")
+            .value_name("FILE")
+            .help("JSON price table (USD per million tokens) used for cost estimates")
+            .required(false),
+    ]
+}
+
+/// `--output` and friends for commands that write full reports
+fn output_args(formats: &[&'static str], default_format: &'static str, output_required: bool) -> Vec<Arg> {
+    vec![
+        Arg::new("output")
+            .long("output")
+            .short('o')
+            .value_name("FILE")
+            .help("Output file path")
+            .required(output_required),
+        Arg::new("format")
+            .long("format")
+            .value_name("FORMAT")
+            .help("Output format (csv = one row per session, csv-daily = one row per day and project, json-timeseries = one object per day including quiet days, aggregate = hours, counts and tokens per week and project without names or text, html = page with a project × week heatmap, compact = a few aligned lines for status bars, sqlite = database file)")
+            .value_parser(formats.to_vec())
+            .default_value(default_format),
+        Arg::new("output-mode")
+            .long("output-mode")
+            .value_name("MODE")
+            .help("How to write the output file (overwrite, append, dated)")
+            .default_value("overwrite"),
+        Arg::new("keep-days")
+            .long("keep-days")
+            .value_name("DAYS")
+            .help("With --output-mode dated, delete dated reports older than N days (0 to 36500)")
+            .value_parser(clap::value_parser!(i64).range(0..=36500))
+            .required(false),
+    ]
+}
+
+/// Baseline period of `compare`
+fn baseline_args() -> Vec<Arg> {
+    vec![
+        Arg::new("baseline-from")
+            .long("baseline-from")
+            .value_name("DATE")
+            .help("Start of the baseline period (YYYY-MM-DD, default: the period before the current one)")
+            .required(false),
+        Arg::new("baseline-to")
+            .long("baseline-to")
+            .value_name("DATE")
+            .help("End of the baseline period (YYYY-MM-DD)")
+            .required(false),
+    ]
+}
+
+/// `--group-by` for reports with per-period aggregates
+fn grouping_arg() -> Arg {
+    Arg::new("group-by")
+        .long("group-by")
+        .value_name("UNIT")
+        .help("Add sessions, hours, messages and tokens per day, week or month to the report")
+        .value_parser(["day", "week", "month"])
+}
+
+/// `--sections` for commands that render the full markdown report
+fn sections_arg() -> Arg {
+    Arg::new("sections")
+        .long("sections")
+        .value_name("LIST")
+        .help("Comma-separated sections of the markdown report to include (default: all)")
+        .value_parser(ReportSection::ALL.map(|section| section.name()))
+        .value_delimiter(',')
+}
+
+/// `--min-confidence` for commands that render the full report
+fn min_confidence_arg() -> Arg {
+    Arg::new("min-confidence")
+        .long("min-confidence")
+        .value_name("CONFIDENCE")
+        .help("Hide activity types, problems and recommendations with a lower confidence, from 0 to 1 (e.g. 0.5)")
+        .value_parser(|value: &str| match value.parse::<f64>() {
+            Ok(confidence) if (0.0..=1.0).contains(&confidence) => Ok(confidence),
+            _ => Err(format!("Invalid confidence '{}'. Expected a number from 0 to 1", value)),
+        })
+}
+
+/// `--budget-monthly` and `--fail-on-budget` for the reports a cron job runs
+fn budget_args() -> Vec<Arg> {
+    vec![
+        Arg::new("budget-monthly")
+            .long("budget-monthly")
+            .value_name("USD")
+            .help("Warn when the estimated spend of this month, projected from the month so far, exceeds USD")
+            .value_parser(|value: &str| match value.parse::<f64>() {
+                Ok(budget) if budget > 0.0 => Ok(budget),
+                _ => Err(format!("Invalid budget '{}'. Expected a positive amount in USD", value)),
+            }),
+        Arg::new("fail-on-budget")
+            .long("fail-on-budget")
+            .help("Exit with status 1 after the report when the projected spend exceeds --budget-monthly")
+            .action(ArgAction::SetTrue)
+            .requires("budget-monthly"),
+    ]
+}
+
+/// `--template` for commands that render the full markdown report
+fn template_arg() -> Arg {
+    Arg::new("template")
+        .long("template")
+        .value_name("FILE")
+        .help("Handlebars template laying out the markdown report (see templates/report.md.hbs)")
+}
+
+/// Long-running `--daemon` mode options
+fn daemon_args() -> Vec<Arg> {
+    vec![
+        Arg::new("daemon")
+            .long("daemon")
+            .help("Keep running and show desktop notifications for milestones")
+            .action(ArgAction::SetTrue),
+        Arg::new("daily-goal-hours")
+            .long("daily-goal-hours")
+            .value_name("HOURS")
+            .help("With --daemon, notify when today's work time reaches this many hours")
+            .value_parser(clap::value_parser!(f64))
+            .required(false),
+        Arg::new("end-of-day")
+            .long("end-of-day")
+            .value_name("HH:MM")
+            .help("With --daemon, write the day's summary once idle after this local time")
+            .required(false),
+        Arg::new("idle-hours")
+            .long("idle-hours")
+            .value_name("HOURS")
+            .help("Hours without activity that end the working day")
+            .value_parser(clap::value_parser!(f64))
+            .default_value("2"),
+        Arg::new("journal-dir")
+            .long("journal-dir")
+            .value_name("DIR")
+            .help("Directory for end-of-day summaries (journal-YYYY-MM-DD.md)")
+            .required(false),
+        Arg::new("long-session-minutes")
+            .long("long-session-minutes")
+            .value_name("MINUTES")
+            .help("With --daemon, notify when a session runs this long without a break")
+            .value_parser(clap::value_parser!(i64))
+            .required(false),
+    ]
+}
+
+/// Everything `analyze` accepts; also accepted without a subcommand for compatibility
+fn analyze_args() -> Vec<Arg> {
+    let mut args = range_args();
+    args.extend(tuning_args());
+    args.extend(output_args(&["markdown", "json", "json-timeseries", "aggregate", "csv", "csv-daily", "html", "compact"], "markdown", false));
+    args.push(grouping_arg());
+    args.push(sections_arg());
+    args.push(min_confidence_arg());
+    args.push(template_arg());
+    args.extend(budget_args());
+    args.push(
+        Arg::new("watch")
+            .long("watch")
+            .help("Keep running and re-render the report whenever new log entries appear")
+            .action(ArgAction::SetTrue)
+            .conflicts_with("daemon"),
+    );
+    args.extend(daemon_args());
+    args.push(
+        Arg::new("low-memory")
+            .long("low-memory")
+            .help("Keep only the statistics of each session, not its messages (no reasoning volume or project topics)")
+            .action(ArgAction::SetTrue),
+    );
+    args.push(git_arg());
+    args.push(
+        Arg::new("output-dir")
+            .long("output-dir")
+            .value_name("DIR")
+            .help("Write one markdown report per project and an index.md linking them into DIR")
+            .conflicts_with_all(["output", "watch", "daemon", "low-memory"]),
+    );
+    #[cfg(feature = "embeddings")]
+    args.push(
+        Arg::new("related")
+            .long("related")
+            .help("Append earlier sessions similar to the reported ones to the markdown report")
+            .action(ArgAction::SetTrue)
+            .conflicts_with_all(["watch", "daemon", "low-memory", "output-dir"]),
+    );
+    args
+}
+
+/// `--git`: correlate sessions with the commits of their repositories
+fn git_arg() -> Arg {
+    Arg::new("git")
+        .long("git")
+        .help(format!(
+            "List the commits made during or within {} minutes after each session, from the git repository of its working directory",
+            git::COMMIT_GRACE_MINUTES
+        ))
+        .action(ArgAction::SetTrue)
+}
+
+/// A `--format` choice for the listing subcommands
+fn listing_format_arg() -> Arg {
+    Arg::new("format")
+        .long("format")
+        .value_name("FORMAT")
+        .help("Output format")
+        .value_parser(["markdown", "csv"])
+        .default_value("markdown")
+}
+
+fn build_cli() -> Command {
+    let export_formats: &[&'static str] = if cfg!(feature = "sqlite") {
+        &["json", "json-timeseries", "aggregate", "csv", "csv-daily", "markdown", "html", "sqlite"]
+    } else {
+        &["json", "json-timeseries", "aggregate", "csv", "csv-daily", "markdown", "html"]
+    };
+    let cli = Command::new("claude-work-analysis")
         .version("0.1.0")
         .about("Analyze Claude Code work logs and generate summaries")
+        .after_help("Without a subcommand, the arguments are those of `analyze`.")
+        .arg(
+            Arg::new("verbose")
+                .long("verbose")
+                .short('v')
+                .help("Also log each log file read and other details to stderr")
+                .action(ArgAction::SetTrue)
+                .global(true),
+        )
+        .arg(
+            Arg::new("quiet")
+                .long("quiet")
+                .short('q')
+                .help("Log only errors and show no progress bar while reading the logs")
+                .action(ArgAction::SetTrue)
+                .conflicts_with("verbose")
+                .global(true),
+        )
+        .arg(
+            Arg::new("no-cache")
+                .long("no-cache")
+                .help("Re-parse every log file instead of using the parse cache")
+                .action(ArgAction::SetTrue)
+                .global(true),
+        )
         .arg(
-            Arg::new("from")
-                .long("from")
-                .value_name("DATE")
-                .help("Start date (YYYY-MM-DD)")
-                .required(false),
+            Arg::new("profile")
+                .long("profile")
+                .value_name("FILE")
+                .help("Print the time and allocations of each pipeline stage to stderr and write them to FILE as folded stacks for flamegraph.pl or inferno")
+                .value_parser(clap::value_parser!(PathBuf))
+                .global(true),
+        )
+        .arg(
+            Arg::new("buffer-size")
+                .long("buffer-size")
+                .value_name("BYTES")
+                .help("Bytes to read from a log file at a time [default: 65536]")
+                .value_parser(clap::value_parser!(usize))
+                .global(true),
+        )
+        .arg(
+            Arg::new("no-refresh")
+                .long("no-refresh")
+                .help("Answer from the parse cache as built, without reading new or changed log files")
+                .action(ArgAction::SetTrue)
+                .conflicts_with("no-cache")
+                .global(true),
+        )
+        .arg(
+            Arg::new("projects-dir")
+                .long("projects-dir")
+                .value_name("DIR")
+                .help("Read logs from DIR instead of ~/.claude/projects; repeat to merge several directories")
+                .value_parser(clap::value_parser!(PathBuf))
+                .action(ArgAction::Append)
+                .conflicts_with("create-sample")
+                .global(true),
         )
         .arg(
-            Arg::new("to")
-                .long("to")
-                .value_name("DATE")
-                .help("End date (YYYY-MM-DD)")
-                .required(false),
+            Arg::new("include")
+                .long("include")
+                .value_name("PATTERN")
+                .help("Read only project directories whose name matches PATTERN, e.g. \"*work*\" (* and ? wildcards); repeat for several, replaces include_projects of the config file")
+                .action(ArgAction::Append)
+                .global(true),
         )
         .arg(
-            Arg::new("project")
-                .long("project")
-                .short('p')
-                .value_name("PROJECT")
-                .help("Filter by project name")
-                .required(false),
+            Arg::new("exclude")
+                .long("exclude")
+                .value_name("PATTERN")
+                .help("Skip project directories whose name matches PATTERN, e.g. \"*-scratch*\"; repeat for several, replaces exclude_projects of the config file")
+                .action(ArgAction::Append)
+                .global(true),
         )
         .arg(
-            Arg::new("output")
-                .long("output")
-                .short('o')
+            Arg::new("keywords")
+                .long("keywords")
                 .value_name("FILE")
-                .help("Output file path")
-                .required(false),
+                .help("TOML file of technologies, problems, solutions and learning keywords to detect, added to the config file's")
+                .value_parser(clap::value_parser!(PathBuf))
+                .global(true),
+        )
+        .arg(
+            Arg::new("language")
+                .long("language")
+                .value_name("LANGUAGE")
+                .help("Language of your prompts, whose keywords detect problems, solutions and learning: auto (both), en or ja (default: language in the config file, or auto)")
+                .value_parser(["auto", "en", "ja"])
+                .global(true),
+        )
+        .arg(
+            Arg::new("create-sample")
+                .long("create-sample")
+                .help("Write a small demo dataset and report on it instead of your logs")
+                .action(ArgAction::SetTrue)
+                .global(true),
+        )
+        .arg(
+            Arg::new("timezone")
+                .long("timezone")
+                .value_name("TZ")
+                .help("Timezone for dates and reports, e.g. UTC, JST or +05:30 (default: system timezone)")
+                .global(true),
         )
         .arg(
-            Arg::new("format")
-                .long("format")
-                .value_name("FORMAT")
-                .help("Output format (markdown, json)")
-                .default_value("markdown"),
+            Arg::new("utc")
+                .long("utc")
+                .help("Interpret dates and render reports in UTC instead of the configured timezone")
+                .action(ArgAction::SetTrue)
+                .conflicts_with_all(["timezone", "timezone-schedule"])
+                .global(true),
+        )
+        .arg(
+            Arg::new("timezone-schedule")
+                .long("timezone-schedule")
+                .value_name("FILE")
+                .help("JSON timezone changes for travel, e.g. {\"changes\": {\"2025-07-10\": \"-07:00\"}}")
+                .global(true),
+        )
+        .args(analyze_args())
+        .subcommand(
+            Command::new("analyze")
+                .about("Generate the full work report (default)")
+                .args(analyze_args()),
+        )
+        .subcommand(
+            Command::new("sessions")
+                .about("List work sessions")
+                .args(range_args())
+                .args(tuning_args())
+                .arg(
+                    Arg::new("sort")
+                        .long("sort")
+                        .value_name("ORDER")
+                        .help("Order by start time, or list the longest, most message-heavy or most token-heavy sessions first")
+                        .value_parser(SessionOrder::ALL.map(|order| order.as_str()))
+                        .default_value("start"),
+                )
+                .arg(
+                    Arg::new("top")
+                        .long("top")
+                        .value_name("N")
+                        .help("Only list the first N sessions")
+                        .value_parser(clap::value_parser!(usize)),
+                )
+                .arg(listing_format_arg()),
+        )
+        .subcommand(
+            Command::new("projects")
+                .about("List projects by work time")
+                .args(range_args())
+                .args(tuning_args())
+                .arg(listing_format_arg()),
+        )
+        .subcommand(
+            Command::new("summary")
+                .about("Show the executive summary only")
+                .args(range_args())
+                .args(tuning_args())
+                .args(budget_args()),
+        )
+        .subcommand(
+            Command::new("status")
+                .about("Print one short line of recent work for a shell prompt or tmux status bar, e.g. \"claude 2.4h · 3 sessions · api\"")
+                .arg(
+                    Arg::new("today")
+                        .long("today")
+                        .help("Today's work (default)")
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("days")
+                        .long("days")
+                        .value_name("N")
+                        .help("The last N days' work, including today")
+                        .value_parser(clap::value_parser!(u32).range(1..))
+                        .conflicts_with("today"),
+                )
+                .arg(project_arg())
+                .args(tuning_args()),
+        )
+        .subcommand(
+            Command::new("current-project-summary")
+                .about("Print recent sessions, open problems and topics of a directory's project as JSON, for editor extensions")
+                .arg(
+                    Arg::new("cwd")
+                        .long("cwd")
+                        .value_name("PATH")
+                        .help("Working directory whose project to summarize (default: the current directory)")
+                        .value_parser(clap::value_parser!(PathBuf)),
+                )
+                .arg(
+                    Arg::new("days")
+                        .long("days")
+                        .value_name("N")
+                        .help("Number of days to look back, including today")
+                        .value_parser(clap::value_parser!(u32).range(1..))
+                        .default_value("30"),
+                )
+                .arg(
+                    Arg::new("limit")
+                        .long("limit")
+                        .value_name("N")
+                        .help("Number of recent sessions to list")
+                        .value_parser(clap::value_parser!(usize))
+                        .default_value("5"),
+                )
+                .args(tuning_args()),
+        )
+        .subcommand(
+            Command::new("standup")
+                .about("Write a short standup update of the last days' work, formatted for pasting into Slack")
+                .arg(
+                    Arg::new("days")
+                        .long("days")
+                        .value_name("N")
+                        .help("Number of days before today to report, e.g. 3 on Monday to include the weekend")
+                        .value_parser(clap::value_parser!(u32).range(1..))
+                        .default_value("1"),
+                )
+                .arg(project_arg())
+                .args(tuning_args()),
+        )
+        .subcommand(
+            Command::new("weekly")
+                .about("Write the weekly report: this week against last week, planned hours, top topics and projects")
+                .arg(
+                    Arg::new("week")
+                        .long("week")
+                        .value_name("WEEK")
+                        .help("ISO week to report, e.g. 2025-W27 (default: this week)"),
+                )
+                .arg(
+                    Arg::new("plans")
+                        .long("plans")
+                        .value_name("FILE")
+                        .help("Plans file with the planned hours (default: <data dir>/claude-work-analysis/plans.json)"),
+                )
+                .arg(project_arg())
+                .args(tuning_args()),
+        )
+        .subcommand(
+            Command::new("export")
+                .about("Write a machine-readable report to a file")
+                .args(range_args())
+                .args(tuning_args())
+                .args(output_args(export_formats, "json", true))
+                .arg(git_arg())
+                .arg(grouping_arg())
+                .arg(sections_arg())
+                .arg(min_confidence_arg())
+                .arg(template_arg())
+                .args(budget_args()),
         )
-        .get_matches();
+        .subcommand(
+            Command::new("compare")
+                .about("Compare a period with a baseline, e.g. this sprint with the last one")
+                .args(range_args())
+                .args(baseline_args())
+                .args(tuning_args())
+                .args(output_args(&["markdown", "json"], "markdown", false)),
+        )
+        .subcommand(
+            Command::new("ingest")
+                .about("Read new and changed log files into the storage; entries already stored are skipped")
+                .arg(
+                    Arg::new("verify")
+                        .long("verify")
+                        .help("Compare the stored data with the log files instead, listing entries that differ")
+                        .action(ArgAction::SetTrue),
+                ),
+        )
+        .subcommand(
+            Command::new("history")
+                .about("Show hours, messages and tokens per day, week or month; instant with storage = \"sqlite\"")
+                .arg(
+                    Arg::new("days")
+                        .long("days")
+                        .value_name("N")
+                        .help("Number of days to show, including today")
+                        .value_parser(clap::value_parser!(i64).range(1..))
+                        .default_value("30"),
+                )
+                .arg(
+                    Arg::new("recorded")
+                        .long("recorded")
+                        .help("Show the hours and cost recorded by earlier reports, including days whose logs are gone")
+                        .action(ArgAction::SetTrue)
+                        .conflicts_with("days"),
+                )
+                .arg(grouping_arg().help("Total per day, week or month (default: day, week with --recorded)"))
+                .arg(listing_format_arg()),
+        )
+        .subcommand(
+            Command::new("archive")
+                .about("Copy new and changed log files to an archive directory before Claude Code deletes them")
+                .arg(
+                    Arg::new("dir")
+                        .long("dir")
+                        .value_name("DIR")
+                        .help("Archive directory (default: archive_dir in the config file, or <data dir>/claude-work-analysis/archive)"),
+                ),
+        )
+        .subcommand(
+            Command::new("import")
+                .about("Convert the history of another AI coding assistant into logs reported next to Claude Code's")
+                .arg(
+                    Arg::new("from")
+                        .long("from")
+                        .value_name("TOOL")
+                        .help("Assistant the history comes from: Codex CLI session logs, or chats exported from Copilot or Cursor as JSON")
+                        .value_parser(ImportSource::ALL.map(|source| source.name()))
+                        .required(true),
+                )
+                .arg(
+                    Arg::new("paths")
+                        .value_name("PATH")
+                        .help("Files or directories to import (default for codex: ~/.codex/sessions)")
+                        .num_args(0..),
+                )
+                .arg(
+                    Arg::new("cwd")
+                        .long("cwd")
+                        .value_name("DIR")
+                        .help("Project directory the exported chats were about (default: the current directory); Codex logs record their own"),
+                )
+                .arg(
+                    Arg::new("dir")
+                        .long("dir")
+                        .value_name("DIR")
+                        .help("Directory the logs are written to (default: import_dir in the config file, or <data dir>/claude-work-analysis/imported)"),
+                ),
+        )
+        .subcommand(
+            Command::new("bundle")
+                .about("Pack the selected logs into one file that can be analyzed on another machine, or unpack one")
+                .subcommand_required(true)
+                .subcommand(
+                    Command::new("export")
+                        .about("Write the entries of the selected sessions and their summary to a compressed bundle")
+                        .args(range_args())
+                        .args(tuning_args())
+                        .arg(
+                            Arg::new("output")
+                                .long("output")
+                                .short('o')
+                                .value_name("FILE")
+                                .help("Bundle file to write, e.g. work.cwa.gz")
+                                .required(true),
+                        ),
+                )
+                .subcommand(
+                    Command::new("import")
+                        .about("Unpack a bundle into a projects directory and show its summary")
+                        .arg(Arg::new("file").value_name("FILE").help("Bundle file").required(true))
+                        .arg(
+                            Arg::new("dir")
+                                .long("dir")
+                                .value_name("DIR")
+                                .help("Directory to unpack the logs to (default: <data dir>/claude-work-analysis/bundles/<bundle name>)"),
+                        ),
+                ),
+        )
+        .subcommand(
+            Command::new("replay")
+                .about("Print a session's conversation turn by turn, paced like it happened")
+                .arg(
+                    Arg::new("session")
+                        .value_name("SESSION_ID")
+                        .help("Session to replay, or the start of one (`sessions --format csv` lists them)")
+                        .required(true),
+                )
+                .arg(
+                    Arg::new("speed")
+                        .long("speed")
+                        .value_name("FACTOR")
+                        .help("How much faster than real time to replay, e.g. 10x")
+                        .default_value("1x"),
+                )
+                .arg(
+                    Arg::new("max-delay")
+                        .long("max-delay")
+                        .value_name("SECONDS")
+                        .help("Longest pause between two turns, so breaks do not stall the replay")
+                        .value_parser(clap::value_parser!(f64))
+                        .default_value("5"),
+                ),
+        )
+        .subcommand(
+            Command::new("serve")
+                .about("Run the MCP server over stdio, or a JSON API over HTTP with --port")
+                .arg(
+                    Arg::new("port")
+                        .long("port")
+                        .value_name("PORT")
+                        .help("Serve /summary, /projects, /projects/{name}, /sessions, /sessions/{id} and /search as JSON, and Prometheus /metrics, over HTTP on this port instead")
+                        .value_parser(clap::value_parser!(u16)),
+                )
+                .arg(
+                    Arg::new("bind")
+                        .long("bind")
+                        .value_name("ADDRESS")
+                        .help("Address the HTTP server listens on")
+                        .default_value("127.0.0.1")
+                        .requires("port"),
+                )
+                .arg(
+                    Arg::new("token")
+                        .long("token")
+                        .value_name("TOKEN")
+                        .help("Answer only requests with an Authorization: Bearer TOKEN header (default: api_token in the config file)")
+                        .requires("port"),
+                )
+                .arg(
+                    Arg::new("no-auth")
+                        .long("no-auth")
+                        .help("Serve without a token, so that anything able to connect can read the logs")
+                        .action(ArgAction::SetTrue)
+                        .conflicts_with("token")
+                        .requires("port"),
+                )
+                .arg(
+                    Arg::new("allow-origin")
+                        .long("allow-origin")
+                        .value_name("ORIGIN")
+                        .help("Let web pages from this origin, e.g. http://localhost:3000, read the API (repeatable; default: api_allowed_origins in the config file, none)")
+                        .action(ArgAction::Append)
+                        .requires("port"),
+                )
+                .arg(
+                    Arg::new("metrics-interval")
+                        .long("metrics-interval")
+                        .value_name("SECONDS")
+                        .help("How often /metrics is computed again")
+                        .default_value("60")
+                        .value_parser(clap::value_parser!(u64).range(1..))
+                        .requires("port"),
+                ),
+        )
+        .subcommand(
+            Command::new("index")
+                .about("Manage the parse cache, storage database and daily summaries that later runs and the MCP server read from")
+                .subcommand_required(true)
+                .subcommand(
+                    Command::new("build")
+                        .about("Parse every log file into the cache, bring the storage database and daily summaries up to date, and print statistics"),
+                ),
+        )
+        .subcommand(
+            Command::new("eval")
+                .about("Score activity classification, problem detection and topic extraction on labeled prompts")
+                .arg(
+                    Arg::new("corpus")
+                        .long("corpus")
+                        .value_name("FILE")
+                        .help("Labeled corpus laid out like eval/corpus.toml (default: the built-in one)")
+                        .value_parser(clap::value_parser!(PathBuf)),
+                ),
+        )
+        .subcommand(
+            Command::new("plan")
+                .about("Record estimated hours per project and compare them with actual work")
+                .subcommand_required(true)
+                .arg(
+                    Arg::new("file")
+                        .long("file")
+                        .value_name("FILE")
+                        .help("Plans file (default: <data dir>/claude-work-analysis/plans.json)")
+                        .global(true),
+                )
+                .subcommand(
+                    Command::new("set")
+                        .about("Set the planned hours of a project for a week")
+                        .arg(Arg::new("project").value_name("PROJECT").required(true))
+                        .arg(
+                            Arg::new("hours")
+                                .value_name("HOURS")
+                                .value_parser(clap::value_parser!(f64))
+                                .required(true),
+                        )
+                        .arg(
+                            Arg::new("week")
+                                .long("week")
+                                .value_name("DATE")
+                                .help("Any date in the planned week (default: this week)"),
+                        ),
+                )
+                .subcommand(
+                    Command::new("show")
+                        .about("Show planned vs actual hours per week")
+                        .arg(
+                            Arg::new("weeks")
+                                .long("weeks")
+                                .value_name("N")
+                                .help("Number of weeks to show, including this one")
+                                .value_parser(clap::value_parser!(i64))
+                                .default_value("4"),
+                        ),
+                ),
+        );
+    #[cfg(feature = "google-calendar")]
+    let cli = cli.subcommand(calendar_command());
+    #[cfg(feature = "embeddings")]
+    let cli = cli.subcommand(similar_command());
+    cli
+}
+
+/// Optional UTC start and end of the reported range
+type DateRange = (Option<DateTime<Utc>>, Option<DateTime<Utc>>);
+
+/// UTC bounds selected by `--from`/`--to` or `--period`
+fn selected_range(matches: &ArgMatches, schedule: &TimezoneSchedule) -> Result<DateRange> {
+    if let Some(period) = matches.get_one::<String>("period") {
+        let period: ReportingPeriod = period.parse()?;
+        let periods_ago = *matches.get_one::<u32>("periods-ago").unwrap();
+        let today = schedule.to_local(Utc::now()).date_naive();
+        let (first_day, last_day) = period.nth_back(today, periods_ago);
+        return Ok((Some(schedule.start_of_day(first_day)), Some(schedule.end_of_day(last_day))));
+    }
 
-    // Parse command line arguments
     let from_date = matches
         .get_one::<String>("from")
-        .map(|s| parse_date_string(s).expect("Invalid from date format"));
-    
+        .map(|s| parse_date_string(s, schedule))
+        .transpose()?;
     let to_date = matches
         .get_one::<String>("to")
-        .map(|s| parse_end_date_string(s).expect("Invalid to date format"));
-    
+        .map(|s| parse_end_date_string(s, schedule))
+        .transpose()?;
+    Ok((from_date, to_date))
+}
+
+/// UTC bounds of the `compare` baseline
+///
+/// Without `--baseline-from`/`--baseline-to`, the baseline is the period just
+/// before the current one: the previous `--period`, or as many days before `--from`.
+/// A `--baseline-from` alone runs up to the day before the current period.
+fn baseline_range(matches: &ArgMatches, schedule: &TimezoneSchedule) -> Result<DateRange> {
+    let baseline_from = matches.get_one::<String>("baseline-from");
+    let baseline_to = matches.get_one::<String>("baseline-to");
+    if baseline_from.is_some() || baseline_to.is_some() {
+        let baseline_to = match baseline_to {
+            Some(s) => Some(parse_end_date_string(s, schedule)?),
+            None => selected_range(matches, schedule)?
+                .0
+                .map(|start| schedule.end_of_day(schedule.to_local(start).date_naive() - Duration::days(1))),
+        };
+        return Ok((baseline_from.map(|s| parse_date_string(s, schedule)).transpose()?, baseline_to));
+    }
+
+    let today = schedule.to_local(Utc::now()).date_naive();
+    let (first_day, last_day) = if let Some(period) = matches.get_one::<String>("period") {
+        let period: ReportingPeriod = period.parse()?;
+        let periods_ago = *matches.get_one::<u32>("periods-ago").unwrap();
+        period.nth_back(today, periods_ago + 1)
+    } else if let Some(from) = matches.get_one::<String>("from") {
+        let from = parse_naive_date(from)?;
+        let to = matches.get_one::<String>("to").map(|s| parse_naive_date(s)).transpose()?.unwrap_or(today);
+        let days = (to - from).num_days() + 1;
+        (from - Duration::days(days), from - Duration::days(1))
+    } else {
+        return Err(anyhow::anyhow!(
+            "compare needs a baseline: pass --baseline-from/--baseline-to, or --from or --period to compare with the period before"
+        ));
+    };
+    Ok((Some(schedule.start_of_day(first_day)), Some(schedule.end_of_day(last_day))))
+}
+
+/// Entry filter for the range and `--project` arguments
+fn selection_filter(matches: &ArgMatches, schedule: &TimezoneSchedule) -> Result<TimeRangeFilter> {
+    let (from_date, to_date) = selected_range(matches, schedule)?;
     let project_filter = matches.get_one::<String>("project").cloned();
-    let output_path = matches.get_one::<String>("output").map(PathBuf::from);
-    let format = matches.get_one::<String>("format").unwrap();
+    Ok(TimeRangeFilter::new(from_date, to_date, project_filter))
+}
 
-    // Create filter
-    let filter = TimeRangeFilter::new(from_date, to_date, project_filter);
-
-    // Scan Claude projects directory
-    let scanner = ProjectScanner::new();
-    let projects_dir = dirs::home_dir()
-        .expect("Cannot find home directory")
-        .join(".claude")
-        .join("projects");
-    
-    let jsonl_files = scanner.scan_projects(&projects_dir)?;
-    
-    // Parse and filter entries
-    let parser = JsonlParser::new();
-    let mut all_entries = Vec::new();
-    
-    for file_path in jsonl_files {
-        let entries = parser.parse_file(&file_path).await?;
-        let filtered_entries = filter.filter_entries(entries);
-        all_entries.extend(filtered_entries);
-    }
-
-    // Analyze work patterns
-    let analyzer = WorkAnalyzer::new();
-    let analysis = analyzer.analyze_entries(&all_entries)?;
-
-    // Generate report
-    let reporter = ReportGenerator::new();
-    let report = match format.as_str() {
-        "json" => reporter.generate_json_report(&analysis)?,
-        _ => reporter.generate_markdown_report(&analysis)?,
-    };
-
-    // Output report
-    if let Some(output_path) = output_path {
-        std::fs::write(output_path, report)?;
+/// WorkAnalyzer configured by the tuning arguments
+fn selection_analyzer(
+    matches: &ArgMatches,
+    storage: &StorageBackend<'_>,
+    schedule: &TimezoneSchedule,
+    config: &Config,
+) -> Result<WorkAnalyzer> {
+    let mut token_analyzer = TokenAnalyzer::new();
+    if let Some(path) = matches.get_one::<String>("prices") {
+        token_analyzer = token_analyzer.with_prices(PriceTable::from_file(Path::new(path))?);
+    }
+    let tuning = AnalysisTuning {
+        session_gap: matches.get_one::<i64>("session-gap").map(|minutes| Duration::minutes(*minutes)),
+        min_messages: matches.get_one::<u64>("min-messages").map(|min_messages| *min_messages as usize),
+        merge_restarts: matches.get_one::<i64>("merge-restarts").map(|minutes| Duration::minutes(*minutes)),
+        include_thinking: matches.get_flag("include-thinking").then_some(true),
+        deep_topics: matches.get_flag("deep").then_some(true),
+        ..Default::default()
+    };
+    let mut analyzer = tuning.apply(
+        config
+            .analyzer()
+            .with_token_analyzer(token_analyzer)
+            .with_timezone_schedule(schedule.clone())
+            .with_project_dirs(&storage.project_directories()?)
+            .with_profiler(storage.source().parser().profiler().clone()),
+    );
+    // Only the full-report commands accept --group-by
+    if let Some(grouping) = matches.try_get_one::<String>("group-by").ok().flatten() {
+        analyzer = analyzer.with_grouping(grouping.parse()?);
+    }
+    if matches.try_get_one::<bool>("low-memory").ok().flatten() == Some(&true) {
+        analyzer = analyzer.with_session_entries(false);
+    }
+    if matches.get_flag("redact") {
+        analyzer = analyzer.with_redactor(config.redactor()?);
+    }
+    Ok(analyzer)
+}
+
+/// An analysis of the selected logs, with what was searched to produce it
+struct Selection {
+    analysis: WorkAnalysis,
+    filter: TimeRangeFilter,
+    coverage: FilterCoverage,
+}
+
+impl Selection {
+    /// The report explaining the filters and data coverage when no session was found
+    fn no_activity_report(&self, reporter: &ReportGenerator) -> Option<String> {
+        (self.analysis.total_sessions == 0)
+            .then(|| reporter.generate_no_activity_report(&self.filter, &self.coverage))
+    }
+}
+
+/// Parse, filter and analyze the logs selected by the range and tuning arguments
+async fn analyze_selection(
+    matches: &ArgMatches,
+    storage: &StorageBackend<'_>,
+    schedule: &TimezoneSchedule,
+    config: &Config,
+) -> Result<Selection> {
+    let filter = selection_filter(matches, schedule)?;
+    let analyzer = selection_analyzer(matches, storage, schedule, config)?;
+    let (mut analysis, coverage) = storage.analyze(&filter, &analyzer).await?;
+    if matches.try_get_one::<bool>("git").ok().flatten() == Some(&true) {
+        analysis.commits = Some(git::correlate(&analysis.sessions));
+    }
+
+    let local_day = |time: DateTime<Utc>| time.with_timezone(&schedule.offset_at(time)).date_naive();
+    // Days without the sessions of one project are expected, only days without any are suspicious
+    let active_days = analysis
+        .sessions
+        .iter()
+        .filter(|_| filter.get_project_filter().is_none())
+        .map(|session| local_day(session.start_time));
+    analysis.log_gaps = Some(LogGaps::find(
+        active_days,
+        filter.get_date_range().0.map(local_day),
+        coverage.first_entry.map(local_day),
+        DEFAULT_MIN_GAP_DAYS,
+    ));
+    // Only the reports a cron job runs accept --budget-monthly
+    if let Some(&budget) = matches.try_get_one::<f64>("budget-monthly").ok().flatten() {
+        analysis.budget = Some(month_to_date_budget(budget, storage, &analyzer, &filter, &analysis, schedule).await?);
+    }
+    Ok(Selection { analysis, filter, coverage })
+}
+
+/// The spend of every project this month, analyzing the month again unless the selection covers it
+async fn month_to_date_budget(
+    budget: f64,
+    storage: &StorageBackend<'_>,
+    analyzer: &WorkAnalyzer,
+    filter: &TimeRangeFilter,
+    analysis: &WorkAnalysis,
+    schedule: &TimezoneSchedule,
+) -> Result<BudgetStatus> {
+    let now = Utc::now();
+    let today = schedule.to_local(now).date_naive();
+    let month_start = schedule.start_of_day(BudgetStatus::month_of(today).0);
+    let (from, to) = filter.get_date_range();
+    let covered = filter.get_project_filter().is_none()
+        && from.is_none_or(|from| from <= month_start)
+        && to.is_none_or(|to| to >= now);
+    let month = if covered {
+        None
     } else {
+        Some(storage.analyze(&TimeRangeFilter::new(Some(month_start), None, None), analyzer).await?.0)
+    };
+    let daily_costs = month
+        .as_ref()
+        .unwrap_or(analysis)
+        .token_analysis
+        .iter()
+        .flat_map(|tokens| tokens.by_day.iter().map(|(day, stats)| (*day, stats.estimated_cost)));
+    Ok(BudgetStatus::new(budget, daily_costs, today))
+}
+
+/// `--fail-on-budget`: fail once the report is out when the month is on track to exceed the budget
+fn check_budget(args: &ArgMatches, analysis: &WorkAnalysis) -> Result<()> {
+    match &analysis.budget {
+        Some(budget) if args.get_flag("fail-on-budget") && budget.exceeded() => {
+            Err(Error::BudgetExceeded { projected: budget.projected(), budget: budget.monthly_budget }.into())
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Copy the logs to the archive directory and tell how to read them from there
+fn run_archive(matches: &ArgMatches, projects_dirs: &[PathBuf], config: &Config) -> Result<()> {
+    let archive_dir = match (matches.get_one::<String>("dir"), &config.archive_dir) {
+        (Some(dir), _) => expand_home(Path::new(dir))?,
+        (None, Some(dir)) => expand_home(dir)?,
+        (None, None) => default_archive_dir().ok_or_else(|| anyhow::anyhow!("Cannot find data directory, pass --dir"))?,
+    };
+    let counts = archive_logs(projects_dirs, &archive_dir)?;
+    println!(
+        "Archived {} new or changed log files to {} ({} already archived)",
+        counts.copied,
+        archive_dir.display(),
+        counts.unchanged
+    );
+    if !projects_dirs.contains(&archive_dir) {
+        println!("Add \"{}\" to projects_dirs in the config file to include the archived logs in reports", archive_dir.display());
+    }
+    Ok(())
+}
+
+/// Convert another assistant's history into logs and tell how to read them
+fn run_import(matches: &ArgMatches, projects_dirs: &[PathBuf], config: &Config) -> Result<()> {
+    let source = ImportSource::from_name(matches.get_one::<String>("from").unwrap()).unwrap();
+    let import_dir = match (matches.get_one::<String>("dir"), &config.import_dir) {
+        (Some(dir), _) => expand_home(Path::new(dir))?,
+        (None, Some(dir)) => expand_home(dir)?,
+        (None, None) => default_import_dir().ok_or_else(|| anyhow::anyhow!("Cannot find data directory, pass --dir"))?,
+    };
+    let paths: Vec<PathBuf> = match matches.get_many::<String>("paths") {
+        Some(paths) => paths.map(|path| expand_home(Path::new(path))).collect::<Result<_>>()?,
+        None => vec![source
+            .default_path()
+            .ok_or_else(|| anyhow::anyhow!("Pass the {} files or directories to import", source.name()))?],
+    };
+    let cwd = match matches.get_one::<String>("cwd") {
+        Some(cwd) => expand_home(Path::new(cwd))?,
+        None => std::env::current_dir().context("Failed to read the current directory")?,
+    };
+    let counts = import_history(source, &paths, &cwd.to_string_lossy(), &import_dir)?;
+    println!(
+        "Imported {} {} sessions ({} messages) from {} files to {}",
+        counts.sessions,
+        source.name(),
+        counts.entries,
+        counts.files,
+        import_dir.display()
+    );
+    if !projects_dirs.contains(&import_dir) {
+        println!("Add \"{}\" to projects_dirs in the config file to include the imported history in reports", import_dir.display());
+    }
+    Ok(())
+}
+
+/// Export the selected sessions to a bundle, or unpack one to analyze it with `--projects-dir`
+async fn run_bundle(
+    matches: &ArgMatches,
+    storage: &StorageBackend<'_>,
+    schedule: &TimezoneSchedule,
+    config: &Config,
+    reporter: &ReportGenerator,
+) -> Result<()> {
+    match matches.subcommand() {
+        Some(("export", args)) => {
+            let selection = analyze_selection(args, storage, schedule, config).await?;
+            let analysis = &selection.analysis;
+            if analysis.total_sessions == 0 {
+                return Err(anyhow::anyhow!("No sessions to bundle in the selected range"));
+            }
+            let local_day = |time: DateTime<Utc>| schedule.to_local(time).date_naive();
+            let (start, end) = selection.filter.get_date_range();
+            let days = (
+                local_day(start.unwrap_or(analysis.time_range.0)),
+                local_day(end.unwrap_or(analysis.time_range.1)),
+            );
+            let project = selection.filter.get_project_filter().map(str::to_string);
+            let bundle = Bundle::from_analysis(analysis, days, project, args.get_flag("redact"), Utc::now());
+            let path = PathBuf::from(args.get_one::<String>("output").unwrap());
+            bundle.write(&path)?;
+            eprintln!(
+                "Bundled {} sessions ({} entries{}) to {}",
+                bundle.manifest.summary.sessions,
+                bundle.manifest.entries,
+                if bundle.manifest.redacted { ", redacted" } else { "" },
+                path.display()
+            );
+        }
+        Some(("import", args)) => {
+            let path = PathBuf::from(args.get_one::<String>("file").unwrap());
+            let dir = match args.get_one::<String>("dir") {
+                Some(dir) => expand_home(Path::new(dir))?,
+                None => default_unpack_dir(&path).ok_or_else(|| anyhow::anyhow!("Cannot find data directory, pass --dir"))?,
+            };
+            let bundle = Bundle::read(&path)?;
+            let sessions = bundle.unpack(&dir)?;
+            print!("{}", reporter.generate_bundle_summary(&bundle.manifest));
+            println!(
+                "\nUnpacked {} sessions to {}; analyze them with --projects-dir \"{}\"",
+                sessions,
+                dir.display(),
+                dir.display()
+            );
+        }
+        _ => unreachable!("clap requires a bundle subcommand"),
+    }
+    Ok(())
+}
+
+/// Print the turns of a session, waiting between them for the time that passed
+/// divided by `--speed`
+async fn run_replay(matches: &ArgMatches, storage: &StorageBackend<'_>, schedule: &TimezoneSchedule) -> Result<()> {
+    let speed: ReplaySpeed = matches.get_one::<String>("speed").unwrap().parse()?;
+    let max_delay = *matches.get_one::<f64>("max-delay").unwrap();
+    if !max_delay.is_finite() || max_delay < 0.0 {
+        return Err(anyhow::anyhow!("--max-delay must be zero or more seconds"));
+    }
+    let max_delay = StdDuration::from_secs_f64(max_delay);
+    let entries = load_entries(storage, &TimeRangeFilter::default()).await?;
+    let session_id = find_session(&entries, matches.get_one::<String>("session").unwrap())?;
+    let session: Vec<ClaudeLogEntry> = entries.into_iter().filter(|entry| entry.session_id == session_id).collect();
+    let turns = replay_turns(&session);
+    let (Some(first), Some(last)) = (turns.first(), turns.last()) else {
+        return Err(anyhow::anyhow!("Session {} has no messages to replay", session_id));
+    };
+
+    let color = std::io::stdout().is_terminal() && std::env::var_os("NO_COLOR").is_none();
+    println!(
+        "Replaying session {} in {} ({} turns over {} minutes at {}x)\n",
+        session_id,
+        session[0].cwd,
+        turns.len(),
+        (last.at - first.at).num_minutes(),
+        speed.factor()
+    );
+    let mut previous = first.at;
+    for turn in &turns {
+        tokio::time::sleep(replay_delay(turn.at - previous, speed, max_delay)).await;
+        previous = turn.at;
+        println!("{}", format_turn(turn, schedule, color));
+    }
+    Ok(())
+}
+
+/// Where reports are recorded, none for the demo data or without a data directory
+fn report_history_path(config: &Config, sample: bool) -> Result<Option<PathBuf>> {
+    if sample {
+        return Ok(None);
+    }
+    match &config.report_history {
+        Some(path) => expand_home(path).map(Some),
+        None => Ok(ReportHistory::default_path()),
+    }
+}
+
+/// Message content the command reads, so that entries are parsed no further
+fn content_level(command: &str, args: &ArgMatches, reporter: &ReportGenerator) -> ContentLevel {
+    let format = args.try_get_one::<String>("format").ok().flatten().map(String::as_str);
+    let flag = |name: &str| args.try_get_one::<bool>(name).ok().flatten() == Some(&true);
+    let output_dir = args.try_get_one::<String>("output-dir").ok().flatten().is_some();
+    match command {
+        "history" | "projects" | "status" => ContentLevel::Metadata,
+        // The CSV has the thinking volume of each session
+        "sessions" if format == Some("csv") => ContentLevel::Outline,
+        "sessions" => ContentLevel::Metadata,
+        "summary" => ReportSection::Summary.content_level(),
+        // Watching, project reports and related sessions read everything
+        "analyze" | "export" if flag("watch") || flag("daemon") || flag("related") || output_dir => ContentLevel::Full,
+        "analyze" | "export" => match format {
+            Some("markdown") => reporter.markdown_content_level(),
+            Some("csv") => ContentLevel::Outline,
+            Some("csv-daily" | "json-timeseries" | "aggregate" | "html") => ContentLevel::Metadata,
+            _ => ContentLevel::Full,
+        },
+        _ => ContentLevel::Full,
+    }
+}
+
+/// Add the report's headline metrics to the report history
+///
+/// A history that cannot be written only warns; the report itself was produced.
+fn record_report(history_path: Option<&Path>, matches: &ArgMatches, selection: &Selection, tz: &FixedOffset) {
+    let Some(path) = history_path else {
+        return;
+    };
+    let project = matches.try_get_one::<String>("project").ok().flatten().map(String::as_str);
+    let recorded = ReportHistory::load(path).and_then(|mut history| {
+        history.record(&selection.analysis, tz, project, Utc::now());
+        history.save()
+    });
+    if let Err(e) = recorded {
+        eprintln!("Warning: Failed to record the report in the history: {:#}", e);
+    }
+}
+
+/// Render the full report in the `--format` requested
+fn render_report(matches: &ArgMatches, reporter: &ReportGenerator, selection: &Selection) -> Result<String> {
+    let analysis = &selection.analysis;
+    match matches.get_one::<String>("format").unwrap().as_str() {
+        "json" => reporter.generate_json_report(analysis),
+        "csv" => reporter.generate_csv_report(analysis),
+        "csv-daily" => reporter.generate_daily_csv_report(analysis),
+        "json-timeseries" => reporter.generate_json_timeseries(analysis, selection.filter.get_date_range(), Utc::now()),
+        "aggregate" => reporter.generate_aggregate_export(analysis),
+        "html" => Ok(reporter.generate_html_report(analysis)),
+        "compact" => Ok(reporter.generate_compact_report(analysis, Utc::now())),
+        _ => match selection.no_activity_report(reporter) {
+            Some(report) => Ok(report),
+            None => reporter.generate_markdown_report(analysis),
+        },
+    }
+}
+
+/// Re-render the report whenever new log entries are written
+///
+/// Files are parsed once up front; afterwards only the files reported by the
+/// watcher are parsed again. An analysis still running when more changes arrive
+/// is cancelled, since its report would be outdated before it is shown.
+async fn run_watch(
+    matches: &ArgMatches,
+    storage: &StorageBackend<'_>,
+    schedule: &TimezoneSchedule,
+    config: &Config,
+    reporter: &ReportGenerator,
+) -> Result<()> {
+    let tz = schedule.offset_at(Utc::now());
+    let filter = selection_filter(matches, schedule)?;
+    // Changed files are parsed directly; the storage backend answers whole-range queries
+    let (parser, projects_dirs) = (storage.source().parser(), storage.source().projects_dirs());
+    let mut watcher = LogWatcher::watch_all(projects_dirs)?;
+
+    let mut files: HashMap<PathBuf, (Vec<ClaudeLogEntry>, FilterCoverage)> = HashMap::new();
+    // Every project's entries of the summarized days, whatever the report shows
+    let today = schedule.to_local(Utc::now()).date_naive();
+    let recent_filter = TimeRangeFilter::new(Some(schedule.start_of_day(today - Duration::days(i64::from(CACHED_DAYS) - 1))), None, None);
+    let mut recent: HashMap<PathBuf, Vec<ClaudeLogEntry>> = HashMap::new();
+    for path in storage.source().log_files()? {
+        let entries = parser.parse_file(&path).await?;
+        recent.insert(path.clone(), entries.iter().filter(|entry| recent_filter.matches_entry(entry)).cloned().collect());
+        let mut coverage = FilterCoverage::default();
+        let entries = filter.filter_entries_with_coverage(entries, &mut coverage);
+        files.insert(path, (entries, coverage));
+    }
+    store_daily_summaries(storage, config, schedule, &recent.values().flatten().cloned().collect::<Vec<_>>());
+    eprintln!("Watching {} (Ctrl-C to stop)", display_dirs(projects_dirs));
+
+    let clear_screen = matches.get_one::<String>("output").is_none() && std::io::stdout().is_terminal();
+    let mut rendered_entries = None;
+    loop {
+        let mut coverage = FilterCoverage::default();
+        for (_, file_coverage) in files.values() {
+            coverage.merge(file_coverage);
+        }
+        // Logs are append-only, so an unchanged count means nothing new to show
+        if rendered_entries != Some(coverage.matched_entries) {
+            let all_entries: Vec<ClaudeLogEntry> = files.values().flat_map(|(entries, _)| entries).cloned().collect();
+            let cancellation = CancellationToken::new();
+            let analyzer = selection_analyzer(matches, storage, schedule, config)?.with_cancellation(cancellation.clone());
+            let mut analysis = tokio::task::spawn_blocking(move || analyzer.analyze_entries(&all_entries));
+            let analysis = tokio::select! {
+                result = &mut analysis => result?,
+                true = watcher.changed() => {
+                    cancellation.cancel();
+                    analysis.await?
+                }
+            };
+            match analysis {
+                Ok(analysis) => {
+                    let selection = Selection {
+                        analysis,
+                        filter: filter.clone(),
+                        coverage,
+                    };
+                    let report = render_report(matches, reporter, &selection)?;
+                    if clear_screen {
+                        print!("\x1b[2J\x1b[H");
+                    }
+                    emit_report(matches, &report, &tz)?;
+                    rendered_entries = Some(selection.coverage.matched_entries);
+                }
+                // The changes that cancelled it are read below and analyzed next
+                Err(e) if Error::is_cancellation(&e) => {}
+                Err(e) => return Err(e),
+            }
+        }
+
+        let Some(batch) = watcher.next_batch().await else {
+            return Ok(());
+        };
+        let patterns = storage.source().project_patterns();
+        for path in batch.into_iter().filter(|path| patterns.selects_file(projects_dirs, path)) {
+            match parser.parse_file(&path).await {
+                Ok(entries) => {
+                    recent.insert(path.clone(), entries.iter().filter(|entry| recent_filter.matches_entry(entry)).cloned().collect());
+                    let mut coverage = FilterCoverage::default();
+                    let entries = filter.filter_entries_with_coverage(entries, &mut coverage);
+                    files.insert(path, (entries, coverage));
+                }
+                // Usually a file caught mid-write; the next change re-parses it
+                Err(e) => eprintln!("Failed to parse {}: {}", path.display(), e),
+            }
+        }
+        store_daily_summaries(storage, config, schedule, &recent.values().flatten().cloned().collect::<Vec<_>>());
+    }
+}
+
+/// Print the report, or write it to `--output` according to `--output-mode`
+fn emit_report(matches: &ArgMatches, report: &str, tz: &FixedOffset) -> Result<()> {
+    let Some(output_path) = matches.get_one::<String>("output").map(PathBuf::from) else {
         println!("{}", report);
+        return Ok(());
+    };
+
+    // Dated file names follow the report's calendar
+    let today = Utc::now().with_timezone(tz).date_naive();
+    let written_path = report_writer(matches)?.write(&output_path, report, today)?;
+    if written_path != output_path {
+        eprintln!("Report written to {}", written_path.display());
+    }
+    Ok(())
+}
+
+/// The writer for `--output-mode` and `--keep-days`
+fn report_writer(matches: &ArgMatches) -> Result<ReportWriter> {
+    let output_mode: OutputMode = matches.get_one::<String>("output-mode").unwrap().parse()?;
+    let keep_days = matches.get_one::<i64>("keep-days").copied();
+    if keep_days.is_some() && output_mode != OutputMode::Dated {
+        return Err(anyhow::anyhow!("--keep-days can only be used with --output-mode dated"));
     }
 
+    let mut writer = ReportWriter::new(output_mode);
+    if let Some(days) = keep_days {
+        writer = writer.with_retention(days);
+    }
+    Ok(writer)
+}
+
+/// Write a markdown report per project and an index linking them into `--output-dir`
+async fn write_project_reports(
+    matches: &ArgMatches,
+    storage: &StorageBackend<'_>,
+    schedule: &TimezoneSchedule,
+    config: &Config,
+    reporter: &ReportGenerator,
+) -> Result<Selection> {
+    let format = matches.get_one::<String>("format").unwrap();
+    if format != "markdown" {
+        return Err(anyhow::anyhow!("--output-dir writes markdown reports and cannot be used with --format {}", format));
+    }
+    let output_dir = PathBuf::from(matches.get_one::<String>("output-dir").unwrap());
+    std::fs::create_dir_all(&output_dir)
+        .with_context(|| format!("Failed to create report directory: {}", output_dir.display()))?;
+
+    let selection = analyze_selection(matches, storage, schedule, config).await?;
+    let analyzer = selection_analyzer(matches, storage, schedule, config)?;
+    let writer = report_writer(matches)?;
+    let today = Utc::now().with_timezone(&schedule.offset_at(Utc::now())).date_naive();
+
+    let mut projects: Vec<&str> = selection.analysis.project_stats.keys().map(String::as_str).collect();
+    projects.sort();
+    let file_names = project_file_names(projects.iter().copied());
+    for project in &projects {
+        let mut analysis = analyzer.analyze_project(&selection.analysis, project)?;
+        let commits = selection.analysis.commits.as_ref().map(|commits| {
+            commits
+                .iter()
+                .filter(|session| analysis.sessions.iter().any(|s| (s.session_id, s.start_time) == (session.session_id, session.start_time)))
+                .cloned()
+                .collect()
+        });
+        analysis.commits = commits;
+        analysis.log_gaps = selection.analysis.log_gaps.clone();
+        writer.write(&output_dir.join(&file_names[*project]), &reporter.generate_markdown_report(&analysis)?, today)?;
+    }
+
+    let index = selection
+        .no_activity_report(reporter)
+        .unwrap_or_else(|| reporter.generate_project_index_report(&selection.analysis, &file_names));
+    let index_path = writer.write(&output_dir.join(INDEX_FILE_NAME), &index, today)?;
+    eprintln!("Wrote {} project reports and {}", projects.len(), index_path.display());
+    Ok(selection)
+}
+
+/// Read the `--daemon` settings
+fn daemon_options(matches: &ArgMatches, schedule: &TimezoneSchedule) -> Result<DaemonOptions> {
+    let config = MilestoneConfig {
+        daily_goal: matches
+            .get_one::<f64>("daily-goal-hours")
+            .map(|hours| Duration::minutes((hours * 60.0) as i64)),
+        long_session: matches
+            .get_one::<i64>("long-session-minutes")
+            .map(|minutes| Duration::minutes(*minutes)),
+        monthly_budget: matches.get_one::<f64>("budget-monthly").copied(),
+    };
+    let end_of_day = match matches.get_one::<String>("end-of-day") {
+        Some(time) => Some(EndOfDayConfig {
+            not_before: NaiveTime::parse_from_str(time, "%H:%M")
+                .map_err(|e| anyhow::anyhow!("Invalid --end-of-day '{}': {}. Expected HH:MM", time, e))?,
+            idle: Duration::minutes((matches.get_one::<f64>("idle-hours").unwrap() * 60.0) as i64),
+        }),
+        None => None,
+    };
+    let journal_dir = match matches.get_one::<String>("journal-dir") {
+        Some(dir) => PathBuf::from(dir),
+        None => dirs::data_dir()
+            .ok_or_else(|| anyhow::anyhow!("Cannot find data directory, pass --journal-dir"))?
+            .join("claude-work-analysis")
+            .join("journal"),
+    };
+
+    Ok(DaemonOptions {
+        project_filter: matches.get_one::<String>("project").cloned(),
+        milestones: config,
+        end_of_day,
+        journal_dir,
+        schedule: schedule.clone(),
+    })
+}
+
+/// Run the `mcp-server` binary installed next to this one, with stdio passed through
+async fn run_serve(matches: &ArgMatches) -> Result<()> {
+    let server = std::env::current_exe()?.with_file_name(format!("mcp-server{}", std::env::consts::EXE_SUFFIX));
+    let mut command = tokio::process::Command::new(&server);
+    if matches.get_flag("no-refresh") {
+        command.arg("--no-refresh");
+    }
+    for dir in matches.get_many::<PathBuf>("projects-dir").into_iter().flatten() {
+        command.arg("--projects-dir").arg(dir);
+    }
+    if let Some(path) = matches.get_one::<PathBuf>("keywords") {
+        command.arg("--keywords").arg(path);
+    }
+    if let Some(language) = matches.get_one::<String>("language") {
+        command.arg("--language").arg(language);
+    }
+    if let Some(path) = matches.get_one::<String>("timezone-schedule") {
+        command.arg("--timezone-schedule").arg(path);
+    }
+    for flag in ["include", "exclude"] {
+        for pattern in matches.get_many::<String>(flag).into_iter().flatten() {
+            command.arg(format!("--{}", flag)).arg(pattern);
+        }
+    }
+    let status = command
+        .status()
+        .await
+        .with_context(|| format!("Failed to start {}", server.display()))?;
+    if !status.success() {
+        std::process::exit(status.code().unwrap_or(1));
+    }
     Ok(())
-}
\ No newline at end of file
+}
+
+/// The token `serve --port` requires, none only when `--no-auth` asks for that
+fn http_token(matches: &ArgMatches, config: &Config) -> Result<Option<String>> {
+    if matches.get_flag("no-auth") {
+        return Ok(None);
+    }
+    match matches.get_one::<String>("token").or(config.api_token.as_ref()) {
+        Some(token) if token.trim().is_empty() => Err(anyhow::anyhow!(
+            "The HTTP API token must not be empty; pass --no-auth to serve it without authentication"
+        )),
+        Some(token) => Ok(Some(token.trim().to_string())),
+        None => Err(anyhow::anyhow!(
+            "The HTTP API serves your prompts and transcripts; pass --token TOKEN or set api_token in the config file, or --no-auth to serve it without authentication"
+        )),
+    }
+}
+
+/// Serve the analysis as a JSON API over HTTP until interrupted
+async fn run_http_server(
+    matches: &ArgMatches,
+    parser: &JsonlParser,
+    projects_dirs: &[PathBuf],
+    schedule: &TimezoneSchedule,
+    config: &Config,
+) -> Result<()> {
+    let port = *matches.get_one::<u16>("port").unwrap();
+    let bind = matches.get_one::<String>("bind").unwrap();
+    let listener = tokio::net::TcpListener::bind((bind.as_str(), port))
+        .await
+        .with_context(|| format!("Failed to listen on {}:{}", bind, port))?;
+    let token = http_token(matches, config)?;
+    let access = if token.is_some() { "with a bearer token" } else { "without authentication" };
+    eprintln!("Serving the JSON API and /metrics on http://{} {} (Ctrl-C to stop)", listener.local_addr()?, access);
+    let interval = std::time::Duration::from_secs(*matches.get_one::<u64>("metrics-interval").unwrap());
+    let origins = match matches.get_many::<String>("allow-origin") {
+        Some(origins) => origins.cloned().collect(),
+        None => config.api_allowed_origins.clone(),
+    };
+    let server = Arc::new(
+        HttpServer::new(projects_dirs.to_vec(), parser.clone(), config.clone(), schedule.clone())
+            .with_token(token)
+            .with_allowed_origins(origins),
+    );
+    tokio::select! {
+        result = Arc::clone(&server).serve(listener) => result,
+        _ = server.refresh_metrics_every(interval) => Ok(()),
+        _ = tokio::signal::ctrl_c() => Ok(()),
+    }
+}
+
+/// Reject `analyze` flags given before another subcommand, where they would be ignored
+fn check_top_level_args(matches: &ArgMatches) -> Result<()> {
+    let Some((command, _)) = matches.subcommand() else {
+        return Ok(());
+    };
+    for arg in analyze_args() {
+        if matches.value_source(arg.get_id().as_str()) == Some(ValueSource::CommandLine) {
+            return Err(anyhow::anyhow!(
+                "--{} must come after the `{}` subcommand",
+                arg.get_id(),
+                command
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Make the config file's `format` the default of every `--format` that accepts it
+fn with_config_defaults(cli: Command, config: &Config) -> Command {
+    match config.output_format() {
+        Some(format) => with_default_format(cli, format),
+        None => cli,
+    }
+}
+
+fn with_default_format(mut command: Command, format: &'static str) -> Command {
+    let accepts_format = command.get_arguments().any(|arg| {
+        arg.get_id() == "format" && arg.get_possible_values().iter().any(|value| value.get_name() == format)
+    });
+    if accepts_format {
+        command = command.mut_arg("format", |arg| arg.default_value(format));
+    }
+    let subcommands: Vec<String> = command.get_subcommands().map(|sub| sub.get_name().to_string()).collect();
+    for name in subcommands {
+        command = command.mut_subcommand(name, |sub| with_default_format(sub, format));
+    }
+    command
+}
+
+/// Log directories from `--projects-dir`, else from the config file, else the default one
+fn projects_dirs(matches: &ArgMatches, config: &Config) -> Result<Vec<PathBuf>> {
+    match matches.get_many::<PathBuf>("projects-dir") {
+        Some(dirs) => dirs.map(|dir| expand_home(dir)).collect(),
+        None => config.resolved_projects_dirs(),
+    }
+}
+
+/// Directories for a status line, e.g. `/a/projects, /b/projects`
+fn display_dirs(dirs: &[PathBuf]) -> String {
+    dirs.iter().map(|dir| dir.display().to_string()).collect::<Vec<_>>().join(", ")
+}
+
+/// Timezones for interpreting `--from`/`--to` and rendering reports
+///
+/// Dates are local days in the configured timezone unless `--utc` is given.
+fn timezone_schedule(matches: &ArgMatches, config: &Config) -> Result<TimezoneSchedule> {
+    if matches.get_flag("utc") {
+        return Ok(TimezoneSchedule::fixed(FixedOffset::east_opt(0).unwrap()));
+    }
+
+    let timezone = matches.get_one::<String>("timezone").or(config.timezone.as_ref());
+    let tz = resolve_timezone(timezone.map(String::as_str))?;
+    match matches.get_one::<String>("timezone-schedule") {
+        Some(path) => TimezoneSchedule::from_file(Path::new(path), tz),
+        None => Ok(TimezoneSchedule::fixed(tz)),
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+#[tokio::main]
+async fn main() {
+    // Show the message chain only; a backtrace does not help with a mistyped date
+    if let Err(e) = run().await {
+        eprintln!("Error: {:#}", e);
+        std::process::exit(1);
+    }
+}
+
+/// Log to stderr at the level `--verbose` or `--quiet` chose, around the progress bar
+fn init_logging(matches: &ArgMatches, progress: &ParseProgress) {
+    let level = if matches.get_flag("verbose") {
+        Level::DEBUG
+    } else if matches.get_flag("quiet") {
+        Level::ERROR
+    } else {
+        Level::INFO
+    };
+    let _ = tracing_subscriber::fmt()
+        .with_max_level(level)
+        .with_target(false)
+        .without_time()
+        .with_ansi(std::io::stderr().is_terminal())
+        .with_writer(progress.stderr())
+        .try_init();
+}
+
+async fn run() -> Result<()> {
+    let mut config = Config::load_default()?;
+    let matches = with_config_defaults(build_cli(), &config).get_matches();
+    let progress = if matches.get_flag("quiet") { ParseProgress::default() } else { ParseProgress::new() };
+    init_logging(&matches, &progress);
+    let profile_path = matches.get_one::<PathBuf>("profile");
+    let profiler = if profile_path.is_some() { Profiler::new() } else { Profiler::default() };
+    if let Some(path) = matches.get_one::<PathBuf>("keywords") {
+        config.keywords.merge(KeywordLists::from_file(path)?);
+    }
+    if let Some(language) = matches.get_one::<String>("language") {
+        config.language = language.parse()?;
+    }
+    if let Some(patterns) = matches.get_many::<String>("include") {
+        config.include_projects = patterns.cloned().collect();
+    }
+    if let Some(patterns) = matches.get_many::<String>("exclude") {
+        config.exclude_projects = patterns.cloned().collect();
+    }
+
+    check_top_level_args(&matches)?;
+    let schedule = timezone_schedule(&matches, &config)?;
+    // "Today" is decided by the timezone in effect right now
+    let tz = schedule.offset_at(Utc::now());
+
+    let sample = matches.get_flag("create-sample");
+    let projects_dirs = if sample {
+        let sample_dir = dirs::data_dir()
+            .ok_or_else(|| anyhow::anyhow!("Cannot find data directory for the sample"))?
+            .join("claude-work-analysis")
+            .join("sample");
+        write_sample(&sample_dir)?;
+        eprintln!("Demo data written to {}; the report below is built from it", sample_dir.display());
+        vec![sample_dir]
+    } else {
+        projects_dirs(&matches, &config)?
+    };
+
+    // The flat flags of earlier versions behave like `analyze`
+    let (command, args) = matches.subcommand().unwrap_or(("analyze", &matches));
+    // The demo data is never mirrored into the configured database
+    let storage_kind = if sample { StorageKind::Jsonl } else { config.storage };
+
+    let mut reporter = ReportGenerator::new()
+        .with_timezone_schedule(schedule.clone())
+        .with_insight_rules(config.insights.clone());
+    // Only the full-report commands accept --sections
+    if let Some(sections) = args.try_get_many::<String>("sections").ok().flatten() {
+        reporter = reporter.with_sections(sections.map(|name| name.parse()).collect::<Result<_>>()?);
+    } else if let Some(sections) = &config.sections {
+        reporter = reporter.with_sections(sections.clone());
+    }
+    if let Some(&min_confidence) = args.try_get_one::<f64>("min-confidence").ok().flatten() {
+        reporter = reporter.with_min_confidence(min_confidence);
+    }
+    let template = args.try_get_one::<String>("template").ok().flatten().map(PathBuf::from);
+    if let Some(path) = template.or_else(|| config.template.clone()) {
+        reporter = reporter.with_template(ReportTemplate::from_file(&expand_home(&path)?)?);
+    }
+
+    // Parse results are cached per file unless disabled. The database mirrors
+    // whole entries, so only the JSONL backend skips content it does not need.
+    let content_level = match storage_kind {
+        StorageKind::Jsonl => content_level(command, args, &reporter),
+        StorageKind::Sqlite | StorageKind::Duckdb => ContentLevel::Full,
+    };
+    let mut parser = JsonlParser::new()
+        .with_refresh(!matches.get_flag("no-refresh"))
+        .with_content_level(content_level)
+        .with_profiler(profiler.clone())
+        .with_progress(progress);
+    if let Some(&buffer_size) = matches.get_one::<usize>("buffer-size") {
+        parser = parser.with_buffer_size(buffer_size);
+    }
+    if !matches.get_flag("no-cache") {
+        if let Some(cache) = ParseCache::default_location() {
+            parser = parser.with_cache(cache);
+        }
+    }
+
+    let database = config.database.as_deref().map(expand_home).transpose()?;
+    // A status line is polled, older log files cannot hold today's entries
+    let storage = StorageBackend::open(storage_kind, database.as_deref(), &parser, &projects_dirs)?
+        .with_machines(config.machine_labels()?)
+        .with_project_patterns(config.project_patterns())
+        .with_quick_scan(command == "status");
+    let history_path = report_history_path(&config, sample)?;
+
+    let result = match command {
+        "plan" => run_plan(args, &storage, &schedule, &config).await,
+        #[cfg(feature = "google-calendar")]
+        "calendar" => run_calendar(args, &storage, &config, tz).await,
+        "compare" => run_compare(args, &storage, &schedule, &config, &reporter).await,
+        "standup" => run_standup(args, &storage, &schedule, &config, &reporter).await,
+        "status" => run_status(args, &storage, &schedule, &config, &reporter, matches.get_flag("no-cache")).await,
+        "current-project-summary" => run_current_project(args, &storage, &schedule, &config, &reporter).await,
+        "weekly" => run_weekly(args, &storage, &schedule, &config, &reporter).await,
+        "serve" if args.contains_id("port") => run_http_server(args, &parser, &projects_dirs, &schedule, &config).await,
+        "serve" => run_serve(&matches).await,
+        "index" => run_index(&parser, &storage, &projects_dirs, &schedule, &config).await,
+        "eval" => run_eval(args, &config, &reporter),
+        "ingest" => run_ingest(args, &storage).await,
+        "archive" => run_archive(args, &projects_dirs, &config),
+        "import" => run_import(args, &projects_dirs, &config),
+        "bundle" => run_bundle(args, &storage, &schedule, &config, &reporter).await,
+        "replay" => run_replay(args, &storage, &schedule).await,
+        "analyze" if args.get_flag("watch") => run_watch(args, &storage, &schedule, &config, &reporter).await,
+        "analyze" if args.get_flag("daemon") => {
+            run_daemon(&storage, &config, daemon_options(args, &schedule)?).await
+        }
+        #[cfg(feature = "sqlite")]
+        "export" if args.get_one::<String>("format").unwrap() == "sqlite" => {
+            let selection = analyze_selection(args, &storage, &schedule, &config).await?;
+            let path = PathBuf::from(args.get_one::<String>("output").unwrap());
+            let counts = SqliteStore::open(&path)?.write_analysis(&selection.analysis)?;
+            eprintln!("Exported {} sessions and {} entries to {}", counts.sessions, counts.entries, path.display());
+            Ok(())
+        }
+        #[cfg(feature = "embeddings")]
+        "similar" => run_similar(args, &storage, &schedule, &config, &reporter).await,
+        #[cfg(feature = "embeddings")]
+        "analyze" if args.get_flag("related") && args.get_one::<String>("format").unwrap() == "markdown" => {
+            let report = related_sessions_report(args, &storage, &schedule, &config, &reporter).await?;
+            emit_report(args, &report, &tz)
+        }
+        "analyze" if args.get_one::<String>("output-dir").is_some() => {
+            let selection = write_project_reports(args, &storage, &schedule, &config, &reporter).await?;
+            record_report(history_path.as_deref(), args, &selection, &tz);
+            check_budget(args, &selection.analysis)
+        }
+        "analyze" | "export" => {
+            let selection = analyze_selection(args, &storage, &schedule, &config).await?;
+            let report = profiler.time("report", || render_report(args, &reporter, &selection))?;
+            emit_report(args, &report, &tz)?;
+            // Status bars poll the compact summary, which would flood the history
+            if args.get_one::<String>("format").unwrap() != "compact" {
+                record_report(history_path.as_deref(), args, &selection, &tz);
+            }
+            check_budget(args, &selection.analysis)
+        }
+        "sessions" => {
+            let selection = analyze_selection(args, &storage, &schedule, &config).await?;
+            let order: SessionOrder = args.get_one::<String>("sort").unwrap().parse()?;
+            let top = args.get_one::<usize>("top").copied();
+            let report = profiler.time("report", || match args.get_one::<String>("format").unwrap().as_str() {
+                "csv" => reporter.generate_sessions_csv_report(&selection.analysis, order, top),
+                _ => Ok(selection.no_activity_report(&reporter)
+                    .unwrap_or_else(|| reporter.generate_sessions_report(&selection.analysis, order, top))),
+            })?;
+            print!("{}", report);
+            record_report(history_path.as_deref(), args, &selection, &tz);
+            Ok(())
+        }
+        "projects" => {
+            let selection = analyze_selection(args, &storage, &schedule, &config).await?;
+            let report = profiler.time("report", || match args.get_one::<String>("format").unwrap().as_str() {
+                "csv" => reporter.generate_projects_csv_report(&selection.analysis),
+                _ => Ok(selection.no_activity_report(&reporter)
+                    .unwrap_or_else(|| reporter.generate_projects_report(&selection.analysis))),
+            })?;
+            print!("{}", report);
+            record_report(history_path.as_deref(), args, &selection, &tz);
+            Ok(())
+        }
+        "history" if args.get_flag("recorded") => {
+            let grouping: TimeGrouping = match args.get_one::<String>("group-by") {
+                Some(grouping) => grouping.parse()?,
+                None => TimeGrouping::Week,
+            };
+            let path = history_path.ok_or_else(|| anyhow::anyhow!("Cannot find data directory, set report_history in the config file"))?;
+            let periods = ReportHistory::load(&path)?.periods(grouping);
+            match args.get_one::<String>("format").unwrap().as_str() {
+                "csv" => print!("{}", reporter.generate_recorded_history_csv_report(&periods)?),
+                _ => print!("{}", reporter.generate_recorded_history_report(grouping, &periods)),
+            }
+            Ok(())
+        }
+        "history" => {
+            let grouping: TimeGrouping = match args.get_one::<String>("group-by") {
+                Some(grouping) => grouping.parse()?,
+                None => TimeGrouping::Day,
+            };
+            let to = Utc::now().with_timezone(&tz).date_naive();
+            let from = to - Duration::days(*args.get_one::<i64>("days").unwrap() - 1);
+            let activity = storage.activity(grouping, tz, from, to).await?;
+            match args.get_one::<String>("format").unwrap().as_str() {
+                "csv" => print!("{}", reporter.generate_history_csv_report(&activity)?),
+                _ => print!("{}", reporter.generate_history_report(grouping, &activity)),
+            }
+            Ok(())
+        }
+        "summary" => {
+            let selection = analyze_selection(args, &storage, &schedule, &config).await?;
+            print!("{}", profiler.time("report", || selection.no_activity_report(&reporter)
+                .unwrap_or_else(|| reporter.generate_summary_report(&selection.analysis))));
+            record_report(history_path.as_deref(), args, &selection, &tz);
+            check_budget(args, &selection.analysis)
+        }
+        _ => unreachable!("clap only accepts known subcommands"),
+    };
+
+    // A failed run is profiled too, it may be what the profile is for
+    if let Some(path) = profile_path {
+        eprint!("{}", reporter.generate_profile_report(&profiler));
+        match std::fs::write(path, profiler.folded()) {
+            Ok(()) => eprintln!("\nFolded stacks written to {}", path.display()),
+            Err(e) => eprintln!("\nWarning: Failed to write profile to {}: {}", path.display(), e),
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn schedule_for(args: &[&str]) -> TimezoneSchedule {
+        let matches = build_cli()
+            .try_get_matches_from(std::iter::once("claude-work-analysis").chain(args.iter().copied()))
+            .unwrap();
+        timezone_schedule(&matches, &Config::default()).unwrap()
+    }
+
+    #[test]
+    fn test_dates_are_local_days() {
+        let schedule = schedule_for(&["--timezone", "JST", "--from", "2025-07-01"]);
+
+        // 2025-07-01 in JST runs from 2025-06-30 15:00 to 2025-07-01 14:59:59 UTC
+        assert_eq!(
+            parse_date_string("2025-07-01", &schedule).unwrap(),
+            Utc.with_ymd_and_hms(2025, 6, 30, 15, 0, 0).unwrap()
+        );
+        assert_eq!(
+            parse_end_date_string("2025-07-01", &schedule).unwrap(),
+            Utc.with_ymd_and_hms(2025, 7, 1, 14, 59, 59).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_utc_flag() {
+        let schedule = schedule_for(&["--utc", "sessions"]);
+
+        assert_eq!(
+            parse_date_string("2025-07-01", &schedule).unwrap(),
+            Utc.with_ymd_and_hms(2025, 7, 1, 0, 0, 0).unwrap()
+        );
+        assert_eq!(
+            parse_end_date_string("2025-07-01", &schedule).unwrap(),
+            Utc.with_ymd_and_hms(2025, 7, 1, 23, 59, 59).unwrap()
+        );
+
+        let misplaced = build_cli().try_get_matches_from(["claude-work-analysis", "--from", "2025-07-01", "sessions"]);
+        assert!(check_top_level_args(&misplaced.unwrap()).is_err());
+
+        let conflict = build_cli().try_get_matches_from(["claude-work-analysis", "--utc", "--timezone", "JST"]);
+        assert!(conflict.is_err());
+    }
+
+    #[test]
+    fn test_invalid_date_is_an_error() {
+        let schedule = schedule_for(&["--utc"]);
+        let error = parse_date_string("2025-02-30", &schedule).unwrap_err();
+        assert!(matches!(error.downcast_ref::<Error>(), Some(Error::InvalidDate { value, .. }) if value == "2025-02-30"));
+        assert!(parse_end_date_string("07/01/2025", &schedule).unwrap_err().to_string().contains("Expected YYYY-MM-DD"));
+    }
+
+    #[test]
+    fn test_early_morning_stays_in_local_day() {
+        let schedule = schedule_for(&["--timezone", "JST"]);
+        let from = parse_date_string("2025-07-01", &schedule).unwrap();
+        let to = parse_end_date_string("2025-07-01", &schedule).unwrap();
+
+        // 00:30 and 23:30 JST on 2025-07-01 are both in the day, 00:30 on the 2nd is not
+        let early = Utc.with_ymd_and_hms(2025, 6, 30, 15, 30, 0).unwrap();
+        let late = Utc.with_ymd_and_hms(2025, 7, 1, 14, 30, 0).unwrap();
+        let next_day = Utc.with_ymd_and_hms(2025, 7, 1, 15, 30, 0).unwrap();
+        assert!(from <= early && early <= to);
+        assert!(from <= late && late <= to);
+        assert!(next_day > to);
+    }
+
+    #[test]
+    fn test_baseline_range() {
+        let compare_matches = |args: &[&str]| {
+            let matches = build_cli()
+                .try_get_matches_from(["claude-work-analysis", "--utc", "compare"].iter().chain(args).copied())
+                .unwrap();
+            let schedule = timezone_schedule(&matches, &Config::default()).unwrap();
+            let (_, sub) = matches.subcommand().unwrap();
+            baseline_range(sub, &schedule)
+        };
+        let day = |d, h, m, s| Some(Utc.with_ymd_and_hms(2025, 7, d, h, m, s).unwrap());
+
+        // The same number of days right before --from
+        let (from, to) = compare_matches(&["--from", "2025-07-15", "--to", "2025-07-28"]).unwrap();
+        assert_eq!((from, to), (day(1, 0, 0, 0), day(14, 23, 59, 59)));
+
+        // A baseline start alone runs up to the current period
+        let (from, to) = compare_matches(&["--from", "2025-07-15", "--baseline-from", "2025-07-03"]).unwrap();
+        assert_eq!((from, to), (day(3, 0, 0, 0), day(14, 23, 59, 59)));
+        let (from, to) = compare_matches(&["--to", "2025-07-15", "--baseline-from", "2025-07-03"]).unwrap();
+        assert_eq!((from, to), (day(3, 0, 0, 0), None));
+        let (from, to) = compare_matches(&["--from", "2025-07-15", "--baseline-to", "2025-07-10"]).unwrap();
+        assert_eq!((from, to), (None, day(10, 23, 59, 59)));
+
+        assert!(compare_matches(&[]).is_err());
+    }
+
+    #[test]
+    fn test_tuning_flag_aliases() {
+        let matches = build_cli()
+            .try_get_matches_from(["claude-work-analysis", "sessions", "--session-gap-minutes", "45", "--min-session-messages", "2"])
+            .unwrap();
+        let (_, sub) = matches.subcommand().unwrap();
+        assert_eq!(sub.get_one::<i64>("session-gap"), Some(&45));
+        assert_eq!(sub.get_one::<u64>("min-messages"), Some(&2));
+
+        let merge_restarts = |minutes: &str| build_cli().try_get_matches_from(["claude-work-analysis", "--merge-restarts", minutes]);
+        assert_eq!(merge_restarts("10080").unwrap().get_one::<i64>("merge-restarts"), Some(&10_080));
+        assert!(merge_restarts("-5").is_err());
+        assert!(merge_restarts("10081").is_err());
+    }
+
+    #[test]
+    fn test_keep_days_range() {
+        let keep_days = |days: &str| {
+            build_cli().try_get_matches_from(["claude-work-analysis", "--output-mode", "dated", "--keep-days", days])
+        };
+        let matches = keep_days("30").unwrap();
+        assert_eq!(matches.get_one::<i64>("keep-days"), Some(&30));
+        assert!(keep_days("0").is_ok());
+        // A negative age would remove the report just written, a huge one overflows the date
+        assert!(keep_days("-1").is_err());
+        assert!(keep_days("36501").is_err());
+    }
+
+    #[test]
+    fn test_config_defaults() {
+        let config = Config {
+            format: Some("csv".to_string()),
+            timezone: Some("+05:30".to_string()),
+            ..Config::default()
+        };
+        let format = |args: &[&str]| {
+            let matches = with_config_defaults(build_cli(), &config)
+                .try_get_matches_from(std::iter::once("claude-work-analysis").chain(args.iter().copied()))
+                .unwrap();
+            let (_, sub) = matches.subcommand().unwrap_or(("analyze", &matches));
+            sub.get_one::<String>("format").unwrap().clone()
+        };
+
+        assert_eq!(format(&[]), "csv");
+        assert_eq!(format(&["sessions"]), "csv");
+        // Commands without csv output keep their own default
+        assert_eq!(format(&["compare", "--period", "week"]), "markdown");
+        assert_eq!(format(&["sessions", "--format", "markdown"]), "markdown");
+
+        let matches = build_cli().try_get_matches_from(["claude-work-analysis"]).unwrap();
+        assert_eq!(timezone_schedule(&matches, &config).unwrap().label(), "UTC+05:30");
+        let matches = build_cli().try_get_matches_from(["claude-work-analysis", "--utc"]).unwrap();
+        assert_eq!(timezone_schedule(&matches, &config).unwrap().label(), "UTC");
+    }
+
+    #[test]
+    fn test_http_token_is_required() {
+        let token = |args: &[&str], config: &Config| {
+            let matches = build_cli()
+                .try_get_matches_from(["claude-work-analysis", "serve", "--port", "8080"].iter().chain(args))
+                .unwrap();
+            http_token(matches.subcommand().unwrap().1, config)
+        };
+        let config = Config { api_token: Some("from-config".to_string()), ..Config::default() };
+
+        assert!(token(&[], &Config::default()).is_err());
+        assert_eq!(token(&["--no-auth"], &Config::default()).unwrap(), None);
+        assert_eq!(token(&[], &config).unwrap().as_deref(), Some("from-config"));
+        assert_eq!(token(&["--token", "s3cret"], &config).unwrap().as_deref(), Some("s3cret"));
+        // The opt-out wins over a token in the config file, not over one on the command line
+        assert_eq!(token(&["--no-auth"], &config).unwrap(), None);
+        assert!(build_cli().try_get_matches_from(["claude-work-analysis", "serve", "--port", "1", "--no-auth", "--token", "x"]).is_err());
+        // A blank token is refused rather than taken as no authentication
+        let error = token(&["--token", ""], &Config::default()).unwrap_err();
+        assert!(error.to_string().contains("--no-auth"));
+        assert!(token(&["--token", "  "], &config).is_err());
+        let blank = Config { api_token: Some(" ".to_string()), ..Config::default() };
+        assert!(token(&[], &blank).is_err());
+    }
+}
@@ -1,27 +1,55 @@
 use anyhow::Result;
-use chrono::{Utc, NaiveDate, TimeZone, FixedOffset};
+use chrono::{DateTime, Duration, Utc, NaiveDate, FixedOffset};
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
-use std::io::{self, BufRead, BufReader, Write};
-use tracing::{debug, error, info};
-
-mod analyzer;
-mod models;
-mod parser;
-mod filter;
-mod scanner;
-mod reporter;
-mod message_analyzer;
-
-use analyzer::WorkAnalyzer;
-use models::WorkAnalysis;
-use filter::TimeRangeFilter;
-use parser::JsonlParser;
-use reporter::ReportGenerator;
-use scanner::ProjectScanner;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU8, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration as StdDuration;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::sync::{mpsc, Notify, Semaphore};
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, error, info, warn, Level};
+use tracing_subscriber::layer::{Context, SubscriberExt};
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{filter, Layer};
+
+use claude_work_analysis::analyzer::WorkAnalyzer;
+use claude_work_analysis::cache::ParseCache;
+use claude_work_analysis::config::{expand_home, Config};
+use claude_work_analysis::daily_summary::{DailySummaryCache, SummaryKey, WorkSummary, CACHED_DAYS};
+use claude_work_analysis::error::Error;
+use claude_work_analysis::filter::{FilterCoverage, TimeRangeFilter};
+use claude_work_analysis::health::DataHealth;
+use claude_work_analysis::message_analyzer::{KeywordLists, Language};
+use claude_work_analysis::models::{WorkAnalysis, WorkSession};
+use claude_work_analysis::parser::JsonlParser;
+use claude_work_analysis::pipeline::{AnalysisPipeline, AnalysisTuning};
+use claude_work_analysis::period::ReportingPeriod;
+use claude_work_analysis::reporter::ReportGenerator;
+use claude_work_analysis::result_cache::ResultCache;
+use claude_work_analysis::scanner::ProjectScanner;
+use claude_work_analysis::standup::Standup;
+use claude_work_analysis::template::ReportTemplate;
+use claude_work_analysis::timezone::{parse_timezone, system_timezone, TimezoneSchedule};
+use claude_work_analysis::watcher::LogWatcher;
+
+/// Resource URI of the summary for the current local day
+const TODAY_SUMMARY_URI: &str = "claude-work://summary/today";
+/// Resource URI of the summary for the current local week, Monday to today
+const WEEK_SUMMARY_URI: &str = "claude-work://summary/week";
+/// Resource URI of the work per project in the last [`PROJECT_RESOURCE_DAYS`] days
+const PROJECTS_URI: &str = "claude-work://projects";
+/// Template of the resource with the stats of one project, like get_project_stats
+const PROJECT_URI_TEMPLATE: &str = "claude-work://projects/{project}";
+/// Days covered by the project resources
+const PROJECT_RESOURCE_DAYS: u32 = 30;
 
 #[derive(Debug, Deserialize)]
 struct McpRequest {
+    #[allow(dead_code)] // Part of the JSON-RPC envelope, not inspected
     jsonrpc: String,
     id: Option<Value>,
     method: String,
@@ -56,6 +84,428 @@ struct AnalyzePeriodParams {
     project_filter: Option<String>,
     #[serde(default)]
     format: Option<String>, // "markdown" or "json"
+    #[serde(flatten)]
+    tuning: AnalysisTuningParams,
+}
+
+#[derive(Debug, Deserialize)]
+struct ListSessionsParams {
+    #[serde(default)]
+    from_date: Option<String>,
+    #[serde(default)]
+    to_date: Option<String>,
+    #[serde(default)]
+    project_filter: Option<String>,
+    #[serde(default = "default_session_limit")]
+    limit: usize,
+    #[serde(default)]
+    offset: usize,
+    #[serde(flatten)]
+    tuning: AnalysisTuningParams,
+}
+
+fn default_session_limit() -> usize {
+    20
+}
+
+/// Tool arguments that failed validation, reported as JSON-RPC -32602
+#[derive(Debug)]
+struct InvalidParams(String);
+
+impl std::fmt::Display for InvalidParams {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::error::Error for InvalidParams {}
+
+fn invalid_params(message: impl Into<String>) -> anyhow::Error {
+    anyhow::Error::new(InvalidParams(message.into()))
+}
+
+/// Requests refused by server limits (busy or timed out), reported as JSON-RPC -32000
+#[derive(Debug)]
+struct RequestRejected(String);
+
+impl std::fmt::Display for RequestRejected {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::error::Error for RequestRejected {}
+
+/// Resource URIs the server does not provide, reported as JSON-RPC -32002
+#[derive(Debug)]
+struct ResourceNotFound(String);
+
+impl std::fmt::Display for ResourceNotFound {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Resource not found: {}", self.0)
+    }
+}
+
+impl std::error::Error for ResourceNotFound {}
+
+/// Messages that are not JSON, reported as JSON-RPC -32700
+#[derive(Debug)]
+struct ParseError(String);
+
+impl std::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Parse error: {}", self.0)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// JSON messages that are not valid requests, reported as JSON-RPC -32600
+#[derive(Debug)]
+struct InvalidRequest(String);
+
+impl std::fmt::Display for InvalidRequest {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Invalid request: {}", self.0)
+    }
+}
+
+impl std::error::Error for InvalidRequest {}
+
+/// Methods the server does not implement, reported as JSON-RPC -32601
+#[derive(Debug)]
+struct MethodNotFound(String);
+
+impl std::fmt::Display for MethodNotFound {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Method not found: {}", self.0)
+    }
+}
+
+impl std::error::Error for MethodNotFound {}
+
+/// Build a JSON-RPC error response, mapping validation failures to -32602
+fn error_response(id: Option<Value>, error: anyhow::Error) -> McpResponse {
+    let code = if error.downcast_ref::<InvalidParams>().is_some() {
+        -32602
+    } else if error.downcast_ref::<ParseError>().is_some() {
+        -32700
+    } else if error.downcast_ref::<InvalidRequest>().is_some() {
+        -32600
+    } else if error.downcast_ref::<MethodNotFound>().is_some() {
+        -32601
+    } else if error.downcast_ref::<RequestRejected>().is_some() {
+        -32000
+    } else if error.downcast_ref::<ResourceNotFound>().is_some() {
+        -32002
+    } else {
+        -32603
+    };
+
+    McpResponse {
+        jsonrpc: "2.0".to_string(),
+        id,
+        result: None,
+        error: Some(McpError {
+            code,
+            message: error.to_string(),
+            data: None,
+        }),
+    }
+}
+
+/// Deserialize tool arguments, treating omitted arguments as an empty object
+fn parse_arguments<T: DeserializeOwned>(arguments: Value) -> Result<T> {
+    let arguments = if arguments.is_null() { json!({}) } else { arguments };
+    serde_json::from_value(arguments)
+        .map_err(|e| invalid_params(format!("Invalid arguments: {}", e)))
+}
+
+/// Parse a YYYY-MM-DD tool argument as the start or end of that local day
+fn parse_date_argument(field: &str, value: &str, schedule: &TimezoneSchedule, end_of_day: bool) -> Result<DateTime<Utc>> {
+    let date = NaiveDate::parse_from_str(value, "%Y-%m-%d").map_err(|_| {
+        invalid_params(format!(
+            "Invalid {} '{}': expected a date in YYYY-MM-DD format (e.g. 2025-07-01)",
+            field, value
+        ))
+    })?;
+
+    Ok(if end_of_day { schedule.end_of_day(date) } else { schedule.start_of_day(date) })
+}
+
+/// Filter for the days from `from_date` to `to_date`, each optional, rejecting a reversed range
+fn date_range_filter(
+    from_date: Option<&str>,
+    to_date: Option<&str>,
+    project_filter: Option<String>,
+    schedule: &TimezoneSchedule,
+) -> Result<TimeRangeFilter> {
+    let from = from_date.map(|value| parse_date_argument("from_date", value, schedule, false)).transpose()?;
+    let to = to_date.map(|value| parse_date_argument("to_date", value, schedule, true)).transpose()?;
+    if let (Some(from), Some(to)) = (from, to) {
+        if from > to {
+            return Err(invalid_params(format!(
+                "from_date ({}) must not be after to_date ({})",
+                from_date.unwrap_or_default(),
+                to_date.unwrap_or_default()
+            )));
+        }
+    }
+    Ok(TimeRangeFilter::new(from, to, project_filter))
+}
+
+fn parse_timezone_argument(value: Option<&str>) -> Result<FixedOffset> {
+    match value {
+        Some(spec) => parse_timezone(spec).map_err(|e| invalid_params(e.to_string())),
+        None => Ok(system_timezone()),
+    }
+}
+
+/// Project names similar to the query, best matches first
+fn closest_project_names(query: &str, candidates: &[String], limit: usize) -> Vec<String> {
+    let query = query.to_lowercase();
+    let mut scored: Vec<(usize, &String)> = candidates
+        .iter()
+        .filter_map(|candidate| {
+            let candidate_lower = candidate.to_lowercase();
+            let last_segment = candidate_lower.rsplit('/').next().unwrap_or(&candidate_lower);
+
+            if candidate_lower.contains(&query) || query.contains(last_segment) {
+                return Some((0, candidate));
+            }
+
+            let distance = levenshtein(&query, last_segment).min(levenshtein(&query, &candidate_lower));
+            let max_distance = (query.chars().count() / 3).max(2);
+            (distance <= max_distance).then_some((distance, candidate))
+        })
+        .collect();
+
+    scored.sort();
+    scored.dedup_by(|a, b| a.1 == b.1);
+    scored.into_iter().take(limit).map(|(_, name)| name.clone()).collect()
+}
+
+fn levenshtein(a: &str, b: &str) -> usize {
+    let b_chars: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b_chars.len()).collect();
+
+    for (i, a_char) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, b_char) in b_chars.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a_char != *b_char);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+
+    previous[b_chars.len()]
+}
+
+/// Extract the `uri` parameter of a resources/* request
+fn resource_uri(params: Option<&Value>) -> Result<String> {
+    params
+        .and_then(|params| params.get("uri"))
+        .and_then(|uri| uri.as_str())
+        .map(|uri| uri.to_string())
+        .ok_or_else(|| invalid_params("Missing uri"))
+}
+
+/// Compact markdown summary shared by summarize_recent and the today resource
+fn compact_summary(title: &str, time_filter: &TimeRangeFilter, schedule: &TimezoneSchedule, analysis: &WorkAnalysis) -> String {
+    let mut summary = format!("# {}\n\n", title);
+    if let (Some(from), Some(to)) = time_filter.get_date_range() {
+        summary.push_str(&format!("- 期間: {} 〜 {} ({})\n",
+            schedule.to_local(from).format("%Y-%m-%d %H:%M"),
+            schedule.to_local(to).format("%Y-%m-%d %H:%M"),
+            schedule.label()));
+    }
+    summary.push_str(&format!("- 総セッション数: {}\n", analysis.total_sessions));
+    summary.push_str(&format!("- 総メッセージ数: {}\n", analysis.total_messages));
+    summary.push_str(&format!("- 作業時間: {:.1}時間\n\n",
+        analysis.total_work_time.num_seconds() as f64 / 3600.0));
+
+    summary.push_str("## アクティブプロジェクト\n");
+    for (project_name, stats) in analysis.project_stats.iter().take(5) {
+        summary.push_str(&format!("- **{}**: {}セッション, {:.1}時間\n",
+            project_name, stats.total_sessions,
+            stats.work_time.num_seconds() as f64 / 3600.0));
+    }
+
+    if let Some(ref conv_summary) = analysis.conversation_summary {
+        summary.push_str("\n## 主要テーマ\n");
+        for theme in conv_summary.topic_themes.iter().take(5) {
+            let topics: Vec<&str> = theme.topics.iter().take(5).map(|(topic, _)| topic.as_str()).collect();
+            summary.push_str(&format!(
+                "- {} ({}セッション, {:.1}時間, ${:.2}): {}\n",
+                theme.name,
+                theme.session_ids.len(),
+                theme.work_time.num_minutes() as f64 / 60.0,
+                theme.tokens.estimated_cost,
+                topics.join(", ")
+            ));
+        }
+
+        if !conv_summary.productivity_insights.is_empty() {
+            summary.push_str("\n## 生産性インサイト\n");
+            for insight in &conv_summary.productivity_insights {
+                summary.push_str(&format!("- {}\n", insight));
+            }
+        }
+    }
+
+    summary
+}
+
+/// Compact markdown summary of the local days summarized by `--watch` or `--daemon`
+fn daily_summary(title: &str, summary: &WorkSummary, written_at: DateTime<Utc>, schedule: &TimezoneSchedule) -> String {
+    let mut text = format!("# {}\n\n", title);
+    text.push_str(&format!("- 期間: {} 〜 {} ({})\n", summary.first_day, summary.last_day, schedule.label()));
+    text.push_str(&format!("- 総セッション数: {}\n", summary.sessions));
+    text.push_str(&format!("- 総メッセージ数: {}\n", summary.messages));
+    text.push_str(&format!("- 作業時間: {:.1}時間\n", summary.work_seconds as f64 / 3600.0));
+    text.push_str(&format!("- 推定コスト: ${:.2}\n\n", summary.estimated_cost));
+
+    text.push_str("## アクティブプロジェクト\n");
+    for (project, work) in summary.projects_by_work_time().into_iter().take(5) {
+        text.push_str(&format!("- **{}**: {}セッション, {:.1}時間\n", project, work.sessions, work.work_seconds as f64 / 3600.0));
+    }
+
+    let topics = summary.top_topics();
+    if !topics.is_empty() {
+        text.push_str("\n## 主要トピック\n");
+        for (topic, mentions) in topics.into_iter().take(5) {
+            text.push_str(&format!("- {} ({}回)\n", topic, mentions));
+        }
+    }
+
+    text.push_str(&format!(
+        "\n_日別サマリー（{}に更新）から集計_\n",
+        schedule.to_local(written_at).format("%H:%M")
+    ));
+    text
+}
+
+/// The project `query` names among the sessions of `analysis`: the one named
+/// exactly that, or else the only one whose name contains it
+fn resolve_project_name(analysis: &WorkAnalysis, query: &str) -> Result<Option<String>> {
+    let candidates: BTreeSet<String> = analysis
+        .sessions
+        .iter()
+        .map(|session| analysis.project_name(session))
+        .filter(|name| name.contains(query))
+        .collect();
+    if candidates.contains(query) {
+        return Ok(Some(query.to_string()));
+    }
+    if candidates.len() <= 1 {
+        return Ok(candidates.into_iter().next());
+    }
+    Err(invalid_params(format!(
+        "project_name '{}' matches several projects: {}",
+        query,
+        candidates.into_iter().collect::<Vec<_>>().join(", ")
+    )))
+}
+
+/// Summary for a period without sessions, listing the filters and the data the logs cover
+fn no_activity_summary(title: &str, time_filter: &TimeRangeFilter, schedule: &TimezoneSchedule, coverage: &FilterCoverage) -> String {
+    let format_time = |time: DateTime<Utc>| schedule.to_local(time).format("%Y-%m-%d %H:%M").to_string();
+    let mut summary = format!("# {}\n\n", title);
+    if coverage.matched_entries == 0 {
+        summary.push_str("活動が見つかりませんでした。\n\n");
+    } else {
+        summary.push_str(&format!(
+            "{}件のログが条件に一致しましたが、セッションを構成するには少なすぎます。\n\n",
+            coverage.matched_entries
+        ));
+    }
+
+    summary.push_str("## 適用した条件\n");
+    let (from, to) = time_filter.get_date_range();
+    summary.push_str(&format!("- 期間: {} 〜 {} ({})\n",
+        from.map(format_time).unwrap_or_else(|| "指定なし".to_string()),
+        to.map(format_time).unwrap_or_else(|| "指定なし".to_string()),
+        schedule.label()));
+    summary.push_str(&format!("- プロジェクト: {}\n\n", time_filter.get_project_filter().unwrap_or("すべて")));
+
+    summary.push_str("## データの範囲\n");
+    match (coverage.first_entry, coverage.last_entry) {
+        (Some(first), Some(last)) => {
+            summary.push_str(&format!("- ログ件数: {}\n", coverage.total_entries));
+            summary.push_str(&format!("- 記録期間: {} 〜 {}\n", format_time(first), format_time(last)));
+        }
+        _ => summary.push_str("- ログが1件も見つかりませんでした\n"),
+    }
+
+    summary
+}
+
+/// Normalize a project name or path the way Claude encodes project directories
+fn encode_project_key(name: &str) -> String {
+    name.chars()
+        .map(|c| if c.is_alphanumeric() { c.to_ascii_lowercase() } else { '-' })
+        .collect()
+}
+
+/// Optional analysis knobs shared by the analysis tools
+#[derive(Debug, Default, Deserialize)]
+struct AnalysisTuningParams {
+    #[serde(default)]
+    session_gap_minutes: Option<i64>,
+    #[serde(default)]
+    min_session_messages: Option<usize>,
+    #[serde(default)]
+    timezone: Option<String>,
+    #[serde(default)]
+    include_sidechains: Option<bool>,
+    #[serde(default)]
+    merge_restarts_minutes: Option<i64>,
+    #[serde(default)]
+    include_thinking: Option<bool>,
+    #[serde(default)]
+    deep_topics: Option<bool>,
+}
+
+impl AnalysisTuningParams {
+    fn validate(&self) -> Result<()> {
+        if let Some(minutes) = self.session_gap_minutes {
+            if minutes <= 0 {
+                return Err(invalid_params(format!(
+                    "session_gap_minutes must be a positive number of minutes, got {}",
+                    minutes
+                )));
+            }
+        }
+        if self.merge_restarts_minutes.is_some_and(|minutes| minutes < 0) {
+            return Err(invalid_params("merge_restarts_minutes must not be negative"));
+        }
+        if self.min_session_messages == Some(0) {
+            return Err(invalid_params("min_session_messages must be at least 1"));
+        }
+        parse_timezone_argument(self.timezone.as_deref())?;
+        Ok(())
+    }
+
+    fn analysis_tuning(&self) -> AnalysisTuning {
+        AnalysisTuning {
+            session_gap: self.session_gap_minutes.map(Duration::minutes),
+            min_messages: self.min_session_messages,
+            include_sidechains: self.include_sidechains,
+            merge_restarts: self.merge_restarts_minutes.map(Duration::minutes),
+            include_thinking: self.include_thinking,
+            deep_topics: self.deep_topics,
+        }
+    }
+
+    /// Build a WorkAnalyzer with the requested overrides applied to the configured defaults
+    fn build_analyzer(&self, config: &Config, schedule: &TimezoneSchedule) -> WorkAnalyzer {
+        self.analysis_tuning().apply(config.analyzer().with_timezone_schedule(schedule.clone()))
+    }
+
+    /// Stands for the analyzer `build_analyzer` returns, to key cached results
+    fn cache_key(&self, config: &Config, schedule: &TimezoneSchedule) -> String {
+        format!("{:?}|{:?}|{:?}", self.analysis_tuning(), config, schedule)
+    }
 }
 
 #[derive(Debug, Deserialize)]
@@ -63,81 +513,621 @@ struct ProjectStatsParams {
     project_name: String,
     #[serde(default)]
     days: Option<u32>,
+    #[serde(flatten)]
+    tuning: AnalysisTuningParams,
 }
 
 #[derive(Debug, Deserialize)]
 struct SummarizeRecentParams {
     #[serde(default = "default_recent_days")]
     days: u32,
+    #[serde(flatten)]
+    tuning: AnalysisTuningParams,
+}
+
+#[derive(Debug, Deserialize)]
+struct StandupParams {
+    #[serde(default = "default_standup_days")]
+    days: u32,
+    #[serde(default)]
+    project_filter: Option<String>,
+    #[serde(flatten)]
+    tuning: AnalysisTuningParams,
+}
+
+fn default_standup_days() -> u32 {
+    1
+}
+
+#[derive(Debug, Deserialize)]
+struct DataHealthParams {
+    #[serde(default)]
+    project_filter: Option<String>,
+    #[serde(default)]
+    gap_days: Option<i64>,
+    #[serde(default)]
+    timezone: Option<String>,
 }
 
 fn default_recent_days() -> u32 {
     7
 }
 
+/// MCP log levels (RFC 5424 severities), least severe first
+const MCP_LOG_LEVELS: [&str; 8] = [
+    "debug", "info", "notice", "warning", "error", "critical", "alert", "emergency",
+];
+
+/// Runtime-adjustable log level shared by stderr logging and MCP log notifications
+#[derive(Clone)]
+struct LogLevelControl {
+    /// Index into MCP_LOG_LEVELS of the minimum level that is emitted
+    level: Arc<AtomicU8>,
+    /// Notifications are only sent once the client has asked for a level
+    notifications_enabled: Arc<AtomicBool>,
+}
+
+impl LogLevelControl {
+    fn new() -> Self {
+        Self {
+            level: Arc::new(AtomicU8::new(1)), // info
+            notifications_enabled: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// Apply a `logging/setLevel` request from the client
+    fn set_level(&self, level: &str) -> Result<()> {
+        let index = MCP_LOG_LEVELS
+            .iter()
+            .position(|candidate| *candidate == level)
+            .ok_or_else(|| invalid_params(format!(
+                "Invalid log level '{}': expected one of {}",
+                level,
+                MCP_LOG_LEVELS.join(", ")
+            )))?;
+
+        self.level.store(index as u8, Ordering::Relaxed);
+        self.notifications_enabled.store(true, Ordering::Relaxed);
+        Ok(())
+    }
+
+    fn current_level(&self) -> &'static str {
+        MCP_LOG_LEVELS[self.level.load(Ordering::Relaxed) as usize]
+    }
+
+    /// Whether a tracing event at this level passes the configured minimum
+    fn enabled(&self, level: &Level) -> bool {
+        mcp_level_index(level) >= self.level.load(Ordering::Relaxed)
+    }
+}
+
+fn mcp_level_index(level: &Level) -> u8 {
+    match *level {
+        Level::ERROR => 4,
+        Level::WARN => 3,
+        Level::INFO => 1,
+        Level::DEBUG | Level::TRACE => 0,
+    }
+}
+
+/// Tracing layer that forwards events to the client as `notifications/message`
+struct McpLogLayer {
+    control: LogLevelControl,
+    /// Weak so that pending log output never keeps the writer alive after EOF
+    outgoing: mpsc::WeakUnboundedSender<String>,
+}
+
+impl<S: tracing::Subscriber> Layer<S> for McpLogLayer {
+    fn on_event(&self, event: &tracing::Event<'_>, _ctx: Context<'_, S>) {
+        let metadata = event.metadata();
+        if !self.control.notifications_enabled.load(Ordering::Relaxed)
+            || !self.control.enabled(metadata.level())
+        {
+            return;
+        }
+        let Some(outgoing) = self.outgoing.upgrade() else {
+            return;
+        };
+
+        let mut message = String::new();
+        event.record(&mut MessageVisitor(&mut message));
+
+        let notification = json!({
+            "jsonrpc": "2.0",
+            "method": "notifications/message",
+            "params": {
+                "level": MCP_LOG_LEVELS[mcp_level_index(metadata.level()) as usize],
+                "logger": metadata.target(),
+                "data": message
+            }
+        });
+        let _ = outgoing.send(notification.to_string());
+    }
+}
+
+/// Collects an event's message and fields into a single line
+struct MessageVisitor<'a>(&'a mut String);
+
+impl tracing::field::Visit for MessageVisitor<'_> {
+    fn record_debug(&mut self, field: &tracing::field::Field, value: &dyn std::fmt::Debug) {
+        if !self.0.is_empty() {
+            self.0.push(' ');
+        }
+        if field.name() == "message" {
+            self.0.push_str(&format!("{:?}", value));
+        } else {
+            self.0.push_str(&format!("{}={:?}", field.name(), value));
+        }
+    }
+}
+
+/// Safeguards against requests that would wedge the server
+#[derive(Debug, Clone)]
+pub struct ServerLimits {
+    /// Maximum wall-clock time for a single request
+    pub request_timeout: StdDuration,
+    /// Maximum number of tool calls processed at the same time
+    pub max_concurrent_tool_calls: usize,
+    /// Maximum size of a tool's text output before it is truncated
+    pub max_response_bytes: usize,
+}
+
+impl ServerLimits {
+    /// Read limits from MCP_REQUEST_TIMEOUT_SECS, MCP_MAX_CONCURRENT_TOOL_CALLS
+    /// and MCP_MAX_RESPONSE_BYTES, falling back to the defaults
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        let read = |name: &str| std::env::var(name).ok().and_then(|v| v.parse::<u64>().ok());
+
+        Self {
+            request_timeout: read("MCP_REQUEST_TIMEOUT_SECS")
+                .map(StdDuration::from_secs)
+                .unwrap_or(defaults.request_timeout),
+            max_concurrent_tool_calls: read("MCP_MAX_CONCURRENT_TOOL_CALLS")
+                .map(|v| v.max(1) as usize)
+                .unwrap_or(defaults.max_concurrent_tool_calls),
+            max_response_bytes: read("MCP_MAX_RESPONSE_BYTES")
+                .map(|v| v as usize)
+                .unwrap_or(defaults.max_response_bytes),
+        }
+    }
+
+    /// Cut text down to the response ceiling, appending a notice when truncated
+    fn truncate_response(&self, text: String) -> String {
+        if text.len() <= self.max_response_bytes {
+            return text;
+        }
+
+        let mut cut = self.max_response_bytes;
+        while !text.is_char_boundary(cut) {
+            cut -= 1;
+        }
+
+        format!(
+            "{}\n\n[Truncated: response was {} bytes, limit is {} bytes. Narrow the date range or project filter to see everything.]",
+            &text[..cut],
+            text.len(),
+            self.max_response_bytes
+        )
+    }
+}
+
+impl Default for ServerLimits {
+    fn default() -> Self {
+        Self {
+            request_timeout: StdDuration::from_secs(120),
+            max_concurrent_tool_calls: 2,
+            max_response_bytes: 512 * 1024,
+        }
+    }
+}
+
 pub struct ClaudeWorkAnalysisServer {
-    analyzer: WorkAnalyzer,
     scanner: ProjectScanner,
     parser: JsonlParser,
-    report_generator: ReportGenerator,
+    limits: ServerLimits,
+    /// Permits for concurrently running tool calls
+    tool_call_slots: Arc<Semaphore>,
+    /// Client-controlled log level
+    log_level: LogLevelControl,
+    /// Log directories given on the command line; empty to use the config file
+    projects_dirs: Vec<PathBuf>,
+    /// Keywords given on the command line, merged into those of the config file
+    keywords: KeywordLists,
+    /// Language given on the command line instead of the config file's
+    language: Option<Language>,
+    /// Timezone schedule file given on the command line, for requests that name no timezone
+    timezone_schedule: Option<PathBuf>,
+    /// Project directory patterns given on the command line instead of the config file's
+    include_projects: Option<Vec<String>>,
+    exclude_projects: Option<Vec<String>>,
+    /// Resource URIs the client has subscribed to
+    subscriptions: Mutex<HashSet<String>>,
+    /// Last content seen per resource, used to detect changes
+    resource_snapshots: Mutex<HashMap<String, String>>,
+    /// Analyses of recent queries, shared by the tools and resources
+    results: ResultCache,
+    /// Per-day summaries kept by `--watch` or `--daemon`, answering recent days without the logs
+    daily_summaries: Option<DailySummaryCache>,
+    /// Cancels the requests in flight, by request id, when the client sends `notifications/cancelled`
+    in_flight: Mutex<HashMap<String, CancellationToken>>,
+    /// Set once the client sent `shutdown`; later requests are refused
+    shutting_down: AtomicBool,
+    /// Wakes the input loop to stop reading after `shutdown`
+    shutdown: Notify,
 }
 
 impl ClaudeWorkAnalysisServer {
     pub fn new() -> Self {
+        Self::with_limits(ServerLimits::default())
+    }
+
+    pub fn with_limits(limits: ServerLimits) -> Self {
         Self {
-            analyzer: WorkAnalyzer::new(),
             scanner: ProjectScanner::new(),
-            parser: JsonlParser::new(),
-            report_generator: ReportGenerator::new(),
+            parser: match ParseCache::default_location() {
+                Some(cache) => JsonlParser::new().with_cache(cache),
+                None => JsonlParser::new(),
+            },
+            tool_call_slots: Arc::new(Semaphore::new(limits.max_concurrent_tool_calls)),
+            limits,
+            log_level: LogLevelControl::new(),
+            projects_dirs: Vec::new(),
+            keywords: KeywordLists::default(),
+            language: None,
+            timezone_schedule: None,
+            include_projects: None,
+            exclude_projects: None,
+            subscriptions: Mutex::new(HashSet::new()),
+            resource_snapshots: Mutex::new(HashMap::new()),
+            results: ResultCache::default(),
+            daily_summaries: DailySummaryCache::default_location(),
+            in_flight: Mutex::new(HashMap::new()),
+            shutting_down: AtomicBool::new(false),
+            shutdown: Notify::new(),
+        }
+    }
+
+    /// Whether log files changed since they were cached are read before answering
+    pub fn with_refresh(mut self, refresh: bool) -> Self {
+        self.parser = self.parser.with_refresh(refresh);
+        self
+    }
+
+    /// Answer recent days from these per-day summaries while they are fresh, or always read the logs
+    pub fn with_daily_summaries(mut self, daily_summaries: Option<DailySummaryCache>) -> Self {
+        self.daily_summaries = daily_summaries;
+        self
+    }
+
+    /// Read logs from these directories instead of the configured ones
+    pub fn with_projects_dirs(mut self, projects_dirs: Vec<PathBuf>) -> Self {
+        self.projects_dirs = projects_dirs;
+        self
+    }
+
+    /// Detect these keywords in addition to those of the config file
+    pub fn with_keywords(mut self, keywords: KeywordLists) -> Self {
+        self.keywords = keywords;
+        self
+    }
+
+    /// Analyze prompts in this language instead of the config file's
+    pub fn with_language(mut self, language: Option<Language>) -> Self {
+        self.language = language;
+        self
+    }
+
+    /// Place local days by this schedule file unless a request names its timezone
+    pub fn with_timezone_schedule(mut self, path: Option<PathBuf>) -> Self {
+        self.timezone_schedule = path;
+        self
+    }
+
+    /// Read the project directories these patterns select instead of the config file's
+    pub fn with_project_patterns(mut self, include: Option<Vec<String>>, exclude: Option<Vec<String>>) -> Self {
+        self.include_projects = include;
+        self.exclude_projects = exclude;
+        self
+    }
+
+    /// The config file, read on each call so edits apply, with the command-line
+    /// keywords, language and project patterns
+    fn config(&self) -> Result<Config> {
+        let mut config = Config::load_default()?;
+        config.keywords.merge(self.keywords.clone());
+        if let Some(language) = self.language {
+            config.language = language;
+        }
+        if let Some(include) = &self.include_projects {
+            config.include_projects = include.clone();
+        }
+        if let Some(exclude) = &self.exclude_projects {
+            config.exclude_projects = exclude.clone();
+        }
+        Ok(config)
+    }
+
+    /// The timezone a request names, or else the schedule file over the configured or system timezone
+    fn timezone_schedule(&self, requested: Option<&str>, config: &Config) -> Result<TimezoneSchedule> {
+        let timezone = parse_timezone_argument(requested.or(config.timezone.as_deref()))?;
+        match &self.timezone_schedule {
+            Some(path) if requested.is_none() => TimezoneSchedule::from_file(path, timezone),
+            _ => Ok(TimezoneSchedule::fixed(timezone)),
+        }
+    }
+
+    /// Log directories to scan, from the command line or the config file
+    fn projects_dirs(&self) -> Result<Vec<PathBuf>> {
+        if !self.projects_dirs.is_empty() {
+            return Ok(self.projects_dirs.clone());
         }
+        self.config()?.resolved_projects_dirs()
     }
 
-    pub async fn run(&self) -> Result<()> {
-        tracing_subscriber::fmt::init();
+    /// Log to stderr (stdout carries the protocol) and to the client via notifications
+    fn init_logging(&self, outgoing: &mpsc::UnboundedSender<String>) {
+        let stderr_control = self.log_level.clone();
+        let stderr_layer = tracing_subscriber::fmt::layer()
+            .with_writer(std::io::stderr)
+            .with_filter(filter::filter_fn(move |metadata| {
+                stderr_control.enabled(metadata.level())
+            }));
+
+        let mcp_layer = McpLogLayer {
+            control: self.log_level.clone(),
+            outgoing: outgoing.downgrade(),
+        };
+
+        let _ = tracing_subscriber::registry()
+            .with(stderr_layer)
+            .with(mcp_layer)
+            .try_init();
+    }
+
+    pub async fn run(self: Arc<Self>) -> Result<()> {
+        // All output goes through one writer task so concurrent responses never interleave
+        let (outgoing, mut outgoing_rx) = mpsc::unbounded_channel::<String>();
+        self.init_logging(&outgoing);
         info!("Claude Work Analysis MCP Server starting...");
 
-        let input = io::stdin();
-        let mut reader = BufReader::new(input.lock());
-        let mut line = String::new();
+        let writer = tokio::spawn(async move {
+            let mut stdout = tokio::io::stdout();
+            while let Some(message) = outgoing_rx.recv().await {
+                stdout.write_all(message.as_bytes()).await?;
+                stdout.write_all(b"\n").await?;
+                stdout.flush().await?;
+            }
+            Ok::<_, std::io::Error>(())
+        });
+
+        let resource_watcher = self.spawn_resource_watcher(&outgoing);
+        let mut lines = BufReader::new(tokio::io::stdin()).lines();
 
-        while reader.read_line(&mut line)? > 0 {
-            let trimmed = line.trim();
+        loop {
+            let line = tokio::select! {
+                line = lines.next_line() => line?,
+                _ = self.shutdown.notified() => {
+                    info!("Shutdown requested, finishing in-flight requests");
+                    break;
+                }
+            };
+            let Some(line) = line else {
+                info!("Input closed, finishing in-flight requests");
+                break;
+            };
+            let trimmed = line.trim().to_string();
             if trimmed.is_empty() {
-                line.clear();
                 continue;
             }
 
-            let response = match self.handle_request(trimmed).await {
-                Ok(resp) => resp,
-                Err(e) => {
-                    error!("Error handling request: {}", e);
-                    McpResponse {
-                        jsonrpc: "2.0".to_string(),
-                        id: None,
-                        result: None,
-                        error: Some(McpError {
-                            code: -32603,
-                            message: e.to_string(),
-                            data: None,
-                        }),
+            let server = Arc::clone(&self);
+            let outgoing = outgoing.clone();
+            tokio::spawn(async move {
+                let Some(response) = server.process_request(trimmed).await else {
+                    return;
+                };
+                match serde_json::to_string(&response) {
+                    Ok(response_json) => {
+                        let _ = outgoing.send(response_json);
                     }
+                    Err(e) => error!("Failed to serialize response: {}", e),
                 }
-            };
-
-            let response_json = serde_json::to_string(&response)?;
-            println!("{}", response_json);
-            io::stdout().flush()?;
+            });
+        }
 
-            line.clear();
+        // Let in-flight requests finish before exiting on EOF or shutdown
+        if let Some(task) = resource_watcher {
+            task.abort();
         }
+        drop(outgoing);
+        writer.await??;
 
         Ok(())
     }
 
-    async fn handle_request(&self, request_json: &str) -> Result<McpResponse> {
+    /// Watch the log directory and notify subscribers when a resource changes
+    fn spawn_resource_watcher(
+        self: &Arc<Self>,
+        outgoing: &mpsc::UnboundedSender<String>,
+    ) -> Option<tokio::task::JoinHandle<()>> {
+        let projects_dirs = match self.projects_dirs() {
+            Ok(projects_dirs) => projects_dirs,
+            Err(e) => {
+                warn!("Resource updates disabled: {:#}", e);
+                return None;
+            }
+        };
+        let mut watcher = match LogWatcher::watch_all(&projects_dirs) {
+            Ok(watcher) => watcher,
+            Err(e) => {
+                warn!("Resource updates disabled: {:#}", e);
+                return None;
+            }
+        };
+
+        let server = Arc::clone(self);
+        let outgoing = outgoing.downgrade();
+        Some(tokio::spawn(async move {
+            while let Some(changed) = watcher.next_batch().await {
+                debug!("{} log files changed", changed.len());
+                let Some(outgoing) = outgoing.upgrade() else {
+                    break;
+                };
+                for uri in server.changed_resources().await {
+                    let notification = json!({
+                        "jsonrpc": "2.0",
+                        "method": "notifications/resources/updated",
+                        "params": { "uri": uri }
+                    });
+                    let _ = outgoing.send(notification.to_string());
+                }
+            }
+        }))
+    }
+
+    /// Subscribed resources whose content differs from the last snapshot
+    async fn changed_resources(&self) -> Vec<String> {
+        let subscribed: Vec<String> = self.subscriptions.lock().unwrap().iter().cloned().collect();
+        let mut changed = Vec::new();
+
+        for uri in subscribed {
+            match self.read_resource(&uri, &CancellationToken::new()).await {
+                Ok(text) => {
+                    if self.record_snapshot(&uri, text) {
+                        changed.push(uri);
+                    }
+                }
+                Err(e) => warn!("Failed to refresh resource {}: {:#}", uri, e),
+            }
+        }
+
+        changed
+    }
+
+    /// Store the latest content of a resource, returning whether it changed
+    fn record_snapshot(&self, uri: &str, text: String) -> bool {
+        let mut snapshots = self.resource_snapshots.lock().unwrap();
+        if snapshots.get(uri) == Some(&text) {
+            return false;
+        }
+        snapshots.insert(uri.to_string(), text);
+        true
+    }
+
+    /// Handle one message under the concurrency cap and request timeout
+    ///
+    /// Notifications (messages without an id) are never answered, so they give `None`,
+    /// and neither are requests the client cancelled.
+    async fn process_request(self: Arc<Self>, request_json: String) -> Option<McpResponse> {
+        let envelope: Value = match serde_json::from_str(&request_json) {
+            Ok(envelope) => envelope,
+            Err(e) => return Some(error_response(None, anyhow::Error::new(ParseError(e.to_string())))),
+        };
+        if !envelope.is_object() {
+            return Some(error_response(None, anyhow::Error::new(InvalidRequest("expected a JSON object".to_string()))));
+        }
+        let Some(id) = envelope.get("id").cloned() else {
+            self.handle_notification(&envelope);
+            return None;
+        };
+        let id = Some(id);
+        if self.shutting_down.load(Ordering::Relaxed) {
+            return Some(error_response(id, anyhow::Error::new(InvalidRequest("server is shutting down".to_string()))));
+        }
+
+        // Only tool calls do real work; protocol requests are always answered
+        let permit = if envelope.get("method").and_then(|m| m.as_str()) == Some("tools/call") {
+            match Arc::clone(&self.tool_call_slots).try_acquire_owned() {
+                Ok(permit) => Some(permit),
+                Err(_) => {
+                    return Some(error_response(id, anyhow::Error::new(RequestRejected(format!(
+                        "Server busy: {} tool calls already running, retry shortly",
+                        self.limits.max_concurrent_tool_calls
+                    )))));
+                }
+            }
+        } else {
+            None
+        };
+
+        let timeout = self.limits.request_timeout;
+        let request_key = id.as_ref().map(Value::to_string).unwrap_or_default();
+        let cancellation = CancellationToken::new();
+        self.in_flight.lock().unwrap().insert(request_key.clone(), cancellation.clone());
+        let server = Arc::clone(&self);
+        let request_cancellation = cancellation.clone();
+        // The slot is held until the handler really stops, not just until the request is answered
+        let mut task = tokio::spawn(async move {
+            let _permit = permit;
+            server.handle_request(&request_json, &request_cancellation).await
+        });
+        let abort_handle = task.abort_handle();
+
+        let outcome = tokio::select! {
+            outcome = tokio::time::timeout(timeout, &mut task) => Some(outcome),
+            _ = cancellation.cancelled() => None,
+        };
+        self.in_flight.lock().unwrap().remove(&request_key);
+
+        let response = match outcome {
+            None => {
+                abort_handle.abort();
+                return None;
+            }
+            Some(Ok(Ok(Ok(response)))) => response,
+            Some(Ok(Ok(Err(e)))) => {
+                error!("Error handling request: {}", e);
+                error_response(id, e)
+            }
+            Some(Ok(Err(e))) => error_response(id, anyhow::anyhow!("Request handler failed: {}", e)),
+            Some(Err(_)) => {
+                // Stop the analysis too, which may be running outside the aborted task
+                cancellation.cancel();
+                abort_handle.abort();
+                error!("Request timed out after {}s", timeout.as_secs());
+                error_response(id, anyhow::Error::new(RequestRejected(format!(
+                    "Request timed out after {} seconds. Narrow the date range or project filter and retry",
+                    timeout.as_secs()
+                ))))
+            }
+        };
+        Some(response)
+    }
+
+    /// Act on a notification from the client; unknown ones are ignored as the protocol requires
+    fn handle_notification(&self, envelope: &Value) {
+        match envelope.get("method").and_then(|method| method.as_str()) {
+            Some("notifications/initialized") => info!("Client initialized"),
+            Some("notifications/cancelled") => self.cancel_request(envelope.get("params")),
+            Some(method) => debug!("Ignoring notification {}", method),
+            None => debug!("Ignoring message without method or id"),
+        }
+    }
+
+    /// Stop the request named by the params of `notifications/cancelled`, if it is still running
+    fn cancel_request(&self, params: Option<&Value>) {
+        let Some(request_id) = params.and_then(|params| params.get("requestId")) else {
+            debug!("Ignoring cancellation without requestId");
+            return;
+        };
+        let reason = params.and_then(|params| params["reason"].as_str()).unwrap_or("no reason given");
+        match self.in_flight.lock().unwrap().get(&request_id.to_string()) {
+            Some(cancellation) => {
+                info!("Cancelling request {}: {}", request_id, reason);
+                cancellation.cancel();
+            }
+            None => debug!("Request {} to cancel is not running", request_id),
+        }
+    }
+
+    async fn handle_request(&self, request_json: &str, cancellation: &CancellationToken) -> Result<McpResponse> {
         debug!("Received request: {}", request_json);
         
-        let request: McpRequest = serde_json::from_str(request_json)?;
+        let request: Value = serde_json::from_str(request_json)
+            .map_err(|e| anyhow::Error::new(ParseError(e.to_string())))?;
+        let request: McpRequest = serde_json::from_value(request)
+            .map_err(|e| anyhow::Error::new(InvalidRequest(e.to_string())))?;
         
         match request.method.as_str() {
             "initialize" => {
@@ -147,7 +1137,12 @@ impl ClaudeWorkAnalysisServer {
                     result: Some(json!({
                         "protocolVersion": "2024-11-05",
                         "capabilities": {
-                            "tools": {}
+                            "tools": {},
+                            "logging": {},
+                            "resources": {
+                                "subscribe": true,
+                                "listChanged": false
+                            }
                         },
                         "serverInfo": {
                             "name": "claude-work-analysis",
@@ -157,6 +1152,141 @@ impl ClaudeWorkAnalysisServer {
                     error: None,
                 })
             }
+            "ping" => {
+                Ok(McpResponse {
+                    jsonrpc: "2.0".to_string(),
+                    id: request.id,
+                    result: Some(json!({})),
+                    error: None,
+                })
+            }
+            "shutdown" => {
+                info!("Client requested shutdown");
+                self.shutting_down.store(true, Ordering::Relaxed);
+                self.shutdown.notify_one();
+                Ok(McpResponse {
+                    jsonrpc: "2.0".to_string(),
+                    id: request.id,
+                    result: Some(json!({})),
+                    error: None,
+                })
+            }
+            "logging/setLevel" => {
+                let level = request.params
+                    .as_ref()
+                    .and_then(|params| params.get("level"))
+                    .and_then(|level| level.as_str())
+                    .ok_or_else(|| invalid_params("Missing level"));
+
+                match level.and_then(|level| self.log_level.set_level(level)) {
+                    Ok(()) => {
+                        info!("Log level set to {}", self.log_level.current_level());
+                        Ok(McpResponse {
+                            jsonrpc: "2.0".to_string(),
+                            id: request.id,
+                            result: Some(json!({})),
+                            error: None,
+                        })
+                    }
+                    Err(e) => Ok(error_response(request.id, e)),
+                }
+            }
+            "resources/list" => {
+                Ok(McpResponse {
+                    jsonrpc: "2.0".to_string(),
+                    id: request.id,
+                    result: Some(json!({
+                        "resources": [
+                            {
+                                "uri": TODAY_SUMMARY_URI,
+                                "name": "今日の活動サマリー",
+                                "description": "今日(システムのタイムゾーン)の作業セッション、作業時間、アクティブプロジェクトのサマリー",
+                                "mimeType": "text/markdown"
+                            },
+                            {
+                                "uri": WEEK_SUMMARY_URI,
+                                "name": "今週の活動サマリー",
+                                "description": "今週(月曜日から今日まで)の作業セッション、作業時間、アクティブプロジェクト、主要テーマのサマリー",
+                                "mimeType": "text/markdown"
+                            },
+                            {
+                                "uri": PROJECTS_URI,
+                                "name": "プロジェクト別統計",
+                                "description": format!("直近{}日間のプロジェクトごとのセッション数、メッセージ数、作業時間(作業時間の長い順)", PROJECT_RESOURCE_DAYS),
+                                "mimeType": "text/markdown"
+                            }
+                        ]
+                    })),
+                    error: None,
+                })
+            }
+            "resources/templates/list" => {
+                Ok(McpResponse {
+                    jsonrpc: "2.0".to_string(),
+                    id: request.id,
+                    result: Some(json!({
+                        "resourceTemplates": [
+                            {
+                                "uriTemplate": PROJECT_URI_TEMPLATE,
+                                "name": "プロジェクト統計",
+                                "description": format!("指定プロジェクトの直近{}日間のセッション数、作業時間、主要トピック、技術スタック(get_project_statsと同じ内容)", PROJECT_RESOURCE_DAYS),
+                                "mimeType": "text/markdown"
+                            }
+                        ]
+                    })),
+                    error: None,
+                })
+            }
+            "resources/read" => {
+                let result = match resource_uri(request.params.as_ref()) {
+                    Ok(uri) => self.read_resource(&uri, cancellation).await.map(|text| (uri, text)),
+                    Err(e) => Err(e),
+                };
+
+                match result {
+                    Ok((uri, text)) => {
+                        self.record_snapshot(&uri, text.clone());
+                        Ok(McpResponse {
+                            jsonrpc: "2.0".to_string(),
+                            id: request.id,
+                            result: Some(json!({
+                                "contents": [
+                                    {
+                                        "uri": uri,
+                                        "mimeType": "text/markdown",
+                                        "text": self.limits.truncate_response(text)
+                                    }
+                                ]
+                            })),
+                            error: None,
+                        })
+                    }
+                    Err(e) => Ok(error_response(request.id, e)),
+                }
+            }
+            "resources/subscribe" | "resources/unsubscribe" => {
+                let uri = match resource_uri(request.params.as_ref()) {
+                    Ok(uri) => uri,
+                    Err(e) => return Ok(error_response(request.id, e)),
+                };
+
+                let mut subscriptions = self.subscriptions.lock().unwrap();
+                if request.method == "resources/subscribe" {
+                    info!("Client subscribed to {}", uri);
+                    subscriptions.insert(uri);
+                } else {
+                    info!("Client unsubscribed from {}", uri);
+                    self.resource_snapshots.lock().unwrap().remove(&uri);
+                    subscriptions.remove(&uri);
+                }
+
+                Ok(McpResponse {
+                    jsonrpc: "2.0".to_string(),
+                    id: request.id,
+                    result: Some(json!({})),
+                    error: None,
+                })
+            }
             "tools/list" => {
                 Ok(McpResponse {
                     jsonrpc: "2.0".to_string(),
@@ -185,6 +1315,34 @@ impl ClaudeWorkAnalysisServer {
                                             "type": "string",
                                             "enum": ["markdown", "json"],
                                             "description": "出力形式"
+                                        },
+                                        "session_gap_minutes": {
+                                            "type": "number",
+                                            "description": "セッションを分割する無操作時間(分、デフォルト120)"
+                                        },
+                                        "min_session_messages": {
+                                            "type": "number",
+                                            "description": "セッションとみなす最小メッセージ数(デフォルト3)"
+                                        },
+                                        "timezone": {
+                                            "type": "string",
+                                            "description": "日付の解釈と表示に使うタイムゾーン(UTC, JST, +09:00等、デフォルトはシステムのタイムゾーン)"
+                                        },
+                                        "include_sidechains": {
+                                            "type": "boolean",
+                                            "description": "サブエージェント(sidechain)のメッセージを含めるか(デフォルトtrue)"
+                                        },
+                                        "merge_restarts_minutes": {
+                                            "type": "number",
+                                            "description": "同じプロジェクトでこの分数以内に再開したセッションを結合(再起動対策、デフォルトは結合しない)"
+                                        },
+                                        "include_thinking": {
+                                            "type": "boolean",
+                                            "description": "アシスタントの拡張思考(thinking)もトピック・問題・解決策の検出に含めるか(デフォルトfalse)"
+                                        },
+                                        "deep_topics": {
+                                            "type": "boolean",
+                                            "description": "プロジェクトのトピック・技術スタックを全メッセージから数えるか(デフォルトfalseはセッション要約から集計、高速だが粗い)"
                                         }
                                     }
                                 }
@@ -197,26 +1355,197 @@ impl ClaudeWorkAnalysisServer {
                                     "properties": {
                                         "project_name": {
                                             "type": "string",
-                                            "description": "プロジェクト名"
+                                            "description": "プロジェクト名(一部でもよい、複数のプロジェクトに当てはまる場合はエラー)"
+                                        },
+                                        "days": {
+                                            "type": "number",
+                                            "description": "過去何日分を分析するか"
+                                        },
+                                        "session_gap_minutes": {
+                                            "type": "number",
+                                            "description": "セッションを分割する無操作時間(分、デフォルト120)"
+                                        },
+                                        "min_session_messages": {
+                                            "type": "number",
+                                            "description": "セッションとみなす最小メッセージ数(デフォルト3)"
+                                        },
+                                        "timezone": {
+                                            "type": "string",
+                                            "description": "日付の解釈と表示に使うタイムゾーン(UTC, JST, +09:00等、デフォルトはシステムのタイムゾーン)"
+                                        },
+                                        "include_sidechains": {
+                                            "type": "boolean",
+                                            "description": "サブエージェント(sidechain)のメッセージを含めるか(デフォルトtrue)"
+                                        },
+                                        "merge_restarts_minutes": {
+                                            "type": "number",
+                                            "description": "同じプロジェクトでこの分数以内に再開したセッションを結合(再起動対策、デフォルトは結合しない)"
+                                        },
+                                        "include_thinking": {
+                                            "type": "boolean",
+                                            "description": "アシスタントの拡張思考(thinking)もトピック・問題・解決策の検出に含めるか(デフォルトfalse)"
+                                        },
+                                        "deep_topics": {
+                                            "type": "boolean",
+                                            "description": "プロジェクトのトピック・技術スタックを全メッセージから数えるか(デフォルトfalseはセッション要約から集計、高速だが粗い)"
+                                        }
+                                    },
+                                    "required": ["project_name"]
+                                }
+                            },
+                            {
+                                "name": "summarize_recent",
+                                "description": "直近の作業活動をサマリー",
+                                "inputSchema": {
+                                    "type": "object", 
+                                    "properties": {
+                                        "days": {
+                                            "type": "number",
+                                            "default": 7,
+                                            "description": "過去何日分をサマリーするか"
+                                        },
+                                        "session_gap_minutes": {
+                                            "type": "number",
+                                            "description": "セッションを分割する無操作時間(分、デフォルト120)"
+                                        },
+                                        "min_session_messages": {
+                                            "type": "number",
+                                            "description": "セッションとみなす最小メッセージ数(デフォルト3)"
+                                        },
+                                        "timezone": {
+                                            "type": "string",
+                                            "description": "日付の解釈と表示に使うタイムゾーン(UTC, JST, +09:00等、デフォルトはシステムのタイムゾーン)"
+                                        },
+                                        "include_sidechains": {
+                                            "type": "boolean",
+                                            "description": "サブエージェント(sidechain)のメッセージを含めるか(デフォルトtrue)"
+                                        },
+                                        "merge_restarts_minutes": {
+                                            "type": "number",
+                                            "description": "同じプロジェクトでこの分数以内に再開したセッションを結合(再起動対策、デフォルトは結合しない)"
+                                        },
+                                        "include_thinking": {
+                                            "type": "boolean",
+                                            "description": "アシスタントの拡張思考(thinking)もトピック・問題・解決策の検出に含めるか(デフォルトfalse)"
+                                        }
+                                    }
+                                }
+                            },
+                            {
+                                "name": "list_sessions",
+                                "description": "期間・プロジェクトで絞り込んだセッションの一覧(ID、プロジェクト、時間、メッセージ数、要約)を新しい順にJSONで取得。limitとoffsetでページ送り",
+                                "inputSchema": {
+                                    "type": "object",
+                                    "properties": {
+                                        "from_date": {
+                                            "type": "string",
+                                            "description": "開始日(YYYY-MM-DD形式)"
+                                        },
+                                        "to_date": {
+                                            "type": "string",
+                                            "description": "終了日(YYYY-MM-DD形式)"
+                                        },
+                                        "project_filter": {
+                                            "type": "string",
+                                            "description": "プロジェクト名でフィルタリング"
+                                        },
+                                        "limit": {
+                                            "type": "number",
+                                            "default": 20,
+                                            "description": "返すセッションの最大数"
+                                        },
+                                        "offset": {
+                                            "type": "number",
+                                            "default": 0,
+                                            "description": "先頭から読み飛ばすセッション数(前の結果のnext_offsetを渡す)"
+                                        },
+                                        "session_gap_minutes": {
+                                            "type": "number",
+                                            "description": "セッションを分割する無操作時間(分、デフォルト120)"
+                                        },
+                                        "min_session_messages": {
+                                            "type": "number",
+                                            "description": "セッションとみなす最小メッセージ数(デフォルト3)"
+                                        },
+                                        "timezone": {
+                                            "type": "string",
+                                            "description": "日付の解釈と表示に使うタイムゾーン(UTC, JST, +09:00等、デフォルトはシステムのタイムゾーン)"
+                                        },
+                                        "include_sidechains": {
+                                            "type": "boolean",
+                                            "description": "サブエージェント(sidechain)のメッセージを含めるか(デフォルトtrue)"
+                                        },
+                                        "merge_restarts_minutes": {
+                                            "type": "number",
+                                            "description": "同じプロジェクトでこの分数以内に再開したセッションを結合(再起動対策、デフォルトは結合しない)"
+                                        },
+                                        "include_thinking": {
+                                            "type": "boolean",
+                                            "description": "アシスタントの拡張思考(thinking)もトピック・問題・解決策の検出に含めるか(デフォルトfalse)"
+                                        }
+                                    }
+                                }
+                            },
+                            {
+                                "name": "standup",
+                                "description": "前日(またはdays日前から昨日まで)の作業をスタンドアップ用の短い箇条書き(プロジェクトと作業時間、取り組んだ課題、次にやること)にまとめる。Slackにそのまま貼れる形式",
+                                "inputSchema": {
+                                    "type": "object",
+                                    "properties": {
+                                        "days": {
+                                            "type": "number",
+                                            "default": 1,
+                                            "description": "今日より前の何日分をまとめるか(月曜日に週末を含めるなら3)"
+                                        },
+                                        "project_filter": {
+                                            "type": "string",
+                                            "description": "プロジェクト名でフィルタリング"
+                                        },
+                                        "session_gap_minutes": {
+                                            "type": "number",
+                                            "description": "セッションを分割する無操作時間(分、デフォルト120)"
+                                        },
+                                        "min_session_messages": {
+                                            "type": "number",
+                                            "description": "セッションとみなす最小メッセージ数(デフォルト3)"
+                                        },
+                                        "timezone": {
+                                            "type": "string",
+                                            "description": "日付の解釈と表示に使うタイムゾーン(UTC, JST, +09:00等、デフォルトはシステムのタイムゾーン)"
                                         },
-                                        "days": {
+                                        "include_sidechains": {
+                                            "type": "boolean",
+                                            "description": "サブエージェント(sidechain)のメッセージを含めるか(デフォルトtrue)"
+                                        },
+                                        "merge_restarts_minutes": {
                                             "type": "number",
-                                            "description": "過去何日分を分析するか"
+                                            "description": "同じプロジェクトでこの分数以内に再開したセッションを結合(再起動対策、デフォルトは結合しない)"
+                                        },
+                                        "include_thinking": {
+                                            "type": "boolean",
+                                            "description": "アシスタントの拡張思考(thinking)もトピック・問題・解決策の検出に含めるか(デフォルトfalse)"
                                         }
-                                    },
-                                    "required": ["project_name"]
+                                    }
                                 }
                             },
                             {
-                                "name": "summarize_recent",
-                                "description": "直近の作業活動をサマリー",
+                                "name": "data_health",
+                                "description": "ログデータの網羅状況(プロジェクト別の期間、ファイル数、解析エラー率、欠損期間)を確認",
                                 "inputSchema": {
-                                    "type": "object", 
+                                    "type": "object",
                                     "properties": {
-                                        "days": {
+                                        "project_filter": {
+                                            "type": "string",
+                                            "description": "プロジェクト名でフィルタリング"
+                                        },
+                                        "gap_days": {
                                             "type": "number",
-                                            "default": 7,
-                                            "description": "過去何日分をサマリーするか"
+                                            "default": 3,
+                                            "description": "欠損期間として報告する最小連続日数"
+                                        },
+                                        "timezone": {
+                                            "type": "string",
+                                            "description": "日付の判定と表示に使うタイムゾーン(デフォルトはシステムのタイムゾーン)"
                                         }
                                     }
                                 }
@@ -227,16 +1556,27 @@ impl ClaudeWorkAnalysisServer {
                 })
             }
             "tools/call" => {
-                let params = request.params.ok_or_else(|| anyhow::anyhow!("Missing params"))?;
+                let params = request.params.ok_or_else(|| invalid_params("Missing params"))?;
                 let tool_name = params["name"].as_str()
-                    .ok_or_else(|| anyhow::anyhow!("Missing tool name"))?;
+                    .ok_or_else(|| invalid_params("Missing tool name"))?;
                 let arguments = params.get("arguments").cloned().unwrap_or(Value::Null);
 
                 let result = match tool_name {
-                    "analyze_work_period" => self.analyze_work_period(arguments).await?,
-                    "get_project_stats" => self.get_project_stats(arguments).await?,
-                    "summarize_recent" => self.summarize_recent(arguments).await?,
-                    _ => return Err(anyhow::anyhow!("Unknown tool: {}", tool_name)),
+                    "analyze_work_period" => self.analyze_work_period(arguments, cancellation).await,
+                    "get_project_stats" => self.get_project_stats(arguments, cancellation).await,
+                    "summarize_recent" => self.summarize_recent(arguments, cancellation).await,
+                    "data_health" => self.data_health(arguments, cancellation).await,
+                    "list_sessions" => self.list_sessions(arguments, cancellation).await,
+                    "standup" => self.standup(arguments, cancellation).await,
+                    _ => Err(invalid_params(format!("Unknown tool: {}", tool_name))),
+                };
+
+                let result = match result {
+                    Ok(text) => self.limits.truncate_response(text),
+                    Err(e) => {
+                        error!("Tool {} failed: {}", tool_name, e);
+                        return Ok(error_response(request.id, e));
+                    }
                 };
 
                 Ok(McpResponse {
@@ -254,125 +1594,194 @@ impl ClaudeWorkAnalysisServer {
                 })
             }
             _ => {
-                Err(anyhow::anyhow!("Unknown method: {}", request.method))
+                Err(anyhow::Error::new(MethodNotFound(request.method)))
             }
         }
     }
 
-    async fn analyze_work_period(&self, params: Value) -> Result<String> {
-        let params: AnalyzePeriodParams = serde_json::from_value(params)?;
-        
-        // Parse date filters (JST timezone)
-        let jst = FixedOffset::east_opt(9 * 3600).unwrap();
-        let from_date = if let Some(from_str) = params.from_date {
-            let date = NaiveDate::parse_from_str(&from_str, "%Y-%m-%d")?;
-            Some(jst.from_local_datetime(&date.and_hms_opt(0, 0, 0).unwrap()).unwrap().with_timezone(&Utc))
-        } else {
-            None
-        };
-        
-        let to_date = if let Some(to_str) = params.to_date {
-            let date = NaiveDate::parse_from_str(&to_str, "%Y-%m-%d")?;
-            Some(jst.from_local_datetime(&date.and_hms_opt(23, 59, 59).unwrap()).unwrap().with_timezone(&Utc))
-        } else {
-            None
-        };
-        
-        let time_filter = TimeRangeFilter::new(from_date, to_date, params.project_filter.clone());
+    async fn analyze_work_period(&self, params: Value, cancellation: &CancellationToken) -> Result<String> {
+        let params: AnalyzePeriodParams = parse_arguments(params)?;
+        params.tuning.validate()?;
+        let config = self.config()?;
+        let schedule = self.timezone_schedule(params.tuning.timezone.as_deref(), &config)?;
 
-        // Get Claude projects directory
-        let home_dir = dirs::home_dir().ok_or_else(|| anyhow::anyhow!("Could not find home directory"))?;
-        let projects_dir = home_dir.join(".claude").join("projects");
+        let format = params.format.as_deref().unwrap_or("markdown");
+        if !matches!(format, "markdown" | "json") {
+            return Err(invalid_params(format!(
+                "Invalid format '{}': expected \"markdown\" or \"json\"",
+                format
+            )));
+        }
         
-        // Scan projects and parse entries
-        let project_paths = self.scanner.scan_projects(&projects_dir)?;
-        let mut all_entries = Vec::new();
-
-        for path in project_paths {
-            match self.parser.parse_file(&path).await {
-                Ok(entries) => {
-                    let filtered_entries = time_filter.filter_entries(entries);
-                    if let Some(project_filter) = &params.project_filter {
-                        let project_entries: Vec<_> = filtered_entries
-                            .into_iter()
-                            .filter(|entry| entry.cwd.contains(project_filter))
-                            .collect();
-                        all_entries.extend(project_entries);
-                    } else {
-                        all_entries.extend(filtered_entries);
-                    }
-                }
-                Err(e) => {
-                    debug!("Failed to parse {}: {}", path.display(), e);
-                }
-            }
+        // Parse date filters in the requested timezone
+        let time_filter =
+            date_range_filter(params.from_date.as_deref(), params.to_date.as_deref(), params.project_filter.clone(), &schedule)?;
+        let (analysis, coverage) = self.analyze_period(&params.tuning, &config, &schedule, &time_filter, cancellation).await?;
+        let mut reporter = ReportGenerator::new().with_timezone_schedule(schedule.clone()).with_insight_rules(config.insights.clone());
+        if let Some(path) = &config.template {
+            reporter = reporter.with_template(ReportTemplate::from_file(&expand_home(path)?)?);
         }
-
-        // Analyze entries
-        let analysis = self.analyzer.analyze_entries(&all_entries)?;
         
         // Generate report
-        let format = params.format.as_deref().unwrap_or("markdown");
         let report = match format {
             "json" => {
                 // For JSON output, create a simplified version
-                let simple_analysis = serde_json::json!({
+                let mut simple_analysis = serde_json::json!({
                     "total_sessions": analysis.total_sessions,
                     "total_messages": analysis.total_messages,
                     "total_work_time_hours": analysis.total_work_time.num_seconds() as f64 / 3600.0,
                     "project_count": analysis.project_stats.len(),
-                    "time_range": {
-                        "start": analysis.time_range.0.with_timezone(&jst),
-                        "end": analysis.time_range.1.with_timezone(&jst)
-                    }
+                    "time_range": null
                 });
+                if analysis.total_sessions > 0 {
+                    simple_analysis["time_range"] = json!({
+                        "start": schedule.to_local(analysis.time_range.0),
+                        "end": schedule.to_local(analysis.time_range.1)
+                    });
+                } else {
+                    simple_analysis["data_coverage"] = json!({
+                        "matched_entries": coverage.matched_entries,
+                        "total_entries": coverage.total_entries,
+                        "first_entry": coverage.first_entry.map(|t| schedule.to_local(t)),
+                        "last_entry": coverage.last_entry.map(|t| schedule.to_local(t))
+                    });
+                }
                 serde_json::to_string_pretty(&simple_analysis)?
             },
-            _ => self.report_generator.generate_markdown_report(&analysis)?,
+            _ if analysis.total_sessions == 0 => reporter.generate_no_activity_report(&time_filter, &coverage),
+            _ => reporter.generate_markdown_report(&analysis)?,
         };
 
         Ok(report)
     }
 
-    async fn get_project_stats(&self, params: Value) -> Result<String> {
-        let params: ProjectStatsParams = serde_json::from_value(params)?;
-        
-        let time_filter = if let Some(days) = params.days {
-            TimeRangeFilter::last_days(days as i64)
-        } else {
-            TimeRangeFilter::new(None, None, Some(params.project_name.clone()))
-        };
+    /// Fold the entries matching the period and project of `time_filter` into sessions
+    ///
+    /// Unknown projects are rejected with suggestions, and files that fail to
+    /// parse are skipped. Results are reused while the log files are unchanged.
+    async fn analyze_period(
+        &self,
+        tuning: &AnalysisTuningParams,
+        config: &Config,
+        schedule: &TimezoneSchedule,
+        time_filter: &TimeRangeFilter,
+        cancellation: &CancellationToken,
+    ) -> Result<(WorkAnalysis, FilterCoverage)> {
+        let projects_dirs = self.projects_dirs()?;
+        if let Some(project_filter) = time_filter.get_project_filter() {
+            self.validate_project(&projects_dirs, project_filter)?;
+        }
 
-        // Get Claude projects directory
-        let home_dir = dirs::home_dir().ok_or_else(|| anyhow::anyhow!("Could not find home directory"))?;
-        let projects_dir = home_dir.join(".claude").join("projects");
-        
-        // Scan and analyze
-        let project_paths = self.scanner.scan_projects(&projects_dir)?;
-        let mut all_entries = Vec::new();
+        let output = AnalysisPipeline::builder()
+            .data_dirs(&projects_dirs)
+            .parser(&self.parser)
+            .filter(time_filter.clone())
+            .analyzer(tuning.build_analyzer(config, schedule))
+            .skip_failed_files(true)
+            .cancellation(cancellation.clone())
+            .result_cache(&self.results, tuning.cache_key(config, schedule))
+            .machines(config.machine_labels()?)
+            .project_patterns(config.project_patterns())
+            .run()
+            .await?;
+        for (path, error) in &output.diagnostics.failed_files {
+            debug!("Failed to parse {}: {}", path.display(), error);
+        }
+        Ok((output.analysis, output.diagnostics.coverage))
+    }
 
-        for path in project_paths {
-            if let Ok(entries) = self.parser.parse_file(&path).await {
-                let filtered_entries = time_filter.filter_entries(entries);
-                let project_entries: Vec<_> = filtered_entries
-                    .into_iter()
-                    .filter(|entry| entry.cwd.contains(&params.project_name))
-                    .collect();
-                all_entries.extend(project_entries);
-            }
+    /// One page of the period's sessions, newest first, as JSON
+    async fn list_sessions(&self, params: Value, cancellation: &CancellationToken) -> Result<String> {
+        let params: ListSessionsParams = parse_arguments(params)?;
+        if params.limit == 0 {
+            return Err(invalid_params("limit must be at least 1"));
+        }
+        params.tuning.validate()?;
+        let config = self.config()?;
+        let schedule = self.timezone_schedule(params.tuning.timezone.as_deref(), &config)?;
+
+        let time_filter =
+            date_range_filter(params.from_date.as_deref(), params.to_date.as_deref(), params.project_filter.clone(), &schedule)?;
+        let (analysis, coverage) = self.analyze_period(&params.tuning, &config, &schedule, &time_filter, cancellation).await?;
+
+        let mut sessions: Vec<&WorkSession> = analysis.sessions.iter().collect();
+        sessions.sort_by_key(|session| std::cmp::Reverse(session.start_time));
+        let page: Vec<Value> = sessions
+            .iter()
+            .skip(params.offset)
+            .take(params.limit)
+            .map(|session| {
+                json!({
+                    "session_id": session.session_id,
+                    "project": analysis.project_name(session),
+                    "project_path": session.project_path,
+                    "start_time": schedule.to_local(session.start_time),
+                    "end_time": schedule.to_local(session.end_time),
+                    "duration_minutes": (session.end_time - session.start_time).num_minutes(),
+                    "messages": session.total_messages,
+                    "user_messages": session.user_messages,
+                    "assistant_messages": session.assistant_messages,
+                    "merged_session_ids": session.merged_session_ids,
+                    "summary": session.summary.as_ref().map(|summary| summary.overall_summary.clone())
+                })
+            })
+            .collect();
+        let next_offset = params.offset + page.len();
+
+        let mut list = json!({
+            "total": sessions.len(),
+            "offset": params.offset,
+            "limit": params.limit,
+            "next_offset": (next_offset < sessions.len()).then_some(next_offset),
+            "sessions": page
+        });
+        // Without sessions, tell whether the logs cover the period at all
+        if sessions.is_empty() {
+            list["data_coverage"] = json!({
+                "matched_entries": coverage.matched_entries,
+                "total_entries": coverage.total_entries,
+                "first_entry": coverage.first_entry.map(|t| schedule.to_local(t)),
+                "last_entry": coverage.last_entry.map(|t| schedule.to_local(t))
+            });
+        }
+        Ok(serde_json::to_string_pretty(&list)?)
+    }
+
+    async fn get_project_stats(&self, params: Value, cancellation: &CancellationToken) -> Result<String> {
+        let params: ProjectStatsParams = parse_arguments(params)?;
+        if params.project_name.trim().is_empty() {
+            return Err(invalid_params("project_name must not be empty"));
         }
+        if params.days == Some(0) {
+            return Err(invalid_params("days must be at least 1"));
+        }
+        params.tuning.validate()?;
+        
+        let (from, to) = match params.days {
+            Some(days) => TimeRangeFilter::last_days(days as i64).get_date_range(),
+            None => (None, None),
+        };
+        let time_filter = TimeRangeFilter::new(from, to, Some(params.project_name.clone()));
 
-        let analysis = self.analyzer.analyze_entries(&all_entries)?;
+        let config = self.config()?;
+        let schedule = self.timezone_schedule(params.tuning.timezone.as_deref(), &config)?;
+        let (analysis, coverage) = self.analyze_period(&params.tuning, &config, &schedule, &time_filter, cancellation).await?;
         
         // Generate focused project report
-        let project_sessions = self.analyzer.get_project_sessions(&analysis, &params.project_name);
+        let Some(project_name) = resolve_project_name(&analysis, &params.project_name)? else {
+            let title = format!("{} プロジェクト統計", params.project_name);
+            return Ok(no_activity_summary(&title, &time_filter, &schedule, &coverage));
+        };
+        let project_sessions: Vec<&WorkSession> =
+            analysis.sessions.iter().filter(|session| analysis.project_name(session) == project_name).collect();
+        let title = format!("{} プロジェクト統計", project_name);
         
-        let mut report = format!("# {} プロジェクト統計\n\n", params.project_name);
+        let mut report = format!("# {}\n\n", title);
         report.push_str(&format!("- セッション数: {}\n", project_sessions.len()));
         report.push_str(&format!("- 総メッセージ数: {}\n", 
             project_sessions.iter().map(|s| s.total_messages).sum::<usize>()));
         
-        if let Some(project_stats) = analysis.project_stats.get(&params.project_name) {
+        if let Some(project_stats) = analysis.project_stats.get(&project_name) {
             report.push_str(&format!("- 作業時間: {:.1}時間\n", 
                 project_stats.work_time.num_seconds() as f64 / 3600.0));
             
@@ -392,62 +1801,816 @@ impl ClaudeWorkAnalysisServer {
         Ok(report)
     }
 
-    async fn summarize_recent(&self, params: Value) -> Result<String> {
-        let params: SummarizeRecentParams = serde_json::from_value(params)?;
+    async fn summarize_recent(&self, params: Value, cancellation: &CancellationToken) -> Result<String> {
+        let params: SummarizeRecentParams = parse_arguments(params)?;
+        if params.days == 0 {
+            return Err(invalid_params("days must be at least 1"));
+        }
+        params.tuning.validate()?;
+        let config = self.config()?;
+        let schedule = self.timezone_schedule(params.tuning.timezone.as_deref(), &config)?;
         
+        let title = format!("直近{}日間の活動サマリー", params.days);
+        // Days that --watch or --daemon keep summarized are answered without reading the logs
+        if params.tuning.analysis_tuning() == AnalysisTuning::default() && params.days <= CACHED_DAYS {
+            let now = Utc::now();
+            let key = SummaryKey::new(&self.projects_dirs()?, schedule.offset_at(now), &config);
+            let today = schedule.to_local(now).date_naive();
+            if let Some((summary, written_at)) = self.daily_summaries.as_ref().and_then(|cache| cache.recent(&key, params.days, today, now)) {
+                return Ok(daily_summary(&title, &summary, written_at, &schedule));
+            }
+        }
+
         let time_filter = TimeRangeFilter::last_days(params.days as i64);
+        let (analysis, coverage) = self.analyze_period(&params.tuning, &config, &schedule, &time_filter, cancellation).await?;
 
-        // Get Claude projects directory
-        let home_dir = dirs::home_dir().ok_or_else(|| anyhow::anyhow!("Could not find home directory"))?;
-        let projects_dir = home_dir.join(".claude").join("projects");
-        
-        // Scan and analyze recent activities
-        let project_paths = self.scanner.scan_projects(&projects_dir)?;
-        let mut all_entries = Vec::new();
+        if analysis.total_sessions == 0 {
+            return Ok(no_activity_summary(&title, &time_filter, &schedule, &coverage));
+        }
+        Ok(compact_summary(&title, &time_filter, &schedule, &analysis))
+    }
+
+    /// Standup update of the days before today, as Slack mrkdwn
+    async fn standup(&self, params: Value, cancellation: &CancellationToken) -> Result<String> {
+        let params: StandupParams = parse_arguments(params)?;
+        if params.days == 0 {
+            return Err(invalid_params("days must be at least 1"));
+        }
+        params.tuning.validate()?;
+        let config = self.config()?;
+        let schedule = self.timezone_schedule(params.tuning.timezone.as_deref(), &config)?;
+
+        let (first_day, last_day) = Standup::days_before(schedule.to_local(Utc::now()).date_naive(), params.days);
+        let time_filter = TimeRangeFilter::new(
+            Some(schedule.start_of_day(first_day)),
+            Some(schedule.end_of_day(last_day)),
+            params.project_filter,
+        );
+        let (analysis, _) = self.analyze_period(&params.tuning, &config, &schedule, &time_filter, cancellation).await?;
+        Ok(ReportGenerator::new().generate_standup_report(&Standup::new(&analysis, first_day, last_day)))
+    }
+
+    async fn read_resource(&self, uri: &str, cancellation: &CancellationToken) -> Result<String> {
+        match uri {
+            TODAY_SUMMARY_URI => self.today_summary(cancellation).await,
+            WEEK_SUMMARY_URI => self.week_summary(cancellation).await,
+            PROJECTS_URI => self.projects_summary(cancellation).await,
+            _ => match uri.strip_prefix(PROJECTS_URI).and_then(|rest| rest.strip_prefix('/')) {
+                Some(project) if !project.is_empty() => {
+                    self.get_project_stats(json!({ "project_name": project, "days": PROJECT_RESOURCE_DAYS }), cancellation)
+                        .await
+                }
+                _ => Err(anyhow::Error::new(ResourceNotFound(uri.to_string()))),
+            },
+        }
+    }
+
+    /// Summary of activity since local midnight
+    async fn today_summary(&self, cancellation: &CancellationToken) -> Result<String> {
+        let schedule = self.timezone_schedule(None, &self.config()?)?;
+        let title = format!("今日の活動サマリー ({})", schedule.to_local(Utc::now()).format("%Y-%m-%d"));
+        self.resource_summary(&title, TimeRangeFilter::today(&schedule), &schedule, cancellation).await
+    }
+
+    /// Summary of activity since Monday
+    async fn week_summary(&self, cancellation: &CancellationToken) -> Result<String> {
+        let schedule = self.timezone_schedule(None, &self.config()?)?;
+        let week_start = ReportingPeriod::Week.containing(schedule.to_local(Utc::now()).date_naive()).0;
+        let title = format!("今週の活動サマリー ({}〜)", week_start.format("%Y-%m-%d"));
+        self.resource_summary(&title, TimeRangeFilter::current_week(&schedule), &schedule, cancellation).await
+    }
+
+    /// Sessions, messages and work time of each project, most worked-on first
+    async fn projects_summary(&self, cancellation: &CancellationToken) -> Result<String> {
+        let schedule = self.timezone_schedule(None, &self.config()?)?;
+        let time_filter = TimeRangeFilter::last_days(PROJECT_RESOURCE_DAYS as i64);
+        let (analysis, coverage) = self.resource_analysis(&time_filter, &schedule, cancellation).await?;
+        let title = format!("直近{}日間のプロジェクト別統計", PROJECT_RESOURCE_DAYS);
+        if analysis.total_sessions == 0 {
+            return Ok(no_activity_summary(&title, &time_filter, &schedule, &coverage));
+        }
+
+        let mut projects: Vec<_> = analysis.project_stats.values().collect();
+        projects.sort_by(|a, b| b.work_time.cmp(&a.work_time).then_with(|| a.project_name.cmp(&b.project_name)));
+        let mut summary = format!("# {}
+
+", title);
+        summary.push_str("| プロジェクト | セッション数 | メッセージ数 | 作業時間 |\n");
+        summary.push_str("|---|---:|---:|---:|\n");
+        for stats in projects {
+            summary.push_str(&format!(
+                "| {} | {} | {} | {:.1}時間 |\n",
+                stats.project_name,
+                stats.total_sessions,
+                stats.total_messages,
+                stats.work_time.num_seconds() as f64 / 3600.0
+            ));
+        }
+        Ok(summary)
+    }
 
-        for path in project_paths {
-            if let Ok(entries) = self.parser.parse_file(&path).await {
-                let filtered_entries = time_filter.filter_entries(entries);
-                all_entries.extend(filtered_entries);
+    /// Compact summary of the period for a resource, analyzed with the configured settings
+    async fn resource_summary(
+        &self,
+        title: &str,
+        time_filter: TimeRangeFilter,
+        schedule: &TimezoneSchedule,
+        cancellation: &CancellationToken,
+    ) -> Result<String> {
+        let (analysis, coverage) = self.resource_analysis(&time_filter, schedule, cancellation).await?;
+        if analysis.total_sessions == 0 {
+            return Ok(no_activity_summary(title, &time_filter, schedule, &coverage));
+        }
+        Ok(compact_summary(title, &time_filter, schedule, &analysis))
+    }
+
+    async fn resource_analysis(
+        &self,
+        time_filter: &TimeRangeFilter,
+        schedule: &TimezoneSchedule,
+        cancellation: &CancellationToken,
+    ) -> Result<(WorkAnalysis, FilterCoverage)> {
+        self.analyze_period(&AnalysisTuningParams::default(), &self.config()?, schedule, time_filter, cancellation).await
+    }
+
+    /// Reject project names that match no project directory, suggesting close matches
+    fn validate_project(&self, projects_dirs: &[PathBuf], project: &str) -> Result<()> {
+        // Let the scan report a missing projects directory
+        let Ok(project_dirs) = self.scanner.get_all_project_directories(projects_dirs) else {
+            return Ok(());
+        };
+
+        let key = encode_project_key(project);
+        let matches_any = project_dirs.iter().any(|dir| {
+            dir.file_name()
+                .and_then(|name| name.to_str())
+                .map(|name| name.to_lowercase().contains(&key))
+                .unwrap_or(false)
+        });
+        if matches_any {
+            return Ok(());
+        }
+
+        let known_projects: Vec<String> = project_dirs
+            .iter()
+            .filter_map(|dir| ProjectScanner::project_name_of_dir(dir))
+            .collect();
+        let suggestions = closest_project_names(project, &known_projects, 3);
+
+        if suggestions.is_empty() {
+            Err(invalid_params(format!("Unknown project '{}'", project)))
+        } else {
+            Err(invalid_params(format!(
+                "Unknown project '{}'. Did you mean: {}?",
+                project,
+                suggestions.join(", ")
+            )))
+        }
+    }
+
+    async fn data_health(&self, params: Value, cancellation: &CancellationToken) -> Result<String> {
+        let params: DataHealthParams = parse_arguments(params)?;
+        let config = self.config()?;
+        let tz = self.timezone_schedule(params.timezone.as_deref(), &config)?.offset_at(Utc::now());
+        if let Some(days) = params.gap_days {
+            if days < 1 {
+                return Err(invalid_params("gap_days must be at least 1"));
             }
         }
 
-        let analysis = self.analyzer.analyze_entries(&all_entries)?;
-        
-        // Generate compact summary
-        let mut summary = format!("# 直近{}日間の活動サマリー\n\n", params.days);
-        summary.push_str(&format!("- 総セッション数: {}\n", analysis.total_sessions));
-        summary.push_str(&format!("- 総メッセージ数: {}\n", analysis.total_messages));
-        summary.push_str(&format!("- 作業時間: {:.1}時間\n\n", 
-            analysis.total_work_time.num_seconds() as f64 / 3600.0));
-
-        summary.push_str("## アクティブプロジェクト\n");
-        for (project_name, stats) in analysis.project_stats.iter().take(5) {
-            summary.push_str(&format!("- **{}**: {}セッション, {:.1}時間\n", 
-                project_name, stats.total_sessions,
-                stats.work_time.num_seconds() as f64 / 3600.0));
-        }
-
-        if let Some(ref conv_summary) = analysis.conversation_summary {
-            summary.push_str("\n## 主要トピック\n");
-            for (topic, count) in conv_summary.most_discussed_topics.iter().take(5) {
-                summary.push_str(&format!("- {} ({}回)\n", topic, count));
-            }
-
-            if !conv_summary.productivity_insights.is_empty() {
-                summary.push_str("\n## 生産性インサイト\n");
-                for insight in &conv_summary.productivity_insights {
-                    summary.push_str(&format!("- {}\n", insight));
+        let mut health = DataHealth::new(tz);
+        if let Some(days) = params.gap_days {
+            health = health.with_min_gap_days(days);
+        }
+
+        // Get Claude projects directory
+        let projects_dirs = self.projects_dirs()?;
+
+        let scanner = ProjectScanner::new().with_patterns(config.project_patterns());
+        let mut project_names: HashMap<PathBuf, String> = HashMap::new();
+        for path in scanner.scan_all_projects(&projects_dirs)? {
+            if cancellation.is_cancelled() {
+                return Err(Error::Cancelled.into());
+            }
+            let project_name = match path.parent() {
+                Some(dir) => project_names
+                    .entry(dir.to_path_buf())
+                    .or_insert_with(|| ProjectScanner::project_name_of_dir(dir).unwrap_or_else(|| "unknown".to_string()))
+                    .clone(),
+                None => "unknown".to_string(),
+            };
+
+            if let Some(ref filter) = params.project_filter {
+                if !project_name.to_lowercase().contains(&filter.to_lowercase()) {
+                    continue;
+                }
+            }
+
+            match self.parser.parse_file_with_stats(&path).await {
+                Ok((entries, stats)) => health.record_file(&project_name, &entries, &stats),
+                Err(e) => {
+                    debug!("Failed to parse {}: {}", path.display(), e);
+                    health.record_unreadable_file(&project_name);
                 }
             }
         }
 
-        Ok(summary)
+        Ok(ReportGenerator::new().with_timezone(tz).generate_health_report(&health))
+    }
+}
+
+impl Default for ClaudeWorkAnalysisServer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Values of every `--projects-dir DIR` pair, as passed on by `claude-work-analysis serve`
+fn projects_dir_args(args: &[String]) -> Result<Vec<PathBuf>> {
+    args.windows(2)
+        .filter(|pair| pair[0] == "--projects-dir")
+        .map(|pair| expand_home(Path::new(&pair[1])))
+        .collect()
+}
+
+/// Keywords of the `--keywords FILE` passed on by `claude-work-analysis serve`
+fn keywords_arg(args: &[String]) -> Result<KeywordLists> {
+    match args.windows(2).find(|pair| pair[0] == "--keywords") {
+        Some(pair) => KeywordLists::from_file(&expand_home(Path::new(&pair[1]))?),
+        None => Ok(KeywordLists::default()),
     }
 }
 
+/// Values of every `FLAG PATTERN` pair of `--include` or `--exclude` passed on by
+/// `claude-work-analysis serve`, or `None` without one
+fn pattern_args(args: &[String], flag: &str) -> Option<Vec<String>> {
+    let patterns: Vec<String> = args.windows(2).filter(|pair| pair[0] == flag).map(|pair| pair[1].clone()).collect();
+    (!patterns.is_empty()).then_some(patterns)
+}
+
+/// File of the `--timezone-schedule FILE` passed on by `claude-work-analysis serve`
+fn timezone_schedule_arg(args: &[String]) -> Result<Option<PathBuf>> {
+    args.windows(2)
+        .find(|pair| pair[0] == "--timezone-schedule")
+        .map(|pair| expand_home(Path::new(&pair[1])))
+        .transpose()
+}
+
+/// Language of the `--language LANGUAGE` passed on by `claude-work-analysis serve`
+fn language_arg(args: &[String]) -> Result<Option<Language>> {
+    args.windows(2)
+        .find(|pair| pair[0] == "--language")
+        .map(|pair| pair[1].parse())
+        .transpose()
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
-    let server = ClaudeWorkAnalysisServer::new();
+    let args: Vec<String> = std::env::args().collect();
+    let refresh = !args.iter().any(|arg| arg == "--no-refresh");
+    let server = ClaudeWorkAnalysisServer::with_limits(ServerLimits::from_env())
+        .with_refresh(refresh)
+        .with_projects_dirs(projects_dir_args(&args)?)
+        .with_keywords(keywords_arg(&args)?)
+        .with_language(language_arg(&args)?)
+        .with_timezone_schedule(timezone_schedule_arg(&args)?)
+        .with_project_patterns(pattern_args(&args, "--include"), pattern_args(&args, "--exclude"));
+    let server = Arc::new(server);
     server.run().await
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use tempfile::TempDir;
+
+    async fn call_tool(tool: &str, arguments: Value) -> McpResponse {
+        let server = ClaudeWorkAnalysisServer::new();
+        let request = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "tools/call",
+            "params": { "name": tool, "arguments": arguments }
+        });
+        server.handle_request(&request.to_string(), &CancellationToken::new()).await.unwrap()
+    }
+
+    fn assert_invalid_params(response: &McpResponse, expected_message: &str) {
+        let error = response.error.as_ref().expect("expected an error response");
+        assert_eq!(error.code, -32602);
+        assert!(
+            error.message.contains(expected_message),
+            "'{}' does not contain '{}'",
+            error.message,
+            expected_message
+        );
+        assert_eq!(response.id, Some(json!(1)));
+    }
+
+    #[tokio::test]
+    async fn test_invalid_date_is_rejected() {
+        let response = call_tool("analyze_work_period", json!({ "from_date": "2025-13-01" })).await;
+        assert_invalid_params(&response, "Invalid from_date '2025-13-01'");
+    }
+
+    #[tokio::test]
+    async fn test_reversed_date_range_is_rejected() {
+        let response = call_tool(
+            "analyze_work_period",
+            json!({ "from_date": "2025-07-10", "to_date": "2025-07-01" }),
+        )
+        .await;
+        assert_invalid_params(&response, "must not be after to_date");
+    }
+
+    #[tokio::test]
+    async fn test_invalid_format_is_rejected() {
+        let response = call_tool("analyze_work_period", json!({ "format": "xml" })).await;
+        assert_invalid_params(&response, "Invalid format 'xml'");
+    }
+
+    #[tokio::test]
+    async fn test_invalid_timezone_is_rejected() {
+        let response = call_tool("summarize_recent", json!({ "timezone": "Mars/Olympus" })).await;
+        assert_invalid_params(&response, "Invalid timezone 'Mars/Olympus'");
+    }
+
+    #[tokio::test]
+    async fn test_invalid_tuning_values_are_rejected() {
+        let response = call_tool("summarize_recent", json!({ "session_gap_minutes": 0 })).await;
+        assert_invalid_params(&response, "session_gap_minutes must be a positive");
+
+        let response = call_tool("analyze_work_period", json!({ "min_session_messages": 0 })).await;
+        assert_invalid_params(&response, "min_session_messages must be at least 1");
+
+        let response = call_tool("analyze_work_period", json!({ "merge_restarts_minutes": -5 })).await;
+        assert_invalid_params(&response, "merge_restarts_minutes must not be negative");
+
+        let response = call_tool("get_project_stats", json!({ "project_name": "api", "session_gap_minutes": 0 })).await;
+        assert_invalid_params(&response, "session_gap_minutes must be a positive");
+    }
+
+    #[tokio::test]
+    async fn test_wrong_argument_type_is_rejected() {
+        let response = call_tool("summarize_recent", json!({ "days": "seven" })).await;
+        assert_invalid_params(&response, "Invalid arguments");
+    }
+
+    #[tokio::test]
+    async fn test_zero_days_is_rejected() {
+        let response = call_tool("summarize_recent", json!({ "days": 0 })).await;
+        assert_invalid_params(&response, "days must be at least 1");
+
+        let response = call_tool("get_project_stats", json!({ "project_name": "api", "days": 0 })).await;
+        assert_invalid_params(&response, "days must be at least 1");
+    }
+
+    #[tokio::test]
+    async fn test_standup_tool() {
+        let response = call_tool("standup", json!({ "days": 0 })).await;
+        assert_invalid_params(&response, "days must be at least 1");
+
+        let dir = TempDir::new().unwrap();
+        let server = ClaudeWorkAnalysisServer::new().with_projects_dirs(vec![dir.path().to_path_buf()]);
+        let text = server.standup(json!({ "days": 3, "timezone": "UTC" }), &CancellationToken::new()).await.unwrap();
+        let today = Utc::now().date_naive();
+        assert!(text.starts_with(&format!("*Worked on* ({} to", (today - Duration::days(3)).format("%a %Y-%m-%d"))));
+        assert!(text.contains("• No Claude Code sessions"));
+    }
+
+    #[tokio::test]
+    async fn test_summarize_recent_from_daily_summaries() {
+        let dir = TempDir::new().unwrap();
+        let cache = DailySummaryCache::new(dir.path().join("daily_summaries.json"));
+        let server = ClaudeWorkAnalysisServer::new()
+            .with_projects_dirs(vec![dir.path().to_path_buf()])
+            .with_daily_summaries(Some(cache.clone()));
+        let utc = FixedOffset::east_opt(0).unwrap();
+        let key = SummaryKey::new(&[dir.path().to_path_buf()], utc, &server.config().unwrap());
+        let now = Utc::now();
+        let today = now.date_naive();
+        let summary = WorkSummary {
+            first_day: today,
+            last_day: today,
+            sessions: 2,
+            messages: 12,
+            work_seconds: 5400,
+            estimated_cost: 1.25,
+            projects: [("api".to_string(), claude_work_analysis::daily_summary::ProjectWork { sessions: 2, work_seconds: 5400 })].into(),
+            topics: [("login form".to_string(), 3)].into(),
+        };
+        cache.store(&key, vec![summary], now).unwrap();
+
+        let text = server.summarize_recent(json!({ "days": 1, "timezone": "UTC" }), &CancellationToken::new()).await.unwrap();
+        assert!(text.contains("- 総セッション数: 2\n"));
+        assert!(text.contains("- **api**: 2セッション, 1.5時間\n"));
+        assert!(text.contains("- login form (3回)\n"));
+
+        // Tuned analyses and days that are not summarized read the logs
+        for arguments in [json!({ "days": 1, "timezone": "UTC", "min_session_messages": 1 }), json!({ "days": 2, "timezone": "UTC" })] {
+            let text = server.summarize_recent(arguments, &CancellationToken::new()).await.unwrap();
+            assert!(text.contains("活動が見つかりませんでした"));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_missing_project_name_is_rejected() {
+        let response = call_tool("get_project_stats", json!({})).await;
+        assert_invalid_params(&response, "missing field `project_name`");
+
+        let response = call_tool("get_project_stats", json!({ "project_name": "  " })).await;
+        assert_invalid_params(&response, "project_name must not be empty");
+    }
+
+    #[tokio::test]
+    async fn test_project_stats_name_one_project() {
+        let dir = TempDir::new().unwrap();
+        claude_work_analysis::sample::write_sample(dir.path()).unwrap();
+        // Move the web session to `webapp`, which shares "ap" with `api`
+        let web_dir = dir.path().join(ProjectScanner::encode_project_path("/home/demo/work/web"));
+        let webapp_dir = dir.path().join(ProjectScanner::encode_project_path("/home/demo/work/webapp"));
+        std::fs::create_dir(&webapp_dir).unwrap();
+        let log = std::fs::read_to_string(web_dir.join("session-2.jsonl")).unwrap();
+        std::fs::write(webapp_dir.join("session-2.jsonl"), log.replace("/home/demo/work/web", "/home/demo/work/webapp")).unwrap();
+        std::fs::remove_dir_all(&web_dir).unwrap();
+        let server = ClaudeWorkAnalysisServer::new().with_projects_dirs(vec![dir.path().to_path_buf()]);
+        let cancellation = CancellationToken::new();
+        let stats = |project: &str| server.get_project_stats(json!({ "project_name": project, "timezone": "UTC" }), &cancellation);
+
+        let error = stats("ap").await.unwrap_err();
+        assert!(error.downcast_ref::<InvalidParams>().is_some());
+        assert_eq!(error.to_string(), "project_name 'ap' matches several projects: api, webapp");
+
+        // Sessions and work time come from the same project
+        let report = stats("api").await.unwrap();
+        assert!(report.starts_with("# api プロジェクト統計\n"));
+        assert!(report.contains("- セッション数: 2\n"));
+        assert!(report.contains("- 作業時間: "));
+        let report = stats("bap").await.unwrap();
+        assert!(report.starts_with("# webapp プロジェクト統計\n"));
+        assert!(report.contains("- セッション数: 1\n"));
+    }
+
+    #[tokio::test]
+    async fn test_unknown_tool_is_rejected() {
+        let response = call_tool("delete_everything", json!({})).await;
+        assert_invalid_params(&response, "Unknown tool: delete_everything");
+    }
+
+    #[tokio::test]
+    async fn test_tool_call_without_name_is_rejected() {
+        let server = Arc::new(ClaudeWorkAnalysisServer::new());
+        let send = |request: Value| Arc::clone(&server).process_request(request.to_string());
+
+        let response = send(json!({ "jsonrpc": "2.0", "id": 1, "method": "tools/call" })).await.unwrap();
+        assert_invalid_params(&response, "Missing params");
+
+        let request = json!({ "jsonrpc": "2.0", "id": 1, "method": "tools/call", "params": { "arguments": {} } });
+        let response = send(request).await.unwrap();
+        assert_invalid_params(&response, "Missing tool name");
+    }
+
+    #[test]
+    fn test_unknown_project_suggests_close_matches() {
+        let temp_dir = TempDir::new().unwrap();
+        std::fs::create_dir(temp_dir.path().join("-Users-me-work-billing")).unwrap();
+        std::fs::create_dir(temp_dir.path().join("-Users-me-work-api")).unwrap();
+
+        let projects_dirs = [temp_dir.path().to_path_buf()];
+        let server = ClaudeWorkAnalysisServer::new();
+        assert!(server.validate_project(&projects_dirs, "billing").is_ok());
+        assert!(server.validate_project(&projects_dirs, "work/api").is_ok());
+
+        let error = server.validate_project(&projects_dirs, "biling").unwrap_err();
+        assert!(error.downcast_ref::<InvalidParams>().is_some());
+        assert!(error.to_string().contains("Did you mean: me/work/billing?"));
+
+        let error = server.validate_project(&projects_dirs, "frontend").unwrap_err();
+        assert_eq!(error.to_string(), "Unknown project 'frontend'");
+    }
+
+    #[test]
+    fn test_response_truncation() {
+        let limits = ServerLimits {
+            max_response_bytes: 10,
+            ..Default::default()
+        };
+
+        assert_eq!(limits.truncate_response("short".to_string()), "short");
+
+        let truncated = limits.truncate_response("あいうえおかきくけこ".to_string());
+        assert!(truncated.starts_with("あいう\n\n[Truncated: response was 30 bytes"));
+    }
+
+    #[tokio::test]
+    async fn test_busy_server_rejects_tool_calls() {
+        let server = Arc::new(ClaudeWorkAnalysisServer::with_limits(ServerLimits {
+            max_concurrent_tool_calls: 1,
+            ..Default::default()
+        }));
+        let _held = Arc::clone(&server.tool_call_slots).try_acquire_owned().unwrap();
+
+        let request = json!({
+            "jsonrpc": "2.0",
+            "id": 7,
+            "method": "tools/call",
+            "params": { "name": "summarize_recent", "arguments": {} }
+        });
+        let response = Arc::clone(&server).process_request(request.to_string()).await.unwrap();
+        let error = response.error.unwrap();
+        assert_eq!(error.code, -32000);
+        assert!(error.message.contains("Server busy"));
+        assert_eq!(response.id, Some(json!(7)));
+
+        // Protocol requests are not subject to the tool call cap
+        let request = json!({ "jsonrpc": "2.0", "id": 8, "method": "tools/list" });
+        let response = server.process_request(request.to_string()).await.unwrap();
+        assert!(response.error.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_protocol_messages() {
+        let server = Arc::new(ClaudeWorkAnalysisServer::new());
+        let send = |message: String| Arc::clone(&server).process_request(message);
+
+        let response = send(json!({ "jsonrpc": "2.0", "id": 1, "method": "ping" }).to_string()).await.unwrap();
+        assert_eq!((response.result, response.error.is_none()), (Some(json!({})), true));
+
+        // Notifications are never answered, even unknown ones
+        for method in ["notifications/initialized", "notifications/cancelled", "notifications/unknown"] {
+            assert!(send(json!({ "jsonrpc": "2.0", "method": method }).to_string()).await.is_none());
+        }
+
+        let response = send(json!({ "jsonrpc": "2.0", "id": 2, "method": "prompts/list" }).to_string()).await.unwrap();
+        let error = response.error.unwrap();
+        assert_eq!((error.code, response.id), (-32601, Some(json!(2))));
+        assert_eq!(error.message, "Method not found: prompts/list");
+
+        let response = send("{\"jsonrpc\": \"2.0\", \"id\": 3,".to_string()).await.unwrap();
+        assert_eq!((response.error.unwrap().code, response.id), (-32700, None));
+        let response = send(json!({ "jsonrpc": "2.0", "id": 4 }).to_string()).await.unwrap();
+        assert_eq!((response.error.unwrap().code, response.id), (-32600, Some(json!(4))));
+        let response = send(json!([1, 2]).to_string()).await.unwrap();
+        assert_eq!(response.error.unwrap().code, -32600);
+
+        // Requests after shutdown are refused
+        let response = send(json!({ "jsonrpc": "2.0", "id": 5, "method": "shutdown" }).to_string()).await.unwrap();
+        assert_eq!(response.result, Some(json!({})));
+        let response = send(json!({ "jsonrpc": "2.0", "id": 6, "method": "ping" }).to_string()).await.unwrap();
+        let error = response.error.unwrap();
+        assert_eq!(error.code, -32600);
+        assert!(error.message.contains("shutting down"));
+    }
+
+    #[tokio::test]
+    async fn test_set_log_level() {
+        let server = ClaudeWorkAnalysisServer::new();
+        assert!(server.log_level.enabled(&Level::INFO));
+
+        let request = json!({
+            "jsonrpc": "2.0",
+            "id": 3,
+            "method": "logging/setLevel",
+            "params": { "level": "warning" }
+        });
+        let response = server.handle_request(&request.to_string(), &CancellationToken::new()).await.unwrap();
+        assert_eq!(response.result, Some(json!({})));
+        assert_eq!(server.log_level.current_level(), "warning");
+        assert!(!server.log_level.enabled(&Level::INFO));
+        assert!(server.log_level.enabled(&Level::ERROR));
+
+        let request = json!({
+            "jsonrpc": "2.0",
+            "id": 4,
+            "method": "logging/setLevel",
+            "params": { "level": "verbose" }
+        });
+        let response = server.handle_request(&request.to_string(), &CancellationToken::new()).await.unwrap();
+        assert_eq!(response.error.unwrap().code, -32602);
+    }
+
+    #[tokio::test]
+    async fn test_log_events_become_notifications() {
+        let control = LogLevelControl::new();
+        let (outgoing, mut outgoing_rx) = mpsc::unbounded_channel::<String>();
+        let subscriber = tracing_subscriber::registry().with(McpLogLayer {
+            control: control.clone(),
+            outgoing: outgoing.downgrade(),
+        });
+
+        tracing::subscriber::with_default(subscriber, || {
+            // Nothing is forwarded until the client asks for a level
+            info!("before setLevel");
+            control.set_level("warning").unwrap();
+            info!("filtered out");
+            tracing::warn!(file = "a.jsonl", "parse problem");
+        });
+
+        let notification: Value = serde_json::from_str(&outgoing_rx.recv().await.unwrap()).unwrap();
+        assert_eq!(notification["method"], "notifications/message");
+        assert_eq!(notification["params"]["level"], "warning");
+        assert_eq!(notification["params"]["data"], "parse problem file=\"a.jsonl\"");
+        assert!(outgoing_rx.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_list_sessions_pages() {
+        let temp_dir = TempDir::new().unwrap();
+        claude_work_analysis::sample::write_sample(temp_dir.path()).unwrap();
+        let server = ClaudeWorkAnalysisServer::new().with_projects_dirs(vec![temp_dir.path().to_path_buf()]);
+        let list = |arguments: Value| async {
+            let text = server.list_sessions(arguments, &CancellationToken::new()).await.unwrap();
+            serde_json::from_str::<Value>(&text).unwrap()
+        };
+
+        let first = list(json!({ "limit": 2, "timezone": "UTC" })).await;
+        assert_eq!(first["total"], 3);
+        assert_eq!(first["sessions"].as_array().unwrap().len(), 2);
+        assert_eq!(first["next_offset"], 2);
+        // Newest first
+        assert!(first["sessions"][0]["start_time"].as_str() > first["sessions"][1]["start_time"].as_str());
+        assert_eq!(first["sessions"][0]["project"], "api");
+
+        let rest = list(json!({ "limit": 2, "offset": 2 })).await;
+        assert_eq!(rest["sessions"].as_array().unwrap().len(), 1);
+        assert!(rest["next_offset"].is_null());
+
+        let web = list(json!({ "project_filter": "web" })).await;
+        assert_eq!(web["total"], 1);
+        assert!(web["sessions"][0]["duration_minutes"].as_i64().unwrap() > 0);
+
+        let none = list(json!({ "from_date": "2001-01-01", "to_date": "2001-01-31" })).await;
+        assert_eq!(none["total"], 0);
+        assert_eq!(none["data_coverage"]["matched_entries"], 0);
+
+        assert_invalid_params(&call_tool("list_sessions", json!({ "limit": 0 })).await, "limit must be at least 1");
+    }
+
+    #[test]
+    fn test_closest_project_names() {
+        let candidates = vec!["work/api".to_string(), "work/apps".to_string(), "home/blog".to_string()];
+        assert_eq!(closest_project_names("apo", &candidates, 3), vec!["work/api", "work/apps"]);
+        assert!(closest_project_names("kubernetes", &candidates, 3).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_requests() {
+        let temp_dir = TempDir::new().unwrap();
+        claude_work_analysis::sample::write_sample(temp_dir.path()).unwrap();
+        let server = ClaudeWorkAnalysisServer::new().with_projects_dirs(vec![temp_dir.path().to_path_buf()]);
+
+        // A request cancelled before it ran fails without analyzing anything
+        let cancelled = CancellationToken::new();
+        cancelled.cancel();
+        let error = server.list_sessions(json!({}), &cancelled).await.unwrap_err();
+        assert!(Error::is_cancellation(&error));
+
+        // notifications/cancelled only cancels the request with its id
+        let running = CancellationToken::new();
+        server.in_flight.lock().unwrap().insert(json!(7).to_string(), running.clone());
+        let cancel = |id: Value| {
+            json!({
+                "jsonrpc": "2.0",
+                "method": "notifications/cancelled",
+                "params": { "requestId": id, "reason": "filters changed" }
+            })
+        };
+        server.handle_notification(&cancel(json!(8)));
+        assert!(!running.is_cancelled());
+        server.handle_notification(&cancel(json!(7)));
+        assert!(running.is_cancelled());
+    }
+
+    #[tokio::test]
+    async fn test_resources_list_and_unknown_resource() {
+        let server = ClaudeWorkAnalysisServer::new();
+        let request = json!({ "jsonrpc": "2.0", "id": 1, "method": "resources/list" });
+        let response = server.handle_request(&request.to_string(), &CancellationToken::new()).await.unwrap();
+        let uris: Vec<Value> = response.result.unwrap()["resources"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|resource| resource["uri"].clone())
+            .collect();
+        assert_eq!(uris, [TODAY_SUMMARY_URI, WEEK_SUMMARY_URI, PROJECTS_URI]);
+
+        let request = json!({ "jsonrpc": "2.0", "id": 1, "method": "resources/templates/list" });
+        let response = server.handle_request(&request.to_string(), &CancellationToken::new()).await.unwrap();
+        assert_eq!(response.result.unwrap()["resourceTemplates"][0]["uriTemplate"], PROJECT_URI_TEMPLATE);
+
+        let request = json!({
+            "jsonrpc": "2.0",
+            "id": 2,
+            "method": "resources/read",
+            "params": { "uri": "claude-work://summary/yesterday" }
+        });
+        let response = server.handle_request(&request.to_string(), &CancellationToken::new()).await.unwrap();
+        assert_eq!(response.error.unwrap().code, -32002);
+    }
+
+    #[tokio::test]
+    async fn test_project_resources() {
+        let temp_dir = TempDir::new().unwrap();
+        claude_work_analysis::sample::write_sample(temp_dir.path()).unwrap();
+        let server = ClaudeWorkAnalysisServer::new().with_projects_dirs(vec![temp_dir.path().to_path_buf()]);
+
+        let projects = server.read_resource(PROJECTS_URI, &CancellationToken::new()).await.unwrap();
+        assert!(projects.starts_with("# 直近30日間のプロジェクト別統計"));
+        let rows: Vec<&str> = projects.lines().filter(|line| line.starts_with("| api ") || line.starts_with("| web ")).collect();
+        assert_eq!(rows.len(), 2);
+
+        let web = server.read_resource("claude-work://projects/web", &CancellationToken::new()).await.unwrap();
+        assert!(web.starts_with("# web プロジェクト統計"));
+        let error = server.read_resource("claude-work://projects/", &CancellationToken::new()).await.unwrap_err();
+        assert!(error.downcast_ref::<ResourceNotFound>().is_some());
+    }
+
+    #[tokio::test]
+    async fn test_resource_subscriptions() {
+        let server = ClaudeWorkAnalysisServer::new();
+        for method in ["resources/subscribe", "resources/unsubscribe"] {
+            let request = json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "method": method,
+                "params": { "uri": TODAY_SUMMARY_URI }
+            });
+            let response = server.handle_request(&request.to_string(), &CancellationToken::new()).await.unwrap();
+            assert!(response.error.is_none());
+            let subscribed = server.subscriptions.lock().unwrap().contains(TODAY_SUMMARY_URI);
+            assert_eq!(subscribed, method == "resources/subscribe");
+        }
+
+        let request = json!({ "jsonrpc": "2.0", "id": 2, "method": "resources/subscribe", "params": {} });
+        let response = server.handle_request(&request.to_string(), &CancellationToken::new()).await.unwrap();
+        assert_eq!(response.error.unwrap().code, -32602);
+    }
+
+    #[test]
+    fn test_resource_snapshot_change_detection() {
+        let server = ClaudeWorkAnalysisServer::new();
+        assert!(server.record_snapshot(TODAY_SUMMARY_URI, "3 sessions".to_string()));
+        assert!(!server.record_snapshot(TODAY_SUMMARY_URI, "3 sessions".to_string()));
+        assert!(server.record_snapshot(TODAY_SUMMARY_URI, "4 sessions".to_string()));
+    }
+
+    #[test]
+    fn test_no_activity_summary() {
+        let schedule = TimezoneSchedule::fixed(FixedOffset::east_opt(9 * 3600).unwrap());
+        let from = Utc.with_ymd_and_hms(2025, 7, 9, 15, 0, 0).unwrap();
+        let filter = TimeRangeFilter::new(Some(from), None, None);
+        let coverage = FilterCoverage {
+            total_entries: 12,
+            matched_entries: 0,
+            first_entry: Some(Utc.with_ymd_and_hms(2025, 7, 1, 0, 0, 0).unwrap()),
+            last_entry: Some(Utc.with_ymd_and_hms(2025, 7, 5, 0, 0, 0).unwrap()),
+        };
+
+        let summary = no_activity_summary("今日の活動サマリー", &filter, &schedule, &coverage);
+        assert!(summary.contains("活動が見つかりませんでした"));
+        assert!(summary.contains("- 期間: 2025-07-10 00:00 〜 指定なし (JST)"));
+        assert!(summary.contains("- プロジェクト: すべて"));
+        assert!(summary.contains("- 記録期間: 2025-07-01 09:00 〜 2025-07-05 09:00"));
+
+        let sparse = FilterCoverage { matched_entries: 2, ..coverage };
+        assert!(no_activity_summary("t", &filter, &schedule, &sparse).contains("2件のログが条件に一致しました"));
+        assert!(no_activity_summary("t", &filter, &schedule, &FilterCoverage::default()).contains("ログが1件も見つかりませんでした"));
+    }
+
+    #[test]
+    fn test_projects_dir_args() {
+        let args: Vec<String> = ["mcp-server", "--no-refresh", "--projects-dir", "/a/projects", "--projects-dir", "/b/projects"]
+            .iter()
+            .map(|arg| arg.to_string())
+            .collect();
+        assert_eq!(projects_dir_args(&args).unwrap(), [PathBuf::from("/a/projects"), PathBuf::from("/b/projects")]);
+        assert!(projects_dir_args(&args[..2]).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_pattern_args() {
+        let args: Vec<String> = ["mcp-server", "--include", "*work*", "--exclude", "*-scratch*", "--include", "*api*"]
+            .iter()
+            .map(|arg| arg.to_string())
+            .collect();
+        assert_eq!(pattern_args(&args, "--include"), Some(vec!["*work*".to_string(), "*api*".to_string()]));
+        assert_eq!(pattern_args(&args, "--exclude"), Some(vec!["*-scratch*".to_string()]));
+        assert_eq!(pattern_args(&args[..1], "--include"), None);
+    }
+
+    #[test]
+    fn test_keywords_arg() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("keywords.toml");
+        std::fs::write(&path, "technologies = [\"elixir\"]\n").unwrap();
+        let args = vec!["mcp-server".to_string(), "--keywords".to_string(), path.display().to_string()];
+
+        assert_eq!(keywords_arg(&args).unwrap().technologies, ["elixir"]);
+        assert_eq!(keywords_arg(&args[..1]).unwrap(), KeywordLists::default());
+
+        let language = |args: &[&str]| language_arg(&args.iter().map(|arg| arg.to_string()).collect::<Vec<_>>());
+        assert_eq!(language(&["mcp-server", "--language", "ja"]).unwrap(), Some(Language::Ja));
+        assert_eq!(language(&["mcp-server"]).unwrap(), None);
+        assert!(language(&["mcp-server", "--language", "fr"]).is_err());
+    }
+}
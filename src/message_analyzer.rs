@@ -1,10 +1,188 @@
-use std::collections::HashMap;
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::collections::{BTreeSet, HashMap};
+use std::path::Path;
+use std::str::FromStr;
 
 use crate::models::{
-    ClaudeLogEntry, SessionSummary, ConversationSummary, TopicAnalysis,
-    MessageContentVariant, EntryType
+    confidence, ClaudeLogEntry, SessionSummary, ConversationSummary, TopicAnalysis,
+    MessageContentVariant, EntryType, FollowUpStats
 };
 
+/// Custom keywords to detect, from `[keywords]` in the config file or `--keywords`
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct KeywordLists {
+    pub technologies: Vec<String>,
+    pub problems: Vec<String>,
+    pub solutions: Vec<String>,
+    pub learning: Vec<String>,
+    /// Use the non-empty lists instead of the built-in ones rather than adding to them,
+    /// e.g. to analyze conversations in another language
+    pub replace: bool,
+}
+
+impl KeywordLists {
+    /// Load keyword lists from a TOML file with the keys of `[keywords]`
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read keyword file: {}", path.display()))?;
+        toml::from_str(&content).with_context(|| format!("Invalid keyword file: {}", path.display()))
+    }
+
+    /// Add the keywords of `other`; replacing applies if either asks for it
+    pub fn merge(&mut self, other: KeywordLists) {
+        self.technologies.extend(other.technologies);
+        self.problems.extend(other.problems);
+        self.solutions.extend(other.solutions);
+        self.learning.extend(other.learning);
+        self.replace |= other.replace;
+    }
+}
+
+/// Language of the prompts the built-in problem, solution and learning keywords are for
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Language {
+    /// Both sets; Japanese keywords only match Japanese text and the reverse
+    #[default]
+    Auto,
+    En,
+    Ja,
+}
+
+impl Language {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Language::Auto => "auto",
+            Language::En => "en",
+            Language::Ja => "ja",
+        }
+    }
+}
+
+impl FromStr for Language {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "auto" => Ok(Language::Auto),
+            "en" => Ok(Language::En),
+            "ja" => Ok(Language::Ja),
+            _ => Err(anyhow::anyhow!("Invalid language '{}'. Expected auto, en or ja", s)),
+        }
+    }
+}
+
+const EN_PROBLEMS: &[&str] = &[
+    "error", "bug", "issue", "problem", "fail", "broken", "not work",
+    "doesn't work", "crash", "exception", "undefined", "null", "panic",
+    "stuck", "confused", "help", "troubleshoot", "debug", "fix"
+];
+
+const EN_SOLUTIONS: &[&str] = &[
+    "solution", "fix", "resolve", "implement", "create", "build", "add",
+    "update", "modify", "change", "refactor", "optimize", "improve",
+    "configure", "setup", "install", "deploy"
+];
+
+const EN_LEARNING: &[&str] = &[
+    "learn", "understand", "explain", "how to", "what is", "why",
+    "tutorial", "guide", "documentation", "example", "best practice",
+    "pattern", "concept", "theory", "principle"
+];
+
+const JA_PROBLEMS: &[&str] = &[
+    "エラー", "バグ", "不具合", "失敗", "動かない", "動作しない", "落ちる", "クラッシュ",
+    "例外", "問題", "おかしい", "できない", "直して", "困って", "原因", "警告", "修正"
+];
+
+const JA_SOLUTIONS: &[&str] = &[
+    "解決", "修正", "実装", "対応", "追加", "作成", "変更", "更新", "改善", "最適化",
+    "リファクタ", "設計", "設定", "導入", "構築", "デプロイ"
+];
+
+const JA_LEARNING: &[&str] = &[
+    "学ぶ", "学び", "理解", "説明", "教えて", "とは", "仕組み", "違い", "なぜ", "どうして",
+    "使い方", "入門", "チュートリアル", "ベストプラクティス", "概念"
+];
+
+/// Kanji and katakana words too generic to be topics, besides the Japanese keywords
+const JA_STOPWORDS: &[&str] = &[
+    "今日", "昨日", "明日", "今回", "確認", "方法", "場合", "必要", "使用", "可能", "以下", "以上",
+    "部分", "全部", "内容", "意味", "感じ", "状態", "結果", "理由", "一覧", "作業", "対象", "自分", "仕組",
+    "何", "事", "時", "方", "前", "後", "中", "上", "下", "次", "他", "全", "今", "ファイル", "コード"
+];
+
+/// English words that end a topic phrase rather than belong to one, besides the keywords
+const EN_STOPWORDS: &[&str] = &[
+    "a", "about", "above", "after", "again", "all", "also", "am", "an", "and", "any", "are", "around", "as", "at",
+    "be", "because", "been", "before", "being", "below", "between", "both", "but", "by", "can", "could", "did",
+    "do", "does", "doing", "done", "down", "during", "each", "either", "else", "even", "every", "few", "for",
+    "from", "further", "get", "gets", "getting", "got", "had", "has", "have", "having", "he", "her", "here",
+    "hers", "him", "his", "how", "i", "if", "in", "into", "is", "it", "its", "itself", "just", "let", "lets",
+    "like", "make", "makes", "making", "may", "me", "might", "more", "most", "much", "must", "my", "need",
+    "needs", "new", "no", "nor", "not", "now", "of", "off", "ok", "okay", "on", "once", "one", "only", "or",
+    "other", "our", "out", "over", "own", "please", "put", "really", "same", "see", "she", "should", "so",
+    "some", "something", "still", "such", "sure", "take", "than", "thank", "thanks", "that", "the", "their",
+    "them", "then", "there", "these", "they", "thing", "things", "this", "those", "through", "to", "too",
+    "try", "under", "until", "up", "us", "use", "used", "uses", "using", "very", "via", "want", "was", "way",
+    "we", "well", "were", "what", "when", "where", "whether", "which", "while", "who", "whom", "will", "with",
+    "without", "would", "yes", "yet", "you", "your", "yours",
+    // Requests that say what to do rather than what about
+    "add", "adding", "build", "change", "check", "code", "configure", "create", "design", "file", "files",
+    "find", "give", "go", "keep", "look", "move", "remove", "run", "set", "setup", "show", "slow", "speed",
+    "start", "stop", "tell", "work", "write", "front",
+];
+
+/// Words of an English topic phrase; longer runs of content words are listed word by word
+const MAX_PHRASE_WORDS: usize = 3;
+
+/// Topics kept in a session summary, the most mentioned first
+const MAX_SESSION_TOPICS: usize = 10;
+
+/// Solution patterns and complexity indicators shown per project
+const TOPIC_EXAMPLES: usize = 5;
+
+/// Messages mentioning a technology for it to be in a project's technical stack
+const MIN_STACK_MENTIONS: usize = 3;
+
+/// Topic statistics of some messages, merged into a project's [`TopicAnalysis`]
+#[derive(Debug, Clone, Default)]
+pub struct TopicCounts {
+    topics: HashMap<String, usize>,
+    technologies: HashMap<String, usize>,
+    problem_categories: HashMap<String, usize>,
+    /// First distinct solution patterns in alphabetical order
+    solution_patterns: Vec<String>,
+    /// Complex discussions in message order
+    complexity_indicators: Vec<String>,
+    /// Counted per session from session summaries rather than per message
+    from_summaries: bool,
+}
+
+impl TopicCounts {
+    /// Add the counts of messages following these
+    pub fn merge(&mut self, other: &TopicCounts) {
+        for (counts, other_counts) in [
+            (&mut self.topics, &other.topics),
+            (&mut self.technologies, &other.technologies),
+            (&mut self.problem_categories, &other.problem_categories),
+        ] {
+            for (key, count) in other_counts {
+                *counts.entry(key.clone()).or_insert(0) += count;
+            }
+        }
+        self.solution_patterns.extend(other.solution_patterns.iter().cloned());
+        self.solution_patterns.sort();
+        self.solution_patterns.dedup();
+        self.solution_patterns.truncate(TOPIC_EXAMPLES);
+        let missing = TOPIC_EXAMPLES.saturating_sub(self.complexity_indicators.len());
+        self.complexity_indicators.extend(other.complexity_indicators.iter().take(missing).cloned());
+        self.from_summaries |= other.from_summaries;
+    }
+}
+
 pub struct MessageAnalyzer {
     /// Technology keywords for detection
     tech_keywords: Vec<String>,
@@ -14,6 +192,8 @@ pub struct MessageAnalyzer {
     solution_indicators: Vec<String>,
     /// Learning indicators
     learning_indicators: Vec<String>,
+    /// Whether the assistant's extended thinking is analyzed with the visible text
+    include_thinking: bool,
 }
 
 impl MessageAnalyzer {
@@ -29,30 +209,64 @@ impl MessageAnalyzer {
                 "sql", "nosql", "html", "css", "sass", "scss", "tailwind", "bootstrap"
             ].iter().map(|s| s.to_string()).collect(),
             
-            problem_indicators: vec![
-                "error", "bug", "issue", "problem", "fail", "broken", "not work",
-                "doesn't work", "crash", "exception", "undefined", "null", "panic",
-                "stuck", "confused", "help", "troubleshoot", "debug", "fix"
-            ].iter().map(|s| s.to_string()).collect(),
-            
-            solution_indicators: vec![
-                "solution", "fix", "resolve", "implement", "create", "build", "add",
-                "update", "modify", "change", "refactor", "optimize", "improve",
-                "configure", "setup", "install", "deploy"
-            ].iter().map(|s| s.to_string()).collect(),
-            
-            learning_indicators: vec![
-                "learn", "understand", "explain", "how to", "what is", "why",
-                "tutorial", "guide", "documentation", "example", "best practice",
-                "pattern", "concept", "theory", "principle"
-            ].iter().map(|s| s.to_string()).collect(),
+            problem_indicators: EN_PROBLEMS.iter().chain(JA_PROBLEMS).map(|s| s.to_string()).collect(),
+            solution_indicators: EN_SOLUTIONS.iter().chain(JA_SOLUTIONS).map(|s| s.to_string()).collect(),
+            learning_indicators: EN_LEARNING.iter().chain(JA_LEARNING).map(|s| s.to_string()).collect(),
+
+            include_thinking: false,
+        }
+    }
+
+    /// Keep only the built-in problem, solution and learning keywords of `language`
+    ///
+    /// Keywords added with [`with_keywords`](Self::with_keywords) before are kept
+    /// unless they are built-in keywords of the other language.
+    pub fn with_language(mut self, language: Language) -> Self {
+        let excluded = match language {
+            Language::Auto => return self,
+            Language::En => [JA_PROBLEMS, JA_SOLUTIONS, JA_LEARNING],
+            Language::Ja => [EN_PROBLEMS, EN_SOLUTIONS, EN_LEARNING],
+        };
+        let lists = [&mut self.problem_indicators, &mut self.solution_indicators, &mut self.learning_indicators];
+        for (list, excluded) in lists.into_iter().zip(excluded) {
+            list.retain(|keyword| !excluded.contains(&keyword.as_str()));
+        }
+        self
+    }
+
+    /// Also detect the given keywords, or only them with `replace`; matching is case-insensitive
+    pub fn with_keywords(mut self, keywords: &KeywordLists) -> Self {
+        let lists = [
+            (&mut self.tech_keywords, &keywords.technologies),
+            (&mut self.problem_indicators, &keywords.problems),
+            (&mut self.solution_indicators, &keywords.solutions),
+            (&mut self.learning_indicators, &keywords.learning),
+        ];
+        for (list, extra) in lists {
+            if keywords.replace && !extra.is_empty() {
+                list.clear();
+            }
+            for keyword in extra {
+                let keyword = keyword.to_lowercase();
+                if !keyword.is_empty() && !list.contains(&keyword) {
+                    list.push(keyword);
+                }
+            }
         }
+        self
+    }
+
+    /// Also analyze the assistant's extended thinking, which is skipped by default
+    pub fn with_thinking(mut self, include: bool) -> Self {
+        self.include_thinking = include;
+        self
     }
 
     /// Analyze a single session and generate summary
     pub fn analyze_session(&self, entries: &[ClaudeLogEntry]) -> SessionSummary {
         let mut key_discussions = Vec::new();
         let mut problems_addressed = Vec::new();
+        let mut problem_confidence: HashMap<String, f64> = HashMap::new();
         let mut solutions_proposed = Vec::new();
         let mut learning_moments = Vec::new();
         
@@ -75,7 +289,11 @@ impl MessageAnalyzer {
                 EntryType::User => {
                     // Extract user questions and requests
                     if self.contains_any(&content_lower, &self.problem_indicators) {
-                        problems_addressed.push(self.extract_key_phrase(&content, 100));
+                        let problem = self.extract_key_phrase(&content, 100);
+                        let confidence = self.problem_confidence(&content_lower);
+                        let known = problem_confidence.entry(problem.clone()).or_insert(confidence);
+                        *known = known.max(confidence);
+                        problems_addressed.push(problem);
                     }
                     
                     if self.contains_any(&content_lower, &self.learning_indicators) {
@@ -110,11 +328,8 @@ impl MessageAnalyzer {
             .collect();
         technologies_mentioned.sort();
         
-        let mut main_topics: Vec<String> = topic_keywords
-            .into_iter()
-            .filter(|(_, count)| *count >= 1) // At least 1 mention
-            .map(|(topic, _)| topic)
-            .collect();
+        let topic_mentions = most_mentioned(topic_keywords);
+        let mut main_topics: Vec<String> = topic_mentions.keys().cloned().collect();
         main_topics.sort();
         
         // Generate overall summary
@@ -125,29 +340,80 @@ impl MessageAnalyzer {
             &solutions_proposed
         );
         
+        let problems_addressed: Vec<String> = problems_addressed.into_iter().take(5).collect();
         SessionSummary {
             main_topics,
+            topic_mentions,
             key_discussions: key_discussions.into_iter().take(5).collect(),
             technologies_mentioned,
-            problems_addressed: problems_addressed.into_iter().take(5).collect(),
+            problem_confidence: confidences_of(&problems_addressed, &[&problem_confidence]),
+            problems_addressed,
             solutions_proposed: solutions_proposed.into_iter().take(5).collect(),
             learning_moments: learning_moments.into_iter().take(3).collect(),
             overall_summary,
         }
     }
     
+    /// Summary of two sessions merged after a restart, when their entries were not kept
+    pub fn merge_summaries(&self, first: &SessionSummary, second: &SessionSummary) -> SessionSummary {
+        let union = |a: &[String], b: &[String]| {
+            let mut items: Vec<String> = a.iter().chain(b).cloned().collect();
+            items.sort();
+            items.dedup();
+            items
+        };
+        let chain = |a: &[String], b: &[String], limit: usize| a.iter().chain(b).take(limit).cloned().collect::<Vec<_>>();
+
+        let mut topic_mentions = HashMap::new();
+        for summary in [first, second] {
+            for (topic, mentions) in summary.mentions() {
+                *topic_mentions.entry(topic.clone()).or_insert(0) += mentions;
+            }
+        }
+        let topic_mentions = most_mentioned(topic_mentions);
+        let main_topics = union(&first.main_topics, &second.main_topics)
+            .into_iter()
+            .filter(|topic| topic_mentions.contains_key(topic))
+            .collect::<Vec<_>>();
+        let technologies_mentioned = union(&first.technologies_mentioned, &second.technologies_mentioned);
+        let problems_addressed = chain(&first.problems_addressed, &second.problems_addressed, 5);
+        let solutions_proposed = chain(&first.solutions_proposed, &second.solutions_proposed, 5);
+        let overall_summary =
+            self.generate_session_summary(&main_topics, &technologies_mentioned, &problems_addressed, &solutions_proposed);
+
+        SessionSummary {
+            main_topics,
+            topic_mentions,
+            key_discussions: chain(&first.key_discussions, &second.key_discussions, 5),
+            technologies_mentioned,
+            problem_confidence: confidences_of(&problems_addressed, &[&first.problem_confidence, &second.problem_confidence]),
+            problems_addressed,
+            solutions_proposed,
+            learning_moments: chain(&first.learning_moments, &second.learning_moments, 3),
+            overall_summary,
+        }
+    }
+
     /// Analyze multiple sessions and generate conversation summary
-    pub fn analyze_conversations(&self, sessions_with_summaries: &[(Vec<ClaudeLogEntry>, SessionSummary)]) -> ConversationSummary {
+    ///
+    /// The most discussed topics are ranked by TF-IDF with the sessions as
+    /// documents: prompts mentioning a topic, weighted down the more sessions
+    /// share it, so words every session uses fall behind specific subjects.
+    pub fn analyze_conversations(&self, summaries: &[&SessionSummary]) -> ConversationSummary {
         let mut all_topics: HashMap<String, usize> = HashMap::new();
+        let mut topic_mentions: HashMap<String, usize> = HashMap::new();
         let mut tech_usage: HashMap<String, usize> = HashMap::new();
         let mut common_problems = Vec::new();
         let mut learning_progression = Vec::new();
         
-        for (_, summary) in sessions_with_summaries {
+        for summary in summaries {
             // Aggregate topics
             for topic in &summary.main_topics {
                 *all_topics.entry(topic.clone()).or_insert(0) += 1;
             }
+            for (topic, mentions) in summary.mentions() {
+                *topic_mentions.entry(topic.clone()).or_insert(0) += mentions;
+            }
             
             // Aggregate technologies
             for tech in &summary.technologies_mentioned {
@@ -159,49 +425,152 @@ impl MessageAnalyzer {
             learning_progression.extend(summary.learning_moments.clone());
         }
         
-        // Sort topics by frequency
+        // Rank topics by TF-IDF, ties in alphabetical order
+        let documents = summaries.len() as f64;
+        let weight = |topic: &String, sessions: usize| {
+            let inverse_frequency = ((1.0 + documents) / (1.0 + sessions as f64)).ln() + 1.0;
+            topic_mentions[topic] as f64 * inverse_frequency
+        };
         let mut most_discussed_topics: Vec<(String, usize)> = all_topics.into_iter().collect();
-        most_discussed_topics.sort_by(|a, b| b.1.cmp(&a.1));
+        most_discussed_topics.sort_by(|a, b| {
+            weight(&b.0, b.1).total_cmp(&weight(&a.0, a.1)).then_with(|| a.0.cmp(&b.0))
+        });
         
         // Generate productivity insights
-        let productivity_insights = self.generate_productivity_insights(sessions_with_summaries);
+        let productivity_insights = self.generate_productivity_insights(summaries);
         
         // Extract overall themes
         let overall_themes = self.extract_overall_themes(&most_discussed_topics, &tech_usage);
         
+        let common_problems = self.deduplicate_and_limit(common_problems, 10);
+        let confidences: Vec<&HashMap<String, f64>> = summaries.iter().map(|summary| &summary.problem_confidence).collect();
         ConversationSummary {
             total_topics: most_discussed_topics.len(),
             most_discussed_topics: most_discussed_topics.into_iter().take(10).collect(),
             technology_usage: tech_usage,
-            common_problems: self.deduplicate_and_limit(common_problems, 10),
+            problem_confidence: confidences_of(&common_problems, &confidences),
+            common_problems,
             learning_progression: self.deduplicate_and_limit(learning_progression, 10),
             productivity_insights,
             overall_themes,
+            topic_themes: Vec::new(),
+            follow_ups_by_technology: HashMap::new(),
         }
     }
     
+    /// Problems raised in prompts that no later response proposed a solution for
+    ///
+    /// Responses mention fixes and changes often, so these are mostly the
+    /// problems a session ended on.
+    pub fn open_problems(&self, entries: &[ClaudeLogEntry]) -> Vec<String> {
+        let mut open = Vec::new();
+        for entry in entries.iter().filter(|entry| !entry.is_sidechain) {
+            let content = self.extract_text_content(&entry.message.content);
+            let content_lower = content.to_lowercase();
+            match entry.entry_type {
+                EntryType::User if self.contains_any(&content_lower, &self.problem_indicators) => {
+                    open.push(self.extract_key_phrase(&content, 100));
+                }
+                EntryType::Assistant if self.contains_any(&content_lower, &self.solution_indicators) => open.clear(),
+                _ => {}
+            }
+        }
+        open
+    }
+
+    /// Whether a prompt is counted as raising a problem
+    pub fn raises_problem(&self, content: &str) -> bool {
+        self.contains_any(&content.to_lowercase(), &self.problem_indicators)
+    }
+
+    /// Confidence that a prompt raises a problem, 0 without problem indicators
+    ///
+    /// Each indicator found is a clear signal, or a weak one when it is only part
+    /// of another word, like "fix" in "prefix".
+    pub fn problem_confidence(&self, content: &str) -> f64 {
+        let content = content.to_lowercase();
+        let evidence: f64 = self
+            .problem_indicators
+            .iter()
+            .filter(|keyword| content.contains(keyword.as_str()))
+            .map(|keyword| {
+                let at_word_start = !keyword.is_ascii()
+                    || content.match_indices(keyword.as_str()).any(|(start, _)| {
+                        !content[..start].chars().next_back().is_some_and(char::is_alphanumeric)
+                    });
+                if at_word_start { 2.0 } else { 0.5 }
+            })
+            .sum();
+        confidence(evidence)
+    }
+
+    /// Follow-up prompts of the discussions about each technology in a session
+    ///
+    /// A prompt opens a discussion about the technologies it and the replies to it
+    /// mention. Later prompts follow up on it until one mentions a technology the
+    /// discussion is not about. Discussions without a technology are not counted.
+    pub fn count_follow_ups(&self, entries: &[ClaudeLogEntry]) -> HashMap<String, FollowUpStats> {
+        let mut counts: HashMap<String, FollowUpStats> = HashMap::new();
+        let mut close = |technologies: &BTreeSet<String>, follow_ups: usize| {
+            for tech in technologies {
+                counts.entry(tech.clone()).or_default().merge(&FollowUpStats { discussions: 1, follow_ups });
+            }
+        };
+        // Technologies of the current discussion, its follow-ups so far, and whether
+        // the replies to its opening prompt are still being read
+        let mut discussion: Option<(BTreeSet<String>, usize, bool)> = None;
+
+        for entry in entries.iter().filter(|entry| !entry.is_sidechain) {
+            let content = self.extract_text_content(&entry.message.content);
+            let content_lower = content.to_lowercase();
+            let technologies: BTreeSet<String> =
+                self.tech_keywords.iter().filter(|tech| content_lower.contains(tech.as_str())).cloned().collect();
+            match (entry.entry_type, &mut discussion) {
+                // Tool results carry no text
+                (EntryType::User, _) if content.trim().is_empty() => {}
+                (EntryType::User, Some((current, follow_ups, opening))) if technologies.is_subset(current) => {
+                    *follow_ups += 1;
+                    *opening = false;
+                }
+                (EntryType::User, _) => {
+                    if let Some((current, follow_ups, _)) = discussion.take() {
+                        close(&current, follow_ups);
+                    }
+                    discussion = Some((technologies, 0, true));
+                }
+                (EntryType::Assistant, Some((current, _, true))) => current.extend(technologies),
+                (EntryType::Assistant, _) => {}
+            }
+        }
+        if let Some((current, follow_ups, _)) = discussion {
+            close(&current, follow_ups);
+        }
+        counts
+    }
+
     /// Generate topic analysis for a project
-    pub fn analyze_project_topics(&self, all_entries: &[ClaudeLogEntry]) -> TopicAnalysis {
-        let mut problem_categories: HashMap<String, usize> = HashMap::new();
-        let mut complexity_indicators = Vec::new();
-        
-        let mut topic_frequency: HashMap<String, usize> = HashMap::new();
-        let mut tech_frequency: HashMap<String, usize> = HashMap::new();
-        
-        for entry in all_entries {
+    pub fn analyze_project_topics(&self, all_entries: &[&ClaudeLogEntry]) -> TopicAnalysis {
+        self.topic_analysis(&self.count_topics(all_entries.iter().copied()))
+    }
+
+    /// Count the topics of some messages, e.g. a session's, to merge into a project's analysis
+    pub fn count_topics<'e>(&self, entries: impl IntoIterator<Item = &'e ClaudeLogEntry>) -> TopicCounts {
+        let mut counts = TopicCounts::default();
+
+        for entry in entries {
             let content = self.extract_text_content(&entry.message.content);
             let content_lower = content.to_lowercase();
             
             // Count topic frequencies
             let topics = self.extract_topics(&content);
             for topic in topics {
-                *topic_frequency.entry(topic).or_insert(0) += 1;
+                *counts.topics.entry(topic).or_insert(0) += 1;
             }
             
             // Count technology frequencies
             for tech in &self.tech_keywords {
                 if content_lower.contains(tech) {
-                    *tech_frequency.entry(tech.clone()).or_insert(0) += 1;
+                    *counts.technologies.entry(tech.clone()).or_insert(0) += 1;
                 }
             }
             
@@ -209,41 +578,92 @@ impl MessageAnalyzer {
             if let EntryType::User = entry.entry_type {
                 let problem_category = self.categorize_problem(&content_lower);
                 if !problem_category.is_empty() {
-                    *problem_categories.entry(problem_category).or_insert(0) += 1;
+                    *counts.problem_categories.entry(problem_category).or_insert(0) += 1;
+                }
+            }
+
+            // Collect solution patterns
+            if let EntryType::Assistant = entry.entry_type {
+                if content_lower.contains("pattern") || content_lower.contains("approach") {
+                    counts.solution_patterns.push(self.extract_key_phrase(&content, 120));
                 }
             }
             
             // Detect complexity indicators
-            if self.is_complex_discussion(&content) {
-                complexity_indicators.push(self.extract_key_phrase(&content, 80));
+            if counts.complexity_indicators.len() < TOPIC_EXAMPLES && self.is_complex_discussion(&content) {
+                counts.complexity_indicators.push(self.extract_key_phrase(&content, 80));
             }
         }
+
+        counts.solution_patterns = self.deduplicate_and_limit(counts.solution_patterns, TOPIC_EXAMPLES);
+        counts
+    }
+
+    /// Count the topics of a session from its summary, without reading its messages again
+    ///
+    /// Topics and technologies are counted once per session, and problems are
+    /// categorized from the problems the summary kept, so the analysis is coarser
+    /// than [`Self::count_topics`] over the same messages.
+    pub fn count_summary_topics(&self, summary: &SessionSummary) -> TopicCounts {
+        let mut counts = TopicCounts { from_summaries: true, ..TopicCounts::default() };
+        for topic in &summary.main_topics {
+            *counts.topics.entry(topic.clone()).or_insert(0) += 1;
+        }
+        for tech in &summary.technologies_mentioned {
+            *counts.technologies.entry(tech.clone()).or_insert(0) += 1;
+        }
+        for problem in &summary.problems_addressed {
+            let problem_category = self.categorize_problem(&problem.to_lowercase());
+            if !problem_category.is_empty() {
+                *counts.problem_categories.entry(problem_category).or_insert(0) += 1;
+            }
+        }
+        let patterns = summary
+            .solutions_proposed
+            .iter()
+            .filter(|solution| {
+                let solution = solution.to_lowercase();
+                solution.contains("pattern") || solution.contains("approach")
+            })
+            .cloned()
+            .collect();
+        counts.solution_patterns = self.deduplicate_and_limit(patterns, TOPIC_EXAMPLES);
+        counts.complexity_indicators = summary
+            .key_discussions
+            .iter()
+            .filter(|discussion| self.mentions_complex_subject(discussion))
+            .take(TOPIC_EXAMPLES)
+            .cloned()
+            .collect();
+        counts
+    }
+
+    /// Topic analysis of the messages counted in `counts`
+    pub fn topic_analysis(&self, counts: &TopicCounts) -> TopicAnalysis {
+        // Sort and categorize topics, ties in alphabetical order
+        let mut sorted_topics: Vec<(&String, &usize)> = counts.topics.iter().collect();
+        sorted_topics.sort_by(|a, b| b.1.cmp(a.1).then_with(|| a.0.cmp(b.0)));
         
-        // Sort and categorize topics
-        let mut sorted_topics: Vec<(String, usize)> = topic_frequency.into_iter().collect();
-        sorted_topics.sort_by(|a, b| b.1.cmp(&a.1));
-        
-        let primary_topics: Vec<String> = sorted_topics.iter().take(5).map(|(topic, _)| topic.clone()).collect();
-        let secondary_topics: Vec<String> = sorted_topics.iter().skip(5).take(10).map(|(topic, _)| topic.clone()).collect();
+        let primary_topics: Vec<String> = sorted_topics.iter().take(5).map(|(topic, _)| (*topic).clone()).collect();
+        let secondary_topics: Vec<String> = sorted_topics.iter().skip(5).take(10).map(|(topic, _)| (*topic).clone()).collect();
         
-        // Extract technical stack
-        let mut technical_stack: Vec<String> = tech_frequency
-            .into_iter()
-            .filter(|(_, count)| *count >= 3)
-            .map(|(tech, _)| tech)
+        // Extract technical stack; a session summary lists each technology once
+        let min_mentions = if counts.from_summaries { 1 } else { MIN_STACK_MENTIONS };
+        let mut technical_stack: Vec<String> = counts
+            .technologies
+            .iter()
+            .filter(|(_, count)| **count >= min_mentions)
+            .map(|(tech, _)| tech.clone())
             .collect();
         technical_stack.sort();
         
-        // Generate solution patterns
-        let solution_patterns = self.extract_solution_patterns(all_entries);
-        
         TopicAnalysis {
             primary_topics,
             secondary_topics,
             technical_stack,
-            problem_categories,
-            solution_patterns,
-            complexity_indicators: complexity_indicators.into_iter().take(5).collect(),
+            problem_categories: counts.problem_categories.clone(),
+            solution_patterns: counts.solution_patterns.clone(),
+            complexity_indicators: counts.complexity_indicators.clone(),
         }
     }
     
@@ -254,7 +674,11 @@ impl MessageAnalyzer {
             MessageContentVariant::Array(blocks) => {
                 blocks
                     .iter()
-                    .filter_map(|block| block.text.as_ref())
+                    .filter_map(|block| match (&block.text, &block.thinking) {
+                        (Some(text), _) => Some(text),
+                        (None, Some(thinking)) if self.include_thinking => Some(thinking),
+                        _ => None,
+                    })
                     .cloned()
                     .collect::<Vec<String>>()
                     .join(" ")
@@ -267,7 +691,7 @@ impl MessageAnalyzer {
     }
     
     fn extract_key_phrase(&self, text: &str, max_length: usize) -> String {
-        let sentences: Vec<&str> = text.split('.').collect();
+        let sentences: Vec<&str> = text.split(['.', '。']).collect();
         for sentence in sentences {
             let sentence = sentence.trim();
             if sentence.chars().count() <= max_length && sentence.chars().count() > 10 {
@@ -284,50 +708,69 @@ impl MessageAnalyzer {
         }
     }
     
-    fn extract_topics(&self, content: &str) -> Vec<String> {
+    /// Topics of a prompt: phrases of content words and technical terms
+    ///
+    /// English topics are the runs of words between stop words and keywords, e.g.
+    /// "login form" in "create a login form in react". Japanese is written without
+    /// spaces, so its runs of kanji and katakana are taken as the nouns and the
+    /// hiragana between them as particles.
+    pub fn extract_topics(&self, content: &str) -> Vec<String> {
         let mut topics = Vec::new();
-        
-        // Simple keyword extraction - in a real implementation,
-        // you might use NLP libraries or more sophisticated methods
+        let mut phrase: Vec<&str> = Vec::new();
         let content_lower = content.to_lowercase();
-        let words: Vec<&str> = content_lower
-            .split_whitespace()
-            .filter(|word| word.len() > 3)
-            .collect();
-        
-        // Look for potential topics (nouns, technical terms)
-        for window in words.windows(2) {
-            let phrase = window.join(" ");
-            if self.is_potential_topic(&phrase) {
-                topics.push(phrase);
-            }
-        }
-        
-        // Also include single important words
-        for word in &words {
-            if self.is_important_single_word(word) {
-                topics.push(word.to_string());
+        for word in content_lower.split_whitespace() {
+            for (japanese, token) in split_scripts(word) {
+                if japanese {
+                    self.push_phrase(&mut topics, &mut phrase);
+                    if token.chars().count() >= 2 && !self.is_stopword(token) {
+                        topics.push(token.to_string());
+                    }
+                    continue;
+                }
+                let term = token.trim_matches(|c: char| !c.is_alphanumeric());
+                if self.is_topic_word(term) {
+                    phrase.push(term);
+                } else {
+                    self.push_phrase(&mut topics, &mut phrase);
+                }
+                // Punctuation after a word ends the phrase
+                if !term.is_empty() && !token.ends_with(term) {
+                    self.push_phrase(&mut topics, &mut phrase);
+                }
             }
         }
-        
+        self.push_phrase(&mut topics, &mut phrase);
+
+        let mut seen = std::collections::HashSet::new();
+        topics.retain(|topic| seen.insert(topic.clone()));
         topics
     }
-    
-    fn is_potential_topic(&self, phrase: &str) -> bool {
-        // Simple heuristics for topic detection
-        phrase.contains("implement") ||
-        phrase.contains("create") ||
-        phrase.contains("build") ||
-        phrase.contains("design") ||
-        phrase.contains("configure") ||
-        phrase.contains("setup")
+
+    fn push_phrase(&self, topics: &mut Vec<String>, phrase: &mut Vec<&str>) {
+        if phrase.len() > MAX_PHRASE_WORDS {
+            topics.extend(phrase.iter().map(|word| word.to_string()));
+        } else if !phrase.is_empty() {
+            topics.push(phrase.join(" "));
+        }
+        phrase.clear();
     }
-    
-    fn is_important_single_word(&self, word: &str) -> bool {
-        self.tech_keywords.contains(&word.to_string()) ||
-        word.len() > 6 && !word.chars().all(|c| c.is_ascii_lowercase())
+
+    fn is_topic_word(&self, term: &str) -> bool {
+        (term.chars().count() >= 3 || self.tech_keywords.iter().any(|tech| tech == term))
+            && term.chars().count() <= 30
+            && term.chars().any(|c| c.is_alphabetic())
+            && !self.is_stopword(term)
     }
-    
+
+    fn is_stopword(&self, word: &str) -> bool {
+        [EN_STOPWORDS, JA_STOPWORDS, JA_PROBLEMS, JA_SOLUTIONS, JA_LEARNING, EN_PROBLEMS, EN_SOLUTIONS, EN_LEARNING]
+            .into_iter()
+            .any(|list| list.contains(&word))
+            || [&self.problem_indicators, &self.solution_indicators, &self.learning_indicators]
+                .into_iter()
+                .any(|list| list.iter().any(|keyword| keyword == word))
+    }
+
     fn generate_session_summary(&self, topics: &[String], tech: &[String], problems: &[String], solutions: &[String]) -> String {
         let mut summary_parts = Vec::new();
         
@@ -373,33 +816,19 @@ impl MessageAnalyzer {
     }
     
     fn is_complex_discussion(&self, content: &str) -> bool {
-        content.len() > 500 &&
-        (content.contains("architecture") ||
-         content.contains("design pattern") ||
-         content.contains("best practice") ||
-         content.contains("scalability") ||
-         content.contains("performance") ||
-         content.contains("security"))
+        content.len() > 500 && self.mentions_complex_subject(content)
     }
-    
-    fn extract_solution_patterns(&self, entries: &[ClaudeLogEntry]) -> Vec<String> {
-        let mut patterns = Vec::new();
-        
-        for entry in entries {
-            if let EntryType::Assistant = entry.entry_type {
-                let content = self.extract_text_content(&entry.message.content);
-                let content_lower = content.to_lowercase();
-                
-                if content_lower.contains("pattern") || content_lower.contains("approach") {
-                    patterns.push(self.extract_key_phrase(&content, 120));
-                }
-            }
-        }
-        
-        self.deduplicate_and_limit(patterns, 5)
+
+    fn mentions_complex_subject(&self, content: &str) -> bool {
+        content.contains("architecture") ||
+        content.contains("design pattern") ||
+        content.contains("best practice") ||
+        content.contains("scalability") ||
+        content.contains("performance") ||
+        content.contains("security")
     }
     
-    fn generate_productivity_insights(&self, sessions: &[(Vec<ClaudeLogEntry>, SessionSummary)]) -> Vec<String> {
+    fn generate_productivity_insights(&self, sessions: &[&SessionSummary]) -> Vec<String> {
         let mut insights = Vec::new();
         
         if sessions.len() > 5 {
@@ -408,7 +837,7 @@ impl MessageAnalyzer {
         
         let tech_diversity: std::collections::HashSet<String> = sessions
             .iter()
-            .flat_map(|(_, summary)| summary.technologies_mentioned.clone())
+            .flat_map(|summary| summary.technologies_mentioned.clone())
             .collect();
         
         if tech_diversity.len() > 5 {
@@ -417,7 +846,7 @@ impl MessageAnalyzer {
         
         let total_problems: usize = sessions
             .iter()
-            .map(|(_, summary)| summary.problems_addressed.len())
+            .map(|summary| summary.problems_addressed.len())
             .sum();
         
         if total_problems > 10 {
@@ -453,12 +882,71 @@ impl MessageAnalyzer {
     }
 }
 
+/// The most mentioned topics of a session, ties in alphabetical order
+fn most_mentioned(mentions: HashMap<String, usize>) -> HashMap<String, usize> {
+    let mut topics: Vec<(String, usize)> = mentions.into_iter().collect();
+    topics.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    topics.into_iter().take(MAX_SESSION_TOPICS).collect()
+}
+
+/// The highest confidence of each problem among the summaries' confidences
+fn confidences_of(problems: &[String], confidences: &[&HashMap<String, f64>]) -> HashMap<String, f64> {
+    problems
+        .iter()
+        .filter_map(|problem| {
+            let highest = confidences.iter().filter_map(|known| known.get(problem)).copied().reduce(f64::max)?;
+            Some((problem.clone(), highest))
+        })
+        .collect()
+}
+
 impl Default for MessageAnalyzer {
     fn default() -> Self {
         Self::new()
     }
 }
 
+#[derive(Clone, Copy, PartialEq)]
+enum Script {
+    /// Kanji and katakana, which Japanese nouns are written in
+    Japanese,
+    /// Hiragana and Japanese punctuation, which separate the nouns
+    Separator,
+    Other,
+}
+
+fn script(c: char) -> Script {
+    match c {
+        '\u{4E00}'..='\u{9FFF}' | '\u{3400}'..='\u{4DBF}' | '\u{30A1}'..='\u{30FA}' | 'ー' | '々' => Script::Japanese,
+        '\u{3000}'..='\u{30A0}' | '\u{30FB}' | '\u{FF00}'..='\u{FFEF}' => Script::Separator,
+        _ => Script::Other,
+    }
+}
+
+/// Runs of a whitespace-separated word in the same script, flagged when Japanese;
+/// a word without Japanese characters is a single run
+fn split_scripts(word: &str) -> Vec<(bool, &str)> {
+    let mut runs = Vec::new();
+    let mut start = None;
+    let mut current = Script::Separator;
+    for (index, c) in word.char_indices() {
+        let next = script(c);
+        if next != current {
+            if let Some(from) = start.take() {
+                runs.push((current == Script::Japanese, &word[from..index]));
+            }
+            if next != Script::Separator {
+                start = Some(index);
+            }
+            current = next;
+        }
+    }
+    if let Some(from) = start {
+        runs.push((current == Script::Japanese, &word[from..]));
+    }
+    runs
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -492,6 +980,7 @@ mod tests {
             timestamp: Utc::now(),
             request_id: None,
             tool_use_result: None,
+            source: None,
         }
     }
     
@@ -510,6 +999,30 @@ mod tests {
         assert!(summary.technologies_mentioned.contains(&"rust".to_string()));
     }
     
+    #[test]
+    fn test_follow_ups() {
+        let analyzer = MessageAnalyzer::new();
+        let mut subagent = create_test_entry(EntryType::User, "Search the docker files");
+        subagent.is_sidechain = true;
+        let entries = vec![
+            create_test_entry(EntryType::User, "The build fails"),
+            create_test_entry(EntryType::Assistant, "Cargo cannot find the crate"),
+            create_test_entry(EntryType::User, "Still failing"),
+            subagent,
+            create_test_entry(EntryType::User, "Now it panics in cargo test"),
+            create_test_entry(EntryType::Assistant, "Docker is not involved here"),
+            // A new technology opens another discussion
+            create_test_entry(EntryType::User, "Now the docker image"),
+            create_test_entry(EntryType::Assistant, "Done"),
+        ];
+
+        let counts = analyzer.count_follow_ups(&entries);
+
+        assert_eq!(counts["cargo"], FollowUpStats { discussions: 1, follow_ups: 2 });
+        assert_eq!(counts["docker"], FollowUpStats { discussions: 1, follow_ups: 0 });
+        assert_eq!(counts.len(), 2);
+    }
+
     #[test]
     fn test_technology_detection() {
         let analyzer = MessageAnalyzer::new();
@@ -522,4 +1035,148 @@ mod tests {
         assert!(summary.technologies_mentioned.contains(&"react".to_string()));
         assert!(summary.technologies_mentioned.contains(&"typescript".to_string()));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_extra_keywords() {
+        let keywords = KeywordLists {
+            technologies: vec!["Svelte".to_string(), "rust".to_string()],
+            ..KeywordLists::default()
+        };
+        let analyzer = MessageAnalyzer::new().with_keywords(&keywords);
+        let entries = vec![create_test_entry(EntryType::User, "Port the Svelte store to Rust")];
+
+        let summary = analyzer.analyze_session(&entries);
+
+        assert!(summary.technologies_mentioned.contains(&"svelte".to_string()));
+        assert_eq!(summary.technologies_mentioned.iter().filter(|tech| *tech == "rust").count(), 1);
+    }
+
+    #[test]
+    fn test_problem_confidence() {
+        let analyzer = MessageAnalyzer::new();
+        assert_eq!(analyzer.problem_confidence("The build fails"), 0.75);
+        assert!(analyzer.problem_confidence("The build fails with an error") > 0.9);
+        // "fix" inside another word is weak evidence
+        assert!(analyzer.problem_confidence("Write a prefix tree") < 0.3);
+        assert_eq!(analyzer.problem_confidence("Add a button"), 0.0);
+
+        let entries = vec![
+            create_test_entry(EntryType::User, "ログイン画面でエラーが出る"),
+            create_test_entry(EntryType::User, "Write a prefix tree"),
+        ];
+        let summary = analyzer.analyze_session(&entries);
+        assert_eq!(summary.problem_confidence["ログイン画面でエラーが出る"], 0.75);
+        let merged = analyzer.merge_summaries(&summary, &analyzer.analyze_session(&entries[..1]));
+        assert_eq!(merged.problem_confidence.len(), 2);
+        let conversation = analyzer.analyze_conversations(&[&summary]);
+        assert!(conversation.problem_confidence["Write a prefix tree"] < 0.3);
+    }
+
+    #[test]
+    fn test_topic_extraction() {
+        let analyzer = MessageAnalyzer::new();
+        assert_eq!(analyzer.extract_topics("Please implement that in the login form, with react."), ["login form", "react"]);
+        assert_eq!(analyzer.extract_topics("Can you fix this for me?"), Vec::<String>::new());
+
+        // "database" is in every session, "stripe webhooks" in one but mentioned more
+        let session = |prompts: &[&str]| {
+            let entries: Vec<ClaudeLogEntry> =
+                prompts.iter().map(|prompt| create_test_entry(EntryType::User, prompt)).collect();
+            analyzer.analyze_session(&entries)
+        };
+        let summaries = [
+            session(&["Stripe webhooks for the database", "Retry the stripe webhooks"]),
+            session(&["Index the database"]),
+            session(&["Back up the database"]),
+        ];
+        assert_eq!(summaries[0].topic_mentions["stripe webhooks"], 2);
+        let conversation = analyzer.analyze_conversations(&summaries.iter().collect::<Vec<_>>());
+        assert_eq!(conversation.most_discussed_topics[0], ("stripe webhooks".to_string(), 1));
+        assert_eq!(conversation.most_discussed_topics[1], ("database".to_string(), 3));
+    }
+
+    #[test]
+    fn test_japanese_prompts() {
+        let entries = vec![
+            create_test_entry(EntryType::User, "ログイン画面でエラーが出る。Rustの認証処理を見て"),
+            create_test_entry(EntryType::User, "async/awaitの仕組みを教えて"),
+            create_test_entry(EntryType::Assistant, "認証処理のトークン検証を修正しました"),
+        ];
+        let summary = MessageAnalyzer::new().analyze_session(&entries);
+        assert_eq!(summary.problems_addressed, ["ログイン画面でエラーが出る"]);
+        assert_eq!(summary.learning_moments.len(), 1);
+        assert_eq!(summary.solutions_proposed.len(), 1);
+        // Kanji and katakana runs are the nouns; keywords and generic words are not topics
+        assert_eq!(summary.main_topics, ["async/await", "rust", "ログイン画面", "認証処理"]);
+        assert!(summary.technologies_mentioned.contains(&"rust".to_string()));
+
+        let english = MessageAnalyzer::new().with_language(Language::En);
+        assert!(!english.raises_problem("テストが失敗する") && english.raises_problem("the test fails"));
+        let japanese = MessageAnalyzer::new().with_language(Language::Ja).with_keywords(&KeywordLists {
+            problems: vec!["timeout".to_string()],
+            ..KeywordLists::default()
+        });
+        assert!(japanese.raises_problem("テストが失敗する") && !japanese.raises_problem("the test fails"));
+        assert!(japanese.raises_problem("requests timeout"));
+        assert_eq!("JA".parse::<Language>().unwrap(), Language::Ja);
+        assert!("fr".parse::<Language>().is_err());
+    }
+
+    #[test]
+    fn test_replaced_keywords() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("keywords.toml");
+        std::fs::write(&path, "replace = true\ntechnologies = [\"Elixir\", \"Phoenix\"]\nproblems = [\"エラー\"]\n").unwrap();
+        let mut keywords = KeywordLists {
+            technologies: vec!["unity".to_string()],
+            ..KeywordLists::default()
+        };
+        keywords.merge(KeywordLists::from_file(&path).unwrap());
+        let analyzer = MessageAnalyzer::new().with_keywords(&keywords);
+        let entries = vec![create_test_entry(EntryType::User, "Phoenix LiveViewでエラーが出る、rustではなくElixirの話")];
+
+        let summary = analyzer.analyze_session(&entries);
+
+        let mut technologies = summary.technologies_mentioned.clone();
+        technologies.sort();
+        assert_eq!(technologies, ["elixir", "phoenix"]);
+        assert_eq!(summary.problems_addressed.len(), 1);
+
+        std::fs::write(&path, "technologies = \"elixir\"").unwrap();
+        assert!(KeywordLists::from_file(&path).unwrap_err().to_string().starts_with("Invalid keyword file"));
+    }
+
+    #[test]
+    fn test_thinking_blocks() {
+        let mut entry = create_test_entry(EntryType::Assistant, "");
+        entry.message.content = MessageContentVariant::Array(
+            serde_json::from_value(serde_json::json!([
+                { "type": "thinking", "thinking": "Maybe the docker network is down", "signature": "sig" },
+                { "type": "redacted_thinking", "data": "..." },
+                { "type": "text", "text": "Let me check the logs." }
+            ]))
+            .unwrap(),
+        );
+        let entries = vec![entry];
+
+        let summary = MessageAnalyzer::new().analyze_session(&entries);
+        assert!(!summary.technologies_mentioned.contains(&"docker".to_string()));
+        let summary = MessageAnalyzer::new().with_thinking(true).analyze_session(&entries);
+        assert!(summary.technologies_mentioned.contains(&"docker".to_string()));
+
+        let session = crate::models::WorkSession {
+            session_id: Uuid::new_v4(),
+            project_path: "/test".to_string(),
+            start_time: Utc::now(),
+            end_time: Utc::now(),
+            entries,
+            total_messages: 1,
+            user_messages: 0,
+            assistant_messages: 1,
+            summary: None,
+            merged_session_ids: Vec::new(),
+        };
+        let volume = session.reasoning_volume();
+        assert_eq!((volume.thinking_blocks, volume.thinking_chars), (2, 32));
+    }
+}
@@ -0,0 +1,147 @@
+use chrono::{DateTime, Utc};
+use std::fmt::Write;
+use std::time::Duration;
+
+use crate::models::WorkAnalysis;
+
+/// Prefix of every metric name
+const PREFIX: &str = "claude_work";
+
+/// The analysis in the Prometheus text exposition format, for `/metrics`
+///
+/// Totals are gauges rather than counters: Claude Code deletes old logs, so
+/// the totals over the logs that remain can go down.
+pub fn render(analysis: &WorkAnalysis, refreshed_at: DateTime<Utc>, refresh_duration: Duration) -> String {
+    let mut metrics = Metrics::default();
+
+    metrics.family("sessions", "Sessions in the logs");
+    metrics.sample("sessions", &[], analysis.total_sessions as f64);
+    metrics.family("messages", "Messages in the logs");
+    metrics.sample("messages", &[], analysis.total_messages as f64);
+    metrics.family("work_hours", "Estimated work time in hours");
+    metrics.sample("work_hours", &[], hours(analysis.total_work_time.num_minutes()));
+
+    let mut projects: Vec<_> = analysis.project_stats.values().collect();
+    projects.sort_by(|a, b| a.project_name.cmp(&b.project_name));
+    metrics.family("project_sessions", "Sessions per project");
+    for stats in &projects {
+        metrics.sample("project_sessions", &[("project", &stats.project_name)], stats.total_sessions as f64);
+    }
+    metrics.family("project_messages", "Messages per project");
+    for stats in &projects {
+        metrics.sample("project_messages", &[("project", &stats.project_name)], stats.total_messages as f64);
+    }
+    metrics.family("project_work_hours", "Estimated work time per project in hours");
+    for stats in &projects {
+        metrics.sample("project_work_hours", &[("project", &stats.project_name)], hours(stats.work_time.num_minutes()));
+    }
+
+    if let Some(tokens) = &analysis.token_analysis {
+        let mut models: Vec<_> = tokens.by_model.iter().collect();
+        models.sort_by_key(|(model, _)| model.as_str());
+        metrics.family("tokens", "Tokens per model and kind");
+        for (model, stats) in &models {
+            let usage = &stats.usage;
+            let kinds = [
+                ("input", usage.input_tokens),
+                ("output", usage.output_tokens),
+                ("cache_creation", usage.cache_creation_tokens),
+                ("cache_read", usage.cache_read_tokens),
+            ];
+            for (kind, count) in kinds {
+                metrics.sample("tokens", &[("model", model), ("kind", kind)], count as f64);
+            }
+        }
+        metrics.family("requests", "API requests per model");
+        for (model, stats) in &models {
+            metrics.sample("requests", &[("model", model)], stats.requests as f64);
+        }
+        metrics.family("cost_usd", "Estimated cost per model in US dollars");
+        for (model, stats) in &models {
+            metrics.sample("cost_usd", &[("model", model)], stats.estimated_cost);
+        }
+    }
+
+    if let Some(last) = analysis.sessions.iter().map(|session| session.end_time).max() {
+        metrics.family("last_session_timestamp_seconds", "End of the latest session as a Unix time");
+        metrics.sample("last_session_timestamp_seconds", &[], last.timestamp() as f64);
+    }
+    metrics.family("refresh_timestamp_seconds", "When these metrics were computed as a Unix time");
+    metrics.sample("refresh_timestamp_seconds", &[], refreshed_at.timestamp() as f64);
+    metrics.family("refresh_duration_seconds", "How long computing these metrics took");
+    metrics.sample("refresh_duration_seconds", &[], refresh_duration.as_secs_f64());
+
+    metrics.text
+}
+
+#[derive(Default)]
+struct Metrics {
+    text: String,
+}
+
+impl Metrics {
+    fn family(&mut self, name: &str, help: &str) {
+        let _ = writeln!(self.text, "# HELP {}_{} {}", PREFIX, name, help);
+        let _ = writeln!(self.text, "# TYPE {}_{} gauge", PREFIX, name);
+    }
+
+    fn sample(&mut self, name: &str, labels: &[(&str, &str)], value: f64) {
+        let _ = write!(self.text, "{}_{}", PREFIX, name);
+        if !labels.is_empty() {
+            let labels: Vec<String> =
+                labels.iter().map(|(label, value)| format!("{}=\"{}\"", label, escape_label(value))).collect();
+            let _ = write!(self.text, "{{{}}}", labels.join(","));
+        }
+        let _ = writeln!(self.text, " {}", value);
+    }
+}
+
+/// Label values escape backslashes, double quotes and line feeds
+fn escape_label(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+/// Rounded to two decimals
+fn hours(minutes: i64) -> f64 {
+    (minutes as f64 / 0.6).round() / 100.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyzer::WorkAnalyzer;
+    use crate::pipeline::AnalysisPipeline;
+    use crate::sample::write_sample;
+    use tempfile::TempDir;
+
+    #[tokio::test]
+    async fn test_render() {
+        let temp_dir = TempDir::new().unwrap();
+        write_sample(temp_dir.path()).unwrap();
+        let analysis = AnalysisPipeline::builder()
+            .data_dir(temp_dir.path())
+            .analyzer(WorkAnalyzer::new().with_min_messages(1))
+            .run()
+            .await
+            .unwrap()
+            .analysis;
+
+        let text = render(&analysis, DateTime::from_timestamp(1_750_000_000, 0).unwrap(), Duration::from_millis(250));
+        assert!(text.contains("# TYPE claude_work_sessions gauge\nclaude_work_sessions 3\n"));
+        assert!(text.contains("claude_work_messages 18\n"));
+        assert!(text.contains("claude_work_project_sessions{project=\"api\"} 2\n"));
+        assert!(text.contains(",kind=\"output\"} "));
+        assert!(text.contains("claude_work_refresh_timestamp_seconds 1750000000\n"));
+        assert!(text.contains("claude_work_refresh_duration_seconds 0.25\n"));
+        // Every sample belongs to a declared family
+        for line in text.lines().filter(|line| !line.starts_with('#')) {
+            let name = line.split(['{', ' ']).next().unwrap();
+            assert!(text.contains(&format!("# TYPE {} gauge", name)), "{}", line);
+        }
+    }
+
+    #[test]
+    fn test_escape_label() {
+        assert_eq!(escape_label("a\"b\\c\nd"), "a\\\"b\\\\c\\nd");
+    }
+}
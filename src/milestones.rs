@@ -0,0 +1,272 @@
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use std::collections::HashSet;
+use uuid::Uuid;
+
+use crate::models::WorkAnalysis;
+use crate::timezone::TimezoneSchedule;
+
+/// Thresholds that trigger a milestone notification; `None` disables the event
+#[derive(Debug, Clone, Default)]
+pub struct MilestoneConfig {
+    pub daily_goal: Option<Duration>,
+    pub long_session: Option<Duration>,
+    /// Monthly budget in USD whose projected overrun is announced, see [`crate::budget::BudgetStatus`]
+    pub monthly_budget: Option<f64>,
+}
+
+/// An event worth nudging the user about
+#[derive(Debug, Clone, PartialEq)]
+pub enum Milestone {
+    DailyGoalReached {
+        day: NaiveDate,
+        work_time: Duration,
+    },
+    LongSession {
+        project: String,
+        started: DateTime<Utc>,
+        duration: Duration,
+    },
+    BudgetExceeded {
+        month_start: NaiveDate,
+        projected: f64,
+        budget: f64,
+    },
+}
+
+impl Milestone {
+    pub fn title(&self) -> &'static str {
+        match self {
+            Milestone::DailyGoalReached { .. } => "Daily goal reached",
+            Milestone::LongSession { .. } => "Long session",
+            Milestone::BudgetExceeded { .. } => "Budget exceeded",
+        }
+    }
+
+    pub fn body(&self) -> String {
+        match self {
+            Milestone::DailyGoalReached { work_time, .. } => {
+                format!("{} of work today. Nice job!", format_hours(*work_time))
+            }
+            Milestone::LongSession { project, duration, .. } => {
+                format!(
+                    "{} without a break on {}. Time to stretch?",
+                    format_hours(*duration),
+                    project
+                )
+            }
+            Milestone::BudgetExceeded { projected, budget, .. } => {
+                format!("${:.2} projected this month, over the ${:.2} budget.", projected, budget)
+            }
+        }
+    }
+}
+
+fn format_hours(duration: Duration) -> String {
+    format!("{:.1}h", duration.num_minutes() as f64 / 60.0)
+}
+
+/// Detects milestones in successive analyses, reporting each one only once
+pub struct MilestoneTracker {
+    config: MilestoneConfig,
+    timezone: TimezoneSchedule,
+    /// Days whose goal has already been announced
+    goals_reached: HashSet<NaiveDate>,
+    /// Sessions already announced as long, keyed by session id and start time
+    long_sessions: HashSet<(Uuid, DateTime<Utc>)>,
+    /// Months whose projected overrun has already been announced, by first day
+    budgets_exceeded: HashSet<NaiveDate>,
+}
+
+impl MilestoneTracker {
+    pub fn new(config: MilestoneConfig, timezone: TimezoneSchedule) -> Self {
+        Self {
+            config,
+            timezone,
+            goals_reached: HashSet::new(),
+            long_sessions: HashSet::new(),
+            budgets_exceeded: HashSet::new(),
+        }
+    }
+
+    /// Milestones reached in `analysis` that have not been reported yet
+    ///
+    /// The budget is read from [`WorkAnalysis::budget`], which the caller fills
+    /// in for [`MilestoneConfig::monthly_budget`].
+    pub fn check(&mut self, analysis: &WorkAnalysis, now: DateTime<Utc>) -> Vec<Milestone> {
+        let mut milestones = Vec::new();
+        let today = self.timezone.to_local(now).date_naive();
+
+        if let Some(goal) = self.config.daily_goal {
+            let work_time = analysis
+                .sessions
+                .iter()
+                .filter(|s| self.timezone.to_local(s.start_time).date_naive() == today)
+                .fold(Duration::zero(), |total, s| total + (s.end_time - s.start_time));
+
+            if work_time >= goal && self.goals_reached.insert(today) {
+                milestones.push(Milestone::DailyGoalReached { day: today, work_time });
+            }
+        }
+
+        if let Some(limit) = self.config.long_session {
+            for session in &analysis.sessions {
+                let duration = session.end_time - session.start_time;
+                if duration >= limit
+                    && self.long_sessions.insert((session.session_id, session.start_time))
+                {
+                    milestones.push(Milestone::LongSession {
+                        project: session.project_path.clone(),
+                        started: session.start_time,
+                        duration,
+                    });
+                }
+            }
+        }
+
+        if self.config.monthly_budget.is_some() {
+            if let Some(budget) = analysis.budget.as_ref().filter(|budget| budget.exceeded()) {
+                if self.budgets_exceeded.insert(budget.month_start()) {
+                    milestones.push(Milestone::BudgetExceeded {
+                        month_start: budget.month_start(),
+                        projected: budget.projected(),
+                        budget: budget.monthly_budget,
+                    });
+                }
+            }
+        }
+
+        milestones
+    }
+}
+
+/// Show a milestone as a desktop notification
+pub fn notify_desktop(milestone: &Milestone) -> anyhow::Result<()> {
+    notify_rust::Notification::new()
+        .appname("claude-work-analysis")
+        .summary(milestone.title())
+        .body(&milestone.body())
+        .show()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::budget::BudgetStatus;
+    use crate::models::WorkSession;
+    use crate::timezone::jst;
+    use chrono::TimeZone;
+    use std::collections::HashMap;
+
+    fn create_test_session(start: DateTime<Utc>, minutes: i64) -> WorkSession {
+        WorkSession {
+            session_id: Uuid::new_v4(),
+            project_path: "work/api".to_string(),
+            start_time: start,
+            end_time: start + Duration::minutes(minutes),
+            entries: Vec::new(),
+            total_messages: 10,
+            user_messages: 5,
+            assistant_messages: 5,
+            summary: None,
+            merged_session_ids: Vec::new(),
+        }
+    }
+
+    fn create_test_analysis(sessions: Vec<WorkSession>) -> WorkAnalysis {
+        WorkAnalysis {
+            total_sessions: sessions.len(),
+            total_messages: 0,
+            total_work_time: Duration::zero(),
+            time_range: (Utc::now(), Utc::now()),
+            project_stats: HashMap::new(),
+            conversation_summary: None,
+            token_analysis: None,
+            tool_usage: None,
+            code_blocks: None,
+            time_buckets: None,
+            initiatives: None,
+            commits: None,
+            log_gaps: None,
+            heatmap: None,
+            machines: None,
+            assistants: None,
+            sidechains: None,
+            budget: None,
+            duplicate_entries: 0,
+            project_names: Default::default(),
+            sessions,
+        }
+    }
+
+    #[test]
+    fn test_daily_goal_reported_once() {
+        let config = MilestoneConfig {
+            daily_goal: Some(Duration::hours(2)),
+            long_session: None,
+            monthly_budget: None,
+        };
+        let mut tracker = MilestoneTracker::new(config, TimezoneSchedule::fixed(jst()));
+        let now = Utc.with_ymd_and_hms(2025, 7, 1, 9, 0, 0).unwrap();
+
+        let analysis = create_test_analysis(vec![create_test_session(now - Duration::hours(5), 90)]);
+        assert!(tracker.check(&analysis, now).is_empty());
+
+        let analysis = create_test_analysis(vec![
+            create_test_session(now - Duration::hours(5), 90),
+            create_test_session(now - Duration::hours(2), 45),
+        ]);
+        let milestones = tracker.check(&analysis, now);
+        assert_eq!(milestones.len(), 1);
+        assert_eq!(milestones[0].title(), "Daily goal reached");
+        assert!(tracker.check(&analysis, now).is_empty());
+    }
+
+    #[test]
+    fn test_long_session_reported_once() {
+        let config = MilestoneConfig {
+            daily_goal: None,
+            long_session: Some(Duration::hours(2)),
+            monthly_budget: None,
+        };
+        let mut tracker = MilestoneTracker::new(config, TimezoneSchedule::fixed(jst()));
+        let now = Utc.with_ymd_and_hms(2025, 7, 1, 9, 0, 0).unwrap();
+        let analysis = create_test_analysis(vec![
+            create_test_session(now - Duration::hours(4), 150),
+            create_test_session(now - Duration::hours(1), 30),
+        ]);
+
+        let milestones = tracker.check(&analysis, now);
+        assert_eq!(milestones.len(), 1);
+        assert_eq!(milestones[0].body(), "2.5h without a break on work/api. Time to stretch?");
+        assert!(tracker.check(&analysis, now).is_empty());
+    }
+
+    #[test]
+    fn test_budget_exceeded_reported_once_a_month() {
+        let config = MilestoneConfig {
+            daily_goal: None,
+            long_session: None,
+            monthly_budget: Some(100.0),
+        };
+        let mut tracker = MilestoneTracker::new(config, TimezoneSchedule::fixed(jst()));
+        let now = Utc.with_ymd_and_hms(2025, 6, 10, 9, 0, 0).unwrap();
+        let today = NaiveDate::from_ymd_opt(2025, 6, 10).unwrap();
+        let mut analysis = create_test_analysis(Vec::new());
+
+        analysis.budget = Some(BudgetStatus::new(100.0, [(today, 30.0)], today));
+        assert!(tracker.check(&analysis, now).is_empty());
+
+        analysis.budget = Some(BudgetStatus::new(100.0, [(today, 35.0)], today));
+        let milestones = tracker.check(&analysis, now);
+        assert_eq!(milestones.len(), 1);
+        assert_eq!(milestones[0].title(), "Budget exceeded");
+        assert_eq!(milestones[0].body(), "$105.00 projected this month, over the $100.00 budget.");
+        assert!(tracker.check(&analysis, now).is_empty());
+
+        // A new month is announced again
+        let next_month = NaiveDate::from_ymd_opt(2025, 7, 1).unwrap();
+        analysis.budget = Some(BudgetStatus::new(100.0, [(next_month, 5.0)], next_month));
+        assert_eq!(tracker.check(&analysis, now + Duration::days(21)).len(), 1);
+    }
+}
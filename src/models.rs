@@ -1,8 +1,14 @@
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Datelike, Duration, NaiveDate, TimeZone, Timelike, Utc, Weekday};
+use std::str::FromStr;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use serde_json::value::RawValue;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use uuid::Uuid;
 
+use crate::budget::BudgetStatus;
+use crate::health::LogGaps;
+use crate::scanner::ProjectNames;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ClaudeLogEntry {
     #[serde(rename = "parentUuid")]
@@ -22,8 +28,12 @@ pub struct ClaudeLogEntry {
     pub timestamp: DateTime<Utc>,
     #[serde(rename = "requestId")]
     pub request_id: Option<String>,
+    /// Kept as the raw JSON of the line; only error strings of older logs are read
     #[serde(rename = "toolUseResult")]
-    pub tool_use_result: Option<serde_json::Value>,
+    pub tool_use_result: Option<Box<RawValue>>,
+    /// Assistant the entry was imported from (see [`crate::importers`]); None in Claude Code's own logs
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
 }
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize)]
@@ -48,13 +58,47 @@ pub struct MessageContent {
     pub usage: Option<UsageInfo>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize)]
 #[serde(untagged)]
 pub enum MessageContentVariant {
     String(String),
     Array(Vec<ContentBlock>),
 }
 
+// Deserialized by hand rather than as `untagged`, which buffers the whole
+// content before trying each variant and cannot keep raw values
+impl<'de> Deserialize<'de> for MessageContentVariant {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct ContentVisitor;
+
+        impl<'de> serde::de::Visitor<'de> for ContentVisitor {
+            type Value = MessageContentVariant;
+
+            fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+                formatter.write_str("a string or an array of content blocks")
+            }
+
+            fn visit_str<E: serde::de::Error>(self, value: &str) -> Result<Self::Value, E> {
+                Ok(MessageContentVariant::String(value.to_string()))
+            }
+
+            fn visit_string<E: serde::de::Error>(self, value: String) -> Result<Self::Value, E> {
+                Ok(MessageContentVariant::String(value))
+            }
+
+            fn visit_seq<A: serde::de::SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+                let mut blocks = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+                while let Some(block) = seq.next_element()? {
+                    blocks.push(block);
+                }
+                Ok(MessageContentVariant::Array(blocks))
+            }
+        }
+
+        deserializer.deserialize_any(ContentVisitor)
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ContentBlock {
     #[serde(rename = "type")]
@@ -64,9 +108,12 @@ pub struct ContentBlock {
     pub signature: Option<String>,
     pub id: Option<String>,
     pub name: Option<String>,
-    pub input: Option<serde_json::Value>,
+    /// Kept as the raw JSON of the line, tool inputs are not analyzed
+    pub input: Option<Box<RawValue>>,
     #[serde(rename = "tool_use_id")]
     pub tool_use_id: Option<String>,
+    #[serde(default)]
+    pub is_error: Option<bool>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -78,7 +125,22 @@ pub struct UsageInfo {
     pub service_tier: Option<String>,
 }
 
-#[derive(Debug, Clone)]
+/// (De)serializes a `chrono::Duration` as seconds, with millisecond precision
+mod duration_seconds {
+    use chrono::Duration;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_f64(duration.num_milliseconds() as f64 / 1000.0)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Duration, D::Error> {
+        let seconds = f64::deserialize(deserializer)?;
+        Ok(Duration::milliseconds((seconds * 1000.0).round() as i64))
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct WorkSession {
     pub session_id: Uuid,
     pub project_path: String,
@@ -89,31 +151,108 @@ pub struct WorkSession {
     pub user_messages: usize,
     pub assistant_messages: usize,
     pub summary: Option<SessionSummary>,
+    /// Log sessions folded into this one because they resumed shortly after a restart
+    pub merged_session_ids: Vec<Uuid>,
+}
+
+impl WorkSession {
+    /// Extended thinking in the session's assistant messages
+    pub fn reasoning_volume(&self) -> ReasoningVolume {
+        let mut volume = ReasoningVolume::default();
+        for entry in &self.entries {
+            if let MessageContentVariant::Array(blocks) = &entry.message.content {
+                for block in blocks.iter().filter(|block| block.content_type.ends_with("thinking")) {
+                    volume.thinking_blocks += 1;
+                    volume.thinking_chars += block.thinking.as_deref().map_or(0, |text| text.chars().count());
+                }
+            }
+        }
+        volume
+    }
+}
+
+/// Extended thinking written by the assistant
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ReasoningVolume {
+    /// `thinking` and `redacted_thinking` blocks
+    pub thinking_blocks: usize,
+    /// Characters of thinking text; redacted blocks count as blocks only
+    pub thinking_chars: usize,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct WorkAnalysis {
     pub sessions: Vec<WorkSession>,
     pub project_stats: HashMap<String, ProjectStats>,
     pub time_range: (DateTime<Utc>, DateTime<Utc>),
     pub total_sessions: usize,
     pub total_messages: usize,
+    #[serde(with = "duration_seconds")]
     pub total_work_time: chrono::Duration,
     pub conversation_summary: Option<ConversationSummary>,
+    pub token_analysis: Option<TokenAnalysis>,
+    pub tool_usage: Option<ToolUsageSummary>,
+    /// Fenced code blocks in the assistant's responses
+    pub code_blocks: Option<CodeBlockSummary>,
+    /// Activity per day, week or month when grouping was requested
+    pub time_buckets: Option<TimeBuckets>,
+    /// Work per configured initiative, when there are any
+    pub initiatives: Option<Vec<InitiativeRollup>>,
+    /// Commits per session, when correlating with git was requested
+    pub commits: Option<Vec<SessionCommits>>,
+    /// Days of the period without logs, when the data coverage was checked
+    pub log_gaps: Option<LogGaps>,
+    /// Messages per local weekday and hour
+    pub heatmap: Option<ActivityHeatmap>,
+    /// Work per machine, when the projects directories are labeled with machines
+    pub machines: Option<MachineBreakdown>,
+    /// Work delegated to subagents
+    pub sidechains: Option<SidechainSummary>,
+    /// Work per assistant, when the logs include history imported from other assistants
+    pub assistants: Option<Vec<AssistantStats>>,
+    /// Month-to-date spend against the monthly budget, when one was given
+    pub budget: Option<BudgetStatus>,
+    /// Entries dropped because a newer log file held the same entry, e.g. a backup copy
+    #[serde(default)]
+    pub duplicate_entries: usize,
+    /// Display name of each project path the sessions are counted under
+    #[serde(default)]
+    pub project_names: ProjectNames,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ProjectStats {
     pub project_name: String,
     pub total_sessions: usize,
     pub total_messages: usize,
+    #[serde(with = "duration_seconds")]
     pub work_time: chrono::Duration,
     pub activity_types: HashMap<String, usize>,
+    /// Mean confidence of the classification of the messages counted for each activity type
+    #[serde(default)]
+    pub activity_confidence: HashMap<String, f64>,
     pub most_active_day: Option<DateTime<Utc>>,
     pub topic_analysis: Option<TopicAnalysis>,
 }
 
-#[derive(Debug, Clone)]
+/// Confidence in a finding backed by `evidence`, each unit of which halves the doubt:
+/// 1 gives 0.5 and 2 gives 0.75
+pub fn confidence(evidence: f64) -> f64 {
+    1.0 - 0.5f64.powf(evidence.max(0.0))
+}
+
+/// Advice drawn from the work patterns of an analysis
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Recommendation {
+    pub icon: String,
+    pub title: String,
+    pub advice: String,
+    /// How well the analysis supports the advice, between 0 and 1
+    pub confidence: f64,
+}
+
+/// What a prompt asks for; on a tie between scores the earlier variant wins
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 pub enum ActivityType {
     Coding,
     Debugging,
@@ -125,32 +264,21 @@ pub enum ActivityType {
 }
 
 impl ActivityType {
+    pub const ALL: [ActivityType; 7] = [
+        ActivityType::Coding,
+        ActivityType::Debugging,
+        ActivityType::Planning,
+        ActivityType::Research,
+        ActivityType::Documentation,
+        ActivityType::Learning,
+        ActivityType::Other,
+    ];
+
+    /// The activity with the highest score in the built-in [`ActivityScoring`](crate::activity::ActivityScoring)
     pub fn from_message_content(content: &str) -> Self {
-        let content_lower = content.to_lowercase();
-        
-        if content_lower.contains("implement") || content_lower.contains("write") 
-            || content_lower.contains("create") || content_lower.contains("add") {
-            ActivityType::Coding
-        } else if content_lower.contains("debug") || content_lower.contains("fix") 
-            || content_lower.contains("error") || content_lower.contains("bug") {
-            ActivityType::Debugging
-        } else if content_lower.contains("plan") || content_lower.contains("design") 
-            || content_lower.contains("architect") {
-            ActivityType::Planning
-        } else if content_lower.contains("research") || content_lower.contains("investigate") 
-            || content_lower.contains("analyze") {
-            ActivityType::Research
-        } else if content_lower.contains("document") || content_lower.contains("readme") 
-            || content_lower.contains("comment") {
-            ActivityType::Documentation
-        } else if content_lower.contains("learn") || content_lower.contains("understand") 
-            || content_lower.contains("explain") {
-            ActivityType::Learning
-        } else {
-            ActivityType::Other
-        }
-    }
-    
+        crate::activity::ActivityScoring::builtin().primary(content)
+    }
+
     pub fn as_str(&self) -> &'static str {
         match self {
             ActivityType::Coding => "Coding",
@@ -164,29 +292,186 @@ impl ActivityType {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SessionSummary {
     pub main_topics: Vec<String>,
+    /// Prompts mentioning each of the main topics
+    #[serde(default)]
+    pub topic_mentions: HashMap<String, usize>,
     pub key_discussions: Vec<String>,
     pub technologies_mentioned: Vec<String>,
     pub problems_addressed: Vec<String>,
+    /// Confidence that each of the problems addressed is one, between 0 and 1
+    #[serde(default)]
+    pub problem_confidence: HashMap<String, f64>,
     pub solutions_proposed: Vec<String>,
     pub learning_moments: Vec<String>,
     pub overall_summary: String,
 }
 
-#[derive(Debug, Clone)]
+impl SessionSummary {
+    /// Prompts mentioning each main topic, one each in summaries recorded without the counts
+    pub fn mentions(&self) -> Vec<(&String, usize)> {
+        self.main_topics
+            .iter()
+            .map(|topic| (topic, self.topic_mentions.get(topic).copied().unwrap_or(1)))
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ConversationSummary {
     pub total_topics: usize,
     pub most_discussed_topics: Vec<(String, usize)>,
     pub technology_usage: HashMap<String, usize>,
     pub common_problems: Vec<String>,
+    /// Confidence that each of the common problems is one, between 0 and 1
+    #[serde(default)]
+    pub problem_confidence: HashMap<String, f64>,
     pub learning_progression: Vec<String>,
     pub productivity_insights: Vec<String>,
     pub overall_themes: Vec<String>,
+    /// Sessions clustered by topic, largest theme first
+    pub topic_themes: Vec<TopicTheme>,
+    /// Discussions of each technology and the prompts that followed up on them
+    #[serde(default)]
+    pub follow_ups_by_technology: HashMap<String, FollowUpStats>,
+}
+
+/// Discussions opened about a technology and the further prompts they took
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FollowUpStats {
+    pub discussions: usize,
+    pub follow_ups: usize,
+}
+
+impl FollowUpStats {
+    pub fn merge(&mut self, other: &FollowUpStats) {
+        self.discussions += other.discussions;
+        self.follow_ups += other.follow_ups;
+    }
+
+    /// Follow-up prompts per discussion
+    pub fn rate(&self) -> f64 {
+        if self.discussions == 0 {
+            return 0.0;
+        }
+        self.follow_ups as f64 / self.discussions as f64
+    }
+}
+
+/// Sessions about related topics, named after the terms that set them apart
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TopicTheme {
+    pub name: String,
+    /// Topics and technologies of the theme with the number of its sessions mentioning them
+    pub topics: Vec<(String, usize)>,
+    /// Sessions of the theme, oldest first
+    pub session_ids: Vec<Uuid>,
+    /// Work time of the theme's sessions
+    #[serde(with = "duration_seconds")]
+    pub work_time: chrono::Duration,
+    /// Tokens and estimated cost of the theme's sessions
+    pub tokens: TokenStats,
+}
+
+/// Work done for one initiative, possibly across several projects
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InitiativeRollup {
+    pub name: String,
+    pub sessions: usize,
+    pub messages: usize,
+    #[serde(with = "duration_seconds")]
+    pub work_time: chrono::Duration,
+    /// Projects the initiative's sessions were in, sorted
+    pub projects: Vec<String>,
+    pub tokens: TokenStats,
+    /// Solutions of its latest sessions, newest first
+    pub outcomes: Vec<String>,
+}
+
+/// Messages and token usage of subagent (sidechain) conversations
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SidechainStats {
+    pub messages: usize,
+    pub tokens: TokenStats,
 }
 
-#[derive(Debug, Clone)]
+impl SidechainStats {
+    pub fn merge(&mut self, other: &SidechainStats) {
+        self.messages += other.messages;
+        self.tokens.merge(&other.tokens);
+    }
+}
+
+/// Subagent work of one session
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionSidechains {
+    pub session_id: Uuid,
+    pub start_time: DateTime<Utc>,
+    pub project: String,
+    pub stats: SidechainStats,
+}
+
+/// Work the sessions delegated to subagents, counted within their totals
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SidechainSummary {
+    pub total: SidechainStats,
+    pub by_project: HashMap<String, SidechainStats>,
+    /// Sessions with subagent messages, most tokens first
+    pub by_session: Vec<SessionSidechains>,
+}
+
+/// Work logged on one machine, told apart by the projects directory its logs were read from
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MachineStats {
+    pub name: String,
+    pub sessions: usize,
+    pub messages: usize,
+    #[serde(with = "duration_seconds")]
+    pub work_time: chrono::Duration,
+    pub tokens: TokenStats,
+}
+
+/// Work done with one assistant: Claude Code, or a tool whose history was imported
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AssistantStats {
+    pub name: String,
+    pub sessions: usize,
+    pub messages: usize,
+    #[serde(with = "duration_seconds")]
+    pub work_time: chrono::Duration,
+    pub tokens: TokenStats,
+}
+
+/// Work per machine of the logs merged from several machines
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MachineBreakdown {
+    /// Most worked-on machine first
+    pub machines: Vec<MachineStats>,
+    /// Entries read from more than one file, e.g. synced to several machines, and counted once
+    pub duplicate_entries: usize,
+}
+
+/// A git commit made during a session or shortly after it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Commit {
+    pub hash: String,
+    /// Author date
+    pub time: DateTime<Utc>,
+    /// First line of the commit message
+    pub subject: String,
+}
+
+/// Commits attributed to one session, oldest first
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SessionCommits {
+    pub session_id: Uuid,
+    pub start_time: DateTime<Utc>,
+    pub commits: Vec<Commit>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct TopicAnalysis {
     pub primary_topics: Vec<String>,
     pub secondary_topics: Vec<String>,
@@ -196,8 +481,474 @@ pub struct TopicAnalysis {
     pub complexity_indicators: Vec<String>,
 }
 
+impl MessageContentVariant {
+    /// The text blocks joined by spaces; empty for tool results
+    pub fn text(&self) -> String {
+        match self {
+            MessageContentVariant::String(s) => s.clone(),
+            MessageContentVariant::Array(blocks) => {
+                blocks.iter().filter_map(|block| block.text.as_ref()).cloned().collect::<Vec<String>>().join(" ")
+            }
+        }
+    }
+}
+
 impl Default for MessageContentVariant {
     fn default() -> Self {
         MessageContentVariant::String(String::new())
     }
-}
\ No newline at end of file
+}
+
+/// Invocation outcomes of one tool
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ToolStats {
+    pub invocations: usize,
+    pub successes: usize,
+    pub failures: usize,
+}
+
+impl ToolStats {
+    pub fn merge(&mut self, other: &ToolStats) {
+        self.invocations += other.invocations;
+        self.successes += other.successes;
+        self.failures += other.failures;
+    }
+
+    /// Share of finished invocations that succeeded
+    pub fn success_rate(&self) -> Option<f64> {
+        let finished = self.successes + self.failures;
+        (finished > 0).then(|| self.successes as f64 / finished as f64)
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ToolUsageSummary {
+    pub total_invocations: usize,
+    pub by_tool: HashMap<String, ToolStats>,
+    /// Tool statistics per project name
+    pub by_project: HashMap<String, HashMap<String, ToolStats>>,
+}
+
+impl ToolUsageSummary {
+    /// Add the invocations of other sessions
+    pub fn merge(&mut self, other: &ToolUsageSummary) {
+        self.total_invocations += other.total_invocations;
+        for (tool, stats) in &other.by_tool {
+            self.by_tool.entry(tool.clone()).or_default().merge(stats);
+        }
+        for (project, tools) in &other.by_project {
+            let project_tools = self.by_project.entry(project.clone()).or_default();
+            for (tool, stats) in tools {
+                project_tools.entry(tool.clone()).or_default().merge(stats);
+            }
+        }
+    }
+}
+
+/// Fenced code blocks of one language, or of all of them
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CodeBlockStats {
+    pub blocks: usize,
+    /// Lines between the fences
+    pub lines: usize,
+}
+
+impl CodeBlockStats {
+    pub fn merge(&mut self, other: &CodeBlockStats) {
+        self.blocks += other.blocks;
+        self.lines += other.lines;
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CodeBlockSummary {
+    pub total: CodeBlockStats,
+    /// By the language of the fence, `unspecified` when it has none
+    pub by_language: HashMap<String, CodeBlockStats>,
+    /// Language statistics per project name
+    pub by_project: HashMap<String, HashMap<String, CodeBlockStats>>,
+    /// Language statistics per local day of the response
+    #[serde(default)]
+    pub by_day: BTreeMap<NaiveDate, HashMap<String, CodeBlockStats>>,
+}
+
+impl CodeBlockSummary {
+    /// Add the code blocks of other sessions
+    pub fn merge(&mut self, other: &CodeBlockSummary) {
+        self.total.merge(&other.total);
+        for (language, stats) in &other.by_language {
+            self.by_language.entry(language.clone()).or_default().merge(stats);
+        }
+        for (project, languages) in &other.by_project {
+            let project_languages = self.by_project.entry(project.clone()).or_default();
+            for (language, stats) in languages {
+                project_languages.entry(language.clone()).or_default().merge(stats);
+            }
+        }
+        for (day, languages) in &other.by_day {
+            let day_languages = self.by_day.entry(*day).or_default();
+            for (language, stats) in languages {
+                day_languages.entry(language.clone()).or_default().merge(stats);
+            }
+        }
+    }
+}
+
+/// Token counts reported by the API for one or more requests
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TokenUsage {
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub cache_creation_tokens: u64,
+    pub cache_read_tokens: u64,
+}
+
+impl TokenUsage {
+    pub fn from_usage_info(usage: &UsageInfo) -> Self {
+        Self {
+            input_tokens: usage.input_tokens.unwrap_or(0) as u64,
+            output_tokens: usage.output_tokens.unwrap_or(0) as u64,
+            cache_creation_tokens: usage.cache_creation_input_tokens.unwrap_or(0) as u64,
+            cache_read_tokens: usage.cache_read_input_tokens.unwrap_or(0) as u64,
+        }
+    }
+
+    pub fn total(&self) -> u64 {
+        self.input_tokens + self.output_tokens + self.cache_creation_tokens + self.cache_read_tokens
+    }
+
+    pub fn add(&mut self, other: &TokenUsage) {
+        self.input_tokens += other.input_tokens;
+        self.output_tokens += other.output_tokens;
+        self.cache_creation_tokens += other.cache_creation_tokens;
+        self.cache_read_tokens += other.cache_read_tokens;
+    }
+}
+
+/// Token usage together with its estimated cost in USD
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TokenStats {
+    pub usage: TokenUsage,
+    pub estimated_cost: f64,
+    pub requests: usize,
+}
+
+impl TokenStats {
+    pub fn add(&mut self, usage: &TokenUsage, cost: f64) {
+        self.usage.add(usage);
+        self.estimated_cost += cost;
+        self.requests += 1;
+    }
+
+    /// Add the usage, cost and requests of other stats
+    pub fn merge(&mut self, other: &TokenStats) {
+        self.usage.add(&other.usage);
+        self.estimated_cost += other.estimated_cost;
+        self.requests += other.requests;
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionTokens {
+    pub session_id: Uuid,
+    pub project_path: String,
+    pub start_time: DateTime<Utc>,
+    pub stats: TokenStats,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TokenAnalysis {
+    pub total: TokenStats,
+    pub by_session: Vec<SessionTokens>,
+    pub by_project: HashMap<String, TokenStats>,
+    pub by_day: BTreeMap<NaiveDate, TokenStats>,
+    pub by_model: HashMap<String, TokenStats>,
+    /// Usage by project, then by the activity type of the prompt each response answered
+    #[serde(default)]
+    pub by_project_activity: HashMap<String, HashMap<String, TokenStats>>,
+    /// Models without a price table entry, counted at zero cost
+    pub unpriced_models: Vec<String>,
+    /// Usage of the responses in subagent (sidechain) conversations, included in the totals above
+    #[serde(default)]
+    pub sidechain: TokenStats,
+}
+
+impl TokenAnalysis {
+    /// Add the usage of other sessions, e.g. analyzed one at a time
+    pub fn merge(&mut self, other: TokenAnalysis) {
+        self.total.merge(&other.total);
+        self.by_session.extend(other.by_session);
+        for (project, stats) in other.by_project {
+            self.by_project.entry(project).or_default().merge(&stats);
+        }
+        for (day, stats) in other.by_day {
+            self.by_day.entry(day).or_default().merge(&stats);
+        }
+        for (model, stats) in other.by_model {
+            self.by_model.entry(model).or_default().merge(&stats);
+        }
+        for (project, activities) in other.by_project_activity {
+            let project_activities = self.by_project_activity.entry(project).or_default();
+            for (activity, stats) in activities {
+                project_activities.entry(activity).or_default().merge(&stats);
+            }
+        }
+        for model in other.unpriced_models {
+            if let Err(position) = self.unpriced_models.binary_search(&model) {
+                self.unpriced_models.insert(position, model);
+            }
+        }
+        self.sidechain.merge(&other.sidechain);
+    }
+
+    /// Key the projects by their display names instead of their paths
+    pub fn name_projects(&mut self, names: &ProjectNames) {
+        self.by_project = names.rename(std::mem::take(&mut self.by_project));
+        self.by_project_activity = names.rename(std::mem::take(&mut self.by_project_activity));
+    }
+
+    /// Token stats keyed by session id and start, which together identify a work session
+    pub fn stats_by_session(&self) -> HashMap<(Uuid, DateTime<Utc>), &TokenStats> {
+        self.by_session
+            .iter()
+            .map(|session| ((session.session_id, session.start_time), &session.stats))
+            .collect()
+    }
+}
+
+/// Calendar unit used to aggregate activity over time
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TimeGrouping {
+    Day,
+    Week,
+    Month,
+}
+
+impl TimeGrouping {
+    /// First day of the bucket containing `date`; weeks start on Monday
+    pub fn bucket_start(&self, date: NaiveDate) -> NaiveDate {
+        match self {
+            TimeGrouping::Day => date,
+            TimeGrouping::Week => date - Duration::days(date.weekday().num_days_from_monday() as i64),
+            TimeGrouping::Month => date.with_day(1).unwrap_or(date),
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TimeGrouping::Day => "day",
+            TimeGrouping::Week => "week",
+            TimeGrouping::Month => "month",
+        }
+    }
+}
+
+impl FromStr for ActivityType {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        let name = s.to_lowercase();
+        ActivityType::ALL
+            .into_iter()
+            .find(|activity| activity.as_str().to_lowercase() == name)
+            .ok_or_else(|| anyhow::anyhow!(
+                "Invalid activity type '{}'. Expected coding, debugging, planning, research, documentation, learning or other",
+                s
+            ))
+    }
+}
+
+impl FromStr for TimeGrouping {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s.to_lowercase().as_str() {
+            "day" => Ok(TimeGrouping::Day),
+            "week" => Ok(TimeGrouping::Week),
+            "month" => Ok(TimeGrouping::Month),
+            _ => Err(anyhow::anyhow!(
+                "Invalid grouping '{}'. Expected day, week or month",
+                s
+            )),
+        }
+    }
+}
+
+/// Order of session listings
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SessionOrder {
+    /// Chronological
+    Start,
+    /// Longest first
+    Duration,
+    /// Most messages first
+    Messages,
+    /// Most tokens first
+    Tokens,
+}
+
+impl SessionOrder {
+    pub const ALL: [SessionOrder; 4] = [SessionOrder::Start, SessionOrder::Duration, SessionOrder::Messages, SessionOrder::Tokens];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SessionOrder::Start => "start",
+            SessionOrder::Duration => "duration",
+            SessionOrder::Messages => "messages",
+            SessionOrder::Tokens => "tokens",
+        }
+    }
+}
+
+impl FromStr for SessionOrder {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        let name = s.to_lowercase();
+        SessionOrder::ALL
+            .into_iter()
+            .find(|order| order.as_str() == name)
+            .ok_or_else(|| anyhow::anyhow!("Invalid session order '{}'. Expected start, duration, messages or tokens", s))
+    }
+}
+
+impl WorkAnalysis {
+    /// Name of the project of `session`, the key of its [`Self::project_stats`]
+    pub fn project_name(&self, session: &WorkSession) -> String {
+        self.project_names.name(&session.project_path)
+    }
+
+    /// Log sessions (session IDs) the work sessions were made of, stitched ones included
+    pub fn log_sessions(&self) -> usize {
+        let ids: HashSet<Uuid> = self
+            .sessions
+            .iter()
+            .flat_map(|session| std::iter::once(session.session_id).chain(session.merged_session_ids.iter().copied()))
+            .collect();
+        ids.len()
+    }
+
+    /// Log sessions folded into the work session they continue
+    pub fn stitched_sessions(&self) -> usize {
+        self.sessions.iter().map(|session| session.merged_session_ids.len()).sum()
+    }
+
+    /// Sessions in `order`, earlier sessions first among equals
+    pub fn sessions_by(&self, order: SessionOrder) -> Vec<&WorkSession> {
+        let mut sessions: Vec<&WorkSession> = self.sessions.iter().collect();
+        sessions.sort_by_key(|session| session.start_time);
+        match order {
+            SessionOrder::Start => {}
+            SessionOrder::Duration => sessions.sort_by_key(|session| std::cmp::Reverse(session.end_time - session.start_time)),
+            SessionOrder::Messages => sessions.sort_by_key(|session| std::cmp::Reverse(session.total_messages)),
+            SessionOrder::Tokens => {
+                let tokens = self.session_tokens();
+                sessions.sort_by_key(|session| std::cmp::Reverse(tokens(session)));
+            }
+        }
+        sessions
+    }
+
+    /// Tokens used by each session of the analysis, zero without token data
+    pub fn session_tokens(&self) -> impl Fn(&WorkSession) -> u64 + '_ {
+        let stats = self.token_analysis.as_ref().map(TokenAnalysis::stats_by_session).unwrap_or_default();
+        move |session| stats.get(&(session.session_id, session.start_time)).map_or(0, |stats| stats.usage.total())
+    }
+}
+
+/// Sessions, work time, messages and tokens of one calendar bucket
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TimeBucket {
+    /// First local day of the bucket
+    pub start: NaiveDate,
+    pub sessions: usize,
+    pub messages: usize,
+    #[serde(with = "duration_seconds")]
+    pub work_time: Duration,
+    pub tokens: TokenStats,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TimeBuckets {
+    pub grouping: TimeGrouping,
+    /// Buckets with activity, oldest first
+    pub buckets: Vec<TimeBucket>,
+}
+
+/// Messages per local weekday and hour of the day
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ActivityHeatmap {
+    /// Message counts by weekday from Monday, then by hour
+    pub messages: [[usize; 24]; 7],
+}
+
+impl ActivityHeatmap {
+    /// Count a message sent at `time`, already in local time
+    pub fn add<Tz: TimeZone>(&mut self, time: &DateTime<Tz>) {
+        self.messages[time.weekday().num_days_from_monday() as usize][time.hour() as usize] += 1;
+    }
+
+    pub fn merge(&mut self, other: &ActivityHeatmap) {
+        for (row, other_row) in self.messages.iter_mut().zip(&other.messages) {
+            for (count, other_count) in row.iter_mut().zip(other_row) {
+                *count += other_count;
+            }
+        }
+    }
+
+    pub fn count(&self, weekday: Weekday, hour: u32) -> usize {
+        self.messages[weekday.num_days_from_monday() as usize][hour as usize]
+    }
+
+    pub fn total(&self) -> usize {
+        self.messages.iter().flatten().sum()
+    }
+
+    /// Weekday and hour with the most messages, the earliest in the week on ties
+    pub fn peak(&self) -> Option<(Weekday, u32, usize)> {
+        let mut peak: Option<(Weekday, u32, usize)> = None;
+        for (day, row) in self.messages.iter().enumerate() {
+            for (hour, &count) in row.iter().enumerate() {
+                if count > peak.map_or(0, |(_, _, most)| most) {
+                    peak = Some((Weekday::try_from(day as u8).unwrap_or(Weekday::Mon), hour as u32, count));
+                }
+            }
+        }
+        peak
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pipeline::{AnalysisPipeline, AnalysisTuning};
+    use crate::sample::write_sample;
+    use tempfile::TempDir;
+
+    #[tokio::test]
+    async fn test_analysis_round_trip() {
+        let temp_dir = TempDir::new().unwrap();
+        write_sample(temp_dir.path()).unwrap();
+        let mut analysis = AnalysisPipeline::builder()
+            .data_dir(temp_dir.path())
+            .tuning(AnalysisTuning { min_messages: Some(1), ..Default::default() })
+            .run()
+            .await
+            .unwrap()
+            .analysis;
+        analysis.heatmap = Some(ActivityHeatmap::default());
+        analysis.total_work_time = Duration::milliseconds(5_400_250);
+
+        let json = serde_json::to_string(&analysis).unwrap();
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(value["total_work_time"], 5400.25);
+        assert!(value["token_analysis"]["total"]["usage"]["input_tokens"].as_u64().unwrap() > 0);
+
+        let restored: WorkAnalysis = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.total_work_time, analysis.total_work_time);
+        assert_eq!(restored.sessions.len(), 3);
+        assert_eq!(restored.sessions[0].entries.len(), analysis.sessions[0].entries.len());
+        assert_eq!(serde_json::to_value(&restored).unwrap(), value);
+    }
+}
@@ -0,0 +1,277 @@
+use anyhow::{Context, Result};
+use chrono::{Duration, NaiveDate};
+use std::collections::{HashMap, HashSet};
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// How a generated report is written to its output path
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputMode {
+    /// Replace the file contents (default)
+    Overwrite,
+    /// Append the report to the end of the file
+    Append,
+    /// Write to a date-stamped file next to the output path (report-2025-07-07.md)
+    Dated,
+}
+
+impl OutputMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OutputMode::Overwrite => "overwrite",
+            OutputMode::Append => "append",
+            OutputMode::Dated => "dated",
+        }
+    }
+}
+
+impl FromStr for OutputMode {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "overwrite" => Ok(OutputMode::Overwrite),
+            "append" => Ok(OutputMode::Append),
+            "dated" => Ok(OutputMode::Dated),
+            _ => Err(anyhow::anyhow!(
+                "Invalid output mode '{}'. Expected overwrite, append or dated",
+                s
+            )),
+        }
+    }
+}
+
+pub struct ReportWriter {
+    /// Write strategy for the output path
+    mode: OutputMode,
+    /// Delete dated reports older than this many days (dated mode only)
+    retain_days: Option<i64>,
+}
+
+impl ReportWriter {
+    pub fn new(mode: OutputMode) -> Self {
+        Self {
+            mode,
+            retain_days: None,
+        }
+    }
+
+    pub fn with_retention(mut self, days: i64) -> Self {
+        self.retain_days = Some(days);
+        self
+    }
+
+    /// Write the report according to the output mode and return the path actually written
+    pub fn write(&self, output_path: &Path, report: &str, today: NaiveDate) -> Result<PathBuf> {
+        let target = match self.mode {
+            OutputMode::Overwrite => {
+                std::fs::write(output_path, report)
+                    .with_context(|| format!("Failed to write report: {}", output_path.display()))?;
+                output_path.to_path_buf()
+            }
+            OutputMode::Append => {
+                let has_content = output_path
+                    .metadata()
+                    .map(|metadata| metadata.len() > 0)
+                    .unwrap_or(false);
+
+                let mut file = OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(output_path)
+                    .with_context(|| format!("Failed to open report: {}", output_path.display()))?;
+
+                // Keep consecutive reports visually separated
+                if has_content {
+                    file.write_all(b"\n")?;
+                }
+                file.write_all(report.as_bytes())?;
+                if !report.ends_with('\n') {
+                    file.write_all(b"\n")?;
+                }
+                output_path.to_path_buf()
+            }
+            OutputMode::Dated => {
+                let dated_path = Self::dated_path(output_path, today);
+                std::fs::write(&dated_path, report)
+                    .with_context(|| format!("Failed to write report: {}", dated_path.display()))?;
+                dated_path
+            }
+        };
+
+        if self.mode == OutputMode::Dated {
+            if let Some(days) = self.retain_days {
+                let removed = self.prune_dated_reports(output_path, today - Duration::days(days))?;
+                if removed > 0 {
+                    eprintln!("Info: Removed {} dated reports older than {} days", removed, days);
+                }
+            }
+        }
+
+        Ok(target)
+    }
+
+    /// Build the date-stamped file name for an output path: `report.md` -> `report-2025-07-07.md`
+    pub fn dated_path(output_path: &Path, date: NaiveDate) -> PathBuf {
+        let stem = output_path
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .unwrap_or("report");
+
+        let file_name = match output_path.extension().and_then(|e| e.to_str()) {
+            Some(ext) => format!("{}-{}.{}", stem, date.format("%Y-%m-%d"), ext),
+            None => format!("{}-{}", stem, date.format("%Y-%m-%d")),
+        };
+
+        output_path.with_file_name(file_name)
+    }
+
+    /// Remove dated reports for this output path that are older than the cutoff date
+    fn prune_dated_reports(&self, output_path: &Path, cutoff: NaiveDate) -> Result<usize> {
+        let dir = match output_path.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent.to_path_buf(),
+            _ => PathBuf::from("."),
+        };
+
+        let stem = output_path
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .unwrap_or("report");
+        let suffix = output_path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(|ext| format!(".{}", ext))
+            .unwrap_or_default();
+        let prefix = format!("{}-", stem);
+
+        let mut removed = 0;
+
+        for entry in std::fs::read_dir(&dir)
+            .with_context(|| format!("Failed to read report directory: {}", dir.display()))?
+        {
+            let path = entry?.path();
+            let Some(file_name) = path.file_name().and_then(|n| n.to_str()) else {
+                continue;
+            };
+
+            // Only touch files produced by dated mode for this output path
+            let Some(date_part) = file_name
+                .strip_prefix(&prefix)
+                .and_then(|rest| rest.strip_suffix(suffix.as_str()))
+            else {
+                continue;
+            };
+
+            if let Ok(date) = NaiveDate::parse_from_str(date_part, "%Y-%m-%d") {
+                if date < cutoff {
+                    std::fs::remove_file(&path)
+                        .with_context(|| format!("Failed to remove old report: {}", path.display()))?;
+                    removed += 1;
+                }
+            }
+        }
+
+        Ok(removed)
+    }
+}
+
+/// File name of the index written next to the per-project reports
+pub const INDEX_FILE_NAME: &str = "index.md";
+
+/// Markdown file name per project for `--output-dir`, unique within the directory
+///
+/// Characters that are not safe in file names become `-`; names that collide
+/// after that, or with the index, get a numeric suffix.
+pub fn project_file_names<'a>(projects: impl IntoIterator<Item = &'a str>) -> HashMap<String, String> {
+    let mut taken: HashSet<String> = HashSet::from([INDEX_FILE_NAME.to_string()]);
+    let mut names = HashMap::new();
+    for project in projects {
+        let stem: String = project
+            .chars()
+            .map(|c| if c.is_alphanumeric() || matches!(c, '-' | '_' | '.') { c } else { '-' })
+            .collect();
+        let stem = match stem.trim_matches('.') {
+            "" => "project",
+            stem => stem,
+        };
+        let mut file_name = format!("{}.md", stem);
+        let mut suffix = 2;
+        while !taken.insert(file_name.to_lowercase()) {
+            file_name = format!("{}-{}.md", stem, suffix);
+            suffix += 1;
+        }
+        names.insert(project.to_string(), file_name);
+    }
+    names
+}
+
+impl Default for ReportWriter {
+    fn default() -> Self {
+        Self::new(OutputMode::Overwrite)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[test]
+    fn test_project_file_names() {
+        let names = project_file_names(["api", "my app", "my:app", "index", ".."]);
+        assert_eq!(names["api"], "api.md");
+        assert_eq!(names["my app"], "my-app.md");
+        assert_eq!(names["my:app"], "my-app-2.md");
+        assert_eq!(names["index"], "index-2.md");
+        assert_eq!(names[".."], "project.md");
+    }
+
+    #[test]
+    fn test_output_mode_parsing() {
+        assert_eq!("append".parse::<OutputMode>().unwrap(), OutputMode::Append);
+        assert_eq!("Dated".parse::<OutputMode>().unwrap(), OutputMode::Dated);
+        assert!("rotate".parse::<OutputMode>().is_err());
+    }
+
+    #[test]
+    fn test_dated_path() {
+        let path = ReportWriter::dated_path(Path::new("/tmp/reports/report.md"), date(2025, 7, 7));
+        assert_eq!(path, PathBuf::from("/tmp/reports/report-2025-07-07.md"));
+    }
+
+    #[test]
+    fn test_append_mode_keeps_previous_reports() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("report.md");
+        let writer = ReportWriter::new(OutputMode::Append);
+
+        writer.write(&path, "first", date(2025, 7, 7)).unwrap();
+        writer.write(&path, "second", date(2025, 7, 8)).unwrap();
+
+        let content = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(content, "first\n\nsecond\n");
+    }
+
+    #[test]
+    fn test_dated_mode_prunes_old_reports() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("report.md");
+        std::fs::write(temp_dir.path().join("report-2025-06-01.md"), "old").unwrap();
+        std::fs::write(temp_dir.path().join("report-2025-07-05.md"), "recent").unwrap();
+        std::fs::write(temp_dir.path().join("notes-2025-06-01.md"), "unrelated").unwrap();
+
+        let writer = ReportWriter::new(OutputMode::Dated).with_retention(7);
+        let written = writer.write(&path, "today", date(2025, 7, 7)).unwrap();
+
+        assert_eq!(written, temp_dir.path().join("report-2025-07-07.md"));
+        assert!(!temp_dir.path().join("report-2025-06-01.md").exists());
+        assert!(temp_dir.path().join("report-2025-07-05.md").exists());
+        assert!(temp_dir.path().join("notes-2025-06-01.md").exists());
+    }
+}
@@ -1,22 +1,331 @@
 use anyhow::{Context, Result};
-use std::path::Path;
+use chrono::{DateTime, Utc};
+use futures::{stream, Stream, StreamExt, TryStreamExt};
+use serde::de::IgnoredAny;
+use serde::{Deserialize, Serialize};
+use serde_json::value::RawValue;
+use std::borrow::Cow;
+use std::path::{Path, PathBuf};
 use tokio::fs::File;
-use tokio::io::{AsyncBufReadExt, BufReader};
+use std::io::SeekFrom;
+use tokio::io::{AsyncBufReadExt, AsyncSeekExt, BufReader};
+use tokio_util::sync::CancellationToken;
+
+use crate::cache::ParseCache;
+use crate::error::Error;
+use crate::models::{ClaudeLogEntry, ContentBlock, EntryType, MessageContent, MessageContentVariant, UsageInfo};
+use uuid::Uuid;
+use crate::profile::Profiler;
+use crate::progress::ParseProgress;
+
+/// Line-level statistics collected while parsing a single file
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ParseStats {
+    /// Non-empty lines read from the file
+    pub total_lines: usize,
+    /// Lines successfully parsed into entries
+    pub parsed_entries: usize,
+    /// Summary entries that were intentionally skipped
+    pub summary_entries: usize,
+    /// Lines skipped for exceeding the maximum line length
+    pub oversized_lines: usize,
+    /// Lines that failed to parse
+    pub parse_errors: usize,
+    /// Files left unread because refresh was off and the parse cache had no result for them
+    #[serde(default)]
+    pub uncached_files: usize,
+}
+
+impl ParseStats {
+    /// Add the counts of lines parsed separately, e.g. lines appended to a cached file
+    pub fn add(&mut self, other: &ParseStats) {
+        self.total_lines += other.total_lines;
+        self.parsed_entries += other.parsed_entries;
+        self.summary_entries += other.summary_entries;
+        self.oversized_lines += other.oversized_lines;
+        self.parse_errors += other.parse_errors;
+        self.uncached_files += other.uncached_files;
+    }
+
+    /// Fraction of non-summary lines that could not be parsed
+    pub fn error_rate(&self) -> f64 {
+        let relevant_lines = self.total_lines.saturating_sub(self.summary_entries);
+        if relevant_lines == 0 {
+            0.0
+        } else {
+            (self.parse_errors + self.oversized_lines) as f64 / relevant_lines as f64
+        }
+    }
+}
+
+/// The `type` of a log line, read without building the rest of the line
+#[derive(Deserialize)]
+struct LineType<'a> {
+    #[serde(rename = "type", borrow)]
+    line_type: Option<Cow<'a, str>>,
+}
+
+fn is_summary_line(line: &str) -> bool {
+    serde_json::from_str::<LineType>(line).is_ok_and(|line| line.line_type.as_deref() == Some("summary"))
+}
+
+/// How much of each entry's message content the parser builds
+///
+/// Levels are ordered, so the level a command needs is the greatest its
+/// report sections need.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ContentLevel {
+    /// Timestamps, ids, directories, models and token usage; content is left empty
+    Metadata,
+    /// Also the content blocks with tool calls, tool results and thinking, but
+    /// without message text and tool input
+    Outline,
+    /// Every field
+    #[default]
+    Full,
+}
+
+/// A log line whose message content is built as `C`, skipping what `C` does not
+/// keep while scanning instead of building it
+#[derive(Deserialize)]
+struct PartialLine<C> {
+    #[serde(rename = "parentUuid")]
+    parent_uuid: Option<Uuid>,
+    #[serde(rename = "isSidechain")]
+    is_sidechain: bool,
+    #[serde(rename = "userType")]
+    user_type: String,
+    cwd: String,
+    #[serde(rename = "sessionId")]
+    session_id: Uuid,
+    version: String,
+    #[serde(rename = "type")]
+    entry_type: EntryType,
+    message: PartialMessage<C>,
+    uuid: Uuid,
+    timestamp: DateTime<Utc>,
+    #[serde(rename = "requestId")]
+    request_id: Option<String>,
+    #[serde(rename = "toolUseResult", default)]
+    tool_use_result: Option<ErrorResult>,
+    #[serde(default)]
+    source: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct PartialMessage<C> {
+    role: String,
+    // Required like the content of a full entry, so the same lines are accepted
+    content: C,
+    id: Option<String>,
+    #[serde(rename = "type")]
+    message_type: Option<String>,
+    model: Option<String>,
+    stop_reason: Option<String>,
+    stop_sequence: Option<String>,
+    usage: Option<UsageInfo>,
+}
+
+impl<C: Into<MessageContentVariant>> From<PartialLine<C>> for ClaudeLogEntry {
+    fn from(line: PartialLine<C>) -> Self {
+        let message = line.message;
+        ClaudeLogEntry {
+            parent_uuid: line.parent_uuid,
+            is_sidechain: line.is_sidechain,
+            user_type: line.user_type,
+            cwd: line.cwd,
+            session_id: line.session_id,
+            version: line.version,
+            entry_type: line.entry_type,
+            message: MessageContent {
+                role: message.role,
+                content: message.content.into(),
+                id: message.id,
+                message_type: message.message_type,
+                model: message.model,
+                stop_reason: message.stop_reason,
+                stop_sequence: message.stop_sequence,
+                usage: message.usage,
+            },
+            uuid: line.uuid,
+            timestamp: line.timestamp,
+            request_id: line.request_id,
+            tool_use_result: line.tool_use_result.and_then(|result| result.0),
+
+            source: line.source,
+        }
+    }
+}
+
+/// Content skipped entirely, built as an empty string
+struct SkippedContent;
+
+impl<'de> Deserialize<'de> for SkippedContent {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        IgnoredAny::deserialize(deserializer).map(|_| SkippedContent)
+    }
+}
+
+impl From<SkippedContent> for MessageContentVariant {
+    fn from(_: SkippedContent) -> Self {
+        MessageContentVariant::String(String::new())
+    }
+}
+
+/// Content blocks without their text and tool input
+struct OutlineContent(MessageContentVariant);
+
+/// The fields of a content block kept in an outline
+#[derive(Deserialize)]
+struct OutlineBlock {
+    #[serde(rename = "type")]
+    content_type: String,
+    thinking: Option<String>,
+    id: Option<String>,
+    name: Option<String>,
+    tool_use_id: Option<String>,
+    #[serde(default)]
+    is_error: Option<bool>,
+}
+
+impl<'de> Deserialize<'de> for OutlineContent {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct OutlineVisitor;
+
+        impl<'de> serde::de::Visitor<'de> for OutlineVisitor {
+            type Value = OutlineContent;
+
+            fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+                formatter.write_str("a string or an array of content blocks")
+            }
+
+            fn visit_str<E: serde::de::Error>(self, _: &str) -> Result<Self::Value, E> {
+                Ok(OutlineContent(MessageContentVariant::String(String::new())))
+            }
+
+            fn visit_seq<A: serde::de::SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+                let mut blocks = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+                while let Some(block) = seq.next_element::<OutlineBlock>()? {
+                    blocks.push(ContentBlock {
+                        content_type: block.content_type,
+                        text: None,
+                        thinking: block.thinking,
+                        signature: None,
+                        id: block.id,
+                        name: block.name,
+                        input: None,
+                        tool_use_id: block.tool_use_id,
+                        is_error: block.is_error,
+                    });
+                }
+                Ok(OutlineContent(MessageContentVariant::Array(blocks)))
+            }
+        }
+
+        deserializer.deserialize_any(OutlineVisitor)
+    }
+}
+
+impl From<OutlineContent> for MessageContentVariant {
+    fn from(content: OutlineContent) -> Self {
+        content.0
+    }
+}
+
+/// A tool result kept only if it is an "Error: ..." string, the failure
+/// marker of older logs
+struct ErrorResult(Option<Box<RawValue>>);
 
-use crate::models::ClaudeLogEntry;
+impl<'de> Deserialize<'de> for ErrorResult {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct ErrorVisitor;
 
+        impl<'de> serde::de::Visitor<'de> for ErrorVisitor {
+            type Value = ErrorResult;
+
+            fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+                formatter.write_str("a tool result")
+            }
+
+            fn visit_str<E: serde::de::Error>(self, value: &str) -> Result<Self::Value, E> {
+                if !value.starts_with("Error") {
+                    return Ok(ErrorResult(None));
+                }
+                serde_json::value::to_raw_value(value).map(|raw| ErrorResult(Some(raw))).map_err(E::custom)
+            }
+
+            fn visit_bool<E: serde::de::Error>(self, _: bool) -> Result<Self::Value, E> {
+                Ok(ErrorResult(None))
+            }
+
+            fn visit_i64<E: serde::de::Error>(self, _: i64) -> Result<Self::Value, E> {
+                Ok(ErrorResult(None))
+            }
+
+            fn visit_u64<E: serde::de::Error>(self, _: u64) -> Result<Self::Value, E> {
+                Ok(ErrorResult(None))
+            }
+
+            fn visit_f64<E: serde::de::Error>(self, _: f64) -> Result<Self::Value, E> {
+                Ok(ErrorResult(None))
+            }
+
+            fn visit_unit<E: serde::de::Error>(self) -> Result<Self::Value, E> {
+                Ok(ErrorResult(None))
+            }
+
+            fn visit_seq<A: serde::de::SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+                while seq.next_element::<IgnoredAny>()?.is_some() {}
+                Ok(ErrorResult(None))
+            }
+
+            fn visit_map<A: serde::de::MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+                while map.next_entry::<IgnoredAny, IgnoredAny>()?.is_some() {}
+                Ok(ErrorResult(None))
+            }
+        }
+
+        deserializer.deserialize_any(ErrorVisitor)
+    }
+}
+
+#[derive(Clone)]
 pub struct JsonlParser {
     /// Whether to skip malformed lines or fail on them
     skip_malformed: bool,
     /// Maximum line length to prevent memory issues
     max_line_length: usize,
+    /// Cache of previously parsed files
+    cache: Option<ParseCache>,
+    /// Whether files changed since they were cached are read again
+    refresh: bool,
+    /// Bytes read from a file at a time
+    buffer_size: usize,
+    /// How much of the message content is built
+    content_level: ContentLevel,
+    /// Records the time spent reading each file
+    profiler: Profiler,
+    /// Counts the files read and their entries
+    progress: ParseProgress,
+    /// Stops reading files whose entries are no longer wanted
+    cancellation: CancellationToken,
 }
 
+/// Read buffer of the parser unless configured
+pub const DEFAULT_BUFFER_SIZE: usize = 64 * 1024;
+
 impl JsonlParser {
     pub fn new() -> Self {
         Self {
             skip_malformed: true,
             max_line_length: 10 * 1024 * 1024, // 10MB per line max (for large image content)
+            cache: None,
+            refresh: true,
+            buffer_size: DEFAULT_BUFFER_SIZE,
+            content_level: ContentLevel::Full,
+            profiler: Profiler::default(),
+            progress: ParseProgress::default(),
+            cancellation: CancellationToken::new(),
         }
     }
 
@@ -24,6 +333,13 @@ impl JsonlParser {
         Self {
             skip_malformed: false,
             max_line_length: 1024 * 1024,
+            cache: None,
+            refresh: true,
+            buffer_size: DEFAULT_BUFFER_SIZE,
+            content_level: ContentLevel::Full,
+            profiler: Profiler::default(),
+            progress: ParseProgress::default(),
+            cancellation: CancellationToken::new(),
         }
     }
 
@@ -32,27 +348,192 @@ impl JsonlParser {
         self
     }
 
+    /// Read files `buffer_size` bytes at a time
+    ///
+    /// Larger buffers mean fewer reads on large logs; 0 is treated as 1 byte.
+    pub fn with_buffer_size(mut self, buffer_size: usize) -> Self {
+        self.buffer_size = buffer_size.max(1);
+        self
+    }
+
+    /// Build only as much of each entry's message content as `level`
+    ///
+    /// Below [`ContentLevel::Full`], parsing is much faster for reports that do
+    /// not look at message text. Cached entries are still returned whole, but
+    /// files parsed this way are not cached.
+    pub fn with_content_level(mut self, level: ContentLevel) -> Self {
+        self.content_level = level;
+        self
+    }
+
+    /// Reuse parse results of unchanged files from the given cache
+    pub fn with_cache(mut self, cache: ParseCache) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
+    /// Whether to read files that changed since they were cached
+    ///
+    /// Without refresh, cached results are returned as they are and files missing
+    /// from the cache are skipped, so repeated runs give the same answer.
+    pub fn with_refresh(mut self, refresh: bool) -> Self {
+        self.refresh = refresh;
+        self
+    }
+
+    /// Record each file read as an item of the `parse` stage
+    ///
+    /// The storages reading through the parser record their file scans with it too.
+    pub fn with_profiler(mut self, profiler: Profiler) -> Self {
+        self.profiler = profiler;
+        self
+    }
+
+    /// Fail reading files with [`Error::Cancelled`] once `cancellation` is cancelled
+    ///
+    /// Cloning a shared parser gives each request its own token over the same cache.
+    pub fn with_cancellation(mut self, cancellation: CancellationToken) -> Self {
+        self.cancellation = cancellation;
+        self
+    }
+
+    pub fn profiler(&self) -> &Profiler {
+        &self.profiler
+    }
+
+    /// Count each file read and its entries on `progress`
+    ///
+    /// The storages reading through the parser start it with the files they read.
+    pub fn with_progress(mut self, progress: ParseProgress) -> Self {
+        self.progress = progress;
+        self
+    }
+
+    pub fn progress(&self) -> &ParseProgress {
+        &self.progress
+    }
+
+    /// Whether files changed since they were cached are read again
+    pub fn refreshes(&self) -> bool {
+        self.refresh
+    }
+
     /// Parse a JSONL file and return all valid Claude log entries
     pub async fn parse_file(&self, file_path: &Path) -> Result<Vec<ClaudeLogEntry>> {
-        let file = File::open(file_path)
+        let (entries, _) = self.parse_file_with_stats(file_path).await?;
+        Ok(entries)
+    }
+
+    /// Parse a JSONL file and also return line-level parsing statistics
+    pub async fn parse_file_with_stats(&self, file_path: &Path) -> Result<(Vec<ClaudeLogEntry>, ParseStats)> {
+        let (entries, stats, _) = self.parse_file_with_cache_status(file_path).await?;
+        Ok((entries, stats))
+    }
+
+    /// Like [`Self::parse_file_with_stats`], also telling whether the result came from the cache
+    pub async fn parse_file_with_cache_status(
+        &self,
+        file_path: &Path,
+    ) -> Result<(Vec<ClaudeLogEntry>, ParseStats, bool)> {
+        let result = self.read_file(file_path).await;
+        if let Ok((entries, _, cached)) = &result {
+            tracing::debug!("Read {} entries from {}{}", entries.len(), file_path.display(), if *cached { " (cached)" } else { "" });
+            self.progress.file_read(entries.len());
+        }
+        result
+    }
+
+    /// Entries of the file from the cache or the file itself
+    async fn read_file(&self, file_path: &Path) -> Result<(Vec<ClaudeLogEntry>, ParseStats, bool)> {
+        if self.cancellation.is_cancelled() {
+            return Err(Error::Cancelled.into());
+        }
+        let _parse = self.profiler.enter_item("parse", &file_path.display().to_string());
+        let Some(cache) = &self.cache else {
+            let (entries, stats, _) = self.parse_file_from(file_path, 0).await?;
+            return Ok((entries, stats, false));
+        };
+
+        if let Some((entries, stats)) = cache.load(file_path, self.max_line_length, self.skip_malformed).await {
+            return Ok((entries, stats, true));
+        }
+
+        if !self.refresh {
+            if let Some((entries, stats)) = cache.load_stale(file_path, self.max_line_length, self.skip_malformed).await {
+                return Ok((entries, stats, true));
+            }
+            tracing::info!("{} is not in the parse cache, skipped without refresh", file_path.display());
+            let stats = ParseStats { uncached_files: 1, ..ParseStats::default() };
+            return Ok((Vec::new(), stats, false));
+        }
+
+        if self.content_level < ContentLevel::Full {
+            // Entries without content would be served to every later run
+            let (entries, stats, _) = self.parse_file_from(file_path, 0).await?;
+            return Ok((entries, stats, false));
+        }
+
+        let (entries, stats, parsed_bytes) = match cache.load_appended(file_path, self.max_line_length, self.skip_malformed).await {
+            Some((mut entries, mut stats, offset)) => {
+                let (new_entries, new_stats, parsed_bytes) = self.parse_file_from(file_path, offset).await?;
+                entries.extend(new_entries);
+                stats.add(&new_stats);
+                (entries, stats, parsed_bytes)
+            }
+            None => self.parse_file_from(file_path, 0).await?,
+        };
+        // A cache that cannot be written only costs speed
+        if let Err(e) = cache.store(file_path, self.max_line_length, self.skip_malformed, parsed_bytes, &entries, &stats).await {
+            tracing::warn!("Failed to cache {}: {}", file_path.display(), e);
+        }
+        Ok((entries, stats, false))
+    }
+
+    /// The cache parse results are read from and written to, if any
+    pub fn cache(&self) -> Option<&ParseCache> {
+        self.cache.as_ref()
+    }
+
+    /// Parse the lines starting at byte `offset`, also returning the offset after the
+    /// last complete line (`None` if the file ends in a partial line)
+    ///
+    /// Line numbers in warnings count from `offset`.
+    async fn parse_file_from(&self, file_path: &Path, offset: u64) -> Result<(Vec<ClaudeLogEntry>, ParseStats, Option<u64>)> {
+        let mut file = File::open(file_path)
             .await
             .with_context(|| format!("Failed to open file: {}", file_path.display()))?;
+        file.seek(SeekFrom::Start(offset)).await?;
 
-        let reader = BufReader::new(file);
-        let mut lines = reader.lines();
+        let mut reader = BufReader::with_capacity(self.buffer_size, file);
+        let mut buffer = String::new();
+        let mut parsed_bytes = Some(offset);
         let mut entries = Vec::new();
         let mut line_number = 0;
         let mut skipped_lines = 0;
         let mut oversized_lines = 0;
         let mut summary_entries = 0;
+        let mut non_empty_lines = 0;
 
-        while let Some(line) = lines.next_line().await? {
+        loop {
+            if self.cancellation.is_cancelled() {
+                return Err(Error::Cancelled.into());
+            }
+            buffer.clear();
+            let read = reader.read_line(&mut buffer).await?;
+            if read == 0 {
+                break;
+            }
+            parsed_bytes = parsed_bytes
+                .filter(|_| buffer.ends_with('\n'))
+                .map(|bytes| bytes + read as u64);
+            let line = buffer.trim_end_matches(['\n', '\r']);
             line_number += 1;
 
             // Skip empty lines
             if line.trim().is_empty() {
                 continue;
             }
+            non_empty_lines += 1;
 
             // Check line length
             if line.len() > self.max_line_length {
@@ -60,7 +541,7 @@ impl JsonlParser {
                 if self.skip_malformed {
                     // Only show warning for the first few oversized lines to avoid spam
                     if oversized_lines <= 3 {
-                        eprintln!("Warning: Line {} exceeds maximum length of {} bytes in {}", 
+                        tracing::warn!("Line {} exceeds maximum length of {} bytes in {}",
                                  line_number, self.max_line_length, file_path.display());
                     }
                     continue;
@@ -72,7 +553,7 @@ impl JsonlParser {
                 }
             }
 
-            match self.parse_line(&line) {
+            match self.parse_line(line) {
                 Ok(entry) => entries.push(entry),
                 Err(e) => {
                     let error_str = e.to_string();
@@ -86,7 +567,7 @@ impl JsonlParser {
                     if self.skip_malformed {
                         // Only show warning for the first few parse errors to avoid spam
                         if skipped_lines <= 3 {
-                            eprintln!("Warning: Failed to parse line {} in {}: {}",
+                            tracing::warn!("Failed to parse line {} in {}: {}",
                                      line_number, file_path.display(), e);
                         }
                         continue;
@@ -113,26 +594,36 @@ impl JsonlParser {
             if skipped_lines > 0 {
                 issues.push(format!("{} parse errors", skipped_lines));
             }
-            eprintln!("Info: {} - Skipped {} (out of {} total lines)", 
+            tracing::info!("{} - Skipped {} (out of {} total lines)",
                      filename, issues.join(", "), line_number);
         }
 
-        Ok(entries)
+        let stats = ParseStats {
+            total_lines: non_empty_lines,
+            parsed_entries: entries.len(),
+            summary_entries,
+            oversized_lines,
+            parse_errors: skipped_lines,
+            uncached_files: 0,
+        };
+
+        Ok((entries, stats, parsed_bytes))
     }
 
     /// Parse a single line of JSONL into a ClaudeLogEntry
     pub fn parse_line(&self, line: &str) -> Result<ClaudeLogEntry> {
-        // First check if this is a summary entry, which we should skip
-        if let Ok(summary_check) = serde_json::from_str::<serde_json::Value>(line) {
-            if summary_check.get("type").and_then(|t| t.as_str()) == Some("summary") {
-                return Err(anyhow::anyhow!("Skipping summary entry"));
-            }
+        let parsed = match self.content_level {
+            ContentLevel::Metadata => serde_json::from_str::<PartialLine<SkippedContent>>(line).map(ClaudeLogEntry::from),
+            ContentLevel::Outline => serde_json::from_str::<PartialLine<OutlineContent>>(line).map(ClaudeLogEntry::from),
+            ContentLevel::Full => serde_json::from_str(line),
+        };
+        match parsed {
+            Ok(entry) => Ok(entry),
+            // Only lines that are not entries are checked for being summaries,
+            // so entries are read in a single pass
+            Err(_) if is_summary_line(line) => Err(anyhow::anyhow!("Skipping summary entry")),
+            Err(e) => Err(anyhow::Error::new(e).context("Failed to deserialize JSON line")),
         }
-        
-        let entry: ClaudeLogEntry = serde_json::from_str(line)
-            .context("Failed to deserialize JSON line")?;
-        
-        Ok(entry)
     }
 
     /// Parse multiple JSONL files concurrently
@@ -146,11 +637,22 @@ impl JsonlParser {
         }
 
         // Sort by timestamp to maintain chronological order
-        all_entries.sort_by(|a, b| a.timestamp.cmp(&b.timestamp));
+        all_entries.sort_by_key(|e| e.timestamp);
 
         Ok(all_entries)
     }
 
+    /// Entries of the files as a stream, reading one file at a time
+    ///
+    /// Unlike [`Self::parse_files`], only the file being read is held in memory and
+    /// entries are not sorted, so each log session's entries stay in file order.
+    pub fn stream_files<'a>(&'a self, file_paths: &'a [PathBuf]) -> impl Stream<Item = Result<ClaudeLogEntry>> + 'a {
+        stream::iter(file_paths)
+            .then(move |file_path| self.parse_file(file_path))
+            .map_ok(|entries| stream::iter(entries.into_iter().map(Ok)))
+            .try_flatten()
+    }
+
     /// Parse JSONL content from a string
     pub fn parse_string(&self, content: &str) -> Result<Vec<ClaudeLogEntry>> {
         let mut entries = Vec::new();
@@ -170,7 +672,7 @@ impl JsonlParser {
                     );
 
                     if self.skip_malformed {
-                        eprintln!("Warning: {}", error_msg);
+                        tracing::warn!("{}", error_msg);
                         continue;
                     } else {
                         return Err(anyhow::anyhow!(error_msg));
@@ -271,4 +773,82 @@ invalid json line
         
         assert_eq!(entries.len(), 1);
     }
-}
\ No newline at end of file
+
+    #[tokio::test]
+    async fn test_parse_file_with_stats() {
+        let mut temp_file = NamedTempFile::new().unwrap();
+        let content = r#"{"type":"summary","summary":"Session title","leafUuid":"550e8400-e29b-41d4-a716-446655440002"}
+{"parentUuid":null,"sessionId":"550e8400-e29b-41d4-a716-446655440000","timestamp":"2025-06-30T05:37:52.554Z","type":"user","message":{"role":"user","content":"test"},"uuid":"550e8400-e29b-41d4-a716-446655440001","isSidechain":false,"userType":"external","cwd":"/test","version":"1.0.0"}
+not json
+
+"#;
+
+        temp_file.write_all(content.as_bytes()).unwrap();
+        temp_file.flush().unwrap();
+
+        let parser = JsonlParser::new();
+        let (entries, stats) = parser.parse_file_with_stats(temp_file.path()).await.unwrap();
+
+        assert_eq!(entries.len(), 1);
+        assert_eq!(stats.total_lines, 3);
+        assert_eq!(stats.summary_entries, 1);
+        assert_eq!(stats.parse_errors, 1);
+        assert!((stats.error_rate() - 0.5).abs() < f64::EPSILON);
+    }
+
+    #[tokio::test]
+    async fn test_tool_values_kept_raw() {
+        let mut temp_file = NamedTempFile::new().unwrap();
+        let content = r#"{"parentUuid":null,"sessionId":"550e8400-e29b-41d4-a716-446655440000","timestamp":"2025-06-30T05:37:52.554Z","type":"assistant","message":{"role":"assistant","content":[{"type":"tool_use","id":"t1","name":"Edit","input":{"file_path":"/test/a.rs","old_string":"a","new_string":"b"}}]},"uuid":"550e8400-e29b-41d4-a716-446655440001","isSidechain":false,"userType":"external","cwd":"/test","version":"1.0.0","toolUseResult":{"stdout":"ok","interrupted":false}}
+"#;
+        temp_file.write_all(content.as_bytes()).unwrap();
+        temp_file.flush().unwrap();
+
+        // A buffer shorter than the line still reads it whole
+        let parser = JsonlParser::new().with_buffer_size(16);
+        let entries = parser.parse_file(temp_file.path()).await.unwrap();
+        assert_eq!(entries.len(), 1);
+
+        let json = serde_json::to_string(&entries[0]).unwrap();
+        assert!(json.contains(r#""input":{"file_path":"/test/a.rs","old_string":"a","new_string":"b"}"#));
+        assert!(json.contains(r#""toolUseResult":{"stdout":"ok","interrupted":false}"#));
+    }
+
+    #[tokio::test]
+    async fn test_content_levels() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("session.jsonl");
+        let content = r#"{"parentUuid":null,"sessionId":"550e8400-e29b-41d4-a716-446655440000","timestamp":"2025-06-30T05:37:52.554Z","type":"assistant","message":{"role":"assistant","id":"msg_1","model":"claude-sonnet-4","content":[{"type":"text","text":"done"}],"usage":{"input_tokens":10,"output_tokens":5}},"uuid":"550e8400-e29b-41d4-a716-446655440001","isSidechain":false,"userType":"external","cwd":"/test","version":"1.0.0","toolUseResult":"Error: failed"}
+{"type":"summary","summary":"Session title","leafUuid":"550e8400-e29b-41d4-a716-446655440002"}
+{"parentUuid":null,"sessionId":"550e8400-e29b-41d4-a716-446655440000","timestamp":"2025-06-30T05:37:53.554Z","type":"user","message":{"role":"user"},"uuid":"550e8400-e29b-41d4-a716-446655440003","isSidechain":false,"userType":"external","cwd":"/test","version":"1.0.0"}
+"#;
+        std::fs::write(&path, content).unwrap();
+        let cache = ParseCache::new(temp_dir.path().join("cache"));
+
+        let parser = JsonlParser::new().with_content_level(ContentLevel::Metadata).with_cache(cache.clone());
+        let (entries, stats) = parser.parse_file_with_stats(&path).await.unwrap();
+        assert_eq!(entries.len(), 1);
+        // The same lines are rejected as by a full parse
+        assert_eq!((stats.summary_entries, stats.parse_errors), (1, 1));
+
+        let entry = &entries[0];
+        assert_eq!((entry.cwd.as_str(), entry.message.model.as_deref()), ("/test", Some("claude-sonnet-4")));
+        assert_eq!(entry.message.usage.as_ref().unwrap().output_tokens, Some(5));
+        assert!(matches!(&entry.message.content, MessageContentVariant::String(text) if text.is_empty()));
+        // Failures of older logs are only marked in the tool result
+        assert_eq!(entry.tool_use_result.as_ref().unwrap().get(), "\"Error: failed\"");
+
+        let outline = JsonlParser::new().with_content_level(ContentLevel::Outline).parse_file(&path).await.unwrap();
+        let MessageContentVariant::Array(blocks) = &outline[0].message.content else {
+            panic!("outline without blocks");
+        };
+        assert_eq!((blocks[0].content_type.as_str(), blocks[0].text.as_deref()), ("text", None));
+
+        // Entries without content are not cached for later full parses
+        assert!(cache.load(&path, parser.max_line_length, parser.skip_malformed).await.is_none());
+        let full = JsonlParser::new().with_cache(cache.clone()).parse_file(&path).await.unwrap();
+        assert!(matches!(&full[0].message.content, MessageContentVariant::Array(_)));
+        let (cached, _, from_cache) = parser.parse_file_with_cache_status(&path).await.unwrap();
+        assert!(from_cache && matches!(&cached[0].message.content, MessageContentVariant::Array(_)));
+    }
+}
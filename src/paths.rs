@@ -0,0 +1,97 @@
+/// Whether a path uses Windows conventions: a drive letter or backslashes
+pub fn is_windows_path(path: &str) -> bool {
+    let bytes = path.as_bytes();
+    path.contains('\\') || (bytes.len() >= 2 && bytes[0].is_ascii_alphabetic() && bytes[1] == b':')
+}
+
+/// Non-empty segments of a path, split on both `/` and `\`
+///
+/// Logs may come from Unix or Windows regardless of where the analysis runs, so
+/// log paths are not handled with `std::path`.
+pub fn segments(path: &str) -> impl DoubleEndedIterator<Item = &str> {
+    path.split(['/', '\\']).filter(|segment| !segment.is_empty())
+}
+
+/// Final segment of a path, e.g. `api` for `/home/me/api/` or `C:\Users\me\api`
+pub fn last_segment(path: &str) -> Option<&str> {
+    segments(path).next_back()
+}
+
+/// The path and each of its parents, longest first, in the path's own separator style
+pub fn ancestors(path: &str) -> Vec<String> {
+    let trimmed = path.trim_end_matches(['/', '\\']);
+    let mut ancestors = Vec::new();
+    let mut current = trimmed;
+    while !current.is_empty() {
+        ancestors.push(current.to_string());
+        match current.rfind(['/', '\\']) {
+            Some(index) => current = current[..index].trim_end_matches(['/', '\\']),
+            None => break,
+        }
+    }
+    ancestors
+}
+
+/// Whether `path` is `root` or lies below it, comparing whole segments
+///
+/// Windows paths compare case-insensitively, matching the file system.
+pub fn is_within(path: &str, root: &str) -> bool {
+    let case_insensitive = is_windows_path(path) || is_windows_path(root);
+    let mut path_segments = segments(path);
+    for root_segment in segments(root) {
+        let Some(path_segment) = path_segments.next() else {
+            return false;
+        };
+        let same = if case_insensitive {
+            path_segment.eq_ignore_ascii_case(root_segment)
+        } else {
+            path_segment == root_segment
+        };
+        if !same {
+            return false;
+        }
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_windows_path() {
+        assert!(is_windows_path(r"C:\Users\me\api"));
+        assert!(is_windows_path("d:/work/api"));
+        assert!(is_windows_path(r"\\server\share\api"));
+        assert!(!is_windows_path("/home/me/api"));
+    }
+
+    #[test]
+    fn test_last_segment() {
+        assert_eq!(last_segment("/home/me/api"), Some("api"));
+        assert_eq!(last_segment("/home/me/api/"), Some("api"));
+        assert_eq!(last_segment(r"C:\Users\me\api"), Some("api"));
+        assert_eq!(last_segment(r"C:\Users\me\api\"), Some("api"));
+        assert_eq!(last_segment("C:/Users/me/web"), Some("web"));
+        assert_eq!(last_segment(""), None);
+    }
+
+    #[test]
+    fn test_ancestors() {
+        assert_eq!(ancestors("/home/me/api"), ["/home/me/api", "/home/me", "/home"]);
+        assert_eq!(ancestors(r"C:\Users\me\api"), [r"C:\Users\me\api", r"C:\Users\me", r"C:\Users", "C:"]);
+    }
+
+    #[test]
+    fn test_is_within() {
+        assert!(is_within("/home/me/api/src", "/home/me/api"));
+        assert!(is_within("/home/me/api", "/home/me/api/"));
+        assert!(!is_within("/home/me/api-v2", "/home/me/api"));
+        assert!(!is_within("/home/me/API", "/home/me/api"));
+
+        assert!(is_within(r"C:\Users\me\api\src", r"C:\Users\me\api"));
+        assert!(is_within(r"c:\users\me\API", r"C:\Users\me\api"));
+        assert!(is_within("C:/Users/me/api/src", r"C:\Users\me\api"));
+        assert!(!is_within(r"D:\Users\me\api", r"C:\Users\me\api"));
+    }
+}
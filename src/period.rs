@@ -0,0 +1,145 @@
+use anyhow::Result;
+use chrono::{Datelike, Duration, Months, NaiveDate};
+use std::str::FromStr;
+
+/// How reporting periods are laid out on the calendar
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportingPeriod {
+    /// Monday to Sunday
+    Week,
+    /// Calendar month
+    Month,
+    /// Fixed-length sprints counted from an anchor date
+    Sprint { start: NaiveDate, length_days: i64 },
+    /// Months that begin on a given day, e.g. the 21st to the 20th
+    FiscalMonth { start_day: u32 },
+}
+
+impl FromStr for ReportingPeriod {
+    type Err = anyhow::Error;
+
+    /// `week`, `month`, `sprint:2025-07-07:14` or `fiscal-month:21`
+    fn from_str(s: &str) -> Result<Self> {
+        let invalid = || {
+            anyhow::anyhow!(
+                "Invalid period '{}'. Expected week, month, sprint:YYYY-MM-DD:DAYS or fiscal-month:DAY",
+                s
+            )
+        };
+
+        let mut parts = s.split(':');
+        let period = match parts.next().unwrap_or_default().to_lowercase().as_str() {
+            "week" => ReportingPeriod::Week,
+            "month" => ReportingPeriod::Month,
+            "sprint" => {
+                let start = NaiveDate::parse_from_str(parts.next().ok_or_else(invalid)?, "%Y-%m-%d")
+                    .map_err(|_| invalid())?;
+                let length_days = match parts.next() {
+                    Some(days) => days.parse().map_err(|_| invalid())?,
+                    None => 14,
+                };
+                if length_days < 1 {
+                    return Err(invalid());
+                }
+                ReportingPeriod::Sprint { start, length_days }
+            }
+            "fiscal-month" => {
+                let start_day: u32 = parts.next().ok_or_else(invalid)?.parse().map_err(|_| invalid())?;
+                // Later days do not exist in every month
+                if !(1..=28).contains(&start_day) {
+                    return Err(invalid());
+                }
+                ReportingPeriod::FiscalMonth { start_day }
+            }
+            _ => return Err(invalid()),
+        };
+
+        if parts.next().is_some() {
+            return Err(invalid());
+        }
+        Ok(period)
+    }
+}
+
+impl ReportingPeriod {
+    /// First and last day of the period containing `date`
+    pub fn containing(&self, date: NaiveDate) -> (NaiveDate, NaiveDate) {
+        match *self {
+            ReportingPeriod::Week => {
+                let start = date - Duration::days(date.weekday().num_days_from_monday() as i64);
+                (start, start + Duration::days(6))
+            }
+            ReportingPeriod::Month => {
+                let start = date.with_day(1).unwrap();
+                (start, start + Months::new(1) - Duration::days(1))
+            }
+            ReportingPeriod::Sprint { start, length_days } => {
+                let index = (date - start).num_days().div_euclid(length_days);
+                let sprint_start = start + Duration::days(index * length_days);
+                (sprint_start, sprint_start + Duration::days(length_days - 1))
+            }
+            ReportingPeriod::FiscalMonth { start_day } => {
+                let this_month = date.with_day(start_day).unwrap();
+                let start = if date >= this_month {
+                    this_month
+                } else {
+                    this_month - Months::new(1)
+                };
+                (start, start + Months::new(1) - Duration::days(1))
+            }
+        }
+    }
+
+    /// The period `offset` periods before the one containing `date` (0 = current)
+    pub fn nth_back(&self, date: NaiveDate, offset: u32) -> (NaiveDate, NaiveDate) {
+        let mut period = self.containing(date);
+        for _ in 0..offset {
+            period = self.containing(period.0 - Duration::days(1));
+        }
+        period
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[test]
+    fn test_parse_periods() {
+        assert_eq!("week".parse::<ReportingPeriod>().unwrap(), ReportingPeriod::Week);
+        assert_eq!(
+            "sprint:2025-07-07".parse::<ReportingPeriod>().unwrap(),
+            ReportingPeriod::Sprint { start: date(2025, 7, 7), length_days: 14 }
+        );
+        assert_eq!(
+            "fiscal-month:21".parse::<ReportingPeriod>().unwrap(),
+            ReportingPeriod::FiscalMonth { start_day: 21 }
+        );
+        assert!("sprint:2025-07-07:0".parse::<ReportingPeriod>().is_err());
+        assert!("fiscal-month:31".parse::<ReportingPeriod>().is_err());
+        assert!("quarter".parse::<ReportingPeriod>().is_err());
+    }
+
+    #[test]
+    fn test_sprint_boundaries() {
+        let sprint = ReportingPeriod::Sprint { start: date(2025, 7, 7), length_days: 14 };
+        assert_eq!(sprint.containing(date(2025, 7, 20)), (date(2025, 7, 7), date(2025, 7, 20)));
+        assert_eq!(sprint.containing(date(2025, 7, 21)), (date(2025, 7, 21), date(2025, 8, 3)));
+        // Dates before the anchor fall into earlier sprints
+        assert_eq!(sprint.containing(date(2025, 7, 6)), (date(2025, 6, 23), date(2025, 7, 6)));
+        assert_eq!(sprint.nth_back(date(2025, 7, 21), 1), (date(2025, 7, 7), date(2025, 7, 20)));
+    }
+
+    #[test]
+    fn test_month_boundaries() {
+        assert_eq!(ReportingPeriod::Month.containing(date(2024, 2, 10)), (date(2024, 2, 1), date(2024, 2, 29)));
+        let fiscal = ReportingPeriod::FiscalMonth { start_day: 21 };
+        assert_eq!(fiscal.containing(date(2025, 7, 21)), (date(2025, 7, 21), date(2025, 8, 20)));
+        assert_eq!(fiscal.containing(date(2025, 1, 5)), (date(2024, 12, 21), date(2025, 1, 20)));
+        assert_eq!(ReportingPeriod::Week.nth_back(date(2025, 7, 9), 2), (date(2025, 6, 23), date(2025, 6, 29)));
+    }
+}
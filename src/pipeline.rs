@@ -0,0 +1,454 @@
+use anyhow::Result;
+use chrono::Duration;
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use tokio_util::sync::CancellationToken;
+
+use crate::analyzer::WorkAnalyzer;
+use crate::error::Error;
+use crate::filter::{FilterCoverage, TimeRangeFilter};
+use crate::machines::MachineLabels;
+use crate::models::WorkAnalysis;
+use crate::parser::JsonlParser;
+use crate::result_cache::{DataFingerprint, ResultCache};
+use crate::scanner::{ProjectPatterns, ProjectScanner};
+
+/// Overrides of the analyzer settings that the CLI flags and MCP tool arguments expose
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AnalysisTuning {
+    pub session_gap: Option<Duration>,
+    pub min_messages: Option<usize>,
+    pub include_sidechains: Option<bool>,
+    pub merge_restarts: Option<Duration>,
+    pub include_thinking: Option<bool>,
+    pub deep_topics: Option<bool>,
+}
+
+impl AnalysisTuning {
+    /// `analyzer` with the overrides that are set
+    pub fn apply(&self, mut analyzer: WorkAnalyzer) -> WorkAnalyzer {
+        if let Some(gap) = self.session_gap {
+            analyzer = analyzer.with_session_gap(gap);
+        }
+        if let Some(min_messages) = self.min_messages {
+            analyzer = analyzer.with_min_messages(min_messages);
+        }
+        if let Some(include) = self.include_sidechains {
+            analyzer = analyzer.with_sidechains(include);
+        }
+        if let Some(gap) = self.merge_restarts {
+            analyzer = analyzer.with_restart_merge(gap);
+        }
+        if let Some(include) = self.include_thinking {
+            analyzer = analyzer.with_thinking(include);
+        }
+        if let Some(deep) = self.deep_topics {
+            analyzer = analyzer.with_deep_topics(deep);
+        }
+        analyzer
+    }
+}
+
+/// What a pipeline run read to produce its analysis
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PipelineDiagnostics {
+    /// Log files found under the projects directories
+    pub files_scanned: usize,
+    /// Files skipped because they failed to parse, with the error
+    pub failed_files: Vec<(PathBuf, String)>,
+    /// Entries read again from an older file, e.g. a copied archive, and dropped
+    pub duplicate_entries: usize,
+    /// Entries the filter read and kept
+    pub coverage: FilterCoverage,
+}
+
+/// The analysis of a pipeline run and what it read
+#[derive(Clone)]
+pub struct PipelineOutput {
+    pub analysis: WorkAnalysis,
+    pub diagnostics: PipelineDiagnostics,
+}
+
+/// Scans the projects directories, parses the log files, filters their entries
+/// and folds them into sessions, one file at a time
+///
+/// This is the one path from log files to a [`WorkAnalysis`]; the CLI's JSONL
+/// storage and the MCP tools run it rather than their own loops.
+pub struct AnalysisPipeline<'a> {
+    projects_dirs: Vec<PathBuf>,
+    parser: Option<&'a JsonlParser>,
+    filter: TimeRangeFilter,
+    analyzer: WorkAnalyzer,
+    skip_failed_files: bool,
+    cancellation: CancellationToken,
+    /// Cache of earlier runs, with the key of everything but the filter
+    result_cache: Option<(&'a ResultCache, String)>,
+    machines: MachineLabels,
+    project_patterns: ProjectPatterns,
+}
+
+impl<'a> AnalysisPipeline<'a> {
+    pub fn builder() -> AnalysisPipelineBuilder<'a> {
+        AnalysisPipelineBuilder::default()
+    }
+
+    /// Analyze the entries of the log files matching the filter
+    pub async fn run(&self) -> Result<PipelineOutput> {
+        let parser = self
+            .parser
+            .map_or_else(JsonlParser::new, JsonlParser::clone)
+            .with_cancellation(self.cancellation.clone());
+        let scanner = ProjectScanner::new()
+            .with_cancellation(self.cancellation.clone())
+            .with_patterns(self.project_patterns.clone());
+        let log_files = parser.profiler().time("scan", || scanner.scan_all_projects(&self.projects_dirs))?;
+        let Some((cache, settings_key)) = &self.result_cache else {
+            return analyze_files(&parser, &log_files, &self.filter, &self.analyzer, self.skip_failed_files, &self.machines).await;
+        };
+
+        let data = DataFingerprint::of(&log_files).await;
+        let key = self.cache_key(settings_key, &data);
+        if let Some(output) = cache.get(&key, &data) {
+            return Ok(output);
+        }
+        let output =
+            analyze_files(&parser, &log_files, &self.filter, &self.analyzer, self.skip_failed_files, &self.machines).await?;
+        cache.insert(key, data, &output);
+        Ok(output)
+    }
+
+    /// Key of this run's filter in the result cache
+    ///
+    /// An end at or after the last write to the logs cuts off no entry, so
+    /// queries "until now" share the result of the first one while nothing is written.
+    fn cache_key(&self, settings_key: &str, data: &DataFingerprint) -> String {
+        let (from, to) = self.filter.get_date_range();
+        let to = to.filter(|to| data.last_modified().is_none_or(|last| *to < last));
+        format!("{}|{:?}|{:?}|{:?}", settings_key, from, to, self.filter.get_project_filter())
+    }
+}
+
+/// Builds an [`AnalysisPipeline`]; only the projects directories are needed
+#[derive(Default)]
+pub struct AnalysisPipelineBuilder<'a> {
+    projects_dirs: Vec<PathBuf>,
+    parser: Option<&'a JsonlParser>,
+    filter: Option<TimeRangeFilter>,
+    analyzer: Option<WorkAnalyzer>,
+    tuning: AnalysisTuning,
+    skip_failed_files: bool,
+    cancellation: Option<CancellationToken>,
+    result_cache: Option<(&'a ResultCache, String)>,
+    machines: MachineLabels,
+    project_patterns: ProjectPatterns,
+}
+
+impl<'a> AnalysisPipelineBuilder<'a> {
+    /// Add a projects directory to read, like `~/.claude/projects`
+    pub fn data_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.projects_dirs.push(dir.into());
+        self
+    }
+
+    pub fn data_dirs(mut self, dirs: &[PathBuf]) -> Self {
+        self.projects_dirs.extend_from_slice(dirs);
+        self
+    }
+
+    /// Parser to read the files with, e.g. one with a parse cache; a new parser otherwise
+    pub fn parser(mut self, parser: &'a JsonlParser) -> Self {
+        self.parser = Some(parser);
+        self
+    }
+
+    /// Period and project of the entries to analyze; all entries otherwise
+    pub fn filter(mut self, filter: TimeRangeFilter) -> Self {
+        self.filter = Some(filter);
+        self
+    }
+
+    /// Analyzer the tuning is applied to, e.g. [`crate::config::Config::analyzer`]
+    pub fn analyzer(mut self, analyzer: WorkAnalyzer) -> Self {
+        self.analyzer = Some(analyzer);
+        self
+    }
+
+    pub fn tuning(mut self, tuning: AnalysisTuning) -> Self {
+        self.tuning = tuning;
+        self
+    }
+
+    /// Skip the files that fail to parse, listing them in the diagnostics, instead of failing
+    pub fn skip_failed_files(mut self, skip: bool) -> Self {
+        self.skip_failed_files = skip;
+        self
+    }
+
+    /// Stop scanning, parsing and analyzing once `cancellation` is cancelled, failing
+    /// the run with [`Error::Cancelled`]
+    pub fn cancellation(mut self, cancellation: CancellationToken) -> Self {
+        self.cancellation = Some(cancellation);
+        self
+    }
+
+    /// Reuse the results of earlier runs with the same query while the logs are unchanged
+    ///
+    /// The analyzer cannot be compared, so `analyzer_key` stands for the settings it
+    /// was built with, e.g. the config and timezone. Runs sharing a cache are assumed
+    /// to use the same parser.
+    pub fn result_cache(mut self, cache: &'a ResultCache, analyzer_key: impl Into<String>) -> Self {
+        self.result_cache = Some((cache, analyzer_key.into()));
+        self
+    }
+
+    /// Break the work down by the machine each projects directory was synced from
+    pub fn machines(mut self, machines: MachineLabels) -> Self {
+        self.machines = machines;
+        self
+    }
+
+    /// Read only the project directories `patterns` select
+    pub fn project_patterns(mut self, patterns: ProjectPatterns) -> Self {
+        self.project_patterns = patterns;
+        self
+    }
+
+    /// The pipeline, with the analyzer told about the project directories to find project roots
+    ///
+    /// Without a projects directory the default `~/.claude/projects` is read.
+    pub fn build(self) -> Result<AnalysisPipeline<'a>> {
+        let projects_dirs = if self.projects_dirs.is_empty() {
+            vec![ProjectScanner::default_projects_dir().ok_or(Error::HomeDirNotFound)?]
+        } else {
+            self.projects_dirs
+        };
+        let cancellation = self.cancellation.unwrap_or_default();
+        let result_cache = self.result_cache.map(|(cache, analyzer_key)| {
+            let settings_key = format!(
+                "{:?}|{:?}|{}|{:?}|{:?}|{}",
+                projects_dirs, self.tuning, self.skip_failed_files, self.machines, self.project_patterns, analyzer_key
+            );
+            (cache, settings_key)
+        });
+        let project_dirs = ProjectScanner::new()
+            .with_patterns(self.project_patterns.clone())
+            .get_all_project_directories(&projects_dirs)?;
+        let analyzer = self
+            .tuning
+            .apply(self.analyzer.unwrap_or_default())
+            .with_project_dirs(&project_dirs)
+            .with_cancellation(cancellation.clone());
+        Ok(AnalysisPipeline {
+            projects_dirs,
+            parser: self.parser,
+            filter: self.filter.unwrap_or_default(),
+            analyzer,
+            skip_failed_files: self.skip_failed_files,
+            cancellation,
+            result_cache,
+            machines: self.machines,
+            project_patterns: self.project_patterns,
+        })
+    }
+
+    /// Build the pipeline and run it once
+    pub async fn run(self) -> Result<PipelineOutput> {
+        self.build()?.run().await
+    }
+}
+
+/// Fold the entries of `log_files` that match `filter` into sessions, one file at a time
+///
+/// With `machines`, the analysis is also broken down by machine.
+pub(crate) async fn analyze_files(
+    parser: &JsonlParser,
+    log_files: &[PathBuf],
+    filter: &TimeRangeFilter,
+    analyzer: &WorkAnalyzer,
+    skip_failed_files: bool,
+    machines: &MachineLabels,
+) -> Result<PipelineOutput> {
+    let mut diagnostics = PipelineDiagnostics { files_scanned: log_files.len(), ..Default::default() };
+    let mut seen = HashSet::new();
+    let mut session_machines = HashMap::new();
+    let mut folder = analyzer.folder();
+
+    for file_path in log_files {
+        let machine = machines.machine_of(file_path);
+        analyzer.check_cancelled()?;
+        let entries = match parser.parse_file(file_path).await {
+            Ok(entries) => entries,
+            Err(e) if skip_failed_files && !Error::is_cancellation(&e) => {
+                diagnostics.failed_files.push((file_path.clone(), e.to_string()));
+                continue;
+            }
+            Err(e) => return Err(e),
+        };
+        for entry in entries {
+            // The same entry in several files, e.g. a copied archive, is read once,
+            // from the newest file since the scanner lists those first
+            if !seen.insert(entry.uuid) {
+                diagnostics.duplicate_entries += 1;
+            } else if filter.matches_entry_with_coverage(&entry, &mut diagnostics.coverage) {
+                if !machines.is_empty() {
+                    session_machines.entry(entry.session_id).or_insert_with(|| machine.to_string());
+                }
+                folder.push(entry);
+            }
+        }
+    }
+
+    let mut analysis = folder.finish();
+    analyzer.check_cancelled()?;
+    analysis.duplicate_entries = diagnostics.duplicate_entries;
+    if !machines.is_empty() {
+        analysis.machines = Some(machines.breakdown(&analysis, &session_machines, diagnostics.duplicate_entries));
+    }
+    Ok(PipelineOutput { analysis, diagnostics })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::MessageContentVariant;
+    use crate::sample::write_sample;
+    use tempfile::TempDir;
+
+    #[tokio::test]
+    async fn test_pipeline() {
+        let temp_dir = TempDir::new().unwrap();
+        let projects_dir = temp_dir.path().join("projects");
+        write_sample(&projects_dir).unwrap();
+        let api_log = ProjectScanner::new()
+            .scan_projects(&projects_dir)
+            .unwrap()
+            .into_iter()
+            .find(|path| path.ends_with("session-1.jsonl"))
+            .unwrap();
+        std::fs::copy(&api_log, api_log.with_file_name("archived-copy.jsonl")).unwrap();
+        std::fs::write(api_log.with_file_name("broken.jsonl"), "not json\n").unwrap();
+        let strict = JsonlParser::with_strict_parsing();
+
+        let output = AnalysisPipeline::builder()
+            .data_dir(&projects_dir)
+            .parser(&strict)
+            .filter(TimeRangeFilter::new(None, None, Some("api".to_string())))
+            .tuning(AnalysisTuning { min_messages: Some(1), ..Default::default() })
+            .skip_failed_files(true)
+            .run()
+            .await
+            .unwrap();
+
+        let diagnostics = &output.diagnostics;
+        assert_eq!(diagnostics.files_scanned, 5);
+        assert_eq!(diagnostics.failed_files.len(), 1);
+        assert!(diagnostics.failed_files[0].0.ends_with("broken.jsonl"));
+        assert_eq!(diagnostics.duplicate_entries, 6);
+        assert_eq!((diagnostics.coverage.total_entries, diagnostics.coverage.matched_entries), (18, 12));
+        assert_eq!(output.analysis.total_sessions, 2);
+        assert_eq!(output.analysis.project_stats.keys().collect::<Vec<_>>(), ["api"]);
+
+        // Without skipping, the broken file fails the run
+        let failed = AnalysisPipeline::builder().data_dir(&projects_dir).parser(&strict).run().await;
+        assert!(failed.is_err());
+
+        // A cancelled run stops instead of skipping files as failed
+        let cancellation = CancellationToken::new();
+        cancellation.cancel();
+        let cancelled = AnalysisPipeline::builder()
+            .data_dir(&projects_dir)
+            .skip_failed_files(true)
+            .cancellation(cancellation)
+            .run()
+            .await;
+        assert!(Error::is_cancellation(&cancelled.err().unwrap()));
+    }
+
+    #[tokio::test]
+    async fn test_duplicates_read_from_newest_file() {
+        let temp_dir = TempDir::new().unwrap();
+        let projects_dir = temp_dir.path().join("projects");
+        write_sample(&projects_dir).unwrap();
+        let api_log = ProjectScanner::new()
+            .scan_projects(&projects_dir)
+            .unwrap()
+            .into_iter()
+            .find(|path| path.ends_with("session-1.jsonl"))
+            .unwrap();
+        // An older backup of the same entries, read from a directory listed first
+        let backup_dir = temp_dir.path().join("backup");
+        let backup_log = backup_dir.join(api_log.strip_prefix(&projects_dir).unwrap());
+        std::fs::create_dir_all(backup_log.parent().unwrap()).unwrap();
+        let stale: Vec<String> = std::fs::read_to_string(&api_log)
+            .unwrap()
+            .lines()
+            .map(|line| {
+                let mut entry: serde_json::Value = serde_json::from_str(line).unwrap();
+                entry["message"]["content"] = "stale copy".into();
+                entry.to_string()
+            })
+            .collect();
+        std::fs::write(&backup_log, stale.join("\n")).unwrap();
+        let file = std::fs::File::options().write(true).open(&backup_log).unwrap();
+        file.set_modified(std::time::UNIX_EPOCH + std::time::Duration::from_secs(86_400)).unwrap();
+
+        let output = AnalysisPipeline::builder()
+            .data_dir(&backup_dir)
+            .data_dir(&projects_dir)
+            .tuning(AnalysisTuning { min_messages: Some(1), ..Default::default() })
+            .run()
+            .await
+            .unwrap();
+
+        assert_eq!(output.diagnostics.duplicate_entries, 6);
+        assert_eq!(output.analysis.duplicate_entries, 6);
+        let stale_entries = output
+            .analysis
+            .sessions
+            .iter()
+            .flat_map(|session| &session.entries)
+            .filter(|entry| matches!(&entry.message.content, MessageContentVariant::String(text) if text == "stale copy"))
+            .count();
+        assert_eq!(stale_entries, 0);
+    }
+
+    #[tokio::test]
+    async fn test_pipeline_result_cache() {
+        let temp_dir = TempDir::new().unwrap();
+        let projects_dir = temp_dir.path().join("projects");
+        write_sample(&projects_dir).unwrap();
+        let cache = ResultCache::new(2);
+        let run = |analyzer_key: &'static str| {
+            AnalysisPipeline::builder().data_dir(&projects_dir).result_cache(&cache, analyzer_key).run()
+        };
+
+        let first = run("default").await.unwrap();
+        let repeated = run("default").await.unwrap();
+        assert_eq!(cache.len(), 1);
+        assert_eq!(repeated.analysis.total_sessions, first.analysis.total_sessions);
+        assert_eq!(repeated.diagnostics.files_scanned, 3);
+
+        // Other analyzer settings are cached separately
+        run("tuned").await.unwrap();
+        assert_eq!(cache.len(), 2);
+
+        // A new log file makes the cached result stale
+        let api_log = ProjectScanner::new()
+            .scan_projects(&projects_dir)
+            .unwrap()
+            .into_iter()
+            .find(|path| path.ends_with("session-1.jsonl"))
+            .unwrap();
+        std::fs::copy(&api_log, api_log.with_file_name("archived-copy.jsonl")).unwrap();
+        let updated = run("default").await.unwrap();
+        assert_eq!(updated.diagnostics.files_scanned, 4);
+        assert_eq!(updated.diagnostics.duplicate_entries, 6);
+        assert_eq!(cache.len(), 2);
+
+        // The least recently used result is dropped
+        run("other").await.unwrap();
+        assert_eq!(cache.len(), 2);
+        cache.clear();
+        assert!(cache.is_empty());
+    }
+}
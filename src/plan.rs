@@ -0,0 +1,219 @@
+use anyhow::{Context, Result};
+use chrono::{Datelike, Duration, FixedOffset, NaiveDate};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use crate::models::WorkAnalysis;
+
+/// Planned hours for one project in one week
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Estimate {
+    pub project: String,
+    /// Monday of the planned week
+    pub week_start: NaiveDate,
+    pub hours: f64,
+}
+
+/// Planned and actual hours for one project in one week
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlanComparison {
+    pub week_start: NaiveDate,
+    pub project: String,
+    pub planned_hours: Option<f64>,
+    pub actual_hours: f64,
+}
+
+impl PlanComparison {
+    /// Actual hours relative to the plan, e.g. 1.5 for 50% over
+    pub fn ratio(&self) -> Option<f64> {
+        self.planned_hours
+            .filter(|planned| *planned > 0.0)
+            .map(|planned| self.actual_hours / planned)
+    }
+}
+
+/// Monday of the week containing `date`
+pub fn week_start(date: NaiveDate) -> NaiveDate {
+    date - Duration::days(date.weekday().num_days_from_monday() as i64)
+}
+
+/// Estimates persisted as JSON
+pub struct PlanStore {
+    path: PathBuf,
+    estimates: Vec<Estimate>,
+}
+
+impl PlanStore {
+    /// Load the store, starting empty if the file does not exist yet
+    pub fn load(path: &Path) -> Result<Self> {
+        let estimates = if path.exists() {
+            let content = std::fs::read_to_string(path)
+                .with_context(|| format!("Failed to read plans: {}", path.display()))?;
+            serde_json::from_str(&content)
+                .with_context(|| format!("Invalid plans file: {}", path.display()))?
+        } else {
+            Vec::new()
+        };
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            estimates,
+        })
+    }
+
+    pub fn save(&self) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&self.path, serde_json::to_string_pretty(&self.estimates)?)
+            .with_context(|| format!("Failed to write plans: {}", self.path.display()))
+    }
+
+    /// Record the estimate for a project's week, replacing any earlier one
+    pub fn set_estimate(&mut self, project: &str, week: NaiveDate, hours: f64) {
+        let week_start = week_start(week);
+        self.estimates
+            .retain(|e| !(e.project == project && e.week_start == week_start));
+        self.estimates.push(Estimate {
+            project: project.to_string(),
+            week_start,
+            hours,
+        });
+        self.estimates
+            .sort_by(|a, b| a.week_start.cmp(&b.week_start).then_with(|| a.project.cmp(&b.project)));
+    }
+
+    pub fn estimates(&self) -> &[Estimate] {
+        &self.estimates
+    }
+
+    /// Planned vs actual hours per week and project, for weeks starting at or after `since`
+    ///
+    /// Projects with activity but no plan are included so unplanned work stays visible.
+    pub fn compare(&self, analysis: &WorkAnalysis, timezone: &FixedOffset, since: NaiveDate) -> Vec<PlanComparison> {
+        let since = week_start(since);
+        let mut rows: BTreeMap<(NaiveDate, String), PlanComparison> = BTreeMap::new();
+
+        for estimate in self.estimates.iter().filter(|e| e.week_start >= since) {
+            rows.insert(
+                (estimate.week_start, estimate.project.clone()),
+                PlanComparison {
+                    week_start: estimate.week_start,
+                    project: estimate.project.clone(),
+                    planned_hours: Some(estimate.hours),
+                    actual_hours: 0.0,
+                },
+            );
+        }
+
+        for session in &analysis.sessions {
+            let week = week_start(session.start_time.with_timezone(timezone).date_naive());
+            if week < since {
+                continue;
+            }
+            let project = analysis.project_name(session);
+            let hours = (session.end_time - session.start_time).num_minutes() as f64 / 60.0;
+
+            rows.entry((week, project.clone()))
+                .or_insert_with(|| PlanComparison {
+                    week_start: week,
+                    project,
+                    planned_hours: None,
+                    actual_hours: 0.0,
+                })
+                .actual_hours += hours;
+        }
+
+        rows.into_values().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::WorkSession;
+    use crate::timezone::jst;
+    use chrono::{TimeZone, Utc};
+    use std::collections::HashMap;
+    use tempfile::TempDir;
+    use uuid::Uuid;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[test]
+    fn test_week_start() {
+        assert_eq!(week_start(date(2025, 7, 9)), date(2025, 7, 7));
+        assert_eq!(week_start(date(2025, 7, 7)), date(2025, 7, 7));
+        assert_eq!(week_start(date(2025, 7, 13)), date(2025, 7, 7));
+    }
+
+    #[test]
+    fn test_store_round_trip() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("plans").join("plans.json");
+
+        let mut store = PlanStore::load(&path).unwrap();
+        store.set_estimate("api", date(2025, 7, 9), 10.0);
+        store.set_estimate("api", date(2025, 7, 10), 12.0);
+        store.save().unwrap();
+
+        let store = PlanStore::load(&path).unwrap();
+        assert_eq!(store.estimates().len(), 1);
+        assert_eq!(store.estimates()[0].week_start, date(2025, 7, 7));
+        assert_eq!(store.estimates()[0].hours, 12.0);
+    }
+
+    #[test]
+    fn test_compare_planned_and_actual() {
+        let mut store = PlanStore::load(Path::new("/nonexistent/plans.json")).unwrap();
+        store.set_estimate("api", date(2025, 7, 7), 4.0);
+
+        let start = Utc.with_ymd_and_hms(2025, 7, 8, 1, 0, 0).unwrap();
+        let session = |project: &str, minutes: i64| WorkSession {
+            session_id: Uuid::new_v4(),
+            project_path: format!("/home/user/{}", project),
+            start_time: start,
+            end_time: start + Duration::minutes(minutes),
+            entries: Vec::new(),
+            total_messages: 3,
+            user_messages: 2,
+            assistant_messages: 1,
+            summary: None,
+            merged_session_ids: Vec::new(),
+        };
+        let analysis = WorkAnalysis {
+            sessions: vec![session("api", 180), session("api", 180), session("blog", 30)],
+            project_stats: HashMap::new(),
+            time_range: (start, start),
+            total_sessions: 3,
+            total_messages: 9,
+            total_work_time: Duration::minutes(390),
+            conversation_summary: None,
+            token_analysis: None,
+            tool_usage: None,
+            code_blocks: None,
+            time_buckets: None,
+            initiatives: None,
+            commits: None,
+            log_gaps: None,
+            heatmap: None,
+            machines: None,
+            assistants: None,
+            sidechains: None,
+            budget: None,
+            duplicate_entries: 0,
+            project_names: Default::default(),
+        };
+
+        let rows = store.compare(&analysis, &jst(), date(2025, 7, 1));
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0].project, "api");
+        assert_eq!(rows[0].ratio(), Some(1.5));
+        assert_eq!(rows[1].project, "blog");
+        assert_eq!(rows[1].planned_hours, None);
+        assert_eq!(rows[1].actual_hours, 0.5);
+    }
+}
@@ -0,0 +1,156 @@
+use anyhow::{Context, Result};
+use regex::{Captures, Regex, RegexBuilder};
+use serde_json::value::RawValue;
+use std::borrow::Cow;
+
+use crate::models::{ClaudeLogEntry, MessageContentVariant};
+use crate::paths;
+
+/// Access keys of common services
+const API_KEY: &str = concat!(
+    r"\b(?:sk-ant-[A-Za-z0-9_-]{10,}|sk-[A-Za-z0-9_-]{20,}|gh[pousr]_[A-Za-z0-9]{20,}|github_pat_[A-Za-z0-9_]{20,}",
+    r"|AKIA[0-9A-Z]{16}|xox[abprs]-[A-Za-z0-9-]{10,}|AIza[0-9A-Za-z_-]{35})"
+);
+
+/// Values assigned to secret-looking names, e.g. `password=hunter2` or `"token": "abc"`
+const SECRET: &str =
+    r#"(?i)\b((?:password|passwd|secret|token|api[_-]?key|access[_-]?key|private[_-]?key)"?\s*[:=]\s*"?)[^\s"',;]+"#;
+
+const EMAIL: &str = r"\b[A-Za-z0-9._%+-]+@[A-Za-z0-9-]+(?:\.[A-Za-z0-9-]+)*\.[A-Za-z]{2,}\b";
+
+/// Absolute paths of two or more segments, after the character that starts them
+const UNIX_PATH: &str = r#"(^|[\s(\[='"`:])(/[\w.@-]+(?:/[\w.@-]+)+)"#;
+
+const WINDOWS_PATH: &str = r"\b[A-Za-z]:\\[^\s\\]+(?:\\[^\s\\]+)*";
+
+/// Strips what should not leave the machine from log entries before they are analyzed
+///
+/// Project paths become a stable `project-<hash>` so that the sessions of a
+/// project still add up, other absolute paths become `path-<hash>`, and emails,
+/// API keys, secrets and the configured patterns are replaced by a placeholder.
+#[derive(Debug, Clone)]
+pub struct Redactor {
+    /// Configured patterns, replaced by `[redacted]`
+    patterns: Vec<Regex>,
+    api_key: Regex,
+    secret: Regex,
+    email: Regex,
+    unix_path: Regex,
+    windows_path: Regex,
+}
+
+impl Redactor {
+    /// A redactor that also removes matches of `patterns`, e.g. client names
+    ///
+    /// Patterns are case-insensitive regular expressions.
+    pub fn new(patterns: &[String]) -> Result<Self> {
+        let patterns = patterns
+            .iter()
+            .map(|pattern| {
+                RegexBuilder::new(pattern)
+                    .case_insensitive(true)
+                    .build()
+                    .with_context(|| format!("Invalid redaction pattern '{}'", pattern))
+            })
+            .collect::<Result<_>>()?;
+        Ok(Self {
+            patterns,
+            api_key: Regex::new(API_KEY)?,
+            secret: Regex::new(SECRET)?,
+            email: Regex::new(EMAIL)?,
+            unix_path: Regex::new(UNIX_PATH)?,
+            windows_path: Regex::new(WINDOWS_PATH)?,
+        })
+    }
+
+    /// The name a project path is reported under
+    pub fn project(&self, project_path: &str) -> String {
+        format!("project-{}", short_hash(project_path))
+    }
+
+    /// Redact the texts, tool inputs and working directory of an entry of the project at `project_path`
+    pub fn redact_entry(&self, entry: &mut ClaudeLogEntry, project_path: &str) {
+        entry.cwd = if paths::is_within(&entry.cwd, project_path) {
+            format!("{}{}", self.project(project_path), entry.cwd.get(project_path.len()..).unwrap_or(""))
+        } else {
+            format!("path-{}", short_hash(&entry.cwd))
+        };
+        match &mut entry.message.content {
+            MessageContentVariant::String(text) => *text = self.redact_text(text, project_path),
+            MessageContentVariant::Array(blocks) => {
+                for block in blocks {
+                    for text in [&mut block.text, &mut block.thinking].into_iter().flatten() {
+                        *text = self.redact_text(text, project_path);
+                    }
+                    self.redact_raw(&mut block.input, project_path);
+                }
+            }
+        }
+        self.redact_raw(&mut entry.tool_use_result, project_path);
+    }
+
+    /// `text` with the project path, other paths, secrets and configured patterns replaced
+    pub fn redact_text(&self, text: &str, project_path: &str) -> String {
+        let mut text = text.replace(project_path, &self.project(project_path));
+        replace(&mut text, &self.api_key, "[api-key]");
+        replace(&mut text, &self.secret, "${1}[secret]");
+        replace(&mut text, &self.email, "[email]");
+        replace(&mut text, &self.unix_path, |captures: &Captures| format!("{}path-{}", &captures[1], short_hash(&captures[2])));
+        replace(&mut text, &self.windows_path, |captures: &Captures| format!("path-{}", short_hash(&captures[0])));
+        // Last, so that a pattern does not cut an email or a path apart
+        for pattern in &self.patterns {
+            replace(&mut text, pattern, "[redacted]");
+        }
+        text
+    }
+
+    /// Raw JSON redacted as text; dropped if a pattern broke its syntax
+    fn redact_raw(&self, raw: &mut Option<Box<RawValue>>, project_path: &str) {
+        if let Some(value) = raw.take() {
+            *raw = RawValue::from_string(self.redact_text(value.get(), project_path)).ok();
+        }
+    }
+}
+
+fn replace(text: &mut String, pattern: &Regex, replacement: impl regex::Replacer) {
+    if let Cow::Owned(redacted) = pattern.replace_all(text, replacement) {
+        *text = redacted;
+    }
+}
+
+/// First 8 hex digits of the 64-bit FNV-1a hash, stable across runs and versions
+fn short_hash(value: &str) -> String {
+    let hash = value.bytes().fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x0000_0100_0000_01b3)
+    });
+    format!("{:016x}", hash)[..8].to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_redact_text() {
+        let redactor = Redactor::new(&["acme(corp)?".to_string()]).unwrap();
+        let project = "/home/me/clients/acme/api";
+        let name = redactor.project(project);
+        assert!(name.starts_with("project-") && name.len() == 16);
+        assert_eq!(redactor.project(project), name);
+
+        let text = redactor.redact_text(
+            "Edit /home/me/clients/acme/api/src/main.rs for AcmeCorp, mail bob@acme.com, \
+             key sk-ant-REDACTED, password=hunter2 and see (/etc/hosts/extra) or C:\\Users\\bob\\x.txt",
+            project,
+        );
+        assert!(text.starts_with(&format!("Edit {}/src/main.rs for [redacted], mail [email], key [api-key], password=[secret]", name)));
+        assert!(text.contains(" see (path-") && text.contains(" or path-"));
+        for leaked in ["acme", "Acme", "bob", "hunter2", "etc", "Users"] {
+            assert!(!text.contains(leaked), "{} in {}", leaked, text);
+        }
+
+        // Relative paths and plain words stay
+        assert_eq!(redactor.redact_text("cargo test in src/lib.rs", project), "cargo test in src/lib.rs");
+        assert!(Redactor::new(&["(".to_string()]).is_err());
+    }
+}
@@ -0,0 +1,280 @@
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+static ALLOCATED_BYTES: AtomicU64 = AtomicU64::new(0);
+static ALLOCATIONS: AtomicU64 = AtomicU64::new(0);
+
+/// The system allocator, counting the process's allocations for [`Profiler`]
+///
+/// Allocations are only counted in binaries that install it as their `#[global_allocator]`.
+pub struct CountingAllocator;
+
+fn count_allocation(size: usize) {
+    ALLOCATED_BYTES.fetch_add(size as u64, Ordering::Relaxed);
+    ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        count_allocation(layout.size());
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        count_allocation(layout.size());
+        unsafe { System.alloc_zeroed(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        count_allocation(new_size);
+        unsafe { System.realloc(ptr, layout, new_size) }
+    }
+}
+
+/// Bytes and allocations counted by [`CountingAllocator`] so far
+fn allocation_counters() -> (u64, u64) {
+    (ALLOCATED_BYTES.load(Ordering::Relaxed), ALLOCATIONS.load(Ordering::Relaxed))
+}
+
+/// What a stage spent, not counting the stages nested in it
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct StageUsage {
+    pub calls: usize,
+    pub time: Duration,
+    pub allocated_bytes: u64,
+    pub allocations: u64,
+}
+
+impl StageUsage {
+    fn add(&mut self, other: &StageUsage) {
+        self.calls += other.calls;
+        self.time += other.time;
+        self.allocated_bytes += other.allocated_bytes;
+        self.allocations += other.allocations;
+    }
+}
+
+/// A stage being run, with what it spent since it last resumed
+struct Frame {
+    /// Enclosing stages and this one, separated by `;`
+    stack: String,
+    /// File or other item the stage works on, e.g. the file being parsed
+    item: Option<String>,
+    resumed: Instant,
+    counters: (u64, u64),
+}
+
+impl Frame {
+    /// Stack including the item, as frames of a flame graph
+    fn full_stack(&self) -> String {
+        match &self.item {
+            Some(item) => format!("{};{}", self.stack, item),
+            None => self.stack.clone(),
+        }
+    }
+}
+
+struct ProfileState {
+    started: Instant,
+    frames: Vec<Frame>,
+    /// Usage by stage stack and item
+    stages: BTreeMap<(String, Option<String>), StageUsage>,
+}
+
+impl ProfileState {
+    /// Add what the innermost stage spent since it resumed, restarting its clock
+    fn charge_innermost(&mut self, now: Instant) {
+        let Some(frame) = self.frames.last_mut() else {
+            return;
+        };
+        let counters = allocation_counters();
+        let usage = self.stages.entry((frame.stack.clone(), frame.item.clone())).or_default();
+        usage.time += now - frame.resumed;
+        usage.allocated_bytes += counters.0 - frame.counters.0;
+        usage.allocations += counters.1 - frame.counters.1;
+        frame.resumed = now;
+        frame.counters = counters;
+    }
+}
+
+/// Records the time and allocations of nested pipeline stages
+///
+/// A stage is charged only for what it spends outside the stages nested in it,
+/// so the usage adds up to the run time like the frames of a flame graph.
+/// Clones share their records; the default profiler records nothing.
+#[derive(Clone, Default)]
+pub struct Profiler {
+    state: Option<Arc<Mutex<ProfileState>>>,
+}
+
+impl Profiler {
+    /// A profiler recording from now on
+    pub fn new() -> Self {
+        Self {
+            state: Some(Arc::new(Mutex::new(ProfileState {
+                started: Instant::now(),
+                frames: Vec::new(),
+                stages: BTreeMap::new(),
+            }))),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.state.is_some()
+    }
+
+    /// Start `stage` inside the stage running now, until the guard is dropped
+    pub fn enter(&self, stage: &str) -> StageGuard {
+        self.enter_frame(stage, None)
+    }
+
+    /// Like [`Self::enter`], recording the usage of each `item` of the stage separately
+    pub fn enter_item(&self, stage: &str, item: &str) -> StageGuard {
+        self.enter_frame(stage, Some(item))
+    }
+
+    /// Run `f` as `stage`
+    pub fn time<T>(&self, stage: &str, f: impl FnOnce() -> T) -> T {
+        let _stage = self.enter(stage);
+        f()
+    }
+
+    fn enter_frame(&self, stage: &str, item: Option<&str>) -> StageGuard {
+        let Some(state) = &self.state else {
+            return StageGuard { state: None };
+        };
+        let mut locked = state.lock().unwrap_or_else(|e| e.into_inner());
+        let now = Instant::now();
+        locked.charge_innermost(now);
+        // `;` separates the frames of folded stacks
+        let stage = stage.replace(';', ":");
+        let stack = match locked.frames.last() {
+            Some(parent) => format!("{};{}", parent.full_stack(), stage),
+            None => stage,
+        };
+        locked.frames.push(Frame {
+            stack,
+            item: item.map(|item| item.replace(';', ":")),
+            resumed: now,
+            counters: allocation_counters(),
+        });
+        StageGuard { state: Some(Arc::clone(state)) }
+    }
+
+    /// Time since the profiler was created
+    pub fn elapsed(&self) -> Duration {
+        self.state
+            .as_ref()
+            .map_or(Duration::ZERO, |state| state.lock().unwrap_or_else(|e| e.into_inner()).started.elapsed())
+    }
+
+    /// Usage of each stage stack, the items of a stage added together
+    pub fn stages(&self) -> BTreeMap<String, StageUsage> {
+        let mut stages: BTreeMap<String, StageUsage> = BTreeMap::new();
+        for ((stack, _), usage) in self.records() {
+            stages.entry(stack).or_default().add(&usage);
+        }
+        stages
+    }
+
+    /// Usage of each item of the stage `stack`, the most time first
+    pub fn items(&self, stack: &str) -> Vec<(String, StageUsage)> {
+        let mut items: Vec<(String, StageUsage)> = self
+            .records()
+            .into_iter()
+            .filter_map(|((record_stack, item), usage)| Some((item.filter(|_| record_stack == stack)?, usage)))
+            .collect();
+        items.sort_by_key(|(_, usage)| std::cmp::Reverse(usage.time));
+        items
+    }
+
+    /// Microseconds per stack in the folded format of flamegraph.pl and inferno
+    pub fn folded(&self) -> String {
+        self.records()
+            .into_iter()
+            .filter(|(_, usage)| usage.time.as_micros() > 0)
+            .map(|((stack, item), usage)| match item {
+                Some(item) => format!("{};{} {}\n", stack, item, usage.time.as_micros()),
+                None => format!("{} {}\n", stack, usage.time.as_micros()),
+            })
+            .collect()
+    }
+
+    fn records(&self) -> BTreeMap<(String, Option<String>), StageUsage> {
+        self.state
+            .as_ref()
+            .map(|state| state.lock().unwrap_or_else(|e| e.into_inner()).stages.clone())
+            .unwrap_or_default()
+    }
+}
+
+/// Ends its stage when dropped, resuming the stage it was nested in
+pub struct StageGuard {
+    state: Option<Arc<Mutex<ProfileState>>>,
+}
+
+impl Drop for StageGuard {
+    fn drop(&mut self) {
+        let Some(state) = &self.state else {
+            return;
+        };
+        let mut locked = state.lock().unwrap_or_else(|e| e.into_inner());
+        let now = Instant::now();
+        locked.charge_innermost(now);
+        if let Some(frame) = locked.frames.pop() {
+            locked.stages.entry((frame.stack, frame.item)).or_default().calls += 1;
+        }
+        if let Some(parent) = locked.frames.last_mut() {
+            parent.resumed = now;
+            parent.counters = allocation_counters();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_nested_stages() {
+        let profiler = Profiler::new();
+        profiler.time("sessionize", || {
+            std::thread::sleep(Duration::from_millis(5));
+            profiler.time("message analysis", || std::thread::sleep(Duration::from_millis(20)));
+        });
+        profiler.time("sessionize", || ());
+        for file in ["a.jsonl", "b;c.jsonl"] {
+            let _parse = profiler.enter_item("parse", file);
+        }
+
+        let stages = profiler.stages();
+        assert_eq!(stages.keys().collect::<Vec<_>>(), ["parse", "sessionize", "sessionize;message analysis"]);
+        assert_eq!(stages["sessionize"].calls, 2);
+        assert_eq!(stages["parse"].calls, 2);
+        // Sessionizing is not charged for the analysis nested in it
+        assert!(stages["sessionize"].time < Duration::from_millis(20));
+        assert!(stages["sessionize;message analysis"].time >= Duration::from_millis(20));
+        assert!(profiler.elapsed() >= Duration::from_millis(25));
+
+        let items: Vec<String> = profiler.items("parse").into_iter().map(|(item, _)| item).collect();
+        assert_eq!(items.len(), 2);
+        assert!(items.contains(&"b:c.jsonl".to_string()));
+        assert!(profiler.folded().lines().any(|line| line.starts_with("sessionize;message analysis ")));
+    }
+
+    #[test]
+    fn test_disabled_profiler() {
+        let profiler = Profiler::default();
+        assert_eq!(profiler.time("scan", || 42), 42);
+        assert!(!profiler.is_enabled());
+        assert!(profiler.stages().is_empty());
+        assert_eq!(profiler.folded(), "");
+    }
+}
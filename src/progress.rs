@@ -0,0 +1,141 @@
+use indicatif::{ProgressBar, ProgressDrawTarget, ProgressFinish, ProgressStyle};
+use std::io::Write;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+const SCANNING_TEMPLATE: &str = "{spinner} Scanning log directories";
+const READING_TEMPLATE: &str = "{spinner} Reading logs [{bar:30}] {pos}/{len} files, {msg}";
+
+/// Progress of reading the log files, drawn on stderr while a command loads them
+///
+/// Readers call [`Self::start`] with the number of files they are about to read
+/// and the parser counts each file it reads; the bar clears itself after the
+/// last one, or when the last clone is dropped, e.g. after reading failed.
+/// Clones share one bar. Nothing is drawn when stderr is not a terminal, and
+/// the default progress draws nothing at all.
+#[derive(Clone)]
+pub struct ParseProgress {
+    bar: ProgressBar,
+    entries: Arc<AtomicUsize>,
+}
+
+impl ParseProgress {
+    /// A bar on stderr, hidden until files are scanned or read
+    pub fn new() -> Self {
+        Self::with_draw_target(ProgressDrawTarget::stderr())
+    }
+
+    fn with_draw_target(target: ProgressDrawTarget) -> Self {
+        let bar = ProgressBar::with_draw_target(None, target).with_finish(ProgressFinish::AndClear);
+        // Files read before anything is started are not counted
+        bar.finish_and_clear();
+        Self { bar, entries: Arc::new(AtomicUsize::new(0)) }
+    }
+
+    /// Show a spinner while the log directories are listed
+    pub fn scanning(&self) {
+        self.bar.set_style(ProgressStyle::with_template(SCANNING_TEMPLATE).unwrap());
+        self.bar.reset();
+        self.bar.enable_steady_tick(Duration::from_millis(100));
+    }
+
+    /// Count `files` about to be read, starting from none
+    pub fn start(&self, files: usize) {
+        self.entries.store(0, Ordering::Relaxed);
+        self.bar.set_message("0 entries");
+        self.bar.set_length(files as u64);
+        self.bar.set_style(ProgressStyle::with_template(READING_TEMPLATE).unwrap().progress_chars("=> "));
+        self.bar.reset();
+        if files == 0 {
+            self.bar.finish_and_clear();
+        }
+    }
+
+    /// A file was read, holding `entries`
+    pub fn file_read(&self, entries: usize) {
+        if self.bar.is_finished() {
+            return;
+        }
+        let total = self.entries.fetch_add(entries, Ordering::Relaxed) + entries;
+        self.bar.set_message(format!("{} entries", total));
+        self.bar.inc(1);
+        if self.bar.length().is_some_and(|files| self.bar.position() >= files) {
+            self.bar.finish_and_clear();
+        }
+    }
+
+    /// Files read since [`Self::start`]
+    pub fn files_read(&self) -> u64 {
+        self.bar.position()
+    }
+
+    /// Entries in the files read since [`Self::start`]
+    pub fn entries_read(&self) -> usize {
+        self.entries.load(Ordering::Relaxed)
+    }
+
+    /// Stderr for log lines, taking the bar off the screen while writing so
+    /// that it does not draw over them
+    ///
+    /// The writers do not keep the bar alive, it is still cleared when dropped.
+    pub fn stderr(&self) -> impl Fn() -> ProgressStderr + Send + Sync + 'static {
+        let bar = self.bar.downgrade();
+        move || ProgressStderr(bar.upgrade())
+    }
+}
+
+impl Default for ParseProgress {
+    fn default() -> Self {
+        Self::with_draw_target(ProgressDrawTarget::hidden())
+    }
+}
+
+/// See [`ParseProgress::stderr`]
+pub struct ProgressStderr(Option<ProgressBar>);
+
+impl Write for ProgressStderr {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        match &self.0 {
+            Some(bar) => bar.suspend(|| std::io::stderr().write(buf)),
+            None => std::io::stderr().write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        std::io::stderr().flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::JsonlParser;
+    use crate::sample::write_sample;
+    use crate::scanner::ProjectScanner;
+    use tempfile::TempDir;
+
+    #[tokio::test]
+    async fn test_parse_progress() {
+        let temp_dir = TempDir::new().unwrap();
+        write_sample(temp_dir.path()).unwrap();
+        let files = ProjectScanner::new().scan_projects(temp_dir.path()).unwrap();
+        let progress = ParseProgress::default();
+        let parser = JsonlParser::new().with_progress(progress.clone());
+
+        // Nothing is counted before a start
+        parser.parse_file(&files[0]).await.unwrap();
+        assert_eq!(progress.files_read(), 0);
+
+        progress.start(files.len());
+        let entries = parser.parse_files(&files).await.unwrap();
+        assert_eq!(progress.files_read(), files.len() as u64);
+        assert_eq!(progress.entries_read(), entries.len());
+
+        // Reading more files than were started does not count on
+        parser.parse_file(&files[0]).await.unwrap();
+        assert_eq!(progress.files_read(), files.len() as u64);
+        progress.start(1);
+        assert_eq!((progress.files_read(), progress.entries_read()), (0, 0));
+    }
+}
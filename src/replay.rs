@@ -0,0 +1,191 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use std::str::FromStr;
+use uuid::Uuid;
+
+use crate::models::{ClaudeLogEntry, EntryType, MessageContentVariant};
+use crate::timezone::TimezoneSchedule;
+
+const RESET: &str = "\x1b[0m";
+const DIM: &str = "\x1b[2m";
+
+/// How much faster than it happened a session is replayed, e.g. `10x`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ReplaySpeed(f64);
+
+impl ReplaySpeed {
+    pub fn factor(&self) -> f64 {
+        self.0
+    }
+}
+
+impl Default for ReplaySpeed {
+    fn default() -> Self {
+        Self(1.0)
+    }
+}
+
+impl FromStr for ReplaySpeed {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let number = s.trim().trim_end_matches(['x', 'X']);
+        match number.parse::<f64>() {
+            Ok(factor) if factor.is_finite() && factor > 0.0 => Ok(Self(factor)),
+            _ => Err(anyhow::anyhow!("Invalid speed '{}'. Expected a positive factor like 10x or 0.5x", s)),
+        }
+    }
+}
+
+/// Who a turn of the conversation is from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Role {
+    User,
+    Assistant,
+    /// Tools the assistant called
+    Tool,
+}
+
+impl Role {
+    fn label(&self) -> &'static str {
+        match self {
+            Role::User => "User",
+            Role::Assistant => "Assistant",
+            Role::Tool => "Tool",
+        }
+    }
+
+    /// Bold cyan, green and yellow
+    fn color(&self) -> &'static str {
+        match self {
+            Role::User => "\x1b[1;36m",
+            Role::Assistant => "\x1b[1;32m",
+            Role::Tool => "\x1b[33m",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReplayTurn {
+    pub at: DateTime<Utc>,
+    pub role: Role,
+    pub text: String,
+}
+
+/// The turns of a session's main conversation in the order they were written
+///
+/// Tool results and empty messages have nothing to show and are left out; the
+/// tools the assistant called become a turn of their own.
+pub fn replay_turns(entries: &[ClaudeLogEntry]) -> Vec<ReplayTurn> {
+    let mut entries: Vec<&ClaudeLogEntry> = entries.iter().filter(|entry| !entry.is_sidechain).collect();
+    entries.sort_by_key(|entry| entry.timestamp);
+
+    let mut turns = Vec::new();
+    for entry in entries {
+        let role = match entry.entry_type {
+            EntryType::User => Role::User,
+            EntryType::Assistant => Role::Assistant,
+        };
+        let text = entry.message.content.text();
+        if !text.trim().is_empty() {
+            turns.push(ReplayTurn { at: entry.timestamp, role, text: text.trim().to_string() });
+        }
+        if let MessageContentVariant::Array(blocks) = &entry.message.content {
+            let tools: Vec<&str> = blocks
+                .iter()
+                .filter(|block| block.content_type == "tool_use")
+                .filter_map(|block| block.name.as_deref())
+                .collect();
+            if !tools.is_empty() {
+                turns.push(ReplayTurn { at: entry.timestamp, role: Role::Tool, text: tools.join(", ") });
+            }
+        }
+    }
+    turns
+}
+
+/// How long to wait before showing a turn written `gap` after the previous one,
+/// at most `max_delay`
+pub fn replay_delay(gap: chrono::Duration, speed: ReplaySpeed, max_delay: std::time::Duration) -> std::time::Duration {
+    let seconds = gap.num_milliseconds().max(0) as f64 / 1000.0 / speed.factor();
+    std::time::Duration::from_secs_f64(seconds).min(max_delay)
+}
+
+/// A turn as printed: its local time and role, then its text indented
+pub fn format_turn(turn: &ReplayTurn, schedule: &TimezoneSchedule, color: bool) -> String {
+    let time = schedule.to_local(turn.at).format("%H:%M:%S");
+    let header = if color {
+        format!("{}[{}]{} {}{}{}", DIM, time, RESET, turn.role.color(), turn.role.label(), RESET)
+    } else {
+        format!("[{}] {}", time, turn.role.label())
+    };
+    let body: Vec<String> = turn.text.lines().map(|line| format!("  {}", line)).collect();
+    format!("{}\n{}\n", header, body.join("\n"))
+}
+
+/// The log session `id` names, a full session ID or the start of one
+pub fn find_session(entries: &[ClaudeLogEntry], id: &str) -> Result<Uuid> {
+    let id = id.trim().to_lowercase();
+    let mut matches: Vec<Uuid> = entries
+        .iter()
+        .map(|entry| entry.session_id)
+        .filter(|session_id| session_id.to_string().starts_with(&id))
+        .collect();
+    matches.sort();
+    matches.dedup();
+    match matches.as_slice() {
+        [session_id] => Ok(*session_id),
+        [] => Err(anyhow::anyhow!("No session with ID '{}'; `sessions --format csv` lists them", id)),
+        _ => Err(anyhow::anyhow!("Session ID '{}' is ambiguous, it starts {} sessions", id, matches.len())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::JsonlParser;
+    use crate::sample::write_sample;
+    use crate::scanner::ProjectScanner;
+    use chrono::FixedOffset;
+    use tempfile::TempDir;
+
+    #[tokio::test]
+    async fn test_replay_session() {
+        let temp_dir = TempDir::new().unwrap();
+        write_sample(temp_dir.path()).unwrap();
+        let files = ProjectScanner::new().scan_projects(temp_dir.path()).unwrap();
+        let entries = JsonlParser::new().parse_files(&files).await.unwrap();
+
+        let session_id = entries[0].session_id;
+        assert_eq!(find_session(&entries, &session_id.to_string()[..8]).unwrap(), session_id);
+        assert!(find_session(&entries, "zzzz").is_err());
+        // Every session ID starts with the empty prefix
+        assert!(find_session(&entries, "").unwrap_err().to_string().contains("ambiguous"));
+
+        let session: Vec<ClaudeLogEntry> = entries.iter().filter(|entry| entry.session_id == session_id).cloned().collect();
+        let turns = replay_turns(&session);
+        assert!(!turns.is_empty());
+        assert!(turns.windows(2).all(|pair| pair[0].at <= pair[1].at));
+        assert_eq!(turns[0].role, Role::User);
+
+        let schedule = TimezoneSchedule::fixed(FixedOffset::east_opt(0).unwrap());
+        let plain = format_turn(&turns[0], &schedule, false);
+        assert!(plain.starts_with(&format!("[{}] User\n  ", turns[0].at.format("%H:%M:%S"))));
+        assert!(!plain.contains('\x1b'));
+        assert!(format_turn(&turns[0], &schedule, true).contains("\x1b[1;36mUser"));
+    }
+
+    #[test]
+    fn test_replay_speed() {
+        assert_eq!("10x".parse::<ReplaySpeed>().unwrap().factor(), 10.0);
+        assert_eq!("0.5".parse::<ReplaySpeed>().unwrap().factor(), 0.5);
+        assert!("0x".parse::<ReplaySpeed>().is_err());
+        assert!("fast".parse::<ReplaySpeed>().is_err());
+
+        let max = std::time::Duration::from_secs(5);
+        let speed = ReplaySpeed(10.0);
+        assert_eq!(replay_delay(chrono::Duration::seconds(30), speed, max), std::time::Duration::from_secs(3));
+        assert_eq!(replay_delay(chrono::Duration::minutes(30), speed, max), max);
+        assert_eq!(replay_delay(chrono::Duration::seconds(-1), speed, max), std::time::Duration::ZERO);
+    }
+}
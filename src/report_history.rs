@@ -0,0 +1,241 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration, FixedOffset, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use crate::models::{TimeGrouping, WorkAnalysis};
+
+/// Headline metrics of one generated report
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReportRecord {
+    pub generated_at: DateTime<Utc>,
+    /// First and last session of the report, none without sessions
+    pub time_range: Option<(DateTime<Utc>, DateTime<Utc>)>,
+    /// Project filter the report was generated with
+    pub project: Option<String>,
+    pub sessions: usize,
+    pub messages: usize,
+    pub work_minutes: i64,
+    pub tokens: u64,
+    pub estimated_cost: f64,
+}
+
+/// Work of one local day as the latest report covering it saw it
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct DayRecord {
+    pub sessions: usize,
+    pub messages: usize,
+    pub work_minutes: i64,
+    pub tokens: u64,
+    pub estimated_cost: f64,
+}
+
+/// Recorded work of one day, week or month
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecordedPeriod {
+    /// First local day of the period
+    pub start: NaiveDate,
+    /// Days of the period with recorded activity
+    pub active_days: usize,
+    pub sessions: usize,
+    pub messages: usize,
+    pub work_time: Duration,
+    pub tokens: u64,
+    pub estimated_cost: f64,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct HistoryFile {
+    reports: Vec<ReportRecord>,
+    days: BTreeMap<NaiveDate, DayRecord>,
+}
+
+/// Metrics of every generated report, persisted as JSON
+///
+/// Besides the per-report headlines, the history keeps per-day totals of the
+/// reports over all projects, so the hours and cost of days whose logs have
+/// since been deleted stay available.
+pub struct ReportHistory {
+    path: PathBuf,
+    file: HistoryFile,
+}
+
+impl ReportHistory {
+    /// `<data dir>/claude-work-analysis/report_history.json`
+    pub fn default_path() -> Option<PathBuf> {
+        dirs::data_dir().map(|dir| dir.join("claude-work-analysis").join("report_history.json"))
+    }
+
+    /// Load the history, starting empty if the file does not exist yet
+    pub fn load(path: &Path) -> Result<Self> {
+        let file = if path.exists() {
+            let content = std::fs::read_to_string(path)
+                .with_context(|| format!("Failed to read report history: {}", path.display()))?;
+            serde_json::from_str(&content)
+                .with_context(|| format!("Invalid report history file: {}", path.display()))?
+        } else {
+            HistoryFile::default()
+        };
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            file,
+        })
+    }
+
+    pub fn save(&self) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&self.path, serde_json::to_string(&self.file)?)
+            .with_context(|| format!("Failed to write report history: {}", self.path.display()))
+    }
+
+    /// Record a report generated at `now`
+    ///
+    /// Reports over all projects also replace the totals of the days they have
+    /// sessions on; days without sessions keep what earlier reports recorded.
+    pub fn record(&mut self, analysis: &WorkAnalysis, timezone: &FixedOffset, project: Option<&str>, now: DateTime<Utc>) {
+        let tokens = analysis.token_analysis.as_ref().map(|tokens| tokens.stats_by_session()).unwrap_or_default();
+        let mut days: BTreeMap<NaiveDate, DayRecord> = BTreeMap::new();
+        for session in &analysis.sessions {
+            let day = days.entry(session.start_time.with_timezone(timezone).date_naive()).or_default();
+            day.sessions += 1;
+            day.messages += session.total_messages;
+            day.work_minutes += (session.end_time - session.start_time).num_minutes();
+            if let Some(stats) = tokens.get(&(session.session_id, session.start_time)) {
+                day.tokens += stats.usage.total();
+                day.estimated_cost += stats.estimated_cost;
+            }
+        }
+
+        self.file.reports.push(ReportRecord {
+            generated_at: now,
+            time_range: (analysis.total_sessions > 0).then_some(analysis.time_range),
+            project: project.map(str::to_string),
+            sessions: analysis.total_sessions,
+            messages: analysis.total_messages,
+            work_minutes: analysis.total_work_time.num_minutes(),
+            tokens: days.values().map(|day| day.tokens).sum(),
+            estimated_cost: days.values().map(|day| day.estimated_cost).sum(),
+        });
+        if project.is_none() {
+            self.file.days.extend(days);
+        }
+    }
+
+    pub fn reports(&self) -> &[ReportRecord] {
+        &self.file.reports
+    }
+
+    /// Recorded days merged into the periods of `grouping`, oldest first
+    pub fn periods(&self, grouping: TimeGrouping) -> Vec<RecordedPeriod> {
+        let mut periods: BTreeMap<NaiveDate, RecordedPeriod> = BTreeMap::new();
+        for (date, day) in &self.file.days {
+            let start = grouping.bucket_start(*date);
+            let period = periods.entry(start).or_insert_with(|| RecordedPeriod {
+                start,
+                active_days: 0,
+                sessions: 0,
+                messages: 0,
+                work_time: Duration::zero(),
+                tokens: 0,
+                estimated_cost: 0.0,
+            });
+            period.active_days += 1;
+            period.sessions += day.sessions;
+            period.messages += day.messages;
+            period.work_time += Duration::minutes(day.work_minutes);
+            period.tokens += day.tokens;
+            period.estimated_cost += day.estimated_cost;
+        }
+        periods.into_values().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::WorkSession;
+    use chrono::TimeZone;
+    use std::collections::HashMap;
+    use tempfile::TempDir;
+    use uuid::Uuid;
+
+    fn session(day: u32, minutes: i64) -> WorkSession {
+        let start_time = Utc.with_ymd_and_hms(2025, 7, day, 9, 0, 0).unwrap();
+        WorkSession {
+            session_id: Uuid::new_v4(),
+            project_path: "/home/me/api".to_string(),
+            start_time,
+            end_time: start_time + Duration::minutes(minutes),
+            entries: Vec::new(),
+            total_messages: 4,
+            user_messages: 2,
+            assistant_messages: 2,
+            summary: None,
+            merged_session_ids: Vec::new(),
+        }
+    }
+
+    fn analysis(sessions: Vec<WorkSession>) -> WorkAnalysis {
+        WorkAnalysis {
+            total_sessions: sessions.len(),
+            total_messages: sessions.iter().map(|session| session.total_messages).sum(),
+            total_work_time: sessions.iter().fold(Duration::zero(), |total, session| total + (session.end_time - session.start_time)),
+            time_range: (
+                sessions.first().map_or_else(Utc::now, |session| session.start_time),
+                sessions.last().map_or_else(Utc::now, |session| session.end_time),
+            ),
+            project_stats: HashMap::new(),
+            sessions,
+            conversation_summary: None,
+            tool_usage: None,
+            code_blocks: None,
+            token_analysis: None,
+            time_buckets: None,
+            initiatives: None,
+            commits: None,
+            log_gaps: None,
+            heatmap: None,
+            machines: None,
+            assistants: None,
+            sidechains: None,
+            budget: None,
+            duplicate_entries: 0,
+            project_names: Default::default(),
+        }
+    }
+
+    #[test]
+    fn test_days_outlive_the_logs() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("history.json");
+        let utc = FixedOffset::east_opt(0).unwrap();
+        let now = Utc.with_ymd_and_hms(2025, 7, 20, 0, 0, 0).unwrap();
+
+        let mut history = ReportHistory::load(&path).unwrap();
+        history.record(&analysis(vec![session(7, 60), session(8, 30), session(14, 90)]), &utc, None, now);
+        history.save().unwrap();
+
+        // The logs of the first week are gone by the next report
+        let mut history = ReportHistory::load(&path).unwrap();
+        history.record(&analysis(vec![session(14, 120)]), &utc, None, now);
+        // Project reports do not stand for a whole day's work
+        history.record(&analysis(vec![session(15, 10)]), &utc, Some("api"), now);
+        history.save().unwrap();
+
+        let history = ReportHistory::load(&path).unwrap();
+        assert_eq!(history.reports().len(), 3);
+        assert_eq!(history.reports()[1].work_minutes, 120);
+        assert_eq!(history.reports()[2].project.as_deref(), Some("api"));
+
+        let weeks = history.periods(TimeGrouping::Week);
+        assert_eq!(weeks.len(), 2);
+        assert_eq!(weeks[0].start, NaiveDate::from_ymd_opt(2025, 7, 7).unwrap());
+        assert_eq!((weeks[0].active_days, weeks[0].sessions, weeks[0].work_time), (2, 2, Duration::minutes(90)));
+        assert_eq!((weeks[1].sessions, weeks[1].work_time), (1, Duration::minutes(120)));
+    }
+}
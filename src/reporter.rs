@@ -1,14 +1,192 @@
 use anyhow::Result;
-use chrono::{Timelike, TimeZone, FixedOffset};
-use std::collections::HashMap;
+use chrono::{DateTime, Datelike, Duration, Timelike, FixedOffset, NaiveDate, Utc, Weekday};
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap};
+use std::str::FromStr;
 
-use crate::models::WorkAnalysis;
+use crate::aggregate::AggregateExport;
+use crate::budget::BudgetStatus;
+use crate::bundle::BundleManifest;
+use crate::compare::PeriodComparison;
+use crate::daily_summary::WorkSummary;
+use crate::filter::{FilterCoverage, TimeRangeFilter};
+use crate::health::{CoverageGap, DataHealth, LogGaps};
+use crate::insights::InsightRules;
+use crate::plan::PlanComparison;
+use crate::profile::{Profiler, StageUsage};
+use crate::report_history::RecordedPeriod;
+use crate::models::{
+    confidence, ActivityHeatmap, AssistantStats, CodeBlockStats, CodeBlockSummary, FollowUpStats, InitiativeRollup, MachineBreakdown, ProjectStats, Recommendation, SessionCommits, SessionOrder, SidechainSummary, TimeBuckets, TimeGrouping, TokenAnalysis, TokenStats, ToolStats, ToolUsageSummary, WorkAnalysis,
+    WorkSession,
+};
+use crate::parser::ContentLevel;
+use crate::paths;
+use crate::similarity::SimilarSession;
+use crate::standup::Standup;
+use crate::current_project::CurrentProject;
+use crate::eval::{Evaluation, Miss};
+use crate::weekly::WeeklyReport;
+use crate::storage::ActivityRollup;
+use crate::template::{PeriodContext, ProjectContext, ReportContext, ReportTemplate, SectionContext, SessionContext, TotalsContext};
+use crate::timezone::{jst, timezone_label, TimezoneSchedule};
+
+/// Width of the bar of the period with the most hours in the recorded history
+const HISTORY_BAR_WIDTH: usize = 20;
+
+/// Commits listed per session in the commits section
+const MAX_COMMITS_PER_SESSION: usize = 10;
+
+/// Sessions listed per order in the top sessions section
+const TOP_SESSIONS: usize = 5;
+
+/// Files listed in the slowest files of a profile
+const SLOWEST_FILES: usize = 5;
+
+/// Shades of the project × week heatmap besides the empty one
+const HEATMAP_LEVELS: i64 = 4;
+/// Discussions a technology needs to be listed with its follow-up prompts
+const MIN_FOLLOW_UP_DISCUSSIONS: usize = 2;
+/// Languages that get their own column in the languages over time table
+const LANGUAGE_COLUMNS: usize = 5;
+/// Characters of a project or topic name in the compact report, longer ones are cut
+const COMPACT_NAME_WIDTH: usize = 24;
+
+/// Rows of the weekday × hour heatmap
+const WEEKDAYS: [Weekday; 7] =
+    [Weekday::Mon, Weekday::Tue, Weekday::Wed, Weekday::Thu, Weekday::Fri, Weekday::Sat, Weekday::Sun];
+
+/// Sections of the markdown report, in the order they appear
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ReportSection {
+    Summary,
+    Projects,
+    /// Work per initiative, shown when initiatives are configured
+    Initiatives,
+    /// Work per machine, shown when the config file labels machine directories
+    Machines,
+    /// Work per assistant, shown when history of other assistants was imported
+    Assistants,
+    Activity,
+    Time,
+    /// Per day, week or month aggregates, shown with `--group-by`
+    GroupBy,
+    Tools,
+    /// Work delegated to subagents (sidechain conversations)
+    Subagents,
+    /// Code blocks in the assistant's responses, by language and project
+    CodeBlocks,
+    Tokens,
+    Conversations,
+    Sessions,
+    /// Longest, most message-heavy and most token-heavy sessions
+    TopSessions,
+    /// Commits made during or right after each session, with `--git`
+    Commits,
+    Insights,
+}
+
+impl ReportSection {
+    pub const ALL: [ReportSection; 17] = [
+        ReportSection::Summary,
+        ReportSection::Projects,
+        ReportSection::Initiatives,
+        ReportSection::Machines,
+        ReportSection::Assistants,
+        ReportSection::Activity,
+        ReportSection::Time,
+        ReportSection::GroupBy,
+        ReportSection::Tools,
+        ReportSection::Subagents,
+        ReportSection::CodeBlocks,
+        ReportSection::Tokens,
+        ReportSection::Conversations,
+        ReportSection::Sessions,
+        ReportSection::TopSessions,
+        ReportSection::Commits,
+        ReportSection::Insights,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ReportSection::Summary => "summary",
+            ReportSection::Projects => "projects",
+            ReportSection::Initiatives => "initiatives",
+            ReportSection::Machines => "machines",
+            ReportSection::Assistants => "assistants",
+            ReportSection::Activity => "activity",
+            ReportSection::Time => "time",
+            ReportSection::GroupBy => "group-by",
+            ReportSection::Tools => "tools",
+            ReportSection::Subagents => "subagents",
+            ReportSection::CodeBlocks => "code-blocks",
+            ReportSection::Tokens => "tokens",
+            ReportSection::Conversations => "conversations",
+            ReportSection::Sessions => "sessions",
+            ReportSection::TopSessions => "top-sessions",
+            ReportSection::Commits => "commits",
+            ReportSection::Insights => "insights",
+        }
+    }
+}
+
+impl ReportSection {
+    /// Message content the section reads, beyond timestamps, projects and token usage
+    pub fn content_level(&self) -> ContentLevel {
+        match self {
+            // Tool calls and results, and the thinking volume in the summary
+            ReportSection::Summary | ReportSection::Tools | ReportSection::Subagents => ContentLevel::Outline,
+            // Activity types, topics, session summaries, initiative keywords and response text
+            ReportSection::Projects
+            | ReportSection::Initiatives
+            | ReportSection::Activity
+            | ReportSection::CodeBlocks
+            | ReportSection::Conversations
+            | ReportSection::Sessions
+            | ReportSection::Insights => ContentLevel::Full,
+            ReportSection::Machines
+            | ReportSection::Assistants
+            | ReportSection::Time
+            | ReportSection::GroupBy
+            | ReportSection::Tokens
+            | ReportSection::TopSessions
+            | ReportSection::Commits => ContentLevel::Metadata,
+        }
+    }
+}
+
+impl FromStr for ReportSection {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let name = s.to_lowercase();
+        ReportSection::ALL
+            .into_iter()
+            .find(|section| section.name() == name)
+            .ok_or_else(|| {
+                anyhow::anyhow!(
+                    "Invalid report section '{}'. Expected summary, projects, initiatives, machines, assistants, activity, time, group-by, tools, subagents, code-blocks, tokens, conversations, sessions, top-sessions, commits or insights",
+                    s
+                )
+            })
+    }
+}
 
 pub struct ReportGenerator {
     /// Include detailed session information in reports
     include_session_details: bool,
     /// Maximum number of sessions to detail in reports
     max_detailed_sessions: usize,
+    /// Timezone(s) used to render timestamps and bucket days
+    timezone: TimezoneSchedule,
+    /// Sections of the markdown report to render
+    sections: Vec<ReportSection>,
+    /// Layout of the markdown report
+    template: ReportTemplate,
+    /// Activities, problems and recommendations less certain than this are hidden
+    min_confidence: f64,
+    /// When each recommendation is given
+    insights: InsightRules,
 }
 
 impl ReportGenerator {
@@ -16,6 +194,11 @@ impl ReportGenerator {
         Self {
             include_session_details: true,
             max_detailed_sessions: 10,
+            timezone: TimezoneSchedule::fixed(jst()),
+            sections: ReportSection::ALL.to_vec(),
+            template: ReportTemplate::default(),
+            min_confidence: 0.0,
+            insights: InsightRules::default(),
         }
     }
 
@@ -29,64 +212,830 @@ impl ReportGenerator {
         self
     }
 
-    /// Generate a comprehensive markdown report
+    pub fn with_timezone(mut self, timezone: FixedOffset) -> Self {
+        self.timezone = TimezoneSchedule::fixed(timezone);
+        self
+    }
+
+    /// Render each timestamp in the timezone in effect at that time
+    pub fn with_timezone_schedule(mut self, schedule: TimezoneSchedule) -> Self {
+        self.timezone = schedule;
+        self
+    }
+
+    /// Render only these sections of the markdown report; the header is always shown
+    pub fn with_sections(mut self, sections: Vec<ReportSection>) -> Self {
+        self.sections = sections;
+        self
+    }
+
+    /// Lay the markdown report out with this template instead of the built-in one
+    pub fn with_template(mut self, template: ReportTemplate) -> Self {
+        self.template = template;
+        self
+    }
+
+    /// Hide activity types, problems and recommendations with a lower confidence, between 0 and 1
+    pub fn with_min_confidence(mut self, min_confidence: f64) -> Self {
+        self.min_confidence = min_confidence;
+        self
+    }
+
+    /// Give recommendations by these thresholds instead of the built-in ones
+    pub fn with_insight_rules(mut self, insights: InsightRules) -> Self {
+        self.insights = insights;
+        self
+    }
+
+    /// Whether an item is confident enough to show; items analyzed without a confidence always are
+    fn confident(&self, confidence: Option<f64>) -> bool {
+        confidence.is_none_or(|confidence| confidence >= self.min_confidence)
+    }
+
+    fn shows(&self, section: ReportSection) -> bool {
+        self.sections.contains(&section)
+    }
+
+    /// Message content the Markdown report reads with the sections shown
+    pub fn markdown_content_level(&self) -> ContentLevel {
+        self.sections
+            .iter()
+            .filter(|section| self.include_session_details || **section != ReportSection::Sessions)
+            .map(ReportSection::content_level)
+            .max()
+            .unwrap_or(ContentLevel::Metadata)
+    }
+
+    /// Generate a comprehensive markdown report, laid out by the report template
     pub fn generate_markdown_report(&self, analysis: &WorkAnalysis) -> Result<String> {
-        let mut report = String::new();
+        self.template.render(&self.report_context(analysis))
+    }
+
+    /// What the report template is rendered with for `analysis`
+    pub fn report_context(&self, analysis: &WorkAnalysis) -> ReportContext {
+        let (start, end) = analysis.time_range;
+        let hours = |time: Duration| (time.num_minutes() as f64 / 6.0).round() / 10.0;
+        let projects = self
+            .projects_by_work_time(analysis)
+            .into_iter()
+            .map(|stats| ProjectContext {
+                name: stats.project_name.clone(),
+                sessions: stats.total_sessions,
+                messages: stats.total_messages,
+                work_hours: hours(stats.work_time),
+                topics: stats.topic_analysis.as_ref().map(|topics| topics.primary_topics.clone()).unwrap_or_default(),
+            })
+            .collect();
+        let mut sessions: Vec<&WorkSession> = analysis.sessions.iter().collect();
+        sessions.sort_by_key(|session| session.start_time);
+        let sessions = sessions
+            .into_iter()
+            .map(|session| SessionContext {
+                id: session.session_id.to_string(),
+                project: paths::last_segment(&session.project_path).unwrap_or("Unknown").to_string(),
+                start: self.timezone.to_local(session.start_time).format("%Y-%m-%d %H:%M").to_string(),
+                end: self.timezone.to_local(session.end_time).format("%Y-%m-%d %H:%M").to_string(),
+                duration_minutes: (session.end_time - session.start_time).num_minutes(),
+                messages: session.total_messages,
+                summary: session.summary.as_ref().map(|summary| summary.overall_summary.clone()),
+            })
+            .collect();
+
+        ReportContext {
+            header: self.generate_header(analysis),
+            period: PeriodContext {
+                start: self.timezone.to_local(start).format("%Y-%m-%d %H:%M").to_string(),
+                end: self.timezone.to_local(end).format("%Y-%m-%d %H:%M").to_string(),
+                timezone: self.timezone.label(),
+            },
+            totals: TotalsContext {
+                sessions: analysis.total_sessions,
+                messages: analysis.total_messages,
+                work_hours: hours(analysis.total_work_time),
+                average_session_minutes: analysis
+                    .total_work_time
+                    .num_minutes()
+                    .checked_div(analysis.total_sessions as i64)
+                    .unwrap_or(0),
+                projects: analysis.project_stats.len(),
+            },
+            projects,
+            sessions,
+            sections: self.markdown_sections(analysis),
+        }
+    }
+
+    /// The sections of the built-in report that are shown, in order
+    fn markdown_sections(&self, analysis: &WorkAnalysis) -> Vec<SectionContext> {
+        let mut sections = Vec::new();
+        let mut add = |id: &str, title: &str, body: String| {
+            sections.push(SectionContext { id: id.to_string(), title: title.to_string(), body });
+        };
+
+        // Missing logs make every figure below an undercount
+        if let Some(gaps) = analysis.log_gaps.as_ref().filter(|gaps| !gaps.is_empty()) {
+            add("log-coverage", "⚠️ Log Coverage", self.generate_log_gaps_section(gaps));
+        }
+        if let Some(budget) = analysis.budget.as_ref().filter(|budget| budget.exceeded()) {
+            add("budget", "⚠️ Budget", self.generate_budget_section(budget));
+        }
+        if self.shows(ReportSection::Summary) {
+            add(ReportSection::Summary.name(), "📊 Executive Summary", self.generate_executive_summary(analysis));
+        }
+        if self.shows(ReportSection::Projects) {
+            add(ReportSection::Projects.name(), "🚀 Project Breakdown", self.generate_project_breakdown(analysis));
+        }
+        if let Some(initiatives) = analysis.initiatives.as_ref().filter(|_| self.shows(ReportSection::Initiatives)) {
+            add(
+                ReportSection::Initiatives.name(),
+                "🎯 Initiatives",
+                self.generate_initiatives_section(initiatives, analysis),
+            );
+        }
+        if let Some(machines) = analysis.machines.as_ref().filter(|_| self.shows(ReportSection::Machines)) {
+            add(ReportSection::Machines.name(), "🖥️ Machines", self.generate_machines_section(machines));
+        }
+        if let Some(assistants) = analysis.assistants.as_ref().filter(|_| self.shows(ReportSection::Assistants)) {
+            add(ReportSection::Assistants.name(), "🤖 Assistants", self.generate_assistants_section(assistants));
+        }
+        if self.shows(ReportSection::Activity) {
+            add(ReportSection::Activity.name(), "🔍 Activity Analysis", self.generate_activity_analysis(analysis));
+        }
+        if self.shows(ReportSection::Time) {
+            add(ReportSection::Time.name(), "⏰ Time Analysis", self.generate_time_analysis(analysis));
+        }
+        if let Some(time_buckets) = analysis.time_buckets.as_ref().filter(|_| self.shows(ReportSection::GroupBy)) {
+            let unit = match time_buckets.grouping {
+                TimeGrouping::Day => "Day",
+                TimeGrouping::Week => "Week",
+                TimeGrouping::Month => "Month",
+            };
+            add(
+                ReportSection::GroupBy.name(),
+                &format!("📅 Activity by {}", unit),
+                self.generate_time_buckets_section(time_buckets, analysis.log_gaps.as_ref()),
+            );
+        }
+        if let Some(tool_usage) = analysis.tool_usage.as_ref().filter(|_| self.shows(ReportSection::Tools)) {
+            add(ReportSection::Tools.name(), "🔧 Tool Usage", self.generate_tool_usage_section(tool_usage));
+        }
+        if let Some(sidechains) = analysis.sidechains.as_ref().filter(|_| self.shows(ReportSection::Subagents)) {
+            add(ReportSection::Subagents.name(), "🤖 Subagents", self.generate_subagents_section(sidechains, analysis));
+        }
+        if let Some(code_blocks) = analysis.code_blocks.as_ref().filter(|_| self.shows(ReportSection::CodeBlocks)) {
+            add(ReportSection::CodeBlocks.name(), "🧩 Generated Code", self.generate_code_blocks_section(code_blocks));
+        }
+        if let Some(token_analysis) = analysis.token_analysis.as_ref().filter(|_| self.shows(ReportSection::Tokens)) {
+            add(ReportSection::Tokens.name(), "🪙 Token Usage & Cost", self.generate_token_usage_section(token_analysis));
+        }
+        if self.shows(ReportSection::Conversations) {
+            add(
+                ReportSection::Conversations.name(),
+                "💭 Conversation Summary",
+                self.generate_conversation_summary_section(analysis),
+            );
+        }
+        if self.include_session_details && self.shows(ReportSection::Sessions) {
+            add(ReportSection::Sessions.name(), "💬 Recent Sessions", self.generate_session_details(analysis));
+        }
+        // Where the time, messages and tokens went
+        if self.shows(ReportSection::TopSessions) && !analysis.sessions.is_empty() {
+            add(ReportSection::TopSessions.name(), "🏆 Top Sessions", self.generate_top_sessions_section(analysis));
+        }
+        // Which conversations produced commits
+        if let Some(commits) = analysis.commits.as_ref().filter(|_| self.shows(ReportSection::Commits)) {
+            add(ReportSection::Commits.name(), "🧾 Sessions → Commits", self.generate_commits_section(commits, analysis));
+        }
+        if self.shows(ReportSection::Insights) {
+            add(ReportSection::Insights.name(), "💡 Insights & Recommendations", self.generate_recommendations(analysis));
+        }
+
+        sections
+    }
+
+    /// Generate a short report with only the header and executive summary
+    pub fn generate_summary_report(&self, analysis: &WorkAnalysis) -> String {
+        let budget = match analysis.budget.as_ref().filter(|budget| budget.exceeded()) {
+            Some(budget) => format!("## ⚠️ Budget\n\n{}\n\n", self.generate_budget_section(budget)),
+            None => String::new(),
+        };
+        format!(
+            "{}\n\n{}## 📊 Executive Summary\n\n{}\n",
+            self.generate_header(analysis),
+            budget,
+            self.generate_executive_summary(analysis)
+        )
+    }
+
+    /// Generate a markdown table of the sessions in `order`, the first `limit` of them if given
+    pub fn generate_sessions_report(&self, analysis: &WorkAnalysis, order: SessionOrder, limit: Option<usize>) -> String {
+        let mut report = String::from("# 🔄 Sessions\n\n");
+        if analysis.sessions.is_empty() {
+            report.push_str("No sessions found.\n");
+            return report;
+        }
+        if order != SessionOrder::Start {
+            report.push_str(&format!("Sorted by {}, largest first.\n\n", order.as_str()));
+        }
+
+        report.push_str(&format!("| Start ({}) | End | Project | Duration | Messages | Tokens |\n", self.timezone.label()));
+        report.push_str("|---|---|---|---:|---:|---:|\n");
+
+        let tokens = analysis.session_tokens();
+        let sessions = analysis.sessions_by(order);
+        for session in sessions.into_iter().take(limit.unwrap_or(usize::MAX)) {
+            report.push_str(&format!(
+                "| {} | {} | {} | {}m | {} | {} |\n",
+                self.timezone.to_local(session.start_time).format("%Y-%m-%d %H:%M"),
+                self.timezone.to_local(session.end_time).format("%H:%M"),
+                paths::last_segment(&session.project_path).unwrap_or("Unknown"),
+                (session.end_time - session.start_time).num_minutes(),
+                session.total_messages,
+                tokens(session)
+            ));
+        }
+
+        report
+    }
+
+    /// Generate a markdown table of projects, most worked-on first
+    pub fn generate_projects_report(&self, analysis: &WorkAnalysis) -> String {
+        let mut report = String::from("# 🚀 Projects\n\n");
+        if analysis.project_stats.is_empty() {
+            report.push_str("No projects found.\n");
+            return report;
+        }
+
+        report.push_str("| Project | Sessions | Messages | Work Time |\n");
+        report.push_str("|---|---:|---:|---:|\n");
+        for stats in self.projects_by_work_time(analysis) {
+            report.push_str(&format!(
+                "| {} | {} | {} | {:.1}h |\n",
+                stats.project_name,
+                stats.total_sessions,
+                stats.total_messages,
+                stats.work_time.num_minutes() as f64 / 60.0
+            ));
+        }
+
+        report
+    }
+
+    /// Generate the index of the per-project reports, linking each project to its file
+    pub fn generate_project_index_report(&self, analysis: &WorkAnalysis, file_names: &HashMap<String, String>) -> String {
+        let mut report = self.generate_header(analysis);
+        report.push_str("\n\n| Project | Sessions | Messages | Work Time | Report |\n");
+        report.push_str("|---|---:|---:|---:|---|\n");
+        for stats in self.projects_by_work_time(analysis) {
+            let Some(file_name) = file_names.get(&stats.project_name) else {
+                continue;
+            };
+            report.push_str(&format!(
+                "| {} | {} | {} | {:.1}h | [{}]({}) |\n",
+                stats.project_name,
+                stats.total_sessions,
+                stats.total_messages,
+                stats.work_time.num_minutes() as f64 / 60.0,
+                file_name.trim_end_matches(".md"),
+                file_name
+            ));
+        }
+
+        report
+    }
+
+    /// Generate a CSV table with one row per project
+    pub fn generate_projects_csv_report(&self, analysis: &WorkAnalysis) -> Result<String> {
+        let mut csv = String::from("project,sessions,messages,work_minutes\n");
+        for stats in self.projects_by_work_time(analysis) {
+            csv.push_str(&format!(
+                "{},{},{},{}\n",
+                csv_field(&stats.project_name),
+                stats.total_sessions,
+                stats.total_messages,
+                stats.work_time.num_minutes()
+            ));
+        }
+        Ok(csv)
+    }
+
+    fn projects_by_work_time<'a>(&self, analysis: &'a WorkAnalysis) -> Vec<&'a ProjectStats> {
+        let mut projects: Vec<&ProjectStats> = analysis.project_stats.values().collect();
+        projects.sort_by(|a, b| {
+            b.work_time
+                .cmp(&a.work_time)
+                .then_with(|| a.project_name.cmp(&b.project_name))
+        });
+        projects
+    }
+
+    /// Generate a CSV table with one row per session
+    pub fn generate_csv_report(&self, analysis: &WorkAnalysis) -> Result<String> {
+        self.generate_sessions_csv_report(analysis, SessionOrder::Start, None)
+    }
+
+    /// Session CSV in `order`, the first `limit` sessions if given
+    pub fn generate_sessions_csv_report(&self, analysis: &WorkAnalysis, order: SessionOrder, limit: Option<usize>) -> Result<String> {
+        let mut csv = String::from(
+            "session_id,project,project_path,start,end,duration_minutes,total_messages,user_messages,assistant_messages,thinking_blocks,thinking_chars\n",
+        );
+
+        for session in analysis.sessions_by(order).into_iter().take(limit.unwrap_or(usize::MAX)) {
+            let project_name = paths::last_segment(&session.project_path).unwrap_or("Unknown");
+            let reasoning = session.reasoning_volume();
+            let row = [
+                session.session_id.to_string(),
+                csv_field(project_name),
+                csv_field(&session.project_path),
+                self.timezone.to_local(session.start_time).to_rfc3339(),
+                self.timezone.to_local(session.end_time).to_rfc3339(),
+                (session.end_time - session.start_time).num_minutes().to_string(),
+                session.total_messages.to_string(),
+                session.user_messages.to_string(),
+                session.assistant_messages.to_string(),
+                reasoning.thinking_blocks.to_string(),
+                reasoning.thinking_chars.to_string(),
+            ];
+            csv.push_str(&row.join(","));
+            csv.push('\n');
+        }
+
+        Ok(csv)
+    }
+
+    /// Generate a CSV table with one row per local day and project
+    pub fn generate_daily_csv_report(&self, analysis: &WorkAnalysis) -> Result<String> {
+        #[derive(Default)]
+        struct DailyRow {
+            sessions: usize,
+            minutes: i64,
+            messages: usize,
+            user_messages: usize,
+            assistant_messages: usize,
+        }
+        let mut days: BTreeMap<(String, String), DailyRow> = BTreeMap::new();
+
+        for session in &analysis.sessions {
+            let date = self.timezone.to_local(session.start_time).format("%Y-%m-%d").to_string();
+            let project_name = paths::last_segment(&session.project_path).unwrap_or("Unknown").to_string();
+            let day = days.entry((date, project_name)).or_default();
+            day.sessions += 1;
+            day.minutes += (session.end_time - session.start_time).num_minutes();
+            day.messages += session.total_messages;
+            day.user_messages += session.user_messages;
+            day.assistant_messages += session.assistant_messages;
+        }
+
+        let mut csv = String::from("date,project,sessions,duration_minutes,total_messages,user_messages,assistant_messages\n");
+        for ((date, project), day) in days {
+            csv.push_str(&format!(
+                "{},{},{},{},{},{},{}\n",
+                date,
+                csv_field(&project),
+                day.sessions,
+                day.minutes,
+                day.messages,
+                day.user_messages,
+                day.assistant_messages
+            ));
+        }
+
+        Ok(csv)
+    }
+
+    /// Generate the weekly and per-project rollups of an [`AggregateExport`], without any text of the logs
+    pub fn generate_aggregate_export(&self, analysis: &WorkAnalysis) -> Result<String> {
+        Ok(serde_json::to_string_pretty(&AggregateExport::from_analysis(analysis, &self.timezone))?)
+    }
+
+    /// Generate one JSON object per local day from `start` to `end`, days without sessions included
+    ///
+    /// Without a start the series begins on the day of the first session; the
+    /// end is today at the latest, unless later sessions were analyzed.
+    /// Sessions count on the day they started; tokens on the day of each request.
+    pub fn generate_json_timeseries(
+        &self,
+        analysis: &WorkAnalysis,
+        (start, end): (Option<DateTime<Utc>>, Option<DateTime<Utc>>),
+        now: DateTime<Utc>,
+    ) -> Result<String> {
+        #[derive(Default)]
+        struct Day {
+            sessions: usize,
+            messages: usize,
+            minutes: i64,
+            projects: BTreeMap<String, i64>,
+        }
+        let local_day = |time: DateTime<Utc>| self.timezone.to_local(time).date_naive();
+        let mut days: BTreeMap<NaiveDate, Day> = BTreeMap::new();
+        for session in &analysis.sessions {
+            let day = days.entry(local_day(session.start_time)).or_default();
+            let minutes = (session.end_time - session.start_time).num_minutes();
+            day.sessions += 1;
+            day.messages += session.total_messages;
+            day.minutes += minutes;
+            let project = analysis.project_name(session);
+            *day.projects.entry(project).or_default() += minutes;
+        }
+
+        let first = start.map(local_day).or_else(|| days.keys().next().copied());
+        let last = end.map_or(local_day(now), |end| local_day(end).min(local_day(now)));
+        let last = days.keys().next_back().map_or(last, |&day| last.max(day));
+        let tokens_by_day = analysis.token_analysis.as_ref().map(|tokens| &tokens.by_day);
+        let series: Vec<serde_json::Value> = first
+            .into_iter()
+            .flat_map(|first| first.iter_days().take_while(move |day| *day <= last))
+            .map(|date| {
+                let day = days.get(&date);
+                let top_project = day.and_then(|day| {
+                    day.projects.iter().max_by(|a, b| a.1.cmp(b.1).then_with(|| b.0.cmp(a.0))).map(|(project, _)| project)
+                });
+                serde_json::json!({
+                    "date": date.format("%Y-%m-%d").to_string(),
+                    "sessions": day.map_or(0, |day| day.sessions),
+                    "messages": day.map_or(0, |day| day.messages),
+                    "hours": day.map_or(0.0, |day| (day.minutes as f64 / 60.0 * 100.0).round() / 100.0),
+                    "tokens": tokens_by_day.and_then(|by_day| by_day.get(&date)).map_or(0, |stats| stats.usage.total()),
+                    "top_project": top_project
+                })
+            })
+            .collect();
+        Ok(serde_json::to_string_pretty(&series)?)
+    }
+
+    /// Generate a few plain-text lines for status bars and launcher widgets
+    ///
+    /// Labels, hours and names each keep their column whatever the values, so
+    /// that a widget showing the output does not jump around as it refreshes.
+    /// Today is the local day of `now`, clipped to the analyzed sessions.
+    pub fn generate_compact_report(&self, analysis: &WorkAnalysis, now: DateTime<Utc>) -> String {
+        let today = self.timezone.to_local(now).date_naive();
+        let (day_start, day_end) = (self.timezone.start_of_day(today), self.timezone.end_of_day(today));
+        let today_time = analysis.sessions.iter().fold(Duration::zero(), |total, session| {
+            let (start, end) = (session.start_time.max(day_start), session.end_time.min(day_end));
+            if start < end { total + (end - start) } else { total }
+        });
+        let top_project = busiest_project(analysis);
+        let top_topic = analysis
+            .conversation_summary
+            .as_ref()
+            .and_then(|summary| summary.most_discussed_topics.first())
+            .map(|(topic, _)| topic.as_str());
+
+        let line = |label: &str, hours: Option<Duration>, name: &str| {
+            let hours = hours.map(|time| format!("{:.1}h", time.num_minutes() as f64 / 60.0)).unwrap_or_default();
+            format!("{:<8}{:>6}  {}", label, hours, compact_name(name)).trim_end().to_string()
+        };
+        let sessions = format!("{} session{}", analysis.total_sessions, if analysis.total_sessions == 1 { "" } else { "s" });
+        [
+            line("Today", Some(today_time), ""),
+            line("Period", Some(analysis.total_work_time), &sessions),
+            line("Project", top_project.map(|stats| stats.work_time), top_project.map_or("-", |stats| &stats.project_name)),
+            line("Topic", None, top_topic.unwrap_or("-")),
+        ]
+        .join("\n")
+    }
+
+    /// One line for a shell prompt or tmux status bar, e.g. `claude 2.4h · 3 sessions · api`
+    pub fn generate_status_line(&self, analysis: &WorkAnalysis) -> String {
+        status_line(
+            analysis.total_work_time.num_minutes(),
+            analysis.total_sessions,
+            busiest_project(analysis).map(|project| project.project_name.as_str()),
+        )
+    }
+
+    /// The status line of days summarized by `--watch` or `--daemon`
+    pub fn generate_summary_status_line(&self, summary: &WorkSummary) -> String {
+        status_line(summary.work_seconds / 60, summary.sessions, summary.busiest_project())
+    }
+
+    /// The figures a bundle was exported with, before its logs are analyzed again
+    pub fn generate_bundle_summary(&self, manifest: &BundleManifest) -> String {
+        let summary = &manifest.summary;
+        let mut text = String::from("# 📦 Bundle\n\n");
+        text.push_str(&format!(
+            "- **Period:** {} to {}{}\n",
+            summary.first_day,
+            summary.last_day,
+            manifest.project.as_ref().map(|project| format!(" (project: {})", project)).unwrap_or_default()
+        ));
+        text.push_str(&format!(
+            "- **Exported:** {}{}\n",
+            self.timezone.to_local(manifest.created_at).format("%Y-%m-%d %H:%M"),
+            if manifest.redacted { ", redacted" } else { "" }
+        ));
+        text.push_str(&format!("- **Sessions:** {} ({} messages, {} entries)\n", summary.sessions, summary.messages, manifest.entries));
+        text.push_str(&format!("- **Work Time:** {:.1} hours\n", summary.work_seconds as f64 / 3600.0));
+        text.push_str(&format!("- **Estimated Cost:** ${:.2}\n", summary.estimated_cost));
+        let projects = summary.projects_by_work_time();
+        if !projects.is_empty() {
+            text.push_str("\n## Projects\n\n");
+            for (project, work) in projects {
+                text.push_str(&format!("- **{}**: {} sessions, {:.1} hours\n", project, work.sessions, work.work_seconds as f64 / 3600.0));
+            }
+        }
+        text
+    }
+
+    /// Generate a standalone HTML page with the totals and a project × week heatmap
+    ///
+    /// Each cell is shaded by the project's work time that local week relative to
+    /// the busiest cell, so weeks a project was dormant stand out as blanks.
+    pub fn generate_html_report(&self, analysis: &WorkAnalysis) -> String {
+        let (start, end) = analysis.time_range;
+        let (start_local, end_local) = (self.timezone.to_local(start), self.timezone.to_local(end));
+        let mut html = String::from(
+            "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n<title>Claude Work Analysis Report</title>\n",
+        );
+        html.push_str(HTML_STYLE);
+        html.push_str("</head>\n<body>\n<h1>🤖 Claude Work Analysis Report</h1>\n");
+        html.push_str(&format!(
+            "<p><strong>Analysis Period:</strong> {} {} to {} {}</p>\n",
+            start_local.format("%Y-%m-%d %H:%M"),
+            timezone_label(start_local.offset()),
+            end_local.format("%Y-%m-%d %H:%M"),
+            timezone_label(end_local.offset())
+        ));
+        html.push_str(&format!(
+            "<p>{} sessions, {} messages, {:.1} hours across {} projects</p>\n",
+            analysis.total_sessions,
+            analysis.total_messages,
+            analysis.total_work_time.num_minutes() as f64 / 60.0,
+            analysis.project_stats.len()
+        ));
+
+        html.push_str("<h2>📅 Project Activity by Week</h2>\n");
+        let weeks = self.project_weeks(analysis);
+        if weeks.projects.is_empty() {
+            html.push_str("<p>No sessions in this period.</p>\n");
+        } else {
+            html.push_str(&self.generate_project_weeks_table(&weeks));
+        }
+        html.push_str("</body>\n</html>\n");
+        html
+    }
+
+    /// Work time of each project in each local week, Monday to Sunday
+    fn project_weeks(&self, analysis: &WorkAnalysis) -> ProjectWeeks {
+        let mut cells: HashMap<String, BTreeMap<NaiveDate, WeekCell>> = HashMap::new();
+        for session in &analysis.sessions {
+            let date = self.timezone.to_local(session.start_time).date_naive();
+            let monday = date - Duration::days(date.weekday().num_days_from_monday() as i64);
+            let project = paths::last_segment(&session.project_path).unwrap_or("Unknown").to_string();
+            let cell = cells.entry(project).or_default().entry(monday).or_default();
+            cell.minutes += (session.end_time - session.start_time).num_minutes();
+            cell.sessions += 1;
+        }
+
+        // Every week of the period, so that dormant weeks get a column too
+        let first = cells.values().filter_map(|weeks| weeks.keys().next()).min().copied();
+        let last = cells.values().filter_map(|weeks| weeks.keys().next_back()).max().copied();
+        let weeks: Vec<NaiveDate> = match (first, last) {
+            (Some(first), Some(last)) => first.iter_weeks().take_while(|week| *week <= last).collect(),
+            _ => Vec::new(),
+        };
+        let mut projects: Vec<(String, Vec<WeekCell>)> = cells
+            .into_iter()
+            .map(|(project, cells)| {
+                let row = weeks.iter().map(|week| cells.get(week).copied().unwrap_or_default()).collect();
+                (project, row)
+            })
+            .collect();
+        let total = |row: &[WeekCell]| row.iter().map(|cell| cell.minutes).sum::<i64>();
+        projects.sort_by(|a, b| total(&b.1).cmp(&total(&a.1)).then_with(|| a.0.cmp(&b.0)));
+        ProjectWeeks { weeks, projects }
+    }
+
+    fn generate_project_weeks_table(&self, weeks: &ProjectWeeks) -> String {
+        let busiest = weeks.projects.iter().flat_map(|(_, row)| row).map(|cell| cell.minutes).max().unwrap_or(0);
+        let mut table = String::from("<table class=\"heatmap\">\n<tr><th>Project</th>");
+        for week in &weeks.weeks {
+            table.push_str(&format!("<th title=\"Week of {}\">{}</th>", week.format("%Y-%m-%d"), week.format("%m/%d")));
+        }
+        table.push_str("<th>Total</th></tr>\n");
+
+        for (project, row) in &weeks.projects {
+            let name = html_escape(project);
+            table.push_str(&format!("<tr><th>{}</th>", name));
+            for (week, cell) in weeks.weeks.iter().zip(row) {
+                if cell.sessions == 0 {
+                    table.push_str(&format!("<td class=\"l0\" title=\"{}, week of {}: no sessions\"></td>", name, week.format("%Y-%m-%d")));
+                    continue;
+                }
+                // Any activity gets at least the lightest shade
+                let busiest = busiest.max(1);
+                let level = ((cell.minutes * HEATMAP_LEVELS + busiest - 1) / busiest).clamp(1, HEATMAP_LEVELS);
+                table.push_str(&format!(
+                    "<td class=\"l{}\" title=\"{}, week of {}: {:.1}h in {} session{}\">{:.1}</td>",
+                    level,
+                    name,
+                    week.format("%Y-%m-%d"),
+                    cell.minutes as f64 / 60.0,
+                    cell.sessions,
+                    if cell.sessions == 1 { "" } else { "s" },
+                    cell.minutes as f64 / 60.0
+                ));
+            }
+            let minutes: i64 = row.iter().map(|cell| cell.minutes).sum();
+            table.push_str(&format!("<td class=\"total\">{:.1}h</td></tr>\n", minutes as f64 / 60.0));
+        }
+        table.push_str("</table>\n<p class=\"legend\">Hours of work per week, starting Monday; darker is busier.</p>\n");
+        table
+    }
 
-        // Header
-        report.push_str(&self.generate_header(analysis));
-        report.push_str("\n\n");
+    /// Generate a markdown table of sessions found by `similar`, best match first
+    pub fn generate_similar_report(&self, similar: &[SimilarSession]) -> String {
+        let mut report = String::from("# 🔗 Similar Sessions\n\n");
+        if similar.is_empty() {
+            report.push_str("No sessions found.\n");
+            return report;
+        }
+
+        report.push_str(&format!("| Score | Start ({}) | Project | Session | Summary |\n", self.timezone.label()));
+        report.push_str("|---:|---|---|---|---|\n");
+        for found in similar {
+            let session = found.session;
+            report.push_str(&format!(
+                "| {:.2} | {} | {} | {} | {} |\n",
+                found.score,
+                self.timezone.to_local(session.start_time).format("%Y-%m-%d %H:%M"),
+                paths::last_segment(&session.project_path).unwrap_or("Unknown"),
+                &session.session_id.to_string()[..8],
+                session.summary.as_ref().map(|s| s.overall_summary.as_str()).unwrap_or("-")
+            ));
+        }
+
+        report
+    }
+
+    /// Generate the "Related Past Sessions" section: earlier sessions resembling those in the report
+    pub fn generate_related_sessions_section(&self, related: &[(&WorkSession, SimilarSession)]) -> String {
+        let mut section = String::from("## 🔗 Related Past Sessions\n\n");
+        if related.is_empty() {
+            section.push_str("No earlier sessions on the same topics.\n\n");
+            return section;
+        }
+
+        for (session, earlier) in related {
+            let describe = |session: &WorkSession| {
+                format!(
+                    "`{}` {} {}",
+                    &session.session_id.to_string()[..8],
+                    self.timezone.to_local(session.start_time).format("%Y-%m-%d"),
+                    paths::last_segment(&session.project_path).unwrap_or("Unknown")
+                )
+            };
+            section.push_str(&format!(
+                "- {} resembles {} (similarity {:.2})\n",
+                describe(session),
+                describe(earlier.session),
+                earlier.score
+            ));
+        }
+        section.push('\n');
+        section
+    }
 
-        // Executive Summary
-        report.push_str("## 📊 Executive Summary\n\n");
-        report.push_str(&self.generate_executive_summary(analysis));
-        report.push_str("\n\n");
+    /// Generate a markdown table of hours, messages and tokens per period, with the busiest project
+    pub fn generate_history_report(&self, grouping: TimeGrouping, activity: &[ActivityRollup]) -> String {
+        let heading = match grouping {
+            TimeGrouping::Day => "Day",
+            TimeGrouping::Week => "Week of",
+            TimeGrouping::Month => "Month",
+        };
+        let mut report = format!("# 📈 History ({})\n\n", self.timezone.label());
+        if activity.is_empty() {
+            report.push_str("No activity found.\n");
+            return report;
+        }
 
-        // Project Breakdown
-        report.push_str("## 🚀 Project Breakdown\n\n");
-        report.push_str(&self.generate_project_breakdown(analysis));
-        report.push_str("\n\n");
+        let mut periods: BTreeMap<_, Vec<&ActivityRollup>> = BTreeMap::new();
+        for row in activity {
+            periods.entry(row.start).or_default().push(row);
+        }
 
-        // Activity Analysis
-        report.push_str("## 🔍 Activity Analysis\n\n");
-        report.push_str(&self.generate_activity_analysis(analysis));
-        report.push_str("\n\n");
+        report.push_str(&format!("| {} | Hours | Messages | Tokens | Top Project |\n", heading));
+        report.push_str("|---|---:|---:|---:|---|\n");
+        for (start, rows) in periods {
+            let label = match grouping {
+                TimeGrouping::Month => start.format("%Y-%m").to_string(),
+                _ => start.format("%Y-%m-%d").to_string(),
+            };
+            let top_project = rows
+                .iter()
+                .max_by(|a, b| a.active_seconds.cmp(&b.active_seconds).then_with(|| b.project.cmp(&a.project)))
+                .map(|row| row.project.as_str())
+                .unwrap_or("-");
+            report.push_str(&format!(
+                "| {} | {:.1} | {} | {} | {} |\n",
+                label,
+                rows.iter().map(|row| row.active_seconds).sum::<i64>() as f64 / 3600.0,
+                rows.iter().map(|row| row.messages).sum::<usize>(),
+                rows.iter().map(|row| row.tokens).sum::<u64>(),
+                top_project
+            ));
+        }
 
-        // Time Analysis
-        report.push_str("## ⏰ Time Analysis\n\n");
-        report.push_str(&self.generate_time_analysis(analysis));
-        report.push_str("\n\n");
+        report
+    }
 
-        // Conversation Summary
-        report.push_str("## 💭 Conversation Summary\n\n");
-        report.push_str(&self.generate_conversation_summary_section(analysis));
-        report.push_str("\n\n");
+    /// Generate a markdown chart of the work recorded by earlier reports
+    pub fn generate_recorded_history_report(&self, grouping: TimeGrouping, periods: &[RecordedPeriod]) -> String {
+        let heading = match grouping {
+            TimeGrouping::Day => "Day",
+            TimeGrouping::Week => "Week of",
+            TimeGrouping::Month => "Month",
+        };
+        let mut report = String::from("# 📈 Recorded History\n\n");
+        if periods.is_empty() {
+            report.push_str("No reports recorded yet.\n");
+            return report;
+        }
 
-        // Session Details (if enabled)
-        if self.include_session_details {
-            report.push_str("## 💬 Recent Sessions\n\n");
-            report.push_str(&self.generate_session_details(analysis));
-            report.push_str("\n\n");
+        let max_hours = periods.iter().map(|period| period.work_time.num_minutes()).max().unwrap_or(0).max(1) as f64 / 60.0;
+        report.push_str(&format!("| {} | Hours | Sessions | Messages | Est. Cost | |\n", heading));
+        report.push_str("|---|---:|---:|---:|---:|---|\n");
+        for period in periods {
+            let label = match grouping {
+                TimeGrouping::Month => period.start.format("%Y-%m").to_string(),
+                _ => period.start.format("%Y-%m-%d").to_string(),
+            };
+            let hours = period.work_time.num_minutes() as f64 / 60.0;
+            report.push_str(&format!(
+                "| {} | {:.1} | {} | {} | ${:.2} | {} |\n",
+                label,
+                hours,
+                period.sessions,
+                period.messages,
+                period.estimated_cost,
+                "█".repeat((hours / max_hours * HISTORY_BAR_WIDTH as f64).round() as usize)
+            ));
         }
 
-        // Recommendations
-        report.push_str("## 💡 Insights & Recommendations\n\n");
-        report.push_str(&self.generate_recommendations(analysis));
+        report
+    }
+
+    /// Generate a CSV table with one row per recorded period
+    pub fn generate_recorded_history_csv_report(&self, periods: &[RecordedPeriod]) -> Result<String> {
+        let mut csv = String::from("period_start,active_days,sessions,messages,work_minutes,tokens,estimated_cost\n");
+        for period in periods {
+            csv.push_str(&format!(
+                "{},{},{},{},{},{},{:.4}\n",
+                period.start.format("%Y-%m-%d"),
+                period.active_days,
+                period.sessions,
+                period.messages,
+                period.work_time.num_minutes(),
+                period.tokens,
+                period.estimated_cost
+            ));
+        }
+        Ok(csv)
+    }
 
-        Ok(report)
+    /// Generate a CSV table with one row per period and project
+    pub fn generate_history_csv_report(&self, activity: &[ActivityRollup]) -> Result<String> {
+        let mut csv = String::from("period_start,project,active_minutes,messages,tokens\n");
+        for row in activity {
+            csv.push_str(&format!(
+                "{},{},{},{},{}\n",
+                row.start.format("%Y-%m-%d"),
+                csv_field(&row.project),
+                row.active_seconds / 60,
+                row.messages,
+                row.tokens
+            ));
+        }
+        Ok(csv)
     }
 
     /// Generate a JSON report
     pub fn generate_json_report(&self, analysis: &WorkAnalysis) -> Result<String> {
+        // An analysis without sessions has no meaningful range, only the epoch placeholder
+        let time_range = (analysis.total_sessions > 0).then(|| {
+            serde_json::json!({
+                "start": self.timezone.to_local(analysis.time_range.0).to_rfc3339(),
+                "end": self.timezone.to_local(analysis.time_range.1).to_rfc3339()
+            })
+        });
+        let top_sessions: serde_json::Map<_, _> = [SessionOrder::Duration, SessionOrder::Messages, SessionOrder::Tokens]
+            .into_iter()
+            .map(|order| {
+                let ids: Vec<String> =
+                    analysis.sessions_by(order).iter().take(TOP_SESSIONS).map(|session| session.session_id.to_string()).collect();
+                (order.as_str().to_string(), serde_json::Value::from(ids))
+            })
+            .collect();
         let json_data = serde_json::json!({
             "summary": {
                 "total_sessions": analysis.total_sessions,
+                "log_sessions": analysis.log_sessions(),
+                "stitched_sessions": analysis.stitched_sessions(),
+                "duplicate_entries": analysis.duplicate_entries,
                 "total_messages": analysis.total_messages,
                 "total_work_time_hours": analysis.total_work_time.num_hours(),
-                "time_range": {
-                    "start": analysis.time_range.0.with_timezone(&FixedOffset::east_opt(9 * 3600).unwrap()).to_rfc3339(),
-                    "end": analysis.time_range.1.with_timezone(&FixedOffset::east_opt(9 * 3600).unwrap()).to_rfc3339()
-                }
+                "time_range": time_range
             },
             "projects": analysis.project_stats.iter().map(|(name, stats)| {
                 serde_json::json!({
@@ -94,19 +1043,26 @@ impl ReportGenerator {
                     "sessions": stats.total_sessions,
                     "messages": stats.total_messages,
                     "work_time_hours": stats.work_time.num_hours(),
-                    "activity_types": stats.activity_types
+                    "activity_types": stats.activity_types,
+                    "activity_confidence": stats.activity_confidence
                 })
             }).collect::<Vec<_>>(),
             "sessions": analysis.sessions.iter().take(self.max_detailed_sessions).map(|session| {
+                let reasoning = session.reasoning_volume();
                 serde_json::json!({
                     "session_id": session.session_id,
                     "project_path": session.project_path,
-                    "start_time": session.start_time.with_timezone(&FixedOffset::east_opt(9 * 3600).unwrap()).to_rfc3339(),
-                    "end_time": session.end_time.with_timezone(&FixedOffset::east_opt(9 * 3600).unwrap()).to_rfc3339(),
+                    "start_time": self.timezone.to_local(session.start_time).to_rfc3339(),
+                    "end_time": self.timezone.to_local(session.end_time).to_rfc3339(),
                     "duration_minutes": (session.end_time - session.start_time).num_minutes(),
                     "total_messages": session.total_messages,
                     "user_messages": session.user_messages,
                     "assistant_messages": session.assistant_messages,
+                    "merged_session_ids": session.merged_session_ids,
+                    "reasoning": {
+                        "thinking_blocks": reasoning.thinking_blocks,
+                        "thinking_chars": reasoning.thinking_chars
+                    },
                     "summary": session.summary.as_ref().map(|s| serde_json::json!({
                         "overall_summary": s.overall_summary,
                         "main_topics": s.main_topics,
@@ -118,62 +1074,800 @@ impl ReportGenerator {
             }).collect::<Vec<_>>(),
             "conversation_summary": analysis.conversation_summary.as_ref().map(|cs| serde_json::json!({
                 "total_topics": cs.total_topics,
-                "most_discussed_topics": cs.most_discussed_topics,
+                "most_discussed_topics": cs.most_discussed_topics.iter()
+                    .map(|(topic, sessions)| serde_json::json!({ "topic": topic, "sessions": sessions }))
+                    .collect::<Vec<_>>(),
+                "topic_themes": cs.topic_themes.iter().map(|theme| serde_json::json!({
+                    "name": theme.name,
+                    "topics": theme.topics,
+                    "session_ids": theme.session_ids,
+                    "work_minutes": theme.work_time.num_minutes(),
+                    "tokens": token_stats_json(&theme.tokens)
+                })).collect::<Vec<_>>(),
                 "technology_usage": cs.technology_usage,
+                "follow_ups_by_technology": cs.follow_ups_by_technology,
                 "overall_themes": cs.overall_themes,
-                "productivity_insights": cs.productivity_insights
+                "productivity_insights": cs.productivity_insights,
+                "common_problems": cs.common_problems.iter()
+                    .map(|problem| (problem, cs.problem_confidence.get(problem).copied()))
+                    .filter(|(_, confidence)| self.confident(*confidence))
+                    .map(|(problem, confidence)| serde_json::json!({ "problem": problem, "confidence": confidence }))
+                    .collect::<Vec<_>>()
+            })),
+            "recommendations": self.recommendations(analysis).into_iter()
+                .filter(|recommendation| self.confident(Some(recommendation.confidence)))
+                .map(|recommendation| serde_json::json!({
+                    "title": recommendation.title,
+                    "advice": recommendation.advice,
+                    "confidence": recommendation.confidence
+                }))
+                .collect::<Vec<_>>(),
+            "tool_usage": analysis.tool_usage.as_ref().map(|tu| serde_json::json!({
+                "total_invocations": tu.total_invocations,
+                "by_tool": tu.by_tool.iter().map(|(tool, stats)| (tool.clone(), tool_stats_json(stats))).collect::<serde_json::Map<_, _>>(),
+                "by_project": tu.by_project.iter().map(|(project, tools)| {
+                    (project.clone(), tools.iter().map(|(tool, stats)| (tool.clone(), tool_stats_json(stats))).collect::<serde_json::Map<_, _>>().into())
+                }).collect::<serde_json::Map<_, _>>()
+            })),
+            "code_blocks": analysis.code_blocks,
+            "tokens": analysis.token_analysis.as_ref().map(|ta| serde_json::json!({
+                "total": token_stats_json(&ta.total),
+                "by_model": ta.by_model.iter().map(|(model, stats)| (model.clone(), token_stats_json(stats))).collect::<serde_json::Map<_, _>>(),
+                "by_project": ta.by_project.iter().map(|(project, stats)| (project.clone(), token_stats_json(stats))).collect::<serde_json::Map<_, _>>(),
+                "by_day": ta.by_day.iter().map(|(day, stats)| (day.to_string(), token_stats_json(stats))).collect::<serde_json::Map<_, _>>(),
+                "by_project_activity": ta.by_project_activity.iter().map(|(project, activities)| {
+                    (project.clone(), activities.iter().map(|(activity, stats)| (activity.clone(), token_stats_json(stats))).collect::<serde_json::Map<_, _>>().into())
+                }).collect::<serde_json::Map<_, _>>(),
+                "by_session": ta.by_session.iter().map(|session| serde_json::json!({
+                    "session_id": session.session_id,
+                    "project_path": session.project_path,
+                    "start_time": self.timezone.to_local(session.start_time).to_rfc3339(),
+                    "usage": token_stats_json(&session.stats)
+                })).collect::<Vec<_>>(),
+                "unpriced_models": ta.unpriced_models
+            })),
+            "time_buckets": analysis.time_buckets.as_ref().map(|tb| serde_json::json!({
+                "grouping": tb.grouping.as_str(),
+                "buckets": tb.buckets.iter().map(|bucket| serde_json::json!({
+                    "start": bucket.start.to_string(),
+                    "sessions": bucket.sessions,
+                    "messages": bucket.messages,
+                    "work_minutes": bucket.work_time.num_minutes(),
+                    "tokens": token_stats_json(&bucket.tokens)
+                })).collect::<Vec<_>>()
+            })),
+            "top_sessions": top_sessions,
+            "heatmap": analysis.heatmap.as_ref().map(|heatmap| serde_json::json!({
+                "timezone": self.timezone.label(),
+                "weekdays": WEEKDAYS.map(|day| day.to_string()),
+                "messages": heatmap.messages
+            })),
+            "budget": analysis.budget.as_ref().map(|budget| serde_json::json!({
+                "monthly_budget": budget.monthly_budget,
+                "month_start": budget.month_start().to_string(),
+                "today": budget.today.to_string(),
+                "spent": budget.spent,
+                "projected": budget.projected(),
+                "exceeded": budget.exceeded()
+            })),
+            "log_gaps": analysis.log_gaps.as_ref().map(|gaps| serde_json::json!({
+                "before_logs": gaps.before_logs.as_ref().map(coverage_gap_json),
+                "gaps": gaps.gaps.iter().map(coverage_gap_json).collect::<Vec<_>>()
+            })),
+            "commits": analysis.commits.as_ref().map(|commits| commits.iter().map(|session| serde_json::json!({
+                "session_id": session.session_id,
+                "start_time": self.timezone.to_local(session.start_time).to_rfc3339(),
+                "commits": session.commits.iter().map(|commit| serde_json::json!({
+                    "hash": commit.hash,
+                    "time": self.timezone.to_local(commit.time).to_rfc3339(),
+                    "subject": commit.subject
+                })).collect::<Vec<_>>()
+            })).collect::<Vec<_>>()),
+            "initiatives": analysis.initiatives.as_ref().map(|initiatives| initiatives.iter().map(|initiative| serde_json::json!({
+                "name": initiative.name,
+                "sessions": initiative.sessions,
+                "messages": initiative.messages,
+                "work_minutes": initiative.work_time.num_minutes(),
+                "projects": initiative.projects,
+                "tokens": token_stats_json(&initiative.tokens),
+                "outcomes": initiative.outcomes
+            })).collect::<Vec<_>>()),
+            "machines": analysis.machines.as_ref().map(|breakdown| serde_json::json!({
+                "machines": breakdown.machines.iter().map(|machine| serde_json::json!({
+                    "name": machine.name,
+                    "sessions": machine.sessions,
+                    "messages": machine.messages,
+                    "work_minutes": machine.work_time.num_minutes(),
+                    "tokens": token_stats_json(&machine.tokens)
+                })).collect::<Vec<_>>(),
+                "duplicate_entries": breakdown.duplicate_entries
+            })),
+            "assistants": analysis.assistants.as_ref().map(|assistants| assistants.iter().map(|assistant| serde_json::json!({
+                "name": assistant.name,
+                "sessions": assistant.sessions,
+                "messages": assistant.messages,
+                "work_minutes": assistant.work_time.num_minutes(),
+                "tokens": token_stats_json(&assistant.tokens)
+            })).collect::<Vec<_>>()),
+            "sidechains": analysis.sidechains.as_ref().map(|sidechains| serde_json::json!({
+                "messages": sidechains.total.messages,
+                "tokens": token_stats_json(&sidechains.total.tokens),
+                "by_project": sidechains.by_project.iter().map(|(project, stats)| (project.clone(), serde_json::json!({
+                    "messages": stats.messages,
+                    "tokens": token_stats_json(&stats.tokens)
+                }))).collect::<serde_json::Map<_, _>>(),
+                "by_session": sidechains.by_session.iter().map(|session| serde_json::json!({
+                    "session_id": session.session_id.to_string(),
+                    "start_time": session.start_time.to_rfc3339(),
+                    "project": session.project,
+                    "messages": session.stats.messages,
+                    "tokens": token_stats_json(&session.stats.tokens)
+                })).collect::<Vec<_>>()
             }))
         });
 
         Ok(serde_json::to_string_pretty(&json_data)?)
     }
 
-    fn generate_header(&self, analysis: &WorkAnalysis) -> String {
-        let (start, end) = analysis.time_range;
-        // Convert to JST for display
-        let jst = FixedOffset::east_opt(9 * 3600).unwrap();
-        let start_jst = start.with_timezone(&jst);
-        let end_jst = end.with_timezone(&jst);
-        
-        format!(
-            "# 🤖 Claude Work Analysis Report\n\n**Analysis Period:** {} to {}",
-            start_jst.format("%Y-%m-%d %H:%M JST"),
-            end_jst.format("%Y-%m-%d %H:%M JST")
-        )
-    }
+    /// Generate a markdown report describing log coverage and parse quality
+    pub fn generate_health_report(&self, health: &DataHealth) -> String {
+        let tz = health.timezone();
+        let label = timezone_label(&tz);
+        let totals = health.total_parse_stats();
+        let mut report = String::from("# 🩺 Data Health Report\n\n");
 
-    fn generate_executive_summary(&self, analysis: &WorkAnalysis) -> String {
-        let avg_session_length = if analysis.total_sessions > 0 {
-            analysis.total_work_time.num_minutes() / analysis.total_sessions as i64
-        } else {
-            0
-        };
+        report.push_str(&format!(
+            "- **Projects:** {}\n\
+             - **Files:** {}\n\
+             - **Unreadable Files:** {}\n\
+             - **Entries:** {}\n\
+             - **Parse Error Rate:** {:.1}% ({} errors, {} oversized lines)\n",
+            health.projects().count(),
+            health.total_files(),
+            health.unreadable_files(),
+            health.total_entries(),
+            totals.error_rate() * 100.0,
+            totals.parse_errors,
+            totals.oversized_lines
+        ));
+
+        match health.covered_range() {
+            Some((first, last)) => report.push_str(&format!(
+                "- **Coverage:** {} to {} ({})\n\n",
+                first, last, label
+            )),
+            None => report.push_str("- **Coverage:** no entries found\n\n"),
+        }
 
-        let avg_messages_per_session = if analysis.total_sessions > 0 {
-            analysis.total_messages / analysis.total_sessions
+        report.push_str("## Coverage Gaps\n\n");
+        let gaps = health.coverage_gaps();
+        if gaps.is_empty() {
+            report.push_str("No gaps detected.\n\n");
         } else {
-            0
-        };
+            for gap in &gaps {
+                report.push_str(&format!(
+                    "- {} to {} ({} days without entries)\n",
+                    gap.first_missing_day, gap.last_missing_day, gap.days()
+                ));
+            }
+            report.push('\n');
+        }
 
-        format!(
-            "- **Total Work Sessions:** {}\n\
-             - **Total Messages:** {}\n\
-             - **Total Work Time:** {:.1} hours\n\
-             - **Average Session Length:** {} minutes\n\
-             - **Average Messages per Session:** {}\n\
-             - **Active Projects:** {}",
-            analysis.total_sessions,
+        let skewed = health.skewed_projects();
+        if !skewed.is_empty() {
+            report.push_str("## ⚠️ Clock Skew\n\n");
+            report.push_str("Timestamps out of order within a session were corrected before sessionization. Large skews usually mean a machine with a wrong clock.\n\n");
+            for project in skewed {
+                report.push_str(&format!(
+                    "- **{}**: {} entries out of order (max skew {})\n",
+                    project.project_name,
+                    project.skewed_entries,
+                    format_skew(project.max_skew)
+                ));
+            }
+            report.push('\n');
+        }
+
+        report.push_str("## Projects\n\n");
+        report.push_str(&format!(
+            "| Project | Files | Entries | Earliest ({}) | Latest ({}) | Error Rate | Unreadable |\n",
+            label, label
+        ));
+        report.push_str("|---|---:|---:|---|---|---:|---:|\n");
+
+        let mut projects: Vec<_> = health.projects().collect();
+        projects.sort_by_key(|p| std::cmp::Reverse(p.latest_entry));
+
+        for project in projects {
+            let format_time = |time: Option<chrono::DateTime<chrono::Utc>>| {
+                time.map(|t| t.with_timezone(&tz).format("%Y-%m-%d %H:%M").to_string())
+                    .unwrap_or_else(|| "-".to_string())
+            };
+            report.push_str(&format!(
+                "| {} | {} | {} | {} | {} | {:.1}% | {} |\n",
+                project.project_name,
+                project.file_count,
+                project.entry_count,
+                format_time(project.earliest_entry),
+                format_time(project.latest_entry),
+                project.parse_stats.error_rate() * 100.0,
+                project.unreadable_files
+            ));
+        }
+
+        report
+    }
+
+    /// Generate a markdown breakdown of where a `--profile` run spent its time and allocations
+    ///
+    /// Each stage counts only what it spent outside the stages nested in it; the
+    /// rest of the run is shown as "other".
+    pub fn generate_profile_report(&self, profiler: &Profiler) -> String {
+        let total = profiler.elapsed();
+        let stages = profiler.stages();
+        let share = |time: std::time::Duration| {
+            if total.is_zero() {
+                0.0
+            } else {
+                time.as_secs_f64() / total.as_secs_f64() * 100.0
+            }
+        };
+        let mut totals = StageUsage::default();
+        for usage in stages.values() {
+            totals.time += usage.time;
+            totals.allocated_bytes += usage.allocated_bytes;
+            totals.allocations += usage.allocations;
+        }
+
+        let mut report = format!(
+            "# ⏱️ Profile\n\n**Total:** {}, {} allocated by the stages in {} allocations\n\n",
+            format_elapsed(total),
+            format_bytes(totals.allocated_bytes),
+            totals.allocations
+        );
+        report.push_str("| Stage | Calls | Time | Share | Allocated | Allocations |\n|---|---:|---:|---:|---:|---:|\n");
+        for (stack, usage) in &stages {
+            report.push_str(&format!(
+                "| {} | {} | {} | {:.1}% | {} | {} |\n",
+                stack.replace(';', " › "),
+                usage.calls,
+                format_elapsed(usage.time),
+                share(usage.time),
+                format_bytes(usage.allocated_bytes),
+                usage.allocations
+            ));
+        }
+        let other = total.saturating_sub(totals.time);
+        report.push_str(&format!("| other | | {} | {:.1}% | | |\n", format_elapsed(other), share(other)));
+
+        let files = profiler.items("parse");
+        if !files.is_empty() {
+            report.push_str("\n### 🐢 Slowest Files\n\n| File | Time | Allocated |\n|---|---:|---:|\n");
+            for (file, usage) in files.iter().take(SLOWEST_FILES) {
+                report.push_str(&format!("| {} | {} | {} |\n", file, format_elapsed(usage.time), format_bytes(usage.allocated_bytes)));
+            }
+        }
+        report
+    }
+
+    /// Generate the scores of the analyzer heuristics on a labeled corpus, with the cases they got wrong
+    pub fn generate_eval_report(&self, evaluation: &Evaluation) -> String {
+        let scores = |precision: f64, recall: f64, f1: f64| {
+            format!("**Precision:** {:.1}% · **Recall:** {:.1}% · **F1:** {:.2}\n", precision * 100.0, recall * 100.0, f1)
+        };
+        let misses = |report: &mut String, misses: &[Miss], expected: &str, actual: &str| {
+            if misses.is_empty() {
+                return;
+            }
+            report.push_str(&format!("\n| Prompt | {} | {} |\n|---|---|---|\n", expected, actual));
+            for miss in misses {
+                report.push_str(&format!("| {} | {} | {} |\n", miss.text.replace('|', "\\|"), miss.expected, miss.actual));
+            }
+        };
+
+        let activity = &evaluation.activity;
+        let mut report = String::from("# 🧪 Heuristics Evaluation\n\n## Activity Classification\n\n");
+        report.push_str(&format!(
+            "**Accuracy:** {}/{} ({:.1}%) · **Macro F1:** {:.2}\n\n",
+            activity.correct,
+            activity.cases,
+            activity.accuracy() * 100.0,
+            activity.macro_f1()
+        ));
+        report.push_str("| Activity | Precision | Recall | F1 |\n|---|---:|---:|---:|\n");
+        for (activity_type, counts) in &activity.classes {
+            report.push_str(&format!(
+                "| {} | {:.1}% | {:.1}% | {:.2} |\n",
+                activity_type.as_str(),
+                counts.precision() * 100.0,
+                counts.recall() * 100.0,
+                counts.f1()
+            ));
+        }
+        misses(&mut report, &activity.misses, "Expected", "Classified");
+
+        let problems = &evaluation.problems;
+        report.push_str(&format!(
+            "\n## Problem Detection\n\n**Accuracy:** {}/{} ({:.1}%) · ",
+            problems.cases - problems.misses.len(),
+            problems.cases,
+            problems.accuracy() * 100.0
+        ));
+        report.push_str(&scores(problems.counts.precision(), problems.counts.recall(), problems.counts.f1()));
+        misses(&mut report, &problems.misses, "Expected", "Detected");
+
+        let topics = &evaluation.topics;
+        report.push_str(&format!("\n## Topic Extraction\n\n**Cases:** {} · ", topics.cases));
+        report.push_str(&scores(topics.counts.precision(), topics.counts.recall(), topics.counts.f1()));
+        misses(&mut report, &topics.misses, "Missed Labels", "Unlabeled Topics");
+        report
+    }
+
+    /// Generate a markdown table of planned vs actual hours per week
+    pub fn generate_plan_report(&self, rows: &[PlanComparison]) -> String {
+        let mut report = String::from("# 📐 Planned vs Actual\n");
+        if rows.is_empty() {
+            report.push_str("\nNo estimates or activity in this period.\n");
+            return report;
+        }
+
+        let mut current_week = None;
+        for row in rows {
+            if current_week != Some(row.week_start) {
+                current_week = Some(row.week_start);
+                report.push_str(&format!("\n## Week of {}\n\n", row.week_start));
+                report.push_str("| Project | Planned | Actual | Actual / Planned |\n");
+                report.push_str("|---|---:|---:|---:|\n");
+            }
+            report.push_str(&format!(
+                "| {} | {} | {:.1}h | {} |\n",
+                row.project,
+                row.planned_hours.map(|h| format!("{:.1}h", h)).unwrap_or_else(|| "-".to_string()),
+                row.actual_hours,
+                row.ratio().map(|r| format!("{:.0}%", r * 100.0)).unwrap_or_else(|| "unplanned".to_string())
+            ));
+        }
+
+        let planned: Vec<_> = rows.iter().filter_map(|row| row.ratio()).collect();
+        if !planned.is_empty() {
+            report.push_str(&format!(
+                "\n**Average Actual / Planned:** {:.0}% over {} estimates\n",
+                planned.iter().sum::<f64>() / planned.len() as f64 * 100.0,
+                planned.len()
+            ));
+        }
+
+        report
+    }
+
+    /// Generate the weekly report: this week against last week, goals, top topics and projects
+    pub fn generate_weekly_report(&self, weekly: &WeeklyReport) -> String {
+        let comparison = &weekly.comparison;
+        let mut report = format!(
+            "# 🗓️ Weekly Report {} ({} to {})\n\n",
+            weekly.iso_week(),
+            weekly.week_start,
+            weekly.week_end()
+        );
+
+        report.push_str("## 📊 This Week vs Last Week\n\n");
+        report.push_str("| | Last Week | This Week | Change |\n");
+        report.push_str("|---|---:|---:|---:|\n");
+        let hours = comparison.hours;
+        report.push_str(&format!(
+            "| Hours | {:.1}h | {:.1}h | {:+.1}h{} |\n",
+            hours.baseline,
+            hours.current,
+            hours.current - hours.baseline,
+            format_ratio(hours.ratio())
+        ));
+        for (label, change) in [("Sessions", comparison.sessions), ("Projects", weekly.projects)] {
+            report.push_str(&format!(
+                "| {} | {} | {} | {:+}{} |\n",
+                label,
+                change.baseline,
+                change.current,
+                change.current as i64 - change.baseline as i64,
+                format_ratio(change.ratio())
+            ));
+        }
+        if let Some((project, work_time)) = &weekly.biggest_project {
+            report.push_str(&format!(
+                "\n**Biggest Project:** {} ({:.1}h)\n",
+                project,
+                work_time.num_minutes() as f64 / 60.0
+            ));
+        }
+
+        report.push_str("\n## 🎯 Goals vs Actuals\n\n");
+        let planned = weekly.goals.iter().any(|row| row.planned_hours.is_some());
+        if !planned {
+            report.push_str("No hours planned for this week; set them with `plan set PROJECT HOURS`.\n");
+        }
+        if !weekly.goals.is_empty() {
+            if !planned {
+                report.push('\n');
+            }
+            report.push_str("| Project | Planned | Actual | Actual / Planned |\n");
+            report.push_str("|---|---:|---:|---:|\n");
+            for row in &weekly.goals {
+                report.push_str(&format!(
+                    "| {} | {} | {:.1}h | {} |\n",
+                    row.project,
+                    row.planned_hours.map(|h| format!("{:.1}h", h)).unwrap_or_else(|| "-".to_string()),
+                    row.actual_hours,
+                    row.ratio().map(|r| format!("{:.0}%", r * 100.0)).unwrap_or_else(|| "unplanned".to_string())
+                ));
+            }
+        }
+
+        report.push_str("\n## 🏷️ Top Topics\n\n");
+        if weekly.top_topics.is_empty() {
+            report.push_str("No topics this week.\n");
+        }
+        for (topic, sessions) in &weekly.top_topics {
+            report.push_str(&format!("- {} ({} sessions)\n", topic, sessions));
+        }
+
+        report.push_str("\n## 🚀 Projects\n\n");
+        if comparison.projects.is_empty() {
+            report.push_str("No project activity in either week.\n");
+            return report;
+        }
+        report.push_str("| Project | Last Week | This Week | Change | Sessions |\n");
+        report.push_str("|---|---:|---:|---:|---:|\n");
+        for project in &comparison.projects {
+            report.push_str(&format!(
+                "| {} | {:.1}h | {:.1}h | {:+.1}h | {} → {} |\n",
+                project.project,
+                project.hours.baseline,
+                project.hours.current,
+                project.hours.current - project.hours.baseline,
+                project.sessions.baseline,
+                project.sessions.current
+            ));
+        }
+        report
+    }
+
+    /// JSON recap of a working directory's project for editor extensions
+    pub fn generate_current_project_json(&self, current: &CurrentProject) -> Result<String> {
+        let local = |time: DateTime<Utc>| self.timezone.to_local(time).to_rfc3339();
+        let json_data = serde_json::json!({
+            "cwd": current.cwd,
+            "project": current.project.as_ref().map(|project| serde_json::json!({
+                "name": project.name,
+                "path": project.path,
+                "log_dirs": project.log_dirs
+            })),
+            "since": local(current.since),
+            "sessions": current.sessions,
+            "messages": current.messages,
+            "work_minutes": current.work_time.num_minutes(),
+            "recent_sessions": current.recent_sessions.iter().map(|session| serde_json::json!({
+                "session_id": session.session_id.to_string(),
+                "start_time": local(session.start_time),
+                "end_time": local(session.end_time),
+                "duration_minutes": (session.end_time - session.start_time).num_minutes(),
+                "messages": session.messages,
+                "summary": session.summary,
+                "topics": session.topics
+            })).collect::<Vec<_>>(),
+            "open_problems": current.open_problems,
+            "recent_topics": current.recent_topics
+        });
+        Ok(serde_json::to_string_pretty(&json_data)?)
+    }
+
+    /// Generate a standup update as Slack mrkdwn bullets, ready to paste into a channel
+    pub fn generate_standup_report(&self, standup: &Standup) -> String {
+        let days = if standup.first_day == standup.last_day {
+            standup.last_day.format("%a %Y-%m-%d").to_string()
+        } else {
+            format!("{} to {}", standup.first_day.format("%a %Y-%m-%d"), standup.last_day.format("%a %Y-%m-%d"))
+        };
+        let mut report = format!("*Worked on* ({})\n", days);
+        if standup.projects.is_empty() {
+            report.push_str("• No Claude Code sessions\n");
+        }
+        for project in &standup.projects {
+            let hours = project.work_time.num_minutes() as f64 / 60.0;
+            if project.topics.is_empty() {
+                report.push_str(&format!("• *{}* ({:.1}h)\n", project.project, hours));
+            } else {
+                report.push_str(&format!("• *{}* ({:.1}h): {}\n", project.project, hours, project.topics.join(", ")));
+            }
+        }
+
+        if !standup.problems.is_empty() {
+            report.push_str("\n*Problems tackled*\n");
+            for problem in &standup.problems {
+                report.push_str(&format!("• {}\n", problem));
+            }
+        }
+
+        if !standup.next_steps.is_empty() {
+            report.push_str("\n*Next*\n");
+            for (project, step) in &standup.next_steps {
+                report.push_str(&format!("• *{}*: {}\n", project, step));
+            }
+        }
+        report
+    }
+
+    /// Generate a markdown report of the changes from a baseline period to the current one
+    pub fn generate_comparison_report(
+        &self,
+        comparison: &PeriodComparison,
+        baseline: &TimeRangeFilter,
+        current: &TimeRangeFilter,
+    ) -> String {
+        let mut report = String::from("# ⚖️ Period Comparison\n\n");
+        report.push_str(&format!("**Current:** {}  \n", self.format_period(current)));
+        report.push_str(&format!("**Baseline:** {}\n\n", self.format_period(baseline)));
+
+        report.push_str("## 📊 Summary\n\n");
+        report.push_str("| | Baseline | Current | Change |\n");
+        report.push_str("|---|---:|---:|---:|\n");
+        let hours = comparison.hours;
+        report.push_str(&format!(
+            "| Hours | {:.1}h | {:.1}h | {:+.1}h{} |\n",
+            hours.baseline,
+            hours.current,
+            hours.current - hours.baseline,
+            format_ratio(hours.ratio())
+        ));
+        for (label, change) in [("Sessions", comparison.sessions), ("Messages", comparison.messages)] {
+            report.push_str(&format!(
+                "| {} | {} | {} | {:+}{} |\n",
+                label,
+                change.baseline,
+                change.current,
+                change.current as i64 - change.baseline as i64,
+                format_ratio(change.ratio())
+            ));
+        }
+        report.push('\n');
+
+        report.push_str("## 🔍 Activity Mix\n\n");
+        if comparison.activity_mix.is_empty() {
+            report.push_str("No classified activity in either period.\n\n");
+        } else {
+            report.push_str("| Activity | Baseline | Current | Change |\n");
+            report.push_str("|---|---:|---:|---:|\n");
+            for (activity, share) in &comparison.activity_mix {
+                report.push_str(&format!(
+                    "| {} | {:.1}% | {:.1}% | {:+.1} pts |\n",
+                    activity,
+                    share.baseline,
+                    share.current,
+                    share.current - share.baseline
+                ));
+            }
+            report.push('\n');
+        }
+
+        report.push_str("## 🛠️ Technologies\n\n");
+        let list = |technologies: &[String]| {
+            if technologies.is_empty() {
+                "-".to_string()
+            } else {
+                technologies.join(", ")
+            }
+        };
+        report.push_str(&format!("- **New:** {}\n", list(&comparison.new_technologies)));
+        report.push_str(&format!("- **Dropped:** {}\n", list(&comparison.dropped_technologies)));
+        report.push_str(&format!("- **Both Periods:** {}\n\n", list(&comparison.kept_technologies)));
+
+        report.push_str("## 🚀 Projects\n\n");
+        if comparison.projects.is_empty() {
+            report.push_str("No project activity in either period.\n");
+            return report;
+        }
+        report.push_str("| Project | Baseline | Current | Change | Sessions |\n");
+        report.push_str("|---|---:|---:|---:|---:|\n");
+        for project in &comparison.projects {
+            report.push_str(&format!(
+                "| {} | {:.1}h | {:.1}h | {:+.1}h | {} → {} |\n",
+                project.project,
+                project.hours.baseline,
+                project.hours.current,
+                project.hours.current - project.hours.baseline,
+                project.sessions.baseline,
+                project.sessions.current
+            ));
+        }
+
+        report
+    }
+
+    /// Generate a JSON version of [`Self::generate_comparison_report`]
+    pub fn generate_comparison_json(
+        &self,
+        comparison: &PeriodComparison,
+        baseline: &TimeRangeFilter,
+        current: &TimeRangeFilter,
+    ) -> Result<String> {
+        let period_json = |filter: &TimeRangeFilter| {
+            let (from, to) = filter.get_date_range();
+            serde_json::json!({
+                "from": from.map(|t| self.timezone.to_local(t).to_rfc3339()),
+                "to": to.map(|t| self.timezone.to_local(t).to_rfc3339())
+            })
+        };
+        let change_json = |baseline: f64, current: f64, ratio: Option<f64>| {
+            serde_json::json!({ "baseline": baseline, "current": current, "ratio": ratio })
+        };
+
+        let json_data = serde_json::json!({
+            "baseline_period": period_json(baseline),
+            "current_period": period_json(current),
+            "hours": change_json(comparison.hours.baseline, comparison.hours.current, comparison.hours.ratio()),
+            "sessions": change_json(
+                comparison.sessions.baseline as f64,
+                comparison.sessions.current as f64,
+                comparison.sessions.ratio()
+            ),
+            "messages": change_json(
+                comparison.messages.baseline as f64,
+                comparison.messages.current as f64,
+                comparison.messages.ratio()
+            ),
+            "activity_mix_percent": comparison.activity_mix.iter().map(|(activity, share)| {
+                (activity.clone(), serde_json::json!({ "baseline": share.baseline, "current": share.current }))
+            }).collect::<serde_json::Map<_, _>>(),
+            "technologies": {
+                "new": comparison.new_technologies,
+                "dropped": comparison.dropped_technologies,
+                "kept": comparison.kept_technologies
+            },
+            "projects": comparison.projects.iter().map(|project| {
+                serde_json::json!({
+                    "name": project.project,
+                    "hours": change_json(project.hours.baseline, project.hours.current, project.hours.ratio()),
+                    "sessions": { "baseline": project.sessions.baseline, "current": project.sessions.current }
+                })
+            }).collect::<Vec<_>>()
+        });
+
+        Ok(serde_json::to_string_pretty(&json_data)?)
+    }
+
+    /// Local dates of a filter's range, e.g. `2025-07-01 to 2025-07-14`
+    fn format_period(&self, filter: &TimeRangeFilter) -> String {
+        let (from, to) = filter.get_date_range();
+        format!(
+            "{} to {}",
+            from.map(|t| self.timezone.to_local(t).format("%Y-%m-%d").to_string())
+                .unwrap_or_else(|| "the first log".to_string()),
+            to.map(|t| self.timezone.to_local(t).format("%Y-%m-%d").to_string())
+                .unwrap_or_else(|| "now".to_string())
+        )
+    }
+
+    /// Generate a report for an analysis without sessions, explaining what was searched
+    ///
+    /// Replaces the full report, whose sections would all be empty.
+    pub fn generate_no_activity_report(&self, filter: &TimeRangeFilter, coverage: &FilterCoverage) -> String {
+        let format_time = |time: DateTime<Utc>| {
+            let local = self.timezone.to_local(time);
+            format!("{} {}", local.format("%Y-%m-%d %H:%M"), timezone_label(local.offset()))
+        };
+        let mut report = String::from("# 🤖 Claude Work Analysis Report\n\n## 🔍 No Activity Found\n\n");
+
+        if coverage.matched_entries == 0 {
+            report.push_str("No log entries matched the filters.\n\n");
+        } else {
+            report.push_str(&format!(
+                "{} log entries matched the filters, but too few to form a session.\n\n",
+                coverage.matched_entries
+            ));
+        }
+
+        let (from, to) = filter.get_date_range();
+        let any_time = || "any time".to_string();
+        report.push_str("### Filters\n\n");
+        report.push_str(&format!("- **From:** {}\n", from.map(format_time).unwrap_or_else(any_time)));
+        report.push_str(&format!("- **To:** {}\n", to.map(format_time).unwrap_or_else(any_time)));
+        report.push_str(&format!("- **Project:** {}\n\n", filter.get_project_filter().unwrap_or("all projects")));
+
+        report.push_str("### Data Coverage\n\n");
+        let (Some(first), Some(last)) = (coverage.first_entry, coverage.last_entry) else {
+            report.push_str("No log entries were found at all. Check that the projects directory is the one Claude Code writes to.\n");
+            return report;
+        };
+        report.push_str(&format!(
+            "- **Log Entries:** {}\n- **Logs Cover:** {} to {}\n\n",
+            coverage.total_entries,
+            format_time(first),
+            format_time(last)
+        ));
+
+        let hint = match (from, to) {
+            (Some(from), _) if from > last => "The period starts after the latest log entry.",
+            (_, Some(to)) if to < first => "The period ends before the earliest log entry.",
+            _ if filter.get_project_filter().is_some() => "Check the project name or widen the period.",
+            _ => "Try a wider period.",
+        };
+        report.push_str(&format!("💡 {}\n", hint));
+
+        report
+    }
+
+    fn generate_header(&self, analysis: &WorkAnalysis) -> String {
+        let (start, end) = analysis.time_range;
+        // Convert to the report timezone for display
+        let start_local = self.timezone.to_local(start);
+        let end_local = self.timezone.to_local(end);
+        
+        format!(
+            "# 🤖 Claude Work Analysis Report\n\n**Analysis Period:** {} {} to {} {}",
+            start_local.format("%Y-%m-%d %H:%M"),
+            timezone_label(start_local.offset()),
+            end_local.format("%Y-%m-%d %H:%M"),
+            timezone_label(end_local.offset())
+        )
+    }
+
+    fn generate_executive_summary(&self, analysis: &WorkAnalysis) -> String {
+        let avg_session_length = if analysis.total_sessions > 0 {
+            analysis.total_work_time.num_minutes() / analysis.total_sessions as i64
+        } else {
+            0
+        };
+
+        let avg_messages_per_session = analysis
+            .total_messages
+            .checked_div(analysis.total_sessions)
+            .unwrap_or(0);
+
+        let mut summary = format!(
+            "- **Total Work Sessions:** {}\n\
+             - **Total Messages:** {}\n\
+             - **Total Work Time:** {:.1} hours\n\
+             - **Average Session Length:** {} minutes\n\
+             - **Average Messages per Session:** {}\n\
+             - **Active Projects:** {}",
+            analysis.total_sessions,
             analysis.total_messages,
             analysis.total_work_time.num_minutes() as f64 / 60.0,
             avg_session_length,
             avg_messages_per_session,
             analysis.project_stats.len()
-        )
+        );
+
+        let stitched = analysis.stitched_sessions();
+        if stitched > 0 {
+            summary.push_str(&format!(
+                "\n- **Log Sessions:** {} ({} continued under a new session ID and stitched into the work session they continue)",
+                analysis.log_sessions(),
+                stitched
+            ));
+        }
+
+        if analysis.duplicate_entries > 0 {
+            summary.push_str(&format!(
+                "\n- **Duplicate Entries:** {} dropped (also found in a newer log file, e.g. a backup copy)",
+                analysis.duplicate_entries
+            ));
+        }
+
+        let volumes: Vec<_> = analysis.sessions.iter().map(|session| session.reasoning_volume()).collect();
+        let thinking_blocks: usize = volumes.iter().map(|volume| volume.thinking_blocks).sum();
+        if thinking_blocks > 0 {
+            summary.push_str(&format!(
+                "\n- **Extended Thinking:** {} blocks in {} sessions ({} chars)",
+                thinking_blocks,
+                volumes.iter().filter(|volume| volume.thinking_blocks > 0).count(),
+                volumes.iter().map(|volume| volume.thinking_chars).sum::<usize>()
+            ));
+        }
+        summary
     }
 
     fn generate_project_breakdown(&self, analysis: &WorkAnalysis) -> String {
         let mut projects: Vec<_> = analysis.project_stats.iter().collect();
-        projects.sort_by(|a, b| b.1.work_time.cmp(&a.1.work_time));
+        projects.sort_by_key(|(_, stats)| std::cmp::Reverse(stats.work_time));
 
         let mut breakdown = String::new();
         
@@ -181,6 +1875,7 @@ impl ReportGenerator {
             let work_hours = stats.work_time.num_minutes() as f64 / 60.0;
             let most_active_activity = stats.activity_types
                 .iter()
+                .filter(|(activity, _)| self.confident(stats.activity_confidence.get(*activity).copied()))
                 .max_by_key(|(_, count)| *count)
                 .map(|(activity, count)| format!("{} ({})", activity, count))
                 .unwrap_or_else(|| "N/A".to_string());
@@ -211,6 +1906,19 @@ impl ReportGenerator {
                     ));
                 }
             }
+            if let Some(languages) = analysis.code_blocks.as_ref().and_then(|blocks| blocks.by_project.get(project_name)) {
+                let mut languages: Vec<_> = languages.iter()
+                    .filter(|(language, stats)| language.as_str() != "unspecified" && stats.lines > 0)
+                    .collect();
+                let lines: usize = languages.iter().map(|(_, stats)| stats.lines).sum();
+                if lines > 0 {
+                    languages.sort_by(|a, b| b.1.lines.cmp(&a.1.lines).then_with(|| a.0.cmp(b.0)));
+                    let shares: Vec<String> = languages.iter()
+                        .map(|(language, stats)| format!("{} {:.0}%", language, stats.lines as f64 * 100.0 / lines as f64))
+                        .collect();
+                    breakdown.push_str(&format!(" - **Languages Written:** {}\n", shares.join(", ")));
+                }
+            }
             breakdown.push('\n');
         }
 
@@ -230,10 +1938,16 @@ impl ReportGenerator {
         activities.sort_by(|a, b| b.1.cmp(a.1));
 
         let total_activities: usize = activities.iter().map(|(_, count)| *count).sum();
+        let confidences = activity_confidences(analysis);
 
         let mut analysis_text = String::new();
+        let mut hidden = 0;
         
         for (activity, count) in activities {
+            if !self.confident(confidences.get(activity).copied()) {
+                hidden += count;
+                continue;
+            }
             let percentage = if total_activities > 0 {
                 (*count as f64 / total_activities as f64) * 100.0
             } else {
@@ -245,17 +1959,75 @@ impl ReportGenerator {
                 activity, count, percentage
             ));
         }
+        if hidden > 0 {
+            analysis_text.push_str(&format!(
+                "- _{} messages classified with less than {:.0}% confidence not shown_\n",
+                hidden,
+                self.min_confidence * 100.0
+            ));
+        }
+
+        if let Some(tokens) = analysis.token_analysis.as_ref().filter(|tokens| !tokens.by_project_activity.is_empty()) {
+            analysis_text.push_str(&self.generate_activity_cost_table(tokens));
+        }
 
         analysis_text
     }
 
+    /// Spend of each project split by the activity of the prompts, costliest first
+    ///
+    /// Shares are of the project's estimated cost, or of its tokens when none of
+    /// its models are priced.
+    fn generate_activity_cost_table(&self, tokens: &TokenAnalysis) -> String {
+        let by_spend = |a: &TokenStats, b: &TokenStats| {
+            b.estimated_cost.total_cmp(&a.estimated_cost).then_with(|| b.usage.total().cmp(&a.usage.total()))
+        };
+        let mut projects: Vec<_> = tokens
+            .by_project_activity
+            .iter()
+            .map(|(project, activities)| {
+                let mut total = TokenStats::default();
+                activities.values().for_each(|stats| total.merge(stats));
+                let mut activities: Vec<_> = activities.iter().collect();
+                activities.sort_by(|a, b| by_spend(a.1, b.1).then_with(|| a.0.cmp(b.0)));
+                (project, activities, total)
+            })
+            .collect();
+        projects.sort_by(|a, b| by_spend(&a.2, &b.2).then_with(|| a.0.cmp(b.0)));
+
+        let mut table = String::from(
+            "\n**Estimated Cost by Activity:**\n\n| Project | Activity | Requests | Tokens | Est. Cost | Share of Project |\n|---|---|---|---|---|---|\n",
+        );
+        for (project, activities, total) in projects {
+            for (activity, stats) in activities {
+                let share = if total.estimated_cost > 0.0 {
+                    stats.estimated_cost / total.estimated_cost
+                } else {
+                    stats.usage.total() as f64 / total.usage.total().max(1) as f64
+                };
+                table.push_str(&format!(
+                    "| {} | {} | {} | {} | ${:.2} | {:.1}% |\n",
+                    project,
+                    activity,
+                    stats.requests,
+                    stats.usage.total(),
+                    stats.estimated_cost,
+                    share * 100.0
+                ));
+            }
+        }
+        table
+    }
+
     fn generate_time_analysis(&self, analysis: &WorkAnalysis) -> String {
-        let mut daily_stats: HashMap<String, (usize, i64)> = HashMap::new(); // (sessions, minutes)
-        let mut hourly_stats: HashMap<u32, usize> = HashMap::new(); // hour -> session_count
+        // Ordered, so that ties go to the latest day and hour on every run
+        let mut daily_stats: BTreeMap<String, (usize, i64)> = BTreeMap::new(); // (sessions, minutes)
+        let mut hourly_stats: BTreeMap<u32, usize> = BTreeMap::new(); // hour -> session_count
 
         for session in &analysis.sessions {
-            let date_key = session.start_time.format("%Y-%m-%d").to_string();
-            let hour = session.start_time.hour();
+            let local_start = self.timezone.to_local(session.start_time);
+            let date_key = local_start.format("%Y-%m-%d").to_string();
+            let hour = local_start.hour();
             let duration_minutes = (session.end_time - session.start_time).num_minutes();
 
             let (session_count, total_minutes) = daily_stats.entry(date_key).or_insert((0, 0));
@@ -286,8 +2058,15 @@ impl ReportGenerator {
             .max_by_key(|(_, count)| *count)
         {
             time_analysis.push_str(&format!(
-                "**Peak Activity Hour:** {}:00 ({} sessions)\n\n",
-                peak_hour, session_count
+                "**Peak Activity Hour:** {}:00 {} ({} sessions)\n\n",
+                peak_hour, self.timezone.label(), session_count
+            ));
+        }
+
+        if let Some((weekday, hour, messages)) = analysis.heatmap.as_ref().and_then(ActivityHeatmap::peak) {
+            time_analysis.push_str(&format!(
+                "**Busiest Hour of the Week:** {} {}:00 {} ({} messages)\n\n",
+                weekday, hour, self.timezone.label(), messages
             ));
         }
 
@@ -305,89 +2084,607 @@ impl ReportGenerator {
             ));
         }
 
+        if let Some(heatmap) = analysis.heatmap.as_ref().filter(|heatmap| heatmap.total() > 0) {
+            time_analysis.push_str(&format!("\n**Messages by Weekday and Hour ({}):**\n\n", self.timezone.label()));
+            time_analysis.push_str(&self.generate_heatmap_table(heatmap));
+        }
+
         time_analysis
     }
 
-    fn generate_session_details(&self, analysis: &WorkAnalysis) -> String {
-        let mut details = String::new();
-        
-        // JST timezone for session display
-        let jst = FixedOffset::east_opt(9 * 3600).unwrap();
-        
-        let mut recent_sessions = analysis.sessions.clone();
-        recent_sessions.sort_by(|a, b| b.start_time.cmp(&a.start_time));
-
-        for session in recent_sessions.iter().take(self.max_detailed_sessions) {
-            let duration = session.end_time - session.start_time;
-            let project_name = session.project_path
-                .split('/')
-                .last()
-                .unwrap_or("Unknown");
+    /// Weekday rows by hour columns, leaving hours without messages blank
+    fn generate_heatmap_table(&self, heatmap: &ActivityHeatmap) -> String {
+        let hours: Vec<String> = (0..24).map(|hour| hour.to_string()).collect();
+        let mut table = format!("| | {} | Total |\n|---|{}---:|\n", hours.join(" | "), "---:|".repeat(24));
+        for weekday in WEEKDAYS {
+            let counts: Vec<String> = (0..24)
+                .map(|hour| match heatmap.count(weekday, hour) {
+                    0 => String::new(),
+                    count => count.to_string(),
+                })
+                .collect();
+            let total: usize = (0..24).map(|hour| heatmap.count(weekday, hour)).sum();
+            table.push_str(&format!("| {} | {} | {} |\n", weekday, counts.join(" | "), total));
+        }
+        table
+    }
 
-            let mut session_detail = format!(
-                "### 🔄 Session: {} \n\
-                 **Project:** {}\n\
-                 **Duration:** {} minutes\n\
-                 **Messages:** {} (User: {}, Assistant: {})\n\
-                 **Time:** {} → {}\n",
-                &session.session_id.to_string()[..8],
-                project_name,
-                duration.num_minutes(),
-                session.total_messages,
-                session.user_messages,
-                session.assistant_messages,
-                session.start_time.with_timezone(&jst).format("%Y-%m-%d %H:%M JST"),
-                session.end_time.with_timezone(&jst).format("%H:%M JST")
-            );
+    fn generate_time_buckets_section(&self, time_buckets: &TimeBuckets, gaps: Option<&LogGaps>) -> String {
+        if time_buckets.buckets.is_empty() {
+            return "No sessions in this period.".to_string();
+        }
 
-            // Add session summary if available
-            if let Some(ref summary) = session.summary {
-                session_detail.push_str(&format!(
-                    "**Summary:** {}\n",
-                    summary.overall_summary
-                ));
-                if !summary.main_topics.is_empty() {
-                    session_detail.push_str(&format!(
-                        "**Topics:** {}\n",
-                        summary.main_topics.join(", ")
-                    ));
-                }
-                if !summary.technologies_mentioned.is_empty() {
-                    session_detail.push_str(&format!(
-                        "**Technologies:** {}\n",
-                        summary.technologies_mentioned.join(", ")
-                    ));
-                }
+        let heading = match time_buckets.grouping {
+            TimeGrouping::Day => "Day",
+            TimeGrouping::Week => "Week of",
+            TimeGrouping::Month => "Month",
+        };
+        let mut section = format!(
+            "| {} | Sessions | Hours | Messages | Tokens | Est. Cost |\n|---|---:|---:|---:|---:|---:|\n",
+            heading
+        );
+        let mut partial = false;
+        for bucket in &time_buckets.buckets {
+            let mut label = match time_buckets.grouping {
+                TimeGrouping::Month => bucket.start.format("%Y-%m").to_string(),
+                _ => bucket.start.format("%Y-%m-%d").to_string(),
+            };
+            let mut days = bucket.start.iter_days().take_while(|day| time_buckets.grouping.bucket_start(*day) == bucket.start);
+            if gaps.is_some_and(|gaps| days.any(|day| gaps.contains(day))) {
+                label.push_str(" ⚠️");
+                partial = true;
             }
-            session_detail.push_str("\n");
-            details.push_str(&session_detail);
+            section.push_str(&format!(
+                "| {} | {} | {:.1} | {} | {} | ${:.2} |\n",
+                label,
+                bucket.sessions,
+                bucket.work_time.num_minutes() as f64 / 60.0,
+                bucket.messages,
+                bucket.tokens.usage.total(),
+                bucket.tokens.estimated_cost
+            ));
         }
-
-        details
+        if partial {
+            section.push_str("\n⚠️ Logs are missing for some days of this period (see Log Coverage).\n");
+        }
+        section
     }
 
-    fn generate_recommendations(&self, analysis: &WorkAnalysis) -> String {
-        let mut recommendations = Vec::new();
+    fn generate_initiatives_section(&self, initiatives: &[InitiativeRollup], analysis: &WorkAnalysis) -> String {
+        let mut section = String::from("| Initiative | Sessions | Hours | Messages | Est. Cost | Projects |\n|---|---:|---:|---:|---:|---|\n");
+        for initiative in initiatives {
+            section.push_str(&format!(
+                "| {} | {} | {:.1} | {} | ${:.2} | {} |\n",
+                initiative.name,
+                initiative.sessions,
+                initiative.work_time.num_minutes() as f64 / 60.0,
+                initiative.messages,
+                initiative.tokens.estimated_cost,
+                initiative.projects.join(", ")
+            ));
+        }
 
-        // Work pattern insights
-        if analysis.total_sessions > 0 {
-            let avg_session_length = analysis.total_work_time.num_minutes() / analysis.total_sessions as i64;
-            
-            if avg_session_length < 15 {
-                recommendations.push("💡 **Short Sessions Detected:** Consider consolidating related tasks into longer, more focused work sessions for better productivity.");
-            } else if avg_session_length > 120 {
-                recommendations.push("⏱️ **Long Sessions Detected:** Consider taking breaks during extended coding sessions to maintain focus and code quality.");
-            }
+        // Sessions count toward one initiative at most
+        let assigned_sessions: usize = initiatives.iter().map(|initiative| initiative.sessions).sum();
+        let assigned_time = initiatives.iter().fold(Duration::zero(), |total, initiative| total + initiative.work_time);
+        if assigned_sessions < analysis.total_sessions {
+            section.push_str(&format!(
+                "| _Unassigned_ | {} | {:.1} | | | |\n",
+                analysis.total_sessions - assigned_sessions,
+                (analysis.total_work_time - assigned_time).num_minutes() as f64 / 60.0
+            ));
         }
 
-        // Project diversity insights
-        if analysis.project_stats.len() > 5 {
-            recommendations.push("🎯 **High Project Diversity:** You're working on many projects. Consider prioritizing or batching similar tasks to reduce context switching overhead.");
-        } else if analysis.project_stats.len() == 1 {
-            recommendations.push("🔍 **Single Project Focus:** Great job maintaining focus on one project! Consider if this aligns with your current goals.");
+        for initiative in initiatives.iter().filter(|initiative| !initiative.outcomes.is_empty()) {
+            section.push_str(&format!("\n**{} Outcomes:**\n", initiative.name));
+            for outcome in &initiative.outcomes {
+                section.push_str(&format!("- {}\n", outcome));
+            }
         }
+        section
+    }
 
-        // Activity pattern insights
+    fn generate_machines_section(&self, breakdown: &MachineBreakdown) -> String {
+        let mut section = String::from("| Machine | Sessions | Hours | Messages | Tokens | Est. Cost |\n|---|---:|---:|---:|---:|---:|\n");
+        for machine in &breakdown.machines {
+            section.push_str(&format!(
+                "| {} | {} | {:.1} | {} | {} | ${:.2} |\n",
+                machine.name,
+                machine.sessions,
+                machine.work_time.num_minutes() as f64 / 60.0,
+                machine.messages,
+                machine.tokens.usage.total(),
+                machine.tokens.estimated_cost
+            ));
+        }
+        if breakdown.duplicate_entries > 0 {
+            section.push_str(&format!(
+                "\n**Duplicate Entries:** {} read from more than one machine, counted once\n",
+                breakdown.duplicate_entries
+            ));
+        }
+        section
+    }
+
+    /// Claude Code next to the assistants whose history was imported
+    fn generate_assistants_section(&self, assistants: &[AssistantStats]) -> String {
+        let mut section = String::from(
+            "| Assistant | Sessions | Hours | Avg Session | Messages | Tokens | Est. Cost |\n|---|---:|---:|---:|---:|---:|---:|\n",
+        );
+        for assistant in assistants {
+            section.push_str(&format!(
+                "| {} | {} | {:.1} | {}m | {} | {} | ${:.2} |\n",
+                assistant.name,
+                assistant.sessions,
+                assistant.work_time.num_minutes() as f64 / 60.0,
+                assistant.work_time.num_minutes() / assistant.sessions.max(1) as i64,
+                assistant.messages,
+                assistant.tokens.usage.total(),
+                assistant.tokens.estimated_cost
+            ));
+        }
+        section
+    }
+
+    /// Tables of the sessions with the most work time, messages and tokens
+    fn generate_top_sessions_section(&self, analysis: &WorkAnalysis) -> String {
+        let tokens = analysis.session_tokens();
+        let mut orders = vec![("⏱️ Longest Sessions", SessionOrder::Duration), ("💬 Most Messages", SessionOrder::Messages)];
+        if analysis.token_analysis.as_ref().is_some_and(|tokens| !tokens.by_session.is_empty()) {
+            orders.push(("🪙 Most Tokens", SessionOrder::Tokens));
+        }
+
+        let mut section = String::new();
+        for (title, order) in orders {
+            section.push_str(&format!("### {}\n\n", title));
+            section.push_str(&format!("| Start ({}) | Project | Duration | Messages | Tokens | Session |\n", self.timezone.label()));
+            section.push_str("|---|---|---:|---:|---:|---|\n");
+            for session in analysis.sessions_by(order).into_iter().take(TOP_SESSIONS) {
+                section.push_str(&format!(
+                    "| {} | {} | {}m | {} | {} | `{}` |\n",
+                    self.timezone.to_local(session.start_time).format("%Y-%m-%d %H:%M"),
+                    paths::last_segment(&session.project_path).unwrap_or("Unknown"),
+                    (session.end_time - session.start_time).num_minutes(),
+                    session.total_messages,
+                    tokens(session),
+                    &session.session_id.to_string()[..8]
+                ));
+            }
+            section.push('\n');
+        }
+        section.trim_end().to_string()
+    }
+
+    /// Periods without logs and how to keep logs from being pruned
+    fn generate_log_gaps_section(&self, gaps: &LogGaps) -> String {
+        let mut section = String::new();
+        if let Some(gap) = &gaps.before_logs {
+            section.push_str(&format!(
+                "- No logs before {}: the {} days of the period from {} are not covered.\n",
+                gap.last_missing_day + Duration::days(1),
+                gap.days(),
+                gap.first_missing_day
+            ));
+        }
+        for gap in &gaps.gaps {
+            section.push_str(&format!(
+                "- {} to {}: {} days without logs between days with activity.\n",
+                gap.first_missing_day,
+                gap.last_missing_day,
+                gap.days()
+            ));
+        }
+        section.push_str(
+            "\nClaude Code deletes logs older than `cleanupPeriodDays` (30 days by default), so reports on these days fall short. \
+             Run `claude-work-analysis archive` regularly and add the archive directory to `projects_dirs` to keep them.",
+        );
+        section
+    }
+
+    /// The month-to-date spend and where it is heading
+    fn generate_budget_section(&self, budget: &BudgetStatus) -> String {
+        format!(
+            "- Spent from {} to {}: ${:.2} of the ${:.2} monthly budget.\n\
+             - Projected for the month ({} days at the same rate): ${:.2}, {:.0}% of the budget.\n\n\
+             Costs are estimated from the token usage of every project, at the prices of `--prices` or the built-in ones.",
+            budget.month_start(),
+            budget.today,
+            budget.spent,
+            budget.monthly_budget,
+            budget.days_in_month(),
+            budget.projected(),
+            budget.projected() / budget.monthly_budget * 100.0
+        )
+    }
+
+    /// Sessions that led to commits, each with the subjects of its commits
+    fn generate_commits_section(&self, commits: &[SessionCommits], analysis: &WorkAnalysis) -> String {
+        let total: usize = commits.iter().map(|session| session.commits.len()).sum();
+        let mut section = format!(
+            "**{} of {} sessions led to commits ({} commit{}).**\n",
+            commits.len(),
+            analysis.total_sessions,
+            total,
+            if total == 1 { "" } else { "s" }
+        );
+
+        let sessions: HashMap<_, _> = analysis
+            .sessions
+            .iter()
+            .map(|session| ((session.session_id, session.start_time), session))
+            .collect();
+        for session_commits in commits {
+            let Some(session) = sessions.get(&(session_commits.session_id, session_commits.start_time)) else {
+                continue;
+            };
+            section.push_str(&format!(
+                "\n### {} · {} · {}m · {} commit{}\n\n",
+                self.timezone.to_local(session.start_time).format("%Y-%m-%d %H:%M"),
+                paths::last_segment(&session.project_path).unwrap_or("Unknown"),
+                (session.end_time - session.start_time).num_minutes(),
+                session_commits.commits.len(),
+                if session_commits.commits.len() == 1 { "" } else { "s" }
+            ));
+            for commit in session_commits.commits.iter().take(MAX_COMMITS_PER_SESSION) {
+                section.push_str(&format!("- `{}` {}\n", &commit.hash[..commit.hash.len().min(7)], commit.subject));
+            }
+            if session_commits.commits.len() > MAX_COMMITS_PER_SESSION {
+                section.push_str(&format!("- …and {} more\n", session_commits.commits.len() - MAX_COMMITS_PER_SESSION));
+            }
+        }
+        section.trim_end().to_string()
+    }
+
+    fn generate_tool_usage_section(&self, tool_usage: &ToolUsageSummary) -> String {
+        if tool_usage.total_invocations == 0 {
+            return "No tool invocations recorded in this period.".to_string();
+        }
+
+        let format_rate = |stats: &ToolStats| {
+            stats.success_rate()
+                .map(|rate| format!("{:.0}%", rate * 100.0))
+                .unwrap_or_else(|| "-".to_string())
+        };
+
+        let mut section = format!("**Total Tool Calls:** {}\n\n", tool_usage.total_invocations);
+        section.push_str("| Tool | Calls | Succeeded | Failed | Success Rate |\n|---|---:|---:|---:|---:|\n");
+        let mut tools: Vec<_> = tool_usage.by_tool.iter().collect();
+        tools.sort_by(|a, b| b.1.invocations.cmp(&a.1.invocations).then_with(|| a.0.cmp(b.0)));
+        for (tool, stats) in tools {
+            section.push_str(&format!(
+                "| {} | {} | {} | {} | {} |\n",
+                tool, stats.invocations, stats.successes, stats.failures, format_rate(stats)
+            ));
+        }
+
+        section.push_str("\n**By Project:**\n");
+        let mut projects: Vec<_> = tool_usage.by_project.iter().collect();
+        projects.sort_by_key(|(name, _)| name.as_str());
+        for (project, tools) in projects {
+            let mut tools: Vec<_> = tools.iter().collect();
+            tools.sort_by(|a, b| b.1.invocations.cmp(&a.1.invocations).then_with(|| a.0.cmp(b.0)));
+            let breakdown: Vec<String> = tools
+                .iter()
+                .map(|(tool, stats)| format!("{} {}", tool, stats.invocations))
+                .collect();
+            section.push_str(&format!("- **{}:** {}\n", project, breakdown.join(", ")));
+        }
+
+        section
+    }
+
+    /// How much of the work, by messages and tokens, ran in subagent conversations
+    fn generate_subagents_section(&self, sidechains: &SidechainSummary, analysis: &WorkAnalysis) -> String {
+        if sidechains.total.messages == 0 {
+            return "No work delegated to subagents in this period.".to_string();
+        }
+
+        let share = |part: f64, whole: f64| if whole > 0.0 { part / whole * 100.0 } else { 0.0 };
+        let mut section = format!(
+            "**Delegated Messages:** {} of {} ({:.0}%)\n",
+            sidechains.total.messages,
+            analysis.total_messages,
+            share(sidechains.total.messages as f64, analysis.total_messages as f64)
+        );
+        if let Some(tokens) = &analysis.token_analysis {
+            section.push_str(&format!(
+                "**Delegated Tokens:** {} of {} ({:.0}%), ${:.2} of ${:.2}\n",
+                sidechains.total.tokens.usage.total(),
+                tokens.total.usage.total(),
+                share(sidechains.total.tokens.usage.total() as f64, tokens.total.usage.total() as f64),
+                sidechains.total.tokens.estimated_cost,
+                tokens.total.estimated_cost
+            ));
+        }
+        if let Some(launches) = analysis.tool_usage.as_ref().and_then(|tool_usage| tool_usage.by_tool.get("Task")) {
+            section.push_str(&format!("**Subagent Launches:** {}\n", launches.invocations));
+        }
+
+        section.push_str("\n| Project | Subagent Messages | Share | Tokens | Est. Cost |\n|---|---:|---:|---:|---:|\n");
+        let mut projects: Vec<_> = sidechains.by_project.iter().collect();
+        projects.sort_by(|a, b| b.1.messages.cmp(&a.1.messages).then_with(|| a.0.cmp(b.0)));
+        for (project, stats) in projects {
+            let messages = analysis.project_stats.get(project).map_or(0, |stats| stats.total_messages);
+            section.push_str(&format!(
+                "| {} | {} | {:.0}% | {} | ${:.2} |\n",
+                project,
+                stats.messages,
+                share(stats.messages as f64, messages as f64),
+                stats.tokens.usage.total(),
+                stats.tokens.estimated_cost
+            ));
+        }
+
+        section.push_str(&format!(
+            "\n**Most Delegated Sessions:**\n\n| Start ({}) | Project | Subagent Messages | Tokens | Session |\n|---|---|---:|---:|---|\n",
+            self.timezone.label()
+        ));
+        for session in sidechains.by_session.iter().take(TOP_SESSIONS) {
+            section.push_str(&format!(
+                "| {} | {} | {} | {} | `{}` |\n",
+                self.timezone.to_local(session.start_time).format("%Y-%m-%d %H:%M"),
+                session.project,
+                session.stats.messages,
+                session.stats.tokens.usage.total(),
+                &session.session_id.to_string()[..8]
+            ));
+        }
+        section
+    }
+
+    fn generate_code_blocks_section(&self, code_blocks: &CodeBlockSummary) -> String {
+        if code_blocks.total.blocks == 0 {
+            return "No code blocks in the assistant's responses in this period.".to_string();
+        }
+
+        let by_lines = |a: &(&String, &CodeBlockStats), b: &(&String, &CodeBlockStats)| {
+            b.1.lines.cmp(&a.1.lines).then_with(|| b.1.blocks.cmp(&a.1.blocks)).then_with(|| a.0.cmp(b.0))
+        };
+        let mut section = format!(
+            "**Code Blocks:** {} ({} lines)\n\n| Language | Blocks | Lines |\n|---|---:|---:|\n",
+            code_blocks.total.blocks, code_blocks.total.lines
+        );
+        let mut languages: Vec<_> = code_blocks.by_language.iter().collect();
+        languages.sort_by(by_lines);
+        for (language, stats) in languages {
+            section.push_str(&format!("| {} | {} | {} |\n", language, stats.blocks, stats.lines));
+        }
+
+        section.push_str("\n**Lines by Project:**\n");
+        let mut projects: Vec<_> = code_blocks.by_project.iter().collect();
+        projects.sort_by_key(|(name, _)| name.as_str());
+        for (project, languages) in projects {
+            let mut languages: Vec<_> = languages.iter().collect();
+            languages.sort_by(by_lines);
+            let breakdown: Vec<String> =
+                languages.iter().map(|(language, stats)| format!("{} {}", language, stats.lines)).collect();
+            section.push_str(&format!("- **{}:** {}\n", project, breakdown.join(", ")));
+        }
+
+        section.push_str(&self.generate_languages_over_time(code_blocks));
+        section
+    }
+
+    /// Weekly lines per language, with the less used languages folded into "other"
+    fn generate_languages_over_time(&self, code_blocks: &CodeBlockSummary) -> String {
+        if code_blocks.by_day.is_empty() {
+            return String::new();
+        }
+
+        let mut languages: Vec<_> = code_blocks.by_language.iter()
+            .filter(|(language, _)| language.as_str() != "unspecified")
+            .collect();
+        languages.sort_by(|a, b| b.1.lines.cmp(&a.1.lines).then_with(|| a.0.cmp(b.0)));
+        let columns: Vec<&String> = languages.into_iter().take(LANGUAGE_COLUMNS).map(|(language, _)| language).collect();
+
+        let mut weeks: BTreeMap<NaiveDate, HashMap<&String, usize>> = BTreeMap::new();
+        for (day, languages) in &code_blocks.by_day {
+            let monday = *day - Duration::days(day.weekday().num_days_from_monday() as i64);
+            let week = weeks.entry(monday).or_default();
+            for (language, stats) in languages {
+                *week.entry(language).or_default() += stats.lines;
+            }
+        }
+
+        let header: Vec<&str> = columns.iter().map(|language| language.as_str()).collect();
+        let mut section = format!("\n**Languages Over Time:**\n\n| Week | {} | other |\n|---|", header.join(" | "));
+        section.push_str(&"---:|".repeat(columns.len() + 1));
+        section.push('\n');
+        for (monday, lines) in weeks {
+            let cells: Vec<String> = columns.iter()
+                .map(|language| lines.get(language).copied().unwrap_or(0).to_string())
+                .collect();
+            let other: usize = lines.iter()
+                .filter(|(language, _)| !columns.contains(language))
+                .map(|(_, lines)| lines)
+                .sum();
+            section.push_str(&format!("| {} | {} | {} |\n", monday.format("%Y-%m-%d"), cells.join(" | "), other));
+        }
+        section
+    }
+
+    fn generate_token_usage_section(&self, token_analysis: &TokenAnalysis) -> String {
+        if token_analysis.total.requests == 0 {
+            return "No token usage recorded in this period.".to_string();
+        }
+
+        let total = &token_analysis.total;
+        let mut section = format!(
+            "- **Total Tokens:** {} (input {}, output {}, cache write {}, cache read {})\n\
+             - **API Requests:** {}\n\
+             - **Estimated Cost:** ${:.2}\n\n",
+            total.usage.total(),
+            total.usage.input_tokens,
+            total.usage.output_tokens,
+            total.usage.cache_creation_tokens,
+            total.usage.cache_read_tokens,
+            total.requests,
+            total.estimated_cost
+        );
+
+        let mut models: Vec<_> = token_analysis.by_model.iter().collect();
+        models.sort_by(|a, b| b.1.estimated_cost.total_cmp(&a.1.estimated_cost));
+        section.push_str("| Model | Requests | Tokens | Est. Cost |\n|---|---|---|---|\n");
+        for (model, stats) in models {
+            section.push_str(&format!(
+                "| {} | {} | {} | ${:.2} |\n",
+                model, stats.requests, stats.usage.total(), stats.estimated_cost
+            ));
+        }
+
+        let mut projects: Vec<_> = token_analysis.by_project.iter().collect();
+        projects.sort_by(|a, b| b.1.estimated_cost.total_cmp(&a.1.estimated_cost));
+        section.push_str("\n| Project | Requests | Tokens | Est. Cost |\n|---|---|---|---|\n");
+        for (project, stats) in projects {
+            section.push_str(&format!(
+                "| {} | {} | {} | ${:.2} |\n",
+                project, stats.requests, stats.usage.total(), stats.estimated_cost
+            ));
+        }
+
+        section.push_str("\n**Daily Cost:**\n");
+        for (day, stats) in token_analysis.by_day.iter().rev().take(7) {
+            section.push_str(&format!(
+                "- {}: ${:.2} ({} tokens)\n",
+                day, stats.estimated_cost, stats.usage.total()
+            ));
+        }
+
+        let mut sessions: Vec<_> = token_analysis.by_session.iter().collect();
+        sessions.sort_by(|a, b| b.stats.estimated_cost.total_cmp(&a.stats.estimated_cost));
+        section.push_str("\n**Most Expensive Sessions:**\n");
+        for session in sessions.iter().take(self.max_detailed_sessions.min(5)) {
+            section.push_str(&format!(
+                "- {} ({}): ${:.2}\n",
+                self.timezone.to_local(session.start_time).format("%Y-%m-%d %H:%M"),
+                session.project_path,
+                session.stats.estimated_cost
+            ));
+        }
+
+        if !token_analysis.unpriced_models.is_empty() {
+            section.push_str(&format!(
+                "\n_No price configured for: {} (counted as $0)_\n",
+                token_analysis.unpriced_models.join(", ")
+            ));
+        }
+
+        section
+    }
+
+    fn generate_session_details(&self, analysis: &WorkAnalysis) -> String {
+        let mut details = String::new();
+        
+        let mut recent_sessions = analysis.sessions.clone();
+        recent_sessions.sort_by_key(|s| std::cmp::Reverse(s.start_time));
+
+        for session in recent_sessions.iter().take(self.max_detailed_sessions) {
+            let duration = session.end_time - session.start_time;
+            let project_name = paths::last_segment(&session.project_path).unwrap_or("Unknown");
+            let start_local = self.timezone.to_local(session.start_time);
+            let end_local = self.timezone.to_local(session.end_time);
+
+            let mut session_detail = format!(
+                "### 🔄 Session: {} \n\
+                 **Project:** {}\n\
+                 **Duration:** {} minutes\n\
+                 **Messages:** {} (User: {}, Assistant: {})\n\
+                 **Time:** {} {} → {} {}\n",
+                &session.session_id.to_string()[..8],
+                project_name,
+                duration.num_minutes(),
+                session.total_messages,
+                session.user_messages,
+                session.assistant_messages,
+                start_local.format("%Y-%m-%d %H:%M"),
+                timezone_label(start_local.offset()),
+                end_local.format("%H:%M"),
+                timezone_label(end_local.offset())
+            );
+
+            let reasoning = session.reasoning_volume();
+            if reasoning.thinking_blocks > 0 {
+                session_detail.push_str(&format!(
+                    "**Reasoning:** {} thinking blocks ({} chars)\n",
+                    reasoning.thinking_blocks, reasoning.thinking_chars
+                ));
+            }
+
+            if !session.merged_session_ids.is_empty() {
+                let ids: Vec<String> = session
+                    .merged_session_ids
+                    .iter()
+                    .map(|id| id.to_string()[..8].to_string())
+                    .collect();
+                session_detail.push_str(&format!(
+                    "**Merged Restarts:** {} ({})\n",
+                    ids.len(),
+                    ids.join(", ")
+                ));
+            }
+
+            // Add session summary if available
+            if let Some(ref summary) = session.summary {
+                session_detail.push_str(&format!(
+                    "**Summary:** {}\n",
+                    summary.overall_summary
+                ));
+                if !summary.main_topics.is_empty() {
+                    session_detail.push_str(&format!(
+                        "**Topics:** {}\n",
+                        summary.main_topics.join(", ")
+                    ));
+                }
+                if !summary.technologies_mentioned.is_empty() {
+                    session_detail.push_str(&format!(
+                        "**Technologies:** {}\n",
+                        summary.technologies_mentioned.join(", ")
+                    ));
+                }
+            }
+            session_detail.push('\n');
+            details.push_str(&session_detail);
+        }
+
+        details
+    }
+
+    /// Advice on the work patterns of the analysis, each with how well the analysis supports it
+    ///
+    /// Advice drawn from a few sessions or messages is less confident; advice on
+    /// the main activity also depends on how confidently messages were classified.
+    pub fn recommendations(&self, analysis: &WorkAnalysis) -> Vec<Recommendation> {
+        let recommendation = |icon: &str, title: &str, advice: &str, confidence: f64| Recommendation {
+            icon: icon.to_string(),
+            title: title.to_string(),
+            advice: advice.to_string(),
+            confidence,
+        };
+        let mut recommendations = Vec::new();
+        let sessions = analysis.total_sessions as f64;
+        let rules = &self.insights;
+
+        // Work pattern insights
+        if analysis.total_sessions > 0 {
+            let avg_session_length = analysis.total_work_time.num_minutes() / analysis.total_sessions as i64;
+            
+            if rules.short_sessions.enabled && avg_session_length < rules.short_sessions.max_average_minutes {
+                recommendations.push(recommendation("💡", "Short Sessions Detected", "Consider consolidating related tasks into longer, more focused work sessions for better productivity.", confidence(sessions / 2.0)));
+            } else if rules.long_sessions.enabled && avg_session_length > rules.long_sessions.min_average_minutes {
+                recommendations.push(recommendation("⏱️", "Long Sessions Detected", "Consider taking breaks during extended coding sessions to maintain focus and code quality.", confidence(sessions / 2.0)));
+            }
+        }
+
+        // Project diversity insights
+        let projects = analysis.project_stats.len();
+        let max_projects = rules.project_diversity.max_projects;
+        if rules.project_diversity.enabled && projects > max_projects {
+            recommendations.push(recommendation("🎯", "High Project Diversity", "You're working on many projects. Consider prioritizing or batching similar tasks to reduce context switching overhead.", confidence(2.0 * (projects - max_projects) as f64)));
+        } else if rules.single_project.enabled && projects == 1 {
+            recommendations.push(recommendation("🔍", "Single Project Focus", "Great job maintaining focus on one project! Consider if this aligns with your current goals.", confidence(sessions / 2.0)));
+        }
+
+        // Activity pattern insights
         let mut all_activities: HashMap<String, usize> = HashMap::new();
         for stats in analysis.project_stats.values() {
             for (activity, count) in &stats.activity_types {
@@ -395,20 +2692,39 @@ impl ReportGenerator {
             }
         }
 
-        if let Some((top_activity, _)) = all_activities.iter().max_by_key(|(_, count)| *count) {
+        let top_activity = all_activities.iter().max_by_key(|(_, count)| *count).filter(|_| rules.top_activity.enabled);
+        if let Some((top_activity, count)) = top_activity {
+            let classified = activity_confidences(analysis).get(top_activity).copied().unwrap_or(1.0);
+            let confidence = classified * confidence(*count as f64 / 2.0);
             match top_activity.as_str() {
-                "Debugging" => recommendations.push("🐛 **Debug-Heavy Period:** High debugging activity detected. Consider implementing more tests or code review practices."),
-                "Learning" => recommendations.push("📚 **Learning Mode:** Lots of learning activity! Great for skill development. Document your learnings for future reference."),
-                "Coding" => recommendations.push("⚡ **High Productivity:** Strong coding activity detected. Excellent work!"),
+                "Debugging" => recommendations.push(recommendation("🐛", "Debug-Heavy Period", "High debugging activity detected. Consider implementing more tests or code review practices.", confidence)),
+                "Learning" => recommendations.push(recommendation("📚", "Learning Mode", "Lots of learning activity! Great for skill development. Document your learnings for future reference.", confidence)),
+                "Coding" => recommendations.push(recommendation("⚡", "High Productivity", "Strong coding activity detected. Excellent work!", confidence)),
                 _ => {}
             }
         }
 
-        if recommendations.is_empty() {
-            recommendations.push("✨ **Overall:** Your work patterns look healthy. Keep up the great work!");
+        if recommendations.is_empty() && rules.overall.enabled {
+            recommendations.push(recommendation("✨", "Overall", "Your work patterns look healthy. Keep up the great work!", confidence(sessions / 2.0)));
         }
 
-        recommendations.join("\n\n")
+        recommendations
+    }
+
+    fn generate_recommendations(&self, analysis: &WorkAnalysis) -> String {
+        let recommendations = self.recommendations(analysis);
+        if recommendations.is_empty() {
+            return "_No recommendations._".to_string();
+        }
+        let lines: Vec<String> = recommendations
+            .iter()
+            .filter(|recommendation| self.confident(Some(recommendation.confidence)))
+            .map(|recommendation| format!("{} **{}:** {}", recommendation.icon, recommendation.title, recommendation.advice))
+            .collect();
+        if lines.is_empty() {
+            return format!("_No recommendations with at least {:.0}% confidence._", self.min_confidence * 100.0);
+        }
+        lines.join("\n\n")
     }
 
     fn generate_conversation_summary_section(&self, analysis: &WorkAnalysis) -> String {
@@ -423,11 +2739,32 @@ impl ReportGenerator {
                 ));
             }
 
-            // Most discussed topics
-            if !conv_summary.most_discussed_topics.is_empty() {
-                summary.push_str("**Most Discussed Topics:**\n");
-                for (topic, count) in conv_summary.most_discussed_topics.iter().take(5) {
-                    summary.push_str(&format!("- {} ({} mentions)\n", topic, count));
+            // Themes, their topics and their most recent sessions
+            if !conv_summary.topic_themes.is_empty() {
+                summary.push_str("**Topic Themes:**\n");
+                for theme in conv_summary.topic_themes.iter().take(5) {
+                    let topics: Vec<String> = theme
+                        .topics
+                        .iter()
+                        .take(5)
+                        .map(|(topic, sessions)| format!("{} ({})", topic, sessions))
+                        .collect();
+                    let recent: Vec<String> = theme
+                        .session_ids
+                        .iter()
+                        .rev()
+                        .take(5)
+                        .map(|id| id.to_string()[..8].to_string())
+                        .collect();
+                    summary.push_str(&format!(
+                        "- **{}** ({} sessions, {:.1}h, ${:.2})\n  - Topics: {}\n  - Recent Sessions: {}\n",
+                        theme.name,
+                        theme.session_ids.len(),
+                        theme.work_time.num_minutes() as f64 / 60.0,
+                        theme.tokens.estimated_cost,
+                        topics.join(", "),
+                        recent.join(", ")
+                    ));
                 }
                 summary.push('\n');
             }
@@ -443,10 +2780,16 @@ impl ReportGenerator {
                 summary.push('\n');
             }
 
+            summary.push_str(&self.generate_follow_ups_table(&conv_summary.follow_ups_by_technology));
+
             // Common problems
             if !conv_summary.common_problems.is_empty() {
                 summary.push_str("**Common Problem Areas:**\n");
-                for problem in conv_summary.common_problems.iter().take(3) {
+                let problems = conv_summary
+                    .common_problems
+                    .iter()
+                    .filter(|problem| self.confident(conv_summary.problem_confidence.get(*problem).copied()));
+                for problem in problems.take(3) {
                     summary.push_str(&format!("- {}\n", problem));
                 }
                 summary.push('\n');
@@ -474,6 +2817,190 @@ impl ReportGenerator {
             "会話内容の分析は利用できません。".to_string()
         }
     }
+
+    /// Technologies whose discussions took the most follow-up prompts first
+    fn generate_follow_ups_table(&self, follow_ups: &HashMap<String, FollowUpStats>) -> String {
+        let mut technologies: Vec<_> = follow_ups
+            .iter()
+            .filter(|(_, stats)| stats.discussions >= MIN_FOLLOW_UP_DISCUSSIONS)
+            .collect();
+        if technologies.is_empty() {
+            return String::new();
+        }
+        technologies.sort_by(|a, b| {
+            b.1.rate().total_cmp(&a.1.rate()).then_with(|| b.1.discussions.cmp(&a.1.discussions)).then_with(|| a.0.cmp(b.0))
+        });
+
+        let mut table = String::from(
+            "**Follow-up Prompts by Technology:**\n\n| Technology | Discussions | Follow-ups | Per Discussion |\n|---|---:|---:|---:|\n",
+        );
+        for (tech, stats) in technologies.into_iter().take(8) {
+            table.push_str(&format!("| {} | {} | {} | {:.1} |\n", tech, stats.discussions, stats.follow_ups, stats.rate()));
+        }
+        table.push('\n');
+        table
+    }
+}
+
+/// Quote a CSV field if it contains a delimiter, quote or line break (RFC 4180)
+/// Work time of each project in each week of the HTML heatmap
+struct ProjectWeeks {
+    /// Mondays of every week from the first session to the last
+    weeks: Vec<NaiveDate>,
+    /// Cells of each project by week, most worked-on project first
+    projects: Vec<(String, Vec<WeekCell>)>,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct WeekCell {
+    minutes: i64,
+    sessions: usize,
+}
+
+const HTML_STYLE: &str = "<style>
+body { font-family: sans-serif; margin: 2em; color: #24292f; }
+table.heatmap { border-collapse: collapse; }
+.heatmap th, .heatmap td { padding: 4px 8px; text-align: center; font-size: 0.85em; }
+.heatmap tr > th:first-child { text-align: left; }
+.heatmap td { border: 1px solid #fff; min-width: 2.5em; }
+.heatmap td.l0 { background: #ebedf0; }
+.heatmap td.l1 { background: #9be9a8; }
+.heatmap td.l2 { background: #40c463; }
+.heatmap td.l3 { background: #30a14e; color: #fff; }
+.heatmap td.l4 { background: #216e39; color: #fff; }
+.heatmap td.total { font-weight: bold; }
+.legend { color: #57606a; font-size: 0.85em; }
+</style>
+";
+
+/// Project with the most work time, the first by name on a tie
+fn busiest_project(analysis: &WorkAnalysis) -> Option<&ProjectStats> {
+    analysis
+        .project_stats
+        .values()
+        .max_by(|a, b| a.work_time.cmp(&b.work_time).then_with(|| b.project_name.cmp(&a.project_name)))
+}
+
+fn status_line(work_minutes: i64, sessions: usize, busiest_project: Option<&str>) -> String {
+    let mut parts = vec![
+        format!("claude {:.1}h", work_minutes as f64 / 60.0),
+        format!("{} session{}", sessions, if sessions == 1 { "" } else { "s" }),
+    ];
+    if let Some(project) = busiest_project {
+        parts.push(compact_name(project));
+    }
+    parts.join(" · ")
+}
+
+/// A name cut to the width of the compact report's last column
+fn compact_name(name: &str) -> String {
+    if name.chars().count() <= COMPACT_NAME_WIDTH {
+        return name.to_string();
+    }
+    let cut: String = name.chars().take(COMPACT_NAME_WIDTH - 1).collect();
+    format!("{}…", cut)
+}
+
+/// Escape text for HTML content and attribute values
+fn html_escape(value: &str) -> String {
+    value.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;").replace('\'', "&#39;")
+}
+
+/// Quote a text field for CSV
+///
+/// Text starting like a formula is prefixed with `'` so that spreadsheets
+/// show it instead of evaluating it.
+fn csv_field(value: &str) -> String {
+    let value = if value.starts_with(['=', '+', '-', '@']) {
+        format!("'{}", value)
+    } else {
+        value.to_string()
+    };
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value
+    }
+}
+
+/// Mean classification confidence of each activity type over the projects that recorded one
+fn activity_confidences(analysis: &WorkAnalysis) -> HashMap<String, f64> {
+    let mut sums: HashMap<String, (f64, usize)> = HashMap::new();
+    for stats in analysis.project_stats.values() {
+        for (activity, confidence) in &stats.activity_confidence {
+            let count = stats.activity_types.get(activity).copied().unwrap_or(0);
+            let sum = sums.entry(activity.clone()).or_default();
+            sum.0 += confidence * count as f64;
+            sum.1 += count;
+        }
+    }
+    sums.into_iter()
+        .filter(|(_, (_, count))| *count > 0)
+        .map(|(activity, (sum, count))| (activity, sum / count as f64))
+        .collect()
+}
+
+/// Relative change in parentheses, e.g. ` (+25%)`, or nothing without a baseline
+fn format_ratio(ratio: Option<f64>) -> String {
+    ratio.map(|r| format!(" ({:+.0}%)", r * 100.0)).unwrap_or_default()
+}
+
+/// Human-readable skew such as `45s`, `12m` or `3h 05m`
+fn format_skew(skew: Duration) -> String {
+    if skew < Duration::minutes(1) {
+        format!("{}s", skew.num_seconds())
+    } else if skew < Duration::hours(1) {
+        format!("{}m", skew.num_minutes())
+    } else {
+        format!("{}h {:02}m", skew.num_hours(), skew.num_minutes() % 60)
+    }
+}
+
+/// Wall time such as `0.4ms`, `850.0ms` or `2.41s`
+fn format_elapsed(elapsed: std::time::Duration) -> String {
+    if elapsed < std::time::Duration::from_secs(1) {
+        format!("{:.1}ms", elapsed.as_secs_f64() * 1000.0)
+    } else {
+        format!("{:.2}s", elapsed.as_secs_f64())
+    }
+}
+
+/// Byte count such as `512 KB` or `3.2 MB`
+fn format_bytes(bytes: u64) -> String {
+    if bytes < 1024 * 1024 {
+        format!("{} KB", bytes.div_ceil(1024))
+    } else {
+        format!("{:.1} MB", bytes as f64 / (1024.0 * 1024.0))
+    }
+}
+
+fn coverage_gap_json(gap: &CoverageGap) -> serde_json::Value {
+    serde_json::json!({
+        "first_missing_day": gap.first_missing_day.to_string(),
+        "last_missing_day": gap.last_missing_day.to_string(),
+        "days": gap.days()
+    })
+}
+
+fn tool_stats_json(stats: &ToolStats) -> serde_json::Value {
+    serde_json::json!({
+        "invocations": stats.invocations,
+        "successes": stats.successes,
+        "failures": stats.failures,
+        "success_rate": stats.success_rate()
+    })
+}
+
+fn token_stats_json(stats: &TokenStats) -> serde_json::Value {
+    serde_json::json!({
+        "requests": stats.requests,
+        "input_tokens": stats.usage.input_tokens,
+        "output_tokens": stats.usage.output_tokens,
+        "cache_creation_tokens": stats.usage.cache_creation_tokens,
+        "cache_read_tokens": stats.usage.cache_read_tokens,
+        "total_tokens": stats.usage.total(),
+        "estimated_cost_usd": stats.estimated_cost
+    })
 }
 
 impl Default for ReportGenerator {
@@ -485,9 +3012,7 @@ impl Default for ReportGenerator {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::models::{WorkSession, ProjectStats};
-    use chrono::{Duration, Utc};
-    use std::collections::HashMap;
+    use chrono::{TimeZone, Utc};
     use uuid::Uuid;
 
     fn create_test_analysis() -> WorkAnalysis {
@@ -505,6 +3030,7 @@ mod tests {
                     activities.insert("Debugging".to_string(), 3);
                     activities
                 },
+                activity_confidence: HashMap::from([("Coding".to_string(), 0.8), ("Debugging".to_string(), 0.3)]),
                 most_active_day: Some(Utc::now()),
                 topic_analysis: None,
             }
@@ -522,6 +3048,7 @@ mod tests {
                     user_messages: 3,
                     assistant_messages: 2,
                     summary: None,
+                    merged_session_ids: Vec::new(),
                 }
             ],
             project_stats,
@@ -530,6 +3057,20 @@ mod tests {
             total_messages: 10,
             total_work_time: Duration::hours(2),
             conversation_summary: None,
+            token_analysis: None,
+            tool_usage: None,
+            code_blocks: None,
+            time_buckets: None,
+            initiatives: None,
+            commits: None,
+            log_gaps: None,
+            heatmap: None,
+            machines: None,
+            assistants: None,
+            sidechains: None,
+            budget: None,
+            duplicate_entries: 0,
+            project_names: Default::default(),
         }
     }
 
@@ -547,6 +3088,24 @@ mod tests {
     }
 
     #[test]
+    fn test_report_template() {
+        let analysis = create_test_analysis();
+        let generator = ReportGenerator::new()
+            .with_timezone(FixedOffset::east_opt(0).unwrap())
+            .with_sections(vec![ReportSection::Summary]);
+
+        let context = generator.report_context(&analysis);
+        assert_eq!((context.totals.sessions, context.totals.work_hours), (2, 2.0));
+        assert_eq!(context.projects[0].name, "test-project");
+        assert_eq!(context.sessions[0].project, "project");
+        assert_eq!(context.sections.iter().map(|section| section.id.as_str()).collect::<Vec<_>>(), ["summary"]);
+
+        let template = ReportTemplate::new("{{#each sections}}[{{id}}] {{/each}}{{period.timezone}}").unwrap();
+        assert_eq!(generator.with_template(template).generate_markdown_report(&analysis).unwrap(), "[summary] UTC");
+    }
+
+    #[test]
+    #[allow(clippy::len_zero)]
     fn test_json_report_generation() {
         let generator = ReportGenerator::new();
         let analysis = create_test_analysis();
@@ -559,6 +3118,971 @@ mod tests {
         assert!(json["projects"].as_array().unwrap().len() > 0);
     }
 
+    #[test]
+    fn test_min_confidence() {
+        let analysis = create_test_analysis();
+        let report = ReportGenerator::new()
+            .with_sections(vec![ReportSection::Activity, ReportSection::Insights])
+            .with_min_confidence(0.6)
+            .generate_markdown_report(&analysis)
+            .unwrap();
+        assert!(report.contains("- **Coding:** 5 times (62.5%)"));
+        assert!(!report.contains("**Debugging:**"));
+        assert!(report.contains("_3 messages classified with less than 60% confidence not shown_"));
+        // One project over two sessions is weak evidence of focus, five coding prompts are not
+        assert!(report.contains("**High Productivity:**") && !report.contains("**Single Project Focus:**"));
+
+        let json: serde_json::Value =
+            serde_json::from_str(&ReportGenerator::new().generate_json_report(&analysis).unwrap()).unwrap();
+        let titles: Vec<&str> = json["recommendations"].as_array().unwrap().iter().map(|r| r["title"].as_str().unwrap()).collect();
+        assert_eq!(titles, ["Single Project Focus", "High Productivity"]);
+        assert_eq!(json["recommendations"][0]["confidence"], 0.5);
+        assert_eq!(json["projects"][0]["activity_confidence"]["Debugging"], 0.3);
+    }
+
+    #[test]
+    fn test_insight_rules() {
+        let analysis = create_test_analysis();
+        let titles = |rules: InsightRules| -> Vec<String> {
+            ReportGenerator::new().with_insight_rules(rules).recommendations(&analysis).into_iter().map(|r| r.title).collect()
+        };
+        assert_eq!(titles(InsightRules::default()), ["Single Project Focus", "High Productivity"]);
+
+        // Two one-hour sessions are short once sessions should last 90 minutes
+        let mut rules = InsightRules::default();
+        rules.short_sessions.max_average_minutes = 90;
+        rules.single_project.enabled = false;
+        assert_eq!(titles(rules.clone()), ["Short Sessions Detected", "High Productivity"]);
+
+        rules.short_sessions.enabled = false;
+        rules.top_activity.enabled = false;
+        assert_eq!(titles(rules.clone()), ["Overall"]);
+
+        rules.overall.enabled = false;
+        let report = ReportGenerator::new()
+            .with_sections(vec![ReportSection::Insights])
+            .with_insight_rules(rules)
+            .generate_markdown_report(&analysis)
+            .unwrap();
+        assert!(report.contains("_No recommendations._"));
+    }
+
+    #[test]
+    fn test_budget_section() {
+        let mut analysis = create_test_analysis();
+        let today = NaiveDate::from_ymd_opt(2025, 6, 10).unwrap();
+        analysis.budget = Some(BudgetStatus::new(100.0, [(today, 35.0)], today));
+        let report = ReportGenerator::new().generate_summary_report(&analysis);
+        assert!(report.contains("## ⚠️ Budget"));
+        assert!(report.contains("- Spent from 2025-06-01 to 2025-06-10: $35.00 of the $100.00 monthly budget."));
+        assert!(report.contains("- Projected for the month (30 days at the same rate): $105.00, 105% of the budget."));
+        let json: serde_json::Value =
+            serde_json::from_str(&ReportGenerator::new().generate_json_report(&analysis).unwrap()).unwrap();
+        assert_eq!(json["budget"]["exceeded"], true);
+        assert_eq!(json["budget"]["month_start"], "2025-06-01");
+
+        analysis.budget = Some(BudgetStatus::new(200.0, [(today, 35.0)], today));
+        let report = ReportGenerator::new().generate_markdown_report(&analysis).unwrap();
+        assert!(!report.contains("Budget"));
+    }
+
+    #[test]
+    fn test_token_usage_report() {
+        let generator = ReportGenerator::new();
+        let mut analysis = create_test_analysis();
+        let mut token_analysis = TokenAnalysis::default();
+        let usage = crate::models::TokenUsage {
+            input_tokens: 1000,
+            output_tokens: 500,
+            ..Default::default()
+        };
+        token_analysis.total.add(&usage, 1.25);
+        token_analysis.by_model.entry("claude-sonnet-4".to_string()).or_default().add(&usage, 1.25);
+        analysis.token_analysis = Some(token_analysis);
+
+        let report = generator.generate_markdown_report(&analysis).unwrap();
+        assert!(report.contains("## 🪙 Token Usage & Cost"));
+        assert!(report.contains("**Estimated Cost:** $1.25"));
+        assert!(report.contains("| claude-sonnet-4 | 1 | 1500 | $1.25 |"));
+
+        let json: serde_json::Value = serde_json::from_str(&generator.generate_json_report(&analysis).unwrap()).unwrap();
+        assert_eq!(json["tokens"]["total"]["total_tokens"], 1500);
+        assert_eq!(json["tokens"]["by_model"]["claude-sonnet-4"]["estimated_cost_usd"], 1.25);
+    }
+
+    #[test]
+    fn test_languages_over_time() {
+        let generator = ReportGenerator::new();
+        let mut analysis = create_test_analysis();
+        let mut code_blocks = CodeBlockSummary::default();
+        for (day, language, lines) in [(12, "rust", 30), (14, "typescript", 10), (20, "rust", 10), (20, "unspecified", 2)] {
+            let mut day_blocks = CodeBlockSummary::default();
+            let stats = CodeBlockStats { blocks: 1, lines };
+            day_blocks.total = stats;
+            day_blocks.by_language.insert(language.to_string(), stats);
+            day_blocks.by_project.entry("test-project".to_string()).or_default().insert(language.to_string(), stats);
+            day_blocks.by_day.entry(NaiveDate::from_ymd_opt(2026, 10, day).unwrap()).or_default().insert(language.to_string(), stats);
+            code_blocks.merge(&day_blocks);
+        }
+        analysis.code_blocks = Some(code_blocks);
+
+        let report = generator.generate_markdown_report(&analysis).unwrap();
+        assert!(report.contains(" - **Languages Written:** rust 80%, typescript 20%"));
+        assert!(report.contains("| Week | rust | typescript | other |"));
+        assert!(report.contains("| 2026-10-12 | 30 | 10 | 0 |"));
+        assert!(report.contains("| 2026-10-19 | 10 | 0 | 2 |"));
+
+        let json: serde_json::Value = serde_json::from_str(&generator.generate_json_report(&analysis).unwrap()).unwrap();
+        assert_eq!(json["code_blocks"]["by_day"]["2026-10-20"]["rust"]["lines"], 10);
+    }
+
+    #[test]
+    fn test_history_report() {
+        let generator = ReportGenerator::new().with_timezone(FixedOffset::east_opt(0).unwrap());
+        let day = chrono::NaiveDate::from_ymd_opt(2025, 7, 1).unwrap();
+        let row = |project: &str, active_seconds| ActivityRollup {
+            start: day,
+            project: project.to_string(),
+            messages: 10,
+            active_seconds,
+            tokens: 500,
+        };
+        let activity = vec![row("api", 1800), row("web", 5400)];
+
+        let report = generator.generate_history_report(TimeGrouping::Day, &activity);
+        assert!(report.contains("| 2025-07-01 | 2.0 | 20 | 1000 | web |"));
+        let csv = generator.generate_history_csv_report(&activity).unwrap();
+        assert_eq!(csv.lines().nth(1), Some("2025-07-01,api,30,10,500"));
+        assert!(generator.generate_history_report(TimeGrouping::Week, &[]).contains("No activity found."));
+    }
+
+    #[test]
+    fn test_eval_report() {
+        let corpus = crate::eval::EvalCorpus::parse(
+            "[[activity]]\ntext = \"Thanks | bye\"\nexpected = \"coding\"\n\n[[problems]]\ntext = \"the build fails\"\nproblem = true\n",
+        )
+        .unwrap();
+        let evaluation = Evaluation::run(&corpus, &crate::activity::ActivityScoring::new(), &crate::message_analyzer::MessageAnalyzer::new());
+        let report = ReportGenerator::new().generate_eval_report(&evaluation);
+        assert!(report.contains("**Accuracy:** 0/1 (0.0%)"));
+        assert!(report.contains("| Thanks \\| bye | Coding | Other |"));
+        assert!(report.contains("**Accuracy:** 1/1 (100.0%) · **Precision:** 100.0%"));
+        assert!(!report.contains("| Prompt | Expected | Detected |"));
+    }
+
+    #[test]
+    fn test_profile_report() {
+        let profiler = Profiler::new();
+        profiler.time("scan", || ());
+        for file in ["/logs/a.jsonl", "/logs/b.jsonl"] {
+            let _parse = profiler.enter_item("parse", file);
+        }
+        profiler.time("sessionize", || profiler.time("message analysis", || ()));
+
+        let report = ReportGenerator::new().generate_profile_report(&profiler);
+        assert!(report.starts_with("# ⏱️ Profile"));
+        assert!(report.contains("| parse | 2 |"));
+        assert!(report.contains("| sessionize › message analysis | 1 |"));
+        assert!(report.contains("| other | |"));
+        assert!(report.contains("| /logs/a.jsonl |"));
+        assert!(!ReportGenerator::new().generate_profile_report(&Profiler::new()).contains("Slowest Files"));
+    }
+
+    #[test]
+    fn test_recorded_history_report() {
+        let generator = ReportGenerator::new();
+        let period = |day, hours, cost| RecordedPeriod {
+            start: chrono::NaiveDate::from_ymd_opt(2025, 7, day).unwrap(),
+            active_days: 3,
+            sessions: 4,
+            messages: 40,
+            work_time: Duration::hours(hours),
+            tokens: 1000,
+            estimated_cost: cost,
+        };
+        let periods = vec![period(7, 10, 2.5), period(14, 5, 1.0)];
+
+        let report = generator.generate_recorded_history_report(TimeGrouping::Week, &periods);
+        assert!(report.contains(&format!("| 2025-07-07 | 10.0 | 4 | 40 | $2.50 | {} |", "█".repeat(20))));
+        assert!(report.contains(&format!("| 2025-07-14 | 5.0 | 4 | 40 | $1.00 | {} |", "█".repeat(10))));
+        let csv = generator.generate_recorded_history_csv_report(&periods).unwrap();
+        assert_eq!(csv.lines().nth(2), Some("2025-07-14,3,4,40,300,1000,1.0000"));
+        assert!(generator.generate_recorded_history_report(TimeGrouping::Week, &[]).contains("No reports recorded yet."));
+    }
+
+    #[test]
+    fn test_topic_themes_in_reports() {
+        let generator = ReportGenerator::new();
+        let mut analysis = create_test_analysis();
+        let session_id = analysis.sessions[0].session_id;
+        analysis.conversation_summary = Some(crate::models::ConversationSummary {
+            total_topics: 2,
+            most_discussed_topics: vec![("rust".to_string(), 1), ("cargo".to_string(), 1)],
+            technology_usage: HashMap::new(),
+            common_problems: Vec::new(),
+            problem_confidence: HashMap::new(),
+            learning_progression: Vec::new(),
+            productivity_insights: Vec::new(),
+            overall_themes: Vec::new(),
+            topic_themes: vec![crate::models::TopicTheme {
+                name: "rust / cargo".to_string(),
+                topics: vec![("rust".to_string(), 1), ("cargo".to_string(), 1)],
+                session_ids: vec![session_id],
+                work_time: Duration::minutes(90),
+                tokens: TokenStats { estimated_cost: 2.5, ..Default::default() },
+            }],
+            follow_ups_by_technology: HashMap::from([
+                ("cargo".to_string(), FollowUpStats { discussions: 4, follow_ups: 3 }),
+                ("docker".to_string(), FollowUpStats { discussions: 2, follow_ups: 5 }),
+                ("react".to_string(), FollowUpStats { discussions: 1, follow_ups: 9 }),
+            ]),
+        });
+
+        let report = generator.generate_markdown_report(&analysis).unwrap();
+        assert!(report.contains("- **rust / cargo** (1 sessions, 1.5h, $2.50)\n  - Topics: rust (1), cargo (1)\n"));
+        assert!(report.contains(&format!("  - Recent Sessions: {}\n", &session_id.to_string()[..8])));
+        assert!(!report.contains("Most Discussed Topics"));
+        assert!(report.contains("| Technology | Discussions | Follow-ups | Per Discussion |\n|---|---:|---:|---:|\n| docker | 2 | 5 | 2.5 |\n| cargo | 4 | 3 | 0.8 |\n\n"));
+
+        let json: serde_json::Value = serde_json::from_str(&generator.generate_json_report(&analysis).unwrap()).unwrap();
+        let theme = &json["conversation_summary"]["topic_themes"][0];
+        assert_eq!(theme["name"], "rust / cargo");
+        assert_eq!(theme["session_ids"][0], session_id.to_string());
+        assert_eq!(theme["work_minutes"], 90);
+    }
+
+    #[test]
+    fn test_initiatives_section() {
+        let mut analysis = create_test_analysis();
+        analysis.initiatives = Some(vec![InitiativeRollup {
+            name: "Checkout".to_string(),
+            sessions: 0,
+            messages: 0,
+            work_time: Duration::zero(),
+            projects: Vec::new(),
+            tokens: TokenStats::default(),
+            outcomes: Vec::new(),
+        }]);
+        let generator = ReportGenerator::new();
+        let report = generator.generate_markdown_report(&analysis).unwrap();
+        assert!(report.contains("## 🎯 Initiatives"));
+        assert!(report.contains("| Checkout | 0 | 0.0 | 0 | $0.00 |  |\n"));
+        assert!(report.contains("| _Unassigned_ | 2 | 2.0 |"));
+
+        let rollup = analysis.initiatives.as_mut().unwrap();
+        rollup[0].sessions = analysis.total_sessions;
+        rollup[0].work_time = analysis.total_work_time;
+        rollup[0].projects = vec!["api".to_string(), "web".to_string()];
+        rollup[0].outcomes = vec!["retry failed charges".to_string()];
+        let report = generator.generate_markdown_report(&analysis).unwrap();
+        assert!(report.contains("| api, web |\n"));
+        assert!(!report.contains("_Unassigned_"));
+        assert!(report.contains("**Checkout Outcomes:**\n- retry failed charges\n"));
+
+        let json: serde_json::Value = serde_json::from_str(&generator.generate_json_report(&analysis).unwrap()).unwrap();
+        assert_eq!(json["initiatives"][0]["projects"][1], "web");
+        let without = ReportGenerator::new().with_sections(vec![ReportSection::Summary]);
+        assert!(!without.generate_markdown_report(&analysis).unwrap().contains("Initiatives"));
+    }
+
+    #[test]
+    fn test_compact_report() {
+        let mut analysis = create_test_analysis();
+        analysis.sessions[0].start_time = Utc.with_ymd_and_hms(2025, 7, 1, 23, 0, 0).unwrap();
+        analysis.sessions[0].end_time = Utc.with_ymd_and_hms(2025, 7, 2, 1, 30, 0).unwrap();
+        analysis.conversation_summary = Some(crate::models::ConversationSummary {
+            total_topics: 1,
+            most_discussed_topics: vec![("a rather long topic about pagination".to_string(), 3)],
+            technology_usage: HashMap::new(),
+            common_problems: Vec::new(),
+            problem_confidence: HashMap::new(),
+            learning_progression: Vec::new(),
+            productivity_insights: Vec::new(),
+            overall_themes: Vec::new(),
+            topic_themes: Vec::new(),
+            follow_ups_by_technology: HashMap::new(),
+        });
+        let generator = ReportGenerator::new().with_timezone(FixedOffset::east_opt(0).unwrap());
+        let now = Utc.with_ymd_and_hms(2025, 7, 2, 12, 0, 0).unwrap();
+
+        // Only the part of the session after local midnight counts for today
+        let report = generator.generate_compact_report(&analysis, now);
+        assert_eq!(
+            report,
+            "Today     1.5h\nPeriod    2.0h  2 sessions\nProject   2.0h  test-project\nTopic           a rather long topic abo…"
+        );
+
+        let empty = WorkAnalysis { sessions: Vec::new(), project_stats: HashMap::new(), total_sessions: 0, total_work_time: Duration::zero(), conversation_summary: None, ..analysis };
+        assert_eq!(generator.generate_compact_report(&empty, now), "Today     0.0h\nPeriod    0.0h  0 sessions\nProject         -\nTopic           -");
+    }
+
+    #[test]
+    fn test_status_line() {
+        let analysis = create_test_analysis();
+        let generator = ReportGenerator::new();
+        assert_eq!(generator.generate_status_line(&analysis), "claude 2.0h · 2 sessions · test-project");
+
+        let no_projects = WorkAnalysis { project_stats: HashMap::new(), total_sessions: 1, total_work_time: Duration::minutes(15), ..analysis };
+        assert_eq!(generator.generate_status_line(&no_projects), "claude 0.2h · 1 session");
+    }
+
+    #[test]
+    fn test_assistants_section() {
+        let mut analysis = create_test_analysis();
+        let assistant = |name: &str, sessions: usize, minutes: i64, cost: f64| AssistantStats {
+            name: name.to_string(),
+            sessions,
+            messages: sessions * 4,
+            work_time: Duration::minutes(minutes),
+            tokens: TokenStats { estimated_cost: cost, ..Default::default() },
+        };
+        analysis.assistants = Some(vec![assistant("claude-code", 3, 180, 2.5), assistant("codex", 2, 30, 0.0)]);
+        let generator = ReportGenerator::new();
+        let report = generator.generate_markdown_report(&analysis).unwrap();
+        assert!(report.contains("## 🤖 Assistants"));
+        assert!(report.contains("| claude-code | 3 | 3.0 | 60m | 12 | 0 | $2.50 |\n| codex | 2 | 0.5 | 15m | 8 | 0 | $0.00 |\n"));
+
+        let json: serde_json::Value = serde_json::from_str(&generator.generate_json_report(&analysis).unwrap()).unwrap();
+        assert_eq!(json["assistants"][1]["name"], "codex");
+        assert_eq!(json["assistants"][1]["work_minutes"], 30);
+        analysis.assistants = None;
+        assert!(!generator.generate_markdown_report(&analysis).unwrap().contains("Assistants"));
+    }
+
+    #[test]
+    fn test_machines_section() {
+        let mut analysis = create_test_analysis();
+        let machine = |name: &str, sessions: usize, minutes: i64, cost: f64| crate::models::MachineStats {
+            name: name.to_string(),
+            sessions,
+            messages: sessions * 4,
+            work_time: Duration::minutes(minutes),
+            tokens: TokenStats { estimated_cost: cost, ..Default::default() },
+        };
+        analysis.machines = Some(MachineBreakdown {
+            machines: vec![machine("laptop", 2, 90, 1.5), machine("desktop", 1, 30, 0.25)],
+            duplicate_entries: 12,
+        });
+        let generator = ReportGenerator::new();
+        let report = generator.generate_markdown_report(&analysis).unwrap();
+        assert!(report.contains("## 🖥️ Machines"));
+        assert!(report.contains("| laptop | 2 | 1.5 | 8 | 0 | $1.50 |\n| desktop | 1 | 0.5 | 4 | 0 | $0.25 |\n"));
+        assert!(report.contains("**Duplicate Entries:** 12 read from more than one machine, counted once"));
+
+        let json: serde_json::Value = serde_json::from_str(&generator.generate_json_report(&analysis).unwrap()).unwrap();
+        assert_eq!(json["machines"]["machines"][1]["name"], "desktop");
+        assert_eq!(json["machines"]["duplicate_entries"], 12);
+        let without = ReportGenerator::new().with_sections(vec![ReportSection::Summary]);
+        assert!(!without.generate_markdown_report(&analysis).unwrap().contains("Machines"));
+    }
+
+    #[test]
+    fn test_commits_section() {
+        let mut analysis = create_test_analysis();
+        let session = &analysis.sessions[0];
+        let commit = |subject: &str| crate::models::Commit {
+            hash: "0123456789abcdef0123456789abcdef01234567".to_string(),
+            time: session.end_time,
+            subject: subject.to_string(),
+        };
+        analysis.commits = Some(vec![SessionCommits {
+            session_id: session.session_id,
+            start_time: session.start_time,
+            commits: (0..12).map(|i| commit(&format!("Change {}", i))).collect(),
+        }]);
+        let generator = ReportGenerator::new().with_timezone(FixedOffset::east_opt(0).unwrap());
+
+        let report = generator.generate_markdown_report(&analysis).unwrap();
+        assert!(report.contains("## 🧾 Sessions → Commits\n\n**1 of 2 sessions led to commits (12 commits).**"));
+        assert!(report.contains("· project · 60m · 12 commits\n\n- `0123456` Change 0\n"));
+        assert!(report.contains("- `0123456` Change 9\n- …and 2 more"));
+        let json: serde_json::Value = serde_json::from_str(&generator.generate_json_report(&analysis).unwrap()).unwrap();
+        assert_eq!(json["commits"][0]["commits"][11]["subject"], "Change 11");
+
+        analysis.commits = None;
+        assert!(!generator.generate_markdown_report(&analysis).unwrap().contains("Sessions → Commits"));
+    }
+
+    #[test]
+    fn test_similar_report() {
+        let generator = ReportGenerator::new().with_timezone(FixedOffset::east_opt(0).unwrap());
+        let analysis = create_test_analysis();
+        // Formatting only; the same session stands in for the earlier one
+        let (current, earlier) = (&analysis.sessions[0], &analysis.sessions[0]);
+        let found = SimilarSession { session: earlier, score: 0.912 };
+
+        let report = generator.generate_similar_report(&[found]);
+        assert!(report.contains(&format!("| 0.91 | {} |", earlier.start_time.format("%Y-%m-%d %H:%M"))));
+        assert!(report.contains(&earlier.session_id.to_string()[..8]));
+
+        let section = generator.generate_related_sessions_section(&[(current, found)]);
+        assert!(section.contains("resembles"));
+        assert!(section.contains("(similarity 0.91)"));
+        assert!(generator.generate_related_sessions_section(&[]).contains("No earlier sessions"));
+    }
+
+    #[test]
+    fn test_plan_report_generation() {
+        let week = chrono::NaiveDate::from_ymd_opt(2025, 7, 7).unwrap();
+        let rows = vec![
+            PlanComparison { week_start: week, project: "api".to_string(), planned_hours: Some(4.0), actual_hours: 6.0 },
+            PlanComparison { week_start: week, project: "blog".to_string(), planned_hours: None, actual_hours: 0.5 },
+        ];
+
+        let report = ReportGenerator::new().generate_plan_report(&rows);
+        assert!(report.contains("## Week of 2025-07-07"));
+        assert!(report.contains("| api | 4.0h | 6.0h | 150% |"));
+        assert!(report.contains("| blog | - | 0.5h | unplanned |"));
+        assert!(report.contains("**Average Actual / Planned:** 150% over 1 estimates"));
+    }
+
+    #[test]
+    fn test_standup_report() {
+        use crate::standup::StandupProject;
+
+        let day = |d| chrono::NaiveDate::from_ymd_opt(2025, 7, d).unwrap();
+        let mut standup = Standup {
+            first_day: day(4),
+            last_day: day(6),
+            projects: vec![
+                StandupProject { project: "api".to_string(), work_time: Duration::minutes(150), topics: vec!["rust".to_string(), "fix tests".to_string()] },
+                StandupProject { project: "web".to_string(), work_time: Duration::minutes(30), topics: Vec::new() },
+            ],
+            problems: vec!["flaky test".to_string()],
+            next_steps: vec![("api".to_string(), "use a state machine".to_string())],
+        };
+
+        let report = ReportGenerator::new().generate_standup_report(&standup);
+        assert_eq!(
+            report,
+            "*Worked on* (Fri 2025-07-04 to Sun 2025-07-06)\n\
+             • *api* (2.5h): rust, fix tests\n\
+             • *web* (0.5h)\n\
+             \n*Problems tackled*\n\
+             • flaky test\n\
+             \n*Next*\n\
+             • *api*: use a state machine\n"
+        );
+
+        standup.first_day = day(6);
+        standup.projects.clear();
+        standup.problems.clear();
+        standup.next_steps.clear();
+        let report = ReportGenerator::new().generate_standup_report(&standup);
+        assert_eq!(report, "*Worked on* (Sun 2025-07-06)\n• No Claude Code sessions\n");
+    }
+
+    #[test]
+    fn test_health_report_generation() {
+        let generator = ReportGenerator::new();
+        let mut health = DataHealth::new(jst());
+        health.record_unreadable_file("broken-project");
+
+        let report = generator.generate_health_report(&health);
+
+        assert!(report.contains("# 🩺 Data Health Report"));
+        assert!(report.contains("**Coverage:** no entries found"));
+        assert!(report.contains("- **Unreadable Files:** 1\n"));
+        assert!(report.contains("- **Parse Error Rate:** 0.0% (0 errors, 0 oversized lines)\n"));
+        assert!(report.contains("| broken-project | 1 | 0 | - | - | 0.0% | 1 |"));
+    }
+
+    #[test]
+    fn test_csv_field() {
+        assert_eq!(csv_field("api"), "api");
+        assert_eq!(csv_field("a,b"), "\"a,b\"");
+        assert_eq!(csv_field("=HYPERLINK(\"http://x\")"), "\"'=HYPERLINK(\"\"http://x\"\")\"");
+        assert_eq!(csv_field("+1"), "'+1");
+        assert_eq!(csv_field("-rf"), "'-rf");
+        assert_eq!(csv_field("@SUM(A1)"), "'@SUM(A1)");
+        assert_eq!(csv_field("my-api"), "my-api");
+    }
+
+    #[test]
+    fn test_csv_report_generation() {
+        let generator = ReportGenerator::new().with_timezone(FixedOffset::east_opt(0).unwrap());
+        let mut analysis = create_test_analysis();
+        let start = Utc.with_ymd_and_hms(2025, 7, 1, 9, 0, 0).unwrap();
+        analysis.sessions[0].start_time = start;
+        analysis.sessions[0].end_time = start + Duration::minutes(90);
+        let mut second = analysis.sessions[0].clone();
+        second.project_path = "/work/a,b".to_string();
+        analysis.sessions.push(second);
+
+        let csv = generator.generate_csv_report(&analysis).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].starts_with("session_id,project,project_path,start,end,duration_minutes"));
+        assert!(lines[1].ends_with(",project,/test/project,2025-07-01T09:00:00+00:00,2025-07-01T10:30:00+00:00,90,5,3,2,0,0"));
+        assert!(lines[2].contains(",\"a,b\",\"/work/a,b\","));
+
+        let daily = generator.generate_daily_csv_report(&analysis).unwrap();
+        assert_eq!(
+            daily,
+            "date,project,sessions,duration_minutes,total_messages,user_messages,assistant_messages\n\
+             2025-07-01,\"a,b\",1,90,5,3,2\n\
+             2025-07-01,project,1,90,5,3,2\n"
+        );
+    }
+
+    #[test]
+    fn test_daily_csv_with_timezone_schedule() {
+        let mut analysis = create_test_analysis();
+        // 15:00 JST before the flight and 13:00 Pacific time after landing
+        let before = Utc.with_ymd_and_hms(2025, 7, 10, 6, 0, 0).unwrap();
+        let after = Utc.with_ymd_and_hms(2025, 7, 10, 20, 0, 0).unwrap();
+        analysis.sessions[0].start_time = before;
+        analysis.sessions[0].end_time = before + Duration::minutes(30);
+        let mut second = analysis.sessions[0].clone();
+        second.start_time = after;
+        second.end_time = after + Duration::minutes(30);
+        analysis.sessions.push(second);
+
+        let fixed = ReportGenerator::new().generate_daily_csv_report(&analysis).unwrap();
+        assert!(fixed.contains("2025-07-10,project,1,30,") && fixed.contains("2025-07-11,project,1,30,"));
+
+        let pacific = FixedOffset::west_opt(7 * 3600).unwrap();
+        let schedule = TimezoneSchedule::fixed(jst()).with_change(chrono::NaiveDate::from_ymd_opt(2025, 7, 10).unwrap(), pacific);
+        let travelling = ReportGenerator::new().with_timezone_schedule(schedule);
+        let daily = travelling.generate_daily_csv_report(&analysis).unwrap();
+        assert!(daily.contains("2025-07-10,project,2,60,"));
+
+        analysis.sessions[1].merged_session_ids = vec![Uuid::parse_str("5abda76b-79c3-406d-bea9-e607520f0047").unwrap()];
+        let report = travelling.generate_markdown_report(&analysis).unwrap();
+        assert!(report.contains("**Merged Restarts:** 1 (5abda76b)"));
+        assert!(report.contains("**Time:** 2025-07-10 13:00 UTC-07:00 → 13:30 UTC-07:00"));
+        assert!(report.contains("**Time:** 2025-07-10 15:00 JST → 15:30 JST"));
+    }
+
+    #[test]
+    fn test_json_timeseries() {
+        let generator = ReportGenerator::new().with_timezone(FixedOffset::east_opt(0).unwrap());
+        let mut analysis = create_test_analysis();
+        let start = Utc.with_ymd_and_hms(2025, 7, 2, 9, 0, 0).unwrap();
+        analysis.sessions[0].start_time = start;
+        analysis.sessions[0].end_time = start + Duration::minutes(90);
+        let mut tokens = TokenAnalysis::default();
+        tokens.by_day.insert(start.date_naive(), TokenStats { usage: crate::models::TokenUsage { input_tokens: 100, output_tokens: 20, ..Default::default() }, ..Default::default() });
+        analysis.token_analysis = Some(tokens);
+        let now = Utc.with_ymd_and_hms(2025, 7, 10, 12, 0, 0).unwrap();
+
+        let range = (Some(Utc.with_ymd_and_hms(2025, 7, 1, 0, 0, 0).unwrap()), Some(Utc.with_ymd_and_hms(2025, 7, 3, 23, 59, 59).unwrap()));
+        let series: serde_json::Value = serde_json::from_str(&generator.generate_json_timeseries(&analysis, range, now).unwrap()).unwrap();
+        assert_eq!(
+            series,
+            serde_json::json!([
+                {"date": "2025-07-01", "sessions": 0, "messages": 0, "hours": 0.0, "tokens": 0, "top_project": null},
+                {"date": "2025-07-02", "sessions": 1, "messages": 5, "hours": 1.5, "tokens": 120, "top_project": "project"},
+                {"date": "2025-07-03", "sessions": 0, "messages": 0, "hours": 0.0, "tokens": 0, "top_project": null}
+            ])
+        );
+
+        // Without a range the series runs from the first session to today
+        let series: serde_json::Value = serde_json::from_str(&generator.generate_json_timeseries(&analysis, (None, None), now).unwrap()).unwrap();
+        let dates: Vec<&str> = series.as_array().unwrap().iter().map(|day| day["date"].as_str().unwrap()).collect();
+        assert_eq!((dates.len(), dates[0], dates[8]), (9, "2025-07-02", "2025-07-10"));
+        let empty = WorkAnalysis { sessions: Vec::new(), ..analysis };
+        assert_eq!(generator.generate_json_timeseries(&empty, (None, None), now).unwrap(), "[]");
+    }
+
+    #[test]
+    fn test_listing_reports() {
+        let generator = ReportGenerator::new().with_timezone(FixedOffset::east_opt(0).unwrap());
+        let mut analysis = create_test_analysis();
+        let start = Utc.with_ymd_and_hms(2025, 7, 1, 9, 0, 0).unwrap();
+        analysis.sessions[0].start_time = start;
+        analysis.sessions[0].end_time = start + Duration::minutes(45);
+
+        let sessions = generator.generate_sessions_report(&analysis, SessionOrder::Start, None);
+        assert!(sessions.contains("| Start (UTC) | End | Project | Duration | Messages | Tokens |"));
+        assert!(sessions.contains("| 2025-07-01 09:00 | 09:45 | project | 45m | 5 | 0 |"));
+
+        let projects = generator.generate_projects_report(&analysis);
+        assert!(projects.contains("| test-project | 2 | 10 | 2.0h |"));
+        assert_eq!(
+            generator.generate_projects_csv_report(&analysis).unwrap(),
+            "project,sessions,messages,work_minutes\ntest-project,2,10,120\n"
+        );
+
+        let summary = generator.generate_summary_report(&analysis);
+        assert!(summary.contains("## 📊 Executive Summary"));
+        assert!(!summary.contains("## 🚀 Project Breakdown"));
+    }
+
+    #[test]
+    fn test_heatmap() {
+        let generator = ReportGenerator::new().with_timezone(FixedOffset::east_opt(0).unwrap());
+        let mut analysis = create_test_analysis();
+        let mut heatmap = ActivityHeatmap::default();
+        // Tuesday 2025-07-01
+        for minute in [0, 10, 20] {
+            heatmap.add(&Utc.with_ymd_and_hms(2025, 7, 1, 14, minute, 0).unwrap());
+        }
+        heatmap.add(&Utc.with_ymd_and_hms(2025, 7, 6, 9, 0, 0).unwrap());
+        analysis.heatmap = Some(heatmap);
+
+        let report = generator.generate_markdown_report(&analysis).unwrap();
+        assert!(report.contains("**Busiest Hour of the Week:** Tue 14:00 UTC (3 messages)"));
+        assert!(report.contains("**Messages by Weekday and Hour (UTC):**"));
+        let row = |weekday: &str| -> Vec<String> {
+            let line = report.lines().find(|line| line.starts_with(&format!("| {} |", weekday))).unwrap();
+            line.split('|').skip(2).take(25).map(|cell| cell.trim().to_string()).collect()
+        };
+        let tuesday = row("Tue");
+        assert_eq!((tuesday[14].as_str(), tuesday[13].as_str(), tuesday[24].as_str()), ("3", "", "3"));
+        assert_eq!((row("Sun")[9].as_str(), row("Sun")[24].as_str()), ("1", "1"));
+        assert!(row("Mon")[..24].iter().all(String::is_empty));
+
+        let json: serde_json::Value = serde_json::from_str(&generator.generate_json_report(&analysis).unwrap()).unwrap();
+        assert_eq!(json["heatmap"]["weekdays"][1], "Tue");
+        assert_eq!(json["heatmap"]["messages"][1][14], 3);
+        assert_eq!(json["heatmap"]["messages"][6][9], 1);
+    }
+
+    #[test]
+    fn test_html_report() {
+        let generator = ReportGenerator::new().with_timezone(FixedOffset::east_opt(0).unwrap());
+        let mut analysis = create_test_analysis();
+        let session = |project: &str, day: u32, minutes: i64| {
+            let start_time = Utc.with_ymd_and_hms(2025, 7, day, 9, 0, 0).unwrap();
+            WorkSession {
+                project_path: format!("/work/{}", project),
+                start_time,
+                end_time: start_time + Duration::minutes(minutes),
+                ..analysis.sessions[0].clone()
+            }
+        };
+        // Weeks of Monday 2025-06-30, 07-07 and 07-14
+        analysis.sessions = vec![session("api", 1, 90), session("api", 3, 30), session("web<x>", 8, 60), session("api", 20, 30)];
+
+        let html = generator.generate_html_report(&analysis);
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("<th title=\"Week of 2025-06-30\">06/30</th><th title=\"Week of 2025-07-07\">07/07</th><th title=\"Week of 2025-07-14\">07/14</th>"));
+        let row = |project: &str| html.lines().find(|line| line.starts_with(&format!("<tr><th>{}</th>", project))).unwrap();
+        let api = row("api");
+        assert!(api.contains("<td class=\"l4\" title=\"api, week of 2025-06-30: 2.0h in 2 sessions\">2.0</td>"));
+        assert!(api.contains("<td class=\"l0\" title=\"api, week of 2025-07-07: no sessions\"></td>"));
+        assert!(api.contains("<td class=\"l1\" title=\"api, week of 2025-07-14: 0.5h in 1 session\">0.5</td>"));
+        assert!(api.ends_with("<td class=\"total\">2.5h</td></tr>"));
+        // Names are escaped, and the most worked-on project comes first
+        let web = row("web&lt;x&gt;");
+        assert!(web.contains("<td class=\"l2\" title=\"web&lt;x&gt;, week of 2025-07-07: 1.0h in 1 session\">1.0</td>"));
+        assert!(html.find(api).unwrap() < html.find(web).unwrap());
+
+        analysis.sessions.clear();
+        assert!(generator.generate_html_report(&analysis).contains("<p>No sessions in this period.</p>"));
+    }
+
+    #[test]
+    fn test_top_sessions() {
+        let generator = ReportGenerator::new().with_timezone(FixedOffset::east_opt(0).unwrap());
+        let mut analysis = create_test_analysis();
+        let start = Utc.with_ymd_and_hms(2025, 7, 1, 9, 0, 0).unwrap();
+        analysis.sessions[0].start_time = start;
+        analysis.sessions[0].end_time = start + Duration::minutes(45);
+        let mut chatty = analysis.sessions[0].clone();
+        chatty.session_id = Uuid::new_v4();
+        chatty.start_time = start + Duration::hours(2);
+        chatty.end_time = chatty.start_time + Duration::minutes(20);
+        chatty.total_messages = 30;
+        analysis.sessions.push(chatty);
+
+        let by_messages = generator.generate_sessions_report(&analysis, SessionOrder::Messages, Some(1));
+        assert!(by_messages.contains("Sorted by messages, largest first."));
+        assert!(by_messages.contains("| 2025-07-01 11:00 | 11:20 | project | 20m | 30 | 0 |"));
+        assert!(!by_messages.contains("| 45m |"));
+        let csv = generator.generate_sessions_csv_report(&analysis, SessionOrder::Duration, None).unwrap();
+        let rows: Vec<&str> = csv.lines().skip(1).collect();
+        assert!(rows[0].contains(",45,") && rows[1].contains(",20,"));
+
+        let report = generator.generate_markdown_report(&analysis).unwrap();
+        let top = &report[report.find("## 🏆 Top Sessions").unwrap()..];
+        let longest = top.find("### ⏱️ Longest Sessions").unwrap();
+        let most_messages = top.find("### 💬 Most Messages").unwrap();
+        assert!(top[longest..most_messages].find("| 45m |").unwrap() < top[longest..most_messages].find("| 20m |").unwrap());
+        assert!(top[most_messages..].find("| 20m |").unwrap() < top[most_messages..].find("| 45m |").unwrap());
+        // Without token data there is nothing to rank by tokens
+        assert!(!top.contains("### 🪙 Most Tokens"));
+
+        assert_eq!("Tokens".parse::<SessionOrder>().unwrap(), SessionOrder::Tokens);
+        assert!("cost".parse::<SessionOrder>().unwrap_err().to_string().starts_with("Invalid session order 'cost'"));
+    }
+
+    #[test]
+    fn test_project_index_report() {
+        let generator = ReportGenerator::new();
+        let analysis = create_test_analysis();
+        let file_names = HashMap::from([("test-project".to_string(), "test-project.md".to_string())]);
+
+        let index = generator.generate_project_index_report(&analysis, &file_names);
+        assert!(index.starts_with("# 🤖 Claude Work Analysis Report"));
+        assert!(index.contains("| test-project | 2 | 10 | 2.0h | [test-project](test-project.md) |"));
+    }
+
+    #[test]
+    fn test_report_sections() {
+        let analysis = create_test_analysis();
+        let reporter = ReportGenerator::new().with_sections(vec![ReportSection::Insights, ReportSection::Summary]);
+        let report = reporter.generate_markdown_report(&analysis).unwrap();
+
+        assert!(report.starts_with("# 🤖 Claude Work Analysis Report"));
+        // Sections keep the report's own order
+        let summary = report.find("## 📊 Executive Summary").unwrap();
+        assert!(summary < report.find("## 💡 Insights & Recommendations").unwrap());
+        assert!(!report.contains("## 🚀 Project Breakdown"));
+        assert!(!report.contains("## 💬 Recent Sessions"));
+
+        assert_eq!("Group-By".parse::<ReportSection>().unwrap(), ReportSection::GroupBy);
+        assert!("charts".parse::<ReportSection>().unwrap_err().to_string().starts_with("Invalid report section 'charts'"));
+    }
+
+    #[tokio::test]
+    async fn test_section_content_levels() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        crate::sample::write_sample(temp_dir.path()).unwrap();
+        let files = crate::scanner::ProjectScanner::new().scan_all_projects(&[temp_dir.path().to_path_buf()]).unwrap();
+
+        // Thinking and a failed tool call, which only the outline keeps of the content
+        let path = files.iter().find(|path| path.ends_with("session-3.jsonl")).unwrap();
+        let content = std::fs::read_to_string(path).unwrap();
+        let last: serde_json::Value = serde_json::from_str(content.lines().last().unwrap()).unwrap();
+        let time = |minutes: i64| (last["timestamp"].as_str().unwrap().parse::<DateTime<Utc>>().unwrap() + Duration::minutes(minutes)).to_rfc3339();
+        let result = serde_json::json!({
+            "parentUuid": last["uuid"], "isSidechain": false, "userType": "external", "cwd": last["cwd"],
+            "sessionId": last["sessionId"], "version": "1.0.0", "type": "user", "uuid": Uuid::new_v4(), "timestamp": time(1),
+            "message": {"role": "user", "content": [{"type": "tool_result", "tool_use_id": "toolu_2", "content": "not found"}]},
+            "toolUseResult": "Error: not found",
+        });
+        let thinking = serde_json::json!({
+            "parentUuid": result["uuid"], "isSidechain": false, "userType": "external", "cwd": last["cwd"],
+            "sessionId": last["sessionId"], "version": "1.0.0", "type": "assistant", "uuid": Uuid::new_v4(), "timestamp": time(2),
+            "message": {"role": "assistant", "content": [{"type": "thinking", "thinking": "Try another path"}, {"type": "text", "text": "Retrying"}]},
+        });
+        std::fs::write(path, format!("{}{}\n{}\n", content, result, thinking)).unwrap();
+
+        let analyze = |level: ContentLevel| {
+            let files = files.clone();
+            async move {
+                let parser = crate::parser::JsonlParser::new().with_content_level(level);
+                let mut entries = Vec::new();
+                for path in &files {
+                    entries.extend(parser.parse_file(path).await.unwrap());
+                }
+                crate::analyzer::WorkAnalyzer::new().analyze_entries(&entries).unwrap()
+            }
+        };
+        let full = analyze(ContentLevel::Full).await;
+        for section in ReportSection::ALL.into_iter().filter(|section| section.content_level() < ContentLevel::Full) {
+            let partial = analyze(section.content_level()).await;
+            let reporter = ReportGenerator::new().with_sections(vec![section]);
+            assert_eq!(
+                reporter.generate_markdown_report(&partial).unwrap(),
+                reporter.generate_markdown_report(&full).unwrap(),
+                "{} section",
+                section.name()
+            );
+        }
+        let summary = ReportGenerator::new().generate_summary_report(&analyze(ContentLevel::Outline).await);
+        assert!(summary.contains("**Extended Thinking:** 1 blocks"));
+
+        let reporter = ReportGenerator::new().with_sections(vec![ReportSection::Time, ReportSection::Tools]);
+        assert_eq!(reporter.markdown_content_level(), ContentLevel::Outline);
+        assert_eq!(ReportGenerator::new().markdown_content_level(), ContentLevel::Full);
+    }
+
+    #[test]
+    fn test_no_activity_report() {
+        let generator = ReportGenerator::new().with_timezone(FixedOffset::east_opt(0).unwrap());
+        let day = |d| Utc.with_ymd_and_hms(2025, 7, d, 0, 0, 0).unwrap();
+        let filter = TimeRangeFilter::new(Some(day(10)), Some(day(12)), Some("api".to_string()));
+        let coverage = FilterCoverage {
+            total_entries: 40,
+            matched_entries: 0,
+            first_entry: Some(day(1)),
+            last_entry: Some(day(5)),
+        };
+
+        let report = generator.generate_no_activity_report(&filter, &coverage);
+        assert!(report.contains("## 🔍 No Activity Found"));
+        assert!(report.contains("- **From:** 2025-07-10 00:00 UTC"));
+        assert!(report.contains("- **Project:** api"));
+        assert!(report.contains("- **Logs Cover:** 2025-07-01 00:00 UTC to 2025-07-05 00:00 UTC"));
+        assert!(report.contains("starts after the latest log entry"));
+        assert!(!report.contains("1970"));
+
+        let sparse = FilterCoverage { matched_entries: 2, ..coverage };
+        let report = generator.generate_no_activity_report(&TimeRangeFilter::default(), &sparse);
+        assert!(report.contains("2 log entries matched the filters, but too few to form a session"));
+        assert!(report.contains("- **From:** any time"));
+
+        let report = generator.generate_no_activity_report(&TimeRangeFilter::default(), &FilterCoverage::default());
+        assert!(report.contains("No log entries were found at all"));
+
+        let mut empty = create_test_analysis();
+        empty.total_sessions = 0;
+        let json: serde_json::Value = serde_json::from_str(&generator.generate_json_report(&empty).unwrap()).unwrap();
+        assert!(json["summary"]["time_range"].is_null());
+    }
+
+    #[test]
+    fn test_comparison_report() {
+        use crate::compare::{Change, PeriodComparison, ProjectChange};
+
+        let generator = ReportGenerator::new().with_timezone(FixedOffset::east_opt(0).unwrap());
+        let day = |d| Utc.with_ymd_and_hms(2025, 7, d, 0, 0, 0).unwrap();
+        let current = TimeRangeFilter::new(Some(day(15)), Some(day(28)), None);
+        let baseline = TimeRangeFilter::new(Some(day(1)), Some(day(14)), None);
+        let comparison = PeriodComparison {
+            hours: Change { baseline: 10.0, current: 12.5 },
+            sessions: Change { baseline: 4, current: 3 },
+            messages: Change { baseline: 0, current: 30 },
+            activity_mix: [("Coding".to_string(), Change { baseline: 40.0, current: 55.0 })].into(),
+            new_technologies: vec!["React".to_string()],
+            dropped_technologies: Vec::new(),
+            kept_technologies: vec!["Rust".to_string()],
+            projects: vec![ProjectChange {
+                project: "api".to_string(),
+                hours: Change { baseline: 10.0, current: 12.5 },
+                sessions: Change { baseline: 4, current: 3 },
+            }],
+        };
+
+        let report = generator.generate_comparison_report(&comparison, &baseline, &current);
+        assert!(report.contains("**Current:** 2025-07-15 to 2025-07-28"));
+        assert!(report.contains("| Hours | 10.0h | 12.5h | +2.5h (+25%) |"));
+        assert!(report.contains("| Sessions | 4 | 3 | -1 (-25%) |"));
+        assert!(report.contains("| Messages | 0 | 30 | +30 |"));
+        assert!(report.contains("| Coding | 40.0% | 55.0% | +15.0 pts |"));
+        assert!(report.contains("- **New:** React"));
+        assert!(report.contains("- **Dropped:** -"));
+        assert!(report.contains("| api | 10.0h | 12.5h | +2.5h | 4 → 3 |"));
+
+        let json: serde_json::Value =
+            serde_json::from_str(&generator.generate_comparison_json(&comparison, &baseline, &current).unwrap()).unwrap();
+        assert_eq!(json["hours"]["ratio"], 0.25);
+        assert!(json["messages"]["ratio"].is_null());
+        assert_eq!(json["projects"][0]["name"], "api");
+    }
+
+    #[test]
+    fn test_weekly_report() {
+        use crate::compare::{Change, PeriodComparison, ProjectChange};
+        use crate::weekly::WeeklyReport;
+
+        let week = chrono::NaiveDate::from_ymd_opt(2025, 6, 30).unwrap();
+        let mut weekly = WeeklyReport {
+            week_start: week,
+            comparison: PeriodComparison {
+                hours: Change { baseline: 4.0, current: 6.0 },
+                sessions: Change { baseline: 2, current: 3 },
+                messages: Change { baseline: 20, current: 30 },
+                activity_mix: BTreeMap::new(),
+                new_technologies: Vec::new(),
+                dropped_technologies: Vec::new(),
+                kept_technologies: Vec::new(),
+                projects: vec![ProjectChange {
+                    project: "api".to_string(),
+                    hours: Change { baseline: 4.0, current: 6.0 },
+                    sessions: Change { baseline: 2, current: 3 },
+                }],
+            },
+            projects: Change { baseline: 1, current: 1 },
+            goals: vec![PlanComparison { week_start: week, project: "api".to_string(), planned_hours: Some(8.0), actual_hours: 6.0 }],
+            top_topics: vec![("rust".to_string(), 3)],
+            biggest_project: Some(("api".to_string(), Duration::hours(6))),
+        };
+
+        let report = ReportGenerator::new().generate_weekly_report(&weekly);
+        assert!(report.starts_with("# 🗓️ Weekly Report 2025-W27 (2025-06-30 to 2025-07-06)"));
+        assert!(report.contains("| Hours | 4.0h | 6.0h | +2.0h (+50%) |"));
+        assert!(report.contains("| Projects | 1 | 1 | +0 (+0%) |"));
+        assert!(report.contains("**Biggest Project:** api (6.0h)"));
+        assert!(report.contains("| api | 8.0h | 6.0h | 75% |"));
+        assert!(report.contains("- rust (3 sessions)"));
+        assert!(report.contains("| api | 4.0h | 6.0h | +2.0h | 2 → 3 |"));
+
+        weekly.goals[0].planned_hours = None;
+        let report = ReportGenerator::new().generate_weekly_report(&weekly);
+        assert!(report.contains("No hours planned for this week"));
+        assert!(report.contains("| api | - | 6.0h | unplanned |"));
+    }
+
+    #[test]
+    fn test_time_buckets_section() {
+        let generator = ReportGenerator::new();
+        let mut analysis = create_test_analysis();
+        analysis.time_buckets = Some(TimeBuckets {
+            grouping: TimeGrouping::Month,
+            buckets: vec![crate::models::TimeBucket {
+                start: chrono::NaiveDate::from_ymd_opt(2025, 7, 1).unwrap(),
+                sessions: 3,
+                messages: 42,
+                work_time: Duration::minutes(150),
+                tokens: TokenStats::default(),
+            }],
+        });
+
+        let report = generator.generate_markdown_report(&analysis).unwrap();
+        assert!(report.contains("## 📅 Activity by Month"));
+        assert!(report.contains("| 2025-07 | 3 | 2.5 | 42 | 0 | $0.00 |"));
+
+        let json: serde_json::Value = serde_json::from_str(&generator.generate_json_report(&analysis).unwrap()).unwrap();
+        assert_eq!(json["time_buckets"]["grouping"], "month");
+        assert_eq!(json["time_buckets"]["buckets"][0]["work_minutes"], 150);
+    }
+
+    #[test]
+    fn test_log_gaps_section() {
+        let generator = ReportGenerator::new();
+        let mut analysis = create_test_analysis();
+        let day = |d| chrono::NaiveDate::from_ymd_opt(2025, 7, d).unwrap();
+        let bucket = |start| crate::models::TimeBucket {
+            start,
+            sessions: 1,
+            messages: 5,
+            work_time: Duration::minutes(60),
+            tokens: TokenStats::default(),
+        };
+        analysis.time_buckets = Some(TimeBuckets { grouping: TimeGrouping::Week, buckets: vec![bucket(day(7)), bucket(day(21))] });
+        analysis.log_gaps = Some(LogGaps::find([day(8), day(22)], Some(day(3)), Some(day(8)), 3));
+
+        let report = generator.generate_markdown_report(&analysis).unwrap();
+        let warning = report.find("## ⚠️ Log Coverage").unwrap();
+        assert!(warning < report.find("## 📊 Executive Summary").unwrap());
+        assert!(report.contains("- No logs before 2025-07-08: the 5 days of the period from 2025-07-03 are not covered.\n"));
+        assert!(report.contains("- 2025-07-09 to 2025-07-21: 13 days without logs between days with activity.\n"));
+        assert!(report.contains("`claude-work-analysis archive`"));
+        // Both weeks miss days, the first one before the logs start
+        assert!(report.contains("| 2025-07-07 ⚠️ | 1 |") && report.contains("| 2025-07-21 ⚠️ | 1 |"));
+
+        let json: serde_json::Value = serde_json::from_str(&generator.generate_json_report(&analysis).unwrap()).unwrap();
+        assert_eq!(json["log_gaps"]["gaps"][0]["days"], 13);
+
+        analysis.log_gaps = Some(LogGaps::default());
+        let report = generator.generate_markdown_report(&analysis).unwrap();
+        assert!(!report.contains("Log Coverage") && report.contains("| 2025-07-07 | 1 |"));
+    }
+
+    #[test]
+    fn test_windows_project_paths() {
+        let generator = ReportGenerator::new().with_timezone(FixedOffset::east_opt(0).unwrap());
+        let mut analysis = create_test_analysis();
+        analysis.sessions[0].project_path = r"C:\Users\me\work\api".to_string();
+
+        let sessions = generator.generate_sessions_report(&analysis, SessionOrder::Start, None);
+        assert!(sessions.contains("| api |"));
+
+        let csv = generator.generate_csv_report(&analysis).unwrap();
+        assert!(csv.contains(r",api,C:\Users\me\work\api,"));
+    }
+
+    #[test]
+    fn test_format_skew() {
+        assert_eq!(format_skew(Duration::seconds(45)), "45s");
+        assert_eq!(format_skew(Duration::minutes(12)), "12m");
+        assert_eq!(format_skew(Duration::minutes(185)), "3h 05m");
+    }
+
     #[test]
     fn test_executive_summary() {
         let generator = ReportGenerator::new();
@@ -0,0 +1,100 @@
+use chrono::{DateTime, Utc};
+use std::collections::VecDeque;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use crate::cache::FileFingerprint;
+use crate::pipeline::PipelineOutput;
+
+/// Results kept by [`ResultCache::default`]
+pub const DEFAULT_CAPACITY: usize = 8;
+
+/// Size and modification time of every log file a query read
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct DataFingerprint {
+    files: Vec<(PathBuf, Option<FileFingerprint>)>,
+}
+
+impl DataFingerprint {
+    pub(crate) async fn of(log_files: &[PathBuf]) -> Self {
+        let mut files = Vec::with_capacity(log_files.len());
+        for path in log_files {
+            files.push((path.clone(), FileFingerprint::of(path).await));
+        }
+        Self { files }
+    }
+
+    /// When a file was last written; no entry of the files is later
+    pub(crate) fn last_modified(&self) -> Option<DateTime<Utc>> {
+        let nanos = self.files.iter().filter_map(|(_, fingerprint)| *fingerprint).map(|f| f.modified_nanos).max()?;
+        Some(DateTime::from_timestamp_nanos(i64::try_from(nanos).ok()?))
+    }
+}
+
+struct CachedResult {
+    key: String,
+    data: DataFingerprint,
+    output: PipelineOutput,
+}
+
+/// Analyses of recent queries, reused while the log files they read are unchanged
+///
+/// Results are keyed by the query: the projects directories, the filter and the
+/// analyzer settings. Adding, removing or writing any log file makes them stale,
+/// so repeating a question, as MCP conversations often do, skips the parsing and
+/// analysis. The least recently used result is dropped once `capacity` are kept.
+pub struct ResultCache {
+    capacity: usize,
+    /// Least recently used first
+    results: Mutex<VecDeque<CachedResult>>,
+}
+
+impl ResultCache {
+    pub fn new(capacity: usize) -> Self {
+        Self { capacity, results: Mutex::new(VecDeque::new()) }
+    }
+
+    pub fn len(&self) -> usize {
+        self.results.lock().unwrap_or_else(|e| e.into_inner()).len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn clear(&self) {
+        self.results.lock().unwrap_or_else(|e| e.into_inner()).clear();
+    }
+
+    /// The result of `key` if it was computed from the same data
+    pub(crate) fn get(&self, key: &str, data: &DataFingerprint) -> Option<PipelineOutput> {
+        let mut results = self.results.lock().unwrap_or_else(|e| e.into_inner());
+        let position = results.iter().position(|result| result.key == key)?;
+        if results[position].data != *data {
+            results.remove(position);
+            return None;
+        }
+        let result = results.remove(position)?;
+        let output = result.output.clone();
+        results.push_back(result);
+        Some(output)
+    }
+
+    pub(crate) fn insert(&self, key: String, data: DataFingerprint, output: &PipelineOutput) {
+        if self.capacity == 0 {
+            return;
+        }
+        let mut results = self.results.lock().unwrap_or_else(|e| e.into_inner());
+        results.retain(|result| result.key != key);
+        while results.len() >= self.capacity {
+            results.pop_front();
+        }
+        results.push_back(CachedResult { key, data, output: output.clone() });
+    }
+}
+
+impl Default for ResultCache {
+    fn default() -> Self {
+        Self::new(DEFAULT_CAPACITY)
+    }
+}
@@ -0,0 +1,134 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration, Utc};
+use serde_json::{json, Value};
+use std::path::Path;
+use uuid::Uuid;
+
+/// Conversation of one demo session: (user prompt, assistant reply, tool used)
+type Script = [(&'static str, &'static str, &'static str)];
+
+const API_SCRIPT: &Script = &[
+    ("Add pagination to the users endpoint", "I'll add limit and offset parameters to the handler.", "Edit"),
+    ("Please run the tests", "All 42 tests pass.", "Bash"),
+    ("Fix the error when offset is negative", "The handler now returns 400 for a negative offset.", "Edit"),
+];
+
+const WEB_SCRIPT: &Script = &[
+    ("Create a settings page component", "I created SettingsPage with a form for the profile.", "Write"),
+    ("Refactor the form validation into a hook", "Validation now lives in useFormValidation.", "Edit"),
+    ("Write documentation for the new hook", "I documented the hook in the README.", "Write"),
+];
+
+/// Write a small demo dataset in the layout of `~/.claude/projects`
+///
+/// Two projects with a few sessions over the last three days, so every report
+/// section has something to show.
+pub fn write_sample(projects_dir: &Path) -> Result<()> {
+    let now = Utc::now();
+    let sessions = [
+        ("/home/demo/work/api", "session-1.jsonl", API_SCRIPT, now - Duration::days(2) - Duration::hours(3)),
+        ("/home/demo/work/web", "session-2.jsonl", WEB_SCRIPT, now - Duration::days(1) - Duration::hours(5)),
+        ("/home/demo/work/api", "session-3.jsonl", API_SCRIPT, now - Duration::hours(4)),
+    ];
+
+    for (cwd, file_name, script, start) in sessions {
+        let project_dir = projects_dir.join(crate::scanner::ProjectScanner::encode_project_path(cwd));
+        std::fs::create_dir_all(&project_dir)
+            .with_context(|| format!("Failed to create {}", project_dir.display()))?;
+
+        let lines: Vec<String> = session_entries(cwd, script, start)
+            .iter()
+            .map(Value::to_string)
+            .collect();
+        let path = project_dir.join(file_name);
+        std::fs::write(&path, lines.join("\n") + "\n")
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+    }
+
+    Ok(())
+}
+
+/// Log entries of one session, a few minutes apart
+fn session_entries(cwd: &str, script: &Script, start: DateTime<Utc>) -> Vec<Value> {
+    let session_id = Uuid::new_v4();
+    let mut entries = Vec::new();
+    let mut parent: Option<Uuid> = None;
+    let mut timestamp = start;
+
+    for (turn, (prompt, reply, tool)) in script.iter().enumerate() {
+        let user_id = Uuid::new_v4();
+        entries.push(json!({
+            "parentUuid": parent,
+            "isSidechain": false,
+            "userType": "external",
+            "cwd": cwd,
+            "sessionId": session_id,
+            "version": "1.0.0",
+            "type": "user",
+            "message": {"role": "user", "content": prompt},
+            "uuid": user_id,
+            "timestamp": timestamp,
+        }));
+
+        timestamp += Duration::minutes(4);
+        let assistant_id = Uuid::new_v4();
+        entries.push(json!({
+            "parentUuid": user_id,
+            "isSidechain": false,
+            "userType": "external",
+            "cwd": cwd,
+            "sessionId": session_id,
+            "version": "1.0.0",
+            "type": "assistant",
+            "message": {
+                "role": "assistant",
+                "content": [
+                    {"type": "text", "text": reply},
+                    {"type": "tool_use", "id": format!("toolu_{}", turn), "name": tool, "input": {}},
+                ],
+                "model": "claude-sonnet-4-20250514",
+                "usage": {
+                    "input_tokens": 1200,
+                    "output_tokens": 350,
+                    "cache_creation_input_tokens": 0,
+                    "cache_read_input_tokens": 8000,
+                },
+            },
+            "uuid": assistant_id,
+            "timestamp": timestamp,
+        }));
+
+        parent = Some(assistant_id);
+        timestamp += Duration::minutes(6);
+    }
+
+    entries
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyzer::WorkAnalyzer;
+    use crate::parser::JsonlParser;
+    use crate::scanner::ProjectScanner;
+    use tempfile::TempDir;
+
+    #[tokio::test]
+    async fn test_sample_is_analyzable() {
+        let temp_dir = TempDir::new().unwrap();
+        write_sample(temp_dir.path()).unwrap();
+        // Writing again replaces the sample instead of adding to it
+        write_sample(temp_dir.path()).unwrap();
+
+        let parser = JsonlParser::new();
+        let mut entries = Vec::new();
+        for path in ProjectScanner::new().scan_projects(temp_dir.path()).unwrap() {
+            entries.extend(parser.parse_file(&path).await.unwrap());
+        }
+        assert_eq!(entries.len(), 18);
+
+        let analysis = WorkAnalyzer::new().analyze_entries(&entries).unwrap();
+        assert_eq!(analysis.total_sessions, 3);
+        assert_eq!(analysis.project_stats.len(), 2);
+    }
+}
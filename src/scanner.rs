@@ -1,28 +1,52 @@
 use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::io::{BufRead, BufReader};
 use std::path::{Path, PathBuf};
+use tokio_util::sync::CancellationToken;
 use walkdir::WalkDir;
 
+use crate::error::Error;
+use crate::paths;
+
+/// Lines read for a cwd before a log file is taken to record none; summaries
+/// and snapshots come first and have none
+const CWD_LOOKUP_LINES: usize = 50;
+
 pub struct ProjectScanner {
     /// Maximum depth to traverse in directory structure
     max_depth: usize,
+    /// Stops a scan whose result is no longer wanted
+    cancellation: CancellationToken,
+    /// Project directories to read
+    patterns: ProjectPatterns,
 }
 
 impl ProjectScanner {
     pub fn new() -> Self {
-        Self { max_depth: 3 }
+        Self::with_max_depth(3)
     }
 
     pub fn with_max_depth(max_depth: usize) -> Self {
-        Self { max_depth }
+        Self { max_depth, cancellation: CancellationToken::new(), patterns: ProjectPatterns::default() }
+    }
+
+    /// Fail scans with [`Error::Cancelled`] once `cancellation` is cancelled
+    pub fn with_cancellation(mut self, cancellation: CancellationToken) -> Self {
+        self.cancellation = cancellation;
+        self
+    }
+
+    /// Read only the project directories `patterns` select
+    pub fn with_patterns(mut self, patterns: ProjectPatterns) -> Self {
+        self.patterns = patterns;
+        self
     }
 
     /// Scan the Claude projects directory and return all JSONL files
     pub fn scan_projects(&self, projects_dir: &Path) -> Result<Vec<PathBuf>> {
         if !projects_dir.exists() {
-            return Err(anyhow::anyhow!(
-                "Projects directory does not exist: {}",
-                projects_dir.display()
-            ));
+            return Err(Self::missing_projects_dir_error(projects_dir));
         }
 
         let mut jsonl_files = Vec::new();
@@ -30,29 +54,33 @@ impl ProjectScanner {
         for entry in WalkDir::new(projects_dir)
             .max_depth(self.max_depth)
             .into_iter()
+            .filter_entry(|e| e.depth() != 1 || !e.file_type().is_dir() || self.patterns.selects(&e.file_name().to_string_lossy()))
             .filter_map(|e| e.ok())
         {
+            if self.cancellation.is_cancelled() {
+                return Err(Error::Cancelled.into());
+            }
             let path = entry.path();
             if self.is_jsonl_file(path) {
                 jsonl_files.push(path.to_path_buf());
             }
         }
 
-        jsonl_files.sort_by(|a, b| {
-            // Sort by modification time, newest first
-            let a_metadata = a.metadata().unwrap_or_else(|_| {
-                std::fs::metadata("/dev/null").unwrap()
-            });
-            let b_metadata = b.metadata().unwrap_or_else(|_| {
-                std::fs::metadata("/dev/null").unwrap()
-            });
-            
-            b_metadata
-                .modified()
-                .unwrap_or(std::time::UNIX_EPOCH)
-                .cmp(&a_metadata.modified().unwrap_or(std::time::UNIX_EPOCH))
-        });
+        sort_newest_first(&mut jsonl_files);
+        Ok(jsonl_files)
+    }
 
+    /// Scan several projects directories, e.g. logs synced from other machines
+    ///
+    /// A directory listed more than once is scanned once. The files of all
+    /// directories are listed newest first, so that of an entry copied into
+    /// several of them the latest version is read first.
+    pub fn scan_all_projects(&self, projects_dirs: &[PathBuf]) -> Result<Vec<PathBuf>> {
+        let mut jsonl_files = Vec::new();
+        for projects_dir in unique_dirs(projects_dirs) {
+            jsonl_files.extend(self.scan_projects(projects_dir)?);
+        }
+        sort_newest_first(&mut jsonl_files);
         Ok(jsonl_files)
     }
 
@@ -72,6 +100,9 @@ impl ProjectScanner {
             .into_iter()
             .filter_map(|e| e.ok())
         {
+            if self.cancellation.is_cancelled() {
+                return Err(Error::Cancelled.into());
+            }
             let path = entry.path();
             if self.is_jsonl_file(path) {
                 jsonl_files.push(path.to_path_buf());
@@ -83,34 +114,83 @@ impl ProjectScanner {
 
     /// Extract project name from the encoded directory path
     pub fn extract_project_name(project_dir: &Path) -> Option<String> {
-        project_dir
-            .file_name()
-            .and_then(|name| name.to_str())
-            .map(|name| {
-                // Claude encodes paths like: -Users-user-projects-project-name
-                // We want to extract the meaningful part
-                if name.starts_with('-') {
-                    let parts: Vec<&str> = name.split('-').collect();
-                    if parts.len() >= 3 {
-                        // Take the last 2-3 segments as they're usually the meaningful project path
-                        let meaningful_parts = &parts[parts.len().saturating_sub(3)..];
-                        meaningful_parts.join("/")
-                    } else {
-                        name.to_string()
-                    }
-                } else {
-                    name.to_string()
-                }
+        // A cwd from Windows logs is not split by `Path` on other platforms
+        let name = match project_dir.to_str() {
+            Some(path) => paths::last_segment(path)?,
+            None => project_dir.file_name()?.to_str()?,
+        };
+
+        // Claude encodes paths like: -Users-user-projects-project-name, or
+        // C--Users-user-project-name for C:\Users\user\project-name
+        if Self::is_encoded_project_dir(name) {
+            let parts: Vec<&str> = name.split('-').filter(|part| !part.is_empty()).collect();
+            if parts.len() >= 3 {
+                // Take the last 2-3 segments as they're usually the meaningful project path
+                let meaningful_parts = &parts[parts.len().saturating_sub(3)..];
+                return Some(meaningful_parts.join("/"));
+            }
+        }
+        Some(name.to_string())
+    }
+
+    /// Name of the project a directory holds logs of, from the paths its entries
+    /// record: `my-awesome-project` for `-home-me-my-awesome-project`
+    ///
+    /// Encoding a path loses its hyphens and slashes, so the name is only guessed
+    /// from the directory name with [`Self::extract_project_name`] when no entry
+    /// records a cwd in the directory's project.
+    pub fn project_name_of_dir(project_dir: &Path) -> Option<String> {
+        let logged = project_dir.file_name().and_then(|name| name.to_str()).and_then(|dir_name| {
+            ProjectScanner::new()
+                .scan_project(project_dir)
+                .unwrap_or_default()
+                .iter()
+                .find_map(|path| Self::logged_project_path(path, dir_name))
+        });
+        match logged {
+            Some(project_path) => paths::last_segment(&project_path).map(str::to_string),
+            None => Self::extract_project_name(project_dir),
+        }
+    }
+
+    /// The path a log file's project directory `dir_name` encodes, from the first
+    /// cwd recorded in or below it
+    fn logged_project_path(path: &Path, dir_name: &str) -> Option<String> {
+        let file = std::fs::File::open(path).ok()?;
+        BufReader::new(file)
+            .lines()
+            .map_while(|line| line.ok())
+            .take(CWD_LOOKUP_LINES)
+            .filter_map(|line| serde_json::from_str::<serde_json::Value>(&line).ok())
+            .filter_map(|entry| entry.get("cwd")?.as_str().map(str::to_string))
+            .find_map(|cwd| {
+                paths::ancestors(&cwd)
+                    .into_iter()
+                    .find(|ancestor| Self::encode_project_path(ancestor).eq_ignore_ascii_case(dir_name))
             })
     }
 
+    /// Whether a directory name is an encoded Unix (`-home-...`) or Windows (`C--Users-...`) path
+    fn is_encoded_project_dir(name: &str) -> bool {
+        let bytes = name.as_bytes();
+        name.starts_with('-')
+            || (bytes.len() >= 3 && bytes[0].is_ascii_alphabetic() && bytes[1] == b'-' && bytes[2] == b'-')
+    }
+
+    /// Encode a working directory the way Claude names its project directories
+    ///
+    /// Every character other than an ASCII letter or digit becomes `-`, so
+    /// `/home/user/work/api` is stored under `-home-user-work-api`.
+    pub fn encode_project_path(path: &str) -> String {
+        path.chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+            .collect()
+    }
+
     /// Get all project directories in the Claude projects directory
     pub fn get_project_directories(&self, projects_dir: &Path) -> Result<Vec<PathBuf>> {
         if !projects_dir.exists() {
-            return Err(anyhow::anyhow!(
-                "Projects directory does not exist: {}",
-                projects_dir.display()
-            ));
+            return Err(Self::missing_projects_dir_error(projects_dir));
         }
 
         let mut project_dirs = Vec::new();
@@ -124,7 +204,7 @@ impl ProjectScanner {
             if path.is_dir() {
                 // Skip hidden directories and current/parent directory references
                 if let Some(dir_name) = path.file_name().and_then(|n| n.to_str()) {
-                    if !dir_name.starts_with('.') {
+                    if !dir_name.starts_with('.') && self.patterns.selects(dir_name) {
                         project_dirs.push(path);
                     }
                 }
@@ -137,6 +217,82 @@ impl ProjectScanner {
         Ok(project_dirs)
     }
 
+    /// Project directories of every projects directory, in the order the roots are given
+    pub fn get_all_project_directories(&self, projects_dirs: &[PathBuf]) -> Result<Vec<PathBuf>> {
+        let mut project_dirs = Vec::new();
+        for projects_dir in unique_dirs(projects_dirs) {
+            project_dirs.extend(self.get_project_directories(projects_dir)?);
+        }
+        Ok(project_dirs)
+    }
+
+    /// Where Claude keeps project logs: `$CLAUDE_CONFIG_DIR/projects`, else `~/.claude/projects`
+    pub fn default_projects_dir() -> Option<PathBuf> {
+        if let Some(config_dir) = std::env::var_os("CLAUDE_CONFIG_DIR").filter(|dir| !dir.is_empty()) {
+            return Some(PathBuf::from(config_dir).join("projects"));
+        }
+        dirs::home_dir().map(|home| home.join(".claude").join("projects"))
+    }
+
+    /// Other places Claude project logs are commonly found
+    pub fn alternative_projects_dirs() -> Vec<PathBuf> {
+        let mut candidates = Vec::new();
+        if let Some(home) = dirs::home_dir() {
+            candidates.push(home.join(".claude").join("projects"));
+        }
+        // $XDG_CONFIG_HOME/claude, or ~/.config/claude
+        if let Some(config_dir) = dirs::config_dir() {
+            candidates.push(config_dir.join("claude").join("projects"));
+        }
+        // Under WSL, logs of the Windows install live in the Windows profile
+        candidates.extend(Self::windows_profile_projects_dirs(Path::new("/mnt/c/Users")));
+        candidates
+    }
+
+    /// `.claude/projects` of every profile in a Windows `Users` directory
+    fn windows_profile_projects_dirs(users_dir: &Path) -> Vec<PathBuf> {
+        let Ok(profiles) = std::fs::read_dir(users_dir) else {
+            return Vec::new();
+        };
+        let mut dirs: Vec<PathBuf> = profiles
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path().join(".claude").join("projects"))
+            .collect();
+        dirs.sort();
+        dirs
+    }
+
+    /// Error for a missing projects directory that points at logs found elsewhere
+    pub fn missing_projects_dir_error(projects_dir: &Path) -> anyhow::Error {
+        let found: Vec<PathBuf> = Self::alternative_projects_dirs()
+            .into_iter()
+            .filter(|dir| dir != projects_dir && dir.is_dir())
+            .collect();
+        Error::ProjectsDirNotFound {
+            path: projects_dir.to_path_buf(),
+            found,
+        }
+        .into()
+    }
+
+    pub(crate) fn missing_projects_dir_message(projects_dir: &Path, found: &[PathBuf]) -> String {
+        let mut message = format!("Projects directory does not exist: {}", projects_dir.display());
+        if found.is_empty() {
+            message.push_str(
+                "\nNo Claude logs were found. Run `claude-work-analysis --create-sample` to see a report built from demo data.",
+            );
+        } else {
+            message.push_str("\nClaude logs were found at:");
+            for dir in found {
+                message.push_str(&format!("\n  {}", dir.display()));
+            }
+            message.push_str(
+                "\nPass one with --projects-dir, or set CLAUDE_CONFIG_DIR to the directory that contains `projects`, to use them.",
+            );
+        }
+        message
+    }
+
     /// Check if a path represents a JSONL file
     fn is_jsonl_file(&self, path: &Path) -> bool {
         path.is_file() 
@@ -147,6 +303,166 @@ impl ProjectScanner {
     }
 }
 
+/// Glob patterns choosing the project directories to read by name, e.g.
+/// `*work*` for `-home-me-work-api`
+///
+/// `*` matches any run of characters and `?` any one character.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ProjectPatterns {
+    include: Vec<String>,
+    exclude: Vec<String>,
+}
+
+impl ProjectPatterns {
+    pub fn new(include: Vec<String>, exclude: Vec<String>) -> Self {
+        Self { include, exclude }
+    }
+
+    /// Whether a project directory is read: it matches an include pattern, if
+    /// any are given, and no exclude pattern
+    pub fn selects(&self, dir_name: &str) -> bool {
+        (self.include.is_empty() || self.include.iter().any(|pattern| glob_matches(pattern, dir_name)))
+            && !self.exclude.iter().any(|pattern| glob_matches(pattern, dir_name))
+    }
+
+    /// Whether a log file under one of `projects_dirs` is in a selected project directory
+    pub fn selects_file(&self, projects_dirs: &[PathBuf], path: &Path) -> bool {
+        let project_dir = projects_dirs
+            .iter()
+            .filter_map(|root| path.strip_prefix(root).ok())
+            .find_map(|relative| relative.components().next())
+            .and_then(|component| component.as_os_str().to_str());
+        project_dir.is_none_or(|name| self.selects(name))
+    }
+}
+
+/// Checkouts of the same project in different places, counted as one
+///
+/// Each root maps the sessions in it or below it to the project path (or name)
+/// they are counted under, e.g. a second clone of a repository to the first.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ProjectAliases {
+    roots: Vec<(String, String)>,
+}
+
+impl ProjectAliases {
+    pub fn new(roots: impl IntoIterator<Item = (String, String)>) -> Self {
+        Self { roots: roots.into_iter().collect() }
+    }
+
+    /// The project a session's project path is counted under: that of the deepest root containing it
+    pub fn resolve<'a>(&'a self, project_path: &'a str) -> &'a str {
+        self.roots
+            .iter()
+            .filter(|(root, _)| paths::is_within(project_path, root))
+            .max_by_key(|(root, _)| paths::segments(root).count())
+            .map_or(project_path, |(_, project)| project.as_str())
+    }
+}
+
+/// Display names of the project paths sessions are counted under
+///
+/// A project is named after its last path segment, with as many parent
+/// segments as it takes to tell it apart where projects share one: `a/api` and
+/// `b/api` for `~/a/api` and `~/b/api`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct ProjectNames {
+    names: HashMap<String, String>,
+}
+
+impl ProjectNames {
+    pub fn new<'a>(project_paths: impl IntoIterator<Item = &'a str>) -> Self {
+        let mut by_last_segment: BTreeMap<String, BTreeSet<&str>> = BTreeMap::new();
+        for path in project_paths {
+            by_last_segment.entry(Self::short_name(path)).or_default().insert(path);
+        }
+
+        let mut names = HashMap::new();
+        for (name, paths) in by_last_segment {
+            if paths.len() == 1 {
+                names.extend(paths.into_iter().map(|path| (path.to_string(), name.clone())));
+                continue;
+            }
+            let segments: Vec<Vec<&str>> = paths.iter().map(|path| paths::segments(path).collect()).collect();
+            let longest = segments.iter().map(Vec::len).max().unwrap_or(0);
+            let qualified = |count: usize| -> Vec<String> {
+                segments.iter().map(|segments| segments[segments.len().saturating_sub(count)..].join("/")).collect()
+            };
+            // Paths differing only in their separators or root keep their full path
+            let unique = (2..=longest)
+                .map(qualified)
+                .find(|qualified| qualified.iter().collect::<HashSet<_>>().len() == qualified.len())
+                .unwrap_or_else(|| paths.iter().map(|path| path.to_string()).collect());
+            names.extend(paths.into_iter().map(str::to_string).zip(unique));
+        }
+        Self { names }
+    }
+
+    /// Display name of the sessions of `project_path`, its short name if it is not one of the names' paths
+    pub fn name(&self, project_path: &str) -> String {
+        self.names.get(project_path).cloned().unwrap_or_else(|| Self::short_name(project_path))
+    }
+
+    /// `by_path` keyed by the display names of its project paths instead
+    pub fn rename<V>(&self, by_path: HashMap<String, V>) -> HashMap<String, V> {
+        by_path.into_iter().map(|(path, value)| (self.name(&path), value)).collect()
+    }
+
+    fn short_name(project_path: &str) -> String {
+        ProjectScanner::extract_project_name(Path::new(project_path)).unwrap_or_else(|| project_path.to_string())
+    }
+}
+
+/// Whether all of `text` matches `pattern` of `*` and `?` wildcards
+fn glob_matches(pattern: &str, text: &str) -> bool {
+    let (pattern, text): (Vec<char>, Vec<char>) = (pattern.chars().collect(), text.chars().collect());
+    let (mut p, mut t) = (0, 0);
+    // Position after the last `*` and the text position it was tried at
+    let mut backtrack = None;
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                p += 1;
+                backtrack = Some((p, t));
+            }
+            Some(&c) if c == '?' || c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match backtrack {
+                // Let the last `*` swallow one more character
+                Some((star_p, star_t)) => {
+                    p = star_p;
+                    t = star_t + 1;
+                    backtrack = Some((star_p, star_t + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// Sort files by modification time, newest first; files of the same time keep their order
+fn sort_newest_first(files: &mut [PathBuf]) {
+    let modified = |path: &PathBuf| {
+        path.metadata().and_then(|metadata| metadata.modified()).unwrap_or(std::time::UNIX_EPOCH)
+    };
+    files.sort_by_cached_key(|path| std::cmp::Reverse(modified(path)));
+}
+
+/// Directories in their given order, without repeats
+fn unique_dirs(dirs: &[PathBuf]) -> Vec<&PathBuf> {
+    let mut unique: Vec<&PathBuf> = Vec::new();
+    for dir in dirs {
+        if !unique.contains(&dir) {
+            unique.push(dir);
+        }
+    }
+    unique
+}
+
 impl Default for ProjectScanner {
     fn default() -> Self {
         Self::new()
@@ -158,6 +474,12 @@ mod tests {
     use super::*;
     use tempfile::TempDir;
 
+    #[test]
+    fn test_encode_project_path() {
+        assert_eq!(ProjectScanner::encode_project_path("/home/user/work/api"), "-home-user-work-api");
+        assert_eq!(ProjectScanner::encode_project_path("/home/user/my.app_v2"), "-home-user-my-app-v2");
+    }
+
     #[test]
     fn test_extract_project_name() {
         let path = Path::new("-Users-user-projects-my-awesome-project");
@@ -165,6 +487,48 @@ mod tests {
         assert_eq!(result, Some("my/awesome/project".to_string()));
     }
 
+    #[test]
+    fn test_project_name_of_dir() {
+        let temp_dir = TempDir::new().unwrap();
+        let project_dir = temp_dir.path().join("-Users-me-my-awesome-project");
+        std::fs::create_dir(&project_dir).unwrap();
+        // The name is guessed from the directory until an entry records the path
+        assert_eq!(ProjectScanner::project_name_of_dir(&project_dir), Some("my/awesome/project".to_string()));
+
+        std::fs::write(
+            project_dir.join("session.jsonl"),
+            "{\"type\":\"summary\"}\n{\"type\":\"user\",\"cwd\":\"/Users/me/my-awesome-project/src\"}\n",
+        )
+        .unwrap();
+        assert_eq!(ProjectScanner::project_name_of_dir(&project_dir), Some("my-awesome-project".to_string()));
+    }
+
+    #[test]
+    fn test_project_aliases() {
+        let aliases = ProjectAliases::new([
+            ("/home/me/clones/api".to_string(), "/home/me/work/api".to_string()),
+            ("/home/me/clones".to_string(), "scratch".to_string()),
+        ]);
+        assert_eq!(aliases.resolve("/home/me/clones/api"), "/home/me/work/api");
+        assert_eq!(aliases.resolve("/home/me/clones/api-v2"), "scratch");
+        assert_eq!(aliases.resolve("/home/me/work/api"), "/home/me/work/api");
+    }
+
+    #[test]
+    fn test_windows_project_names() {
+        assert_eq!(ProjectScanner::encode_project_path(r"C:\Users\me\work\api"), "C--Users-me-work-api");
+        assert_eq!(
+            ProjectScanner::extract_project_name(Path::new("C--Users-me-work-api")),
+            Some("me/work/api".to_string())
+        );
+        // A cwd recorded on Windows
+        assert_eq!(
+            ProjectScanner::extract_project_name(Path::new(r"C:\Users\me\work\api")),
+            Some("api".to_string())
+        );
+        assert_eq!(ProjectScanner::extract_project_name(Path::new("/home/me/api/")), Some("api".to_string()));
+    }
+
     #[test]
     fn test_is_jsonl_file() {
         use std::fs::File;
@@ -184,6 +548,84 @@ mod tests {
         assert!(!scanner.is_jsonl_file(Path::new("nonexistent.jsonl")));
     }
 
+    #[test]
+    fn test_windows_profile_projects_dirs() {
+        let users_dir = TempDir::new().unwrap();
+        std::fs::create_dir_all(users_dir.path().join("alice").join(".claude").join("projects")).unwrap();
+        std::fs::create_dir(users_dir.path().join("Public")).unwrap();
+
+        let dirs = ProjectScanner::windows_profile_projects_dirs(users_dir.path());
+        assert_eq!(dirs.len(), 2);
+        assert!(dirs.contains(&users_dir.path().join("alice").join(".claude").join("projects")));
+
+        assert!(ProjectScanner::windows_profile_projects_dirs(&users_dir.path().join("missing")).is_empty());
+    }
+
+    fn patterns_selected_file(scanner: &ProjectScanner, root: &Path, project: &str) -> bool {
+        scanner.patterns.selects_file(&[root.to_path_buf()], &root.join(project).join("session.jsonl"))
+    }
+
+    #[test]
+    fn test_project_patterns() {
+        let temp_dir = TempDir::new().unwrap();
+        for project in ["-home-me-work-api", "-home-me-work-scratch", "-home-me-blog"] {
+            std::fs::create_dir(temp_dir.path().join(project)).unwrap();
+            std::fs::write(temp_dir.path().join(project).join("session.jsonl"), "").unwrap();
+        }
+        let patterns = ProjectPatterns::new(vec!["*work*".to_string()], vec!["*-scratch*".to_string()]);
+        let scanner = ProjectScanner::new().with_patterns(patterns);
+
+        let files = scanner.scan_projects(temp_dir.path()).unwrap();
+        assert_eq!(files, [temp_dir.path().join("-home-me-work-api").join("session.jsonl")]);
+        let dirs = scanner.get_project_directories(temp_dir.path()).unwrap();
+        assert_eq!(dirs, [temp_dir.path().join("-home-me-work-api")]);
+        assert_eq!(ProjectScanner::new().scan_projects(temp_dir.path()).unwrap().len(), 3);
+
+        assert!(patterns_selected_file(&scanner, temp_dir.path(), "-home-me-work-api"));
+        assert!(!patterns_selected_file(&scanner, temp_dir.path(), "-home-me-blog"));
+
+        assert!(glob_matches("-home-?e-*", "-home-me-blog"));
+        assert!(glob_matches("*", ""));
+        assert!(!glob_matches("*work", "-home-me-work-api"));
+    }
+
+    #[test]
+    fn test_missing_projects_dir_message() {
+        let missing = Path::new("/home/me/.claude/projects");
+
+        let message = ProjectScanner::missing_projects_dir_message(missing, &[]);
+        assert!(message.contains("/home/me/.claude/projects"));
+        assert!(message.contains("--create-sample"));
+
+        let found = [PathBuf::from("/home/me/.config/claude/projects")];
+        let message = ProjectScanner::missing_projects_dir_message(missing, &found);
+        assert!(message.contains("/home/me/.config/claude/projects"));
+        assert!(message.contains("CLAUDE_CONFIG_DIR"));
+    }
+
+    #[test]
+    fn test_scan_missing_directory() {
+        let temp_dir = TempDir::new().unwrap();
+        let error = ProjectScanner::new().scan_projects(&temp_dir.path().join("projects")).unwrap_err();
+        assert!(error.to_string().starts_with("Projects directory does not exist"));
+    }
+
+    #[test]
+    fn test_scan_all_projects() {
+        let laptop = TempDir::new().unwrap();
+        let desktop = TempDir::new().unwrap();
+        for (root, file) in [(&laptop, "a.jsonl"), (&desktop, "b.jsonl")] {
+            let project = root.path().join("-home-me-api");
+            std::fs::create_dir(&project).unwrap();
+            std::fs::File::create(project.join(file)).unwrap();
+        }
+
+        let scanner = ProjectScanner::new();
+        let roots = [laptop.path().to_path_buf(), desktop.path().to_path_buf(), laptop.path().to_path_buf()];
+        assert_eq!(scanner.scan_all_projects(&roots).unwrap().len(), 2);
+        assert_eq!(scanner.get_all_project_directories(&roots).unwrap().len(), 2);
+    }
+
     #[test]
     fn test_scan_empty_directory() {
         let temp_dir = TempDir::new().unwrap();
@@ -192,4 +634,18 @@ mod tests {
         let result = scanner.scan_projects(temp_dir.path()).unwrap();
         assert!(result.is_empty());
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_project_names() {
+        let names = ProjectNames::new(["/home/me/a/api", "/home/me/b/api", "/home/me/web", "/srv/x/a/api"]);
+        assert_eq!(names.name("/home/me/web"), "web");
+        assert_eq!(names.name("/home/me/a/api"), "me/a/api");
+        assert_eq!(names.name("/srv/x/a/api"), "x/a/api");
+        assert_eq!(names.name("/home/me/b/api"), "me/b/api");
+
+        let names = ProjectNames::new(["/home/me/a/api", "/home/me/b/api", "/home/me/b/api"]);
+        assert_eq!((names.name("/home/me/a/api"), names.name("/home/me/b/api")), ("a/api".to_string(), "b/api".to_string()));
+        // Paths that were not named keep their short name
+        assert_eq!(names.name("/elsewhere/api"), "api");
+    }
+}
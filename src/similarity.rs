@@ -0,0 +1,334 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use crate::models::{EntryType, MessageContentVariant, WorkSession};
+use crate::paths;
+
+/// Characters of user prompts included in a session's text
+const MAX_PROMPT_CHARS: usize = 2000;
+
+/// Similarity from which an earlier session is listed as related in reports
+pub const RELATED_MIN_SCORE: f32 = 0.85;
+
+/// Turns texts into vectors whose cosine similarity reflects how related they are
+pub trait Embedder {
+    /// Identifies the model; cached vectors of another model are not reused
+    fn model_name(&self) -> &str;
+    fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>>;
+}
+
+/// Text a session is embedded from: project, summary and the start of the user's prompts
+pub fn session_text(session: &WorkSession) -> String {
+    let mut parts = vec![paths::last_segment(&session.project_path).unwrap_or("Unknown").to_string()];
+    if let Some(summary) = &session.summary {
+        parts.push(summary.overall_summary.clone());
+        parts.extend(summary.main_topics.iter().cloned());
+        parts.extend(summary.technologies_mentioned.iter().cloned());
+    }
+
+    let mut prompts = String::new();
+    for entry in session.entries.iter().filter(|entry| matches!(entry.entry_type, EntryType::User)) {
+        let text = match &entry.message.content {
+            MessageContentVariant::String(text) => text.clone(),
+            MessageContentVariant::Array(blocks) => blocks
+                .iter()
+                .filter(|block| block.content_type == "text")
+                .filter_map(|block| block.text.clone())
+                .collect::<Vec<_>>()
+                .join(" "),
+        };
+        if !text.trim().is_empty() {
+            prompts.push_str(text.trim());
+            prompts.push('\n');
+        }
+        if prompts.chars().count() >= MAX_PROMPT_CHARS {
+            break;
+        }
+    }
+    parts.push(prompts.chars().take(MAX_PROMPT_CHARS).collect());
+    parts.retain(|part| !part.is_empty());
+    parts.join("\n")
+}
+
+pub fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norm = |v: &[f32]| v.iter().map(|x| x * x).sum::<f32>().sqrt();
+    let norms = norm(a) * norm(b);
+    if norms == 0.0 {
+        0.0
+    } else {
+        dot / norms
+    }
+}
+
+/// A session and how similar it is to the one searched for
+#[derive(Debug, Clone, Copy)]
+pub struct SimilarSession<'a> {
+    pub session: &'a WorkSession,
+    pub score: f32,
+}
+
+/// Vectors computed earlier, stored as JSON and keyed by session id, start and message count
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct CachedEmbeddings {
+    model: String,
+    vectors: HashMap<String, Vec<f32>>,
+}
+
+/// Sessions with their embeddings, searchable by similarity
+pub struct SessionIndex<'a> {
+    sessions: Vec<&'a WorkSession>,
+    vectors: Vec<Vec<f32>>,
+}
+
+impl<'a> SessionIndex<'a> {
+    /// Embed every session, reusing and updating the vectors cached at `cache_path`
+    pub fn build(sessions: &'a [WorkSession], embedder: &impl Embedder, cache_path: Option<&Path>) -> Result<Self> {
+        // A log session split by the session gap yields several work sessions with its id
+        let key = |session: &WorkSession| {
+            format!("{}:{}:{}", session.session_id, session.start_time.timestamp(), session.total_messages)
+        };
+        let mut cache = match cache_path.filter(|path| path.exists()) {
+            Some(path) => {
+                let content = std::fs::read_to_string(path)
+                    .with_context(|| format!("Failed to read embedding cache: {}", path.display()))?;
+                serde_json::from_str(&content).unwrap_or_default()
+            }
+            None => CachedEmbeddings::default(),
+        };
+        if cache.model != embedder.model_name() {
+            cache = CachedEmbeddings {
+                model: embedder.model_name().to_string(),
+                vectors: HashMap::new(),
+            };
+        }
+
+        let missing: Vec<&WorkSession> = sessions.iter().filter(|s| !cache.vectors.contains_key(&key(s))).collect();
+        if !missing.is_empty() {
+            let texts: Vec<String> = missing.iter().map(|session| session_text(session)).collect();
+            let vectors = embedder.embed(&texts)?;
+            for (session, vector) in missing.iter().zip(vectors) {
+                cache.vectors.insert(key(session), vector);
+            }
+            if let Some(path) = cache_path {
+                if let Some(parent) = path.parent() {
+                    std::fs::create_dir_all(parent)?;
+                }
+                std::fs::write(path, serde_json::to_string(&cache)?)
+                    .with_context(|| format!("Failed to write embedding cache: {}", path.display()))?;
+            }
+        }
+
+        let vectors = sessions.iter().map(|session| cache.vectors[&key(session)].clone()).collect();
+        Ok(Self { sessions: sessions.iter().collect(), vectors })
+    }
+
+    /// The session whose id is or starts with `id`, as shown in reports
+    ///
+    /// Of the work sessions split from one log session, the latest is returned.
+    pub fn find(&self, id: &str) -> Result<&'a WorkSession> {
+        let id = id.to_lowercase();
+        let matching: Vec<&'a WorkSession> = self
+            .sessions
+            .iter()
+            .copied()
+            .filter(|session| session.session_id.to_string().starts_with(&id))
+            .collect();
+        let Some(latest) = matching.iter().copied().max_by_key(|session| session.start_time) else {
+            return Err(anyhow::anyhow!("No session with id '{}'", id));
+        };
+        if matching.iter().any(|session| session.session_id != latest.session_id) {
+            return Err(anyhow::anyhow!("Session id '{}' is ambiguous; give more characters", id));
+        }
+        Ok(latest)
+    }
+
+    /// Sessions most similar to `session`, best first, excluding the session itself
+    pub fn similar_to(&self, session: &WorkSession, limit: usize) -> Vec<SimilarSession<'a>> {
+        let Some(index) = self.position(session) else {
+            return Vec::new();
+        };
+        self.ranked(&self.vectors[index], limit, |other| other.session_id != session.session_id)
+    }
+
+    /// Sessions most similar to a text, e.g. a description of the problem at hand
+    pub fn search(&self, text: &str, embedder: &impl Embedder, limit: usize) -> Result<Vec<SimilarSession<'a>>> {
+        let vector = embedder
+            .embed(&[text.to_string()])?
+            .pop()
+            .ok_or_else(|| anyhow::anyhow!("The embedding model returned no vector"))?;
+        Ok(self.ranked(&vector, limit, |_| true))
+    }
+
+    /// The most similar session that started before `session`, if it is at least
+    /// `RELATED_MIN_SCORE` similar
+    pub fn related_before(&self, session: &WorkSession) -> Option<SimilarSession<'a>> {
+        let index = self.position(session)?;
+        self.ranked(&self.vectors[index], 1, |other| other.start_time < session.start_time)
+            .pop()
+            .filter(|similar| similar.score >= RELATED_MIN_SCORE)
+    }
+
+    fn position(&self, session: &WorkSession) -> Option<usize> {
+        self.sessions
+            .iter()
+            .position(|other| other.session_id == session.session_id && other.start_time == session.start_time)
+    }
+
+    fn ranked(&self, vector: &[f32], limit: usize, include: impl Fn(&WorkSession) -> bool) -> Vec<SimilarSession<'a>> {
+        let mut ranked: Vec<SimilarSession> = self
+            .sessions
+            .iter()
+            .zip(&self.vectors)
+            .filter(|(session, _)| include(session))
+            .map(|(session, other)| SimilarSession { session, score: cosine_similarity(vector, other) })
+            .collect();
+        ranked.sort_by(|a, b| b.score.total_cmp(&a.score));
+        ranked.truncate(limit);
+        ranked
+    }
+}
+
+/// `~/.cache/claude-work-analysis/embeddings.json` (or the platform equivalent)
+pub fn default_cache_path() -> Option<PathBuf> {
+    dirs::cache_dir().map(|dir| dir.join("claude-work-analysis").join("embeddings.json"))
+}
+
+/// Multilingual E5 model run locally through ONNX Runtime
+///
+/// The model is downloaded into the cache directory on first use.
+#[cfg(feature = "embeddings")]
+pub struct FastEmbedder {
+    model: fastembed::TextEmbedding,
+}
+
+#[cfg(feature = "embeddings")]
+impl FastEmbedder {
+    pub fn new() -> Result<Self> {
+        use fastembed::{EmbeddingModel, InitOptions, TextEmbedding};
+
+        let mut options = InitOptions::new(EmbeddingModel::MultilingualE5Small).with_show_download_progress(true);
+        if let Some(dir) = dirs::cache_dir() {
+            options = options.with_cache_dir(dir.join("claude-work-analysis").join("models"));
+        }
+        let model = TextEmbedding::try_new(options).context("Failed to load the embedding model")?;
+        Ok(Self { model })
+    }
+}
+
+#[cfg(feature = "embeddings")]
+impl Embedder for FastEmbedder {
+    fn model_name(&self) -> &str {
+        "multilingual-e5-small"
+    }
+
+    fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+        // E5 expects a prefix; "query: " is the one for symmetric similarity
+        let texts: Vec<String> = texts.iter().map(|text| format!("query: {}", text)).collect();
+        self.model.embed(texts, None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::SessionSummary;
+    use chrono::{Duration, TimeZone, Utc};
+    use uuid::Uuid;
+
+    /// Counts of a few words, enough to tell topics apart
+    struct WordCounts;
+
+    impl Embedder for WordCounts {
+        fn model_name(&self) -> &str {
+            "word-counts"
+        }
+
+        fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+            let words = ["rust", "borrow", "css", "layout", "sql"];
+            Ok(texts
+                .iter()
+                .map(|text| {
+                    let text = text.to_lowercase();
+                    words.iter().map(|word| text.matches(word).count() as f32).collect()
+                })
+                .collect())
+        }
+    }
+
+    fn session(hours_ago: i64, overall_summary: &str) -> WorkSession {
+        let start_time = Utc.with_ymd_and_hms(2025, 7, 10, 12, 0, 0).unwrap() - Duration::hours(hours_ago);
+        WorkSession {
+            session_id: Uuid::new_v4(),
+            project_path: "/home/me/app".to_string(),
+            start_time,
+            end_time: start_time + Duration::minutes(30),
+            entries: Vec::new(),
+            total_messages: 4,
+            user_messages: 2,
+            assistant_messages: 2,
+            summary: Some(SessionSummary {
+                main_topics: Vec::new(),
+                topic_mentions: HashMap::new(),
+                key_discussions: Vec::new(),
+                technologies_mentioned: Vec::new(),
+                problems_addressed: Vec::new(),
+                problem_confidence: HashMap::new(),
+                solutions_proposed: Vec::new(),
+                learning_moments: Vec::new(),
+                overall_summary: overall_summary.to_string(),
+            }),
+            merged_session_ids: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_similar_sessions() {
+        let sessions = vec![
+            session(48, "Rust borrow checker errors"),
+            session(24, "CSS layout of the header"),
+            session(0, "Another Rust borrow problem"),
+        ];
+        let dir = tempfile::tempdir().unwrap();
+        let cache_path = dir.path().join("embeddings.json");
+        let index = SessionIndex::build(&sessions, &WordCounts, Some(&cache_path)).unwrap();
+
+        let similar = index.similar_to(&sessions[2], 5);
+        assert_eq!(similar.len(), 2);
+        assert_eq!(similar[0].session.session_id, sessions[0].session_id);
+        assert!(similar[0].score > 0.99);
+
+        let related = index.related_before(&sessions[2]).unwrap();
+        assert_eq!(related.session.session_id, sessions[0].session_id);
+        assert!(index.related_before(&sessions[1]).is_none());
+
+        let found = index.search("flex layout broken in css", &WordCounts, 1).unwrap();
+        assert_eq!(found[0].session.session_id, sessions[1].session_id);
+
+        let prefix = &sessions[1].session_id.to_string()[..8];
+        assert_eq!(index.find(prefix).unwrap().session_id, sessions[1].session_id);
+        assert!(index.find("zzzz").is_err());
+
+        // Parts of one log session split by the session gap are told apart by their start
+        let mut split = sessions.clone();
+        split[1].session_id = split[0].session_id;
+        let split_index = SessionIndex::build(&split, &WordCounts, None).unwrap();
+        let id = split[0].session_id.to_string();
+        assert_eq!(split_index.find(&id).unwrap().start_time, split[1].start_time);
+        assert_eq!(split_index.related_before(&split[2]).unwrap().session.start_time, split[0].start_time);
+
+        // Cached vectors are reused without asking the embedder again
+        struct Unavailable;
+        impl Embedder for Unavailable {
+            fn model_name(&self) -> &str {
+                "word-counts"
+            }
+            fn embed(&self, _texts: &[String]) -> Result<Vec<Vec<f32>>> {
+                Err(anyhow::anyhow!("not cached"))
+            }
+        }
+        assert!(SessionIndex::build(&sessions, &Unavailable, Some(&cache_path)).is_ok());
+    }
+}
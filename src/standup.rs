@@ -0,0 +1,194 @@
+use chrono::{Duration, NaiveDate};
+use std::collections::{HashMap, HashSet};
+
+use crate::models::{WorkAnalysis, WorkSession};
+
+/// Main topics listed per project
+const TOPICS_PER_PROJECT: usize = 3;
+
+/// Problems and next steps listed
+const MAX_ITEMS: usize = 5;
+
+/// Work on one project in the standup period
+#[derive(Debug, Clone, PartialEq)]
+pub struct StandupProject {
+    pub project: String,
+    pub work_time: Duration,
+    /// Main topics of its sessions, most frequent first
+    pub topics: Vec<String>,
+}
+
+/// A daily standup update: what was worked on, the problems tackled and what comes next
+#[derive(Debug, Clone, PartialEq)]
+pub struct Standup {
+    pub first_day: NaiveDate,
+    pub last_day: NaiveDate,
+    /// Projects worked on, most hours first
+    pub projects: Vec<StandupProject>,
+    /// Problems addressed in the sessions, earliest first
+    pub problems: Vec<String>,
+    /// What the latest session of each project left to follow up on
+    pub next_steps: Vec<(String, String)>,
+}
+
+impl Standup {
+    /// The local days a standup on `today` reports: the `days` days before it
+    pub fn days_before(today: NaiveDate, days: u32) -> (NaiveDate, NaiveDate) {
+        let days = days.max(1) as i64;
+        (today - Duration::days(days), today - Duration::days(1))
+    }
+
+    /// Standup from the session summaries of an analysis of `first_day` to `last_day`
+    pub fn new(analysis: &WorkAnalysis, first_day: NaiveDate, last_day: NaiveDate) -> Self {
+        let mut sessions_by_project: HashMap<String, Vec<&WorkSession>> = HashMap::new();
+        let mut sessions: Vec<&WorkSession> = analysis.sessions.iter().collect();
+        sessions.sort_by_key(|session| session.start_time);
+        for session in &sessions {
+            let project = analysis.project_name(session);
+            sessions_by_project.entry(project).or_default().push(session);
+        }
+
+        let mut projects: Vec<StandupProject> = sessions_by_project
+            .iter()
+            .map(|(project, sessions)| StandupProject {
+                project: project.clone(),
+                work_time: match analysis.project_stats.get(project) {
+                    Some(stats) => stats.work_time,
+                    None => sessions.iter().map(|s| s.end_time - s.start_time).fold(Duration::zero(), |a, b| a + b),
+                },
+                topics: main_topics(sessions),
+            })
+            .collect();
+        projects.sort_by(|a, b| b.work_time.cmp(&a.work_time).then_with(|| a.project.cmp(&b.project)));
+
+        let mut seen = HashSet::new();
+        let problems = sessions
+            .iter()
+            .filter_map(|session| session.summary.as_ref())
+            .flat_map(|summary| summary.problems_addressed.iter())
+            .filter(|problem| seen.insert(problem.as_str()))
+            .take(MAX_ITEMS)
+            .cloned()
+            .collect();
+
+        // Solutions proposed last are the likeliest to still be open
+        let next_steps = projects
+            .iter()
+            .filter_map(|project| {
+                let summary = sessions_by_project[&project.project].last()?.summary.as_ref()?;
+                let step = match summary.solutions_proposed.first() {
+                    Some(solution) => solution.clone(),
+                    None => format!("Continue: {}", summary.main_topics.first()?),
+                };
+                Some((project.project.clone(), step))
+            })
+            .take(MAX_ITEMS)
+            .collect();
+
+        Self { first_day, last_day, projects, problems, next_steps }
+    }
+}
+
+/// The topics most often among the sessions' main topics, ties in alphabetical order
+fn main_topics(sessions: &[&WorkSession]) -> Vec<String> {
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    for topic in sessions.iter().filter_map(|s| s.summary.as_ref()).flat_map(|summary| &summary.main_topics) {
+        *counts.entry(topic.as_str()).or_insert(0) += 1;
+    }
+    let mut topics: Vec<(&str, usize)> = counts.into_iter().collect();
+    topics.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+    topics.into_iter().take(TOPICS_PER_PROJECT).map(|(topic, _)| topic.to_string()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::SessionSummary;
+    use chrono::{TimeZone, Utc};
+    use uuid::Uuid;
+
+    fn session(project: &str, hour: u32, minutes: i64, topics: &[&str], problems: &[&str], solutions: &[&str]) -> WorkSession {
+        let start = Utc.with_ymd_and_hms(2025, 7, 1, hour, 0, 0).unwrap();
+        let strings = |items: &[&str]| items.iter().map(|item| item.to_string()).collect::<Vec<_>>();
+        WorkSession {
+            session_id: Uuid::new_v4(),
+            project_path: format!("/home/me/{}", project),
+            start_time: start,
+            end_time: start + Duration::minutes(minutes),
+            entries: Vec::new(),
+            total_messages: 10,
+            user_messages: 5,
+            assistant_messages: 5,
+            summary: Some(SessionSummary {
+                main_topics: strings(topics),
+                topic_mentions: HashMap::new(),
+                key_discussions: Vec::new(),
+                technologies_mentioned: Vec::new(),
+                problems_addressed: strings(problems),
+                problem_confidence: HashMap::new(),
+                solutions_proposed: strings(solutions),
+                learning_moments: Vec::new(),
+                overall_summary: String::new(),
+            }),
+            merged_session_ids: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_standup() {
+        let sessions = vec![
+            session("api", 1, 90, &["implement parser", "rust"], &["parser fails on empty lines"], &["use a state machine"]),
+            session("web", 3, 30, &["configure vite"], &[], &[]),
+            session("api", 5, 60, &["rust", "fix tests"], &["parser fails on empty lines", "flaky test"], &[]),
+        ];
+        let now = Utc::now();
+        let analysis = WorkAnalysis {
+            sessions,
+            project_stats: HashMap::new(),
+            time_range: (now, now),
+            total_sessions: 3,
+            total_messages: 30,
+            total_work_time: Duration::minutes(180),
+            conversation_summary: None,
+            token_analysis: None,
+            tool_usage: None,
+            code_blocks: None,
+            time_buckets: None,
+            initiatives: None,
+            commits: None,
+            log_gaps: None,
+            heatmap: None,
+            machines: None,
+            assistants: None,
+            sidechains: None,
+            budget: None,
+            duplicate_entries: 0,
+            project_names: Default::default(),
+        };
+        let day = NaiveDate::from_ymd_opt(2025, 7, 1).unwrap();
+        let standup = Standup::new(&analysis, day, day);
+
+        let projects: Vec<(&str, i64)> =
+            standup.projects.iter().map(|p| (p.project.as_str(), p.work_time.num_minutes())).collect();
+        assert_eq!(projects, [("api", 150), ("web", 30)]);
+        assert_eq!(standup.projects[0].topics, ["rust", "fix tests", "implement parser"]);
+        assert_eq!(standup.problems, ["parser fails on empty lines", "flaky test"]);
+        // The latest api session proposed nothing, so its main topic is next
+        assert_eq!(
+            standup.next_steps,
+            [
+                ("api".to_string(), "Continue: rust".to_string()),
+                ("web".to_string(), "Continue: configure vite".to_string())
+            ]
+        );
+    }
+
+    #[test]
+    fn test_days_before() {
+        let today = NaiveDate::from_ymd_opt(2025, 7, 7).unwrap();
+        let day = |d| NaiveDate::from_ymd_opt(2025, 7, d).unwrap();
+        assert_eq!(Standup::days_before(today, 1), (day(6), day(6)));
+        // Monday's standup covering Friday to Sunday
+        assert_eq!(Standup::days_before(today, 3), (day(4), day(6)));
+    }
+}
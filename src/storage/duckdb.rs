@@ -0,0 +1,325 @@
+use ::duckdb::{params, params_from_iter, Connection, OptionalExt, Transaction};
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use super::{MirrorStorage, MirrorStore};
+use crate::filter::FilterCoverage;
+use crate::models::ClaudeLogEntry;
+
+/// Schema changes in order; `schema_version` counts those applied
+const MIGRATIONS: &[&str] = &[
+    // 1: mirror of the log files
+    "
+    CREATE TABLE log_files (
+        path VARCHAR NOT NULL,
+        size BIGINT NOT NULL,
+        modified_nanos BIGINT NOT NULL
+    );
+    CREATE TABLE log_entries (
+        uuid VARCHAR NOT NULL,
+        session_id VARCHAR NOT NULL,
+        timestamp_micros BIGINT NOT NULL,
+        data VARCHAR NOT NULL
+    );
+    CREATE TABLE log_entry_files (
+        file_path VARCHAR NOT NULL,
+        uuid VARCHAR NOT NULL
+    );
+    ",
+];
+
+/// Mirror of the log files in a DuckDB database
+///
+/// Laid out like the SQLite mirror: entries are stored once by uuid however
+/// many files contain them, and `log_entry_files` records which files do.
+/// DuckDB checks key constraints eagerly, so the tables have no keys: rows are
+/// deleted before they are written again, and entries are replaced as a set
+/// through a staging table.
+pub struct DuckdbStore {
+    conn: Connection,
+}
+
+impl DuckdbStore {
+    /// Open or create the database and bring its schema up to date
+    pub fn open(path: &Path) -> Result<Self> {
+        let conn = Connection::open(path)
+            .with_context(|| format!("Failed to open DuckDB database: {}", path.display()))?;
+        let mut store = Self { conn };
+        store.migrate()?;
+        store.conn.execute_batch(
+            "CREATE TEMP TABLE staged_entries (
+                 uuid VARCHAR NOT NULL,
+                 session_id VARCHAR NOT NULL,
+                 timestamp_micros BIGINT NOT NULL,
+                 data VARCHAR NOT NULL
+             );",
+        )?;
+        Ok(store)
+    }
+
+    /// Schema version of the database
+    pub fn schema_version(&self) -> Result<usize> {
+        let version: Option<i64> = self.conn.query_row("SELECT MAX(version) FROM schema_version", [], |row| row.get(0))?;
+        Ok(version.unwrap_or(0) as usize)
+    }
+
+    fn migrate(&mut self) -> Result<()> {
+        self.conn.execute_batch("CREATE TABLE IF NOT EXISTS schema_version (version INTEGER NOT NULL);")?;
+        let version = self.schema_version()?;
+        if version > MIGRATIONS.len() {
+            return Err(anyhow::anyhow!(
+                "Database schema version {} is newer than this version supports ({})",
+                version,
+                MIGRATIONS.len()
+            ));
+        }
+
+        for (index, migration) in MIGRATIONS.iter().enumerate().skip(version) {
+            let tx = self.conn.transaction()?;
+            tx.execute_batch(migration)
+                .with_context(|| format!("Failed to apply schema migration {}", index + 1))?;
+            tx.execute("INSERT INTO schema_version (version) VALUES (?)", params![(index + 1) as i64])?;
+            tx.commit()?;
+        }
+        Ok(())
+    }
+}
+
+impl MirrorStore for DuckdbStore {
+    const FILE_NAME: &'static str = "entries.duckdb";
+
+    fn open(path: &Path) -> Result<Self> {
+        DuckdbStore::open(path)
+    }
+
+    fn is_log_file_current(&self, path: &Path, size: u64, modified_nanos: u128) -> Result<bool> {
+        let stored: Option<(i64, i64)> = self
+            .conn
+            .query_row(
+                "SELECT size, modified_nanos FROM log_files WHERE path = ?",
+                params![path.to_string_lossy()],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()?;
+        Ok(stored == Some((size as i64, modified_nanos as i64)))
+    }
+
+    fn replace_log_file(
+        &mut self,
+        path: &Path,
+        size: u64,
+        modified_nanos: u128,
+        entries: &[ClaudeLogEntry],
+    ) -> Result<usize> {
+        let path = path.to_string_lossy();
+        let tx = self.conn.transaction()?;
+        tx.execute("DELETE FROM log_files WHERE path = ?", params![path])?;
+        tx.execute(
+            "INSERT INTO log_files (path, size, modified_nanos) VALUES (?, ?, ?)",
+            params![path, size as i64, modified_nanos as i64],
+        )?;
+        let new_entries = Self::mirror_file(&tx, &path, entries)?;
+        tx.commit()?;
+        Ok(new_entries)
+    }
+
+    fn retain_log_files(&mut self, existing: &[PathBuf]) -> Result<()> {
+        let existing: Vec<String> = existing.iter().map(|path| path.to_string_lossy().into_owned()).collect();
+        let stored: Vec<String> = self
+            .conn
+            .prepare("SELECT path FROM log_files")?
+            .query_map([], |row| row.get(0))?
+            .collect::<::duckdb::Result<_>>()?;
+
+        let tx = self.conn.transaction()?;
+        for path in stored.iter().filter(|path| !existing.contains(path)) {
+            Self::mirror_file(&tx, path, &[])?;
+            tx.execute("DELETE FROM log_files WHERE path = ?", params![path])?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    fn mirrored_log_files(&self) -> Result<Vec<PathBuf>> {
+        let paths: Vec<String> = self
+            .conn
+            .prepare("SELECT path FROM log_files UNION SELECT file_path FROM log_entry_files ORDER BY 1")?
+            .query_map([], |row| row.get(0))?
+            .collect::<::duckdb::Result<_>>()?;
+        Ok(paths.into_iter().map(PathBuf::from).collect())
+    }
+
+    fn log_file_entries(&self, path: &Path) -> Result<Vec<ClaudeLogEntry>> {
+        self.entries(
+            "SELECT e.data FROM log_entry_files f JOIN log_entries e ON e.uuid = f.uuid
+             WHERE f.file_path = ? ORDER BY e.timestamp_micros",
+            vec![path.to_string_lossy().into_owned().into()],
+        )
+    }
+
+    fn query_log_entries(&self, from: Option<DateTime<Utc>>, to: Option<DateTime<Utc>>) -> Result<Vec<ClaudeLogEntry>> {
+        // Parameters of `? IS NULL` have no type DuckDB can infer, so open ends are left out
+        let mut conditions = Vec::new();
+        let mut bounds = Vec::new();
+        if let Some(from) = from {
+            conditions.push("timestamp_micros >= ?");
+            bounds.push(from.timestamp_micros().into());
+        }
+        if let Some(to) = to {
+            conditions.push("timestamp_micros <= ?");
+            bounds.push(to.timestamp_micros().into());
+        }
+        let filter = if conditions.is_empty() { String::new() } else { format!("WHERE {}", conditions.join(" AND ")) };
+        self.entries(&format!("SELECT data FROM log_entries {} ORDER BY timestamp_micros", filter), bounds)
+    }
+
+    fn log_entry_coverage(&self) -> Result<FilterCoverage> {
+        let (count, first, last): (i64, Option<i64>, Option<i64>) = self.conn.query_row(
+            "SELECT COUNT(*), MIN(timestamp_micros), MAX(timestamp_micros) FROM log_entries",
+            [],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        )?;
+        Ok(FilterCoverage {
+            total_entries: count as usize,
+            matched_entries: 0,
+            first_entry: first.and_then(DateTime::from_timestamp_micros),
+            last_entry: last.and_then(DateTime::from_timestamp_micros),
+        })
+    }
+}
+
+impl DuckdbStore {
+    /// Make `entries` the entries of a log file and return how many were not stored before
+    fn mirror_file(tx: &Transaction, path: &str, entries: &[ClaudeLogEntry]) -> Result<usize> {
+        tx.execute("DELETE FROM log_entry_files WHERE file_path = ?", params![path])?;
+
+        let mut staged = HashSet::new();
+        {
+            let mut insert = tx.prepare(
+                "INSERT INTO staged_entries (uuid, session_id, timestamp_micros, data) VALUES (?, ?, ?, ?)",
+            )?;
+            // A file holding an entry twice keeps its last version, as the SQLite mirror does
+            for entry in entries.iter().rev().filter(|entry| staged.insert(entry.uuid)) {
+                insert.execute(params![
+                    entry.uuid.to_string(),
+                    entry.session_id.to_string(),
+                    entry.timestamp.timestamp_micros(),
+                    serde_json::to_string(entry)?,
+                ])?;
+            }
+        }
+
+        let new_entries: i64 = tx.query_row(
+            "SELECT COUNT(*) FROM staged_entries WHERE uuid NOT IN (SELECT uuid FROM log_entries)",
+            [],
+            |row| row.get(0),
+        )?;
+        tx.execute_batch(
+            "DELETE FROM log_entries WHERE uuid IN (SELECT uuid FROM staged_entries);
+             INSERT INTO log_entries SELECT uuid, session_id, timestamp_micros, data FROM staged_entries;",
+        )?;
+        tx.execute("INSERT INTO log_entry_files SELECT CAST(? AS VARCHAR), uuid FROM staged_entries", params![path])?;
+        // Entries of the file's previous version that no file contains any more
+        tx.execute_batch(
+            "DELETE FROM staged_entries;
+             DELETE FROM log_entries WHERE uuid NOT IN (SELECT uuid FROM log_entry_files);",
+        )?;
+        Ok(new_entries as usize)
+    }
+
+    fn entries(&self, query: &str, values: Vec<::duckdb::types::Value>) -> Result<Vec<ClaudeLogEntry>> {
+        let mut statement = self.conn.prepare(query)?;
+        let rows = statement.query_map(params_from_iter(values), |row| row.get::<_, String>(0))?;
+        let mut entries = Vec::new();
+        for data in rows {
+            entries.push(serde_json::from_str(&data?).context("Corrupt entry in the DuckDB mirror")?);
+        }
+        Ok(entries)
+    }
+}
+
+/// Answers queries from a DuckDB mirror of the JSONL files
+pub type DuckdbStorage<'a> = MirrorStorage<'a, DuckdbStore>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filter::TimeRangeFilter;
+    use crate::models::TimeGrouping;
+    use crate::parser::JsonlParser;
+    use crate::storage::{daily_rollups, name_projects, JsonlStorage, MismatchKind, Storage};
+    use chrono::{FixedOffset, NaiveDate};
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_migrations_are_applied_once() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("entries.duckdb");
+
+        let store = DuckdbStore::open(&path).unwrap();
+        assert_eq!(store.schema_version().unwrap(), MIGRATIONS.len());
+        drop(store);
+
+        let store = DuckdbStore::open(&path).unwrap();
+        assert_eq!(store.schema_version().unwrap(), MIGRATIONS.len());
+        store.conn.execute("INSERT INTO schema_version (version) VALUES (99)", []).unwrap();
+        drop(store);
+        assert!(DuckdbStore::open(&path).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_duckdb_storage_mirrors_logs() {
+        let temp_dir = TempDir::new().unwrap();
+        let projects_dirs = [temp_dir.path().join("projects")];
+        crate::sample::write_sample(&projects_dirs[0]).unwrap();
+        let parser = JsonlParser::new();
+        let storage = DuckdbStorage::open(
+            &temp_dir.path().join("db").join("entries.duckdb"),
+            JsonlStorage::new(&parser, &projects_dirs),
+        )
+        .unwrap();
+        // A re-imported archive holds a copy of a session already in the logs
+        let original = storage.source().log_files().unwrap().remove(0);
+        let archived = original.with_file_name("archived-copy.jsonl");
+        std::fs::copy(&original, &archived).unwrap();
+
+        let counts = storage.ingest().await.unwrap();
+        assert_eq!((counts.files_read, counts.entries_read, counts.new_entries), (4, 24, 18));
+        let counts = storage.ingest().await.unwrap();
+        assert_eq!((counts.files_read, counts.files_unchanged, counts.new_entries), (0, 4, 0));
+
+        let everything = TimeRangeFilter::new(None, None, None);
+        let (entries, coverage) = storage.load_entries(&everything).await.unwrap();
+        assert_eq!(entries.len(), 18);
+        assert_eq!((coverage.total_entries, coverage.matched_entries), (18, 18));
+        assert!(entries.windows(2).all(|pair| pair[0].timestamp <= pair[1].timestamp));
+        assert!(storage.verify().await.unwrap().mismatches.is_empty());
+
+        let filter = TimeRangeFilter::new(Some(entries[6].timestamp), None, Some("api".to_string()));
+        let (selected, coverage) = storage.load_entries(&filter).await.unwrap();
+        assert!(selected.iter().all(|entry| entry.cwd.ends_with("/api") && entry.timestamp >= entries[6].timestamp));
+        assert_eq!(coverage.matched_entries, selected.len());
+
+        let utc = FixedOffset::east_opt(0).unwrap();
+        let from = NaiveDate::from_ymd_opt(2000, 1, 1).unwrap();
+        let to = NaiveDate::from_ymd_opt(2100, 1, 1).unwrap();
+        let days = storage.activity(TimeGrouping::Day, utc, from, to).await.unwrap();
+        assert_eq!(days, name_projects(daily_rollups(&entries, &utc), &storage.project_directories().unwrap()));
+
+        // The entries stay while the archived copy still contains them
+        std::fs::remove_file(&original).unwrap();
+        let (entries, _) = storage.load_entries(&everything).await.unwrap();
+        assert_eq!(entries.len(), 18);
+
+        // Changes not ingested yet are reported, and dropped once they are
+        let content = std::fs::read_to_string(&archived).unwrap();
+        std::fs::write(&archived, format!("{}\n", content.lines().next().unwrap())).unwrap();
+        let report = storage.verify().await.unwrap();
+        assert_eq!(report.mismatches.len(), 5);
+        assert!(report.mismatches.iter().all(|mismatch| mismatch.kind == MismatchKind::Extra));
+        let (entries, _) = storage.load_entries(&everything).await.unwrap();
+        assert_eq!(entries.len(), 13);
+    }
+}
@@ -0,0 +1,197 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, FixedOffset, NaiveDate, Utc};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use super::{daily_rollups, group_rollups, local_day_range, name_projects, ActivityRollup, IngestCounts, JsonlStorage, Storage, VerifyReport};
+use crate::cache::FileFingerprint;
+use crate::filter::{FilterCoverage, TimeRangeFilter};
+use crate::models::{ClaudeLogEntry, TimeGrouping};
+use crate::parser::JsonlParser;
+use crate::scanner::ProjectPatterns;
+
+/// A database holding a copy of the log files, entry by entry
+///
+/// Entries are stored once by uuid however many files contain them, and are
+/// dropped when the last of those files no longer does.
+pub trait MirrorStore: Sized {
+    /// File name of the database in `<data dir>/claude-work-analysis`
+    const FILE_NAME: &'static str;
+
+    /// Open or create the database and bring its schema up to date
+    fn open(path: &Path) -> Result<Self>;
+
+    /// Whether the mirrored copy of a log file has the given size and mtime
+    fn is_log_file_current(&self, path: &Path, size: u64, modified_nanos: u128) -> Result<bool>;
+
+    /// Replace the mirrored entries of a log file; returns how many were not stored before
+    fn replace_log_file(&mut self, path: &Path, size: u64, modified_nanos: u128, entries: &[ClaudeLogEntry]) -> Result<usize>;
+
+    /// Drop the mirror of log files that no longer exist
+    ///
+    /// Their entries stay mirrored as long as another file, e.g. a copy in an
+    /// archive, still contains them.
+    fn retain_log_files(&mut self, existing: &[PathBuf]) -> Result<()>;
+
+    /// Log files with mirrored entries, including files that no longer exist
+    fn mirrored_log_files(&self) -> Result<Vec<PathBuf>>;
+
+    /// Mirrored entries of one log file
+    fn log_file_entries(&self, path: &Path) -> Result<Vec<ClaudeLogEntry>>;
+
+    /// Mirrored entries between `from` and `to` inclusive, oldest first
+    fn query_log_entries(&self, from: Option<DateTime<Utc>>, to: Option<DateTime<Utc>>) -> Result<Vec<ClaudeLogEntry>>;
+
+    /// Number of mirrored entries and the time span they cover
+    fn log_entry_coverage(&self) -> Result<FilterCoverage>;
+
+    /// Rollups the database keeps, by session path like [`super::daily_rollups`],
+    /// or `None` to compute them from the entries
+    fn rollups(
+        &mut self,
+        _grouping: TimeGrouping,
+        _timezone: FixedOffset,
+        _from: NaiveDate,
+        _to: NaiveDate,
+    ) -> Result<Option<Vec<ActivityRollup>>> {
+        Ok(None)
+    }
+}
+
+/// Answers queries from a database mirror of the JSONL files
+///
+/// Before each query, files whose size or mtime changed are parsed again and
+/// their entries replaced, so the mirror never lags behind the logs.
+pub struct MirrorStorage<'a, S> {
+    store: Mutex<S>,
+    source: JsonlStorage<'a>,
+}
+
+impl<'a, S: MirrorStore> MirrorStorage<'a, S> {
+    pub fn open(path: &Path, source: JsonlStorage<'a>) -> Result<Self> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create {}", parent.display()))?;
+        }
+        Ok(Self {
+            store: Mutex::new(S::open(path)?),
+            source,
+        })
+    }
+
+    /// `<data dir>/claude-work-analysis/` and the store's file name
+    pub fn default_path() -> Option<PathBuf> {
+        dirs::data_dir().map(|dir| dir.join("claude-work-analysis").join(S::FILE_NAME))
+    }
+
+    /// Bring the mirror up to date with the log files, reading only files that changed
+    pub async fn sync(&self) -> Result<IngestCounts> {
+        let files = self.source.files_to_read()?;
+        let mut counts = IngestCounts::default();
+        for path in &files {
+            let Some(fingerprint) = FileFingerprint::of(path).await else {
+                self.source.parser().progress().file_read(0);
+                continue;
+            };
+            if self.lock().is_log_file_current(path, fingerprint.size, fingerprint.modified_nanos)? {
+                counts.files_unchanged += 1;
+                // Files left as mirrored count as read for the progress
+                self.source.parser().progress().file_read(0);
+                continue;
+            }
+            // Without refresh the parser answers from the parse cache, which may be
+            // older than the file or miss it, so the mirror waits for a refreshing run
+            if !self.source.parser().refreshes() {
+                self.source.parser().progress().file_read(0);
+                continue;
+            }
+            // The store is not locked while parsing
+            let entries = self.source.parser().parse_file(path).await?;
+            counts.new_entries += self
+                .lock()
+                .replace_log_file(path, fingerprint.size, fingerprint.modified_nanos, &entries)?;
+            counts.files_read += 1;
+            counts.entries_read += entries.len();
+        }
+        self.lock().retain_log_files(&files)?;
+        Ok(counts)
+    }
+
+    pub fn source(&self) -> &JsonlStorage<'a> {
+        &self.source
+    }
+
+    /// Mirror only the project directories `patterns` select
+    pub fn with_project_patterns(mut self, patterns: ProjectPatterns) -> Self {
+        self.source = self.source.with_project_patterns(patterns);
+        self
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, S> {
+        self.store.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl<S: MirrorStore> Storage for MirrorStorage<'_, S> {
+    async fn load_entries(&self, filter: &TimeRangeFilter) -> Result<(Vec<ClaudeLogEntry>, FilterCoverage)> {
+        self.sync().await?;
+        let store = self.lock();
+        let (from, to) = filter.get_date_range();
+        let entries = filter.filter_entries(store.query_log_entries(from, to)?);
+        let coverage = FilterCoverage {
+            matched_entries: entries.len(),
+            ..store.log_entry_coverage()?
+        };
+        Ok((entries, coverage))
+    }
+
+    fn project_directories(&self) -> Result<Vec<PathBuf>> {
+        self.source.project_directories()
+    }
+
+    async fn ingest(&self) -> Result<IngestCounts> {
+        self.sync().await
+    }
+
+    async fn verify(&self) -> Result<VerifyReport> {
+        let fresh = JsonlParser::new();
+        let files = self.source.log_files()?;
+        let mut report = VerifyReport::default();
+        for path in &files {
+            let stored = self.lock().log_file_entries(path)?;
+            let raw = fresh.parse_file(path).await?;
+            report.compare_file(path, &stored, &raw);
+        }
+        // Everything still mirrored for files that are gone is extra
+        let mirrored = self.lock().mirrored_log_files()?;
+        for path in mirrored {
+            if !files.contains(&path) {
+                let stored = self.lock().log_file_entries(&path)?;
+                report.compare_file(&path, &stored, &[]);
+            }
+        }
+        Ok(report)
+    }
+
+    async fn activity(
+        &self,
+        grouping: TimeGrouping,
+        timezone: FixedOffset,
+        from: NaiveDate,
+        to: NaiveDate,
+    ) -> Result<Vec<ActivityRollup>> {
+        self.sync().await?;
+        let rollups = {
+            let mut store = self.lock();
+            match store.rollups(grouping, timezone, from, to)? {
+                Some(rollups) => rollups,
+                None => {
+                    let (start, end) = local_day_range(&timezone, from, to);
+                    let entries = store.query_log_entries(Some(start), Some(end))?;
+                    group_rollups(daily_rollups(&entries, &timezone), grouping)
+                }
+            }
+        };
+        Ok(name_projects(rollups, &self.source.project_directories()?))
+    }
+}
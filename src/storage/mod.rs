@@ -0,0 +1,563 @@
+use anyhow::Result;
+use chrono::{DateTime, FixedOffset, NaiveDate, TimeZone, Utc};
+use serde::Deserialize;
+use std::collections::{HashMap, HashSet};
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use uuid::Uuid;
+
+use crate::analyzer::WorkAnalyzer;
+use crate::filter::{FilterCoverage, TimeRangeFilter};
+use crate::machines::MachineLabels;
+use crate::models::{ClaudeLogEntry, TimeGrouping, WorkAnalysis};
+use crate::parser::JsonlParser;
+use crate::pipeline::analyze_files;
+use crate::scanner::{ProjectPatterns, ProjectScanner};
+
+#[cfg(feature = "duckdb")]
+mod duckdb;
+#[cfg(any(feature = "sqlite", feature = "duckdb"))]
+mod mirror;
+mod rollup;
+#[cfg(feature = "sqlite")]
+mod sqlite;
+
+#[cfg(any(feature = "sqlite", feature = "duckdb"))]
+pub use mirror::{MirrorStorage, MirrorStore};
+pub use rollup::{daily_rollups, group_rollups, name_projects, ActivityRollup, ROLLUP_SESSION_GAP_MINUTES};
+#[cfg(feature = "duckdb")]
+pub use self::duckdb::{DuckdbStorage, DuckdbStore};
+#[cfg(feature = "sqlite")]
+pub use sqlite::{ExportCounts, SqliteStorage, SqliteStore};
+
+/// What an ingest read from the log files
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct IngestCounts {
+    /// Log files parsed
+    pub files_read: usize,
+    /// Log files skipped because they did not change since the last ingest
+    pub files_unchanged: usize,
+    /// Entries in the files parsed
+    pub entries_read: usize,
+    /// Entries not stored before; the others were already stored or appeared in
+    /// another file, e.g. a re-imported archive
+    pub new_entries: usize,
+}
+
+/// How a stored entry differs from the log files
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MismatchKind {
+    /// In the log file but not stored
+    Missing,
+    /// Stored with different content than in the log file
+    Changed,
+    /// Stored for a log file that does not contain it, or no longer exists
+    Extra,
+}
+
+impl MismatchKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MismatchKind::Missing => "missing",
+            MismatchKind::Changed => "changed",
+            MismatchKind::Extra => "extra",
+        }
+    }
+}
+
+/// An entry on which a log file and the stored data disagree
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mismatch {
+    pub kind: MismatchKind,
+    pub file: PathBuf,
+    pub uuid: Uuid,
+}
+
+/// Result of comparing the stored data with a fresh parse of the log files
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct VerifyReport {
+    pub files_checked: usize,
+    pub entries_checked: usize,
+    pub mismatches: Vec<Mismatch>,
+}
+
+impl VerifyReport {
+    /// Record the differences between the stored and the raw entries of one file
+    fn compare_file(&mut self, file: &Path, stored: &[ClaudeLogEntry], raw: &[ClaudeLogEntry]) {
+        let stored: HashMap<Uuid, &ClaudeLogEntry> = stored.iter().map(|entry| (entry.uuid, entry)).collect();
+        let mut seen = HashSet::new();
+        self.files_checked += 1;
+        for entry in raw {
+            if !seen.insert(entry.uuid) {
+                continue;
+            }
+            self.entries_checked += 1;
+            let kind = match stored.get(&entry.uuid) {
+                None => MismatchKind::Missing,
+                Some(stored) if serde_json::to_value(stored).ok() != serde_json::to_value(entry).ok() => {
+                    MismatchKind::Changed
+                }
+                Some(_) => continue,
+            };
+            self.push(kind, file, entry.uuid);
+        }
+        for uuid in stored.keys().filter(|uuid| !seen.contains(uuid)) {
+            self.push(MismatchKind::Extra, file, *uuid);
+        }
+    }
+
+    fn push(&mut self, kind: MismatchKind, file: &Path, uuid: Uuid) {
+        self.mismatches.push(Mismatch { kind, file: file.to_path_buf(), uuid });
+    }
+}
+
+/// Where analyses read log entries from
+///
+/// The JSONL files stay the source of truth; a database backend mirrors them and
+/// answers time-range queries from an index instead of reading every file.
+/// Entries are identified by uuid, so ingesting a file twice, or a copy of it
+/// from an archive, stores and counts each entry once.
+pub trait Storage {
+    /// Entries selected by the filter, with what was searched to find them
+    fn load_entries(&self, filter: &TimeRangeFilter) -> impl Future<Output = Result<(Vec<ClaudeLogEntry>, FilterCoverage)>>;
+
+    /// Analysis of the entries selected by the filter, with what was searched to find them
+    fn analyze(
+        &self,
+        filter: &TimeRangeFilter,
+        analyzer: &WorkAnalyzer,
+    ) -> impl Future<Output = Result<(WorkAnalysis, FilterCoverage)>> {
+        async move {
+            let (entries, coverage) = self.load_entries(filter).await?;
+            Ok((analyzer.analyze_entries(&entries)?, coverage))
+        }
+    }
+
+    /// Directories of the projects the entries come from
+    fn project_directories(&self) -> Result<Vec<PathBuf>>;
+
+    /// Bring the stored data up to date with the log files
+    fn ingest(&self) -> impl Future<Output = Result<IngestCounts>>;
+
+    /// Compare the stored data with a fresh parse of every log file, without ingesting
+    fn verify(&self) -> impl Future<Output = Result<VerifyReport>>;
+
+    /// Work per project in each day, week or month from `from` to `to` in `timezone`
+    ///
+    /// Computed from the entries by default; the SQLite backend answers from
+    /// rollup tables it keeps up to date as logs are mirrored.
+    fn activity(
+        &self,
+        grouping: TimeGrouping,
+        timezone: FixedOffset,
+        from: NaiveDate,
+        to: NaiveDate,
+    ) -> impl Future<Output = Result<Vec<ActivityRollup>>> {
+        async move {
+            let (start, end) = local_day_range(&timezone, from, to);
+            let filter = TimeRangeFilter::new(Some(start), Some(end), None);
+            let (entries, _) = self.load_entries(&filter).await?;
+            let rollups = group_rollups(daily_rollups(&entries, &timezone), grouping);
+            Ok(name_projects(rollups, &self.project_directories()?))
+        }
+    }
+}
+
+/// From local midnight on `from` to the last second of `to`
+fn local_day_range(timezone: &FixedOffset, from: NaiveDate, to: NaiveDate) -> (DateTime<Utc>, DateTime<Utc>) {
+    let start = timezone.from_local_datetime(&from.and_hms_opt(0, 0, 0).unwrap()).unwrap();
+    let end = timezone.from_local_datetime(&to.and_hms_opt(23, 59, 59).unwrap()).unwrap();
+    (start.with_timezone(&Utc), end.with_timezone(&Utc))
+}
+
+/// Storage backends selectable with `storage` in the config file
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StorageKind {
+    /// Scan the JSONL files on every run; no setup needed
+    #[default]
+    Jsonl,
+    /// Mirror the logs into a SQLite database, needs the `sqlite` feature
+    Sqlite,
+    /// Mirror the logs into a DuckDB database, needs the `duckdb` feature
+    Duckdb,
+}
+
+/// Reads the JSONL files under the projects directories on every query
+pub struct JsonlStorage<'a> {
+    parser: &'a JsonlParser,
+    projects_dirs: &'a [PathBuf],
+    machines: MachineLabels,
+    project_patterns: ProjectPatterns,
+    /// Skip files last written before the start of the analyzed range
+    quick_scan: bool,
+}
+
+impl<'a> JsonlStorage<'a> {
+    pub fn new(parser: &'a JsonlParser, projects_dirs: &'a [PathBuf]) -> Self {
+        Self {
+            parser,
+            projects_dirs,
+            machines: MachineLabels::default(),
+            project_patterns: ProjectPatterns::default(),
+            quick_scan: false,
+        }
+    }
+
+    /// Break analyses down by the machine each projects directory was synced from
+    pub fn with_machines(mut self, machines: MachineLabels) -> Self {
+        self.machines = machines;
+        self
+    }
+
+    /// Read only the project directories `patterns` select
+    pub fn with_project_patterns(mut self, patterns: ProjectPatterns) -> Self {
+        self.project_patterns = patterns;
+        self
+    }
+
+    /// Analyze only the files written since the start of the range
+    ///
+    /// Their entries can only be older, but the coverage then no longer tells
+    /// when the logs begin, so this is for quick lookups of recent work.
+    pub fn with_quick_scan(mut self, quick_scan: bool) -> Self {
+        self.quick_scan = quick_scan;
+        self
+    }
+
+    pub fn parser(&self) -> &'a JsonlParser {
+        self.parser
+    }
+
+    pub fn projects_dirs(&self) -> &'a [PathBuf] {
+        self.projects_dirs
+    }
+
+    pub fn project_patterns(&self) -> &ProjectPatterns {
+        &self.project_patterns
+    }
+
+    /// Every JSONL file under the selected project directories
+    pub fn log_files(&self) -> Result<Vec<PathBuf>> {
+        let files = self.parser.profiler().time("scan", || self.scanner().scan_all_projects(self.projects_dirs))?;
+        tracing::debug!("Found {} log files", files.len());
+        Ok(files)
+    }
+
+    /// The log files, started on the parser's progress as the files about to be read
+    pub(crate) fn files_to_read(&self) -> Result<Vec<PathBuf>> {
+        self.parser.progress().scanning();
+        let files = self.log_files()?;
+        self.parser.progress().start(files.len());
+        Ok(files)
+    }
+
+    fn scanner(&self) -> ProjectScanner {
+        ProjectScanner::new().with_patterns(self.project_patterns.clone())
+    }
+}
+
+/// The parse cache is what this backend stores: ingesting fills it, and
+/// verifying compares it with the log files
+impl Storage for JsonlStorage<'_> {
+    async fn load_entries(&self, filter: &TimeRangeFilter) -> Result<(Vec<ClaudeLogEntry>, FilterCoverage)> {
+        let mut all_entries = Vec::new();
+        let mut coverage = FilterCoverage::default();
+        let mut seen = HashSet::new();
+
+        for file_path in self.files_to_read()? {
+            let mut entries = self.parser.parse_file(&file_path).await?;
+            // The same entry in several files, e.g. a copied archive, is loaded once
+            entries.retain(|entry| seen.insert(entry.uuid));
+            all_entries.extend(filter.filter_entries_with_coverage(entries, &mut coverage));
+        }
+
+        Ok((all_entries, coverage))
+    }
+
+    /// Entries are folded into sessions file by file instead of being loaded first
+    async fn analyze(&self, filter: &TimeRangeFilter, analyzer: &WorkAnalyzer) -> Result<(WorkAnalysis, FilterCoverage)> {
+        self.parser.progress().scanning();
+        let mut log_files = self.log_files()?;
+        if let Some(from) = filter.get_date_range().0.filter(|_| self.quick_scan) {
+            log_files.retain(|path| {
+                let modified = std::fs::metadata(path).and_then(|metadata| metadata.modified());
+                modified.ok().is_none_or(|modified| DateTime::<Utc>::from(modified) >= from)
+            });
+        }
+        self.parser.progress().start(log_files.len());
+        let output = analyze_files(self.parser, &log_files, filter, analyzer, false, &self.machines).await?;
+        Ok((output.analysis, output.diagnostics.coverage))
+    }
+
+    fn project_directories(&self) -> Result<Vec<PathBuf>> {
+        self.scanner().get_all_project_directories(self.projects_dirs)
+    }
+
+    async fn ingest(&self) -> Result<IngestCounts> {
+        let mut counts = IngestCounts::default();
+        let mut seen = HashSet::new();
+        for file_path in self.files_to_read()? {
+            let entries = self.parser.parse_file(&file_path).await?;
+            counts.files_read += 1;
+            counts.entries_read += entries.len();
+            counts.new_entries += entries.iter().filter(|entry| seen.insert(entry.uuid)).count();
+        }
+        Ok(counts)
+    }
+
+    async fn verify(&self) -> Result<VerifyReport> {
+        let fresh = JsonlParser::new();
+        let mut report = VerifyReport::default();
+        for file_path in self.files_to_read()? {
+            let stored = self.parser.parse_file(&file_path).await?;
+            let raw = fresh.parse_file(&file_path).await?;
+            report.compare_file(&file_path, &stored, &raw);
+        }
+        Ok(report)
+    }
+}
+
+/// The storage backend chosen in the config file
+pub enum StorageBackend<'a> {
+    Jsonl(JsonlStorage<'a>),
+    #[cfg(feature = "sqlite")]
+    Sqlite(SqliteStorage<'a>),
+    #[cfg(feature = "duckdb")]
+    Duckdb(DuckdbStorage<'a>),
+}
+
+impl<'a> StorageBackend<'a> {
+    /// Open a backend of `kind` over the logs in `projects_dirs`
+    ///
+    /// `database` is where a database backend keeps its mirror; it defaults to
+    /// `<data dir>/claude-work-analysis/entries.db` for SQLite and `entries.duckdb`
+    /// for DuckDB.
+    pub fn open(
+        kind: StorageKind,
+        database: Option<&Path>,
+        parser: &'a JsonlParser,
+        projects_dirs: &'a [PathBuf],
+    ) -> Result<Self> {
+        let source = JsonlStorage::new(parser, projects_dirs);
+        match kind {
+            StorageKind::Jsonl => Ok(Self::Jsonl(source)),
+            #[cfg(feature = "sqlite")]
+            StorageKind::Sqlite => {
+                let path = match database {
+                    Some(path) => path.to_path_buf(),
+                    None => SqliteStorage::default_path()
+                        .ok_or_else(|| anyhow::anyhow!("Cannot find data directory, set `database` in the config file"))?,
+                };
+                Ok(Self::Sqlite(SqliteStorage::open(&path, source)?))
+            }
+            #[cfg(not(feature = "sqlite"))]
+            StorageKind::Sqlite => {
+                let _ = database;
+                Err(anyhow::anyhow!(
+                    "storage = \"sqlite\" needs a build with `--features sqlite`; use storage = \"jsonl\" otherwise"
+                ))
+            }
+            #[cfg(feature = "duckdb")]
+            StorageKind::Duckdb => {
+                let path = match database {
+                    Some(path) => path.to_path_buf(),
+                    None => DuckdbStorage::default_path()
+                        .ok_or_else(|| anyhow::anyhow!("Cannot find data directory, set `database` in the config file"))?,
+                };
+                Ok(Self::Duckdb(DuckdbStorage::open(&path, source)?))
+            }
+            #[cfg(not(feature = "duckdb"))]
+            StorageKind::Duckdb => {
+                let _ = database;
+                Err(anyhow::anyhow!(
+                    "storage = \"duckdb\" needs a build with `--features duckdb`; use storage = \"jsonl\" otherwise"
+                ))
+            }
+        }
+    }
+
+    /// Break analyses down by machine; the database mirrors do not know where their entries came from
+    pub fn with_machines(self, machines: MachineLabels) -> Self {
+        match self {
+            Self::Jsonl(storage) => Self::Jsonl(storage.with_machines(machines)),
+            #[cfg(feature = "sqlite")]
+            Self::Sqlite(storage) => Self::Sqlite(storage),
+            #[cfg(feature = "duckdb")]
+            Self::Duckdb(storage) => Self::Duckdb(storage),
+        }
+    }
+
+    /// Read only the project directories `patterns` select; the database mirrors
+    /// drop the entries of the others when they sync
+    pub fn with_project_patterns(self, patterns: ProjectPatterns) -> Self {
+        match self {
+            Self::Jsonl(storage) => Self::Jsonl(storage.with_project_patterns(patterns)),
+            #[cfg(feature = "sqlite")]
+            Self::Sqlite(storage) => Self::Sqlite(storage.with_project_patterns(patterns)),
+            #[cfg(feature = "duckdb")]
+            Self::Duckdb(storage) => Self::Duckdb(storage.with_project_patterns(patterns)),
+        }
+    }
+
+    /// Skip files written before the analyzed range; the database mirrors are queried by time already
+    pub fn with_quick_scan(self, quick_scan: bool) -> Self {
+        match self {
+            Self::Jsonl(storage) => Self::Jsonl(storage.with_quick_scan(quick_scan)),
+            #[cfg(feature = "sqlite")]
+            Self::Sqlite(storage) => Self::Sqlite(storage),
+            #[cfg(feature = "duckdb")]
+            Self::Duckdb(storage) => Self::Duckdb(storage),
+        }
+    }
+
+    /// The JSONL files the backend reads from
+    pub fn source(&self) -> &JsonlStorage<'a> {
+        match self {
+            Self::Jsonl(storage) => storage,
+            #[cfg(feature = "sqlite")]
+            Self::Sqlite(storage) => storage.source(),
+            #[cfg(feature = "duckdb")]
+            Self::Duckdb(storage) => storage.source(),
+        }
+    }
+}
+
+impl Storage for StorageBackend<'_> {
+    async fn load_entries(&self, filter: &TimeRangeFilter) -> Result<(Vec<ClaudeLogEntry>, FilterCoverage)> {
+        match self {
+            Self::Jsonl(storage) => storage.load_entries(filter).await,
+            #[cfg(feature = "sqlite")]
+            Self::Sqlite(storage) => storage.load_entries(filter).await,
+            #[cfg(feature = "duckdb")]
+            Self::Duckdb(storage) => storage.load_entries(filter).await,
+        }
+    }
+
+    async fn analyze(&self, filter: &TimeRangeFilter, analyzer: &WorkAnalyzer) -> Result<(WorkAnalysis, FilterCoverage)> {
+        match self {
+            Self::Jsonl(storage) => storage.analyze(filter, analyzer).await,
+            #[cfg(feature = "sqlite")]
+            Self::Sqlite(storage) => storage.analyze(filter, analyzer).await,
+            #[cfg(feature = "duckdb")]
+            Self::Duckdb(storage) => storage.analyze(filter, analyzer).await,
+        }
+    }
+
+    fn project_directories(&self) -> Result<Vec<PathBuf>> {
+        match self {
+            Self::Jsonl(storage) => storage.project_directories(),
+            #[cfg(feature = "sqlite")]
+            Self::Sqlite(storage) => storage.project_directories(),
+            #[cfg(feature = "duckdb")]
+            Self::Duckdb(storage) => storage.project_directories(),
+        }
+    }
+
+    async fn ingest(&self) -> Result<IngestCounts> {
+        match self {
+            Self::Jsonl(storage) => storage.ingest().await,
+            #[cfg(feature = "sqlite")]
+            Self::Sqlite(storage) => storage.ingest().await,
+            #[cfg(feature = "duckdb")]
+            Self::Duckdb(storage) => storage.ingest().await,
+        }
+    }
+
+    async fn verify(&self) -> Result<VerifyReport> {
+        match self {
+            Self::Jsonl(storage) => storage.verify().await,
+            #[cfg(feature = "sqlite")]
+            Self::Sqlite(storage) => storage.verify().await,
+            #[cfg(feature = "duckdb")]
+            Self::Duckdb(storage) => storage.verify().await,
+        }
+    }
+
+    async fn activity(
+        &self,
+        grouping: TimeGrouping,
+        timezone: FixedOffset,
+        from: NaiveDate,
+        to: NaiveDate,
+    ) -> Result<Vec<ActivityRollup>> {
+        match self {
+            Self::Jsonl(storage) => storage.activity(grouping, timezone, from, to).await,
+            #[cfg(feature = "sqlite")]
+            Self::Sqlite(storage) => storage.activity(grouping, timezone, from, to).await,
+            #[cfg(feature = "duckdb")]
+            Self::Duckdb(storage) => storage.activity(grouping, timezone, from, to).await,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sample::write_sample;
+    use tempfile::TempDir;
+
+    #[tokio::test]
+    async fn test_jsonl_storage() {
+        let temp_dir = TempDir::new().unwrap();
+        let projects_dirs = [temp_dir.path().join("projects")];
+        write_sample(&projects_dirs[0]).unwrap();
+        let parser = JsonlParser::new();
+
+        let storage = StorageBackend::open(StorageKind::Jsonl, None, &parser, &projects_dirs).unwrap();
+        let filter = TimeRangeFilter::new(None, None, Some("web".to_string()));
+        let (entries, coverage) = storage.load_entries(&filter).await.unwrap();
+
+        assert_eq!(entries.len(), 6);
+        assert_eq!((coverage.total_entries, coverage.matched_entries), (18, 6));
+        assert_eq!(storage.project_directories().unwrap().len(), 2);
+
+        // Streaming the files gives the same analysis as loading them first
+        let analyzer = WorkAnalyzer::new();
+        let (analysis, streamed_coverage) = storage.analyze(&filter, &analyzer).await.unwrap();
+        let loaded = analyzer.analyze_entries(&entries).unwrap();
+        assert_eq!(streamed_coverage, coverage);
+        assert_eq!((analysis.total_sessions, analysis.total_messages), (loaded.total_sessions, loaded.total_messages));
+        assert_eq!(analysis.time_range, loaded.time_range);
+    }
+
+    #[tokio::test]
+    async fn test_jsonl_quick_scan() {
+        let temp_dir = TempDir::new().unwrap();
+        let projects_dirs = [temp_dir.path().join("projects")];
+        write_sample(&projects_dirs[0]).unwrap();
+        let parser = JsonlParser::new();
+        let storage = JsonlStorage::new(&parser, &projects_dirs);
+        // A file last written long ago, e.g. an old log that a sync copied with its times
+        let web_log = storage.log_files().unwrap().into_iter().find(|path| path.to_string_lossy().contains("web")).unwrap();
+        let written = std::time::SystemTime::now() - std::time::Duration::from_secs(60 * 24 * 3600);
+        std::fs::File::options().write(true).open(&web_log).unwrap().set_modified(written).unwrap();
+
+        let filter = TimeRangeFilter::new(Some(Utc::now() - chrono::Duration::days(30)), None, None);
+        let analyzer = WorkAnalyzer::new();
+        let (full, _) = storage.analyze(&filter, &analyzer).await.unwrap();
+        let (quick, _) = JsonlStorage::new(&parser, &projects_dirs).with_quick_scan(true).analyze(&filter, &analyzer).await.unwrap();
+        assert_eq!((full.total_sessions, quick.total_sessions), (3, 2));
+        assert!(!quick.project_stats.contains_key("web"));
+    }
+
+    #[tokio::test]
+    async fn test_jsonl_ingest_counts_copies_once() {
+        let temp_dir = TempDir::new().unwrap();
+        let projects_dirs = [temp_dir.path().join("projects")];
+        write_sample(&projects_dirs[0]).unwrap();
+        let parser = JsonlParser::new();
+        let storage = JsonlStorage::new(&parser, &projects_dirs);
+        let original = storage.log_files().unwrap().remove(0);
+        std::fs::copy(&original, original.with_file_name("archived-copy.jsonl")).unwrap();
+
+        let (entries, _) = storage.load_entries(&TimeRangeFilter::new(None, None, None)).await.unwrap();
+        assert_eq!(entries.len(), 18);
+        let counts = storage.ingest().await.unwrap();
+        assert_eq!((counts.files_read, counts.entries_read, counts.new_entries), (4, 24, 18));
+
+        let report = storage.verify().await.unwrap();
+        assert_eq!((report.files_checked, report.entries_checked), (4, 24));
+        assert!(report.mismatches.is_empty());
+    }
+}
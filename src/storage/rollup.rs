@@ -0,0 +1,195 @@
+use chrono::{Duration, FixedOffset, NaiveDate};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashSet};
+use std::path::PathBuf;
+use uuid::Uuid;
+
+use crate::analyzer::WorkAnalyzer;
+use crate::models::{ClaudeLogEntry, TimeGrouping};
+use crate::paths;
+use crate::scanner::ProjectNames;
+
+/// Gap between two entries of a session up to which the time in between counts
+/// as work, the analyzer's default session gap
+pub const ROLLUP_SESSION_GAP_MINUTES: i64 = 120;
+
+/// Work on one project in one local day, week or month
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct ActivityRollup {
+    /// First local day of the period
+    pub start: NaiveDate,
+    pub project: String,
+    pub messages: usize,
+    pub active_seconds: i64,
+    /// Input and output tokens
+    pub tokens: u64,
+}
+
+/// Per local day and session path totals of a set of entries
+///
+/// A session counts under the shallowest directory it worked in, as the analyzer
+/// does for sessions outside the known project directories; [`name_projects`]
+/// turns these paths into projects. The time up to the next entry of the same
+/// session counts towards the day of the earlier entry, unless the gap starts a
+/// new session. Sums of these rows over disjoint sets of sessions add up, which
+/// is what lets the SQLite backend maintain them per log file.
+pub fn daily_rollups(entries: &[ClaudeLogEntry], timezone: &FixedOffset) -> Vec<ActivityRollup> {
+    let mut seen = HashSet::new();
+    let mut sessions: BTreeMap<Uuid, Vec<&ClaudeLogEntry>> = BTreeMap::new();
+    for entry in entries.iter().filter(|entry| !entry.is_sidechain) {
+        if seen.insert(entry.uuid) {
+            sessions.entry(entry.session_id).or_default().push(entry);
+        }
+    }
+
+    let gap = Duration::minutes(ROLLUP_SESSION_GAP_MINUTES);
+    let mut days: BTreeMap<(NaiveDate, String), ActivityRollup> = BTreeMap::new();
+    for mut session in sessions.into_values() {
+        session.sort_by_key(|entry| entry.timestamp);
+        // Subfolders visited during the session share its path
+        let project = session
+            .iter()
+            .map(|entry| entry.cwd.as_str())
+            .min_by_key(|cwd| paths::segments(cwd).count())
+            .unwrap_or_default()
+            .to_string();
+        for (index, entry) in session.iter().enumerate() {
+            let start = entry.timestamp.with_timezone(timezone).date_naive();
+            let project = project.clone();
+            let row = days.entry((start, project.clone())).or_insert_with(|| ActivityRollup {
+                start,
+                project,
+                ..Default::default()
+            });
+            row.messages += 1;
+            if let Some(usage) = &entry.message.usage {
+                row.tokens += usage.input_tokens.unwrap_or(0) as u64 + usage.output_tokens.unwrap_or(0) as u64;
+            }
+            if let Some(next) = session.get(index + 1) {
+                let elapsed = next.timestamp - entry.timestamp;
+                if elapsed <= gap {
+                    row.active_seconds += elapsed.num_seconds();
+                }
+            }
+        }
+    }
+    days.into_values().collect()
+}
+
+/// Merge daily rows into the periods of `grouping`, ordered by period and project
+pub fn group_rollups(daily: Vec<ActivityRollup>, grouping: TimeGrouping) -> Vec<ActivityRollup> {
+    sum_rollups(daily.into_iter().map(|row| ActivityRollup { start: grouping.bucket_start(row.start), ..row }))
+}
+
+/// Replace the session paths of `rows` with the names the analyzer gives their projects
+///
+/// A path counts under the deepest of `project_dirs` it is in, and projects get
+/// their [`ProjectNames`] display names, so the rows line up with the project
+/// stats of an analysis. Rows that end up in one project are merged.
+pub fn name_projects(rows: Vec<ActivityRollup>, project_dirs: &[PathBuf]) -> Vec<ActivityRollup> {
+    let analyzer = WorkAnalyzer::new().with_project_dirs(project_dirs);
+    let rows: Vec<ActivityRollup> = rows
+        .into_iter()
+        .map(|row| ActivityRollup { project: analyzer.project_root(&row.project).unwrap_or(row.project.clone()), ..row })
+        .collect();
+    let names = ProjectNames::new(rows.iter().map(|row| row.project.as_str()));
+    sum_rollups(rows.into_iter().map(|row| ActivityRollup { project: names.name(&row.project), ..row }))
+}
+
+/// Sum rows of the same period and project, ordered by period and project
+fn sum_rollups(rows: impl IntoIterator<Item = ActivityRollup>) -> Vec<ActivityRollup> {
+    let mut periods: BTreeMap<(NaiveDate, String), ActivityRollup> = BTreeMap::new();
+    for row in rows {
+        let period = periods.entry((row.start, row.project.clone())).or_insert_with(|| ActivityRollup {
+            start: row.start,
+            project: row.project.clone(),
+            ..Default::default()
+        });
+        period.messages += row.messages;
+        period.active_seconds += row.active_seconds;
+        period.tokens += row.tokens;
+    }
+    periods.into_values().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{EntryType, MessageContent, MessageContentVariant};
+    use chrono::{TimeZone, Utc};
+
+    fn entry(session_id: Uuid, cwd: &str, hour: u32, minute: u32) -> ClaudeLogEntry {
+        ClaudeLogEntry {
+            parent_uuid: None,
+            is_sidechain: false,
+            user_type: "external".to_string(),
+            cwd: cwd.to_string(),
+            session_id,
+            version: "1.0.0".to_string(),
+            entry_type: EntryType::User,
+            message: MessageContent {
+                role: "user".to_string(),
+                content: MessageContentVariant::String("test".to_string()),
+                id: None,
+                message_type: None,
+                model: None,
+                stop_reason: None,
+                stop_sequence: None,
+                usage: None,
+            },
+            uuid: Uuid::new_v4(),
+            timestamp: Utc.with_ymd_and_hms(2025, 7, 1, hour, minute, 0).unwrap(),
+            request_id: None,
+            tool_use_result: None,
+            source: None,
+        }
+    }
+
+    #[test]
+    fn test_daily_rollups() {
+        let session = Uuid::new_v4();
+        let entries = vec![
+            entry(session, "/home/me/api", 14, 30),
+            entry(session, "/home/me/api", 14, 50),
+            // 15:10 UTC is the next day in JST
+            entry(session, "/home/me/api", 15, 10),
+            // Three hours later starts a new session
+            entry(session, "/home/me/api", 18, 10),
+            entry(Uuid::new_v4(), "/home/me/web", 14, 0),
+        ];
+        let jst = FixedOffset::east_opt(9 * 3600).unwrap();
+
+        let days = daily_rollups(&entries, &jst);
+        let day = |d| NaiveDate::from_ymd_opt(2025, 7, d).unwrap();
+        let summary: Vec<_> = days
+            .iter()
+            .map(|row| (row.start, row.project.as_str(), row.messages, row.active_seconds))
+            .collect();
+        assert_eq!(
+            summary,
+            [(day(1), "/home/me/api", 2, 2400), (day(1), "/home/me/web", 1, 0), (day(2), "/home/me/api", 2, 0)]
+        );
+
+        let weeks = group_rollups(days, TimeGrouping::Week);
+        assert_eq!(weeks.len(), 2);
+        // 2025-07-01 is a Tuesday
+        let monday = NaiveDate::from_ymd_opt(2025, 6, 30).unwrap();
+        assert_eq!((weeks[0].start, weeks[0].project.as_str(), weeks[0].messages), (monday, "/home/me/api", 4));
+    }
+
+    #[test]
+    fn test_name_projects() {
+        let entries = vec![
+            entry(Uuid::new_v4(), "/home/me/a/api", 9, 0),
+            // A session that stays in a subfolder belongs to the project above it
+            entry(Uuid::new_v4(), "/home/me/a/api/src", 10, 0),
+            entry(Uuid::new_v4(), "/home/me/b/api", 11, 0),
+        ];
+        let utc = FixedOffset::east_opt(0).unwrap();
+        let project_dirs = [PathBuf::from("-home-me-a-api"), PathBuf::from("-home-me-b-api")];
+
+        let days = name_projects(daily_rollups(&entries, &utc), &project_dirs);
+        let projects: Vec<_> = days.iter().map(|row| (row.project.as_str(), row.messages)).collect();
+        assert_eq!(projects, [("a/api", 2), ("b/api", 1)]);
+    }
+}
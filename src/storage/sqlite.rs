@@ -0,0 +1,971 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, FixedOffset, NaiveDate, SecondsFormat, Utc};
+use rusqlite::{params, Connection, OptionalExtension, Transaction};
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+
+use super::{daily_rollups, group_rollups, ActivityRollup, MirrorStorage, MirrorStore};
+use crate::filter::FilterCoverage;
+use crate::models::{ClaudeLogEntry, EntryType, MessageContentVariant, TimeGrouping, WorkAnalysis, WorkSession};
+
+/// Schema changes in order; the database's `user_version` counts those applied
+const MIGRATIONS: &[&str] = &[
+    // 1: initial schema
+    "
+    CREATE TABLE projects (
+        id INTEGER PRIMARY KEY,
+        path TEXT NOT NULL UNIQUE,
+        name TEXT NOT NULL
+    );
+    CREATE TABLE sessions (
+        id INTEGER PRIMARY KEY,
+        session_id TEXT NOT NULL,
+        project_id INTEGER NOT NULL REFERENCES projects(id),
+        start_time TEXT NOT NULL,
+        end_time TEXT NOT NULL,
+        duration_minutes INTEGER NOT NULL,
+        total_messages INTEGER NOT NULL,
+        user_messages INTEGER NOT NULL,
+        assistant_messages INTEGER NOT NULL,
+        UNIQUE (session_id, start_time)
+    );
+    CREATE TABLE entries (
+        uuid TEXT PRIMARY KEY,
+        session_id INTEGER NOT NULL REFERENCES sessions(id) ON DELETE CASCADE,
+        parent_uuid TEXT,
+        entry_type TEXT NOT NULL,
+        timestamp TEXT NOT NULL,
+        is_sidechain INTEGER NOT NULL,
+        cwd TEXT NOT NULL,
+        content TEXT NOT NULL
+    );
+    CREATE INDEX entries_session_id ON entries(session_id);
+    CREATE INDEX entries_timestamp ON entries(timestamp);
+    CREATE TABLE token_usage (
+        entry_uuid TEXT PRIMARY KEY REFERENCES entries(uuid) ON DELETE CASCADE,
+        model TEXT,
+        input_tokens INTEGER NOT NULL,
+        output_tokens INTEGER NOT NULL,
+        cache_creation_tokens INTEGER NOT NULL,
+        cache_read_tokens INTEGER NOT NULL
+    );
+    CREATE TABLE project_stats (
+        project_name TEXT PRIMARY KEY,
+        total_sessions INTEGER NOT NULL,
+        total_messages INTEGER NOT NULL,
+        work_minutes INTEGER NOT NULL,
+        estimated_cost REAL
+    );
+    ",
+    // 2: mirror of the log files for the sqlite storage backend
+    "
+    CREATE TABLE log_files (
+        path TEXT PRIMARY KEY,
+        size INTEGER NOT NULL,
+        modified_nanos INTEGER NOT NULL
+    );
+    CREATE TABLE log_entries (
+        uuid TEXT PRIMARY KEY,
+        file_path TEXT NOT NULL,
+        timestamp TEXT NOT NULL,
+        data TEXT NOT NULL
+    );
+    CREATE INDEX log_entries_file_path ON log_entries(file_path);
+    CREATE INDEX log_entries_timestamp ON log_entries(timestamp);
+    ",
+    // 3: daily and weekly rollups of the mirrored entries
+    "
+    CREATE TABLE rollup_settings (
+        id INTEGER PRIMARY KEY CHECK (id = 1),
+        utc_offset_seconds INTEGER NOT NULL
+    );
+    CREATE TABLE log_file_activity (
+        file_path TEXT NOT NULL,
+        day TEXT NOT NULL,
+        project TEXT NOT NULL,
+        messages INTEGER NOT NULL,
+        active_seconds INTEGER NOT NULL,
+        tokens INTEGER NOT NULL,
+        PRIMARY KEY (file_path, day, project)
+    );
+    CREATE INDEX log_file_activity_day ON log_file_activity(day);
+    CREATE TABLE daily_activity (
+        day TEXT NOT NULL,
+        project TEXT NOT NULL,
+        messages INTEGER NOT NULL,
+        active_seconds INTEGER NOT NULL,
+        tokens INTEGER NOT NULL,
+        PRIMARY KEY (day, project)
+    );
+    CREATE TABLE weekly_activity (
+        week TEXT NOT NULL,
+        project TEXT NOT NULL,
+        messages INTEGER NOT NULL,
+        active_seconds INTEGER NOT NULL,
+        tokens INTEGER NOT NULL,
+        PRIMARY KEY (week, project)
+    );
+    ",
+    // 4: entries in several files are stored once; rollups are kept per session.
+    // The mirror is read again from the log files.
+    "
+    DROP TABLE log_entries;
+    CREATE TABLE log_entries (
+        uuid TEXT PRIMARY KEY,
+        session_id TEXT NOT NULL,
+        timestamp TEXT NOT NULL,
+        data TEXT NOT NULL
+    );
+    CREATE INDEX log_entries_session_id ON log_entries(session_id);
+    CREATE INDEX log_entries_timestamp ON log_entries(timestamp);
+    CREATE TABLE log_entry_files (
+        file_path TEXT NOT NULL,
+        uuid TEXT NOT NULL,
+        PRIMARY KEY (file_path, uuid)
+    );
+    CREATE INDEX log_entry_files_uuid ON log_entry_files(uuid);
+    DELETE FROM log_files;
+    DROP TABLE log_file_activity;
+    CREATE TABLE session_activity (
+        session_id TEXT NOT NULL,
+        day TEXT NOT NULL,
+        project TEXT NOT NULL,
+        messages INTEGER NOT NULL,
+        active_seconds INTEGER NOT NULL,
+        tokens INTEGER NOT NULL,
+        PRIMARY KEY (session_id, day, project)
+    );
+    CREATE INDEX session_activity_day ON session_activity(day);
+    DELETE FROM daily_activity;
+    DELETE FROM weekly_activity;
+    DELETE FROM rollup_settings;
+    ",
+    // 5: rollups are kept by session path instead of project name, and built again
+    "
+    DELETE FROM session_activity;
+    DELETE FROM daily_activity;
+    DELETE FROM weekly_activity;
+    DELETE FROM rollup_settings;
+    ",
+];
+
+/// Rows written by one export
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ExportCounts {
+    pub sessions: usize,
+    pub entries: usize,
+}
+
+/// Work history in a normalized SQLite database
+///
+/// Exports are idempotent: sessions are keyed by log session id and start time and
+/// entries by uuid, so exporting overlapping ranges updates rows instead of
+/// duplicating them. `project_stats` holds the statistics of the latest export.
+pub struct SqliteStore {
+    conn: Connection,
+}
+
+impl SqliteStore {
+    /// Open or create the database and bring its schema up to date
+    pub fn open(path: &Path) -> Result<Self> {
+        let conn = Connection::open(path)
+            .with_context(|| format!("Failed to open SQLite database: {}", path.display()))?;
+        let mut store = Self { conn };
+        store.migrate()?;
+        Ok(store)
+    }
+
+    /// Schema version of the database
+    pub fn schema_version(&self) -> Result<usize> {
+        let version: i64 = self.conn.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+        Ok(version as usize)
+    }
+
+    fn migrate(&mut self) -> Result<()> {
+        self.conn.pragma_update(None, "foreign_keys", true)?;
+        let version = self.schema_version()?;
+        if version > MIGRATIONS.len() {
+            return Err(anyhow::anyhow!(
+                "Database schema version {} is newer than this version supports ({})",
+                version,
+                MIGRATIONS.len()
+            ));
+        }
+
+        for (index, migration) in MIGRATIONS.iter().enumerate().skip(version) {
+            let tx = self.conn.transaction()?;
+            tx.execute_batch(migration)
+                .with_context(|| format!("Failed to apply schema migration {}", index + 1))?;
+            tx.pragma_update(None, "user_version", (index + 1) as i64)?;
+            tx.commit()?;
+        }
+        Ok(())
+    }
+
+    /// Write the sessions, entries, token usage and project statistics of an analysis
+    pub fn write_analysis(&mut self, analysis: &WorkAnalysis) -> Result<ExportCounts> {
+        let tx = self.conn.transaction()?;
+        let mut counts = ExportCounts::default();
+
+        for session in &analysis.sessions {
+            let session_row = Self::upsert_session(&tx, session, &analysis.project_name(session))?;
+            for entry in &session.entries {
+                Self::upsert_entry(&tx, session_row, entry)?;
+                counts.entries += 1;
+            }
+            counts.sessions += 1;
+        }
+
+        tx.execute("DELETE FROM project_stats", [])?;
+        for stats in analysis.project_stats.values() {
+            let cost = analysis
+                .token_analysis
+                .as_ref()
+                .and_then(|tokens| tokens.by_project.get(&stats.project_name))
+                .map(|tokens| tokens.estimated_cost);
+            tx.execute(
+                "INSERT INTO project_stats (project_name, total_sessions, total_messages, work_minutes, estimated_cost)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+                params![
+                    stats.project_name,
+                    stats.total_sessions as i64,
+                    stats.total_messages as i64,
+                    stats.work_time.num_minutes(),
+                    cost,
+                ],
+            )?;
+        }
+
+        tx.commit()?;
+        Ok(counts)
+    }
+
+    /// Insert or update a session and return its row id
+    ///
+    /// Rows of the same log sessions that overlap it in time are earlier
+    /// exports of the same work, cut at another range boundary, and are
+    /// replaced along with their entries.
+    fn upsert_session(tx: &Transaction, session: &WorkSession, project_name: &str) -> Result<i64> {
+        // A project's name gains parent segments once another project shares its last one
+        tx.execute(
+            "INSERT INTO projects (path, name) VALUES (?1, ?2) ON CONFLICT (path) DO UPDATE SET name = excluded.name",
+            params![session.project_path, project_name],
+        )?;
+        let project_id: i64 = tx.query_row(
+            "SELECT id FROM projects WHERE path = ?1",
+            params![session.project_path],
+            |row| row.get(0),
+        )?;
+
+        let session_id = session.session_id.to_string();
+        let start_time = session.start_time.to_rfc3339();
+        let end_time = session.end_time.to_rfc3339();
+        for log_session_id in std::iter::once(&session.session_id).chain(&session.merged_session_ids) {
+            tx.execute(
+                "DELETE FROM sessions
+                 WHERE session_id = ?1 AND start_time <= ?3 AND end_time >= ?2
+                     AND NOT (session_id = ?4 AND start_time = ?2)",
+                params![log_session_id.to_string(), start_time, end_time, session_id],
+            )?;
+        }
+        tx.execute(
+            "INSERT INTO sessions (session_id, project_id, start_time, end_time, duration_minutes,
+                 total_messages, user_messages, assistant_messages)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+             ON CONFLICT (session_id, start_time) DO UPDATE SET
+                 project_id = excluded.project_id,
+                 end_time = excluded.end_time,
+                 duration_minutes = excluded.duration_minutes,
+                 total_messages = excluded.total_messages,
+                 user_messages = excluded.user_messages,
+                 assistant_messages = excluded.assistant_messages",
+            params![
+                session_id,
+                project_id,
+                start_time,
+                end_time,
+                (session.end_time - session.start_time).num_minutes(),
+                session.total_messages as i64,
+                session.user_messages as i64,
+                session.assistant_messages as i64,
+            ],
+        )?;
+        let id = tx.query_row(
+            "SELECT id FROM sessions WHERE session_id = ?1 AND start_time = ?2",
+            params![session_id, start_time],
+            |row| row.get(0),
+        )?;
+        Ok(id)
+    }
+
+    fn upsert_entry(tx: &Transaction, session_row: i64, entry: &ClaudeLogEntry) -> Result<()> {
+        let entry_type = match entry.entry_type {
+            EntryType::User => "user",
+            EntryType::Assistant => "assistant",
+        };
+        tx.execute(
+            "INSERT OR REPLACE INTO entries (uuid, session_id, parent_uuid, entry_type, timestamp, is_sidechain, cwd, content)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            params![
+                entry.uuid.to_string(),
+                session_row,
+                entry.parent_uuid.map(|uuid| uuid.to_string()),
+                entry_type,
+                entry.timestamp.to_rfc3339(),
+                entry.is_sidechain,
+                entry.cwd,
+                message_text(&entry.message.content),
+            ],
+        )?;
+
+        if let Some(usage) = &entry.message.usage {
+            tx.execute(
+                "INSERT OR REPLACE INTO token_usage
+                     (entry_uuid, model, input_tokens, output_tokens, cache_creation_tokens, cache_read_tokens)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                params![
+                    entry.uuid.to_string(),
+                    entry.message.model,
+                    usage.input_tokens.unwrap_or(0),
+                    usage.output_tokens.unwrap_or(0),
+                    usage.cache_creation_input_tokens.unwrap_or(0),
+                    usage.cache_read_input_tokens.unwrap_or(0),
+                ],
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl MirrorStore for SqliteStore {
+    const FILE_NAME: &'static str = "entries.db";
+
+    fn open(path: &Path) -> Result<Self> {
+        SqliteStore::open(path)
+    }
+
+    fn is_log_file_current(&self, path: &Path, size: u64, modified_nanos: u128) -> Result<bool> {
+        let stored: Option<(i64, i64)> = self
+            .conn
+            .query_row(
+                "SELECT size, modified_nanos FROM log_files WHERE path = ?1",
+                params![path.to_string_lossy()],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()?;
+        Ok(stored == Some((size as i64, modified_nanos as i64)))
+    }
+
+    fn replace_log_file(
+        &mut self,
+        path: &Path,
+        size: u64,
+        modified_nanos: u128,
+        entries: &[ClaudeLogEntry],
+    ) -> Result<usize> {
+        let path = path.to_string_lossy();
+        let tx = self.conn.transaction()?;
+        tx.execute(
+            "INSERT INTO log_files (path, size, modified_nanos) VALUES (?1, ?2, ?3)
+             ON CONFLICT (path) DO UPDATE SET size = excluded.size, modified_nanos = excluded.modified_nanos",
+            params![path, size as i64, modified_nanos as i64],
+        )?;
+        let new_entries = Self::mirror_file(&tx, &path, entries)?;
+        tx.commit()?;
+        Ok(new_entries)
+    }
+
+    fn retain_log_files(&mut self, existing: &[PathBuf]) -> Result<()> {
+        let existing: Vec<String> = existing.iter().map(|path| path.to_string_lossy().into_owned()).collect();
+        let stored: Vec<String> = self
+            .conn
+            .prepare("SELECT path FROM log_files")?
+            .query_map([], |row| row.get(0))?
+            .collect::<rusqlite::Result<_>>()?;
+
+        let tx = self.conn.transaction()?;
+        for path in stored.iter().filter(|path| !existing.contains(path)) {
+            Self::mirror_file(&tx, path, &[])?;
+            tx.execute("DELETE FROM log_files WHERE path = ?1", params![path])?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    fn mirrored_log_files(&self) -> Result<Vec<PathBuf>> {
+        let paths: Vec<String> = self
+            .conn
+            .prepare("SELECT path FROM log_files UNION SELECT file_path FROM log_entry_files ORDER BY 1")?
+            .query_map([], |row| row.get(0))?
+            .collect::<rusqlite::Result<_>>()?;
+        Ok(paths.into_iter().map(PathBuf::from).collect())
+    }
+
+    fn log_file_entries(&self, path: &Path) -> Result<Vec<ClaudeLogEntry>> {
+        let mut statement = self.conn.prepare(
+            "SELECT e.data FROM log_entry_files f JOIN log_entries e ON e.uuid = f.uuid
+             WHERE f.file_path = ?1 ORDER BY e.timestamp",
+        )?;
+        let rows = statement.query_map(params![path.to_string_lossy()], |row| row.get::<_, String>(0))?;
+        let mut entries = Vec::new();
+        for data in rows {
+            entries.push(serde_json::from_str(&data?).context("Corrupt entry in the SQLite mirror")?);
+        }
+        Ok(entries)
+    }
+
+    fn query_log_entries(&self, from: Option<DateTime<Utc>>, to: Option<DateTime<Utc>>) -> Result<Vec<ClaudeLogEntry>> {
+        let mut statement = self.conn.prepare(
+            "SELECT data FROM log_entries
+             WHERE (?1 IS NULL OR timestamp >= ?1) AND (?2 IS NULL OR timestamp <= ?2)
+             ORDER BY timestamp",
+        )?;
+        let rows = statement.query_map(
+            params![from.map(sortable_timestamp), to.map(sortable_timestamp)],
+            |row| row.get::<_, String>(0),
+        )?;
+        let mut entries = Vec::new();
+        for data in rows {
+            entries.push(serde_json::from_str(&data?).context("Corrupt entry in the SQLite mirror")?);
+        }
+        Ok(entries)
+    }
+
+    fn log_entry_coverage(&self) -> Result<FilterCoverage> {
+        let (count, first, last): (i64, Option<String>, Option<String>) = self.conn.query_row(
+            "SELECT COUNT(*), MIN(timestamp), MAX(timestamp) FROM log_entries",
+            [],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        )?;
+        let parse = |timestamp: Option<String>| {
+            timestamp
+                .and_then(|t| DateTime::parse_from_rfc3339(&t).ok())
+                .map(|t| t.with_timezone(&Utc))
+        };
+        Ok(FilterCoverage {
+            total_entries: count as usize,
+            matched_entries: 0,
+            first_entry: parse(first),
+            last_entry: parse(last),
+        })
+    }
+
+    fn rollups(
+        &mut self,
+        grouping: TimeGrouping,
+        timezone: FixedOffset,
+        from: NaiveDate,
+        to: NaiveDate,
+    ) -> Result<Option<Vec<ActivityRollup>>> {
+        self.activity(grouping, timezone, from, to).map(Some)
+    }
+}
+
+impl SqliteStore {
+    /// Make `entries` the entries of a log file and return how many were not stored before
+    fn mirror_file(tx: &Transaction, path: &str, entries: &[ClaudeLogEntry]) -> Result<usize> {
+        let previous: Vec<(String, String)> = tx
+            .prepare(
+                "SELECT e.uuid, e.session_id FROM log_entry_files f JOIN log_entries e ON e.uuid = f.uuid
+                 WHERE f.file_path = ?1",
+            )?
+            .query_map(params![path], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<rusqlite::Result<_>>()?;
+        tx.execute("DELETE FROM log_entry_files WHERE file_path = ?1", params![path])?;
+
+        let mut sessions = BTreeSet::new();
+        let mut new_entries = 0;
+        for entry in entries {
+            let uuid = entry.uuid.to_string();
+            let stored: bool = tx.query_row(
+                "SELECT EXISTS (SELECT 1 FROM log_entries WHERE uuid = ?1)",
+                params![uuid],
+                |row| row.get(0),
+            )?;
+            if !stored {
+                new_entries += 1;
+            }
+            tx.execute(
+                "INSERT INTO log_entries (uuid, session_id, timestamp, data) VALUES (?1, ?2, ?3, ?4)
+                 ON CONFLICT (uuid) DO UPDATE SET
+                     session_id = excluded.session_id,
+                     timestamp = excluded.timestamp,
+                     data = excluded.data",
+                params![
+                    uuid,
+                    entry.session_id.to_string(),
+                    sortable_timestamp(entry.timestamp),
+                    serde_json::to_string(entry)?,
+                ],
+            )?;
+            tx.execute(
+                "INSERT OR IGNORE INTO log_entry_files (file_path, uuid) VALUES (?1, ?2)",
+                params![path, uuid],
+            )?;
+            sessions.insert(entry.session_id.to_string());
+        }
+
+        for (uuid, session_id) in previous {
+            tx.execute(
+                "DELETE FROM log_entries
+                 WHERE uuid = ?1 AND NOT EXISTS (SELECT 1 FROM log_entry_files WHERE uuid = ?1)",
+                params![uuid],
+            )?;
+            sessions.insert(session_id);
+        }
+        Self::refresh_sessions(tx, &sessions)?;
+        Ok(new_entries)
+    }
+}
+
+/// Rollups of the mirrored entries
+///
+/// `session_activity` holds what each session adds to each local day and
+/// project. When a log file is mirrored again only the rows of its sessions are
+/// computed again, and the `daily_activity` and `weekly_activity` rows of the
+/// days they touch are summed again, so the rollups stay current without reading
+/// other sessions.
+impl SqliteStore {
+    /// Timezone the rollup days are in, or `None` before they are first built
+    fn rollup_timezone(conn: &Connection) -> Result<Option<FixedOffset>> {
+        let seconds: Option<i32> = conn
+            .query_row("SELECT utc_offset_seconds FROM rollup_settings WHERE id = 1", [], |row| row.get(0))
+            .optional()?;
+        Ok(seconds.and_then(FixedOffset::east_opt))
+    }
+
+    /// Rebuild every rollup from the mirrored entries with days in `timezone`
+    pub fn rebuild_rollups(&mut self, timezone: FixedOffset) -> Result<()> {
+        let tx = self.conn.transaction()?;
+        tx.execute_batch("DELETE FROM session_activity; DELETE FROM daily_activity; DELETE FROM weekly_activity;")?;
+        tx.execute(
+            "INSERT INTO rollup_settings (id, utc_offset_seconds) VALUES (1, ?1)
+             ON CONFLICT (id) DO UPDATE SET utc_offset_seconds = excluded.utc_offset_seconds",
+            params![timezone.local_minus_utc()],
+        )?;
+        let sessions: BTreeSet<String> = tx
+            .prepare("SELECT DISTINCT session_id FROM log_entries")?
+            .query_map([], |row| row.get(0))?
+            .collect::<rusqlite::Result<_>>()?;
+        Self::refresh_sessions(&tx, &sessions)?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Compute the rollup rows of sessions again and re-sum the days they touch
+    fn refresh_sessions(tx: &Transaction, sessions: &BTreeSet<String>) -> Result<()> {
+        let Some(timezone) = Self::rollup_timezone(tx)? else {
+            return Ok(());
+        };
+
+        let mut days = BTreeSet::new();
+        for session_id in sessions {
+            for day in tx
+                .prepare("SELECT day FROM session_activity WHERE session_id = ?1")?
+                .query_map(params![session_id], |row| row.get::<_, String>(0))?
+            {
+                days.extend(NaiveDate::parse_from_str(&day?, "%Y-%m-%d").ok());
+            }
+            tx.execute("DELETE FROM session_activity WHERE session_id = ?1", params![session_id])?;
+
+            let mut entries = Vec::new();
+            for data in tx
+                .prepare("SELECT data FROM log_entries WHERE session_id = ?1")?
+                .query_map(params![session_id], |row| row.get::<_, String>(0))?
+            {
+                entries.push(serde_json::from_str(&data?).context("Corrupt entry in the SQLite mirror")?);
+            }
+            for row in daily_rollups(&entries, &timezone) {
+                tx.execute(
+                    "INSERT INTO session_activity (session_id, day, project, messages, active_seconds, tokens)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                    params![
+                        session_id,
+                        row.start.to_string(),
+                        row.project,
+                        row.messages as i64,
+                        row.active_seconds,
+                        row.tokens as i64
+                    ],
+                )?;
+                days.insert(row.start);
+            }
+        }
+
+        let mut weeks = BTreeSet::new();
+        for day in days {
+            tx.execute("DELETE FROM daily_activity WHERE day = ?1", params![day.to_string()])?;
+            tx.execute(
+                "INSERT INTO daily_activity (day, project, messages, active_seconds, tokens)
+                 SELECT day, project, SUM(messages), SUM(active_seconds), SUM(tokens)
+                 FROM session_activity WHERE day = ?1 GROUP BY project",
+                params![day.to_string()],
+            )?;
+            weeks.insert(TimeGrouping::Week.bucket_start(day));
+        }
+        for week in weeks {
+            let last_day = week + chrono::Duration::days(6);
+            tx.execute("DELETE FROM weekly_activity WHERE week = ?1", params![week.to_string()])?;
+            tx.execute(
+                "INSERT INTO weekly_activity (week, project, messages, active_seconds, tokens)
+                 SELECT ?1, project, SUM(messages), SUM(active_seconds), SUM(tokens)
+                 FROM daily_activity WHERE day BETWEEN ?1 AND ?2 GROUP BY project",
+                params![week.to_string(), last_day.to_string()],
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Rollup rows of the periods between `from` and `to`, ordered by period and project
+    ///
+    /// The rollups are rebuilt first if their days are in another timezone.
+    pub fn activity(
+        &mut self,
+        grouping: TimeGrouping,
+        timezone: FixedOffset,
+        from: NaiveDate,
+        to: NaiveDate,
+    ) -> Result<Vec<ActivityRollup>> {
+        if Self::rollup_timezone(&self.conn)? != Some(timezone) {
+            self.rebuild_rollups(timezone)?;
+        }
+
+        // Weeks are read from their own table, months are summed from the daily rows
+        let (query, from, to) = match grouping {
+            TimeGrouping::Week => (
+                "SELECT week, project, messages, active_seconds, tokens FROM weekly_activity
+                 WHERE week BETWEEN ?1 AND ?2 ORDER BY week, project",
+                grouping.bucket_start(from),
+                grouping.bucket_start(to),
+            ),
+            TimeGrouping::Day | TimeGrouping::Month => (
+                "SELECT day, project, messages, active_seconds, tokens FROM daily_activity
+                 WHERE day BETWEEN ?1 AND ?2 ORDER BY day, project",
+                grouping.bucket_start(from),
+                to,
+            ),
+        };
+        let mut statement = self.conn.prepare(query)?;
+        let rows = statement.query_map(params![from.to_string(), to.to_string()], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                ActivityRollup {
+                    start: NaiveDate::default(),
+                    project: row.get(1)?,
+                    messages: row.get::<_, i64>(2)? as usize,
+                    active_seconds: row.get(3)?,
+                    tokens: row.get::<_, i64>(4)? as u64,
+                },
+            ))
+        })?;
+        let mut activity = Vec::new();
+        for row in rows {
+            let (start, row) = row?;
+            let start = NaiveDate::parse_from_str(&start, "%Y-%m-%d").context("Corrupt rollup in the SQLite mirror")?;
+            activity.push(ActivityRollup { start, ..row });
+        }
+        Ok(match grouping {
+            TimeGrouping::Month => group_rollups(activity, grouping),
+            _ => activity,
+        })
+    }
+}
+
+/// RFC 3339 in UTC with a fixed number of digits, so text order is time order
+fn sortable_timestamp(timestamp: DateTime<Utc>) -> String {
+    timestamp.to_rfc3339_opts(SecondsFormat::Nanos, true)
+}
+
+/// Answers queries from a SQLite mirror of the JSONL files
+pub type SqliteStorage<'a> = MirrorStorage<'a, SqliteStore>;
+
+/// Plain text of a message, joining the text blocks of structured content
+fn message_text(content: &MessageContentVariant) -> String {
+    match content {
+        MessageContentVariant::String(text) => text.clone(),
+        MessageContentVariant::Array(blocks) => blocks
+            .iter()
+            .filter_map(|block| block.text.as_deref())
+            .collect::<Vec<_>>()
+            .join("\n"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyzer::WorkAnalyzer;
+    use crate::models::{MessageContent, UsageInfo};
+    use crate::filter::TimeRangeFilter;
+    use crate::parser::JsonlParser;
+    use crate::storage::{name_projects, JsonlStorage, MismatchKind, Storage};
+    use chrono::{Duration, TimeZone, Utc};
+    use tempfile::TempDir;
+    use uuid::Uuid;
+
+    fn create_test_entry(session_id: Uuid, minute: i64, entry_type: EntryType) -> ClaudeLogEntry {
+        let usage = matches!(entry_type, EntryType::Assistant).then_some(UsageInfo {
+            input_tokens: Some(100),
+            output_tokens: Some(50),
+            cache_creation_input_tokens: None,
+            cache_read_input_tokens: Some(1000),
+            service_tier: None,
+        });
+        ClaudeLogEntry {
+            parent_uuid: None,
+            is_sidechain: false,
+            user_type: "external".to_string(),
+            cwd: "/home/user/work/api".to_string(),
+            session_id,
+            version: "1.0.0".to_string(),
+            entry_type,
+            message: MessageContent {
+                role: "user".to_string(),
+                content: MessageContentVariant::String("implement the api".to_string()),
+                id: None,
+                message_type: None,
+                model: Some("claude-sonnet-4-20250514".to_string()),
+                stop_reason: None,
+                stop_sequence: None,
+                usage,
+            },
+            uuid: Uuid::new_v4(),
+            timestamp: Utc.with_ymd_and_hms(2025, 7, 1, 9, 0, 0).unwrap() + Duration::minutes(minute),
+            request_id: None,
+            tool_use_result: None,
+            source: None,
+        }
+    }
+
+    fn count(store: &SqliteStore, table: &str) -> i64 {
+        store
+            .conn
+            .query_row(&format!("SELECT COUNT(*) FROM {}", table), [], |row| row.get(0))
+            .unwrap()
+    }
+
+    #[test]
+    fn test_migrations_are_applied_once() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("work.db");
+
+        let store = SqliteStore::open(&path).unwrap();
+        assert_eq!(store.schema_version().unwrap(), MIGRATIONS.len());
+        drop(store);
+
+        // Reopening an up-to-date database changes nothing
+        let store = SqliteStore::open(&path).unwrap();
+        assert_eq!(store.schema_version().unwrap(), MIGRATIONS.len());
+
+        store.conn.pragma_update(None, "user_version", 99).unwrap();
+        drop(store);
+        assert!(SqliteStore::open(&path).is_err());
+    }
+
+    #[test]
+    fn test_write_analysis() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut store = SqliteStore::open(&temp_dir.path().join("work.db")).unwrap();
+
+        let session_id = Uuid::new_v4();
+        let entries = vec![
+            create_test_entry(session_id, 0, EntryType::User),
+            create_test_entry(session_id, 5, EntryType::Assistant),
+            create_test_entry(session_id, 10, EntryType::User),
+            create_test_entry(session_id, 15, EntryType::Assistant),
+        ];
+        let analysis = WorkAnalyzer::new().analyze_entries(&entries).unwrap();
+
+        let counts = store.write_analysis(&analysis).unwrap();
+        assert_eq!(counts, ExportCounts { sessions: 1, entries: 4 });
+
+        // Exporting the same data again updates the existing rows
+        store.write_analysis(&analysis).unwrap();
+        assert_eq!(count(&store, "projects"), 1);
+        assert_eq!(count(&store, "sessions"), 1);
+        assert_eq!(count(&store, "entries"), 4);
+        assert_eq!(count(&store, "token_usage"), 2);
+        assert_eq!(count(&store, "project_stats"), 1);
+
+        let (duration, output_tokens): (i64, i64) = store
+            .conn
+            .query_row(
+                "SELECT s.duration_minutes, SUM(t.output_tokens)
+                 FROM sessions s JOIN entries e ON e.session_id = s.id JOIN token_usage t ON t.entry_uuid = e.uuid",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!(duration, 15);
+        assert_eq!(output_tokens, 100);
+    }
+
+    #[test]
+    fn test_overlapping_exports_replace_the_session() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut store = SqliteStore::open(&temp_dir.path().join("work.db")).unwrap();
+
+        let session_id = Uuid::new_v4();
+        let entries: Vec<_> = (0..4)
+            .map(|i| create_test_entry(session_id, i * 5, if i % 2 == 0 { EntryType::User } else { EntryType::Assistant }))
+            .collect();
+        let analyzer = WorkAnalyzer::new();
+
+        // A range starting inside the session cuts it at another start time
+        store.write_analysis(&analyzer.analyze_entries(&entries).unwrap()).unwrap();
+        store.write_analysis(&analyzer.analyze_entries(&entries[1..]).unwrap()).unwrap();
+        assert_eq!(count(&store, "sessions"), 1);
+        assert_eq!(count(&store, "entries"), 3);
+
+        store.write_analysis(&analyzer.analyze_entries(&entries).unwrap()).unwrap();
+        assert_eq!(count(&store, "sessions"), 1);
+        assert_eq!(count(&store, "entries"), 4);
+        let duration: i64 = store
+            .conn
+            .query_row("SELECT duration_minutes FROM sessions", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(duration, 15);
+    }
+
+    #[tokio::test]
+    async fn test_sqlite_storage_mirrors_logs() {
+        let temp_dir = TempDir::new().unwrap();
+        let projects_dirs = [temp_dir.path().join("projects")];
+        crate::sample::write_sample(&projects_dirs[0]).unwrap();
+        let parser = JsonlParser::new();
+        let storage = SqliteStorage::open(
+            &temp_dir.path().join("db").join("entries.db"),
+            JsonlStorage::new(&parser, &projects_dirs),
+        )
+        .unwrap();
+
+        let (entries, coverage) = storage.load_entries(&TimeRangeFilter::new(None, None, None)).await.unwrap();
+        assert_eq!(entries.len(), 18);
+        assert_eq!((coverage.total_entries, coverage.matched_entries), (18, 18));
+        assert!(entries.windows(2).all(|pair| pair[0].timestamp <= pair[1].timestamp));
+
+        // Unchanged files are not read again
+        assert_eq!(storage.sync().await.unwrap().files_read, 0);
+
+        let filter = TimeRangeFilter::new(Some(entries[6].timestamp), None, Some("api".to_string()));
+        let (selected, coverage) = storage.load_entries(&filter).await.unwrap();
+        assert!(selected.iter().all(|entry| entry.cwd.ends_with("/api") && entry.timestamp >= entries[6].timestamp));
+        assert_eq!(coverage.matched_entries, selected.len());
+
+        // Removed log files drop out of the mirror
+        let removed = storage.source().log_files().unwrap().remove(0);
+        std::fs::remove_file(removed).unwrap();
+        let (entries, _) = storage.load_entries(&TimeRangeFilter::new(None, None, None)).await.unwrap();
+        assert_eq!(entries.len(), 12);
+    }
+
+    #[tokio::test]
+    async fn test_sync_without_refresh_leaves_the_mirror() {
+        let temp_dir = TempDir::new().unwrap();
+        let projects_dirs = [temp_dir.path().join("projects")];
+        crate::sample::write_sample(&projects_dirs[0]).unwrap();
+        let path = temp_dir.path().join("entries.db");
+
+        // Cached or skipped results may be older than the files, so nothing is recorded
+        let parser = JsonlParser::new().with_refresh(false);
+        let storage = SqliteStorage::open(&path, JsonlStorage::new(&parser, &projects_dirs)).unwrap();
+        let counts = storage.sync().await.unwrap();
+        assert_eq!((counts.files_read, counts.new_entries), (0, 0));
+        drop(storage);
+
+        let parser = JsonlParser::new();
+        let storage = SqliteStorage::open(&path, JsonlStorage::new(&parser, &projects_dirs)).unwrap();
+        let counts = storage.sync().await.unwrap();
+        assert_eq!((counts.files_read, counts.new_entries), (3, 18));
+    }
+
+    #[tokio::test]
+    async fn test_ingest_is_idempotent() {
+        let temp_dir = TempDir::new().unwrap();
+        let projects_dirs = [temp_dir.path().join("projects")];
+        crate::sample::write_sample(&projects_dirs[0]).unwrap();
+        let parser = JsonlParser::new();
+        let storage = SqliteStorage::open(
+            &temp_dir.path().join("entries.db"),
+            JsonlStorage::new(&parser, &projects_dirs),
+        )
+        .unwrap();
+        // A re-imported archive holds a copy of a session already in the logs
+        let original = storage.source().log_files().unwrap().remove(0);
+        let archived = original.with_file_name("archived-copy.jsonl");
+        std::fs::copy(&original, &archived).unwrap();
+
+        let counts = storage.ingest().await.unwrap();
+        assert_eq!((counts.files_read, counts.entries_read, counts.new_entries), (4, 24, 18));
+        let counts = storage.ingest().await.unwrap();
+        assert_eq!((counts.files_read, counts.files_unchanged, counts.new_entries), (0, 4, 0));
+
+        let everything = TimeRangeFilter::new(None, None, None);
+        let (entries, _) = storage.load_entries(&everything).await.unwrap();
+        assert_eq!(entries.len(), 18);
+        let utc = FixedOffset::east_opt(0).unwrap();
+        let from = NaiveDate::from_ymd_opt(2000, 1, 1).unwrap();
+        let to = NaiveDate::from_ymd_opt(2100, 1, 1).unwrap();
+        let days = storage.activity(TimeGrouping::Day, utc, from, to).await.unwrap();
+        assert_eq!(days, name_projects(daily_rollups(&entries, &utc), &storage.project_directories().unwrap()));
+        assert!(storage.verify().await.unwrap().mismatches.is_empty());
+
+        // The entries stay while the archived copy still contains them
+        std::fs::remove_file(&original).unwrap();
+        let (entries, _) = storage.load_entries(&everything).await.unwrap();
+        assert_eq!(entries.len(), 18);
+        assert_eq!(storage.activity(TimeGrouping::Day, utc, from, to).await.unwrap(), days);
+
+        // Changes not ingested yet are reported
+        let content = std::fs::read_to_string(&archived).unwrap();
+        std::fs::write(&archived, format!("{}\n", content.lines().next().unwrap())).unwrap();
+        let report = storage.verify().await.unwrap();
+        assert_eq!(report.files_checked, 3);
+        assert_eq!(report.mismatches.len(), 5);
+        assert!(report.mismatches.iter().all(|mismatch| mismatch.kind == MismatchKind::Extra));
+    }
+
+    #[tokio::test]
+    async fn test_activity_rollups() {
+        let temp_dir = TempDir::new().unwrap();
+        let projects_dirs = [temp_dir.path().join("projects")];
+        crate::sample::write_sample(&projects_dirs[0]).unwrap();
+        let parser = JsonlParser::new();
+        let storage = SqliteStorage::open(
+            &temp_dir.path().join("entries.db"),
+            JsonlStorage::new(&parser, &projects_dirs),
+        )
+        .unwrap();
+        let from = NaiveDate::from_ymd_opt(2000, 1, 1).unwrap();
+        let to = NaiveDate::from_ymd_opt(2100, 1, 1).unwrap();
+        let everything = TimeRangeFilter::new(None, None, None);
+        let project_dirs = storage.project_directories().unwrap();
+        let expected = |entries: &[ClaudeLogEntry], timezone: FixedOffset, grouping| {
+            name_projects(group_rollups(daily_rollups(entries, &timezone), grouping), &project_dirs)
+        };
+
+        let utc = FixedOffset::east_opt(0).unwrap();
+        let (entries, _) = storage.source().load_entries(&everything).await.unwrap();
+        let days = storage.activity(TimeGrouping::Day, utc, from, to).await.unwrap();
+        assert_eq!(days[0].project, "api");
+        assert_eq!(days, expected(&entries, utc, TimeGrouping::Day));
+        let weeks = storage.activity(TimeGrouping::Week, utc, from, to).await.unwrap();
+        assert_eq!(weeks, expected(&entries, utc, TimeGrouping::Week));
+        let months = storage.activity(TimeGrouping::Month, utc, from, to).await.unwrap();
+        assert_eq!(months, expected(&entries, utc, TimeGrouping::Month));
+
+        // Removing a log file updates the days it touched
+        let removed = storage.source().log_files().unwrap().remove(0);
+        std::fs::remove_file(removed).unwrap();
+        let (entries, _) = storage.source().load_entries(&everything).await.unwrap();
+        let days = storage.activity(TimeGrouping::Day, utc, from, to).await.unwrap();
+        assert_eq!(days, expected(&entries, utc, TimeGrouping::Day));
+        let weeks = storage.activity(TimeGrouping::Week, utc, from, to).await.unwrap();
+        assert_eq!(weeks, expected(&entries, utc, TimeGrouping::Week));
+
+        // Another timezone rebuilds the rollups
+        let jst = FixedOffset::east_opt(9 * 3600).unwrap();
+        let days = storage.activity(TimeGrouping::Day, jst, from, to).await.unwrap();
+        assert_eq!(days, expected(&entries, jst, TimeGrouping::Day));
+    }
+}
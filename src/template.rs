@@ -0,0 +1,151 @@
+use anyhow::{Context, Result};
+use handlebars::{no_escape, Handlebars};
+use serde::Serialize;
+use std::path::Path;
+
+/// The built-in layout of the markdown report: the header, then each section
+pub const DEFAULT_TEMPLATE: &str = include_str!("../templates/report.md.hbs");
+
+const TEMPLATE_NAME: &str = "report";
+
+/// What a report template is rendered with
+///
+/// The figures of the analysis for custom layouts, and the sections of the
+/// built-in report already rendered as markdown for templates that only
+/// rearrange or wrap them.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ReportContext {
+    /// Title and period as the built-in report shows them
+    pub header: String,
+    pub period: PeriodContext,
+    pub totals: TotalsContext,
+    /// Most worked-on first
+    pub projects: Vec<ProjectContext>,
+    /// Earliest first
+    pub sessions: Vec<SessionContext>,
+    /// The built-in sections the report shows, in their order
+    pub sections: Vec<SectionContext>,
+}
+
+/// Local times formatted like `2025-07-01 09:30`
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct PeriodContext {
+    pub start: String,
+    pub end: String,
+    /// Label of the report timezone, e.g. `JST`
+    pub timezone: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct TotalsContext {
+    pub sessions: usize,
+    pub messages: usize,
+    /// Rounded to one decimal
+    pub work_hours: f64,
+    pub average_session_minutes: i64,
+    pub projects: usize,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ProjectContext {
+    pub name: String,
+    pub sessions: usize,
+    pub messages: usize,
+    pub work_hours: f64,
+    /// Primary topics, when topics were analyzed
+    pub topics: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct SessionContext {
+    pub id: String,
+    pub project: String,
+    pub start: String,
+    pub end: String,
+    pub duration_minutes: i64,
+    pub messages: usize,
+    pub summary: Option<String>,
+}
+
+/// A section of the built-in report
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct SectionContext {
+    /// Name as given to `--sections`, e.g. `top-sessions`, or `log-coverage`
+    pub id: String,
+    /// Heading without the `##`
+    pub title: String,
+    pub body: String,
+}
+
+/// A Handlebars template of the markdown report, e.g. from `--template my_report.hbs`
+///
+/// Values are inserted as they are, without HTML escaping, and referring to a
+/// value the context does not have is an error rather than an empty string.
+#[derive(Clone)]
+pub struct ReportTemplate {
+    registry: Handlebars<'static>,
+}
+
+impl ReportTemplate {
+    pub fn new(source: &str) -> Result<Self> {
+        let mut registry = Handlebars::new();
+        registry.register_escape_fn(no_escape);
+        registry.set_strict_mode(true);
+        registry.register_template_string(TEMPLATE_NAME, source)?;
+        Ok(Self { registry })
+    }
+
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let source = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read report template: {}", path.display()))?;
+        Self::new(&source).with_context(|| format!("Invalid report template: {}", path.display()))
+    }
+
+    pub fn render(&self, context: &ReportContext) -> Result<String> {
+        self.registry.render(TEMPLATE_NAME, context).context("Failed to render report template")
+    }
+}
+
+impl Default for ReportTemplate {
+    fn default() -> Self {
+        Self::new(DEFAULT_TEMPLATE).expect("the built-in report template is valid")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn context() -> ReportContext {
+        ReportContext {
+            header: "# Report".to_string(),
+            totals: TotalsContext { sessions: 3, work_hours: 2.5, ..Default::default() },
+            projects: vec![ProjectContext { name: "api<v2>".to_string(), ..Default::default() }],
+            sections: vec![
+                SectionContext { id: "summary".to_string(), title: "Summary".to_string(), body: "- a\n- b".to_string() },
+                SectionContext { id: "insights".to_string(), title: "Insights".to_string(), body: "None".to_string() },
+            ],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_default_template() {
+        let report = ReportTemplate::default().render(&context()).unwrap();
+        assert_eq!(report, "# Report\n\n## Summary\n\n- a\n- b\n\n## Insights\n\nNone\n");
+    }
+
+    #[test]
+    fn test_custom_template() {
+        let template = ReportTemplate::new(
+            "{{totals.sessions}} sessions, {{totals.work_hours}}h\n{{#each projects}}* {{name}}\n{{/each}}",
+        )
+        .unwrap();
+        assert_eq!(template.render(&context()).unwrap(), "3 sessions, 2.5h\n* api<v2>\n");
+
+        assert!(ReportTemplate::new("{{#each projects}}").is_err());
+        // Misspelled values fail instead of rendering nothing
+        let typo = ReportTemplate::new("{{totals.sesions}}").unwrap();
+        assert!(typo.render(&context()).is_err());
+    }
+}
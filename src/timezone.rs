@@ -0,0 +1,273 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration, FixedOffset, Local, NaiveDate, Offset, TimeZone, Utc};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// Japan Standard Time (UTC+9), the historical default of this tool
+pub fn jst() -> FixedOffset {
+    FixedOffset::east_opt(9 * 3600).unwrap()
+}
+
+/// Parse a timezone specification such as `UTC`, `JST`, `+09:00` or `-0530`
+pub fn parse_timezone(spec: &str) -> Result<FixedOffset> {
+    let spec = spec.trim();
+
+    match spec.to_uppercase().as_str() {
+        "UTC" | "GMT" | "Z" => return Ok(FixedOffset::east_opt(0).unwrap()),
+        "JST" => return Ok(jst()),
+        _ => {}
+    }
+
+    // Accept "UTC+9" / "GMT-05:00" style prefixes as well as bare offsets
+    let offset = match spec.get(..3) {
+        Some(prefix) if prefix.eq_ignore_ascii_case("UTC") || prefix.eq_ignore_ascii_case("GMT") => &spec[3..],
+        _ => spec,
+    };
+
+    let invalid = || {
+        anyhow::anyhow!(
+            "Invalid timezone '{}'. Expected UTC, JST or an offset like +09:00",
+            spec
+        )
+    };
+
+    let (sign, digits) = match offset.chars().next() {
+        Some('+') => (1, &offset[1..]),
+        Some('-') => (-1, &offset[1..]),
+        _ => return Err(invalid()),
+    };
+
+    let (hours, minutes) = if let Some((h, m)) = digits.split_once(':') {
+        (h, m)
+    } else if digits.len() == 4 {
+        digits.split_at(2)
+    } else {
+        (digits, "0")
+    };
+
+    let hours: i32 = hours.parse().map_err(|_| invalid())?;
+    let minutes: i32 = minutes.parse().map_err(|_| invalid())?;
+    if hours > 14 || minutes >= 60 {
+        return Err(invalid());
+    }
+
+    FixedOffset::east_opt(sign * (hours * 3600 + minutes * 60)).ok_or_else(invalid)
+}
+
+/// Current offset of the system timezone (honours the `TZ` environment variable)
+pub fn system_timezone() -> FixedOffset {
+    Local::now().offset().fix()
+}
+
+/// Parse an explicit timezone option, falling back to the system timezone
+pub fn resolve_timezone(spec: Option<&str>) -> Result<FixedOffset> {
+    match spec {
+        Some(spec) => parse_timezone(spec),
+        None => Ok(system_timezone()),
+    }
+}
+
+/// Short label used when rendering times, e.g. `JST`, `UTC` or `UTC-05:00`
+pub fn timezone_label(tz: &FixedOffset) -> String {
+    match tz.local_minus_utc() {
+        0 => "UTC".to_string(),
+        32400 => "JST".to_string(),
+        seconds => {
+            let sign = if seconds < 0 { '-' } else { '+' };
+            let seconds = seconds.abs();
+            format!("UTC{}{:02}:{:02}", sign, seconds / 3600, (seconds % 3600) / 60)
+        }
+    }
+}
+
+/// Timezones that take effect on given local dates, e.g. while travelling
+///
+/// A change applies from local midnight of its date in the new timezone, so the
+/// day of a flight is one long (or short) day rather than being split or merged.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TimezoneSchedule {
+    default: FixedOffset,
+    /// First day and timezone of each change, sorted by day
+    changes: Vec<(NaiveDate, FixedOffset)>,
+}
+
+impl TimezoneSchedule {
+    /// A schedule that always uses the same timezone
+    pub fn fixed(timezone: FixedOffset) -> Self {
+        Self {
+            default: timezone,
+            changes: Vec::new(),
+        }
+    }
+
+    /// Use `timezone` from local midnight of `from` on
+    pub fn with_change(mut self, from: NaiveDate, timezone: FixedOffset) -> Self {
+        self.changes.retain(|(day, _)| *day != from);
+        self.changes.push((from, timezone));
+        self.changes.sort_by_key(|(day, _)| *day);
+        self
+    }
+
+    /// Load `{"default": "JST", "changes": {"2025-07-10": "-07:00"}}`
+    ///
+    /// `default` may be omitted, in which case `fallback` applies before the first change.
+    pub fn from_file(path: &Path, fallback: FixedOffset) -> Result<Self> {
+        #[derive(Deserialize)]
+        struct ScheduleFile {
+            default: Option<String>,
+            #[serde(default)]
+            changes: BTreeMap<NaiveDate, String>,
+        }
+
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read timezone schedule: {}", path.display()))?;
+        let file: ScheduleFile = serde_json::from_str(&content)
+            .with_context(|| format!("Invalid timezone schedule: {}", path.display()))?;
+
+        let default = match file.default {
+            Some(spec) => parse_timezone(&spec)?,
+            None => fallback,
+        };
+        let mut schedule = Self::fixed(default);
+        for (day, spec) in file.changes {
+            schedule = schedule.with_change(day, parse_timezone(&spec)?);
+        }
+        Ok(schedule)
+    }
+
+    /// Timezone in effect at an instant
+    pub fn offset_at(&self, instant: DateTime<Utc>) -> FixedOffset {
+        self.changes
+            .iter()
+            .rev()
+            .find(|(day, timezone)| instant >= local_midnight(*day, timezone))
+            .map_or(self.default, |(_, timezone)| *timezone)
+    }
+
+    /// Timezone in effect on a local calendar day
+    pub fn offset_on(&self, day: NaiveDate) -> FixedOffset {
+        self.changes
+            .iter()
+            .rev()
+            .find(|(from, _)| *from <= day)
+            .map_or(self.default, |(_, timezone)| *timezone)
+    }
+
+    /// An instant in the local time of wherever the schedule says you were
+    pub fn to_local(&self, instant: DateTime<Utc>) -> DateTime<FixedOffset> {
+        instant.with_timezone(&self.offset_at(instant))
+    }
+
+    /// Start of a local calendar day (00:00:00), as UTC
+    ///
+    /// On the day of a change this is whichever of the old and new midnights comes first,
+    /// matching the day [`to_local`](Self::to_local) assigns to those instants.
+    pub fn start_of_day(&self, day: NaiveDate) -> DateTime<Utc> {
+        let current = local_midnight(day, &self.offset_on(day));
+        let previous = local_midnight(day, &self.offset_on(day - Duration::days(1)));
+        current.min(previous)
+    }
+
+    /// End of a local calendar day (23:59:59), as UTC
+    pub fn end_of_day(&self, day: NaiveDate) -> DateTime<Utc> {
+        self.start_of_day(day + Duration::days(1)) - Duration::seconds(1)
+    }
+
+    /// Label for times rendered with this schedule
+    pub fn label(&self) -> String {
+        if self.changes.is_empty() {
+            timezone_label(&self.default)
+        } else {
+            "local time".to_string()
+        }
+    }
+}
+
+fn local_midnight(day: NaiveDate, timezone: &FixedOffset) -> DateTime<Utc> {
+    timezone
+        .from_local_datetime(&day.and_hms_opt(0, 0, 0).unwrap())
+        .unwrap()
+        .with_timezone(&Utc)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_named_timezones() {
+        assert_eq!(parse_timezone("utc").unwrap().local_minus_utc(), 0);
+        assert_eq!(parse_timezone("JST").unwrap(), jst());
+    }
+
+    #[test]
+    fn test_parse_offsets() {
+        assert_eq!(parse_timezone("+09:00").unwrap(), jst());
+        assert_eq!(parse_timezone("-0530").unwrap().local_minus_utc(), -(5 * 3600 + 30 * 60));
+        assert_eq!(parse_timezone("UTC-5").unwrap().local_minus_utc(), -5 * 3600);
+        assert_eq!(parse_timezone("utc+9").unwrap(), jst());
+        assert_eq!(parse_timezone("Gmt-05:00").unwrap().local_minus_utc(), -5 * 3600);
+        assert!(parse_timezone("Mars/Olympus").is_err());
+        assert!(parse_timezone("+25:00").is_err());
+    }
+
+    #[test]
+    fn test_resolve_timezone() {
+        assert_eq!(resolve_timezone(Some("JST")).unwrap(), jst());
+        assert_eq!(resolve_timezone(None).unwrap(), system_timezone());
+        assert!(resolve_timezone(Some("nowhere")).is_err());
+    }
+
+    #[test]
+    fn test_timezone_schedule() {
+        use chrono::TimeZone;
+
+        let pacific = parse_timezone("-07:00").unwrap();
+        let day = |d: u32| NaiveDate::from_ymd_opt(2025, 7, d).unwrap();
+        let schedule = TimezoneSchedule::fixed(jst()).with_change(day(10), pacific);
+
+        // Still JST on the morning of the flight, Pacific time after local midnight there
+        let before = Utc.with_ymd_and_hms(2025, 7, 10, 6, 0, 0).unwrap();
+        let after = Utc.with_ymd_and_hms(2025, 7, 10, 7, 0, 0).unwrap();
+        assert_eq!(schedule.offset_at(before), jst());
+        assert_eq!(schedule.offset_at(after), pacific);
+        assert_eq!(schedule.to_local(before).date_naive(), day(10));
+        assert_eq!(schedule.to_local(after).date_naive(), day(10));
+
+        assert_eq!(schedule.start_of_day(day(9)), Utc.with_ymd_and_hms(2025, 7, 8, 15, 0, 0).unwrap());
+        // The day of travel lasts from midnight JST until midnight Pacific time
+        assert_eq!(schedule.end_of_day(day(9)), Utc.with_ymd_and_hms(2025, 7, 9, 14, 59, 59).unwrap());
+        assert_eq!(schedule.end_of_day(day(10)), Utc.with_ymd_and_hms(2025, 7, 11, 6, 59, 59).unwrap());
+        // Flying east, the new day begins at the earlier JST midnight
+        let eastbound = TimezoneSchedule::fixed(pacific).with_change(day(20), jst());
+        assert_eq!(eastbound.start_of_day(day(20)), Utc.with_ymd_and_hms(2025, 7, 19, 15, 0, 0).unwrap());
+        assert_eq!(eastbound.to_local(Utc.with_ymd_and_hms(2025, 7, 19, 15, 0, 0).unwrap()).date_naive(), day(20));
+
+        assert_eq!(schedule.label(), "local time");
+        assert_eq!(TimezoneSchedule::fixed(jst()).label(), "JST");
+    }
+
+    #[test]
+    fn test_timezone_schedule_from_file() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("schedule.json");
+        std::fs::write(&path, r#"{"changes": {"2025-07-20": "JST", "2025-07-10": "UTC-7"}}"#).unwrap();
+
+        let utc = parse_timezone("UTC").unwrap();
+        let schedule = TimezoneSchedule::from_file(&path, utc).unwrap();
+        let day = |d: u32| NaiveDate::from_ymd_opt(2025, 7, d).unwrap();
+        assert_eq!(schedule.offset_on(day(1)), utc);
+        assert_eq!(schedule.offset_on(day(15)).local_minus_utc(), -7 * 3600);
+        assert_eq!(schedule.offset_on(day(25)), jst());
+
+        std::fs::write(&path, r#"{"changes": {"2025-07-10": "Mars"}}"#).unwrap();
+        assert!(TimezoneSchedule::from_file(&path, utc).is_err());
+    }
+
+    #[test]
+    fn test_timezone_label() {
+        assert_eq!(timezone_label(&jst()), "JST");
+        assert_eq!(timezone_label(&parse_timezone("-05:00").unwrap()), "UTC-05:00");
+    }
+}
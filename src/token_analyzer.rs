@@ -0,0 +1,347 @@
+use anyhow::{Context, Result};
+use chrono::FixedOffset;
+use serde::Deserialize;
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::path::Path;
+
+use crate::activity::ActivityScoring;
+use crate::models::{ActivityType, EntryType, SessionTokens, TokenAnalysis, TokenStats, TokenUsage, WorkSession};
+use crate::scanner::ProjectNames;
+use crate::timezone::{jst, TimezoneSchedule};
+
+/// Prices in USD per million tokens
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+pub struct ModelPrice {
+    pub input: f64,
+    pub output: f64,
+    pub cache_write: f64,
+    pub cache_read: f64,
+}
+
+impl ModelPrice {
+    pub fn cost(&self, usage: &TokenUsage) -> f64 {
+        (usage.input_tokens as f64 * self.input
+            + usage.output_tokens as f64 * self.output
+            + usage.cache_creation_tokens as f64 * self.cache_write
+            + usage.cache_read_tokens as f64 * self.cache_read)
+            / 1_000_000.0
+    }
+}
+
+/// Model prices keyed by a fragment of the model name, e.g. `sonnet`
+#[derive(Debug, Clone)]
+pub struct PriceTable {
+    prices: Vec<(String, ModelPrice)>,
+}
+
+impl PriceTable {
+    /// Build a table from `(model name fragment, price)` pairs
+    pub fn new(prices: impl IntoIterator<Item = (String, ModelPrice)>) -> Self {
+        let mut prices: Vec<(String, ModelPrice)> = prices
+            .into_iter()
+            .map(|(model, price)| (model.to_lowercase(), price))
+            .collect();
+        // The most specific fragment wins, e.g. `claude-3-5-haiku` over `haiku`
+        prices.sort_by_key(|(model, _)| std::cmp::Reverse(model.len()));
+        Self { prices }
+    }
+
+    /// Load a JSON object mapping model name fragments to prices
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read price table: {}", path.display()))?;
+        let prices: BTreeMap<String, ModelPrice> = serde_json::from_str(&content)
+            .with_context(|| format!("Invalid price table: {}", path.display()))?;
+        Ok(Self::new(prices))
+    }
+
+    pub fn price_for(&self, model: &str) -> Option<&ModelPrice> {
+        let model = model.to_lowercase();
+        self.prices
+            .iter()
+            .find(|(fragment, _)| model.contains(fragment.as_str()))
+            .map(|(_, price)| price)
+    }
+}
+
+impl Default for PriceTable {
+    /// Public list prices of the Claude model families
+    fn default() -> Self {
+        let price = |input: f64, output: f64| ModelPrice {
+            input,
+            output,
+            cache_write: input * 1.25,
+            cache_read: input * 0.1,
+        };
+        Self::new([
+            ("opus".to_string(), price(15.0, 75.0)),
+            ("sonnet".to_string(), price(3.0, 15.0)),
+            ("haiku".to_string(), price(0.8, 4.0)),
+        ])
+    }
+}
+
+/// Aggregates API token usage recorded in assistant entries
+pub struct TokenAnalyzer {
+    prices: PriceTable,
+    /// Timezone(s) used to decide which calendar day a request belongs to
+    timezone: TimezoneSchedule,
+    /// Classifies the prompts that responses are attributed to
+    activity_scoring: ActivityScoring,
+}
+
+impl TokenAnalyzer {
+    pub fn new() -> Self {
+        Self {
+            prices: PriceTable::default(),
+            timezone: TimezoneSchedule::fixed(jst()),
+            activity_scoring: ActivityScoring::new(),
+        }
+    }
+
+    pub fn with_prices(mut self, prices: PriceTable) -> Self {
+        self.prices = prices;
+        self
+    }
+
+    pub fn with_timezone(mut self, timezone: FixedOffset) -> Self {
+        self.timezone = TimezoneSchedule::fixed(timezone);
+        self
+    }
+
+    pub fn with_timezone_schedule(mut self, schedule: TimezoneSchedule) -> Self {
+        self.timezone = schedule;
+        self
+    }
+
+    pub fn with_activity_scoring(mut self, activity_scoring: ActivityScoring) -> Self {
+        self.activity_scoring = activity_scoring;
+        self
+    }
+
+    /// Timezones used to assign usage to calendar days
+    pub fn timezone(&self) -> &TimezoneSchedule {
+        &self.timezone
+    }
+
+    pub fn analyze(&self, sessions: &[WorkSession]) -> TokenAnalysis {
+        let mut analysis = TokenAnalysis::default();
+        let mut seen_messages = HashSet::new();
+        for session in sessions {
+            analysis.merge(self.analyze_session(session, &mut seen_messages));
+        }
+        analysis.name_projects(&ProjectNames::new(sessions.iter().map(|session| session.project_path.as_str())));
+        analysis
+    }
+
+    /// Usage of one session, skipping the API responses in `seen_messages` and adding its own
+    ///
+    /// One API response is logged once per content block with the same usage, so
+    /// sessions analyzed one at a time share the set to count each response once.
+    /// Responses are attributed to the activity type of the last prompt before
+    /// them; tool results are not prompts, so a tool loop stays with its request.
+    /// Projects are keyed by their paths until named with [`TokenAnalysis::name_projects`].
+    pub fn analyze_session(&self, session: &WorkSession, seen_messages: &mut HashSet<String>) -> TokenAnalysis {
+        let mut analysis = TokenAnalysis::default();
+        let mut unpriced = BTreeSet::new();
+        let project_path = &session.project_path;
+        let mut session_stats = TokenStats::default();
+        let mut activity = ActivityType::Other;
+
+        for entry in &session.entries {
+            if let EntryType::User = entry.entry_type {
+                let prompt = entry.message.content.text();
+                if !prompt.trim().is_empty() {
+                    activity = self.activity_scoring.primary(&prompt);
+                }
+            }
+            let Some(usage) = entry.message.usage.as_ref() else {
+                continue;
+            };
+            let message_key = entry.message.id.clone().or_else(|| entry.request_id.clone());
+            if let Some(key) = message_key {
+                if !seen_messages.insert(key) {
+                    continue;
+                }
+            }
+
+            let usage = TokenUsage::from_usage_info(usage);
+            let model = entry.message.model.as_deref().unwrap_or("unknown");
+            let cost = match self.prices.price_for(model) {
+                Some(price) => price.cost(&usage),
+                None => {
+                    unpriced.insert(model.to_string());
+                    0.0
+                }
+            };
+
+            session_stats.add(&usage, cost);
+            analysis.total.add(&usage, cost);
+            analysis.by_project.entry(project_path.clone()).or_default().add(&usage, cost);
+            analysis.by_model.entry(model.to_string()).or_default().add(&usage, cost);
+            analysis
+                .by_project_activity
+                .entry(project_path.clone())
+                .or_default()
+                .entry(activity.as_str().to_string())
+                .or_default()
+                .add(&usage, cost);
+            analysis
+                .by_day
+                .entry(self.timezone.to_local(entry.timestamp).date_naive())
+                .or_default()
+                .add(&usage, cost);
+            if entry.is_sidechain {
+                analysis.sidechain.add(&usage, cost);
+            }
+        }
+
+        if session_stats.requests > 0 {
+            analysis.by_session.push(SessionTokens {
+                session_id: session.session_id,
+                project_path: session.project_path.clone(),
+                start_time: session.start_time,
+                stats: session_stats,
+            });
+        }
+
+        analysis.unpriced_models = unpriced.into_iter().collect();
+        analysis
+    }
+}
+
+impl Default for TokenAnalyzer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{ClaudeLogEntry, ContentBlock, MessageContent, MessageContentVariant, UsageInfo};
+    use chrono::{DateTime, Duration, NaiveDate, TimeZone, Utc};
+    use tempfile::TempDir;
+    use uuid::Uuid;
+
+    fn create_assistant_entry(timestamp: DateTime<Utc>, message_id: &str, model: &str, input: u32, output: u32) -> ClaudeLogEntry {
+        ClaudeLogEntry {
+            parent_uuid: None,
+            is_sidechain: false,
+            user_type: "external".to_string(),
+            cwd: "/test".to_string(),
+            session_id: Uuid::new_v4(),
+            version: "1.0.0".to_string(),
+            entry_type: EntryType::Assistant,
+            message: MessageContent {
+                role: "assistant".to_string(),
+                content: MessageContentVariant::String("done".to_string()),
+                id: Some(message_id.to_string()),
+                message_type: None,
+                model: Some(model.to_string()),
+                stop_reason: None,
+                stop_sequence: None,
+                usage: Some(UsageInfo {
+                    input_tokens: Some(input),
+                    output_tokens: Some(output),
+                    cache_creation_input_tokens: None,
+                    cache_read_input_tokens: None,
+                    service_tier: None,
+                }),
+            },
+            uuid: Uuid::new_v4(),
+            timestamp,
+            request_id: None,
+            tool_use_result: None,
+            source: None,
+        }
+    }
+
+    fn create_test_session(entries: Vec<ClaudeLogEntry>) -> WorkSession {
+        WorkSession {
+            session_id: Uuid::new_v4(),
+            project_path: "/home/user/.claude/projects/-home-user-work-api".to_string(),
+            start_time: entries[0].timestamp,
+            end_time: entries[entries.len() - 1].timestamp,
+            total_messages: entries.len(),
+            user_messages: 0,
+            assistant_messages: entries.len(),
+            entries,
+            summary: None,
+            merged_session_ids: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_token_aggregation() {
+        let start = Utc.with_ymd_and_hms(2025, 7, 1, 14, 0, 0).unwrap();
+        let session = create_test_session(vec![
+            create_assistant_entry(start, "msg_1", "claude-sonnet-4-20250514", 1_000_000, 0),
+            // Same response logged again for its second content block
+            create_assistant_entry(start, "msg_1", "claude-sonnet-4-20250514", 1_000_000, 0),
+            create_assistant_entry(start + Duration::hours(12), "msg_2", "claude-opus-4-20250514", 0, 1_000_000),
+            create_assistant_entry(start + Duration::hours(12), "msg_3", "gpt-4", 10, 10),
+        ]);
+
+        let analysis = TokenAnalyzer::new().analyze(&[session]);
+
+        assert_eq!(analysis.total.requests, 3);
+        assert_eq!(analysis.total.usage.input_tokens, 1_000_010);
+        assert!((analysis.total.estimated_cost - 78.0).abs() < 1e-9);
+        assert_eq!(analysis.by_session.len(), 1);
+        assert_eq!(analysis.by_model.len(), 3);
+        assert_eq!(analysis.unpriced_models, vec!["gpt-4"]);
+        // 23:00 JST on July 1st and 11:00 JST on July 2nd
+        let days: Vec<&NaiveDate> = analysis.by_day.keys().collect();
+        assert_eq!(days, [&NaiveDate::from_ymd_opt(2025, 7, 1).unwrap(), &NaiveDate::from_ymd_opt(2025, 7, 2).unwrap()]);
+        assert!((analysis.by_day[days[0]].estimated_cost - 3.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_cost_by_activity() {
+        let start = Utc.with_ymd_and_hms(2025, 7, 1, 14, 0, 0).unwrap();
+        let user_entry = |content: MessageContentVariant| {
+            let mut entry = create_assistant_entry(start, "", "", 0, 0);
+            entry.entry_type = EntryType::User;
+            entry.message = MessageContent { role: "user".to_string(), content, id: None, model: None, usage: None, ..entry.message };
+            entry
+        };
+        let tool_result: Vec<ContentBlock> = serde_json::from_str(r#"[{"type": "tool_result", "tool_use_id": "toolu_1", "content": "ok"}]"#).unwrap();
+        let model = "claude-sonnet-4-20250514";
+        let session = create_test_session(vec![
+            create_assistant_entry(start, "msg_0", model, 10, 0),
+            user_entry(MessageContentVariant::String("Fix the login bug".to_string())),
+            create_assistant_entry(start, "msg_1", model, 1_000_000, 0),
+            // The tool loop answers the same prompt
+            user_entry(MessageContentVariant::Array(tool_result)),
+            create_assistant_entry(start, "msg_2", model, 1_000_000, 0),
+            user_entry(MessageContentVariant::String("Now implement the signup form".to_string())),
+            create_assistant_entry(start, "msg_3", model, 0, 100_000),
+        ]);
+
+        let analysis = TokenAnalyzer::new().analyze(&[session]);
+        let activities = analysis.by_project_activity.values().next().unwrap();
+        assert_eq!((analysis.by_project_activity.len(), activities.len()), (1, 3));
+        assert_eq!(activities["Other"].usage.input_tokens, 10);
+        assert_eq!(activities["Debugging"].requests, 2);
+        assert!((activities["Debugging"].estimated_cost - 6.0).abs() < 1e-9);
+        assert!((activities["Coding"].estimated_cost - 1.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_price_table_from_file() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("prices.json");
+        std::fs::write(
+            &path,
+            r#"{"sonnet": {"input": 1.0, "output": 2.0, "cache_write": 0.0, "cache_read": 0.0},
+                "claude-3-5-sonnet": {"input": 5.0, "output": 6.0, "cache_write": 0.0, "cache_read": 0.0}}"#,
+        )
+        .unwrap();
+
+        let table = PriceTable::from_file(&path).unwrap();
+        assert_eq!(table.price_for("claude-3-5-sonnet-20241022").unwrap().input, 5.0);
+        assert_eq!(table.price_for("claude-sonnet-4").unwrap().input, 1.0);
+        assert!(table.price_for("claude-opus-4").is_none());
+    }
+}
@@ -0,0 +1,125 @@
+use anyhow::{Context, Result};
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+/// Watches log directories and reports batches of changed JSONL files
+pub struct LogWatcher {
+    /// Kept alive for as long as events should be delivered
+    _watcher: RecommendedWatcher,
+    receiver: mpsc::UnboundedReceiver<PathBuf>,
+    /// A change seen by [`Self::changed`], the start of the next batch
+    pending: Option<PathBuf>,
+    /// Quiet period used to coalesce bursts of writes into one batch
+    debounce: Duration,
+}
+
+impl LogWatcher {
+    /// Start watching a projects directory recursively
+    pub fn watch(projects_dir: &Path) -> Result<Self> {
+        Self::watch_all(&[projects_dir.to_path_buf()])
+    }
+
+    /// Start watching several projects directories recursively
+    pub fn watch_all(projects_dirs: &[PathBuf]) -> Result<Self> {
+        let (sender, receiver) = mpsc::unbounded_channel();
+
+        let mut watcher = notify::recommended_watcher(move |result: notify::Result<Event>| {
+            let Ok(event) = result else {
+                return;
+            };
+            if !matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_)) {
+                return;
+            }
+            for path in event.paths {
+                let is_jsonl = path
+                    .extension()
+                    .and_then(|ext| ext.to_str())
+                    .map(|ext| ext.eq_ignore_ascii_case("jsonl"))
+                    .unwrap_or(false);
+                if is_jsonl {
+                    let _ = sender.send(path);
+                }
+            }
+        })
+        .context("Failed to create filesystem watcher")?;
+
+        for projects_dir in projects_dirs {
+            watcher
+                .watch(projects_dir, RecursiveMode::Recursive)
+                .with_context(|| format!("Failed to watch directory: {}", projects_dir.display()))?;
+        }
+
+        Ok(Self {
+            _watcher: watcher,
+            receiver,
+            pending: None,
+            debounce: Duration::from_millis(500),
+        })
+    }
+
+    pub fn with_debounce(mut self, debounce: Duration) -> Self {
+        self.debounce = debounce;
+        self
+    }
+
+    /// Wait until a JSONL file changes, leaving the change to the next batch
+    ///
+    /// Returns `false` once the watcher has stopped delivering events. Unlike
+    /// [`Self::next_batch`] it loses nothing when dropped, so it can race other work.
+    pub async fn changed(&mut self) -> bool {
+        if self.pending.is_none() {
+            self.pending = self.receiver.recv().await;
+        }
+        self.pending.is_some()
+    }
+
+    /// Wait for the next batch of changed JSONL files
+    ///
+    /// Returns `None` once the watcher has stopped delivering events.
+    pub async fn next_batch(&mut self) -> Option<Vec<PathBuf>> {
+        let first = match self.pending.take() {
+            Some(path) => path,
+            None => self.receiver.recv().await?,
+        };
+        let mut changed = BTreeSet::from([first]);
+
+        // Keep collecting until the directory has been quiet for the debounce period
+        while let Ok(Some(path)) = tokio::time::timeout(self.debounce, self.receiver.recv()).await {
+            changed.insert(path);
+        }
+
+        Some(changed.into_iter().collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[tokio::test]
+    async fn test_reports_changed_jsonl_files() {
+        let temp_dir = TempDir::new().unwrap();
+        let project_dir = temp_dir.path().join("-Users-me-project");
+        std::fs::create_dir(&project_dir).unwrap();
+
+        let mut watcher = LogWatcher::watch(temp_dir.path())
+            .unwrap()
+            .with_debounce(Duration::from_millis(100));
+
+        std::fs::write(project_dir.join("notes.txt"), "ignored").unwrap();
+        std::fs::write(project_dir.join("session.jsonl"), "{}\n").unwrap();
+
+        assert!(tokio::time::timeout(Duration::from_secs(5), watcher.changed()).await.unwrap());
+        let batch = tokio::time::timeout(Duration::from_secs(5), watcher.next_batch())
+            .await
+            .expect("watcher did not report the change")
+            .unwrap();
+
+        assert_eq!(batch.len(), 1);
+        assert!(batch[0].ends_with("session.jsonl"));
+    }
+}
@@ -0,0 +1,222 @@
+use anyhow::Result;
+use chrono::{Datelike, Duration, FixedOffset, NaiveDate, Weekday};
+use std::collections::{HashMap, HashSet};
+
+use crate::compare::{Change, PeriodComparison};
+use crate::models::WorkAnalysis;
+use crate::plan::{PlanComparison, PlanStore};
+
+/// Topics listed in the weekly report
+const TOP_TOPICS: usize = 5;
+
+/// Monday of an ISO week like `2025-W27`
+pub fn parse_iso_week(s: &str) -> Result<NaiveDate> {
+    let invalid = || anyhow::anyhow!("Invalid week '{}'. Expected YYYY-Www, e.g. 2025-W27", s);
+    let lower = s.to_lowercase();
+    let (year, week) = lower.split_once("-w").ok_or_else(invalid)?;
+    let year: i32 = year.parse().map_err(|_| invalid())?;
+    let week: u32 = week.parse().map_err(|_| invalid())?;
+    NaiveDate::from_isoywd_opt(year, week, Weekday::Mon).ok_or_else(invalid)
+}
+
+/// A week's work compared with the week before and with the hours planned for it
+#[derive(Debug, Clone, PartialEq)]
+pub struct WeeklyReport {
+    /// Monday of the reported week
+    pub week_start: NaiveDate,
+    /// The week before as the baseline, the reported week as the current period
+    pub comparison: PeriodComparison,
+    /// Projects with sessions in the week before and in the reported week
+    pub projects: Change<usize>,
+    /// Planned vs actual hours of the reported week
+    pub goals: Vec<PlanComparison>,
+    /// Main topics of the week's sessions with the number of sessions, most frequent first
+    pub top_topics: Vec<(String, usize)>,
+    /// Project with the most work time in the reported week
+    pub biggest_project: Option<(String, Duration)>,
+}
+
+impl WeeklyReport {
+    /// Report from the analyses of the week starting `week_start` and of the week before
+    pub fn new(
+        current: &WorkAnalysis,
+        previous: &WorkAnalysis,
+        plans: &PlanStore,
+        week_start: NaiveDate,
+        timezone: &FixedOffset,
+    ) -> Self {
+        let goals = plans
+            .compare(current, timezone, week_start)
+            .into_iter()
+            .filter(|row| row.week_start == week_start)
+            .collect();
+
+        let biggest_project = current
+            .project_stats
+            .values()
+            .max_by(|a, b| a.work_time.cmp(&b.work_time).then_with(|| b.project_name.cmp(&a.project_name)))
+            .map(|stats| (stats.project_name.clone(), stats.work_time));
+
+        Self {
+            week_start,
+            comparison: PeriodComparison::new(previous, current),
+            projects: Change { baseline: active_projects(previous), current: active_projects(current) },
+            goals,
+            top_topics: top_topics(current),
+            biggest_project,
+        }
+    }
+
+    /// ISO week label like `2025-W27`
+    pub fn iso_week(&self) -> String {
+        let week = self.week_start.iso_week();
+        format!("{}-W{:02}", week.year(), week.week())
+    }
+
+    pub fn week_end(&self) -> NaiveDate {
+        self.week_start + Duration::days(6)
+    }
+}
+
+/// Projects with at least one session
+fn active_projects(analysis: &WorkAnalysis) -> usize {
+    analysis
+        .sessions
+        .iter()
+        .map(|session| {
+            analysis.project_name(session)
+        })
+        .collect::<HashSet<_>>()
+        .len()
+}
+
+/// The main topics of the most sessions, ties in alphabetical order
+fn top_topics(analysis: &WorkAnalysis) -> Vec<(String, usize)> {
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    for summary in analysis.sessions.iter().filter_map(|session| session.summary.as_ref()) {
+        for topic in summary.main_topics.iter().collect::<HashSet<_>>() {
+            *counts.entry(topic.as_str()).or_insert(0) += 1;
+        }
+    }
+    let mut topics: Vec<(&str, usize)> = counts.into_iter().collect();
+    topics.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+    topics.into_iter().take(TOP_TOPICS).map(|(topic, count)| (topic.to_string(), count)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{ProjectStats, SessionSummary, WorkSession};
+    use crate::timezone::jst;
+    use chrono::{TimeZone, Utc};
+    use std::path::Path;
+    use uuid::Uuid;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    fn analysis(day: u32, sessions: &[(&str, i64, &[&str])]) -> WorkAnalysis {
+        let start = Utc.with_ymd_and_hms(2025, 7, day, 1, 0, 0).unwrap();
+        let sessions: Vec<WorkSession> = sessions
+            .iter()
+            .map(|(project, minutes, topics)| WorkSession {
+                session_id: Uuid::new_v4(),
+                project_path: format!("/home/me/{}", project),
+                start_time: start,
+                end_time: start + Duration::minutes(*minutes),
+                entries: Vec::new(),
+                total_messages: 4,
+                user_messages: 2,
+                assistant_messages: 2,
+                summary: Some(SessionSummary {
+                    main_topics: topics.iter().map(|topic| topic.to_string()).collect(),
+                    topic_mentions: HashMap::new(),
+                    key_discussions: Vec::new(),
+                    technologies_mentioned: Vec::new(),
+                    problems_addressed: Vec::new(),
+                    problem_confidence: HashMap::new(),
+                    solutions_proposed: Vec::new(),
+                    learning_moments: Vec::new(),
+                    overall_summary: String::new(),
+                }),
+                merged_session_ids: Vec::new(),
+            })
+            .collect();
+        let mut project_stats: HashMap<String, ProjectStats> = HashMap::new();
+        for session in &sessions {
+            let name = session.project_path.rsplit('/').next().unwrap().to_string();
+            let stats = project_stats.entry(name.clone()).or_insert_with(|| ProjectStats {
+                project_name: name,
+                total_sessions: 0,
+                total_messages: 0,
+                work_time: Duration::zero(),
+                activity_types: HashMap::new(),
+                activity_confidence: HashMap::new(),
+                most_active_day: None,
+                topic_analysis: None,
+            });
+            stats.total_sessions += 1;
+            stats.total_messages += session.total_messages;
+            stats.work_time += session.end_time - session.start_time;
+        }
+        WorkAnalysis {
+            total_sessions: sessions.len(),
+            total_messages: sessions.iter().map(|s| s.total_messages).sum(),
+            total_work_time: sessions.iter().map(|s| s.end_time - s.start_time).fold(Duration::zero(), |a, b| a + b),
+            sessions,
+            project_stats,
+            time_range: (start, start),
+            conversation_summary: None,
+            token_analysis: None,
+            tool_usage: None,
+            code_blocks: None,
+            time_buckets: None,
+            initiatives: None,
+            commits: None,
+            log_gaps: None,
+            heatmap: None,
+            machines: None,
+            assistants: None,
+            sidechains: None,
+            budget: None,
+            duplicate_entries: 0,
+            project_names: Default::default(),
+        }
+    }
+
+    #[test]
+    fn test_parse_iso_week() {
+        assert_eq!(parse_iso_week("2025-W27").unwrap(), date(2025, 6, 30));
+        assert_eq!(parse_iso_week("2026-w01").unwrap(), date(2025, 12, 29));
+        assert!(parse_iso_week("2025-W54").is_err());
+        assert!(parse_iso_week("2025-07-07").is_err());
+    }
+
+    #[test]
+    fn test_weekly_report() {
+        let previous = analysis(1, &[("api", 120, &["rust"])]);
+        let current = analysis(8, &[
+            ("api", 90, &["rust", "parser"]),
+            ("web", 240, &["vite"]),
+            ("api", 60, &["rust"]),
+        ]);
+        let mut plans = PlanStore::load(Path::new("/nonexistent/plans.json")).unwrap();
+        plans.set_estimate("api", date(2025, 7, 7), 3.0);
+        plans.set_estimate("api", date(2025, 7, 14), 5.0);
+
+        let report = WeeklyReport::new(&current, &previous, &plans, date(2025, 7, 7), &jst());
+
+        assert_eq!(report.iso_week(), "2025-W28");
+        assert_eq!(report.week_end(), date(2025, 7, 13));
+        assert_eq!(report.comparison.hours, Change { baseline: 2.0, current: 6.5 });
+        assert_eq!(report.projects, Change { baseline: 1, current: 2 });
+        assert_eq!(report.biggest_project, Some(("web".to_string(), Duration::minutes(240))));
+        assert_eq!(report.top_topics[0], ("rust".to_string(), 2));
+        assert_eq!(report.top_topics.len(), 3);
+        // Only the reported week's plans, with the unplanned project
+        let goals: Vec<(&str, Option<f64>, f64)> =
+            report.goals.iter().map(|row| (row.project.as_str(), row.planned_hours, row.actual_hours)).collect();
+        assert_eq!(goals, [("api", Some(3.0), 2.5), ("web", None, 4.0)]);
+    }
+}